@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::{BTreeMap, HashMap},
     hash::Hash,
+    ops::Deref,
 };
 
 use crate::resource::{CardResource, PaymentCost, Resource};
@@ -22,6 +23,77 @@ pub enum CardTag {
     Event,
 }
 
+impl CardTag {
+    pub const COUNT: usize = 12;
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    #[inline]
+    fn bit(self) -> u16 {
+        1 << self.index()
+    }
+}
+
+/// A card's tags, plus a bitmask and per-tag counts computed once when the card is loaded,
+/// so that checking for or counting tags does not need to scan the list.
+///
+/// Serialized as the plain list of tags.
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<CardTag>", into = "Vec<CardTag>")]
+pub struct CardTags {
+    tags: Vec<CardTag>,
+    mask: u16,
+    counts: [u8; CardTag::COUNT],
+}
+
+impl CardTags {
+    #[inline]
+    pub fn mask(&self) -> u16 {
+        self.mask
+    }
+
+    #[inline]
+    pub fn contains(&self, tag: &CardTag) -> bool {
+        self.mask & tag.bit() != 0
+    }
+
+    #[inline]
+    pub fn count(&self, tag: CardTag) -> usize {
+        self.counts[tag.index()] as usize
+    }
+}
+
+impl From<Vec<CardTag>> for CardTags {
+    fn from(tags: Vec<CardTag>) -> Self {
+        let mut mask = 0;
+        let mut counts = [0; CardTag::COUNT];
+        for tag in &tags {
+            mask |= tag.bit();
+            counts[tag.index()] += 1;
+        }
+
+        Self { tags, mask, counts }
+    }
+}
+
+impl From<CardTags> for Vec<CardTag> {
+    fn from(card_tags: CardTags) -> Self {
+        card_tags.tags
+    }
+}
+
+impl Deref for CardTags {
+    type Target = [CardTag];
+
+    #[inline]
+    fn deref(&self) -> &[CardTag] {
+        &self.tags
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CardRequirement {
     MaxOxygen(usize),
@@ -250,6 +322,15 @@ pub enum CardEffect {
     // per player's choice for each
     GlobalRequirementsTolerance(usize),
 
+    // megacredits for oceans next to placed tiles are multiplied by the given factor
+    OceanAdjacencyBonusMultiplier(usize),
+
+    // leftover energy isn't converted to heat in the production phase
+    EnergyKeptAtProduction,
+
+    // heat can be spent as megacredits when paying for things
+    HeatAsMegacredits,
+
     CannotRemoveThisCardResource(CardResource),
     CannotRemoveAnyCardResources(Vec<CardResource>),
 
@@ -270,7 +351,7 @@ pub enum CardEffect {
 pub struct Card {
     pub name: String,
     pub kind: CardKind,
-    pub tags: Vec<CardTag>,
+    pub tags: CardTags,
     pub cost: PaymentCost,
 
     #[serde(default)]
@@ -307,7 +388,8 @@ pub struct Card {
 }
 
 impl Card {
-    pub(crate) fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
         name: String,
         kind: CardKind,
         tags: Vec<CardTag>,
@@ -333,7 +415,7 @@ impl Card {
         Self {
             name,
             kind,
-            tags,
+            tags: tags.into(),
             cost,
             requirements,
             points,
@@ -370,12 +452,32 @@ impl Card {
             }
         }
 
-        for impact in possible_impacts {
+        for effect in &self.effects {
+            match effect {
+                CardEffect::OnAnyPlacedOcean(impact)
+                | CardEffect::OnAnyPlacedCity(impact)
+                | CardEffect::OnAnyTagPlayed(_, impact)
+                | CardEffect::OnOwnPlacedGreenery(impact)
+                | CardEffect::OnOwnTagPlayed(_, impact) => possible_impacts.push(impact),
+                CardEffect::OnOwnTagCombinationPlayed(_, impacts) => {
+                    possible_impacts.extend(impacts.iter())
+                }
+                _ => {}
+            }
+        }
+
+        while let Some(impact) = possible_impacts.pop() {
             match impact {
-                ImmediateImpact::AddResourceToSameCard(cr, _) => {
+                ImmediateImpact::AddResourceToSameCard(cr, _)
+                | ImmediateImpact::SpendResourceFromSameCard(cr, _, _) => {
                     assert_eq!(*cr, result.unwrap_or(*cr));
                     result = Some(*cr);
                 }
+                ImmediateImpact::OneOf(impacts) => possible_impacts.extend(impacts.iter()),
+                ImmediateImpact::Chained(first, second) => {
+                    possible_impacts.push(first);
+                    possible_impacts.push(second);
+                }
                 _ => {}
             }
         }
@@ -402,7 +504,7 @@ pub fn get_corporate_era_deck() -> Vec<Card> {
     deck
 }
 
-fn get_cards_by_name(cards: &'static Vec<Card>) -> HashMap<&'static str, &'static Card> {
+fn get_cards_by_name(cards: &'static [Card]) -> HashMap<&'static str, &'static Card> {
     cards
         .iter()
         .map(|card| (card.name.as_ref(), card))
@@ -469,7 +571,7 @@ mod tests {
                 is_valid &= card.tags.contains(&CardTag::Building);
                 is_valid &= card.tags.contains(&CardTag::Space);
             }
-            PaymentCost::Megacredits(_) | _ => {
+            _ => {
                 is_valid &= !card.tags.contains(&CardTag::Building);
                 is_valid &= !card.tags.contains(&CardTag::Space);
             }
@@ -496,6 +598,35 @@ mod tests {
         assert!(invalid_cards.is_empty(), "{:?}", invalid_cards);
     }
 
+    #[test]
+    fn tag_masks_and_counts_match_the_tag_lists() {
+        for card in get_base_game_deck()
+            .iter()
+            .chain(get_corporate_deck_only().iter())
+        {
+            for &tag in card.tags.iter() {
+                assert!(card.tags.mask() & (1 << tag.index()) != 0);
+                assert_eq!(
+                    card.tags.iter().filter(|&&other| other == tag).count(),
+                    card.tags.count(tag)
+                );
+            }
+            assert_eq!(
+                card.tags.len(),
+                card.tags.counts.iter().map(|&count| count as usize).sum::<usize>()
+            );
+        }
+
+        // The precomputed fields are not part of the serialized card.
+        let card = BASE_GAME_CARDS_BY_NAME["Beam From A Thorium Asteroid"];
+        let serialized = serde_json::to_value(card).unwrap();
+        assert_eq!(
+            serde_json::json!(["Jovian", "Space", "Power"]),
+            serialized["tags"]
+        );
+        assert_eq!(*card, serde_json::from_value::<Card>(serialized).unwrap());
+    }
+
     #[test]
     fn card_resource_is_detected_properly() {
         let card = BASE_GAME_CARDS_BY_NAME["Predators"];
@@ -0,0 +1,134 @@
+//! Serialize cards as references to the crate's deck data instead of embedding full card bodies.
+//!
+//! Every card is written out as its name, and is looked up again by name when deserialized.
+//! Use these modules with `#[serde(with = "...")]` on fields that hold cards.
+
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{card::Card, registry::CardRegistry};
+
+fn lookup<'de, D: Deserializer<'de>>(name: &str) -> Result<Card, D::Error> {
+    CardRegistry::global()
+        .get_by_name(name)
+        .cloned()
+        .ok_or_else(|| D::Error::custom(format!("unknown card \"{}\"", name)))
+}
+
+pub mod card {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::card::Card;
+
+    pub fn serialize<S: Serializer>(card: &Card, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&card.name)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Card, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        super::lookup::<D>(&name)
+    }
+}
+
+pub mod cards {
+    use serde::{Deserializer, Serializer};
+
+    use crate::card::Card;
+
+    pub fn serialize<S: Serializer>(cards: &[Card], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(cards.iter().map(|card| &card.name))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Card>, D::Error> {
+        super::deserialize_names::<D>(deserializer)?
+            .iter()
+            .map(|name| super::lookup::<D>(name))
+            .collect()
+    }
+}
+
+pub mod card_set {
+    use std::collections::HashSet;
+
+    use serde::{Deserializer, Serializer};
+
+    use crate::card::Card;
+
+    pub fn serialize<S: Serializer>(cards: &HashSet<Card>, serializer: S) -> Result<S::Ok, S::Error> {
+        // Sort the names so the output does not depend on the set's iteration order.
+        let mut names: Vec<&String> = cards.iter().map(|card| &card.name).collect();
+        names.sort();
+        serializer.collect_seq(names)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashSet<Card>, D::Error> {
+        super::deserialize_names::<D>(deserializer)?
+            .iter()
+            .map(|name| super::lookup::<D>(name))
+            .collect()
+    }
+}
+
+pub mod card_resources {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::{card::Card, resource::CardResource};
+
+    // Written out as a list of (card name, card resource, amount) entries,
+    // since the map's keys cannot be used as keys in most serialization formats.
+    pub fn serialize<S: Serializer>(
+        card_resources: &BTreeMap<(Card, CardResource), usize>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            card_resources
+                .iter()
+                .map(|((card, card_resource), amount)| (&card.name, card_resource, amount)),
+        )
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<(Card, CardResource), usize>, D::Error> {
+        Vec::<(String, CardResource, usize)>::deserialize(deserializer)?
+            .drain(..)
+            .map(|(name, card_resource, amount)| {
+                super::lookup::<D>(&name).map(|card| ((card, card_resource), amount))
+            })
+            .collect()
+    }
+}
+
+fn deserialize_names<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    Vec::<String>::deserialize(deserializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::card::{Card, BASE_GAME_CARDS_BY_NAME};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Hand {
+        #[serde(with = "crate::card_ref::cards")]
+        cards: Vec<Card>,
+    }
+
+    #[test]
+    fn cards_are_written_as_their_names() {
+        let hand = Hand {
+            cards: vec![BASE_GAME_CARDS_BY_NAME["Predators"].clone()],
+        };
+
+        let json = serde_json::to_string(&hand).unwrap();
+        assert_eq!(r#"{"cards":["Predators"]}"#, json);
+        assert_eq!(hand, serde_json::from_str(&json).unwrap());
+
+        let error = serde_json::from_str::<Hand>(r#"{"cards":["Not A Real Card"]}"#).unwrap_err();
+        assert!(error.to_string().contains("Not A Real Card"));
+    }
+}
@@ -0,0 +1,158 @@
+//! Corporations: the cards players start the game with, which set up their starting
+//! resources and production, and give them effects and actions for the rest of the game.
+//!
+//! Only the corporations whose abilities the card effects can express are in the deck.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{CardAction, CardEffect, CardTags, ImmediateImpact},
+    resource::Resource,
+};
+
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Corporation {
+    pub name: String,
+    pub tags: CardTags,
+    pub starting_megacredits: usize,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub starting_resources: BTreeMap<Resource, usize>, // other than megacredits
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub starting_production: BTreeMap<Resource, isize>,
+
+    // taken as the player's first action of the game, instead of a regular one
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub first_action: Vec<ImmediateImpact>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<CardAction>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<CardEffect>,
+}
+
+pub const BEGINNER_CORPORATION_NAME: &str = "Beginner Corporation";
+
+impl Corporation {
+    /// The corporation of the beginner variant: 42 M$ and no abilities,
+    /// and its player keeps every card they were dealt for free.
+    pub fn beginner() -> Self {
+        Self {
+            name: BEGINNER_CORPORATION_NAME.to_string(),
+            tags: CardTags::from(vec![]),
+            starting_megacredits: 42,
+            starting_resources: BTreeMap::new(),
+            starting_production: BTreeMap::new(),
+            first_action: vec![],
+            actions: vec![],
+            effects: vec![],
+        }
+    }
+
+    pub fn is_beginner(&self) -> bool {
+        self.name == BEGINNER_CORPORATION_NAME
+    }
+}
+
+pub fn get_corporations() -> Vec<Corporation> {
+    let corporations_text = include_str!("./cards/corporations/deck.json");
+    let corporations: Vec<Corporation> = serde_json::from_str(corporations_text).unwrap();
+    corporations
+}
+
+lazy_static! {
+    pub static ref CORPORATIONS: Vec<Corporation> = get_corporations();
+    pub static ref CORPORATIONS_BY_NAME: HashMap<&'static str, &'static Corporation> = CORPORATIONS
+        .iter()
+        .map(|corporation| (corporation.name.as_ref(), corporation))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{
+        card::{CardEffect, CardTag, ImmediateImpact, CORPORATE_GAME_CARDS_BY_NAME},
+        corporation::{get_corporations, Corporation, CORPORATIONS_BY_NAME},
+        resource::Resource,
+    };
+
+    fn is_corporation_valid(corporation: &Corporation) -> bool {
+        let mut is_valid = corporation.starting_megacredits > 0;
+
+        // Corporations stay in play for the whole game.
+        is_valid &= !corporation.tags.contains(&CardTag::Event);
+
+        is_valid &= !corporation
+            .starting_resources
+            .contains_key(&Resource::Megacredits);
+        is_valid &= corporation
+            .starting_resources
+            .values()
+            .all(|amount| *amount > 0);
+        is_valid &= corporation
+            .starting_production
+            .values()
+            .all(|amount| *amount > 0);
+
+        is_valid
+    }
+
+    #[test]
+    fn corporations_are_valid() {
+        let corporations = get_corporations();
+        assert!(!corporations.is_empty());
+
+        let invalid_corporations: Vec<_> = corporations
+            .iter()
+            .filter(|x| !is_corporation_valid(x))
+            .collect();
+        assert!(
+            invalid_corporations.is_empty(),
+            "{:?}",
+            invalid_corporations
+        );
+    }
+
+    #[test]
+    fn corporation_names_are_unique_and_distinct_from_project_cards() {
+        let corporations = get_corporations();
+        let names: HashSet<&str> = corporations
+            .iter()
+            .map(|corporation| corporation.name.as_ref())
+            .collect();
+        assert_eq!(corporations.len(), names.len());
+        assert!(names
+            .iter()
+            .all(|name| !CORPORATE_GAME_CARDS_BY_NAME.contains_key(name)));
+    }
+
+    #[test]
+    fn corporations_are_loaded_with_their_abilities() {
+        let inventrix = CORPORATIONS_BY_NAME["Inventrix"];
+        assert_eq!(45, inventrix.starting_megacredits);
+        assert_eq!(1, inventrix.tags.count(CardTag::Science));
+        assert_eq!(vec![ImmediateImpact::DrawCard(3)], inventrix.first_action);
+        assert_eq!(
+            vec![CardEffect::GlobalRequirementsTolerance(2)],
+            inventrix.effects
+        );
+
+        let cinematics = CORPORATIONS_BY_NAME["Interplanetary Cinematics"];
+        assert_eq!(
+            Some(&20),
+            cinematics.starting_resources.get(&Resource::Steel)
+        );
+        assert!(cinematics.first_action.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+//! Summaries of whole decks, so that the balance profile of a homebrew set
+//! can be compared against the official expansions.
+
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, CardKind, CardRequirement, CardTag, VictoryPointValue};
+
+// width of the cost ranges cards are counted in, in megacredits
+const COST_BUCKET_SIZE: usize = 5;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeckStats {
+    pub name: String,
+    pub card_count: usize,
+    pub kinds: BTreeMap<CardKind, usize>,
+    pub tags: BTreeMap<CardTag, usize>, // a card with the same tag twice counts twice
+
+    // lowest cost of each range -> number of cards costing that much
+    pub cost_buckets: BTreeMap<usize, usize>,
+
+    // Only fixed victory points are averaged, over all cards of the deck.
+    // Points that depend on the rest of the game are counted separately.
+    pub average_points: f64,
+    pub conditional_point_cards: usize,
+
+    // requirement kind, e.g. "MinOxygen" or "MinTags(Science)" -> number of cards with it
+    pub requirements: BTreeMap<String, usize>,
+    pub cards_without_requirements: usize,
+}
+
+impl DeckStats {
+    pub fn new(name: &str, cards: &[Card]) -> Self {
+        let mut stats = Self {
+            name: name.to_string(),
+            card_count: cards.len(),
+            kinds: BTreeMap::new(),
+            tags: BTreeMap::new(),
+            cost_buckets: BTreeMap::new(),
+            average_points: 0.0,
+            conditional_point_cards: 0,
+            requirements: BTreeMap::new(),
+            cards_without_requirements: 0,
+        };
+
+        let mut total_points = 0;
+        for card in cards {
+            *stats.kinds.entry(card.kind).or_default() += 1;
+            for tag in card.tags.iter() {
+                *stats.tags.entry(*tag).or_default() += 1;
+            }
+
+            let bucket = card.cost.amount() / COST_BUCKET_SIZE * COST_BUCKET_SIZE;
+            *stats.cost_buckets.entry(bucket).or_default() += 1;
+
+            match card.points {
+                Some(VictoryPointValue::Immediate(points)) => total_points += points,
+                Some(_) => stats.conditional_point_cards += 1,
+                None => {}
+            }
+
+            if card.requirements.is_empty() {
+                stats.cards_without_requirements += 1;
+            }
+            for requirement in &card.requirements {
+                *stats
+                    .requirements
+                    .entry(requirement_kind(requirement))
+                    .or_default() += 1;
+            }
+        }
+        if !cards.is_empty() {
+            stats.average_points = total_points as f64 / cards.len() as f64;
+        }
+
+        stats
+    }
+}
+
+fn requirement_kind(requirement: &CardRequirement) -> String {
+    match requirement {
+        CardRequirement::MaxOxygen(_) => "MaxOxygen".to_string(),
+        CardRequirement::MinOxygen(_) => "MinOxygen".to_string(),
+        CardRequirement::MaxTemperature(_) => "MaxTemperature".to_string(),
+        CardRequirement::MinTemperature(_) => "MinTemperature".to_string(),
+        CardRequirement::MaxOceans(_) => "MaxOceans".to_string(),
+        CardRequirement::MinOceans(_) => "MinOceans".to_string(),
+        CardRequirement::MinTags(tag, _) => format!("MinTags({:?})", tag),
+        CardRequirement::MinOwnedGreeneries(_) => "MinOwnedGreeneries".to_string(),
+        CardRequirement::MinProduction(resource, _) => format!("MinProduction({:?})", resource),
+    }
+}
+
+fn join_counts<K, F: Fn(&K) -> String>(counts: &BTreeMap<K, usize>, label: F) -> String {
+    let parts: Vec<String> = counts
+        .iter()
+        .map(|(key, count)| format!("{} {}", label(key), count))
+        .collect();
+    parts.join(", ")
+}
+
+impl fmt::Display for DeckStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {} card(s)", self.name, self.card_count)?;
+        writeln!(
+            f,
+            "    kinds: {}",
+            join_counts(&self.kinds, |kind| format!("{:?}", kind))
+        )?;
+        writeln!(
+            f,
+            "    tags: {}",
+            join_counts(&self.tags, |tag| format!("{:?}", tag))
+        )?;
+        writeln!(
+            f,
+            "    costs: {}",
+            join_counts(&self.cost_buckets, |bucket| format!(
+                "{}-{} M$",
+                bucket,
+                bucket + COST_BUCKET_SIZE - 1
+            ))
+        )?;
+        writeln!(
+            f,
+            "    average VP: {:.2} (not counting {} card(s) with conditional VP)",
+            self.average_points, self.conditional_point_cards
+        )?;
+        write!(
+            f,
+            "    requirements: none {}",
+            self.cards_without_requirements
+        )?;
+        if !self.requirements.is_empty() {
+            write!(f, ", {}", join_counts(&self.requirements, String::clone))?;
+        }
+        Ok(())
+    }
+}
+
+/// Card names that appear in more than one of the decks, with the names of those decks.
+pub fn find_overlaps(decks: &[(String, Vec<Card>)]) -> BTreeMap<String, Vec<String>> {
+    let mut decks_by_card: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (deck_name, cards) in decks {
+        for card in cards {
+            let card_decks = decks_by_card.entry(card.name.clone()).or_default();
+            if !card_decks.contains(deck_name) {
+                card_decks.push(deck_name.clone());
+            }
+        }
+    }
+
+    decks_by_card.retain(|_, card_decks| card_decks.len() > 1);
+    decks_by_card
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{CardKind, CardTag, CORPORATE_GAME_CARDS_BY_NAME},
+        deck_stats::{find_overlaps, DeckStats},
+    };
+
+    #[test]
+    fn stats_count_kinds_tags_costs_points_and_requirements() {
+        // Asteroid Mining: automatic, Jovian and Space tags, costs 30, 2VP.
+        // Predators: active, Animal tag, costs 14, requires 11% oxygen, 1VP per animal.
+        let cards = vec![
+            CORPORATE_GAME_CARDS_BY_NAME["Asteroid Mining"].clone(),
+            CORPORATE_GAME_CARDS_BY_NAME["Predators"].clone(),
+        ];
+        let stats = DeckStats::new("test", &cards);
+
+        assert_eq!(2, stats.card_count);
+        assert_eq!(Some(&1), stats.kinds.get(&CardKind::Automatic));
+        assert_eq!(Some(&1), stats.kinds.get(&CardKind::Active));
+        assert_eq!(Some(&1), stats.tags.get(&CardTag::Jovian));
+        assert_eq!(Some(&1), stats.tags.get(&CardTag::Animal));
+        assert_eq!(Some(&1), stats.cost_buckets.get(&30));
+        assert_eq!(Some(&1), stats.cost_buckets.get(&10));
+        assert_eq!(1.0, stats.average_points);
+        assert_eq!(1, stats.conditional_point_cards);
+        assert_eq!(Some(&1), stats.requirements.get("MinOxygen"));
+        assert_eq!(1, stats.cards_without_requirements);
+
+        let report = stats.to_string();
+        assert!(report.starts_with("test: 2 card(s)\n"));
+        assert!(report.contains("    costs: 10-14 M$ 1, 30-34 M$ 1\n"));
+        assert!(report.ends_with("    requirements: none 1, MinOxygen 1"));
+    }
+
+    #[test]
+    fn overlaps_list_every_deck_sharing_a_card() {
+        let asteroid_mining = CORPORATE_GAME_CARDS_BY_NAME["Asteroid Mining"].clone();
+        let predators = CORPORATE_GAME_CARDS_BY_NAME["Predators"].clone();
+        let decks = vec![
+            ("base".to_string(), vec![asteroid_mining.clone(), predators]),
+            ("homebrew".to_string(), vec![asteroid_mining]),
+        ];
+
+        let overlaps = find_overlaps(&decks);
+        assert_eq!(1, overlaps.len());
+        assert_eq!(
+            vec!["base".to_string(), "homebrew".to_string()],
+            overlaps["Asteroid Mining"]
+        );
+    }
+}
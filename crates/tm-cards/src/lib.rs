@@ -0,0 +1,13 @@
+//! The cards of the game: their data types, and the decks of every expansion.
+
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod card;
+pub mod card_ref;
+pub mod corporation;
+pub mod deck_stats;
+pub mod registry;
+pub mod resource;
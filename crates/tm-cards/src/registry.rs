@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::card::{get_corporate_era_deck, Card};
+
+/// Compact identifier of a card, assigned by the `CardRegistry`.
+///
+/// Ids are only stable for a given version of the deck data;
+/// anything persisted should refer to cards by name instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct CardId(u16);
+
+impl CardId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// All cards of all enabled decks, loaded once and shared by the whole process.
+pub struct CardRegistry {
+    cards: Vec<Card>,
+    ids_by_name: HashMap<String, CardId>,
+    content_hash: u64,
+}
+
+lazy_static! {
+    static ref CARD_REGISTRY: CardRegistry = CardRegistry::new(get_corporate_era_deck());
+}
+
+impl CardRegistry {
+    pub fn global() -> &'static CardRegistry {
+        &CARD_REGISTRY
+    }
+
+    fn new(cards: Vec<Card>) -> Self {
+        assert!(cards.len() <= u16::MAX as usize);
+
+        let ids_by_name: HashMap<String, CardId> = cards
+            .iter()
+            .enumerate()
+            .map(|(index, card)| (card.name.clone(), CardId(index as u16)))
+            .collect();
+        assert_eq!(cards.len(), ids_by_name.len(), "card names must be unique");
+
+        let content_hash = hash_cards(&cards);
+        Self {
+            cards,
+            ids_by_name,
+            content_hash,
+        }
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn id(&self, name: &str) -> Option<CardId> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    pub fn card(&self, id: CardId) -> &Card {
+        &self.cards[id.0 as usize]
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Card> {
+        self.id(name).map(|id| self.card(id))
+    }
+
+    /// Hash of the full contents of every registered card.
+    /// Any change to the deck data, however small, changes the hash.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    pub fn content_hash_hex(&self) -> String {
+        format!("{:016x}", self.content_hash)
+    }
+}
+
+// 64-bit FNV-1a over the cards' canonical JSON representation. Unlike the standard library's
+// hashers, the result is guaranteed to be the same across platforms and compiler versions.
+fn hash_cards(cards: &[Card]) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let canonical_text = serde_json::to_string(cards).unwrap();
+    canonical_text.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{get_corporate_era_deck, CardTag, CORPORATE_ERA_DECK},
+        registry::CardRegistry,
+    };
+
+    #[test]
+    fn every_card_has_an_id() {
+        let registry = CardRegistry::global();
+        assert_eq!(CORPORATE_ERA_DECK.len(), registry.cards().len());
+
+        for card in CORPORATE_ERA_DECK.iter() {
+            let id = registry.id(&card.name).unwrap();
+            assert_eq!(card, registry.card(id));
+        }
+        assert_eq!(None, registry.id("Not A Real Card"));
+    }
+
+    #[test]
+    fn content_hash_detects_changed_cards() {
+        let original = CardRegistry::new(get_corporate_era_deck());
+        assert_eq!(
+            CardRegistry::global().content_hash(),
+            original.content_hash()
+        );
+
+        let mut modified_deck = get_corporate_era_deck();
+        let mut tags = modified_deck[0].tags.to_vec();
+        tags.push(CardTag::Wild);
+        modified_deck[0].tags = tags.into();
+        let modified = CardRegistry::new(modified_deck);
+        assert_ne!(original.content_hash(), modified.content_hash());
+    }
+}
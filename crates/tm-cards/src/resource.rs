@@ -0,0 +1,184 @@
+use std::{
+    collections::BTreeMap,
+    ops::{Index, IndexMut},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CardResource {
+    Microbe,
+    Plant,
+    Animal,
+    Science,
+    Fighter,
+}
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resource {
+    Megacredits,
+    Steel,
+    Titanium,
+    Plants,
+    Energy,
+    Heat,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 6] = [
+        Resource::Megacredits,
+        Resource::Steel,
+        Resource::Titanium,
+        Resource::Plants,
+        Resource::Energy,
+        Resource::Heat,
+    ];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// One value per kind of resource, stored inline in `Resource::ALL` order,
+/// so that it is cheap to copy and to do arithmetic with.
+///
+/// Serialized as a map from resource to value; resources missing from the map get the default value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ResourceMap<T>([T; 6]);
+
+impl<T> ResourceMap<T> {
+    /// The values are given in `Resource::ALL` order.
+    pub fn new(values: [T; 6]) -> Self {
+        Self(values)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Resource, &T)> {
+        Resource::ALL.iter().copied().zip(self.0.iter())
+    }
+}
+
+impl<T> Index<Resource> for ResourceMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, resource: Resource) -> &T {
+        &self.0[resource.index()]
+    }
+}
+
+impl<T> IndexMut<Resource> for ResourceMap<T> {
+    #[inline]
+    fn index_mut(&mut self, resource: Resource) -> &mut T {
+        &mut self.0[resource.index()]
+    }
+}
+
+impl<T> Index<&Resource> for ResourceMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, resource: &Resource) -> &T {
+        &self[*resource]
+    }
+}
+
+impl<T> IndexMut<&Resource> for ResourceMap<T> {
+    #[inline]
+    fn index_mut(&mut self, resource: &Resource) -> &mut T {
+        &mut self[*resource]
+    }
+}
+
+impl<T: Serialize> Serialize for ResourceMap<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de, T: Deserialize<'de> + Default> Deserialize<'de> for ResourceMap<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = Self::default();
+        for (resource, value) in BTreeMap::<Resource, T>::deserialize(deserializer)? {
+            map[resource] = value;
+        }
+        Ok(map)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentCost {
+    Megacredits(usize),
+    Space(usize),
+    Building(usize),
+    SpaceOrBuilding(usize),
+    Steel(usize),
+    Titanium(usize),
+    Plants(usize),
+    Energy(usize),
+    Heat(usize),
+}
+
+impl PaymentCost {
+    /// The amount to pay, regardless of which resources it may be paid with.
+    pub fn amount(self) -> usize {
+        match self {
+            PaymentCost::Megacredits(x)
+            | PaymentCost::Space(x)
+            | PaymentCost::Building(x)
+            | PaymentCost::SpaceOrBuilding(x)
+            | PaymentCost::Steel(x)
+            | PaymentCost::Titanium(x)
+            | PaymentCost::Plants(x)
+            | PaymentCost::Energy(x)
+            | PaymentCost::Heat(x) => x,
+        }
+    }
+
+    /// The same cost with the amount lowered by the discount, down to nothing at most.
+    pub fn discounted(self, discount: usize) -> Self {
+        let amount = self.amount().saturating_sub(discount);
+        match self {
+            PaymentCost::Megacredits(_) => PaymentCost::Megacredits(amount),
+            PaymentCost::Space(_) => PaymentCost::Space(amount),
+            PaymentCost::Building(_) => PaymentCost::Building(amount),
+            PaymentCost::SpaceOrBuilding(_) => PaymentCost::SpaceOrBuilding(amount),
+            PaymentCost::Steel(_) => PaymentCost::Steel(amount),
+            PaymentCost::Titanium(_) => PaymentCost::Titanium(amount),
+            PaymentCost::Plants(_) => PaymentCost::Plants(amount),
+            PaymentCost::Energy(_) => PaymentCost::Energy(amount),
+            PaymentCost::Heat(_) => PaymentCost::Heat(amount),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::resource::{Resource, ResourceMap};
+
+    #[test]
+    fn resource_maps_are_serialized_as_maps() {
+        let map = ResourceMap::new([1, 2, 3, 4, 5, 6]);
+        assert_eq!(4, map[Resource::Plants]);
+
+        let as_btreemap = btreemap! {
+            Resource::Megacredits => 1,
+            Resource::Steel => 2,
+            Resource::Titanium => 3,
+            Resource::Plants => 4,
+            Resource::Energy => 5,
+            Resource::Heat => 6,
+        };
+        let serialized = serde_json::to_string(&map).unwrap();
+        assert_eq!(serde_json::to_string(&as_btreemap).unwrap(), serialized);
+        assert_eq!(
+            map,
+            serde_json::from_str::<ResourceMap<usize>>(&serialized).unwrap()
+        );
+
+        let partial: ResourceMap<isize> = serde_json::from_str(r#"{"Heat": -1}"#).unwrap();
+        assert_eq!(ResourceMap::new([0, 0, 0, 0, 0, -1]), partial);
+    }
+}
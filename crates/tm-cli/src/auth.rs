@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, fmt};
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+use crate::game::PlayerId;
+
+const TOKEN_BYTES: usize = 24;
+
+/// A secret that grants access to a hosted game.
+///
+/// Tokens are drawn from the operating system's secure random source, never from a `GameRng`:
+/// they are not part of the game, and must not be predictable from a game's seed.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessToken(String);
+
+impl AccessToken {
+    fn generate() -> Self {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        Self(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    // Takes the same time for any two tokens of the same length,
+    // so that response times do not reveal how much of a guess was right.
+    fn matches(&self, candidate: &str) -> bool {
+        self.0.len() == candidate.len()
+            && self
+                .0
+                .bytes()
+                .zip(candidate.bytes())
+                .fold(0u8, |difference, (a, b)| difference | (a ^ b))
+                == 0
+    }
+}
+
+// Keeps tokens out of logs.
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken(..)")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Access {
+    Seat(PlayerId), // may act as this player, and see their hand
+    Spectator,      // may only see public information
+}
+
+/// The tokens of a single game: one per seat, plus one shared by all spectators.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameCredentials {
+    pub seats: BTreeMap<PlayerId, AccessToken>,
+    pub spectator: AccessToken,
+}
+
+impl GameCredentials {
+    pub fn issue(player_ids: impl IntoIterator<Item = PlayerId>) -> Self {
+        Self {
+            seats: player_ids
+                .into_iter()
+                .map(|player_id| (player_id, AccessToken::generate()))
+                .collect(),
+            spectator: AccessToken::generate(),
+        }
+    }
+
+    pub fn authenticate(&self, token: &str) -> Option<Access> {
+        // Check every token, so the time taken does not depend on which one matched.
+        let seat = self
+            .seats
+            .iter()
+            .filter(|(_, seat_token)| seat_token.matches(token))
+            .map(|(player_id, _)| Access::Seat(*player_id))
+            .fold(None, |found, access| found.or(Some(access)));
+        let spectator = Some(Access::Spectator).filter(|_| self.spectator.matches(token));
+        seat.or(spectator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        auth::{Access, GameCredentials},
+        game::PlayerId,
+    };
+
+    #[test]
+    fn tokens_grant_access_to_their_seat_only() {
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let credentials = GameCredentials::issue(vec![p1, p2]);
+
+        let p1_token = credentials.seats[&p1].as_str();
+        let p2_token = credentials.seats[&p2].as_str();
+        assert_ne!(p1_token, p2_token);
+        assert_eq!(48, p1_token.len());
+
+        assert_eq!(Some(Access::Seat(p1)), credentials.authenticate(p1_token));
+        assert_eq!(Some(Access::Seat(p2)), credentials.authenticate(p2_token));
+        assert_eq!(
+            Some(Access::Spectator),
+            credentials.authenticate(credentials.spectator.as_str())
+        );
+        assert_eq!(None, credentials.authenticate(""));
+        assert_eq!(None, credentials.authenticate(&p1_token[1..]));
+    }
+
+    #[test]
+    fn tokens_are_not_shown_in_debug_output() {
+        let credentials = GameCredentials::issue(vec![PlayerId::from(1)]);
+        let debug_output = format!("{:?}", credentials);
+        assert!(!debug_output.contains(credentials.spectator.as_str()));
+    }
+}
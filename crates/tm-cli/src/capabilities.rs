@@ -0,0 +1,195 @@
+//! A machine-readable manifest of what this build of the engine supports, so that
+//! front-ends and tournament servers can negotiate features with engines of different versions.
+
+use serde::{
+    de::{self, Visitor},
+    forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+
+use crate::{
+    action_space::{ActionSpace, ACTION_SPACE_VERSION},
+    card::{CardAction, CardEffect, CardRequirement, ImmediateImpact, VictoryPointValue},
+    compact::COMPACT_KEY_VERSION,
+    game::GameOperation,
+    options::{Expansion, TurnOrder},
+    registry::CardRegistry,
+    save::SAVE_FORMAT_VERSION,
+    server::PROTOCOL_VERSION,
+    stamp::RULES_REVISION,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub engine_version: String,
+    pub expansions: Vec<Expansion>,
+    pub turn_orders: Vec<String>,
+
+    // optional rules a game can be created with, as position notation directives
+    pub variants: Vec<String>,
+
+    // the optional cargo features this build was compiled with
+    pub features: Vec<String>,
+
+    // names of the variants of the card data enums, as they appear in deck files and saves
+    pub immediate_impacts: Vec<String>,
+    pub card_effects: Vec<String>,
+    pub card_actions: Vec<String>,
+    pub card_requirements: Vec<String>,
+    pub victory_point_values: Vec<String>,
+    pub operations: Vec<String>,
+
+    pub wire_formats: WireFormats,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WireFormats {
+    pub save: u32,
+    pub protocol: u32,
+    pub compact_key: u32,
+    // saves and compact keys only carry over between builds with the same card data,
+    // and recorded games only between builds with the same rules
+    pub deck_hash: String,
+    pub rules_revision: u32,
+    // the numbering of actions for learned policies and remote agents
+    pub action_space: u32,
+    pub action_space_fingerprint: String,
+}
+
+pub fn capabilities() -> Capabilities {
+    let mut features = vec![];
+    if cfg!(feature = "webhooks") {
+        features.push("webhooks".to_string());
+    }
+    if cfg!(feature = "sqlite-ladder") {
+        features.push("sqlite-ladder".to_string());
+    }
+    if cfg!(feature = "test-utils") {
+        features.push("test-utils".to_string());
+    }
+
+    Capabilities {
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        expansions: Expansion::ALL.to_vec(),
+        turn_orders: variant_names::<TurnOrder>(),
+        variants: vec![
+            "ban".to_string(),
+            "include".to_string(),
+            "handicap".to_string(),
+            "turn-order".to_string(),
+            "solo".to_string(),
+        ],
+        features,
+        immediate_impacts: variant_names::<ImmediateImpact>(),
+        card_effects: variant_names::<CardEffect>(),
+        card_actions: variant_names::<CardAction>(),
+        card_requirements: variant_names::<CardRequirement>(),
+        victory_point_values: variant_names::<VictoryPointValue>(),
+        operations: variant_names::<GameOperation>(),
+        wire_formats: WireFormats {
+            save: SAVE_FORMAT_VERSION,
+            protocol: PROTOCOL_VERSION,
+            compact_key: COMPACT_KEY_VERSION,
+            deck_hash: CardRegistry::global().content_hash_hex(),
+            rules_revision: RULES_REVISION,
+            action_space: ACTION_SPACE_VERSION,
+            action_space_fingerprint: ActionSpace::new().fingerprint(),
+        },
+    }
+}
+
+/// The serialized names of the variants of an enum, read from its `Deserialize` impl
+/// so that the list cannot fall out of date when variants are added.
+fn variant_names<'de, T: Deserialize<'de>>() -> Vec<String> {
+    let mut introspector = VariantIntrospector { variants: None };
+    let _ = T::deserialize(&mut introspector);
+    introspector
+        .variants
+        .expect("not an enum")
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+// Stops deserializing as soon as it is asked for an enum, and remembers its variants.
+struct VariantIntrospector {
+    variants: Option<&'static [&'static str]>,
+}
+
+#[derive(Debug)]
+struct Stop;
+
+impl std::fmt::Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "introspection stopped")
+    }
+}
+
+impl std::error::Error for Stop {}
+
+impl de::Error for Stop {
+    fn custom<T: std::fmt::Display>(_: T) -> Self {
+        Stop
+    }
+}
+
+impl<'de> Deserializer<'de> for &mut VariantIntrospector {
+    type Error = Stop;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Stop> {
+        Err(Stop)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        variants: &'static [&'static str],
+        _: V,
+    ) -> Result<V::Value, Stop> {
+        self.variants = Some(variants);
+        Err(Stop)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        action_space::ActionSpace, capabilities::capabilities, options::Expansion,
+        registry::CardRegistry, stamp::RULES_REVISION,
+    };
+
+    #[test]
+    fn manifest_lists_expansions_variants_and_formats() {
+        let manifest = capabilities();
+
+        assert_eq!(Expansion::ALL.to_vec(), manifest.expansions);
+        assert_eq!(
+            vec!["Sequential".to_string(), "Simultaneous".to_string()],
+            manifest.turn_orders
+        );
+        assert!(manifest
+            .immediate_impacts
+            .contains(&"PlaceGreenery".to_string()));
+        assert!(manifest
+            .card_requirements
+            .contains(&"MinOxygen".to_string()));
+        assert!(manifest.operations.contains(&"PutCardIntoPlay".to_string()));
+        assert_eq!(
+            CardRegistry::global().content_hash_hex(),
+            manifest.wire_formats.deck_hash
+        );
+        assert_eq!(RULES_REVISION, manifest.wire_formats.rules_revision);
+        assert_eq!(
+            ActionSpace::new().fingerprint(),
+            manifest.wire_formats.action_space_fingerprint
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(manifest, serde_json::from_str(&json).unwrap());
+    }
+}
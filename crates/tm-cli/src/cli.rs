@@ -0,0 +1,1222 @@
+use std::{
+    fs,
+    io::{self, BufRead, BufWriter, Write},
+    net::TcpListener,
+    path::Path,
+    process,
+    sync::Arc,
+    thread,
+};
+
+use crate::{
+    archive::{load_archive, upgrade_record, ArchiveIndex, ArchiveQuery},
+    blunder::{review_game, ReviewOptions},
+    capabilities::capabilities,
+    dataset::export_archive_dataset,
+    card::Card,
+    deck_stats::{find_overlaps, DeckStats},
+    draft::{DraftAgent, SynergyDraftBot},
+    eval::{evaluate_ensemble, evaluate_with_trace, EvalEstimate, EvalWeights},
+    eval_cache::{cached_evaluate, EvalCache, FileEvalCache, InMemoryEvalCache},
+    event::EventBus,
+    game::{GameState, PlayerId, PlayerState, TurnAction},
+    game_stats::{GameStatistics, TerraformSource},
+    knowledge::KnowledgeTracker,
+    ladder::{FileLadderStore, Ladder, LadderStore},
+    migrate::Migration,
+    options::Expansion,
+    position::{import_position, parse_player_id, split_card_names},
+    projection::{project_final_score, ProjectionAssumptions},
+    registry::CardRegistry,
+    rng::GameRng,
+    save::{load_game_leniently, save_game},
+    scenario::{generate_scenarios, load_scenario, write_scenarios, Scenario},
+    scoring::score_all_players,
+    search_tree::SearchTree,
+    server::{serve, GameManager},
+    setup::{advise_setup, SetupAdvice, SetupOffer, SETUP_PLANNING},
+    sim::{plan_generations, PlanEvaluation, PlanningOptions},
+    store::FileGameStore,
+    synergy::player_synergies,
+    threat::{plant_destruction_warning, PLANT_WARNING_THRESHOLD},
+    tuning::{save_weight_set, tune_weights, TunedWeights, TuningOptions, WEIGHTED_BOT},
+    whatif::{Alteration, GameRecord, GameStep, WhatIfReport},
+};
+
+const USAGE: &str = "\
+usage: terraforming_mars <command> [arguments]
+
+commands:
+    analyze <position-file> [cache-file]
+                               project the final score of every player in a position,
+                               list the synergies among their cards, and compare the
+                               cards each could play next; evaluations are reused from
+                               and added to the cache file, if given
+    capabilities               print the features this build supports, as JSON
+    deck-stats [<deck-file>...]
+                               summarize the cards of every expansion and of the given
+                               JSON deck files, and list cards found in several decks
+    draft <position-file> <player> <card>; <card>...
+                               pick a card for the player from a draft pack,
+                               explaining the value of every card in the pack
+    export-dataset <directory> <output-file>
+                               turn every decision of the recorded games in the
+                               directory into a training example, written as JSON lines
+    generate-scenarios <directory> [count] [seed]
+                               write practice positions for the early, middle, and late
+                               game (count of each, default 5) into the directory, each
+                               with the best line of play found by the endgame solver
+    puzzle <scenario-file>
+                               show a practice scenario, ask which cards to play in each
+                               generation, and grade the answer against the best line
+    review <record-file> [threshold]
+                               re-plan every research decision of a recorded game, and
+                               flag those more than the threshold (default 2) victory
+                               points below the best alternative
+    search-archive <directory> [<filter>...]
+                               index the recorded games in the directory, and list the
+                               ones with a player matching every filter: card=<name>,
+                               corporation=<name>, min-score=<points>, or winner;
+                               players=<count> restricts the number of players
+    search-tree <position-file> <player> <card>; <card>... [dot|json]
+                               plan the player's purchase from the offered cards, and
+                               print every play explored with its rollouts and values
+    serve <directory> [address]
+                               host games stored in the directory, accepting requests
+                               as JSON lines on the address (default 127.0.0.1:7878)
+    setup <position-file> <player> <offer-file>
+                               rank every choice of corporation and preludes in the JSON
+                               offer file, with the cards to buy from the initial offer
+    summary <record-file>      replay a recorded game, and show every player's final
+                               score with their spending, tiles, and terraform rating
+    tune-weights <weights-file> <ladder-file> [iterations] [seed]
+                               tune the evaluator's weights by self-play, save them to
+                               the weights file, and rate them against the default
+                               weights on the ladder
+    upgrade <save-or-record-file>
+                               read a save or recorded game written by an older build,
+                               wherever it can still be read, rewrite it for this build,
+                               and list every change made to it
+    what-if <record-file> <step> [<replacement>]
+                               replay a recorded game with the step (counted from 0)
+                               replaced by the JSON list of steps, or removed if none
+                               is given, and show how the final scores change";
+
+const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:7878";
+
+const DEFAULT_SCENARIOS_PER_STAGE: usize = 5;
+
+const SEARCH_TREE_PLANNING: PlanningOptions = PlanningOptions {
+    generations: 2,
+    rollouts: 4,
+    threads: 1,
+};
+
+pub fn run(args: &[String]) {
+    match args.first().map(String::as_str) {
+        Some("analyze") => match &args[1..] {
+            [path] => analyze(path, None),
+            [path, cache_path] => analyze(path, Some(cache_path)),
+            _ => exit_with_usage(),
+        },
+        Some("capabilities") => match &args[1..] {
+            [] => println!("{}", serde_json::to_string_pretty(&capabilities()).unwrap()),
+            _ => exit_with_usage(),
+        },
+        Some("deck-stats") => deck_stats(&args[1..]),
+        Some("draft") => match &args[1..] {
+            [path, player, pack] => draft(path, player, pack),
+            _ => exit_with_usage(),
+        },
+        Some("export-dataset") => match &args[1..] {
+            [directory, output_path] => export_dataset(directory, output_path),
+            _ => exit_with_usage(),
+        },
+        Some("generate-scenarios") => match &args[1..] {
+            [directory] => generate_scenario_files(directory, None, None),
+            [directory, count] => generate_scenario_files(directory, Some(count), None),
+            [directory, count, seed] => generate_scenario_files(directory, Some(count), Some(seed)),
+            _ => exit_with_usage(),
+        },
+        Some("puzzle") => match &args[1..] {
+            [path] => puzzle(path),
+            _ => exit_with_usage(),
+        },
+        Some("search-archive") => match &args[1..] {
+            [directory, filters @ ..] => search_archive(directory, filters),
+            _ => exit_with_usage(),
+        },
+        Some("search-tree") => match &args[1..] {
+            [path, player, pack] => search_tree(path, player, pack, None),
+            [path, player, pack, format] => search_tree(path, player, pack, Some(format)),
+            _ => exit_with_usage(),
+        },
+        Some("review") => match &args[1..] {
+            [path] => review(path, None),
+            [path, threshold] => review(path, Some(threshold)),
+            _ => exit_with_usage(),
+        },
+        Some("serve") => match &args[1..] {
+            [directory] => run_server(directory, DEFAULT_SERVER_ADDRESS),
+            [directory, address] => run_server(directory, address),
+            _ => exit_with_usage(),
+        },
+        Some("setup") => match &args[1..] {
+            [path, player, offer_path] => setup(path, player, offer_path),
+            _ => exit_with_usage(),
+        },
+        Some("summary") => match &args[1..] {
+            [path] => summary(path),
+            _ => exit_with_usage(),
+        },
+        Some("tune-weights") => match &args[1..] {
+            [weights_path, ladder_path] => tune(weights_path, ladder_path, None, None),
+            [weights_path, ladder_path, iterations] => {
+                tune(weights_path, ladder_path, Some(iterations), None)
+            }
+            [weights_path, ladder_path, iterations, seed] => {
+                tune(weights_path, ladder_path, Some(iterations), Some(seed))
+            }
+            _ => exit_with_usage(),
+        },
+        Some("upgrade") => match &args[1..] {
+            [path] => upgrade(path),
+            _ => exit_with_usage(),
+        },
+        Some("what-if") => match &args[1..] {
+            [path, step] => what_if(path, step, None),
+            [path, step, replacement] => what_if(path, step, Some(replacement)),
+            _ => exit_with_usage(),
+        },
+        _ => exit_with_usage(),
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn exit_with_error(message: String) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(1);
+}
+
+fn load_position(path: &str) -> GameState {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|error| exit_with_error(format!("could not read {}: {}", path, error)));
+    import_position(&text).unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)))
+}
+
+fn analyze(path: &str, cache_path: Option<&str>) {
+    let game = load_position(path);
+    match cache_path {
+        Some(cache_path) => {
+            let cache = FileEvalCache::open(cache_path, ProjectionAssumptions::default())
+                .unwrap_or_else(|error| exit_with_error(format!("{}: {}", cache_path, error)));
+            println!("{}", format_analysis(&game, &cache));
+            cache
+                .flush()
+                .unwrap_or_else(|error| exit_with_error(format!("{}: {}", cache_path, error)));
+        }
+        None => {
+            let cache = InMemoryEvalCache::new(ProjectionAssumptions::default());
+            println!("{}", format_analysis(&game, &cache));
+        }
+    }
+
+    let plays = format_candidate_plays(&game, &ProjectionAssumptions::default());
+    if !plays.is_empty() {
+        println!("{}", plays);
+    }
+}
+
+fn deck_stats(paths: &[String]) {
+    let mut decks: Vec<(String, Vec<Card>)> = Expansion::ALL
+        .iter()
+        .map(|expansion| (expansion.to_string(), expansion.cards().to_vec()))
+        .collect();
+    for path in paths {
+        let text = fs::read_to_string(path)
+            .unwrap_or_else(|error| exit_with_error(format!("could not read {}: {}", path, error)));
+        let cards: Vec<Card> = serde_json::from_str(&text)
+            .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+        decks.push((path.clone(), cards));
+    }
+
+    println!("{}", format_deck_stats(&decks));
+}
+
+fn draft(path: &str, player: &str, pack: &str) {
+    let (game, player_id, pack) = load_pack(path, player, pack);
+    println!("{}", format_draft(&game, player_id, &pack));
+}
+
+// The position, the player, and the offered cards of the `draft` and `search-tree` commands.
+fn load_pack(path: &str, player: &str, pack: &str) -> (GameState, PlayerId, Vec<Card>) {
+    let game = load_position(path);
+    let player_id = parse_player_id(player).unwrap_or_else(|error| exit_with_error(error));
+    if !game.players.contains_key(&player_id) {
+        exit_with_error(format!("{} is not a player in {}", player, path));
+    }
+    let pack: Vec<Card> = split_card_names(pack)
+        .map(|name| {
+            CardRegistry::global()
+                .get_by_name(name)
+                .cloned()
+                .unwrap_or_else(|| exit_with_error(format!("unknown card \"{}\"", name)))
+        })
+        .collect();
+    if pack.is_empty() {
+        exit_with_usage();
+    }
+    if let Some(card) = pack.iter().find(|card| !game.options.allows(card)) {
+        exit_with_error(format!("\"{}\" is not allowed in {}", card.name, path));
+    }
+
+    (game, player_id, pack)
+}
+
+fn puzzle(path: &str) {
+    let scenario = load_scenario(Path::new(path))
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+    let (game, player_id) = scenario
+        .game()
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+    let assumptions = ProjectionAssumptions {
+        remaining_generations: Some(scenario.generations - 1),
+        ..Default::default()
+    };
+    println!("{}", format_analysis(&game, &InMemoryEvalCache::new(assumptions)));
+    println!("{}", format_puzzle(&scenario, &game.players[&player_id]));
+
+    let mut input = io::stdin().lock().lines();
+    let mut answer = vec![];
+    while answer.len() < scenario.generations {
+        println!(
+            "generation {} of {}: cards to play, separated by \";\", or nothing to pass",
+            answer.len() + 1,
+            scenario.generations
+        );
+        let line = match input.next() {
+            Some(line) => line.unwrap_or_else(|error| exit_with_error(error.to_string())),
+            None => exit_with_error("no answer was given".to_string()),
+        };
+        match parse_puzzle_plays(&line) {
+            Ok(plays) => answer.push(plays),
+            Err(error) => eprintln!("error: {}", error),
+        }
+    }
+    println!("{}", format_puzzle_grade(&scenario, &answer));
+}
+
+fn parse_puzzle_plays(line: &str) -> Result<Vec<TurnAction>, String> {
+    split_card_names(line)
+        .map(|name| {
+            CardRegistry::global()
+                .get_by_name(name)
+                .map(|card| TurnAction::PlayCard(card.clone(), vec![]))
+                .ok_or_else(|| format!("unknown card \"{}\"", name))
+        })
+        .collect()
+}
+
+fn search_archive(directory: &str, filters: &[String]) {
+    let query = parse_archive_query(filters).unwrap_or_else(|error| exit_with_error(error));
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    let index = ArchiveIndex::build(Path::new(directory), threads)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", directory, error)));
+    println!("{}", format_archive_matches(&index, &query));
+}
+
+fn export_dataset(directory: &str, output_path: &str) {
+    let file = fs::File::create(output_path)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", output_path, error)));
+    let mut writer = BufWriter::new(file);
+    let (exported, skipped) = export_archive_dataset(Path::new(directory), &mut writer)
+        .and_then(|result| writer.flush().map(|_| result))
+        .unwrap_or_else(|error| exit_with_error(error.to_string()));
+    for (path, reason) in skipped {
+        eprintln!("skipped {}: {}", path.display(), reason);
+    }
+    println!("{} examples written to {}", exported, output_path);
+}
+
+fn generate_scenario_files(directory: &str, count: Option<&String>, seed: Option<&String>) {
+    let count = count.map_or(DEFAULT_SCENARIOS_PER_STAGE, |count| {
+        count
+            .parse()
+            .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a count", count)))
+    });
+    let seed = seed.map_or(0, |seed| {
+        seed.parse()
+            .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a seed", seed)))
+    });
+    let scenarios = generate_scenarios(seed, count);
+    let paths = write_scenarios(Path::new(directory), &scenarios)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", directory, error)));
+    for path in paths {
+        println!("{}", path.display());
+    }
+}
+
+fn tune(
+    weights_path: &str,
+    ladder_path: &str,
+    iterations: Option<&String>,
+    seed: Option<&String>,
+) {
+    let defaults = TuningOptions::default();
+    let iterations = iterations.map_or(defaults.iterations, |iterations| {
+        iterations
+            .parse()
+            .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a count", iterations)))
+    });
+    let seed = seed.map_or(defaults.seed, |seed| {
+        seed.parse()
+            .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a seed", seed)))
+    });
+    let options = TuningOptions {
+        iterations,
+        seed,
+        version: format!("tuned-{}-{}", iterations, seed),
+        ..defaults
+    };
+
+    let store = FileLadderStore::open(ladder_path);
+    let mut ladder = store
+        .load()
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", ladder_path, error)));
+    let tuned = tune_weights(&EvalWeights::default(), &options, &mut ladder);
+    store
+        .save(&ladder)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", ladder_path, error)));
+    save_weight_set(Path::new(weights_path), &tuned)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", weights_path, error)));
+    println!("{}", format_tuning(&tuned, &ladder));
+}
+
+fn parse_archive_query(filters: &[String]) -> Result<ArchiveQuery, String> {
+    let mut query = ArchiveQuery::default();
+    for filter in filters {
+        let (name, value) = match filter.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (filter.as_str(), None),
+        };
+        let number_error = || format!("\"{}\" needs a number", filter);
+        match (name, value) {
+            ("players", Some(count)) => {
+                query.player_count = Some(count.parse().map_err(|_| number_error())?)
+            }
+            ("min-score", Some(points)) => {
+                query.min_score = Some(points.parse().map_err(|_| number_error())?)
+            }
+            ("card", Some(card)) => query.card = Some(card.to_string()),
+            ("corporation", Some(corporation)) => query.corporation = Some(corporation.to_string()),
+            ("winner", None) => query.winner = true,
+            _ => return Err(format!("unknown filter \"{}\"", filter)),
+        }
+    }
+    Ok(query)
+}
+
+fn search_tree(path: &str, player: &str, pack: &str, format: Option<&String>) {
+    let (game, player_id, pack) = load_pack(path, player, pack);
+    let tree = SearchTree::from_plans(&plan_purchase(&game, player_id, pack));
+    match format.map(String::as_str) {
+        None | Some("dot") => println!("{}", tree.to_dot()),
+        Some("json") => println!("{}", serde_json::to_string_pretty(&tree).unwrap()),
+        Some(other) => exit_with_error(format!("unknown tree format \"{}\"", other)),
+    }
+}
+
+fn plan_purchase(game: &GameState, player_id: PlayerId, offered: Vec<Card>) -> Vec<PlanEvaluation> {
+    let player = &game.players[&player_id];
+    let opponents: Vec<&PlayerState> = game
+        .players
+        .values()
+        .filter(|opponent| opponent.player_id != player_id)
+        .collect();
+    let knowledge = &KnowledgeTracker::new(game).players[&player_id];
+    let unseen_cards: Vec<Card> = knowledge
+        .unaccounted_cards(CardRegistry::global().cards())
+        .into_iter()
+        .filter(|card| game.options.allows(card) && !offered.contains(card))
+        .cloned()
+        .collect();
+
+    plan_generations(
+        &game.board,
+        player,
+        &opponents,
+        offered,
+        &unseen_cards,
+        &SEARCH_TREE_PLANNING,
+        &mut GameRng::from_seed(0),
+    )
+}
+
+fn setup(path: &str, player: &str, offer_path: &str) {
+    let game = load_position(path);
+    let player_id = parse_player_id(player).unwrap_or_else(|error| exit_with_error(error));
+    if !game.players.contains_key(&player_id) {
+        exit_with_error(format!("{} is not a player in {}", player, path));
+    }
+    let text = fs::read_to_string(offer_path)
+        .unwrap_or_else(|error| exit_with_error(format!("could not read {}: {}", offer_path, error)));
+    let offer: SetupOffer = serde_json::from_str(&text)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", offer_path, error)));
+
+    let advice = advise_setup(
+        &game,
+        player_id,
+        &offer,
+        &SETUP_PLANNING,
+        &mut GameRng::from_seed(0),
+    );
+    println!("{}", format_setup(&advice));
+}
+
+fn load_record(path: &str) -> GameRecord {
+    let record = load_archive(Path::new(path))
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+    // Only differences that the game can be replayed despite are left.
+    for mismatch in record.stamp.mismatches() {
+        eprintln!("warning: {}: recorded game {}", path, mismatch);
+    }
+    record
+}
+
+fn review(path: &str, threshold: Option<&String>) {
+    let record = load_record(path);
+    let mut options = ReviewOptions::default();
+    if let Some(threshold) = threshold {
+        options.threshold = threshold
+            .parse()
+            .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a threshold", threshold)));
+    }
+
+    let review = review_game(&record, &options)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {:?}", path, error)));
+    println!("{}", review);
+}
+
+fn summary(path: &str) {
+    let record = load_record(path);
+    let mut bus = EventBus::new();
+    let handle = bus.subscribe(GameStatistics::new());
+    let game = record
+        .replay_observed(&mut bus)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {:?}", path, error)));
+    println!(
+        "{}",
+        format_summary(&game, bus.observer::<GameStatistics>(handle).unwrap())
+    );
+}
+
+fn upgrade(path: &str) {
+    let text = fs::read_to_string(path)
+        .unwrap_or_else(|error| exit_with_error(format!("could not read {}: {}", path, error)));
+    // Only recorded games start from an initial state.
+    let is_record = serde_json::from_str::<serde_json::Value>(&text)
+        .is_ok_and(|value| value.get("initial_state").is_some());
+    let (upgraded, migrations) = if is_record {
+        let (record, migrations) = upgrade_record(&text)
+            .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+        (serde_json::to_string(&record).unwrap(), migrations)
+    } else {
+        let (game, migrations) = load_game_leniently(&text)
+            .unwrap_or_else(|error| exit_with_error(format!("{}: {}", path, error)));
+        (save_game(&game), migrations)
+    };
+
+    fs::write(path, upgraded)
+        .unwrap_or_else(|error| exit_with_error(format!("could not write {}: {}", path, error)));
+    println!("{}", format_upgrade(path, &migrations));
+}
+
+fn format_upgrade(path: &str, migrations: &[Migration]) -> String {
+    if migrations.is_empty() {
+        return format!("{} is up to date", path);
+    }
+    let mut lines = vec![format!("upgraded {}:", path)];
+    for migration in migrations {
+        lines.push(format!("    {}", migration));
+    }
+    lines.join("\n")
+}
+
+fn what_if(path: &str, step: &str, replacement: Option<&String>) {
+    let record = load_record(path);
+    let step: usize = step
+        .parse()
+        .unwrap_or_else(|_| exit_with_error(format!("\"{}\" is not a step number", step)));
+    let replacement: Vec<GameStep> = match replacement {
+        Some(json) => serde_json::from_str(json)
+            .unwrap_or_else(|error| exit_with_error(format!("invalid replacement: {}", error))),
+        None => vec![],
+    };
+
+    let report = record
+        .what_if(&Alteration { step, replacement })
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {:?}", path, error)));
+    println!("{}", format_what_if(&report));
+}
+
+fn run_server(directory: &str, address: &str) {
+    let store = FileGameStore::open(directory)
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", directory, error)));
+    let manager = GameManager::new(Box::new(store))
+        .unwrap_or_else(|error| exit_with_error(format!("{}: {}", directory, error)));
+    let listener = TcpListener::bind(address)
+        .unwrap_or_else(|error| exit_with_error(format!("could not listen on {}: {}", address, error)));
+
+    eprintln!("hosting {} game(s) on {}", manager.list_games().len(), address);
+    serve(Arc::new(manager), listener)
+        .unwrap_or_else(|error| exit_with_error(format!("server stopped: {}", error)));
+}
+
+fn format_analysis(game: &GameState, cache: &dyn EvalCache) -> String {
+    let assumptions = cache.assumptions();
+    let knowledge = KnowledgeTracker::new(game);
+    let mut player_ids: Vec<_> = game.players.keys().copied().collect();
+    player_ids.sort();
+
+    let mut lines = vec![format!(
+        "generation {}: oxygen {}%, temperature {}C, {} oceans",
+        game.generation,
+        game.board.oxygen,
+        game.board.temperature,
+        game.board.oceans.len()
+    )];
+    for player_id in player_ids {
+        let projection = project_final_score(game, player_id, assumptions);
+        lines.push(format!(
+            "{:?}: {} VP now, {} VP projected after {} more generation(s) \
+             (+{} from {} greeneries, +{} from heat), evaluation {:+.1} \u{b1} {:.1}",
+            player_id,
+            projection.current_points,
+            projection.total_points,
+            projection.remaining_generations,
+            projection.greenery_points,
+            projection.projected_greeneries,
+            projection.temperature_points,
+            cached_evaluate(cache, game, player_id),
+            evaluate_ensemble(game, player_id, assumptions).spread,
+        ));
+        lines.push(format!(
+            "    evaluation: {}",
+            evaluate_with_trace(game, player_id, assumptions)
+        ));
+        let knowledge = &knowledge.players[&player_id];
+        let deck = CardRegistry::global().cards();
+        if let Some(threat) =
+            plant_destruction_warning(game, knowledge, deck, PLANT_WARNING_THRESHOLD)
+        {
+            lines.push(format!("    warning: {}", threat));
+        }
+        for synergy in player_synergies(&game.players[&player_id]) {
+            lines.push(format!("    synergy: {}", synergy));
+        }
+    }
+
+    lines.join("\n")
+}
+
+// Every card each player could play next, and passing, best first by the evaluation of
+// the position after it; the best is marked if it is clearly better than the next best,
+// and the rest if they are roughly as good as the best. Players with nothing to play
+// are left out.
+fn format_candidate_plays(game: &GameState, assumptions: &ProjectionAssumptions) -> String {
+    let mut player_ids: Vec<_> = game.players.keys().copied().collect();
+    player_ids.sort();
+
+    let mut lines = vec![];
+    for player_id in player_ids {
+        let player = &game.players[&player_id];
+        let mut candidates: Vec<(String, EvalEstimate)> =
+            vec![("pass".to_string(), evaluate_ensemble(game, player_id, assumptions))];
+        for card in &player.cards_in_hand {
+            if candidates.iter().any(|(name, _)| *name == card.name) {
+                continue;
+            }
+            let mut after = game.clone();
+            let state = after.players.get_mut(&player_id).unwrap();
+            if state.play_card(&game.board, card).is_some() {
+                candidates.push((
+                    card.name.clone(),
+                    evaluate_ensemble(&after, player_id, assumptions),
+                ));
+            }
+        }
+        if candidates.len() < 2 {
+            continue;
+        }
+        candidates.sort_by(|(_, a), (_, b)| b.value.total_cmp(&a.value));
+
+        lines.push(format!("{:?} plays:", player_id));
+        let best = candidates[0].1;
+        for (index, (name, estimate)) in candidates.iter().enumerate() {
+            let verdict = if index == 0 {
+                if best.is_clearly_better_than(&candidates[1].1) {
+                    ", clearly best"
+                } else {
+                    ""
+                }
+            } else if !best.is_clearly_better_than(estimate) {
+                ", roughly equal to the best"
+            } else {
+                ""
+            };
+            lines.push(format!("    {}: {}{}", name, estimate, verdict));
+        }
+    }
+    lines.join("\n")
+}
+
+fn format_puzzle(scenario: &Scenario, player: &PlayerState) -> String {
+    let mut lines = vec![format!(
+        "puzzle {} ({} game): the game ends after {} generation(s); \
+         which cards do you play, and when, to score the most victory points?",
+        scenario.name, scenario.stage, scenario.generations
+    )];
+    for card in &player.cards_in_hand {
+        lines.push(format!("    {}: {} M$", card.name, card.cost.amount()));
+    }
+    lines.join("\n")
+}
+
+fn format_puzzle_grade(scenario: &Scenario, answer: &[Vec<TurnAction>]) -> String {
+    let played_cards = |plays: &[Vec<TurnAction>]| -> Vec<String> {
+        plays
+            .iter()
+            .flatten()
+            .filter_map(|action| match action {
+                TurnAction::PlayCard(card, _) => Some(card.name.clone()),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let mut lines = vec![match scenario.points_of_line(answer) {
+        Ok(points) if points >= scenario.best_points => {
+            format!("solved: {} VP, as many as the best line", points)
+        }
+        Ok(points) => format!(
+            "missed: {} VP, {} below the best line",
+            points,
+            scenario.best_points - points
+        ),
+        Err(reason) => format!("missed: the line cannot be played, as {}", reason),
+    }];
+
+    lines.push(format!("best line ({} VP):", scenario.best_points));
+    for (generation, plays) in scenario.best_plays.iter().enumerate() {
+        let names = played_cards(std::slice::from_ref(plays));
+        lines.push(format!(
+            "    generation {}: {}",
+            generation + 1,
+            if names.is_empty() {
+                "pass".to_string()
+            } else {
+                names.join("; ")
+            }
+        ));
+    }
+
+    let best_cards = played_cards(&scenario.best_plays);
+    let answer_cards = played_cards(answer);
+    let left_out: Vec<&str> = best_cards
+        .iter()
+        .filter(|name| !answer_cards.contains(name))
+        .map(String::as_str)
+        .collect();
+    let extra: Vec<&str> = answer_cards
+        .iter()
+        .filter(|name| !best_cards.contains(name))
+        .map(String::as_str)
+        .collect();
+    if !left_out.is_empty() {
+        lines.push(format!("    the best line also plays {}", left_out.join("; ")));
+    }
+    if !extra.is_empty() {
+        lines.push(format!("    the best line does without {}", extra.join("; ")));
+    }
+    lines.join("\n")
+}
+
+fn format_deck_stats(decks: &[(String, Vec<Card>)]) -> String {
+    let mut lines: Vec<String> = decks
+        .iter()
+        .map(|(name, cards)| DeckStats::new(name, cards).to_string())
+        .collect();
+    for (card_name, deck_names) in find_overlaps(decks) {
+        lines.push(format!("overlap: {} in {}", card_name, deck_names.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn format_draft(game: &GameState, player_id: PlayerId, pack: &[Card]) -> String {
+    let knowledge = &KnowledgeTracker::new(game).players[&player_id];
+    let mut bot = SynergyDraftBot::for_options(&game.options);
+
+    let mut lines = vec![format!("pick {}", bot.pick(game, knowledge, pack).rationale)];
+    for candidate in bot.rank_pack(game, knowledge, pack) {
+        lines.push(format!("    {}", candidate.rationale));
+    }
+    lines.join("\n")
+}
+
+fn format_tuning(tuned: &TunedWeights, ladder: &Ladder) -> String {
+    let mut lines = vec![format!(
+        "{}@{}: {:+.1} points per game over the default weights, after {} games",
+        WEIGHTED_BOT, tuned.version, tuned.margin, tuned.provenance.games_played
+    )];
+    let weights = serde_json::to_value(&tuned.weights).unwrap();
+    for (name, weight) in weights.as_object().unwrap() {
+        lines.push(format!("    {}: {:.3}", name, weight.as_f64().unwrap()));
+    }
+    lines.push("ladder:".to_string());
+    for (agent, rating) in ladder.standings() {
+        lines.push(format!(
+            "    {}: {:.0} after {} games",
+            agent, rating.rating, rating.games
+        ));
+    }
+    lines.join("\n")
+}
+
+fn format_setup(advice: &[SetupAdvice]) -> String {
+    let names = |cards: &[Card]| {
+        cards
+            .iter()
+            .map(|card| card.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut lines = vec![];
+    for choice in advice {
+        let played: Vec<Card> = choice
+            .first_plays
+            .iter()
+            .filter_map(|action| match action {
+                TurnAction::PlayCard(card, _) => Some(card.clone()),
+                _ => None,
+            })
+            .collect();
+        lines.push(format!(
+            "{:.1} VP: {} with [{}], buy [{}], play [{}]",
+            choice.value,
+            choice.corporation,
+            choice.preludes.join(", "),
+            names(&choice.bought_cards),
+            names(&played)
+        ));
+    }
+    lines.join("\n")
+}
+
+fn format_summary(game: &GameState, statistics: &GameStatistics) -> String {
+    let mut lines = vec![format!("game over after generation {}", game.generation)];
+    for (player_id, breakdown) in score_all_players(game) {
+        let player_statistics = statistics
+            .players
+            .get(&player_id)
+            .cloned()
+            .unwrap_or_default();
+        let gained = |source| {
+            player_statistics
+                .terraform_rating_gained
+                .get(&source)
+                .copied()
+                .unwrap_or(0)
+        };
+        lines.push(format!("{:?}: {} VP", player_id, breakdown.total()));
+        lines.push(format!(
+            "    {} card(s) played for {} M$",
+            player_statistics.cards_played, player_statistics.megacredits_spent_on_cards
+        ));
+        lines.push(format!(
+            "    {} tile(s): {} cities, {} greeneries, {} oceans, {} special",
+            player_statistics.tiles_placed(),
+            player_statistics.cities_placed,
+            player_statistics.greeneries_placed,
+            player_statistics.oceans_placed,
+            player_statistics.special_tiles_placed
+        ));
+        lines.push(format!(
+            "    +{} TR: {} temperature, {} oxygen, {} oceans, {} other",
+            player_statistics.total_terraform_rating_gained(),
+            gained(TerraformSource::Temperature),
+            gained(TerraformSource::Oxygen),
+            gained(TerraformSource::Ocean),
+            gained(TerraformSource::Other)
+        ));
+    }
+    lines.join("\n")
+}
+
+fn format_archive_matches(index: &ArchiveIndex, query: &ArchiveQuery) -> String {
+    let mut lines = vec![];
+    let mut matches = 0;
+    for game in index.query(query) {
+        matches += 1;
+        let scores: Vec<String> = game
+            .players
+            .iter()
+            .map(|player| match &player.corporation {
+                Some(corporation) => format!("{} {} VP", corporation, player.final_score),
+                None => format!("{:?} {} VP", player.player_id, player.final_score),
+            })
+            .collect();
+        lines.push(format!(
+            "{}: generation {}, {}",
+            game.path.display(),
+            game.generation,
+            scores.join(", ")
+        ));
+    }
+    for (path, reason) in &index.skipped {
+        lines.push(format!("skipped {}: {}", path.display(), reason));
+    }
+    lines.push(format!("{} of {} game(s) match", matches, index.games.len()));
+    lines.join("\n")
+}
+
+fn format_what_if(report: &WhatIfReport) -> String {
+    let mut lines = vec![];
+    for (player_id, delta) in &report.score_deltas {
+        lines.push(format!(
+            "{:?}: {} VP -> {} VP ({:+})",
+            player_id, report.original_scores[player_id], report.altered_scores[player_id], delta
+        ));
+    }
+    for (index, error) in &report.skipped_steps {
+        lines.push(format!("skipped step {}: {:?}", index, error));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::Coordinates,
+        card::{BASE_GAME_DECK, CORPORATE_GAME_CARDS_BY_NAME},
+        archive::ArchiveQuery,
+        cli::{
+            format_analysis, format_candidate_plays, format_deck_stats, format_draft, format_setup,
+            format_summary, format_puzzle_grade, format_tuning, format_upgrade, format_what_if,
+            parse_archive_query, parse_puzzle_plays,
+        },
+        eval::EvalWeights,
+        eval_cache::InMemoryEvalCache,
+        event::GameEvent,
+        game::{IllegalOperation, PlayerId, TurnAction},
+        game_stats::GameStatistics,
+        ladder::Ladder,
+        migrate::Migration,
+        position::import_position,
+        projection::ProjectionAssumptions,
+        resource::Resource,
+        scenario::generate_scenarios,
+        setup::SetupAdvice,
+        tuning::{tune_weights, TuningOptions},
+        whatif::WhatIfReport,
+    };
+
+    #[test]
+    fn analysis_lists_every_player() {
+        let game =
+            import_position("generation 3\nplayer p1\nresources 0 0 0 8 0 0\nplayer p2\ntr 22\n")
+                .unwrap();
+        let assumptions = ProjectionAssumptions {
+            remaining_generations: Some(1),
+            ..Default::default()
+        };
+
+        let analysis = format_analysis(&game, &InMemoryEvalCache::new(assumptions));
+        let lines: Vec<_> = analysis.lines().collect();
+        assert_eq!(5, lines.len());
+        assert!(lines[1].starts_with("PlayerId(1): 20 VP now, 22 VP projected"));
+        assert_eq!(
+            "    evaluation: terraform rating +20.0, projected greeneries +2.0, \
+             best opponent (PlayerId(2)) -22.0, total +0.0",
+            lines[2]
+        );
+        assert!(lines[3].starts_with("PlayerId(2): 22 VP now, 22 VP projected"));
+    }
+
+    #[test]
+    fn analysis_warns_about_plants_at_risk() {
+        let game = import_position(
+            "player p1\nresources 0 0 0 9 0 0\nplayer p2\nresources 0 0 5 0 0 0\nhand Asteroid\n",
+        )
+        .unwrap();
+
+        let analysis = format_analysis(
+            &game,
+            &InMemoryEvalCache::new(ProjectionAssumptions::default()),
+        );
+        let lines: Vec<_> = analysis.lines().collect();
+        assert!(lines[2].contains("plants at risk -"));
+        assert!(lines[3].starts_with("    warning: PlayerId(2) may destroy 3 plant(s), "));
+        assert!(lines[4].starts_with("PlayerId(2)"));
+    }
+
+    #[test]
+    fn candidate_plays_tell_clearly_best_from_roughly_equal() {
+        let game = import_position(
+            "player p1\nresources 60 0 0 0 0 0\nhand Asteroid; Comet; Solar Power\nplayer p2\n",
+        )
+        .unwrap();
+        let assumptions = ProjectionAssumptions {
+            remaining_generations: Some(1),
+            ..Default::default()
+        };
+
+        // Each card is worth one point, and passing none.
+        assert_eq!(
+            "PlayerId(1) plays:
+    Asteroid: +1.0 \u{b1} 0.0
+    Comet: +1.0 \u{b1} 0.0, roughly equal to the best
+    Solar Power: +1.0 \u{b1} 0.0, roughly equal to the best
+    pass: +0.0 \u{b1} 0.0",
+            format_candidate_plays(&game, &assumptions)
+        );
+
+        let game =
+            import_position("player p1\nresources 60 0 0 0 0 0\nhand Asteroid\nplayer p2\n")
+                .unwrap();
+        assert_eq!(
+            "PlayerId(1) plays:\n    Asteroid: +1.0 \u{b1} 0.0, clearly best\n    \
+             pass: +0.0 \u{b1} 0.0",
+            format_candidate_plays(&game, &assumptions)
+        );
+    }
+
+    #[test]
+    fn analysis_lists_synergies_under_their_player() {
+        let game = import_position(
+            "player p1
+played Space Station
+hand Asteroid Mining
+player p2
+",
+        )
+        .unwrap();
+
+        let analysis = format_analysis(
+            &game,
+            &InMemoryEvalCache::new(ProjectionAssumptions::default()),
+        );
+        let lines: Vec<_> = analysis.lines().collect();
+        assert_eq!(6, lines.len());
+        assert_eq!(
+            "    synergy: Space Station (Played) -> Asteroid Mining (Hand): \
+             2 M$ discount for its Space tag",
+            lines[3]
+        );
+        assert!(lines[4].starts_with("PlayerId(2)"));
+    }
+
+    #[test]
+    fn deck_stats_summarize_every_deck_and_their_overlap() {
+        let decks = vec![
+            ("base".to_string(), BASE_GAME_DECK.clone()),
+            (
+                "homebrew.json".to_string(),
+                vec![CORPORATE_GAME_CARDS_BY_NAME["Asteroid Mining"].clone()],
+            ),
+        ];
+
+        let report = format_deck_stats(&decks);
+        assert!(report.starts_with(&format!("base: {} card(s)\n", BASE_GAME_DECK.len())));
+        assert!(report.contains("\nhomebrew.json: 1 card(s)\n"));
+        assert!(report.ends_with("\noverlap: Asteroid Mining in base, homebrew.json"));
+    }
+
+    #[test]
+    fn draft_shows_the_pick_and_every_candidate() {
+        let game = import_position("player p1\nplayed Space Station\n").unwrap();
+        let pack = vec![
+            CORPORATE_GAME_CARDS_BY_NAME["Heather"].clone(),
+            CORPORATE_GAME_CARDS_BY_NAME["Trans-Neptune Probe"].clone(),
+        ];
+
+        let draft = format_draft(&game, PlayerId::from(1), &pack);
+        let lines: Vec<_> = draft.lines().collect();
+        assert_eq!(3, lines.len());
+        assert!(lines[0].starts_with("pick Heather: "));
+        assert!(lines[0].ends_with("ahead of Trans-Neptune Probe (1.0 M$)"));
+        assert!(lines[1].starts_with("    Heather: "));
+        assert_eq!(
+            "    Trans-Neptune Probe: 1.0 M$ (costs 6 M$; 1 VP; synergy with Space Station)",
+            lines[2]
+        );
+    }
+
+    #[test]
+    fn setup_lists_every_choice_with_its_purchase() {
+        let heather = CORPORATE_GAME_CARDS_BY_NAME["Heather"].clone();
+        let advice = vec![
+            SetupAdvice {
+                corporation: "Ecoline".to_string(),
+                preludes: vec!["Donation".to_string(), "Mohole".to_string()],
+                bought_cards: vec![heather.clone()],
+                first_plays: vec![TurnAction::PlayCard(heather, vec![])],
+                value: 24.5,
+                card_value: 12.0,
+            },
+            SetupAdvice {
+                corporation: "Helion".to_string(),
+                preludes: vec![],
+                bought_cards: vec![],
+                first_plays: vec![],
+                value: 22.0,
+                card_value: 0.0,
+            },
+        ];
+
+        assert_eq!(
+            "24.5 VP: Ecoline with [Donation, Mohole], buy [Heather], play [Heather]\n\
+             22.0 VP: Helion with [], buy [], play []",
+            format_setup(&advice)
+        );
+    }
+
+    #[test]
+    fn summary_shows_scores_and_statistics_of_every_player() {
+        let game = import_position("player p1\nplayer p2").unwrap();
+        let p1 = PlayerId::from(1);
+        let statistics = GameStatistics::from_events(&[
+            GameEvent::ResourcesChanged(p1, btreemap! { Resource::Megacredits => -12 }),
+            GameEvent::CardPlayed(p1, CORPORATE_GAME_CARDS_BY_NAME["Research"].clone()),
+            GameEvent::OceanPlaced(Coordinates::new(5, -5)),
+            GameEvent::TerraformRatingRaised(p1, 1),
+        ]);
+
+        assert_eq!(
+            "game over after generation 1\n\
+             PlayerId(1): 20 VP\n    \
+             1 card(s) played for 12 M$\n    \
+             1 tile(s): 0 cities, 0 greeneries, 1 oceans, 0 special\n    \
+             +1 TR: 0 temperature, 0 oxygen, 1 oceans, 0 other\n\
+             PlayerId(2): 20 VP\n    \
+             0 card(s) played for 0 M$\n    \
+             0 tile(s): 0 cities, 0 greeneries, 0 oceans, 0 special\n    \
+             +0 TR: 0 temperature, 0 oxygen, 0 oceans, 0 other",
+            format_summary(&game, &statistics)
+        );
+    }
+
+    #[test]
+    fn archive_filters_make_up_the_query() {
+        let filters: Vec<String> = ["players=2", "card=Moss", "min-score=-3", "winner"]
+            .iter()
+            .map(|filter| filter.to_string())
+            .collect();
+        assert_eq!(
+            Ok(ArchiveQuery {
+                player_count: Some(2),
+                card: Some("Moss".to_string()),
+                min_score: Some(-3),
+                winner: true,
+                ..Default::default()
+            }),
+            parse_archive_query(&filters)
+        );
+
+        assert_eq!(
+            Err("\"players=two\" needs a number".to_string()),
+            parse_archive_query(&["players=two".to_string()])
+        );
+        assert_eq!(
+            Err("unknown filter \"card\"".to_string()),
+            parse_archive_query(&["card".to_string()])
+        );
+    }
+
+    #[test]
+    fn what_if_shows_score_changes_and_skipped_steps() {
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let report = WhatIfReport {
+            original_scores: btreemap! { p1 => 23, p2 => 20 },
+            altered_scores: btreemap! { p1 => 22, p2 => 20 },
+            score_deltas: btreemap! { p1 => -1, p2 => 0 },
+            skipped_steps: vec![(3, IllegalOperation::CardNotInHand("Research".to_string()))],
+        };
+
+        assert_eq!(
+            "PlayerId(1): 23 VP -> 22 VP (-1)\n\
+             PlayerId(2): 20 VP -> 20 VP (+0)\n\
+             skipped step 3: CardNotInHand(\"Research\")",
+            format_what_if(&report)
+        );
+    }
+
+    #[test]
+    fn puzzle_answers_are_graded_against_the_best_line() {
+        let scenario = generate_scenarios(5, 1).remove(0);
+        let grade = format_puzzle_grade(&scenario, &scenario.best_plays);
+        assert!(grade.starts_with("solved"), "{}", grade);
+
+        let grade = format_puzzle_grade(&scenario, &[]);
+        assert!(grade.starts_with("missed"), "{}", grade);
+        assert!(grade.contains("the best line also plays"), "{}", grade);
+
+        let not_in_hand = parse_puzzle_plays("Zeppelins; ").unwrap();
+        assert_eq!(1, not_in_hand.len());
+        let grade = format_puzzle_grade(&scenario, &[not_in_hand]);
+        assert!(grade.contains("cannot be played"), "{}", grade);
+
+        assert_eq!(Ok(vec![]), parse_puzzle_plays("  "));
+        assert!(parse_puzzle_plays("No Such Card").is_err());
+    }
+
+    #[test]
+    fn tuning_shows_the_margin_weights_and_ladder() {
+        let options = TuningOptions {
+            iterations: 1,
+            population: 2,
+            elite: 1,
+            games_per_candidate: 1,
+            max_generations: 1,
+            threads: 1,
+            seed: 2,
+            version: "test".to_string(),
+        };
+        let mut ladder = Ladder::new();
+        let tuned = tune_weights(&EvalWeights::default(), &options, &mut ladder);
+        let text = format_tuning(&tuned, &ladder);
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("weighted-bot@test: "), "{}", text);
+        assert!(
+            lines.iter().any(|line| line.starts_with("    terraform_rating: ")),
+            "{}",
+            text
+        );
+        assert_eq!(Some(&"ladder:"), lines.get(1 + EvalWeights::COUNT));
+        assert!(lines[2 + EvalWeights::COUNT..]
+            .iter()
+            .any(|line| line.starts_with("    weighted-bot@default: ")));
+    }
+
+    #[test]
+    fn upgrades_list_every_change() {
+        assert_eq!("game.json is up to date", format_upgrade("game.json", &[]));
+        let migrations = vec![
+            Migration::WrappedGameState,
+            Migration::DroppedField {
+                path: "game.ocean_count".to_string(),
+            },
+        ];
+        assert_eq!(
+            "upgraded game.json:\n    wrapped the game state in a save\n    \
+             game.ocean_count: dropped the unknown field",
+            format_upgrade("game.json", &migrations)
+        );
+    }
+}
@@ -0,0 +1,18 @@
+#![allow(dead_code)]
+
+// The modules of the engine and the agents are found at the same paths as our own.
+use tm_sim::*;
+
+mod auth;
+mod capabilities;
+mod cli;
+mod server;
+mod store;
+mod validation;
+#[cfg(feature = "webhooks")]
+mod webhook;
+
+pub fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    cli::run(&args);
+}
@@ -0,0 +1,1010 @@
+//! Server mode: host many games at once, and accept requests for them
+//! as newline-delimited JSON over TCP, one `Request` per line and one `Response` per line.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, RwLock},
+    thread,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{Access, GameCredentials},
+    capabilities::{capabilities, Capabilities},
+    decision::{AsyncGame, Decision, DecisionRequest, Seat},
+    event::GameEvent,
+    game::{GameState, PlayerId, PlayerTurn},
+    game_stats::GameStatistics,
+    position::import_position,
+    stamp::{BuildStamp, StampMismatch},
+    store::{GameId, GameMetadata, GameStore, StoreError},
+    validation::{ActionValidator, RateLimit, Rejection},
+    view::GameView,
+};
+
+#[derive(Debug)]
+pub enum ServerError {
+    InvalidGameId(String),
+    NoSuchGame(GameId),
+    GameAlreadyExists(GameId),
+    InvalidPosition(String),
+    Unauthorized,
+    // Turns cannot be submitted directly to a turn-based game, only through decisions.
+    TurnBased(GameId),
+    NotTurnBased(GameId),
+    InvalidSeats,
+    Rejected(Rejection),
+    Store(StoreError),
+    // The client plays by other rules or with other cards.
+    Incompatible(StampMismatch),
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::InvalidGameId(id) => write!(f, "\"{}\" is not a valid game id", id),
+            ServerError::NoSuchGame(game_id) => write!(f, "no game with id \"{}\"", game_id),
+            ServerError::GameAlreadyExists(game_id) => {
+                write!(f, "a game with id \"{}\" already exists", game_id)
+            }
+            ServerError::InvalidPosition(message) => write!(f, "invalid position: {}", message),
+            ServerError::Unauthorized => write!(f, "the token does not grant this access"),
+            ServerError::TurnBased(game_id) => {
+                write!(f, "game \"{}\" only accepts answers to decisions", game_id)
+            }
+            ServerError::NotTurnBased(game_id) => {
+                write!(f, "game \"{}\" does not ask for decisions", game_id)
+            }
+            ServerError::InvalidSeats => write!(f, "the seats do not match the players"),
+            ServerError::Rejected(rejection) => rejection.fmt(f),
+            ServerError::Store(error) => error.fmt(f),
+            ServerError::Incompatible(mismatch) => write!(f, "client {}", mismatch),
+        }
+    }
+}
+
+impl From<StoreError> for ServerError {
+    fn from(error: StoreError) -> Self {
+        ServerError::Store(error)
+    }
+}
+
+/// Hosts any number of games, each behind its own lock: requests for different games
+/// proceed in parallel, while requests for the same game are applied one at a time.
+///
+/// Access to each game requires one of the tokens issued when it was created: seat tokens
+/// allow acting as that player, and the spectator token allows viewing the public state.
+/// Every submitted turn is validated before it is applied. Every change is written
+/// to the `GameStore` before it is acknowledged, and all stored games are hosted again
+/// when the manager is restarted.
+///
+/// Games created with seats are turn-based: the manager asks each seat for its decisions,
+/// bots answer right away, and humans answer whenever they get to it, even after a restart.
+pub struct GameManager {
+    store: Box<dyn GameStore>,
+    validator: ActionValidator,
+    games: RwLock<BTreeMap<GameId, Arc<HostedGame>>>,
+    load_warnings: Vec<(GameId, StampMismatch)>,
+}
+
+struct HostedGame {
+    credentials: GameCredentials,
+    state: Mutex<HostedState>,
+}
+
+struct HostedState {
+    game: GameState,
+    async_game: Option<AsyncGame>,
+    statistics: GameStatistics,
+}
+
+impl GameManager {
+    pub fn new(store: Box<dyn GameStore>) -> Result<Self, ServerError> {
+        let mut games = BTreeMap::new();
+        let mut load_warnings = vec![];
+        for game_id in store.list()? {
+            let mut metadata = store.load_metadata(&game_id)?;
+            let (mut game, warnings) = store.load(&game_id)?;
+            load_warnings.extend(warnings.into_iter().map(|warning| (game_id.clone(), warning)));
+            if let Some(async_game) = &mut metadata.async_game {
+                let events = async_game.resume(&mut game);
+                if !events.is_empty() {
+                    store.append_events(&game_id, &events)?;
+                    store.save(&game_id, &game)?;
+                    store.save_metadata(&game_id, &metadata)?;
+                }
+            }
+
+            let hosted_game = HostedGame {
+                credentials: metadata.credentials,
+                state: Mutex::new(HostedState {
+                    game,
+                    async_game: metadata.async_game,
+                    statistics: GameStatistics::from_events(&store.load_events(&game_id)?),
+                }),
+            };
+            games.insert(game_id, Arc::new(hosted_game));
+        }
+
+        Ok(Self {
+            store,
+            validator: ActionValidator::default(),
+            games: RwLock::new(games),
+            load_warnings,
+        })
+    }
+
+    // How the builds that saved the hosted games differ from this one, for the caller to report.
+    pub fn load_warnings(&self) -> &[(GameId, StampMismatch)] {
+        &self.load_warnings
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.validator = ActionValidator::new(rate_limit);
+        self
+    }
+
+    pub fn list_games(&self) -> Vec<GameId> {
+        self.games.read().unwrap().keys().cloned().collect()
+    }
+
+    // The returned credentials are the only copy handed out; they must be passed on to the players.
+    // Without seats, the game accepts the turns of whichever seated player's turn it is.
+    pub fn create_game(
+        &self,
+        game_id: GameId,
+        mut game: GameState,
+        seats: Option<BTreeMap<PlayerId, Seat>>,
+    ) -> Result<GameCredentials, ServerError> {
+        let mut games = self.games.write().unwrap();
+        if games.contains_key(&game_id) {
+            return Err(ServerError::GameAlreadyExists(game_id));
+        }
+
+        if let Some(seats) = &seats {
+            if !seats.keys().eq(game.players.keys()) {
+                return Err(ServerError::InvalidSeats);
+            }
+        }
+
+        // Handicaps and the solo rules are part of the setup, so they come before anything else.
+        let mut events = game
+            .apply_handicaps()
+            .map_err(|error| ServerError::InvalidPosition(error.to_string()))?;
+        events.extend(
+            game.apply_solo_setup()
+                .map_err(|error| ServerError::InvalidPosition(error.to_string()))?,
+        );
+
+        let credentials = GameCredentials::issue(game.players.keys().copied());
+        let async_game = seats.map(|seats| {
+            let (async_game, start_events) = AsyncGame::start(&mut game, seats);
+            events.extend(start_events);
+            async_game
+        });
+        self.store.save(&game_id, &game)?;
+        self.store.append_events(&game_id, &events)?;
+        self.store.save_metadata(
+            &game_id,
+            &GameMetadata {
+                credentials: credentials.clone(),
+                async_game: async_game.clone(),
+            },
+        )?;
+
+        let hosted_game = HostedGame {
+            credentials: credentials.clone(),
+            state: Mutex::new(HostedState {
+                game,
+                async_game,
+                statistics: GameStatistics::from_events(&events),
+            }),
+        };
+        games.insert(game_id, Arc::new(hosted_game));
+        Ok(credentials)
+    }
+
+    pub fn delete_game(&self, game_id: &GameId) -> Result<(), ServerError> {
+        let mut games = self.games.write().unwrap();
+        if games.remove(game_id).is_none() {
+            return Err(ServerError::NoSuchGame(game_id.clone()));
+        }
+
+        self.store.delete(game_id)?;
+        Ok(())
+    }
+
+    pub fn get_game(&self, game_id: &GameId) -> Result<GameState, ServerError> {
+        let hosted_game = self.hosted_game(game_id)?;
+        let state = hosted_game.state.lock().unwrap();
+        Ok(state.game.clone())
+    }
+
+    pub fn authenticate(&self, game_id: &GameId, token: &str) -> Result<Access, ServerError> {
+        self.hosted_game(game_id)?
+            .credentials
+            .authenticate(token)
+            .ok_or(ServerError::Unauthorized)
+    }
+
+    pub fn view(&self, game_id: &GameId, token: &str) -> Result<GameView, ServerError> {
+        let viewer = match self.authenticate(game_id, token)? {
+            Access::Seat(player_id) => Some(player_id),
+            Access::Spectator => None,
+        };
+        Ok(GameView::new(&self.get_game(game_id)?, viewer))
+    }
+
+    // Statistics only use public information, so any token will do.
+    pub fn statistics(&self, game_id: &GameId, token: &str) -> Result<GameStatistics, ServerError> {
+        self.authenticate(game_id, token)?;
+        let hosted_game = self.hosted_game(game_id)?;
+        let state = hosted_game.state.lock().unwrap();
+        Ok(state.statistics.clone())
+    }
+
+    // Takes the turn on behalf of the player whose seat token was given.
+    pub fn submit(
+        &self,
+        game_id: &GameId,
+        token: &str,
+        turn: PlayerTurn,
+    ) -> Result<Vec<GameEvent>, ServerError> {
+        let player_id = self.seated_player(game_id, token)?;
+        self.apply(game_id, player_id, turn)
+    }
+
+    pub fn apply(
+        &self,
+        game_id: &GameId,
+        player_id: PlayerId,
+        turn: PlayerTurn,
+    ) -> Result<Vec<GameEvent>, ServerError> {
+        let hosted_game = self.hosted_game(game_id)?;
+        let mut state = hosted_game.state.lock().unwrap();
+        if state.async_game.is_some() {
+            return Err(ServerError::TurnBased(game_id.clone()));
+        }
+
+        self.validator
+            .validate(game_id, &state.game, player_id, &turn)
+            .map_err(ServerError::Rejected)?;
+        let events = state
+            .game
+            .take_turn(player_id, &turn)
+            .map_err(|error| ServerError::Rejected(Rejection::Illegal(error)))?;
+        self.store.append_events(game_id, &events)?;
+        self.store.save(game_id, &state.game)?;
+        for event in &events {
+            state.statistics.observe(event);
+        }
+        Ok(events)
+    }
+
+    pub fn pending_decisions(
+        &self,
+        game_id: &GameId,
+        player_id: PlayerId,
+    ) -> Result<Vec<DecisionRequest>, ServerError> {
+        let hosted_game = self.hosted_game(game_id)?;
+        let state = hosted_game.state.lock().unwrap();
+        let async_game = state
+            .async_game
+            .as_ref()
+            .ok_or_else(|| ServerError::NotTurnBased(game_id.clone()))?;
+        Ok(async_game.pending_for(player_id).cloned().collect())
+    }
+
+    // Answers one of the player's pending requests, and lets any bots answer theirs.
+    pub fn decide(
+        &self,
+        game_id: &GameId,
+        player_id: PlayerId,
+        request_id: u64,
+        decision: Decision,
+    ) -> Result<Vec<GameEvent>, ServerError> {
+        let hosted_game = self.hosted_game(game_id)?;
+        let mut state = hosted_game.state.lock().unwrap();
+        let HostedState {
+            game,
+            async_game,
+            statistics,
+        } = &mut *state;
+        let async_game = async_game
+            .as_mut()
+            .ok_or_else(|| ServerError::NotTurnBased(game_id.clone()))?;
+
+        self.validator
+            .check_rate_limit(game_id, player_id)
+            .map_err(ServerError::Rejected)?;
+        let events = async_game
+            .answer(game, player_id, request_id, decision)
+            .map_err(|error| ServerError::Rejected(Rejection::InvalidDecision(error)))?;
+        self.store.append_events(game_id, &events)?;
+        self.store.save(game_id, game)?;
+        self.store.save_metadata(
+            game_id,
+            &GameMetadata {
+                credentials: hosted_game.credentials.clone(),
+                async_game: Some(async_game.clone()),
+            },
+        )?;
+        for event in &events {
+            statistics.observe(event);
+        }
+        Ok(events)
+    }
+
+    fn seated_player(&self, game_id: &GameId, token: &str) -> Result<PlayerId, ServerError> {
+        match self.authenticate(game_id, token)? {
+            Access::Seat(player_id) => Ok(player_id),
+            Access::Spectator => Err(ServerError::Unauthorized),
+        }
+    }
+
+    // Only the map of games is locked here, and only briefly.
+    fn hosted_game(&self, game_id: &GameId) -> Result<Arc<HostedGame>, ServerError> {
+        self.games
+            .read()
+            .unwrap()
+            .get(game_id)
+            .cloned()
+            .ok_or_else(|| ServerError::NoSuchGame(game_id.clone()))
+    }
+
+    // One line of a connection, as sent by the client.
+    pub fn handle_line(&self, line: &str) -> Response {
+        match serde_json::from_str::<Request>(line) {
+            Ok(request) => self.handle(request),
+            Err(e) => Response::Error(format!("malformed request: {}", e)),
+        }
+    }
+
+    pub fn handle(&self, request: Request) -> Response {
+        let result = match request {
+            Request::Capabilities => Ok(Response::Capabilities(Box::new(capabilities()))),
+            Request::Handshake { stamp } => stamp
+                .check()
+                .map(|warnings| Response::Handshake {
+                    stamp: BuildStamp::current(),
+                    warnings,
+                })
+                .map_err(ServerError::Incompatible),
+            Request::ListGames => Ok(Response::Games(self.list_games())),
+            Request::CreateGame {
+                game_id,
+                position,
+                seats,
+            } => parse_game_id(&game_id).and_then(|game_id| {
+                let game = import_position(&position)
+                    .map_err(|e| ServerError::InvalidPosition(e.to_string()))?;
+                let credentials = self.create_game(game_id.clone(), game, seats)?;
+                Ok(Response::Created {
+                    game_id,
+                    credentials,
+                })
+            }),
+            // Any seated player may delete the game.
+            Request::DeleteGame { game_id, token } => parse_game_id(&game_id)
+                .and_then(|game_id| {
+                    self.seated_player(&game_id, &token)?;
+                    self.delete_game(&game_id).map(|_| game_id)
+                })
+                .map(Response::Deleted),
+            Request::GetGame { game_id, token } => parse_game_id(&game_id)
+                .and_then(|game_id| self.view(&game_id, &token))
+                .map(|view| Response::Game(Box::new(view))),
+            Request::GetStatistics { game_id, token } => parse_game_id(&game_id)
+                .and_then(|game_id| self.statistics(&game_id, &token))
+                .map(Response::Statistics),
+            Request::Apply {
+                game_id,
+                token,
+                turn,
+            } => parse_game_id(&game_id)
+                .and_then(|game_id| self.submit(&game_id, &token, turn))
+                .map(Response::Applied),
+            Request::PendingDecisions { game_id, token } => parse_game_id(&game_id)
+                .and_then(|game_id| {
+                    let player_id = self.seated_player(&game_id, &token)?;
+                    self.pending_decisions(&game_id, player_id)
+                })
+                .map(Response::Decisions),
+            Request::Decide {
+                game_id,
+                token,
+                request_id,
+                decision,
+            } => parse_game_id(&game_id)
+                .and_then(|game_id| {
+                    let player_id = self.seated_player(&game_id, &token)?;
+                    self.decide(&game_id, player_id, request_id, decision)
+                })
+                .map(Response::Applied),
+        };
+
+        result.unwrap_or_else(|error| match error {
+            ServerError::Rejected(rejection) => Response::Rejected(rejection),
+            _ => Response::Error(error.to_string()),
+        })
+    }
+}
+
+fn parse_game_id(id: &str) -> Result<GameId, ServerError> {
+    GameId::new(id).ok_or_else(|| ServerError::InvalidGameId(id.to_string()))
+}
+
+/// Bumped whenever requests or responses change incompatibly.
+pub const PROTOCOL_VERSION: u32 = 4;
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Request {
+    // what this build of the engine supports, e.g. to check that it speaks the client's protocol
+    Capabilities,
+    // the client's build, refused if it plays by other rules or with other cards
+    Handshake {
+        stamp: BuildStamp,
+    },
+    ListGames,
+    // the position is in the notation of `import_position()`;
+    // with seats for every player, the game is turn-based
+    CreateGame {
+        game_id: String,
+        position: String,
+        #[serde(default)]
+        seats: Option<BTreeMap<PlayerId, Seat>>,
+    },
+    DeleteGame {
+        game_id: String,
+        token: String,
+    },
+    GetGame {
+        game_id: String,
+        token: String,
+    },
+    // per-player statistics of the game so far, for any seat or the spectator
+    GetStatistics {
+        game_id: String,
+        token: String,
+    },
+    // the turn is taken by the player whose seat token is given
+    Apply {
+        game_id: String,
+        token: String,
+        turn: PlayerTurn,
+    },
+    // the decision requests of a turn-based game that wait for the seat's answer
+    PendingDecisions {
+        game_id: String,
+        token: String,
+    },
+    Decide {
+        game_id: String,
+        token: String,
+        request_id: u64,
+        decision: Decision,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Response {
+    Capabilities(Box<Capabilities>),
+    // the server's build, and how the client's differs from it without being refused
+    Handshake {
+        stamp: BuildStamp,
+        warnings: Vec<StampMismatch>,
+    },
+    Games(Vec<GameId>),
+    Created {
+        game_id: GameId,
+        credentials: GameCredentials,
+    },
+    Deleted(GameId),
+    Game(Box<GameView>),
+    Statistics(GameStatistics),
+    Applied(Vec<GameEvent>),
+    Decisions(Vec<DecisionRequest>),
+    Rejected(Rejection),
+    Error(String),
+}
+
+/// Serve requests until the listener fails, with one thread per connection.
+pub fn serve(manager: Arc<GameManager>, listener: TcpListener) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let manager = manager.clone();
+        thread::spawn(move || {
+            // A client that goes away only ends its own connection.
+            let _ = handle_connection(&manager, stream);
+        });
+    }
+    Ok(())
+}
+
+fn handle_connection(manager: &GameManager, stream: TcpStream) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = manager.handle_line(&line);
+        writeln!(writer, "{}", serde_json::to_string(&response).unwrap())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread, time::Duration};
+
+    use maplit::btreemap;
+
+    use crate::{
+        auth::GameCredentials,
+        decision::{Decision, DecisionError, DecisionKind, Seat},
+        board::Coordinates,
+        card::BASE_GAME_CARDS_BY_NAME,
+        event::GameEvent,
+        game::{
+            GameOperation, GameState, IllegalOperation, PlayerId, PlayerTurn, StandardProject,
+            TurnAction,
+        },
+        game_stats::TerraformSource,
+        options::Handicap,
+        resource::Resource,
+        server::{GameManager, Request, Response},
+        stamp::BuildStamp,
+        store::{GameId, GameStore, InMemoryGameStore},
+        validation::{RateLimit, Rejection},
+    };
+
+    fn create_request(game_id: &str) -> Request {
+        Request::CreateGame {
+            game_id: game_id.to_string(),
+            position: "player p1\nhand Solar Power\nplayer p2".to_string(),
+            seats: None,
+        }
+    }
+
+    fn create_game(manager: &GameManager, game_id: &str) -> GameCredentials {
+        match manager.handle(create_request(game_id)) {
+            Response::Created { credentials, .. } => credentials,
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+
+    fn seat_token(credentials: &GameCredentials, player_id: usize) -> String {
+        credentials.seats[&PlayerId::from(player_id)]
+            .as_str()
+            .to_string()
+    }
+
+    #[test]
+    fn games_are_created_listed_and_deleted() {
+        let manager = GameManager::new(Box::new(InMemoryGameStore::new())).unwrap();
+        let game_id = GameId::new("mars").unwrap();
+
+        let credentials = create_game(&manager, "mars");
+        assert_eq!(2, credentials.seats.len());
+        assert!(matches!(
+            manager.handle(create_request("mars")),
+            Response::Error(_)
+        ));
+        assert!(matches!(
+            manager.handle(create_request("not a valid id")),
+            Response::Error(_)
+        ));
+        assert_eq!(
+            Response::Games(vec![game_id.clone()]),
+            manager.handle(Request::ListGames)
+        );
+
+        let delete_request = |token: &str| Request::DeleteGame {
+            game_id: "mars".to_string(),
+            token: token.to_string(),
+        };
+        assert!(matches!(
+            manager.handle(delete_request(credentials.spectator.as_str())),
+            Response::Error(_)
+        ));
+        assert_eq!(
+            Response::Deleted(game_id),
+            manager.handle(delete_request(&seat_token(&credentials, 2)))
+        );
+        assert_eq!(Response::Games(vec![]), manager.handle(Request::ListGames));
+    }
+
+    #[test]
+    fn turns_are_routed_to_their_game_and_persisted() {
+        let store = Arc::new(InMemoryGameStore::new());
+        let manager = Arc::new(GameManager::new(Box::new(store.clone())).unwrap());
+        let game_ids: Vec<String> = (0..4).map(|i| format!("game-{}", i)).collect();
+        // With a single player, every turn is theirs.
+        let tokens: Vec<String> = game_ids
+            .iter()
+            .map(|game_id| {
+                let request = Request::CreateGame {
+                    game_id: game_id.to_string(),
+                    position: "player p1\nresources 100 0 0 0 0 0\nhand Solar Power".to_string(),
+                    seats: None,
+                };
+                match manager.handle(request) {
+                    Response::Created { credentials, .. } => seat_token(&credentials, 1),
+                    response => panic!("unexpected response: {:?}", response),
+                }
+            })
+            .collect();
+        let power_plant = || {
+            PlayerTurn::Play(
+                TurnAction::PlayStandardProject(StandardProject::PowerPlant),
+                None,
+            )
+        };
+
+        // Each thread builds a power plant in its own game, plus one in the first game.
+        let handles: Vec<_> = game_ids
+            .iter()
+            .cloned()
+            .zip(tokens.iter().cloned())
+            .map(|(game_id, token)| {
+                let manager = manager.clone();
+                let first_game_token = tokens[0].clone();
+                thread::spawn(move || {
+                    for (id, token) in [(game_id.as_str(), token), ("game-0", first_game_token)] {
+                        let response = manager.handle(Request::Apply {
+                            game_id: id.to_string(),
+                            token,
+                            turn: power_plant(),
+                        });
+                        assert!(matches!(response, Response::Applied(_)));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let energy_production =
+            |game: GameState| game.players[&PlayerId::from(1)].production[Resource::Energy];
+        let hosted = |id: &str| manager.get_game(&GameId::new(id).unwrap()).unwrap();
+        assert_eq!(5, energy_production(hosted("game-0")));
+        assert_eq!(1, energy_production(hosted("game-3")));
+
+        let first = GameId::new("game-0").unwrap();
+        assert_eq!(5, energy_production(store.load(&first).unwrap().0));
+        assert_eq!(
+            5,
+            store
+                .load_events(&first)
+                .unwrap()
+                .iter()
+                .filter(|event| matches!(event, GameEvent::ProductionChanged(..)))
+                .count()
+        );
+
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+        manager.handle(Request::Apply {
+            game_id: "game-1".to_string(),
+            token: tokens[1].clone(),
+            turn: PlayerTurn::Play(
+                TurnAction::PlayStandardProject(StandardProject::SellPatents(vec![solar_power])),
+                None,
+            ),
+        });
+
+        // Both the game and its tokens survive a restart.
+        let restarted = GameManager::new(Box::new(store)).unwrap();
+        match restarted.handle(Request::GetGame {
+            game_id: "game-1".to_string(),
+            token: tokens[1].clone(),
+        }) {
+            Response::Game(view) => assert!(view.players[0].cards_in_hand.is_empty()),
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn tokens_control_what_can_be_done_and_seen() {
+        let manager = GameManager::new(Box::new(InMemoryGameStore::new())).unwrap();
+        let credentials = create_game(&manager, "mars");
+        let other_credentials = create_game(&manager, "other");
+
+        let before = manager.get_game(&GameId::new("mars").unwrap()).unwrap();
+        let apply = |token: &str| {
+            manager.handle(Request::Apply {
+                game_id: "mars".to_string(),
+                token: token.to_string(),
+                turn: PlayerTurn::Pass,
+            })
+        };
+        assert!(matches!(
+            apply(credentials.spectator.as_str()),
+            Response::Error(_)
+        ));
+        assert!(matches!(
+            apply(&seat_token(&other_credentials, 1)),
+            Response::Error(_)
+        ));
+        assert!(matches!(apply("guess"), Response::Error(_)));
+        assert_eq!(
+            before,
+            manager.get_game(&GameId::new("mars").unwrap()).unwrap()
+        );
+
+        let hand_sizes_seen_with = |token: &str| match manager.handle(Request::GetGame {
+            game_id: "mars".to_string(),
+            token: token.to_string(),
+        }) {
+            Response::Game(view) => view
+                .players
+                .iter()
+                .map(|player| (player.summary.hand_size, player.cards_in_hand.len()))
+                .collect::<Vec<_>>(),
+            response => panic!("unexpected response: {:?}", response),
+        };
+        assert_eq!(
+            vec![(1, 1), (0, 0)],
+            hand_sizes_seen_with(&seat_token(&credentials, 1))
+        );
+        assert_eq!(
+            vec![(1, 0), (0, 0)],
+            hand_sizes_seen_with(credentials.spectator.as_str())
+        );
+    }
+
+    #[test]
+    fn statistics_follow_the_game_and_survive_a_restart() {
+        let store = Arc::new(InMemoryGameStore::new());
+        let manager = GameManager::new(Box::new(store.clone())).unwrap();
+        let credentials = match manager.handle(Request::CreateGame {
+            game_id: "mars".to_string(),
+            position: "player p1\nresources 0 0 0 8 0 0\nplayer p2".to_string(),
+            seats: None,
+        }) {
+            Response::Created { credentials, .. } => credentials,
+            response => panic!("unexpected response: {:?}", response),
+        };
+        assert!(matches!(
+            manager.handle(Request::Apply {
+                game_id: "mars".to_string(),
+                token: seat_token(&credentials, 1),
+                turn: PlayerTurn::Play(TurnAction::ConvertPlants(Coordinates::new(3, 0)), None),
+            }),
+            Response::Applied(_)
+        ));
+
+        let request = Request::GetStatistics {
+            game_id: "mars".to_string(),
+            token: credentials.spectator.as_str().to_string(),
+        };
+        let statistics = match manager.handle(request.clone()) {
+            Response::Statistics(statistics) => statistics,
+            response => panic!("unexpected response: {:?}", response),
+        };
+        assert_eq!(
+            btreemap! { TerraformSource::Oxygen => 1 },
+            statistics.players[&PlayerId::from(1)].terraform_rating_gained
+        );
+
+        let restarted = GameManager::new(Box::new(store)).unwrap();
+        assert_eq!(Response::Statistics(statistics), restarted.handle(request));
+    }
+
+    #[test]
+    fn rejected_turns_leave_the_game_untouched() {
+        let manager = GameManager::new(Box::new(InMemoryGameStore::new()))
+            .unwrap()
+            .with_rate_limit(RateLimit {
+                max_actions: 1,
+                window: Duration::from_secs(3600),
+            });
+        let token = seat_token(&create_game(&manager, "mars"), 1);
+        let before = manager.get_game(&GameId::new("mars").unwrap()).unwrap();
+
+        let submit = |turn| {
+            manager.handle(Request::Apply {
+                game_id: "mars".to_string(),
+                token: token.clone(),
+                turn,
+            })
+        };
+        assert_eq!(
+            Response::Rejected(Rejection::Illegal(IllegalOperation::NotImplemented)),
+            submit(PlayerTurn::Play(TurnAction::ClaimMilestone, None))
+        );
+        assert!(matches!(
+            submit(PlayerTurn::Pass),
+            Response::Rejected(Rejection::RateLimited { .. })
+        ));
+        assert_eq!(
+            before,
+            manager.get_game(&GameId::new("mars").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn clients_cannot_submit_raw_operations() {
+        let manager = GameManager::new(Box::new(InMemoryGameStore::new())).unwrap();
+        let token = seat_token(&create_game(&manager, "mars"), 1);
+        let before = manager.get_game(&GameId::new("mars").unwrap()).unwrap();
+
+        // e.g. granting themselves megacredits
+        let operation = GameOperation::ChangeResources(
+            PlayerId::from(1),
+            btreemap! { Resource::Megacredits => 100 },
+        );
+        let line = format!(
+            r#"{{"Apply":{{"game_id":"mars","token":"{}","operation":{}}}}}"#,
+            token,
+            serde_json::to_string(&operation).unwrap()
+        );
+        match manager.handle_line(&line) {
+            Response::Error(message) => assert!(message.starts_with("malformed request")),
+            response => panic!("unexpected response: {:?}", response),
+        }
+        assert_eq!(
+            before,
+            manager.get_game(&GameId::new("mars").unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn handshakes_refuse_clients_with_other_rules_or_cards() {
+        let manager = GameManager::new(Box::new(InMemoryGameStore::new())).unwrap();
+        let handshake = |stamp| manager.handle(Request::Handshake { stamp });
+
+        let older_engine = BuildStamp {
+            engine_version: "0.0.1".to_string(),
+            ..BuildStamp::current()
+        };
+        match handshake(older_engine) {
+            Response::Handshake { stamp, warnings } => {
+                assert_eq!(BuildStamp::current(), stamp);
+                assert_eq!(1, warnings.len());
+            }
+            response => panic!("unexpected response: {:?}", response),
+        }
+
+        let other_cards = BuildStamp {
+            deck_hash: "0123456789abcdef".to_string(),
+            ..BuildStamp::current()
+        };
+        match handshake(other_cards) {
+            Response::Error(message) => {
+                assert!(message.starts_with("client made with different card data"))
+            }
+            response => panic!("unexpected response: {:?}", response),
+        }
+    }
+
+    #[test]
+    fn handicaps_are_applied_at_setup_and_logged() {
+        let store = Arc::new(InMemoryGameStore::new());
+        let manager = GameManager::new(Box::new(store.clone())).unwrap();
+        assert!(matches!(
+            manager.handle(Request::CreateGame {
+                game_id: "mars".to_string(),
+                position: "player p1\nplayer p2\nhandicap 5 1 2\n".to_string(),
+                seats: None,
+            }),
+            Response::Created { .. }
+        ));
+
+        let game_id = GameId::new("mars").unwrap();
+        let game = manager.get_game(&game_id).unwrap();
+        let p1 = &game.players[&PlayerId::from(1)];
+        let p2 = &game.players[&PlayerId::from(2)];
+        assert_eq!(5, p2.resources[&Resource::Megacredits]);
+        assert_eq!(p1.terraform_rating + 1, p2.terraform_rating);
+        assert_eq!(2, p2.cards_in_hand.len());
+        assert!(p1.cards_in_hand.is_empty());
+
+        let events = store.load_events(&game_id).unwrap();
+        assert_eq!(
+            GameEvent::HandicapApplied(
+                PlayerId::from(2),
+                Handicap {
+                    megacredits: 5,
+                    terraform_rating: 1,
+                    cards: 2,
+                }
+            ),
+            events[0]
+        );
+        assert!(matches!(events[3], GameEvent::CardsDrawn(..)));
+    }
+
+    #[test]
+    fn pending_decisions_survive_a_restart() {
+        let store = Arc::new(InMemoryGameStore::new());
+        let manager = GameManager::new(Box::new(store.clone())).unwrap();
+        let credentials = match manager.handle(Request::CreateGame {
+            game_id: "mars".to_string(),
+            position: "player p1\nresources 30 0 0 0 0 0\nplayer p2".to_string(),
+            seats: Some(btreemap! {
+                PlayerId::from(1) => Seat::Human,
+                PlayerId::from(2) => Seat::Bot,
+            }),
+        }) {
+            Response::Created { credentials, .. } => credentials,
+            response => panic!("unexpected response: {:?}", response),
+        };
+        let token = seat_token(&credentials, 1);
+        let pending = |manager: &GameManager| match manager.handle(Request::PendingDecisions {
+            game_id: "mars".to_string(),
+            token: token.clone(),
+        }) {
+            Response::Decisions(requests) => requests,
+            response => panic!("unexpected response: {:?}", response),
+        };
+
+        // Only the human is waited for, and only through decisions.
+        let requests = pending(&manager);
+        assert_eq!(1, requests.len());
+        assert!(matches!(requests[0].kind, DecisionKind::Research(_)));
+        assert!(matches!(
+            manager.handle(Request::Apply {
+                game_id: "mars".to_string(),
+                token: token.clone(),
+                turn: PlayerTurn::Pass,
+            }),
+            Response::Error(_)
+        ));
+        assert_eq!(
+            Response::Rejected(Rejection::InvalidDecision(
+                DecisionError::WrongKindOfDecision
+            )),
+            manager.handle(Request::Decide {
+                game_id: "mars".to_string(),
+                token: token.clone(),
+                request_id: requests[0].id,
+                decision: Decision::Pass,
+            })
+        );
+
+        // The human answers, and the server restarts before the next answer.
+        assert!(matches!(
+            manager.handle(Request::Decide {
+                game_id: "mars".to_string(),
+                token: token.clone(),
+                request_id: requests[0].id,
+                decision: Decision::BuyCards(vec![]),
+            }),
+            Response::Applied(_)
+        ));
+        let turn = pending(&manager);
+        drop(manager);
+
+        let restarted = GameManager::new(Box::new(store)).unwrap();
+        assert_eq!(turn, pending(&restarted));
+        assert_eq!(DecisionKind::TakeTurn, turn[0].kind);
+        assert!(matches!(
+            restarted.handle(Request::Decide {
+                game_id: "mars".to_string(),
+                token: token.clone(),
+                request_id: turn[0].id,
+                decision: Decision::Act(TurnAction::PlayStandardProject(
+                    StandardProject::Greenery(Coordinates::new(3, -1))
+                )),
+            }),
+            Response::Applied(_)
+        ));
+        assert_eq!(
+            1,
+            restarted
+                .get_game(&GameId::new("mars").unwrap())
+                .unwrap()
+                .board
+                .oxygen
+        );
+    }
+}
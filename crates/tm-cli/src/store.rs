@@ -0,0 +1,524 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::GameCredentials,
+    decision::AsyncGame,
+    event::GameEvent,
+    game::GameState,
+    save::{load_game_with_warnings, save_game, LoadError},
+    stamp::StampMismatch,
+};
+
+/// Identifies a hosted game. Ids are restricted to ASCII letters, digits, `-`, and `_`,
+/// so that they can be used as file names and in URLs as-is.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GameId(String);
+
+impl GameId {
+    pub fn new(id: &str) -> Option<Self> {
+        let is_valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if is_valid {
+            Some(Self(id.to_string()))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for GameId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Everything about a hosted game that is not part of the game itself.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameMetadata {
+    pub credentials: GameCredentials,
+    // only for turn-based games, where the server asks each seat for its decisions
+    #[serde(default)]
+    pub async_game: Option<AsyncGame>,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    NotFound(GameId),
+    Load(GameId, LoadError),
+    Io(io::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NotFound(game_id) => write!(f, "no game with id \"{}\"", game_id),
+            StoreError::Load(game_id, error) => write!(f, "game \"{}\": {}", game_id, error),
+            StoreError::Io(error) => write!(f, "storage error: {}", error),
+        }
+    }
+}
+
+impl From<io::Error> for StoreError {
+    fn from(error: io::Error) -> Self {
+        StoreError::Io(error)
+    }
+}
+
+/// Persistent storage for hosted games: the latest state of each game,
+/// plus the log of all events that happened in it.
+///
+/// Implementations must be safe to use from many threads at once.
+pub trait GameStore: Send + Sync {
+    // Replaces any previously saved state of the game.
+    fn save(&self, game_id: &GameId, game: &GameState) -> Result<(), StoreError>;
+
+    // Along with how the build that saved the game differs from this one, like
+    // `load_game_with_warnings()`; showing those is up to the caller.
+    fn load(&self, game_id: &GameId) -> Result<(GameState, Vec<StampMismatch>), StoreError>;
+
+    // Sorted by id.
+    fn list(&self) -> Result<Vec<GameId>, StoreError>;
+
+    // Removes the game's state, its metadata, and its event log.
+    fn delete(&self, game_id: &GameId) -> Result<(), StoreError>;
+
+    // The game must have been saved first.
+    fn save_metadata(&self, game_id: &GameId, metadata: &GameMetadata) -> Result<(), StoreError>;
+
+    fn load_metadata(&self, game_id: &GameId) -> Result<GameMetadata, StoreError>;
+
+    fn append_events(&self, game_id: &GameId, events: &[GameEvent]) -> Result<(), StoreError>;
+
+    fn load_events(&self, game_id: &GameId) -> Result<Vec<GameEvent>, StoreError>;
+}
+
+// Lets a store be shared, e.g. between a server and the tools inspecting its games.
+impl<T: GameStore + ?Sized> GameStore for Arc<T> {
+    fn save(&self, game_id: &GameId, game: &GameState) -> Result<(), StoreError> {
+        (**self).save(game_id, game)
+    }
+
+    fn load(&self, game_id: &GameId) -> Result<(GameState, Vec<StampMismatch>), StoreError> {
+        (**self).load(game_id)
+    }
+
+    fn list(&self) -> Result<Vec<GameId>, StoreError> {
+        (**self).list()
+    }
+
+    fn delete(&self, game_id: &GameId) -> Result<(), StoreError> {
+        (**self).delete(game_id)
+    }
+
+    fn save_metadata(&self, game_id: &GameId, metadata: &GameMetadata) -> Result<(), StoreError> {
+        (**self).save_metadata(game_id, metadata)
+    }
+
+    fn load_metadata(&self, game_id: &GameId) -> Result<GameMetadata, StoreError> {
+        (**self).load_metadata(game_id)
+    }
+
+    fn append_events(&self, game_id: &GameId, events: &[GameEvent]) -> Result<(), StoreError> {
+        (**self).append_events(game_id, events)
+    }
+
+    fn load_events(&self, game_id: &GameId) -> Result<Vec<GameEvent>, StoreError> {
+        (**self).load_events(game_id)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryGameStore {
+    games: Mutex<BTreeMap<GameId, StoredGame>>,
+}
+
+#[derive(Debug)]
+struct StoredGame {
+    game: GameState,
+    metadata: Option<GameMetadata>,
+    events: Vec<GameEvent>,
+}
+
+impl InMemoryGameStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl GameStore for InMemoryGameStore {
+    fn save(&self, game_id: &GameId, game: &GameState) -> Result<(), StoreError> {
+        let mut games = self.games.lock().unwrap();
+        match games.get_mut(game_id) {
+            Some(stored_game) => stored_game.game = game.clone(),
+            None => {
+                let stored_game = StoredGame {
+                    game: game.clone(),
+                    metadata: None,
+                    events: vec![],
+                };
+                games.insert(game_id.clone(), stored_game);
+            }
+        }
+        Ok(())
+    }
+
+    fn load(&self, game_id: &GameId) -> Result<(GameState, Vec<StampMismatch>), StoreError> {
+        let games = self.games.lock().unwrap();
+        games
+            .get(game_id)
+            .map(|stored_game| (stored_game.game.clone(), vec![]))
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))
+    }
+
+    fn list(&self) -> Result<Vec<GameId>, StoreError> {
+        Ok(self.games.lock().unwrap().keys().cloned().collect())
+    }
+
+    fn delete(&self, game_id: &GameId) -> Result<(), StoreError> {
+        let mut games = self.games.lock().unwrap();
+        games
+            .remove(game_id)
+            .map(|_| ())
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))
+    }
+
+    fn save_metadata(&self, game_id: &GameId, metadata: &GameMetadata) -> Result<(), StoreError> {
+        let mut games = self.games.lock().unwrap();
+        let stored_game = games
+            .get_mut(game_id)
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))?;
+        stored_game.metadata = Some(metadata.clone());
+        Ok(())
+    }
+
+    fn load_metadata(&self, game_id: &GameId) -> Result<GameMetadata, StoreError> {
+        let games = self.games.lock().unwrap();
+        games
+            .get(game_id)
+            .and_then(|stored_game| stored_game.metadata.clone())
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))
+    }
+
+    fn append_events(&self, game_id: &GameId, events: &[GameEvent]) -> Result<(), StoreError> {
+        let mut games = self.games.lock().unwrap();
+        let stored_game = games
+            .get_mut(game_id)
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))?;
+        stored_game.events.extend_from_slice(events);
+        Ok(())
+    }
+
+    fn load_events(&self, game_id: &GameId) -> Result<Vec<GameEvent>, StoreError> {
+        let games = self.games.lock().unwrap();
+        games
+            .get(game_id)
+            .map(|stored_game| stored_game.events.clone())
+            .ok_or_else(|| StoreError::NotFound(game_id.clone()))
+    }
+}
+
+/// Stores each game in a directory, as a save file `<id>.json`, a metadata file `<id>.meta.json`,
+/// and an event log `<id>.events.jsonl` with one JSON-encoded event per line.
+///
+/// Save files are replaced atomically, so a crash mid-save leaves the previous save intact.
+#[derive(Debug)]
+pub struct FileGameStore {
+    directory: PathBuf,
+    lock: Mutex<()>, // serializes all writes to the directory
+}
+
+const SAVE_EXTENSION: &str = ".json";
+const METADATA_EXTENSION: &str = ".meta.json";
+const EVENTS_EXTENSION: &str = ".events.jsonl";
+
+impl FileGameStore {
+    pub fn open(directory: impl Into<PathBuf>) -> Result<Self, StoreError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            lock: Mutex::new(()),
+        })
+    }
+
+    fn save_path(&self, game_id: &GameId) -> PathBuf {
+        self.directory
+            .join(format!("{}{}", game_id, SAVE_EXTENSION))
+    }
+
+    fn metadata_path(&self, game_id: &GameId) -> PathBuf {
+        self.directory
+            .join(format!("{}{}", game_id, METADATA_EXTENSION))
+    }
+
+    fn events_path(&self, game_id: &GameId) -> PathBuf {
+        self.directory
+            .join(format!("{}{}", game_id, EVENTS_EXTENSION))
+    }
+
+    fn ensure_exists(&self, game_id: &GameId) -> Result<(), StoreError> {
+        if self.save_path(game_id).is_file() {
+            Ok(())
+        } else {
+            Err(StoreError::NotFound(game_id.clone()))
+        }
+    }
+}
+
+impl GameStore for FileGameStore {
+    fn save(&self, game_id: &GameId, game: &GameState) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().unwrap();
+
+        let temporary_path = self.directory.join(format!("{}.tmp", game_id));
+        fs::write(&temporary_path, save_game(game))?;
+        fs::rename(&temporary_path, self.save_path(game_id))?;
+        Ok(())
+    }
+
+    fn load(&self, game_id: &GameId) -> Result<(GameState, Vec<StampMismatch>), StoreError> {
+        let text = match fs::read_to_string(self.save_path(game_id)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(StoreError::NotFound(game_id.clone()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        load_game_with_warnings(&text).map_err(|e| StoreError::Load(game_id.clone(), e))
+    }
+
+    fn list(&self) -> Result<Vec<GameId>, StoreError> {
+        let mut result = vec![];
+        for entry in fs::read_dir(&self.directory)? {
+            let file_name = entry?.file_name();
+            let game_id = file_name
+                .to_str()
+                .filter(|name| !name.ends_with(EVENTS_EXTENSION))
+                .and_then(|name| name.strip_suffix(SAVE_EXTENSION))
+                .and_then(GameId::new);
+            result.extend(game_id);
+        }
+        result.sort();
+        Ok(result)
+    }
+
+    fn delete(&self, game_id: &GameId) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        self.ensure_exists(game_id)?;
+
+        fs::remove_file(self.save_path(game_id))?;
+        for path in [self.metadata_path(game_id), self.events_path(game_id)] {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn save_metadata(&self, game_id: &GameId, metadata: &GameMetadata) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        self.ensure_exists(game_id)?;
+
+        let temporary_path = self.directory.join(format!("{}.meta.tmp", game_id));
+        fs::write(
+            &temporary_path,
+            serde_json::to_string_pretty(metadata).unwrap(),
+        )?;
+        fs::rename(&temporary_path, self.metadata_path(game_id))?;
+        Ok(())
+    }
+
+    fn load_metadata(&self, game_id: &GameId) -> Result<GameMetadata, StoreError> {
+        let text = match fs::read_to_string(self.metadata_path(game_id)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(StoreError::NotFound(game_id.clone()))
+            }
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&text)
+            .map_err(|e| StoreError::Load(game_id.clone(), LoadError::Malformed(e.to_string())))
+    }
+
+    fn append_events(&self, game_id: &GameId, events: &[GameEvent]) -> Result<(), StoreError> {
+        let _guard = self.lock.lock().unwrap();
+        self.ensure_exists(game_id)?;
+
+        let mut lines = String::new();
+        for event in events {
+            lines.push_str(&serde_json::to_string(event).unwrap());
+            lines.push('\n');
+        }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.events_path(game_id))?;
+        file.write_all(lines.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    fn load_events(&self, game_id: &GameId) -> Result<Vec<GameEvent>, StoreError> {
+        self.ensure_exists(game_id)?;
+
+        let text = match fs::read_to_string(self.events_path(game_id)) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+        text.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| {
+                    StoreError::Load(game_id.clone(), LoadError::Malformed(e.to_string()))
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::{
+        auth::GameCredentials,
+        event::GameEvent,
+        game::PlayerId,
+        position::import_position,
+        stamp::StampMismatch,
+        store::{FileGameStore, GameId, GameMetadata, GameStore, InMemoryGameStore, StoreError},
+    };
+
+    fn exercise_store(store: &dyn GameStore) {
+        let first = GameId::new("first-game").unwrap();
+        let second = GameId::new("second_game").unwrap();
+        let mut game = import_position("player p1\nplayer p2").unwrap();
+
+        assert!(matches!(store.load(&first), Err(StoreError::NotFound(_))));
+        assert!(matches!(
+            store.append_events(&first, &[]),
+            Err(StoreError::NotFound(_))
+        ));
+
+        store.save(&second, &game).unwrap();
+        store.save(&first, &game).unwrap();
+        assert_eq!(vec![first.clone(), second.clone()], store.list().unwrap());
+
+        game.generation = 2;
+        store.save(&first, &game).unwrap();
+        assert_eq!((game.clone(), vec![]), store.load(&first).unwrap());
+
+        let events = vec![
+            GameEvent::OxygenRaised(1),
+            GameEvent::CardsDrawn(PlayerId::from(1), vec![]),
+        ];
+        store.append_events(&first, &events[..1]).unwrap();
+        store.append_events(&first, &events[1..]).unwrap();
+        assert_eq!(events, store.load_events(&first).unwrap());
+        assert!(store.load_events(&second).unwrap().is_empty());
+
+        let metadata = GameMetadata {
+            credentials: GameCredentials::issue(game.players.keys().copied()),
+            async_game: None,
+        };
+        assert!(matches!(
+            store.load_metadata(&first),
+            Err(StoreError::NotFound(_))
+        ));
+        store.save_metadata(&first, &metadata).unwrap();
+        assert_eq!(metadata, store.load_metadata(&first).unwrap());
+
+        store.delete(&first).unwrap();
+        assert_eq!(vec![second], store.list().unwrap());
+        assert!(matches!(store.delete(&first), Err(StoreError::NotFound(_))));
+    }
+
+    fn temporary_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("terraforming_mars_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn game_ids_are_safe_file_names() {
+        assert!(GameId::new("game-1_b").is_some());
+        assert!(GameId::new("").is_none());
+        assert!(GameId::new("../etc/passwd").is_none());
+        assert!(GameId::new("a b").is_none());
+    }
+
+    #[test]
+    fn in_memory_store_keeps_games_and_events() {
+        exercise_store(&InMemoryGameStore::new());
+    }
+
+    #[test]
+    fn file_store_keeps_games_and_events() {
+        let directory = temporary_directory("file_store");
+        exercise_store(&FileGameStore::open(&directory).unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn file_store_recovers_games_after_reopening() {
+        let directory = temporary_directory("file_store_reopen");
+        let game_id = GameId::new("persistent").unwrap();
+        let game = import_position("generation 4\nplayer p1").unwrap();
+        {
+            let store = FileGameStore::open(&directory).unwrap();
+            store.save(&game_id, &game).unwrap();
+            store
+                .append_events(&game_id, &[GameEvent::OxygenRaised(3)])
+                .unwrap();
+        }
+
+        let reopened = FileGameStore::open(&directory).unwrap();
+        assert_eq!(vec![game_id.clone()], reopened.list().unwrap());
+        assert_eq!((game, vec![]), reopened.load(&game_id).unwrap());
+        assert_eq!(
+            vec![GameEvent::OxygenRaised(3)],
+            reopened.load_events(&game_id).unwrap()
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn file_store_returns_warnings_about_older_saves() {
+        let directory = temporary_directory("file_store_warnings");
+        let game_id = GameId::new("older").unwrap();
+        let game = import_position("player p1").unwrap();
+        let store = FileGameStore::open(&directory).unwrap();
+        store.save(&game_id, &game).unwrap();
+
+        let path = directory.join("older.json");
+        let mut saved: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        saved["engine_version"] = "0.0.1".into();
+        fs::write(&path, saved.to_string()).unwrap();
+
+        let (loaded, warnings) = store.load(&game_id).unwrap();
+        assert_eq!(game, loaded);
+        assert!(matches!(
+            warnings.as_slice(),
+            [StampMismatch::EngineVersion { .. }]
+        ));
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
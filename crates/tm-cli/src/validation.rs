@@ -0,0 +1,286 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    decision::DecisionError,
+    game::{GameState, IllegalOperation, PlayerId, PlayerTurn},
+    store::GameId,
+};
+
+/// Why a submitted turn was not applied. Sent back to the client that submitted it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rejection {
+    RateLimited {
+        retry_after_ms: u64,
+    },
+    NotInGame(PlayerId),
+    // Outside of the player's turn in the action phase.
+    NotYourTurn(PlayerId),
+    Illegal(IllegalOperation),
+    // An answer to a decision request in a turn-based game that does not fit the request.
+    InvalidDecision(DecisionError),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::RateLimited { retry_after_ms } => {
+                write!(f, "too many actions, retry in {}ms", retry_after_ms)
+            }
+            Rejection::NotInGame(player_id) => write!(f, "{} is not in this game", player_id),
+            Rejection::NotYourTurn(player_id) => write!(f, "it is not {}'s turn", player_id),
+            Rejection::Illegal(reason) => write!(f, "illegal turn: {:?}", reason),
+            Rejection::InvalidDecision(reason) => write!(f, "invalid decision: {:?}", reason),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    // at most this many submissions by one player in one game, within any window
+    pub max_actions: usize,
+    pub window: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            max_actions: 30,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Checks every submitted turn before it reaches the game, so that a buggy or malicious client
+/// can neither corrupt a game nor crash the server with illegal moves, nor flood it with
+/// submissions. Clients only ever submit turns: the operations they stand for are worked out
+/// by the game, with their costs, so no client can e.g. grant itself resources.
+///
+/// Rejected submissions count against the rate limit too.
+#[derive(Debug, Default)]
+pub struct ActionValidator {
+    rate_limit: RateLimit,
+    recent_submissions: Mutex<HashMap<(GameId, PlayerId), VecDeque<Instant>>>,
+}
+
+impl ActionValidator {
+    pub fn new(rate_limit: RateLimit) -> Self {
+        Self {
+            rate_limit,
+            recent_submissions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn validate(
+        &self,
+        game_id: &GameId,
+        game: &GameState,
+        player_id: PlayerId,
+        turn: &PlayerTurn,
+    ) -> Result<(), Rejection> {
+        self.validate_at(game_id, game, player_id, turn, Instant::now())
+    }
+
+    // For submissions that are checked elsewhere, e.g. answers to decision requests.
+    pub fn check_rate_limit(&self, game_id: &GameId, player_id: PlayerId) -> Result<(), Rejection> {
+        self.record_submission(game_id, player_id, Instant::now())
+    }
+
+    fn validate_at(
+        &self,
+        game_id: &GameId,
+        game: &GameState,
+        player_id: PlayerId,
+        turn: &PlayerTurn,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        if !game.players.contains_key(&player_id) {
+            return Err(Rejection::NotInGame(player_id));
+        }
+        self.record_submission(game_id, player_id, now)?;
+
+        if game.current_player() != Some(player_id) {
+            return Err(Rejection::NotYourTurn(player_id));
+        }
+
+        let mut scratch_game = game.clone();
+        scratch_game
+            .take_turn(player_id, turn)
+            .map(|_| ())
+            .map_err(Rejection::Illegal)
+    }
+
+    fn record_submission(
+        &self,
+        game_id: &GameId,
+        player_id: PlayerId,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        let mut recent_submissions = self.recent_submissions.lock().unwrap();
+        // Forget players whose submissions have all left the window, e.g. of finished games,
+        // so that a long-running server doesn't keep them all.
+        let window = self.rate_limit.window;
+        recent_submissions.retain(|_, submissions| {
+            submissions
+                .back()
+                .is_some_and(|latest| now.duration_since(*latest) < window)
+        });
+
+        let submissions = recent_submissions
+            .entry((game_id.clone(), player_id))
+            .or_default();
+
+        while let Some(oldest) = submissions.front() {
+            if now.duration_since(*oldest) >= self.rate_limit.window {
+                submissions.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if submissions.len() >= self.rate_limit.max_actions {
+            let oldest = *submissions.front().unwrap();
+            let retry_after = self.rate_limit.window - now.duration_since(oldest);
+            return Err(Rejection::RateLimited {
+                retry_after_ms: retry_after.as_millis() as u64,
+            });
+        }
+
+        submissions.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{
+        board::{Coordinates, TileLocation},
+        card::BASE_GAME_CARDS_BY_NAME,
+        game::{IllegalOperation, PlayerId, PlayerTurn, StandardProject, TurnAction},
+        position::import_position,
+        resource::Resource,
+        store::GameId,
+        validation::{ActionValidator, RateLimit, Rejection},
+    };
+
+    #[test]
+    fn illegal_turns_are_rejected_with_reasons() {
+        let game = import_position(
+            "ocean 5,-1\nplayer p1\nresources 3 0 0 8 0 0\nhand Solar Power\nplayer p2",
+        )
+        .unwrap();
+        let game_id = GameId::new("game").unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let validator = ActionValidator::default();
+        let validate = |player_id, action| {
+            let turn = PlayerTurn::Play(action, None);
+            validator.validate(&game_id, &game, player_id, &turn)
+        };
+
+        let predators = BASE_GAME_CARDS_BY_NAME["Predators"].clone();
+
+        assert_eq!(
+            Ok(()),
+            validate(p1, TurnAction::ConvertPlants(Coordinates::new(3, 0)))
+        );
+        assert_eq!(
+            Err(Rejection::Illegal(IllegalOperation::SpaceOccupied(
+                TileLocation::OnMars(Coordinates::new(5, -1))
+            ))),
+            validate(p1, TurnAction::ConvertPlants(Coordinates::new(5, -1)))
+        );
+        assert_eq!(
+            Err(Rejection::Illegal(IllegalOperation::InsufficientResource(
+                Resource::Megacredits
+            ))),
+            validate(
+                p1,
+                TurnAction::PlayStandardProject(StandardProject::PowerPlant)
+            )
+        );
+        assert_eq!(
+            Err(Rejection::Illegal(IllegalOperation::CardNotInHand(
+                "Predators".to_string()
+            ))),
+            validate(
+                p1,
+                TurnAction::PlayStandardProject(StandardProject::SellPatents(vec![predators]))
+            )
+        );
+        assert_eq!(
+            Err(Rejection::NotYourTurn(p2)),
+            validator.validate(&game_id, &game, p2, &PlayerTurn::Pass)
+        );
+        assert_eq!(
+            Err(Rejection::NotInGame(PlayerId::from(3))),
+            validator.validate(&game_id, &game, PlayerId::from(3), &PlayerTurn::Pass)
+        );
+    }
+
+    #[test]
+    fn submissions_are_rate_limited_per_player() {
+        let game = import_position("player p1\nplayer p2").unwrap();
+        let game_id = GameId::new("game").unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let validator = ActionValidator::new(RateLimit {
+            max_actions: 2,
+            window: Duration::from_secs(10),
+        });
+
+        let start = Instant::now();
+        let validate_at = |player_id, seconds| {
+            validator.validate_at(
+                &game_id,
+                &game,
+                player_id,
+                &PlayerTurn::Pass,
+                start + Duration::from_secs(seconds),
+            )
+        };
+
+        assert_eq!(Ok(()), validate_at(p1, 0));
+        assert_eq!(Ok(()), validate_at(p1, 1));
+        assert_eq!(
+            Err(Rejection::RateLimited {
+                retry_after_ms: 8000
+            }),
+            validate_at(p1, 2)
+        );
+        // other players are unaffected, even if it's not their turn yet
+        assert_eq!(Err(Rejection::NotYourTurn(p2)), validate_at(p2, 2));
+        assert_eq!(Ok(()), validate_at(p1, 10)); // the first submission left the window
+    }
+
+    #[test]
+    fn players_are_forgotten_once_their_submissions_leave_the_window() {
+        let game = import_position("player p1\nplayer p2").unwrap();
+        let first_game = GameId::new("first").unwrap();
+        let second_game = GameId::new("second").unwrap();
+        let validator = ActionValidator::default();
+        let start = Instant::now();
+
+        let p1 = PlayerId::from(1);
+        let turn = PlayerTurn::Pass;
+        validator.validate_at(&first_game, &game, p1, &turn, start).unwrap();
+        validator.validate_at(&second_game, &game, p1, &turn, start).unwrap();
+        assert_eq!(2, validator.recent_submissions.lock().unwrap().len());
+
+        let later = start + RateLimit::default().window;
+        validator.validate_at(&second_game, &game, p1, &turn, later).unwrap();
+        let recent_submissions = validator.recent_submissions.lock().unwrap();
+        assert_eq!(
+            vec![&(second_game, p1)],
+            recent_submissions.keys().collect::<Vec<_>>()
+        );
+    }
+}
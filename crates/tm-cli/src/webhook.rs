@@ -0,0 +1,389 @@
+//! Notify an external service, e.g. a Discord relay bot, about the progress of a game,
+//! so that players of asynchronous games know when something happened.
+//!
+//! Notifications are POSTed as JSON over HTTP or HTTPS, from a thread of their own, so that
+//! a slow endpoint never holds up the game. The certificates of HTTPS endpoints are checked
+//! against the Mozilla root certificates.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    game::{GameState, PlayerId, PlayerTurn, StandardProject, TurnAction},
+    scoring::score_all_players,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Notification {
+    TurnTaken {
+        player_id: PlayerId,
+        generation: usize,
+        description: String,
+    },
+    GenerationEnded {
+        generation: usize,
+    },
+    GameEnded {
+        generation: usize,
+        scores: BTreeMap<PlayerId, isize>,
+    },
+}
+
+impl Notification {
+    pub fn game_ended(game: &GameState) -> Self {
+        let scores = score_all_players(game)
+            .into_iter()
+            .map(|(player_id, breakdown)| (player_id, breakdown.total()))
+            .collect();
+        Notification::GameEnded {
+            generation: game.generation,
+            scores,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    Http,
+    Https,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WebhookUrl {
+    pub scheme: Scheme,
+    pub host: String,
+    pub port: u16,
+    pub path: String,
+}
+
+impl WebhookUrl {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let parsed = Url::parse(url).map_err(|e| format!("\"{}\" is not a valid URL: {}", url, e))?;
+        let scheme = match parsed.scheme() {
+            "http" => Scheme::Http,
+            "https" => Scheme::Https,
+            _ => return Err(format!("\"{}\" is not an http:// or https:// URL", url)),
+        };
+        // IPv6 hosts keep their brackets, so that they can be joined with the port again.
+        let host = match parsed.host_str() {
+            Some(host) if !host.is_empty() => host.to_string(),
+            _ => return Err(format!("\"{}\" has no host", url)),
+        };
+        let port = parsed.port_or_known_default().unwrap();
+        let path = match parsed.query() {
+            Some(query) => format!("{}?{}", parsed.path(), query),
+            None => parsed.path().to_string(),
+        };
+
+        Ok(Self {
+            scheme,
+            host,
+            port,
+            path,
+        })
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self.scheme {
+            Scheme::Http => "http",
+            Scheme::Https => "https",
+        };
+        write!(f, "{}://{}:{}{}", scheme, self.host, self.port, self.path)
+    }
+}
+
+/// Sends a `Notification` for each turn taken, and for the end of the generation or the game
+/// that a turn brings about.
+///
+/// Notifications are queued and sent in order from a background thread. Delivery failures
+/// never interrupt the game: they are counted and otherwise ignored.
+pub struct WebhookNotifier {
+    queue: Option<Sender<String>>,
+    sender: Option<JoinHandle<()>>,
+    failed_deliveries: Arc<AtomicUsize>,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: WebhookUrl) -> Self {
+        let (queue, bodies) = mpsc::channel::<String>();
+        let failed_deliveries = Arc::new(AtomicUsize::new(0));
+        let failures = failed_deliveries.clone();
+        let sender = thread::spawn(move || {
+            for body in bodies {
+                if post_json(&url, &body).is_err() {
+                    failures.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+        Self {
+            queue: Some(queue),
+            sender: Some(sender),
+            failed_deliveries,
+        }
+    }
+
+    pub fn notify(&self, notification: &Notification) {
+        let body = serde_json::to_string(notification).unwrap();
+        if let Some(queue) = &self.queue {
+            // The sending thread only stops once the queue is closed.
+            queue.send(body).unwrap();
+        }
+    }
+
+    /// Call after the player's turn is over. `generation` is the one the turn was taken in:
+    /// if the turn ended it, that is announced too, and so is the end of the game.
+    pub fn notify_turn(
+        &self,
+        generation: usize,
+        game: &GameState,
+        player_id: PlayerId,
+        turn: &PlayerTurn,
+    ) {
+        self.notify(&Notification::TurnTaken {
+            player_id,
+            generation,
+            description: describe_turn(turn),
+        });
+        if game.generation > generation {
+            self.notify(&Notification::GenerationEnded { generation });
+        }
+        if game.is_over() {
+            self.notify_game_end(game);
+        }
+    }
+
+    pub fn notify_game_end(&self, game: &GameState) {
+        self.notify(&Notification::game_ended(game));
+    }
+
+    pub fn failed_deliveries(&self) -> usize {
+        self.failed_deliveries.load(Ordering::Relaxed)
+    }
+
+    /// Wait until every queued notification has been sent, or has failed to be.
+    pub fn finish(mut self) -> usize {
+        self.close();
+        self.failed_deliveries()
+    }
+
+    fn close(&mut self) {
+        self.queue = None;
+        if let Some(sender) = self.sender.take() {
+            let _ = sender.join();
+        }
+    }
+}
+
+impl Drop for WebhookNotifier {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+fn describe_turn(turn: &PlayerTurn) -> String {
+    match turn {
+        PlayerTurn::Pass => "passed".to_string(),
+        PlayerTurn::Play(action, None) => describe_action(action),
+        PlayerTurn::Play(action, Some(second_action)) => format!(
+            "{} and {}",
+            describe_action(action),
+            describe_action(second_action)
+        ),
+    }
+}
+
+fn describe_action(action: &TurnAction) -> String {
+    match action {
+        TurnAction::PlayStandardProject(project) => match project {
+            StandardProject::SellPatents(cards) => format!("sold {} patents", cards.len()),
+            StandardProject::PowerPlant => "built a power plant".to_string(),
+            StandardProject::Asteroid(_) => "launched an asteroid".to_string(),
+            StandardProject::Aquifer(coordinates) => format!("placed an ocean at {}", coordinates),
+            StandardProject::Greenery(coordinates) => {
+                format!("planted a greenery at {}", coordinates)
+            }
+            StandardProject::City(coordinates) => format!("built a city at {}", coordinates),
+        },
+        TurnAction::PlayCard(card, _) => format!("played {}", card.name),
+        TurnAction::PerformAction(_) => "used a card action".to_string(),
+        TurnAction::ClaimMilestone => "claimed a milestone".to_string(),
+        TurnAction::FundAward(award) => format!("funded the {:?} award", award),
+        TurnAction::ConvertPlants(coordinates) => {
+            format!("turned plants into a greenery at {}", coordinates)
+        }
+        TurnAction::ConvertHeat(_) => "turned heat into a temperature raise".to_string(),
+        TurnAction::PlaceDelegate(party) => format!("placed a delegate in {:?}", party),
+    }
+}
+
+// Responses other than 2xx, after following any redirects, count as failures.
+fn post_json(url: &WebhookUrl, body: &str) -> Result<(), Box<ureq::Error>> {
+    ureq::post(&url.to_string())
+        .timeout(TIMEOUT)
+        .set("Content-Type", "application/json")
+        .send_string(body)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use crate::{
+        game::{PlayerId, PlayerTurn},
+        position::import_position,
+        webhook::{Notification, Scheme, WebhookNotifier, WebhookUrl},
+    };
+
+    // Answers the given number of requests with 204s, and returns what they were.
+    fn serve(requests: usize) -> (u16, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            (0..requests)
+                .map(|_| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request = String::new();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            }
+                        }
+                        request.push_str(&line);
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    request.push_str(&String::from_utf8(body).unwrap());
+
+                    stream
+                        .write_all(
+                            b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\
+                              Connection: close\r\n\r\n",
+                        )
+                        .unwrap();
+                    request
+                })
+                .collect()
+        });
+        (port, server)
+    }
+
+    #[test]
+    fn urls_are_parsed() {
+        assert_eq!(
+            Ok(WebhookUrl {
+                scheme: Scheme::Http,
+                host: "localhost".to_string(),
+                port: 8080,
+                path: "/hooks/mars".to_string(),
+            }),
+            WebhookUrl::parse("http://localhost:8080/hooks/mars")
+        );
+        assert_eq!(
+            Ok(WebhookUrl {
+                scheme: Scheme::Http,
+                host: "example.com".to_string(),
+                port: 80,
+                path: "/".to_string(),
+            }),
+            WebhookUrl::parse("http://example.com")
+        );
+        let https = WebhookUrl::parse("https://example.com/hooks?token=abc").unwrap();
+        assert_eq!((Scheme::Https, 443), (https.scheme, https.port));
+        assert_eq!("https://example.com:443/hooks?token=abc", https.to_string());
+        let ipv6 = WebhookUrl::parse("http://[::1]/hook").unwrap();
+        assert_eq!(("[::1]", 80), (ipv6.host.as_str(), ipv6.port));
+        assert_eq!("http://[::1]:80/hook", ipv6.to_string());
+        assert!(WebhookUrl::parse("ftp://example.com/").is_err());
+        assert!(WebhookUrl::parse("http://example.com:port/").is_err());
+    }
+
+    #[test]
+    fn notifications_are_posted_as_json() {
+        let (port, server) = serve(1);
+        let url = WebhookUrl::parse(&format!("http://127.0.0.1:{}/hook", port)).unwrap();
+        let notifier = WebhookNotifier::new(url);
+        notifier.notify(&Notification::TurnTaken {
+            player_id: PlayerId::from(2),
+            generation: 4,
+            description: "played Predators".to_string(),
+        });
+        assert_eq!(0, notifier.finish());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with(
+            r#"{"event":"turn_taken","player_id":2,"generation":4,"description":"played Predators"}"#
+        ));
+    }
+
+    #[test]
+    fn turns_that_end_the_generation_announce_it() {
+        let mut game = import_position("player p1\nplayer p2").unwrap();
+        let (port, server) = serve(3);
+        let url = WebhookUrl::parse(&format!("http://127.0.0.1:{}/", port)).unwrap();
+        let notifier = WebhookNotifier::new(url);
+
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        game.take_turn(p1, &PlayerTurn::Pass).unwrap();
+        notifier.notify_turn(1, &game, p1, &PlayerTurn::Pass);
+        // The last pass ends the action phase, and production starts the next generation.
+        game.take_turn(p2, &PlayerTurn::Pass).unwrap();
+        game.advance_phase();
+        notifier.notify_turn(1, &game, p2, &PlayerTurn::Pass);
+        assert_eq!(0, notifier.finish());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].ends_with(
+            r#"{"event":"turn_taken","player_id":1,"generation":1,"description":"passed"}"#
+        ));
+        assert!(requests[1].ends_with(
+            r#"{"event":"turn_taken","player_id":2,"generation":1,"description":"passed"}"#
+        ));
+        assert!(requests[2].ends_with(r#"{"event":"generation_ended","generation":1}"#));
+    }
+
+    #[test]
+    fn failed_deliveries_are_counted() {
+        // Bind and immediately drop a listener to find a port that refuses connections.
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = WebhookUrl::parse(&format!("http://127.0.0.1:{}/", port)).unwrap();
+
+        let notifier = WebhookNotifier::new(url);
+        notifier.notify(&Notification::GenerationEnded { generation: 1 });
+        assert_eq!(1, notifier.finish());
+    }
+}
@@ -0,0 +1,21 @@
+# Plants become greeneries and heat raises the temperature, eight at a time.
+# The raise to 0 C places an ocean, like any other.
+position
+  temperature -2
+  player p1
+  resources 0 0 0 15 0 17
+actions
+  g1: p1 convert plants place 3,0
+  ! g1: p1 convert heat                # the ocean at 0 C needs a space
+  g1: p1 convert heat place 5,-1
+  g1: p1 convert heat
+  ! g1: p1 convert heat                # only 1 heat left
+  ! g1: p1 convert plants place 3,-1   # only 7 plants left
+expect
+  oxygen 1
+  temperature 2
+  greenery 3,0 p1
+  ocean 5,-1
+  player p1
+  tr 24
+  resources 0 2 0 7 0 1
@@ -0,0 +1,31 @@
+# A tile with nowhere to go is skipped, and the rest of what called for it still happens.
+# Every ocean space is taken, but only eight oceans are on Mars.
+position
+  temperature -2
+  ocean 5,-1
+  ocean 3,-3
+  ocean 7,-3
+  ocean 4,-4
+  ocean 8,-4
+  ocean 5,-5
+  ocean 8,-5
+  ocean 5,-6
+  greenery 6,-7 p2
+  greenery 8,-7 p2
+  greenery 4,-8 p2
+  greenery 7,-8 p2
+  player p1
+  resources 23 0 0 0 0 8
+  hand Towing A Comet
+  player p2
+actions
+  ! g1: p1 convert heat place 6,-7     # the space is taken
+  g1: p1 convert heat                  # the raise to 0 C comes without an ocean
+  g1: p1 play "Towing A Comet" pay 23M
+expect
+  oxygen 1
+  temperature 0
+  player p1
+  tr 22
+  resources 0 0 0 2 0 0
+  played Towing A Comet
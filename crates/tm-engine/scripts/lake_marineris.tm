@@ -0,0 +1,18 @@
+# Lake Marineris places two oceans, each raising the terraform rating
+# and paying out the bonus of the space it is placed on.
+position
+  temperature 0
+  player p1
+  resources 20 0 0 0 0 0
+  hand Lake Marineris
+actions
+  g1: p1 play "Lake Marineris" pay 18M place 5,-1 place 8,-4
+  ! g1: p1 project aquifer place 5,-1  # the space is taken
+expect
+  ocean 5,-1
+  ocean 8,-4
+  player p1
+  tr 22
+  resources 2 2 0 0 0 0
+  played Lake Marineris
+  hand
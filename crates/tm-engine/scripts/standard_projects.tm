@@ -0,0 +1,20 @@
+# Standard projects are paid for in megacredits, and cities go next to no other city.
+position
+  player p1
+  resources 50 0 0 0 0 0
+actions
+  g1: p1 project power-plant
+  g1: p1 project city place 3,-1
+  ! g1: p1 project city place 3,0      # next to the first city
+  ! g1: p1 project greenery place 3,0  # not enough megacredits left
+  g1: p1 project sell
+  g2: p1 project greenery place 3,0    # after the production phase pays out
+expect
+  generation 2
+  oxygen 1
+  city 3,-1 p1
+  greenery 3,0 p1
+  player p1
+  tr 21
+  resources 12 0 0 0 1 0
+  production 1 0 0 0 1 0
@@ -0,0 +1,399 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::TileStatus,
+    card::{CardKind, CardTag, SpecialTile},
+    event::GameEvent,
+    game::{GameState, PlayerId, PlayerState},
+    resource::Resource,
+    rules::PlayerCountRules,
+};
+
+/// The awards of the Tharsis map.
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Award {
+    Landlord,   // most tiles in play
+    Banker,     // highest megacredit production
+    Scientist,  // most science tags in play
+    Thermalist, // most heat resources
+    Miner,      // most steel and titanium resources
+}
+
+impl Award {
+    pub const COUNT: usize = 5;
+
+    pub const ALL: [Award; Award::COUNT] = [
+        Award::Landlord,
+        Award::Banker,
+        Award::Scientist,
+        Award::Thermalist,
+        Award::Miner,
+    ];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Victory points for first and second place in a funded award.
+pub const FIRST_PLACE_POINTS: isize = 5;
+pub const SECOND_PLACE_POINTS: isize = 2;
+
+/// What funding the first, second, and third award costs. No more than three may be funded.
+pub const AWARD_FUNDING_COSTS: [usize; 3] = [8, 14, 20];
+
+/// Every player's current value for every award, kept up to date as the game's events occur,
+/// so that reading a standing does not require recomputing it from the whole game.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AwardStandings {
+    values: BTreeMap<PlayerId, [isize; Award::COUNT]>,
+}
+
+impl AwardStandings {
+    pub fn compute(game: &GameState) -> Self {
+        let mut standings = Self::default();
+        for player in game.players.values() {
+            standings.refresh_resources(player);
+
+            let science_tags = player
+                .played_cards
+                .iter()
+                .filter(|card| card.kind != CardKind::Event)
+                .map(|card| card.tags.count(CardTag::Science))
+                .sum::<usize>();
+            standings.values_mut(player.player_id)[Award::Scientist.index()] =
+                science_tags as isize;
+        }
+
+        let board = &game.board;
+        let tile_owners = board
+            .cities
+            .values()
+            .map(|(_, owner)| owner)
+            .chain(board.greeneries.values())
+            .chain(
+                board
+                    .special_tiles
+                    .values()
+                    .filter(|(tile, _)| counts_as_tile(*tile))
+                    .map(|(_, owner)| owner),
+            );
+        for owner in tile_owners.filter(|owner| !owner.is_neutral()) {
+            standings.values_mut(*owner)[Award::Landlord.index()] += 1;
+        }
+
+        standings
+    }
+
+    pub fn value(&self, player_id: PlayerId, award: Award) -> isize {
+        self.values
+            .get(&player_id)
+            .map_or(0, |values| values[award.index()])
+    }
+
+    // All players tied for first place, in player id order.
+    pub fn leaders(&self, award: Award) -> Vec<PlayerId> {
+        let best = self
+            .values
+            .values()
+            .map(|values| values[award.index()])
+            .max();
+        self.values
+            .iter()
+            .filter(|(_, values)| Some(values[award.index()]) == best)
+            .map(|(player_id, _)| *player_id)
+            .collect()
+    }
+
+    /// The victory points each player would score for the award if the game ended now,
+    /// for the players who score any.
+    ///
+    /// Players tied for first place all score first place, and then no second place is
+    /// awarded. Otherwise, players tied for second place all score second place, if the
+    /// `PlayerCountRules` score second place at all.
+    pub fn award_points(&self, award: Award) -> BTreeMap<PlayerId, isize> {
+        let leaders = self.leaders(award);
+        let mut points: BTreeMap<PlayerId, isize> = leaders
+            .iter()
+            .map(|player_id| (*player_id, FIRST_PLACE_POINTS))
+            .collect();
+        if leaders.len() > 1 || !PlayerCountRules::new(self.values.len()).scores_second_place() {
+            return points;
+        }
+
+        let runner_up = self
+            .values
+            .iter()
+            .filter(|(player_id, _)| !leaders.contains(player_id))
+            .map(|(_, values)| values[award.index()])
+            .max();
+        for (player_id, values) in &self.values {
+            if !leaders.contains(player_id) && Some(values[award.index()]) == runner_up {
+                points.insert(*player_id, SECOND_PLACE_POINTS);
+            }
+        }
+        points
+    }
+
+    /// Every player's victory points from the given funded awards, in player id order.
+    pub fn points(&self, funded_awards: &[Award]) -> BTreeMap<PlayerId, isize> {
+        let mut points: BTreeMap<PlayerId, isize> =
+            self.values.keys().map(|player_id| (*player_id, 0)).collect();
+        for award in funded_awards {
+            for (player_id, award_points) in self.award_points(*award) {
+                *points.entry(player_id).or_default() += award_points;
+            }
+        }
+        points
+    }
+
+    pub(crate) fn record(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::ResourcesChanged(player_id, changes) => {
+                let change = |resource| changes.get(&resource).copied().unwrap_or_default();
+                let values = self.values_mut(*player_id);
+                values[Award::Thermalist.index()] += change(Resource::Heat);
+                values[Award::Miner.index()] +=
+                    change(Resource::Steel) + change(Resource::Titanium);
+            }
+            GameEvent::ProductionChanged(player_id, changes) => {
+                let change = changes
+                    .get(&Resource::Megacredits)
+                    .copied()
+                    .unwrap_or_default();
+                self.values_mut(*player_id)[Award::Banker.index()] += change;
+            }
+            GameEvent::CardPlayed(player_id, card) if card.kind != CardKind::Event => {
+                self.values_mut(*player_id)[Award::Scientist.index()] +=
+                    card.tags.count(CardTag::Science) as isize;
+            }
+            GameEvent::CityPlaced(player_id, _, _) | GameEvent::GreeneryPlaced(player_id, _) => {
+                self.values_mut(*player_id)[Award::Landlord.index()] += 1;
+            }
+            GameEvent::SpecialTilePlaced(player_id, tile, _) if counts_as_tile(*tile) => {
+                self.values_mut(*player_id)[Award::Landlord.index()] += 1;
+            }
+            GameEvent::TileRemoved(TileStatus::City(_, _, player_id))
+            | GameEvent::TileRemoved(TileStatus::Greenery(_, player_id)) => {
+                self.values_mut(*player_id)[Award::Landlord.index()] -= 1;
+            }
+            GameEvent::TileRemoved(TileStatus::SpecialTile(_, tile, player_id))
+                if counts_as_tile(*tile) =>
+            {
+                self.values_mut(*player_id)[Award::Landlord.index()] -= 1;
+            }
+            _ => {}
+        }
+    }
+
+    // For changes made outside of events, e.g. production at the end of a generation.
+    pub(crate) fn refresh_resources(&mut self, player: &PlayerState) {
+        let values = self.values_mut(player.player_id);
+        values[Award::Banker.index()] = player.production[Resource::Megacredits];
+        values[Award::Thermalist.index()] = player.resources[Resource::Heat] as isize;
+        values[Award::Miner.index()] =
+            (player.resources[Resource::Steel] + player.resources[Resource::Titanium]) as isize;
+    }
+
+    fn values_mut(&mut self, player_id: PlayerId) -> &mut [isize; Award::COUNT] {
+        self.values.entry(player_id).or_default()
+    }
+}
+
+// Land claims are markers rather than tiles.
+fn counts_as_tile(tile: SpecialTile) -> bool {
+    tile != SpecialTile::LandClaim
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        award::{Award, AwardStandings, FIRST_PLACE_POINTS, SECOND_PLACE_POINTS},
+        board::Coordinates,
+        card::CORPORATE_GAME_CARDS_BY_NAME,
+        event::GameEvent,
+        game::{GameOperation, IllegalOperation, PlayerId},
+        position::import_position,
+        resource::Resource,
+        scoring::score_all_players,
+    };
+
+    #[test]
+    fn standings_are_kept_up_to_date_as_the_game_progresses() {
+        let mut game = import_position(
+            "greenery 4,-3 p2\n\
+             player p1\n\
+             resources 0 2 1 0 3 4\n\
+             production 2 0 0 0 0 1\n\
+             hand Research\n\
+             player p2\n\
+             played Physics Complex",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        assert_eq!(AwardStandings::compute(&game), game.award_standings);
+        assert_eq!(vec![p2], game.award_standings.leaders(Award::Landlord));
+        assert_eq!(vec![p2], game.award_standings.leaders(Award::Scientist));
+
+        let research = CORPORATE_GAME_CARDS_BY_NAME["Research"].clone();
+        for operation in [
+            GameOperation::PutCardIntoPlay(p1, research),
+            GameOperation::PlaceGreenery(p1, Coordinates::new(5, -3)),
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Steel => -2 }),
+            GameOperation::ChangeProduction(p2, btreemap! { Resource::Megacredits => 3 }),
+        ] {
+            game.execute_operation(operation);
+        }
+        assert_eq!(AwardStandings::compute(&game), game.award_standings);
+        assert_eq!(vec![p1], game.award_standings.leaders(Award::Scientist));
+        assert_eq!(vec![p1, p2], game.award_standings.leaders(Award::Landlord));
+        assert_eq!(3, game.award_standings.value(p2, Award::Banker));
+
+        // Production: energy becomes heat, and production is paid out.
+        game.advance_generation();
+        assert_eq!(AwardStandings::compute(&game), game.award_standings);
+        assert_eq!(8, game.award_standings.value(p1, Award::Thermalist));
+        assert_eq!(1, game.award_standings.value(p1, Award::Miner));
+    }
+
+    #[test]
+    fn tied_players_share_their_place() {
+        let (p1, p2, p3, p4) = (
+            PlayerId::from(1),
+            PlayerId::from(2),
+            PlayerId::from(3),
+            PlayerId::from(4),
+        );
+        let game = import_position(
+            "player p1\n\
+             resources 0 0 0 0 0 6\n\
+             production 3 0 0 0 0 0\n\
+             player p2\n\
+             resources 0 0 0 0 0 6\n\
+             production 1 0 0 0 0 0\n\
+             player p3\n\
+             resources 0 0 0 0 0 2\n\
+             production 1 0 0 0 0 0\n\
+             player p4\n\
+             resources 0 0 0 0 0 1",
+        )
+        .unwrap();
+        let standings = &game.award_standings;
+
+        // Tied for first, both score it, and nobody scores second place.
+        assert_eq!(
+            btreemap! { p1 => FIRST_PLACE_POINTS, p2 => FIRST_PLACE_POINTS },
+            standings.award_points(Award::Thermalist)
+        );
+        // A clear first place, and a tie for second.
+        assert_eq!(
+            btreemap! {
+                p1 => FIRST_PLACE_POINTS,
+                p2 => SECOND_PLACE_POINTS,
+                p3 => SECOND_PLACE_POINTS,
+            },
+            standings.award_points(Award::Banker)
+        );
+        // Nobody has any, so everyone is tied for first.
+        assert_eq!(4, standings.award_points(Award::Miner).len());
+
+        assert_eq!(
+            btreemap! { p1 => 10, p2 => 7, p3 => 2, p4 => 0 },
+            standings.points(&[Award::Thermalist, Award::Banker])
+        );
+    }
+
+    #[test]
+    fn second_place_is_not_awarded_to_two_players() {
+        let game = import_position(
+            "player p1\n\
+             production 3 0 0 0 0 0\n\
+             player p2\n\
+             production 1 0 0 0 0 0",
+        )
+        .unwrap();
+        assert_eq!(
+            btreemap! { PlayerId::from(1) => FIRST_PLACE_POINTS },
+            game.award_standings.award_points(Award::Banker)
+        );
+    }
+
+    #[test]
+    fn awards_cost_more_the_more_have_been_funded() {
+        let mut game = import_position(
+            "player p1\n\
+             resources 50 0 0 0 0 0\n\
+             player p2\n\
+             resources 10 0 0 0 0 0",
+        )
+        .unwrap();
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+
+        let fund = |player_id, award| GameOperation::FundAward(player_id, award);
+        assert_eq!(Some(8), game.award_funding_cost());
+        game.check_operation(&fund(p2, Award::Banker)).unwrap();
+        assert_eq!(
+            vec![
+                GameEvent::ResourcesChanged(p2, btreemap! { Resource::Megacredits => -8 }),
+                GameEvent::AwardFunded(p2, Award::Banker),
+            ],
+            game.execute_operation(fund(p2, Award::Banker))
+        );
+        assert_eq!(
+            Err(IllegalOperation::AwardAlreadyFunded(Award::Banker)),
+            game.check_operation(&fund(p1, Award::Banker))
+        );
+
+        // p2 can no longer afford the second award.
+        assert_eq!(Some(14), game.award_funding_cost());
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(Resource::Megacredits)),
+            game.check_operation(&fund(p2, Award::Miner))
+        );
+        game.execute_operation(fund(p1, Award::Miner));
+        assert_eq!(Some(20), game.award_funding_cost());
+        game.execute_operation(fund(p1, Award::Landlord));
+        assert_eq!(16, game.players[&p1].resources[Resource::Megacredits]);
+
+        assert_eq!(None, game.award_funding_cost());
+        assert_eq!(
+            Err(IllegalOperation::AllAwardsFunded),
+            game.check_operation(&fund(p1, Award::Scientist))
+        );
+        assert_eq!(
+            vec![
+                (Award::Banker, p2),
+                (Award::Miner, p1),
+                (Award::Landlord, p1)
+            ],
+            game.funded_awards
+        );
+    }
+
+    #[test]
+    fn only_funded_awards_are_scored() {
+        let game = import_position(
+            "award Thermalist p3\n\
+             player p1\n\
+             resources 0 0 0 0 0 6\n\
+             production 3 0 0 0 0 0\n\
+             player p2\n\
+             resources 0 0 0 0 0 6\n\
+             player p3\n\
+             resources 0 0 0 0 0 2",
+        )
+        .unwrap();
+        let awards: Vec<_> = score_all_players(&game)
+            .into_iter()
+            .map(|(player_id, breakdown)| (usize::from(player_id), breakdown.awards))
+            .collect();
+        // p1 leads the unfunded Banker award, which scores nothing.
+        assert_eq!(vec![(1, 5), (2, 5), (3, 0)], awards);
+    }
+}
@@ -0,0 +1,1501 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+
+use crate::{card::{CardEffect, CityKind, ImmediateImpact, LocationRestriction, SpecialLocation, SpecialTile}, game::{GameOperation, PlayAttempt, PlayerId, PlayerState}, resource::Resource};
+
+/// Using implicit 3-axis "cube" coordinate system, with all points satisfying x + y + z = 0.
+/// We always drop the z coordinate, since it's implicitly z = -(x + y).
+/// Properties:
+/// - (0, 0) on the left-most hex of the center row.
+/// - Bottom left edge has x = 0. The x coordinate increases to the top-right.
+/// - Top left edge has y = 0. The y coordinate increases to the top-left.
+/// - Center row has z = 0. The z coordinate increases downward.
+///
+/// Diagram at:
+/// https://www.redblobgames.com/grids/hexagons/
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Coordinates {
+    x: isize,
+    y: isize,
+}
+
+// Written as "x,y", the same way positions are described.
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+impl Coordinates {
+    const NEIGHBORS_DX_DY: [(isize, isize); 6] = [
+        // clockwise neighbors, starting from the right neighbor
+        (1, -1),
+        (0, -1),
+        (-1, 0),
+        (-1, 1),
+        (0, 1),
+        (1, 0),
+    ];
+
+    #[inline]
+    pub fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    #[inline]
+    pub const fn get_z(&self) -> isize {
+        -(self.x + self.y)
+    }
+}
+
+/// The extent of a map on Mars: the spaces between each pair of parallel edges of the hex.
+/// A map has a space at every location within its bounds, so maps with more rows
+/// or a differently shaped outline only need different bounds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BoardBounds {
+    pub min_x: isize,
+    pub max_x: isize,
+    pub min_y: isize,
+    pub max_y: isize,
+    pub min_z: isize,
+    pub max_z: isize,
+}
+
+impl BoardBounds {
+    /// The 9-row hex of the Tharsis map.
+    pub const BASE_GAME: BoardBounds = BoardBounds {
+        min_x: 0,
+        max_x: 8,
+        min_y: -8,
+        max_y: 0,
+        min_z: -4,
+        max_z: 4,
+    };
+
+    /// The smallest bounds that contain all the locations, if there are any.
+    pub fn enclosing(locations: impl IntoIterator<Item = Coordinates>) -> Option<BoardBounds> {
+        locations.into_iter().fold(None, |bounds, c| {
+            let z = c.get_z();
+            Some(match bounds {
+                None => BoardBounds {
+                    min_x: c.x,
+                    max_x: c.x,
+                    min_y: c.y,
+                    max_y: c.y,
+                    min_z: z,
+                    max_z: z,
+                },
+                Some(b) => BoardBounds {
+                    min_x: b.min_x.min(c.x),
+                    max_x: b.max_x.max(c.x),
+                    min_y: b.min_y.min(c.y),
+                    max_y: b.max_y.max(c.y),
+                    min_z: b.min_z.min(z),
+                    max_z: b.max_z.max(z),
+                },
+            })
+        })
+    }
+
+    #[inline]
+    pub fn contains(&self, coordinates: Coordinates) -> bool {
+        // Check that the point is between the bottom-left and the top-right edge.
+        let within_x = (self.min_x..=self.max_x).contains(&coordinates.x);
+
+        // Check that the point is between the bottom-right and the top-left edge.
+        let within_y = (self.min_y..=self.max_y).contains(&coordinates.y);
+
+        // Check that the point is between the top and bottom edges.
+        let within_z = (self.min_z..=self.max_z).contains(&coordinates.get_z());
+
+        within_x && within_y && within_z
+    }
+
+    /// All the locations within the bounds, in no particular order.
+    pub fn locations(&self) -> impl Iterator<Item = Coordinates> + '_ {
+        (self.min_x..=self.max_x)
+            .flat_map(move |x| (self.min_y..=self.max_y).map(move |y| Coordinates::new(x, y)))
+            .filter(move |coordinates| self.contains(*coordinates))
+    }
+
+    #[inline]
+    pub fn neighbors(&self, location: &TileLocation) -> Neighbors {
+        Neighbors {
+            center: match location {
+                TileLocation::OnMars(coordinates) => Some(*coordinates),
+                TileLocation::OffMars(_) => None,
+            },
+            next_direction: 0,
+            bounds: *self,
+        }
+    }
+}
+
+impl Default for BoardBounds {
+    fn default() -> Self {
+        BoardBounds::BASE_GAME
+    }
+}
+
+/// The in-bounds neighbors of a location, clockwise starting from the right neighbor.
+/// Locations off Mars have no neighbors.
+#[derive(Clone, Copy, Debug)]
+pub struct Neighbors {
+    center: Option<Coordinates>,
+    next_direction: usize,
+    bounds: BoardBounds,
+}
+
+impl Iterator for Neighbors {
+    type Item = Coordinates;
+
+    #[inline]
+    fn next(&mut self) -> Option<Coordinates> {
+        let center = self.center?;
+        while let Some((dx, dy)) = Coordinates::NEIGHBORS_DX_DY.get(self.next_direction) {
+            self.next_direction += 1;
+
+            let neighbor = Coordinates::new(center.x + dx, center.y + dy);
+            if self.bounds.contains(neighbor) {
+                return Some(neighbor);
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.center {
+            Some(_) => (0, Some(Coordinates::NEIGHBORS_DX_DY.len() - self.next_direction)),
+            None => (0, Some(0)),
+        }
+    }
+}
+
+impl From<(isize, isize)> for Coordinates {
+    #[inline]
+    fn from(coordinates: (isize, isize)) -> Self {
+        Self::new(coordinates.0, coordinates.1)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TileLocation {
+    OnMars(Coordinates),
+    OffMars(SpecialLocation),
+}
+
+impl From<Coordinates> for TileLocation {
+    #[inline]
+    fn from(coordinates: Coordinates) -> Self {
+        Self::OnMars(coordinates)
+    }
+}
+
+impl fmt::Display for TileLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileLocation::OnMars(coordinates) => coordinates.fmt(f),
+            TileLocation::OffMars(special_location) => write!(f, "{:?}", special_location),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[repr(transparent)]
+pub struct EmptyLocation(TileLocation);
+
+impl From<TileLocation> for EmptyLocation {
+    #[inline]
+    fn from(location: TileLocation) -> Self {
+        Self(location)
+    }
+}
+
+impl From<EmptyLocation> for TileLocation {
+    #[inline]
+    fn from(empty_location: EmptyLocation) -> Self {
+        empty_location.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileStatus {
+    Empty(EmptyLocation),
+    Ocean(TileLocation),
+    City(TileLocation, CityKind, PlayerId),
+    Greenery(TileLocation, PlayerId),
+    SpecialTile(TileLocation, SpecialTile, PlayerId),
+}
+
+impl TileStatus {
+    pub fn location(&self) -> &TileLocation {
+        match self {
+            TileStatus::Empty(EmptyLocation(location))
+            | TileStatus::Ocean(location)
+            | TileStatus::City(location, _, _)
+            | TileStatus::Greenery(location, _)
+            | TileStatus::SpecialTile(location, _, _) => location,
+        }
+    }
+
+    // Oceans belong to nobody, even though placing them earns a player terraform rating.
+    pub fn owner(&self) -> Option<PlayerId> {
+        match self {
+            TileStatus::City(_, _, player_id)
+            | TileStatus::Greenery(_, player_id)
+            | TileStatus::SpecialTile(_, _, player_id) => Some(*player_id),
+            TileStatus::Empty(_) | TileStatus::Ocean(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarsBoard {
+    pub board_name: String,
+
+    #[serde(with = "map_entries")]
+    pub spaces: HashMap<TileLocation, BoardSpace>,
+    #[serde(default)]
+    pub bounds: BoardBounds,
+
+    #[serde(with = "map_entries")]
+    pub cities: HashMap<TileLocation, (CityKind, PlayerId)>,
+    pub oceans: HashSet<Coordinates>,
+    #[serde(with = "map_entries")]
+    pub greeneries: HashMap<Coordinates, PlayerId>,
+    #[serde(with = "map_entries")]
+    pub special_tiles: HashMap<Coordinates, (SpecialTile, PlayerId)>,
+
+    pub oxygen: usize,
+    pub temperature: isize,
+
+    // gained for each ocean next to a newly placed tile; some maps and variants change it
+    #[serde(default = "default_ocean_adjacency_megacredits")]
+    pub ocean_adjacency_megacredits: usize,
+}
+
+fn default_ocean_adjacency_megacredits() -> usize {
+    MarsBoard::DEFAULT_OCEAN_ADJACENCY_MEGACREDITS
+}
+
+// Maps keyed by locations are written out as lists of (key, value) entries,
+// since their keys cannot be used as keys in most serialization formats.
+mod map_entries {
+    use std::{collections::HashMap, hash::Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        K: Serialize + Ord,
+        V: Serialize,
+    {
+        // Sort the entries so the output does not depend on the map's iteration order.
+        let mut entries: Vec<(&K, &V)> = map.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        serializer.collect_seq(entries)
+    }
+
+    pub fn deserialize<'de, D, K, V>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+impl MarsBoard {
+    pub const DEFAULT_OCEAN_ADJACENCY_MEGACREDITS: usize = 2;
+    pub const MAX_OCEANS: usize = 9;
+    pub const STARTING_TEMPERATURE: isize = -30;
+    pub const MAX_TEMPERATURE: isize = 8;
+    pub const TEMPERATURE_INCREMENT: isize = 2;
+    pub const STARTING_OXYGEN: usize = 0;
+    pub const MAX_OXYGEN: usize = 14;
+    pub const OXYGEN_INCREMENT: usize = 1;
+
+    /// Whether every global parameter is at its maximum: the game ends with this generation.
+    pub fn is_terraformed(&self) -> bool {
+        self.oxygen >= MarsBoard::MAX_OXYGEN
+            && self.temperature >= MarsBoard::MAX_TEMPERATURE
+            && self.oceans.len() >= MarsBoard::MAX_OCEANS
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        board_name: String,
+        spaces: HashMap<TileLocation, BoardSpace>,
+        cities: HashMap<TileLocation, (CityKind, PlayerId)>,
+        oceans: HashSet<Coordinates>,
+        greeneries: HashMap<Coordinates, PlayerId>,
+        special_tiles: HashMap<Coordinates, (SpecialTile, PlayerId)>,
+        oxygen: usize,
+        temperature: isize,
+    ) -> Self {
+        // Ensure no board location is marked as occupied by two different tile types.
+        let mut occupied_locations: HashSet<TileLocation> = HashSet::new();
+        occupied_locations.extend(cities.keys().cloned());
+        occupied_locations.extend(oceans.iter().map(|x| TileLocation::OnMars(*x)));
+        occupied_locations.extend(greeneries.keys().map(|x| TileLocation::OnMars(*x)));
+        occupied_locations.extend(special_tiles.keys().map(|x| TileLocation::OnMars(*x)));
+        assert_eq!(
+            occupied_locations.len(),
+            cities.len() + oceans.len() + greeneries.len() + special_tiles.len()
+        );
+
+        let bounds = BoardBounds::enclosing(spaces.keys().filter_map(|location| match location {
+            TileLocation::OnMars(coordinates) => Some(*coordinates),
+            TileLocation::OffMars(_) => None,
+        }))
+        .unwrap_or_default();
+
+        Self {
+            board_name,
+            spaces,
+            bounds,
+            cities,
+            oceans,
+            greeneries,
+            special_tiles,
+            oxygen,
+            temperature,
+            ocean_adjacency_megacredits: Self::DEFAULT_OCEAN_ADJACENCY_MEGACREDITS,
+        }
+    }
+
+    pub fn increase_temperature(&self, player: &PlayerState) -> PlayAttempt {
+        assert!(self.temperature <= MarsBoard::MAX_TEMPERATURE);
+        assert!(self.temperature >= MarsBoard::STARTING_TEMPERATURE);
+        assert_eq!(self.temperature % MarsBoard::TEMPERATURE_INCREMENT, 0);
+
+        if self.temperature == MarsBoard::MAX_TEMPERATURE {
+            PlayAttempt::Unplayable
+        } else {
+            let mut operations = vec![
+                GameOperation::RaiseTemperature,
+                GameOperation::RaiseTerraformRating(player.player_id, 1),
+            ];
+
+            let new_temperature = self.temperature + MarsBoard::TEMPERATURE_INCREMENT;
+
+            if new_temperature == 0 && self.oceans.len() < MarsBoard::MAX_OCEANS {
+                PlayAttempt::PartiallyPlayable(
+                    operations,
+                    vec![
+                        ImmediateImpact::PlaceOcean(vec![
+                            LocationRestriction::ReservedForOcean,
+                        ])
+                    ]
+                )
+            } else {
+                if new_temperature == -24 || new_temperature == -20 {
+                    operations.push(GameOperation::ChangeProduction(player.player_id, btreemap! { Resource::Heat => 1 }));
+                }
+                PlayAttempt::Playable(operations)
+            }
+        }
+    }
+
+    pub fn can_increase_oxygen(&self, player: &PlayerState) -> PlayAttempt {
+        assert!(self.oxygen <= MarsBoard::MAX_OXYGEN);
+
+        if self.oxygen == MarsBoard::MAX_OXYGEN {
+            PlayAttempt::Unplayable
+        } else {
+            let new_oxygen = self.oxygen + MarsBoard::OXYGEN_INCREMENT;
+            let mut operations = vec![
+                GameOperation::RaiseOxygen,
+                GameOperation::RaiseTerraformRating(player.player_id, 1),
+            ];
+
+            if new_oxygen == 8 && self.temperature < MarsBoard::MAX_TEMPERATURE {
+                operations.extend_from_slice(&[
+                    GameOperation::RaiseTemperature,
+                    GameOperation::RaiseTerraformRating(player.player_id, 1),
+                ]);
+            }
+
+            PlayAttempt::Playable(operations)
+        }
+    }
+
+    /// Whether the location is within the outline of this map.
+    #[inline]
+    pub fn is_in_bounds(&self, coordinates: Coordinates) -> bool {
+        self.bounds.contains(coordinates)
+    }
+
+    /// The neighbors of the location on this map, clockwise starting from the right neighbor.
+    #[inline]
+    pub fn neighbors(&self, location: &TileLocation) -> Neighbors {
+        self.bounds.neighbors(location)
+    }
+
+    pub fn get_tile_status(&self, location: &TileLocation) -> TileStatus {
+        match location {
+            TileLocation::OnMars(coordinates) => self.get_on_mars_tile_status(*coordinates),
+            TileLocation::OffMars(_) => match self.cities.get(location) {
+                Some(&(city_kind, player_id)) => {
+                    TileStatus::City(location.clone(), city_kind, player_id)
+                }
+
+                // The only things that can be placed off Mars are cities.
+                None => TileStatus::Empty(location.clone().into()),
+            },
+        }
+    }
+
+    // Locations on Mars are cheap to copy, so looking up their status never allocates.
+    fn get_on_mars_tile_status(&self, coordinates: Coordinates) -> TileStatus {
+        let location = TileLocation::OnMars(coordinates);
+        if let Some(&(city_kind, player_id)) = self.cities.get(&location) {
+            TileStatus::City(location, city_kind, player_id)
+        } else if self.oceans.contains(&coordinates) {
+            TileStatus::Ocean(location)
+        } else if let Some(&player_id) = self.greeneries.get(&coordinates) {
+            TileStatus::Greenery(location, player_id)
+        } else if let Some(&(tile, player_id)) = self.special_tiles.get(&coordinates) {
+            TileStatus::SpecialTile(location, tile, player_id)
+        } else {
+            TileStatus::Empty(location.into())
+        }
+    }
+
+    pub fn count_adjacent_oceans(&self, empty_location: &EmptyLocation) -> usize {
+        let location = &empty_location.0;
+
+        self.get_neighbor_tile_status(location)
+            .filter(|status| matches!(status, TileStatus::Ocean(_)))
+            .count()
+    }
+
+    pub fn get_neighbor_tile_status<'a>(
+        &'a self,
+        location: &TileLocation,
+    ) -> impl Iterator<Item = TileStatus> + 'a {
+        self.neighbors(location)
+            .map(move |neighbor| self.get_on_mars_tile_status(neighbor))
+    }
+
+    /// Whether any space of this map is reserved for the special location.
+    pub fn has_special_location(&self, special_location: &SpecialLocation) -> bool {
+        self.spaces.values().any(|space| space.is_at_special_location(special_location))
+    }
+
+    /// What the player gets for placing a tile on the location: the space's own bonus,
+    /// and megacredits for the adjacent oceans, multiplied by the player's effects.
+    pub fn get_placement_bonuses(
+        &self,
+        player: &PlayerState,
+        empty_location: &EmptyLocation,
+    ) -> Vec<ImmediateImpact> {
+        let multiplier: usize = player
+            .effects
+            .iter()
+            .map(|effect| match effect {
+                CardEffect::OceanAdjacencyBonusMultiplier(multiplier) => *multiplier,
+                _ => 1,
+            })
+            .product();
+        let adjacent_oceans = self.count_adjacent_oceans(empty_location);
+        let ocean_adjacency_megacredits =
+            adjacent_oceans * self.ocean_adjacency_megacredits * multiplier;
+
+        let board_space = self
+            .spaces
+            .get(&empty_location.0)
+            .expect("Tile location did not existon this board.");
+        let mut placement_bonuses = board_space.placement_bonus.clone();
+
+        let mut coalesced = false;
+        for bonus in placement_bonuses.iter_mut() {
+            let total_megacredits = match bonus {
+                &mut ImmediateImpact::GainResource(Resource::Megacredits, megacredits) => {
+                    Some(megacredits + ocean_adjacency_megacredits)
+                }
+                _ => None,
+            };
+
+            if let Some(megacredits) = total_megacredits {
+                coalesced = true;
+                bonus.clone_from(&ImmediateImpact::GainResource(
+                    Resource::Megacredits,
+                    megacredits,
+                ));
+                break;
+            }
+        }
+
+        if !coalesced {
+            placement_bonuses.push(ImmediateImpact::GainResource(
+                Resource::Megacredits,
+                ocean_adjacency_megacredits,
+            ));
+        }
+
+        placement_bonuses
+    }
+
+    /// The operations that give the player the bonuses for placing a tile on the location.
+    /// Cards are drawn from the shared deck into the player's hand, without buying them.
+    pub fn placement_bonus_operations(
+        &self,
+        player: &PlayerState,
+        empty_location: &EmptyLocation,
+    ) -> Vec<GameOperation> {
+        self.get_placement_bonuses(player, empty_location)
+            .into_iter()
+            .filter_map(|bonus| match bonus {
+                ImmediateImpact::GainResource(_, 0) | ImmediateImpact::DrawCard(0) => None,
+                ImmediateImpact::GainResource(resource, amount) => Some(
+                    GameOperation::ChangeResources(
+                        player.player_id,
+                        btreemap! { resource => amount as isize },
+                    ),
+                ),
+                ImmediateImpact::DrawCard(count) => {
+                    Some(GameOperation::DrawCards(player.player_id, count))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn placement_satisfies_restrictions(
+        &self,
+        player: &PlayerState,
+        empty_location: &EmptyLocation,
+        location_restrictions: &[LocationRestriction],
+    ) -> bool {
+        let location = &empty_location.0;
+
+        let mut adjacent_tiles_of_any_kind: usize = 0;
+        let mut adjacent_greeneries: usize = 0;
+        let mut adjacent_cities: usize = 0;
+        let mut adjacent_owned_tiles: usize = 0;
+
+        for status in self.get_neighbor_tile_status(location) {
+            if !matches!(status, TileStatus::Empty(_)) {
+                adjacent_tiles_of_any_kind += 1;
+            }
+
+            match status {
+                TileStatus::City(_, _, _) => {
+                    adjacent_cities += 1;
+                }
+                TileStatus::Greenery(_, _) => {
+                    adjacent_greeneries += 1;
+                }
+                TileStatus::Empty(_) | TileStatus::Ocean(_) | TileStatus::SpecialTile(_, _, _) => {}
+            }
+
+            adjacent_owned_tiles += match status {
+                TileStatus::City(_, _, owner_id)
+                | TileStatus::Greenery(_, owner_id)
+                | TileStatus::SpecialTile(_, _, owner_id)
+                    if owner_id == player.player_id =>
+                {
+                    1
+                }
+                _ => 0,
+            }
+        }
+
+        let board_space = self.spaces.get(location).unwrap();
+        for restriction in location_restrictions {
+            match restriction {
+                LocationRestriction::LandTile => {
+                    if !board_space.is_land() {
+                        return false;
+                    }
+                }
+                LocationRestriction::ReservedForOcean => {
+                    if !board_space.is_reserved_for_ocean() {
+                        return false;
+                    }
+                }
+                LocationRestriction::OnSteelOrTitaniumPlacementBonus => {
+                    let is_on_metal_placement_bonus =
+                        board_space.placement_bonus.iter().any(|impact| {
+                            matches!(
+                                impact,
+                                ImmediateImpact::GainResource(Resource::Steel, _)
+                                    | ImmediateImpact::GainResource(Resource::Titanium, _)
+                            )
+                        });
+                    if !is_on_metal_placement_bonus {
+                        return false;
+                    }
+                }
+                LocationRestriction::AtSpecialLocation(special_location) => {
+                    if board_space.is_at_special_location(special_location) {
+                        continue;
+                    }
+
+                    // Maps without volcanic areas or a Noctis City space let those tiles
+                    // go anywhere a standard tile of their kind could go.
+                    let placed_as_standard_tile = match special_location {
+                        SpecialLocation::VolcanicArea => board_space.is_land(),
+                        SpecialLocation::NoctisCity => board_space.is_land() && adjacent_cities == 0,
+                        SpecialLocation::PhobosSpaceHaven | SpecialLocation::GanymedeColony => false,
+                    };
+                    if !placed_as_standard_tile || self.has_special_location(special_location) {
+                        return false;
+                    }
+                }
+                LocationRestriction::AdjacentToOwnedTile => {
+                    if adjacent_owned_tiles == 0 {
+                        return false;
+                    }
+                }
+                LocationRestriction::AdjacentToOwnedTileIfAble => {
+                    if adjacent_owned_tiles == 0
+                        && self.has_empty_land_next_to_owned_tile(player.player_id)
+                    {
+                        return false;
+                    }
+                }
+                LocationRestriction::NotNextToAnyOtherTile => {
+                    if adjacent_tiles_of_any_kind > 0 {
+                        return false;
+                    }
+                }
+                LocationRestriction::NotNextToACity => {
+                    if adjacent_cities > 0 {
+                        return false;
+                    }
+                }
+                LocationRestriction::NextToACity => {
+                    if adjacent_cities < 1 {
+                        return false;
+                    }
+                }
+                LocationRestriction::NextToAtLeastTwoCities => {
+                    if adjacent_cities < 2 {
+                        return false;
+                    }
+                }
+                LocationRestriction::NextToAGreenery => {
+                    if adjacent_greeneries < 1 {
+                        return false;
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Whether the player's tile has anywhere to go. If it doesn't, the official rules
+    /// skip the placement, while the rest of what called for the tile still happens.
+    pub fn has_legal_placement(
+        &self,
+        player: &PlayerState,
+        location_restrictions: &[LocationRestriction],
+    ) -> bool {
+        self.spaces.keys().any(|location| {
+            matches!(self.get_tile_status(location), TileStatus::Empty(_))
+                && self.placement_satisfies_restrictions(
+                    player,
+                    &EmptyLocation::from(location.clone()),
+                    location_restrictions,
+                )
+        })
+    }
+
+    pub fn has_legal_ocean_placement(&self, player: &PlayerState) -> bool {
+        self.oceans.len() < MarsBoard::MAX_OCEANS
+            && self.has_legal_placement(player, &[LocationRestriction::ReservedForOcean])
+    }
+
+    // Whether any empty land space is next to one of the player's tiles. Tiles that go
+    // next to an owned tile "if able" may only go elsewhere when there is none.
+    fn has_empty_land_next_to_owned_tile(&self, player_id: PlayerId) -> bool {
+        self.spaces.iter().any(|(location, space)| {
+            space.is_land()
+                && matches!(self.get_tile_status(location), TileStatus::Empty(_))
+                && self.get_neighbor_tile_status(location).any(|status| match status {
+                    TileStatus::City(_, _, owner_id)
+                    | TileStatus::Greenery(_, owner_id)
+                    | TileStatus::SpecialTile(_, _, owner_id) => owner_id == player_id,
+                    TileStatus::Empty(_) | TileStatus::Ocean(_) => false,
+                })
+        })
+    }
+
+    pub fn can_place_greenery(
+        &self,
+        player: &PlayerState,
+        empty_location: EmptyLocation,
+        location_restrictions: &[LocationRestriction],
+    ) -> PlayAttempt {
+        if !self.placement_satisfies_restrictions(player, &empty_location, location_restrictions) {
+            return PlayAttempt::Unplayable;
+        }
+
+        let coordinates = match empty_location.0 {
+            TileLocation::OnMars(c) => c,
+            TileLocation::OffMars(_) => unreachable!(),
+        };
+
+        assert!(!self.greeneries.contains_key(&coordinates));
+        let mut operations = vec![
+            GameOperation::PlaceGreenery(player.player_id, coordinates),
+        ];
+        operations.extend(self.placement_bonus_operations(player, &empty_location));
+
+        match self.can_increase_oxygen(player) {
+            PlayAttempt::Playable(ops) => {
+                operations.extend_from_slice(&ops);
+                PlayAttempt::Playable(operations)
+            }
+            PlayAttempt::PartiallyPlayable(ops, impacts) => {
+                operations.extend_from_slice(&ops);
+                PlayAttempt::PartiallyPlayable(operations, impacts)
+            }
+            PlayAttempt::Unplayable => {
+                // can still place the greneery but can't get the oxygen boost
+                PlayAttempt::Playable(operations)
+            }
+        }
+    }
+
+    pub fn can_place_city(
+        &mut self,
+        player: &mut PlayerState,
+        empty_location: EmptyLocation,
+        city_kind: CityKind,
+        location_restrictions: &[LocationRestriction],
+    ) -> Option<()> {
+        if !self.placement_satisfies_restrictions(player, &empty_location, location_restrictions) {
+            return None;
+        }
+
+        let existing_tile = self
+            .cities
+            .insert(empty_location.0, (city_kind, player.player_id));
+        assert!(existing_tile.is_none());
+
+        // TODO: turn the impacts from self.get_placement_bonuses(player, empty_location) into operations here
+
+        Some(())
+    }
+}
+
+/// Builds a base game board tile by tile, e.g. for a test scenario. Every tile must go on
+/// an empty space of the board, and the global parameters must be at levels they can reach.
+pub struct MarsBoardBuilder {
+    board: MarsBoard,
+}
+
+impl MarsBoardBuilder {
+    pub fn new() -> MarsBoardBuilder {
+        MarsBoardBuilder {
+            board: make_base_game_board(),
+        }
+    }
+
+    pub fn with_city(
+        mut self,
+        location: impl Into<TileLocation>,
+        city_kind: CityKind,
+        player_id: PlayerId,
+    ) -> MarsBoardBuilder {
+        let location = self.assert_empty(location.into());
+        self.board.cities.insert(location, (city_kind, player_id));
+        self
+    }
+
+    pub fn with_ocean(mut self, coordinates: Coordinates) -> MarsBoardBuilder {
+        self.assert_empty(coordinates.into());
+        assert!(self.board.oceans.len() < MarsBoard::MAX_OCEANS);
+
+        self.board.oceans.insert(coordinates);
+        self
+    }
+
+    pub fn with_greenery(
+        mut self,
+        coordinates: Coordinates,
+        player_id: PlayerId,
+    ) -> MarsBoardBuilder {
+        self.assert_empty(coordinates.into());
+        self.board.greeneries.insert(coordinates, player_id);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: isize) -> MarsBoardBuilder {
+        assert!(temperature >= MarsBoard::STARTING_TEMPERATURE);
+        assert!(temperature <= MarsBoard::MAX_TEMPERATURE);
+        assert_eq!(temperature % MarsBoard::TEMPERATURE_INCREMENT, 0);
+
+        self.board.temperature = temperature;
+        self
+    }
+
+    pub fn with_oxygen(mut self, oxygen: usize) -> MarsBoardBuilder {
+        assert!(oxygen <= MarsBoard::MAX_OXYGEN);
+
+        self.board.oxygen = oxygen;
+        self
+    }
+
+    pub fn build(self) -> MarsBoard {
+        self.board
+    }
+
+    fn assert_empty(&self, location: TileLocation) -> TileLocation {
+        assert!(
+            self.board.spaces.contains_key(&location),
+            "{} is not on the board",
+            location
+        );
+        assert!(
+            matches!(self.board.get_tile_status(&location), TileStatus::Empty(_)),
+            "{} is already occupied",
+            location
+        );
+        location
+    }
+}
+
+impl Default for MarsBoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Designation {
+    Land,
+    ReservedForOcean,
+    VolcanicArea,
+    NonMarsTile,
+    Special(SpecialLocation),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSpace {
+    pub name: Option<String>,
+    pub location: TileLocation,
+    pub designations: Vec<Designation>,
+    pub placement_bonus: Vec<ImmediateImpact>,
+}
+
+impl BoardSpace {
+    pub fn new(
+        name: Option<String>,
+        location: TileLocation,
+        designations: Vec<Designation>,
+        placement_bonus: Vec<ImmediateImpact>,
+    ) -> Self {
+        BoardSpace {
+            name,
+            location,
+            designations,
+            placement_bonus,
+        }
+    }
+
+    #[inline]
+    pub fn new_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        designations: Vec<Designation>,
+        placement_bonus: Vec<ImmediateImpact>,
+    ) -> BoardSpace {
+        BoardSpace::new(
+            None,
+            TileLocation::OnMars(mars_coordinates.into()),
+            designations,
+            placement_bonus,
+        )
+    }
+
+    #[inline]
+    pub fn new_land_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        placement_bonus: Vec<ImmediateImpact>,
+    ) -> BoardSpace {
+        BoardSpace::new_on_mars(mars_coordinates, vec![Designation::Land], placement_bonus)
+    }
+
+    #[inline]
+    pub fn new_non_bonus_land_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+    ) -> BoardSpace {
+        BoardSpace::new_land_on_mars(mars_coordinates, vec![])
+    }
+
+    #[inline]
+    pub fn new_resource_bonus_land_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        resource: Resource,
+        count: usize,
+    ) -> BoardSpace {
+        BoardSpace::new_land_on_mars(
+            mars_coordinates,
+            vec![ImmediateImpact::GainResource(resource, count)],
+        )
+    }
+
+    #[inline]
+    pub fn new_card_draw_land_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        card_count: usize,
+    ) -> BoardSpace {
+        BoardSpace::new_land_on_mars(
+            mars_coordinates,
+            vec![ImmediateImpact::DrawCard(card_count)],
+        )
+    }
+
+    #[inline]
+    pub fn new_ocean_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        placement_bonus: Vec<ImmediateImpact>,
+    ) -> BoardSpace {
+        BoardSpace::new(
+            None,
+            TileLocation::OnMars(mars_coordinates.into()),
+            vec![Designation::ReservedForOcean],
+            placement_bonus,
+        )
+    }
+
+    #[inline]
+    pub fn new_resource_bonus_ocean_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        resource: Resource,
+        count: usize,
+    ) -> BoardSpace {
+        BoardSpace::new_ocean_on_mars(
+            mars_coordinates,
+            vec![ImmediateImpact::GainResource(resource, count)],
+        )
+    }
+
+    #[inline]
+    pub fn new_card_draw_ocean_on_mars<CoordT: Into<Coordinates>>(
+        mars_coordinates: CoordT,
+        card_count: usize,
+    ) -> BoardSpace {
+        BoardSpace::new_ocean_on_mars(
+            mars_coordinates,
+            vec![ImmediateImpact::DrawCard(card_count)],
+        )
+    }
+
+    #[inline]
+    pub fn is_land(&self) -> bool {
+        self.designations
+            .iter()
+            .any(|d| matches!(d, Designation::Land))
+    }
+
+    #[inline]
+    pub fn is_reserved_for_ocean(&self) -> bool {
+        self.designations
+            .iter()
+            .any(|d| matches!(d, Designation::ReservedForOcean))
+    }
+
+    #[inline]
+    pub fn is_at_special_location(&self, special_location: &SpecialLocation) -> bool {
+        self.designations
+            .iter()
+            .any(|d| matches!(d, Designation::Special(s) if s == special_location))
+    }
+}
+
+pub fn make_standard_non_mars_board_spaces() -> Vec<BoardSpace> {
+    vec![
+        BoardSpace::new(
+            Some("Phobos Space Haven".into()),
+            TileLocation::OffMars(SpecialLocation::PhobosSpaceHaven),
+            vec![
+                Designation::NonMarsTile,
+                Designation::Special(SpecialLocation::PhobosSpaceHaven),
+            ],
+            vec![],
+        ),
+        BoardSpace::new(
+            Some("Ganymede Colony".into()),
+            TileLocation::OffMars(SpecialLocation::GanymedeColony),
+            vec![
+                Designation::NonMarsTile,
+                Designation::Special(SpecialLocation::GanymedeColony),
+            ],
+            vec![],
+        ),
+    ]
+}
+
+/// A map as it is written down, e.g. one of the community maps: its name and its spaces.
+/// The spaces off Mars are the standard ones, unless the map lists its own.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardDefinition {
+    pub board_name: String,
+    pub spaces: Vec<BoardSpace>,
+    #[serde(default = "default_ocean_adjacency_megacredits")]
+    pub ocean_adjacency_megacredits: usize,
+}
+
+/// Loads a map written as JSON, e.g. one of the community maps with more rows
+/// or different reserved areas than the base game's.
+pub fn load_board(json: &str) -> Result<MarsBoard, String> {
+    let definition: BoardDefinition =
+        serde_json::from_str(json).map_err(|e| format!("invalid board: {}", e))?;
+    make_board(definition)
+}
+
+/// Builds an empty board for the map, checking that it can host a game: its spaces on
+/// Mars must fill their bounds, and there must be room for every ocean.
+pub fn make_board(definition: BoardDefinition) -> Result<MarsBoard, String> {
+    let BoardDefinition {
+        board_name,
+        spaces,
+        ocean_adjacency_megacredits,
+    } = definition;
+
+    let mut spaces_by_location: HashMap<TileLocation, BoardSpace> = HashMap::new();
+    for space in spaces {
+        let location = space.location.clone();
+        if spaces_by_location.insert(location.clone(), space).is_some() {
+            return Err(format!("{} appears twice on the {} board", location, board_name));
+        }
+    }
+    for space in make_standard_non_mars_board_spaces() {
+        spaces_by_location.entry(space.location.clone()).or_insert(space);
+    }
+
+    let on_mars = spaces_by_location.keys().filter_map(|location| match location {
+        TileLocation::OnMars(coordinates) => Some(*coordinates),
+        TileLocation::OffMars(_) => None,
+    });
+    let bounds = BoardBounds::enclosing(on_mars)
+        .ok_or_else(|| format!("the {} board has no spaces on Mars", board_name))?;
+    if let Some(missing) = bounds
+        .locations()
+        .find(|coordinates| !spaces_by_location.contains_key(&TileLocation::OnMars(*coordinates)))
+    {
+        return Err(format!("the {} board has no space at {}", board_name, missing));
+    }
+
+    let ocean_spaces = spaces_by_location
+        .values()
+        .filter(|space| space.is_reserved_for_ocean())
+        .count();
+    if ocean_spaces < MarsBoard::MAX_OCEANS {
+        return Err(format!(
+            "the {} board has {} ocean spaces, but {} are needed",
+            board_name,
+            ocean_spaces,
+            MarsBoard::MAX_OCEANS
+        ));
+    }
+
+    let mut board = MarsBoard::new(
+        board_name,
+        spaces_by_location,
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        MarsBoard::STARTING_OXYGEN,
+        MarsBoard::STARTING_TEMPERATURE,
+    );
+    board.ocean_adjacency_megacredits = ocean_adjacency_megacredits;
+    Ok(board)
+}
+
+pub fn make_base_game_board() -> MarsBoard {
+    let board_name = "Tharsis".into();
+    let oxygen = 0usize;
+    let temperature = -30isize;
+
+    let mut spaces = make_standard_non_mars_board_spaces();
+    spaces.extend_from_slice(&[
+        // top-left edge = first top-rightward column
+        BoardSpace::new(
+            Some("Arsia Mons".into()),
+            TileLocation::OnMars((0, 0).into()),
+            vec![
+                Designation::Land,
+                Designation::Special(SpecialLocation::VolcanicArea),
+            ],
+            vec![ImmediateImpact::GainResource(Resource::Plants, 2)],
+        ),
+        BoardSpace::new(
+            Some("Pavonis Mons".into()),
+            TileLocation::OnMars((1, 0).into()),
+            vec![
+                Designation::Land,
+                Designation::Special(SpecialLocation::VolcanicArea),
+            ],
+            vec![
+                ImmediateImpact::GainResource(Resource::Plants, 1),
+                ImmediateImpact::GainResource(Resource::Titanium, 1),
+            ],
+        ),
+        BoardSpace::new(
+            Some("Ascraeus Mons".into()),
+            TileLocation::OnMars((2, 0).into()),
+            vec![
+                Designation::Land,
+                Designation::Special(SpecialLocation::VolcanicArea),
+            ],
+            vec![ImmediateImpact::DrawCard(1)],
+        ),
+        BoardSpace::new_non_bonus_land_on_mars((3, 0)),
+        BoardSpace::new_resource_bonus_land_on_mars((4, 0), Resource::Steel, 2),
+        //
+        // second top-rightward column
+        BoardSpace::new_resource_bonus_land_on_mars((0, -1), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_land_on_mars((1, -1), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((2, -1), Resource::Plants, 1),
+        BoardSpace::new_non_bonus_land_on_mars((3, -1)),
+        BoardSpace::new(
+            Some("Tharsis Tholus".into()),
+            TileLocation::OnMars((4, -1).into()),
+            vec![
+                Designation::Land,
+                Designation::Special(SpecialLocation::VolcanicArea),
+            ],
+            vec![ImmediateImpact::GainResource(Resource::Steel, 1)],
+        ),
+        BoardSpace::new_resource_bonus_ocean_on_mars((5, -1), Resource::Steel, 2),
+        //
+        // third top-rightward column
+        BoardSpace::new_non_bonus_land_on_mars((0, -2)),
+        BoardSpace::new_resource_bonus_land_on_mars((1, -2), Resource::Plants, 2),
+        BoardSpace::new(
+            Some("Noctis City".into()),
+            TileLocation::OnMars((2, -2).into()),
+            vec![
+                Designation::Land,
+                Designation::Special(SpecialLocation::NoctisCity),
+            ],
+            vec![ImmediateImpact::GainResource(Resource::Plants, 2)],
+        ),
+        BoardSpace::new_resource_bonus_land_on_mars((3, -2), Resource::Plants, 1),
+        BoardSpace::new_non_bonus_land_on_mars((4, -2)),
+        BoardSpace::new_non_bonus_land_on_mars((5, -2)),
+        BoardSpace::new_non_bonus_land_on_mars((6, -2)),
+        //
+        // fourth top-rightward column
+        BoardSpace::new_resource_bonus_land_on_mars((0, -3), Resource::Steel, 2),
+        BoardSpace::new_non_bonus_land_on_mars((1, -3)),
+        BoardSpace::new_resource_bonus_land_on_mars((2, -3), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_ocean_on_mars((3, -3), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((4, -3), Resource::Plants, 1),
+        BoardSpace::new_non_bonus_land_on_mars((5, -3)),
+        BoardSpace::new_non_bonus_land_on_mars((6, -3)),
+        BoardSpace::new_card_draw_ocean_on_mars((7, -3), 1),
+        //
+        // fifth top-rightward column -- bottom-left map corner
+        BoardSpace::new_resource_bonus_land_on_mars((0, -4), Resource::Steel, 1),
+        BoardSpace::new_non_bonus_land_on_mars((1, -4)),
+        BoardSpace::new_non_bonus_land_on_mars((2, -4)),
+        BoardSpace::new_resource_bonus_land_on_mars((3, -4), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_ocean_on_mars((4, -4), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((5, -4), Resource::Plants, 2),
+        BoardSpace::new_non_bonus_land_on_mars((6, -4)),
+        BoardSpace::new_non_bonus_land_on_mars((7, -4)),
+        BoardSpace::new_ocean_on_mars((8, -4), vec![]),
+        //
+        // sixth top-rightward column -- starts with second tile on the bottom edge
+        BoardSpace::new_resource_bonus_land_on_mars((1, -5), Resource::Steel, 2),
+        BoardSpace::new_non_bonus_land_on_mars((2, -5)),
+        BoardSpace::new_non_bonus_land_on_mars((3, -5)),
+        BoardSpace::new_resource_bonus_land_on_mars((4, -5), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_ocean_on_mars((5, -5), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((6, -5), Resource::Plants, 1),
+        BoardSpace::new_non_bonus_land_on_mars((7, -5)),
+        BoardSpace::new_card_draw_ocean_on_mars((8, -5), 2),
+        //
+        // seventh top-rightward column -- starts with third tile on the bottom edge
+        BoardSpace::new_non_bonus_land_on_mars((2, -6)),
+        BoardSpace::new_card_draw_land_on_mars((3, -6), 1),
+        BoardSpace::new_non_bonus_land_on_mars((4, -6)),
+        BoardSpace::new_resource_bonus_ocean_on_mars((5, -6), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_land_on_mars((6, -6), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((7, -6), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_land_on_mars((8, -6), Resource::Steel, 1),
+        //
+        // eighth top-rightward column -- starts with fourth tile on the bottom edge
+        BoardSpace::new_non_bonus_land_on_mars((3, -7)),
+        BoardSpace::new_non_bonus_land_on_mars((4, -7)),
+        BoardSpace::new_resource_bonus_land_on_mars((5, -7), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_ocean_on_mars((6, -7), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_land_on_mars((7, -7), Resource::Plants, 2),
+        BoardSpace::new_resource_bonus_ocean_on_mars((8, -7), Resource::Plants, 2),
+        //
+        // last top-rightward column -- lower-right edge
+        BoardSpace::new_resource_bonus_ocean_on_mars((4, -8), Resource::Titanium, 2),
+        BoardSpace::new_resource_bonus_land_on_mars((5, -8), Resource::Titanium, 1),
+        BoardSpace::new_non_bonus_land_on_mars((6, -8)),
+        BoardSpace::new_resource_bonus_ocean_on_mars((7, -8), Resource::Plants, 1),
+        BoardSpace::new_resource_bonus_land_on_mars((8, -8), Resource::Plants, 2),
+    ]);
+
+    MarsBoard::new(
+        board_name,
+        spaces
+            .drain(..)
+            .map(|tile| (tile.location.clone(), tile))
+            .collect(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+        oxygen,
+        temperature,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{
+            load_board, make_base_game_board, BoardDefinition, BoardSpace, Coordinates,
+            Designation, MarsBoard, MarsBoardBuilder, TileLocation, TileStatus,
+        },
+        card::{
+            CardEffect, CityKind, ImmediateImpact, LocationRestriction, SpecialLocation,
+            BASE_GAME_DECK,
+        },
+        event::GameEvent,
+        game::{GameOperation, PlayAttempt, PlayerId, PlayerStateBuilder},
+        resource::Resource,
+        test_utils::make_game,
+    };
+
+    #[test]
+    fn neighbors_are_clockwise_and_within_bounds() {
+        let board = make_base_game_board();
+        let on_mars = |x, y| TileLocation::OnMars(Coordinates::new(x, y));
+        let center: Vec<_> = board.neighbors(&on_mars(4, -4)).collect();
+        assert_eq!(
+            vec![
+                Coordinates::new(5, -5),
+                Coordinates::new(4, -5),
+                Coordinates::new(3, -4),
+                Coordinates::new(3, -3),
+                Coordinates::new(4, -3),
+                Coordinates::new(5, -4),
+            ],
+            center
+        );
+
+        // (0, 0) is the left-most space of the center row.
+        assert_eq!(3, board.neighbors(&on_mars(0, 0)).count());
+        assert!(board.is_in_bounds(Coordinates::new(0, 0)));
+        assert!(!board.is_in_bounds(Coordinates::new(-1, 0)));
+        assert_eq!(
+            0,
+            board
+                .neighbors(&TileLocation::OffMars(SpecialLocation::GanymedeColony))
+                .count()
+        );
+    }
+
+    #[test]
+    fn neighbor_statuses_match_the_board() {
+        let player_id = PlayerId::from(1);
+        let city = TileLocation::OnMars(Coordinates::new(4, -4));
+        let board = MarsBoardBuilder::new()
+            .with_ocean(Coordinates::new(5, -5))
+            .with_greenery(Coordinates::new(3, -3), player_id)
+            .with_city(city.clone(), CityKind::RegularCity, player_id)
+            .build();
+
+        let statuses: Vec<_> = board.get_neighbor_tile_status(&city).collect();
+        let expected: Vec<_> = board
+            .neighbors(&city)
+            .map(|neighbor| board.get_tile_status(&TileLocation::OnMars(neighbor)))
+            .collect();
+        assert_eq!(expected, statuses);
+        assert!(matches!(statuses[0], TileStatus::Ocean(_)));
+        assert!(matches!(statuses[3], TileStatus::Greenery(_, _)));
+        assert_eq!(
+            4,
+            statuses
+                .iter()
+                .filter(|status| matches!(status, TileStatus::Empty(_)))
+                .count()
+        );
+    }
+
+    #[test]
+    fn ocean_adjacency_bonus_follows_the_board_and_the_player() {
+        let location = TileLocation::OnMars(Coordinates::new(4, -4)).into();
+        let megacredits = |board: &MarsBoard, effects: Vec<CardEffect>| -> usize {
+            let player = PlayerStateBuilder::new(1)
+                .with_effects_override(effects)
+                .build();
+            board
+                .get_placement_bonuses(&player, &location)
+                .iter()
+                .map(|bonus| match bonus {
+                    ImmediateImpact::GainResource(Resource::Megacredits, amount) => *amount,
+                    _ => 0,
+                })
+                .sum()
+        };
+
+        let without_oceans = megacredits(&MarsBoardBuilder::new().build(), vec![]);
+        let mut board = MarsBoardBuilder::new()
+            .with_ocean(Coordinates::new(5, -5))
+            .with_ocean(Coordinates::new(3, -3))
+            .build();
+        assert_eq!(without_oceans + 4, megacredits(&board, vec![]));
+
+        board.ocean_adjacency_megacredits = 3;
+        assert_eq!(without_oceans + 6, megacredits(&board, vec![]));
+        assert_eq!(
+            without_oceans + 12,
+            megacredits(&board, vec![CardEffect::OceanAdjacencyBonusMultiplier(2)])
+        );
+    }
+
+    #[test]
+    fn placement_bonus_cards_are_drawn_into_the_hand_for_free() {
+        let mut game = make_game(vec![PlayerStateBuilder::new(1).build()]);
+        game.draw_deck = BASE_GAME_DECK.clone();
+        let player = game.players[&PlayerId::from(1)].clone();
+
+        // Ascraeus Mons grants a card.
+        let location = TileLocation::OnMars(Coordinates::new(2, 0));
+        let operations = match game.board.can_place_greenery(&player, location.into(), &[]) {
+            PlayAttempt::Playable(operations) => operations,
+            attempt => panic!("unexpected attempt: {:?}", attempt),
+        };
+        assert_eq!(GameOperation::DrawCards(player.player_id, 1), operations[1]);
+
+        let events: Vec<GameEvent> = operations
+            .into_iter()
+            .flat_map(|operation| game.execute_operation(operation))
+            .collect();
+        let player = &game.players[&player.player_id];
+        assert_eq!(1, player.cards_in_hand.len());
+        assert_eq!(0, player.resources[Resource::Megacredits]);
+        assert!(events.contains(&GameEvent::CardsDrawn(
+            player.player_id,
+            player.cards_in_hand.clone()
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "already occupied")]
+    fn builder_rejects_tiles_on_occupied_spaces() {
+        MarsBoardBuilder::new()
+            .with_temperature(MarsBoard::STARTING_TEMPERATURE + MarsBoard::TEMPERATURE_INCREMENT)
+            .with_ocean(Coordinates::new(5, -5))
+            .with_greenery(Coordinates::new(5, -5), PlayerId::from(1));
+    }
+
+    fn tharsis_definition() -> BoardDefinition {
+        let mut spaces: Vec<BoardSpace> = make_base_game_board().spaces.into_values().collect();
+        spaces.sort_by(|a, b| a.location.cmp(&b.location));
+        BoardDefinition {
+            board_name: "Tharsis".into(),
+            spaces,
+            ocean_adjacency_megacredits: MarsBoard::DEFAULT_OCEAN_ADJACENCY_MEGACREDITS,
+        }
+    }
+
+    #[test]
+    fn maps_with_more_rows_have_wider_bounds() {
+        let mut definition = tharsis_definition();
+        definition.board_name = "Vastitas".into();
+
+        // An extra row below the bottom edge of Tharsis, one space shorter than it.
+        definition.spaces.extend(
+            [(0, -5), (1, -6), (2, -7), (3, -8)]
+                .iter()
+                .map(|&coordinates| BoardSpace::new_non_bonus_land_on_mars(coordinates)),
+        );
+        let board = load_board(&serde_json::to_string(&definition).unwrap()).unwrap();
+        assert_eq!(5, board.bounds.max_z);
+        assert_eq!(65 + 2, board.spaces.len());
+
+        let location = TileLocation::OnMars(Coordinates::new(0, -4));
+        let neighbors: Vec<_> = board.neighbors(&location).collect();
+        assert!(neighbors.contains(&Coordinates::new(0, -5)));
+        assert_eq!(4, neighbors.len());
+        assert_eq!(3, make_base_game_board().neighbors(&location).count());
+        assert!(board.is_in_bounds(Coordinates::new(3, -8)));
+
+        // Every space within the bounds must exist.
+        definition.spaces.pop();
+        assert_eq!(
+            Err("the Vastitas board has no space at 3,-8".to_string()),
+            load_board(&serde_json::to_string(&definition).unwrap())
+        );
+    }
+
+    #[test]
+    fn maps_must_have_room_for_every_ocean() {
+        let mut definition = tharsis_definition();
+        for space in definition
+            .spaces
+            .iter_mut()
+            .filter(|space| space.is_reserved_for_ocean())
+            .skip(MarsBoard::MAX_OCEANS - 1)
+        {
+            space.designations = vec![Designation::Land];
+        }
+        assert_eq!(
+            Err("the Tharsis board has 8 ocean spaces, but 9 are needed".to_string()),
+            load_board(&serde_json::to_string(&definition).unwrap()).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn special_tiles_go_anywhere_on_maps_without_their_reserved_areas() {
+        let player = PlayerStateBuilder::new(1).build();
+        let volcanic = [LocationRestriction::AtSpecialLocation(SpecialLocation::VolcanicArea)];
+        let noctis = [LocationRestriction::AtSpecialLocation(SpecialLocation::NoctisCity)];
+        let non_bonus_land = TileLocation::OnMars(Coordinates::new(3, -1)).into();
+        let next_to_it = Coordinates::new(3, 0);
+        let ocean_space = TileLocation::OnMars(Coordinates::new(5, -1)).into();
+
+        let tharsis = make_base_game_board();
+        assert!(!tharsis.placement_satisfies_restrictions(&player, &non_bonus_land, &volcanic));
+        assert!(!tharsis.placement_satisfies_restrictions(&player, &non_bonus_land, &noctis));
+
+        let mut definition = tharsis_definition();
+        definition.board_name = "Hellas-like".into();
+        for space in definition.spaces.iter_mut() {
+            if matches!(space.location, TileLocation::OnMars(_)) {
+                space
+                    .designations
+                    .retain(|designation| !matches!(designation, Designation::Special(_)));
+            }
+        }
+        let mut board = load_board(&serde_json::to_string(&definition).unwrap()).unwrap();
+        assert!(!board.has_special_location(&SpecialLocation::VolcanicArea));
+        assert!(board.has_special_location(&SpecialLocation::PhobosSpaceHaven));
+
+        assert!(board.placement_satisfies_restrictions(&player, &non_bonus_land, &volcanic));
+        assert!(board.placement_satisfies_restrictions(&player, &non_bonus_land, &noctis));
+        assert!(!board.placement_satisfies_restrictions(&player, &ocean_space, &volcanic));
+
+        // Noctis City is placed as a regular city, so it cannot go next to another city.
+        board.cities.insert(
+            TileLocation::OnMars(next_to_it),
+            (CityKind::RegularCity, PlayerId::from(2)),
+        );
+        assert!(board.placement_satisfies_restrictions(&player, &non_bonus_land, &volcanic));
+        assert!(!board.placement_satisfies_restrictions(&player, &non_bonus_land, &noctis));
+    }
+}
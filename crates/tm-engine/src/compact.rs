@@ -0,0 +1,397 @@
+//! Bit-packed canonical encoding of game states.
+//!
+//! Two states have the same `CompactKey` exactly when they agree on everything that
+//! the key encodes, regardless of e.g. the order of cards in a hand. Keys are a few
+//! hundred bytes, a little more with Turmoil, so they can be stored by the million, e.g. as
+//! transposition-table keys or to deduplicate large simulation archives.
+//!
+//! The draw deck, the discard pile, the global event deck, the random number generator,
+//! and the options of the game are not encoded.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{
+    award::Award,
+    board::{MarsBoard, TileStatus},
+    card::{Card, CardKind, CardTag},
+    game::{GameState, PlayerState},
+    registry::{CardId, CardRegistry},
+    resource::CardResource,
+};
+
+const COUNTED_TAGS: [CardTag; 11] = [
+    CardTag::Building,
+    CardTag::Space,
+    CardTag::Power,
+    CardTag::Science,
+    CardTag::Jovian,
+    CardTag::Earth,
+    CardTag::Plant,
+    CardTag::Microbe,
+    CardTag::Animal,
+    CardTag::City,
+    CardTag::Wild,
+];
+
+/// Bumped whenever the encoding changes, since keys of different versions never compare equal.
+pub const COMPACT_KEY_VERSION: u32 = 2;
+
+// Production may be negative, so it is stored offset by half its range.
+const PRODUCTION_OFFSET: isize = 1 << 15;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompactKey(Box<[u64]>);
+
+/// Why a state has no `CompactKey`, e.g. a card the registry doesn't know.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CompactKeyError {
+    UnregisteredCard(String),
+    TooWide { value: u64, width: usize },
+}
+
+impl fmt::Display for CompactKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompactKeyError::UnregisteredCard(name) => {
+                write!(f, "{} is not a registered card", name)
+            }
+            CompactKeyError::TooWide { value, width } => {
+                write!(f, "{} does not fit in {} bits", value, width)
+            }
+        }
+    }
+}
+
+impl CompactKey {
+    // The inverse of `as_words()`, e.g. for keys read back from storage.
+    pub fn from_words(words: Vec<u64>) -> Self {
+        CompactKey(words.into_boxed_slice())
+    }
+
+    pub fn as_words(&self) -> &[u64] {
+        &self.0
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        self.0.len() * 8
+    }
+}
+
+// The first value that could not be packed is kept, and fails the whole key.
+#[derive(Debug, Default)]
+struct BitPacker {
+    words: Vec<u64>,
+    bit_len: usize,
+    error: Option<CompactKeyError>,
+}
+
+impl BitPacker {
+    fn push(&mut self, value: u64, width: usize) {
+        if self.error.is_some() {
+            return;
+        }
+        if width > 64 || (width < 64 && value >> width != 0) {
+            self.error = Some(CompactKeyError::TooWide { value, width });
+            return;
+        }
+
+        let offset = self.bit_len % 64;
+        if offset == 0 {
+            self.words.push(0);
+        }
+        *self.words.last_mut().unwrap() |= value << offset;
+        if offset + width > 64 {
+            self.words.push(value >> (64 - offset));
+        }
+        self.bit_len += width;
+    }
+
+    fn push_usize(&mut self, value: usize, width: usize) {
+        self.push(value as u64, width);
+    }
+
+    fn push_flag(&mut self, flag: bool) {
+        self.push(flag as u64, 1);
+    }
+
+    fn push_card(&mut self, card: &Card) {
+        match CardRegistry::global().id(&card.name) {
+            Some(id) => self.push(id.index() as u64, 16),
+            None if self.error.is_none() => {
+                self.error = Some(CompactKeyError::UnregisteredCard(card.name.clone()));
+            }
+            None => {}
+        }
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.push_usize(bytes.len(), 16);
+        for &byte in bytes {
+            self.push(byte as u64, 8);
+        }
+    }
+
+    // State with too many shapes to give each its own bits, e.g. effects, goes in as JSON.
+    fn push_serialized(&mut self, value: &impl Serialize) {
+        self.push_bytes(serde_json::to_string(value).unwrap().as_bytes());
+    }
+
+    // In sorted order, for values whose order doesn't matter.
+    fn push_serialized_set<'a, T: Serialize + 'a>(&mut self, values: impl Iterator<Item = &'a T>) {
+        let mut serialized: Vec<String> = values
+            .map(|value| serde_json::to_string(value).unwrap())
+            .collect();
+        serialized.sort();
+
+        self.push_usize(serialized.len(), 8);
+        for value in serialized {
+            self.push_bytes(value.as_bytes());
+        }
+    }
+
+    fn finish(self) -> Result<CompactKey, CompactKeyError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(CompactKey(self.words.into_boxed_slice())),
+        }
+    }
+}
+
+impl GameState {
+    pub fn compact_key(&self) -> Result<CompactKey, CompactKeyError> {
+        let mut packer = BitPacker::default();
+
+        packer.push_usize(self.generation, 8);
+        packer.push(self.phase as u64, 3);
+        packer.push_usize(self.turn_player.map_or(0, usize::from), 8);
+        packer.push_usize(self.board.oxygen, 5);
+        packer.push_usize(
+            (self.board.temperature - MarsBoard::STARTING_TEMPERATURE) as usize,
+            6,
+        );
+        pack_tiles(&mut packer, &self.board);
+        for award in Award::ALL.iter() {
+            packer.push_flag(self.funded_awards.iter().any(|(funded, _)| funded == award));
+        }
+
+        packer.push_usize(self.players.len(), 4);
+        for player in self.players.values() {
+            pack_player(&mut packer, player);
+            packer.push_flag(self.passed_players.contains(&player.player_id));
+        }
+
+        // The events still to be drawn are left out, like the draw deck.
+        packer.push_flag(self.turmoil.is_some());
+        if let Some(turmoil) = &self.turmoil {
+            let mut turmoil = turmoil.clone();
+            turmoil.event_deck.clear();
+            packer.push_serialized(&turmoil);
+        }
+        packer.push_serialized(&self.scheduled_effects);
+
+        packer.finish()
+    }
+}
+
+// One occupancy bitmap per kind of tile, over all spaces in location order,
+// followed by the owner and kind of each occupied space in the same order.
+fn pack_tiles(packer: &mut BitPacker, board: &MarsBoard) {
+    let mut locations: Vec<_> = board.spaces.keys().collect();
+    locations.sort();
+    let statuses: Vec<_> = locations
+        .into_iter()
+        .map(|location| board.get_tile_status(location))
+        .collect();
+
+    packer.push_usize(statuses.len(), 8);
+    for status in &statuses {
+        packer.push_flag(matches!(status, TileStatus::Ocean(_)));
+    }
+    for status in &statuses {
+        packer.push_flag(matches!(status, TileStatus::Greenery(..)));
+    }
+    for status in &statuses {
+        packer.push_flag(matches!(status, TileStatus::City(..)));
+    }
+    for status in &statuses {
+        packer.push_flag(matches!(status, TileStatus::SpecialTile(..)));
+    }
+
+    for status in &statuses {
+        match status {
+            TileStatus::Empty(_) | TileStatus::Ocean(_) => {}
+            TileStatus::Greenery(_, owner) => packer.push_usize((*owner).into(), 8),
+            TileStatus::City(_, city_kind, owner) => {
+                packer.push_usize((*owner).into(), 8);
+                packer.push(*city_kind as u64, 4);
+            }
+            TileStatus::SpecialTile(_, special_tile, owner) => {
+                packer.push_usize((*owner).into(), 8);
+                packer.push(*special_tile as u64, 4);
+            }
+        }
+    }
+}
+
+fn pack_player(packer: &mut BitPacker, player: &PlayerState) {
+    packer.push_usize(player.player_id.into(), 8);
+    packer.push_usize(player.terraform_rating, 8);
+    packer.push_usize(player.steel_value, 4);
+    packer.push_usize(player.titanium_value, 4);
+    match &player.corporation {
+        Some(corporation) => {
+            packer.push_flag(true);
+            packer.push_bytes(corporation.name.as_bytes());
+        }
+        None => packer.push_flag(false),
+    }
+
+    for (_, &amount) in player.resources.iter() {
+        packer.push_usize(amount, 16);
+    }
+    for (_, &production) in player.production.iter() {
+        packer.push_usize((production + PRODUCTION_OFFSET) as usize, 16);
+    }
+
+    for &tag in COUNTED_TAGS.iter() {
+        let count = player
+            .played_cards
+            .iter()
+            .filter(|card| card.kind != CardKind::Event)
+            .map(|card| card.tags.count(tag))
+            .sum();
+        packer.push_usize(count, 7);
+    }
+
+    pack_card_set(packer, player.cards_in_hand.iter());
+    pack_card_set(packer, player.played_cards.iter());
+    pack_card_set(packer, player.tapped_active_cards.iter());
+
+    packer.push_usize(player.card_resources.len(), 8);
+    for ((card, card_resource), count) in &player.card_resources {
+        packer.push_card(card);
+        packer.push(card_resource_index(*card_resource), 3);
+        packer.push_usize(*count, 16);
+    }
+
+    packer.push_serialized_set(player.effects.iter());
+    packer.push_serialized_set(player.next_card_this_generation_effects.iter());
+}
+
+// Cards are sorted by id, so that the order in which they were e.g. drawn does not matter.
+fn pack_card_set<'a>(packer: &mut BitPacker, cards: impl Iterator<Item = &'a Card>) {
+    let mut ids: Vec<CardId> = vec![];
+    for card in cards {
+        match CardRegistry::global().id(&card.name) {
+            Some(id) => ids.push(id),
+            None => packer.push_card(card),
+        }
+    }
+    ids.sort();
+
+    packer.push_usize(ids.len(), 8);
+    for id in ids {
+        packer.push(id.index() as u64, 16);
+    }
+}
+
+fn card_resource_index(card_resource: CardResource) -> u64 {
+    match card_resource {
+        CardResource::Microbe => 0,
+        CardResource::Plant => 1,
+        CardResource::Animal => 2,
+        CardResource::Science => 3,
+        CardResource::Fighter => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::CardEffect,
+        compact::{BitPacker, CompactKeyError},
+        corporation::CORPORATIONS,
+        game::GamePhase,
+        position::import_position,
+        save::save_game,
+    };
+
+    #[test]
+    fn values_are_packed_across_word_boundaries() {
+        let mut packer = BitPacker::default();
+        packer.push(0b101, 3);
+        packer.push(u64::MAX >> 4, 60);
+        packer.push(0b11, 2);
+
+        let key = packer.finish().unwrap();
+        assert_eq!(
+            &[0b101 | (u64::MAX >> 4) << 3 | 1 << 63, 0b1],
+            key.as_words()
+        );
+    }
+
+    #[test]
+    fn equivalent_states_have_equal_keys() {
+        let game = import_position(
+            "oxygen 3\nplayer p1\nhand Solar Power; Comet\nresources 5 0 0 0 0 0\nplayer p2",
+        )
+        .unwrap();
+        let reordered = import_position(
+            "oxygen 3\nplayer p1\nhand Comet; Solar Power\nresources 5 0 0 0 0 0\nplayer p2",
+        )
+        .unwrap();
+        assert_eq!(game.compact_key(), reordered.compact_key());
+
+        let mut richer = game.clone();
+        richer.players.values_mut().next().unwrap().terraform_rating += 1;
+        assert_ne!(game.compact_key(), richer.compact_key());
+
+        let mut warmer = game.clone();
+        warmer.board.temperature += 2;
+        assert_ne!(game.compact_key(), warmer.compact_key());
+
+        // Far smaller than a save file.
+        let key = game.compact_key().unwrap();
+        assert!(key.size_in_bytes() * 20 < save_game(&game).len());
+    }
+
+    #[test]
+    fn corporations_effects_and_phase_are_part_of_the_key() {
+        let game = import_position("player p1\nresources 5 0 0 0 0 0\nplayer p2").unwrap();
+        let key = game.compact_key().unwrap();
+
+        let mut incorporated = game.clone();
+        incorporated.players.values_mut().next().unwrap().corporation =
+            Some(CORPORATIONS[0].clone());
+        assert_ne!(key, incorporated.compact_key().unwrap());
+
+        let mut discounted = game.clone();
+        let player = discounted.players.values_mut().next().unwrap();
+        player.next_card_this_generation_effects.push(CardEffect::AnyCardDiscount(8));
+        assert_ne!(key, discounted.compact_key().unwrap());
+
+        let mut researching = game.clone();
+        researching.phase = GamePhase::Research;
+        assert_ne!(key, researching.compact_key().unwrap());
+    }
+
+    #[test]
+    fn states_that_cannot_be_packed_have_no_key() {
+        let mut game = import_position("player p1\nhand Comet\nplayer p2").unwrap();
+        let player = game.players.values_mut().next().unwrap();
+        player.cards_in_hand[0].name = "Homebrew Comet".to_string();
+        assert_eq!(
+            Err(CompactKeyError::UnregisteredCard("Homebrew Comet".to_string())),
+            game.compact_key()
+        );
+
+        let mut packer = BitPacker::default();
+        packer.push(0b100, 2);
+        assert_eq!(
+            Err(CompactKeyError::TooWide { value: 0b100, width: 2 }),
+            packer.finish()
+        );
+    }
+}
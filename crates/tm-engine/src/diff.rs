@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::{TileLocation, TileStatus},
+    card::Card,
+    card_ref,
+    game::{GameOperation, GameState, IllegalOperation, PlayerId, PlayerState},
+    resource::{CardResource, Resource, ResourceMap},
+};
+
+/// What changed between two states of a game, e.g. what executing an operation would do.
+///
+/// Like `GameView`, it leaves out hidden information: cards entering or leaving a hand
+/// are only counted, so that previewing a draw does not reveal the top of the deck.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub players: BTreeMap<PlayerId, PlayerDiff>, // only players that changed
+    pub placed_tiles: Vec<TileStatus>,           // in location order
+    pub temperature: isize,
+    pub oxygen: isize,
+    pub draw_deck_size: isize,
+    pub discard_pile_size: isize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerDiff {
+    pub resources: BTreeMap<Resource, isize>,
+    pub production: BTreeMap<Resource, isize>,
+    pub terraform_rating: isize,
+    pub hand_size: isize,
+    #[serde(with = "card_ref::cards")]
+    pub played_cards: Vec<Card>, // newly played
+    pub card_resources: Vec<CardResourceChange>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardResourceChange {
+    #[serde(with = "card_ref::card")]
+    pub card: Card,
+    pub resource: CardResource,
+    pub change: isize,
+}
+
+impl StateDiff {
+    pub fn between(before: &GameState, after: &GameState) -> Self {
+        let players = after
+            .players
+            .iter()
+            .filter_map(|(player_id, after_player)| {
+                let diff = PlayerDiff::between(before.players.get(player_id)?, after_player);
+                if diff == PlayerDiff::default() {
+                    None
+                } else {
+                    Some((*player_id, diff))
+                }
+            })
+            .collect();
+
+        let (before_board, after_board) = (&before.board, &after.board);
+        let new_locations: BTreeSet<TileLocation> = after_board
+            .cities
+            .keys()
+            .filter(|location| !before_board.cities.contains_key(location))
+            .cloned()
+            .chain(
+                after_board
+                    .greeneries
+                    .keys()
+                    .filter(|coordinates| !before_board.greeneries.contains_key(coordinates))
+                    .chain(after_board.special_tiles.keys().filter(|coordinates| {
+                        !before_board.special_tiles.contains_key(coordinates)
+                    }))
+                    .chain(
+                        after_board
+                            .oceans
+                            .iter()
+                            .filter(|coordinates| !before_board.oceans.contains(coordinates)),
+                    )
+                    .map(|coordinates| TileLocation::OnMars(*coordinates)),
+            )
+            .collect();
+        let placed_tiles = new_locations
+            .iter()
+            .map(|location| after_board.get_tile_status(location))
+            .collect();
+
+        Self {
+            players,
+            placed_tiles,
+            temperature: after_board.temperature - before_board.temperature,
+            oxygen: after_board.oxygen as isize - before_board.oxygen as isize,
+            draw_deck_size: after.draw_deck.len() as isize - before.draw_deck.len() as isize,
+            discard_pile_size: after.discard_pile.len() as isize
+                - before.discard_pile.len() as isize,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl PlayerDiff {
+    fn between(before: &PlayerState, after: &PlayerState) -> Self {
+        let changes = |before: &ResourceMap<isize>, after: &ResourceMap<isize>| {
+            Resource::ALL
+                .iter()
+                .map(|resource| (*resource, after[resource] - before[resource]))
+                .filter(|(_, change)| *change != 0)
+                .collect()
+        };
+        let as_signed = |resources: &ResourceMap<usize>| {
+            let mut signed = ResourceMap::default();
+            for (resource, amount) in resources.iter() {
+                signed[resource] = *amount as isize;
+            }
+            signed
+        };
+
+        // Played cards are only ever added to the end of the list.
+        let played_cards = after
+            .played_cards
+            .get(before.played_cards.len()..)
+            .unwrap_or_default()
+            .to_vec();
+
+        let card_resources = before
+            .card_resources
+            .keys()
+            .chain(after.card_resources.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|key| {
+                let amount = |player: &PlayerState| {
+                    player.card_resources.get(key).copied().unwrap_or_default() as isize
+                };
+                let change = amount(after) - amount(before);
+                if change == 0 {
+                    return None;
+                }
+                let (card, resource) = key;
+                Some(CardResourceChange {
+                    card: card.clone(),
+                    resource: *resource,
+                    change,
+                })
+            })
+            .collect();
+
+        Self {
+            resources: changes(&as_signed(&before.resources), &as_signed(&after.resources)),
+            production: changes(&before.production, &after.production),
+            terraform_rating: after.terraform_rating as isize - before.terraform_rating as isize,
+            hand_size: after.cards_in_hand.len() as isize - before.cards_in_hand.len() as isize,
+            played_cards,
+            card_resources,
+        }
+    }
+}
+
+impl GameState {
+    /// What executing the operation would change, without changing the game itself.
+    pub fn preview(&self, operation: &GameOperation) -> Result<StateDiff, IllegalOperation> {
+        self.check_operation(operation)?;
+
+        let mut scratch = self.clone();
+        scratch.execute_operation(operation.clone());
+        Ok(StateDiff::between(self, &scratch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::{Coordinates, TileLocation, TileStatus},
+        diff::{PlayerDiff, StateDiff},
+        game::{GameOperation, IllegalOperation, PlayerId},
+        position::import_position,
+        resource::Resource,
+    };
+
+    #[test]
+    fn previews_describe_the_operation_without_executing_it() {
+        let game = import_position(
+            "player p1\n\
+             resources 5 0 0 0 0 0\n\
+             player p2",
+        )
+        .unwrap();
+        let unchanged = game.clone();
+        let p1 = PlayerId::from(1);
+
+        let draw = GameOperation::DrawCards(p1, 2);
+        let expected = StateDiff {
+            players: btreemap! {
+                p1 => PlayerDiff {
+                    hand_size: 2,
+                    ..PlayerDiff::default()
+                },
+            },
+            draw_deck_size: -2,
+            ..StateDiff::default()
+        };
+        assert_eq!(Ok(expected), game.preview(&draw));
+
+        let greenery = GameOperation::PlaceGreenery(p1, Coordinates::new(1, 0));
+        let diff = game.preview(&greenery).unwrap();
+        assert_eq!(
+            vec![TileStatus::Greenery(
+                TileLocation::OnMars(Coordinates::new(1, 0)),
+                p1
+            )],
+            diff.placed_tiles
+        );
+        assert!(diff.players.is_empty());
+
+        let payment = GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -6 });
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(
+                Resource::Megacredits
+            )),
+            game.preview(&payment)
+        );
+
+        // Previewing leaves the game as it was.
+        assert_eq!(unchanged, game);
+        assert!(StateDiff::between(&game, &game).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+use std::{any::Any, collections::BTreeMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::Award,
+    board::{Coordinates, TileLocation, TileStatus},
+    card::{Card, CardEffect, CityKind, SpecialTile},
+    card_ref,
+    corporation::Corporation,
+    game::{GameOperation, GameState, IllegalOperation, PlayerId},
+    milestone::Milestone,
+    options::Handicap,
+    resource::{CardResource, Resource},
+    schedule::ScheduledEffect,
+    turmoil::{Delegate, GlobalEvent, Party},
+};
+
+/// Something that happened in a game, as a result of executing a `GameOperation`.
+///
+/// Unlike operations, events carry the outcome of any hidden information that was resolved,
+/// e.g. which cards were drawn. Events describing private information (like drawn cards)
+/// are published to all observers, so observers are responsible for what they reveal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    ResourcesChanged(PlayerId, BTreeMap<Resource, isize>),
+    ProductionChanged(PlayerId, BTreeMap<Resource, isize>),
+    CardResourceChanged(
+        PlayerId,
+        #[serde(with = "card_ref::card")] Card,
+        CardResource,
+        isize,
+    ),
+
+    CardsDrawn(PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    CardsDiscarded(PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    // (from, to, cards)
+    CardsPassed(PlayerId, PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    // drawn from the deck, shown, and discarded
+    CardRevealed(#[serde(with = "card_ref::card")] Card),
+    DiscardPileReshuffled,
+    CardPlayed(PlayerId, #[serde(with = "card_ref::card")] Card),
+    // in the research draft, the pack in front of the player
+    DraftPackDealt(PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    CardDrafted(PlayerId, #[serde(with = "card_ref::card")] Card), // into the player's hand
+    // (from, to, cards): what is left of a pack once every player has picked from theirs
+    DraftPackPassed(PlayerId, PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+
+    CityPlaced(PlayerId, CityKind, TileLocation),
+    GreeneryPlaced(PlayerId, Coordinates),
+    SpecialTilePlaced(PlayerId, SpecialTile, Coordinates),
+    OceanPlaced(Coordinates),
+    NeutralCityPlaced(Coordinates), // in the solo game, owned by `PlayerId::NEUTRAL`
+    NeutralGreeneryPlaced(Coordinates),
+    TileRemoved(TileStatus), // the tile as it was before it was removed
+
+    TemperatureRaised(isize), // new temperature
+    OxygenRaised(usize),      // new oxygen level
+    TerraformRatingRaised(PlayerId, usize),
+    TerraformRatingLowered(PlayerId, usize),
+
+    EffectAdded(PlayerId, CardEffect),
+    // followed by the events of the effect's impacts on the player
+    EffectTriggered(PlayerId, CardEffect),
+    CardActionUsed(PlayerId, #[serde(with = "card_ref::card")] Card),
+    CardActionsReset,
+
+    // the player qualifies for an unclaimed milestone for the first time
+    MilestoneEligible(PlayerId, Milestone),
+    AwardFunded(PlayerId, Award),
+
+    DelegatePlaced(Delegate, Party),
+    GlobalEventResolved(GlobalEvent),
+    NewGovernment(Party, Delegate), // the new ruling party and its chairman
+
+    EffectScheduled(ScheduledEffect),
+    // when it came due, one of the effect's operations was no longer legal
+    ScheduledEffectSkipped(IllegalOperation),
+
+    // at setup, the corporations the player is to choose from
+    CorporationsDealt(PlayerId, Vec<Corporation>),
+    CorporationPlayed(PlayerId, Corporation),
+
+    // at setup, followed by the events of what the handicap grants
+    HandicapApplied(PlayerId, Handicap),
+
+    // in simultaneous turn order, an action of the player's plan was no longer legal
+    PlannedActionSkipped(PlayerId, IllegalOperation),
+}
+
+pub trait GameObserver: Any {
+    // Called after the event has been applied to the game state.
+    fn on_event(&mut self, game: &GameState, event: &GameEvent);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverHandle(usize);
+
+#[derive(Default)]
+pub struct EventBus {
+    observers: Vec<Box<dyn GameObserver>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe<T: GameObserver>(&mut self, observer: T) -> ObserverHandle {
+        self.observers.push(Box::new(observer));
+        ObserverHandle(self.observers.len() - 1)
+    }
+
+    pub fn observer<T: GameObserver>(&self, handle: ObserverHandle) -> Option<&T> {
+        let observer: &dyn Any = self.observers.get(handle.0)?.as_ref();
+        observer.downcast_ref()
+    }
+
+    pub fn observer_mut<T: GameObserver>(&mut self, handle: ObserverHandle) -> Option<&mut T> {
+        let observer: &mut dyn Any = self.observers.get_mut(handle.0)?.as_mut();
+        observer.downcast_mut()
+    }
+
+    pub fn publish(&mut self, game: &GameState, event: &GameEvent) {
+        for observer in self.observers.iter_mut() {
+            observer.on_event(game, event);
+        }
+    }
+
+    /// Execute the operation, then publish the resulting events to all observers.
+    pub fn execute(&mut self, game: &mut GameState, operation: GameOperation) -> Vec<GameEvent> {
+        let events = game.execute_operation(operation);
+        for event in &events {
+            self.publish(game, event);
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::BASE_GAME_DECK,
+        event::{EventBus, GameEvent, GameObserver},
+        game::{GameOperation, GameState, PlayerStateBuilder},
+        test_utils::make_game,
+    };
+
+    #[derive(Default)]
+    struct EventCounter {
+        events: usize,
+    }
+
+    impl GameObserver for EventCounter {
+        fn on_event(&mut self, _game: &GameState, _event: &GameEvent) {
+            self.events += 1;
+        }
+    }
+
+    #[test]
+    fn observers_receive_the_events_of_executed_operations() {
+        let player = PlayerStateBuilder::new(1).build();
+        let player_id = player.player_id;
+        let mut game = make_game(vec![player]);
+        game.draw_deck = BASE_GAME_DECK.clone();
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(EventCounter::default());
+
+        let events = bus.execute(&mut game, GameOperation::DrawCards(player_id, 2));
+        match &events[..] {
+            [GameEvent::CardsDrawn(id, cards)] => {
+                assert_eq!(player_id, *id);
+                assert_eq!(&game.players[&player_id].cards_in_hand, cards);
+            }
+            _ => panic!("unexpected events: {:?}", events),
+        }
+
+        bus.execute(&mut game, GameOperation::RaiseOxygen);
+        assert_eq!(2, bus.observer::<EventCounter>(handle).unwrap().events);
+    }
+
+    #[test]
+    fn unknown_card_names_are_rejected() {
+        let event = GameEvent::CardsDrawn(1.into(), vec![]);
+        let json = serde_json::to_string(&event).unwrap();
+        let tampered = json.replace("[]", r#"["Not A Real Card"]"#);
+
+        let error = serde_json::from_str::<GameEvent>(&tampered).unwrap_err();
+        assert!(error.to_string().contains("Not A Real Card"));
+    }
+}
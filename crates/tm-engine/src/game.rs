@@ -0,0 +1,3108 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
+};
+
+use maplit::btreemap;
+use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::{Award, AwardStandings, AWARD_FUNDING_COSTS},
+    board::{Coordinates, EmptyLocation, MarsBoard, TileLocation, TileStatus},
+    card::{
+        Card, CardAction, CardEffect, CardKind, CardRequirement, CardTag, CityKind,
+        ImmediateImpact, LocationRestriction, SpecialTile, VictoryPointValue,
+    },
+    card_ref,
+    corporation::Corporation,
+    event::GameEvent,
+    milestone::MilestoneNotices,
+    options::{GameOptions, ProductionStep},
+    payment::{PaymentError, PaymentPlan},
+    projection::{estimate_game_generations_left, ProjectionAssumptions},
+    resource::{CardResource, PaymentCost, Resource, ResourceMap},
+    rng::GameRng,
+    rules::PlayerCountRules,
+    schedule::{EffectTiming, ScheduledEffect},
+    scoring::{ScoreBreakdown, TilePoints},
+    timeline::ParameterTimeline,
+    turmoil::{Delegate, Party, Turmoil},
+};
+
+pub const CARD_PURCHASE_COST: usize = 3;
+pub const RESEARCH_PHASE_OFFER_SIZE: usize = 4;
+pub const POWER_PLANT_COST: usize = 11;
+pub const ASTEROID_COST: usize = 14;
+pub const AQUIFER_COST: usize = 18;
+pub const GREENERY_COST: usize = 23;
+pub const CITY_COST: usize = 25;
+pub const PLANTS_PER_GREENERY: usize = 8;
+pub const HEAT_PER_TEMPERATURE_STEP: usize = 8;
+const DEFAULT_STARTING_TERRAFORM_RATING: usize = 20;
+pub(crate) const DEFAULT_SOLO_STARTING_TERRAFORM_RATING: usize = 14;
+const DEFAULT_STEEL_VALUE: usize = 2;
+const DEFAULT_TITANIUM_VALUE: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PlayerId(usize);
+
+impl From<usize> for PlayerId {
+    #[inline]
+    fn from(player_id: usize) -> Self {
+        Self(player_id)
+    }
+}
+
+impl From<PlayerId> for usize {
+    #[inline]
+    fn from(player_id: PlayerId) -> Self {
+        player_id.0
+    }
+}
+
+impl PlayerId {
+    /// The owner of the solo game's neutral tiles, which belong to no player.
+    pub const NEUTRAL: PlayerId = PlayerId(0);
+
+    pub fn is_neutral(self) -> bool {
+        self == PlayerId::NEUTRAL
+    }
+}
+
+impl fmt::Display for PlayerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_neutral() {
+            write!(f, "Neutral")
+        } else {
+            write!(f, "Player {}", self.0)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerState {
+    // primary data
+    pub player_id: PlayerId,
+    pub resources: ResourceMap<usize>,
+    pub production: ResourceMap<isize>,
+    #[serde(with = "card_ref::cards")]
+    pub played_cards: Vec<Card>,
+    #[serde(with = "card_ref::card_resources")]
+    pub card_resources: BTreeMap<(Card, CardResource), usize>,
+    #[serde(with = "card_ref::card_set")]
+    pub tapped_active_cards: HashSet<Card>,
+    #[serde(with = "card_ref::cards")]
+    pub cards_in_hand: Vec<Card>,
+    pub terraform_rating: usize,
+    pub steel_value: usize,
+    pub titanium_value: usize,
+    pub next_card_this_generation_effects: Vec<CardEffect>,
+    // chosen at setup; its tags count along with those of the played cards
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corporation: Option<Corporation>,
+
+    // indexes of primary data
+    pub effects: Vec<CardEffect>,
+}
+
+pub struct PlayerStateBuilder {
+    pub player_id: PlayerId,
+    pub resources: Option<ResourceMap<usize>>,
+    pub production: Option<ResourceMap<isize>>,
+    pub played_cards: Option<Vec<Card>>,
+    pub card_resources: BTreeMap<(Card, CardResource), usize>,
+    pub tapped_active_cards: Option<HashSet<Card>>,
+    pub cards_in_hand: Option<Vec<Card>>,
+    pub terraform_rating: usize,
+    pub next_card_this_generation_effects: Option<Vec<CardEffect>>,
+
+    // replaces the effects otherwise derived from the played cards
+    pub effects_override: Option<Vec<CardEffect>>,
+}
+
+impl PlayerStateBuilder {
+    pub fn new(player_id: usize) -> PlayerStateBuilder {
+        PlayerStateBuilder {
+            player_id: PlayerId(player_id),
+            resources: None,
+            production: None,
+            played_cards: None,
+            card_resources: btreemap! {},
+            tapped_active_cards: None,
+            cards_in_hand: None,
+            terraform_rating: DEFAULT_STARTING_TERRAFORM_RATING,
+            next_card_this_generation_effects: None,
+            effects_override: None,
+        }
+    }
+
+    pub fn with_played_cards(mut self, played_cards: Vec<Card>) -> PlayerStateBuilder {
+        self.played_cards = Some(played_cards);
+        self
+    }
+
+    pub fn with_cards_in_hand(mut self, cards_in_hand: Vec<Card>) -> PlayerStateBuilder {
+        assert!(self.cards_in_hand.is_none());
+
+        self.cards_in_hand = Some(cards_in_hand);
+        self
+    }
+
+    pub fn with_card_resources(
+        mut self,
+        card_resources: BTreeMap<(Card, CardResource), usize>,
+    ) -> PlayerStateBuilder {
+        assert!(self.card_resources.is_empty());
+
+        self.card_resources = card_resources;
+        self
+    }
+
+    pub fn with_terraform_rating(mut self, terraform_rating: usize) -> PlayerStateBuilder {
+        self.terraform_rating = terraform_rating;
+        self
+    }
+
+    pub fn with_tapped_cards(mut self, tapped_cards: Vec<Card>) -> PlayerStateBuilder {
+        assert!(self.tapped_active_cards.is_none());
+
+        self.tapped_active_cards = Some(tapped_cards.into_iter().collect());
+        self
+    }
+
+    /// Use the given effects instead of those of the played cards, e.g. to test an effect
+    /// without finding a card that has it. Steel and titanium values follow the effects.
+    pub fn with_effects_override(mut self, effects: Vec<CardEffect>) -> PlayerStateBuilder {
+        assert!(self.effects_override.is_none());
+
+        self.effects_override = Some(effects);
+        self
+    }
+
+    pub fn with_resources(
+        mut self,
+        megacredits: usize,
+        steel: usize,
+        titanium: usize,
+        plants: usize,
+        energy: usize,
+        heat: usize,
+    ) -> PlayerStateBuilder {
+        assert!(self.resources.is_none());
+
+        let resources = ResourceMap::new([megacredits, steel, titanium, plants, energy, heat]);
+
+        self.resources = Some(resources);
+        self
+    }
+
+    pub fn with_production(
+        mut self,
+        megacredits: isize,
+        steel: isize,
+        titanium: isize,
+        plants: isize,
+        energy: isize,
+        heat: isize,
+    ) -> PlayerStateBuilder {
+        assert!(self.production.is_none());
+
+        assert!(steel >= 0);
+        assert!(titanium >= 0);
+        assert!(plants >= 0);
+        assert!(energy >= 0);
+        assert!(heat >= 0);
+
+        let production = ResourceMap::new([megacredits, steel, titanium, plants, energy, heat]);
+
+        self.production = Some(production);
+        self
+    }
+
+    pub fn build(self) -> PlayerState {
+        let card_resources = self.card_resources;
+
+        let resources = self.resources.unwrap_or_default();
+        let production = self.production.unwrap_or_default();
+
+        let played_cards = &self.played_cards;
+        let effects: Vec<_> = self.effects_override.unwrap_or_else(|| {
+            played_cards
+                .as_ref()
+                .map(|cards| cards.iter().flat_map(|c| c.effects.clone()).collect())
+                .unwrap_or_default()
+        });
+
+        let mut steel_value = DEFAULT_STEEL_VALUE;
+        let mut titanium_value = DEFAULT_TITANIUM_VALUE;
+        for effect in &effects {
+            if let &CardEffect::IncreasedMetalsValue(increase) = effect {
+                steel_value += increase;
+                titanium_value += increase;
+            }
+        }
+
+        PlayerState {
+            player_id: self.player_id,
+            resources,
+            production,
+            played_cards: self.played_cards.unwrap_or_default(),
+            card_resources,
+            tapped_active_cards: self.tapped_active_cards.unwrap_or_default(),
+            cards_in_hand: self.cards_in_hand.unwrap_or_default(),
+            terraform_rating: self.terraform_rating,
+            steel_value,
+            titanium_value,
+            next_card_this_generation_effects: self.next_card_this_generation_effects.unwrap_or_default(),
+            corporation: None,
+            effects,
+        }
+    }
+}
+
+pub(crate) trait ActiveTags {
+    fn active_tag_count(&self, tag_kind: CardTag) -> usize;
+    fn active_tag_count_for_action(&self, tag_kind: CardTag) -> usize;
+    fn event_count(&self) -> usize;
+    fn get_non_event_tags(&self) -> Box<dyn Iterator<Item = CardTag> + '_>;
+}
+
+impl ActiveTags for Vec<Card> {
+    fn event_count(&self) -> usize {
+        self.iter()
+            .filter(|card| card.kind == CardKind::Event)
+            .count()
+    }
+
+    fn active_tag_count(&self, tag_kind: CardTag) -> usize {
+        assert_ne!(tag_kind, CardTag::Event);
+        self.iter()
+            .filter(|card| card.kind != CardKind::Event)
+            .map(|card| card.tags.count(tag_kind))
+            .sum()
+    }
+
+    fn active_tag_count_for_action(&self, tag_kind: CardTag) -> usize {
+        // Wild tags only count for the purposes of performing actions.
+        assert_ne!(tag_kind, CardTag::Event);
+        self.iter()
+            .filter(|card| card.kind != CardKind::Event)
+            .map(|card| card.tags.count(tag_kind) + card.tags.count(CardTag::Wild))
+            .sum()
+    }
+
+    fn get_non_event_tags(&self) -> Box<dyn Iterator<Item = CardTag> + '_> {
+        Box::new(self.iter().flat_map(|card| match card.kind {
+            CardKind::Event => [].iter().copied(),
+            _ => card.tags.iter().copied(),
+        }))
+    }
+}
+
+impl PlayerState {
+    pub fn purchase_cards(&mut self, cards: &Vec<&Card>) -> Option<()> {
+        let megacredits_balance = self.resources[&Resource::Megacredits];
+        let megacredits_cost = cards.len() * CARD_PURCHASE_COST;
+
+        if megacredits_balance < megacredits_cost {
+            None
+        } else {
+            self.cards_in_hand.extend(cards.iter().copied().cloned());
+            self.resources[Resource::Megacredits] = megacredits_balance - megacredits_cost;
+            Some(())
+        }
+    }
+
+    pub fn get_total_victory_points(&self, board: &MarsBoard) -> isize {
+        self.score_breakdown(board).total()
+    }
+
+    pub fn score_breakdown(&self, board: &MarsBoard) -> ScoreBreakdown {
+        let tile_points = TilePoints::compute(board);
+        tile_points.breakdown(self, self.card_victory_points(board))
+    }
+
+    pub fn card_victory_points(&self, board: &MarsBoard) -> isize {
+        self.played_cards
+            .iter()
+            .map(|c| match c.points {
+                Some(VictoryPointValue::Immediate(x)) => x,
+                Some(VictoryPointValue::PerTag(vp, count, tag)) => {
+                    assert!(tag != CardTag::Event);
+
+                    let tag_count = self.played_cards.active_tag_count(tag);
+                    ((tag_count / count) * vp) as isize
+                }
+                Some(VictoryPointValue::PerCardResource(vp, count, cr)) => {
+                    let resources_present = self
+                        .card_resources
+                        .get(&(c.clone(), cr))
+                        .copied()
+                        .unwrap_or_default();
+
+                    ((resources_present / count) * vp) as isize
+                }
+                Some(VictoryPointValue::FixedPointsIfAnyCardResourcePresent(count, cr)) => {
+                    let resources_present = self
+                        .card_resources
+                        .get(&(c.clone(), cr))
+                        .copied()
+                        .unwrap_or_default();
+                    if resources_present > 0 {
+                        count as isize
+                    } else {
+                        0
+                    }
+                }
+                Some(VictoryPointValue::PerNCities(n_cities)) => {
+                    (board.cities.len() / n_cities) as isize
+                }
+                None => 0,
+            })
+            .sum()
+    }
+
+    /// By how many steps the player may miss the oxygen, temperature, and ocean requirements
+    /// of the cards they play, from their effects and those of the card played just before.
+    pub fn global_requirements_tolerance(&self) -> usize {
+        self.effects
+            .iter()
+            .chain(self.next_card_this_generation_effects.iter())
+            .map(|effect| match effect {
+                CardEffect::GlobalRequirementsTolerance(steps) => *steps,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub fn can_play_card(&self, board: &MarsBoard, card: &Card) -> Option<PaymentCost> {
+        if !self.meets_card_requirements(board, card) {
+            return None;
+        }
+
+        // Helion's heat counts towards the cost along with steel and titanium.
+        let cost = self.card_cost(card);
+        self.default_payment_plan(cost).map(|_| cost)
+    }
+
+    /// Whether the board and the player's own tags and production allow them to play the card,
+    /// whatever it costs.
+    pub fn meets_card_requirements(&self, board: &MarsBoard, card: &Card) -> bool {
+        let tolerance = self.global_requirements_tolerance();
+        let temperature_tolerance = tolerance as isize * MarsBoard::TEMPERATURE_INCREMENT;
+        let fails_requirements = card
+            .requirements
+            .iter()
+            .any(|requirement| !match requirement {
+                CardRequirement::MaxOxygen(max_oxygen) => board.oxygen <= max_oxygen + tolerance,
+                CardRequirement::MinOxygen(min_oxygen) => board.oxygen + tolerance >= *min_oxygen,
+                CardRequirement::MaxTemperature(max_temp) => {
+                    board.temperature <= max_temp + temperature_tolerance
+                }
+                CardRequirement::MinTemperature(min_temp) => {
+                    board.temperature + temperature_tolerance >= *min_temp
+                }
+                CardRequirement::MaxOceans(max_oceans) => {
+                    board.oceans.len() <= max_oceans + tolerance
+                }
+                CardRequirement::MinOceans(min_oceans) => {
+                    board.oceans.len() + tolerance >= *min_oceans
+                }
+                CardRequirement::MinOwnedGreeneries(min_greeneries) => {
+                    let owned_greeneries = board
+                        .greeneries
+                        .values()
+                        .filter(|player_id| self.player_id == **player_id)
+                        .count();
+
+                    owned_greeneries >= *min_greeneries
+                }
+                CardRequirement::MinTags(tag, count) => self.active_tag_count(*tag) >= *count,
+                CardRequirement::MinProduction(resource, amount) => {
+                    self.production[resource] >= (*amount as isize)
+                }
+            });
+        !fails_requirements
+    }
+
+    /// How many temperature raises the player's heat pays for,
+    /// up to the raises left before the temperature maxes out.
+    pub fn heat_conversions_available(&self, board: &MarsBoard) -> usize {
+        let raises_left =
+            (MarsBoard::MAX_TEMPERATURE - board.temperature) / MarsBoard::TEMPERATURE_INCREMENT;
+        (self.resources[Resource::Heat] / HEAT_PER_TEMPERATURE_STEP).min(raises_left as usize)
+    }
+
+    /// Turn heat into a temperature raise, for plans that only follow the player's own state:
+    /// the board, and any bonuses for the temperature it reaches, are left as they are.
+    pub fn convert_heat(&mut self) {
+        assert!(self.resources[Resource::Heat] >= HEAT_PER_TEMPERATURE_STEP);
+        self.resources[Resource::Heat] -= HEAT_PER_TEMPERATURE_STEP;
+        self.terraform_rating += 1;
+    }
+
+    /// Play a card from the hand, for plans that only follow the player's own state: it is
+    /// paid for with the default payment plan, its production changes and
+    /// effects take hold, and the impacts on the player alone are resolved, including those of
+    /// the effects in play that its tags trigger. Like heat conversions, global parameter raises
+    /// count for the terraform rating but leave the board as it is. Returns what is left
+    /// to choose, or None if the card cannot be played.
+    pub fn play_card(&mut self, board: &MarsBoard, card: &Card) -> Option<Vec<FollowUpChoice>> {
+        let plan = self.default_payment_plan(self.can_play_card(board, card)?)?;
+        self.play_card_with_payment(board, card, &plan)
+    }
+
+    /// Play a card from the hand like `play_card`, paying for it exactly as planned.
+    /// Returns None if the card cannot be played, or the plan does not pay for it.
+    pub fn play_card_with_payment(
+        &mut self,
+        board: &MarsBoard,
+        card: &Card,
+        plan: &PaymentPlan,
+    ) -> Option<Vec<FollowUpChoice>> {
+        let index = self
+            .cards_in_hand
+            .iter()
+            .position(|hand_card| hand_card == card)?;
+        let cost = self.can_play_card(board, card)?;
+        plan.check(self, cost).ok()?;
+        self.apply_payment(plan);
+        let card = self.cards_in_hand.remove(index);
+
+        for (resource, change) in &card.own_production {
+            self.production[resource] += change;
+        }
+        // Tags of the card itself count for its own impacts.
+        self.played_cards.push(card.clone());
+        let triggered: Vec<(Card, ImmediateImpact)> = self
+            .impacts_triggered_by_card(&card, true)
+            .into_iter()
+            .map(|(source, _, impact)| (source.unwrap_or(&card).clone(), impact))
+            .collect();
+        for effect in &card.effects {
+            if let &CardEffect::IncreasedMetalsValue(increase) = effect {
+                self.steel_value += increase;
+                self.titanium_value += increase;
+            }
+            self.effects.push(effect.clone());
+        }
+        // The effects on the next card played are used up by this one.
+        self.next_card_this_generation_effects = card.next_card_this_generation_effects.clone();
+
+        let mut follow_ups = vec![];
+        if !card.any_production.is_empty() {
+            follow_ups.push(FollowUpChoice::AnyPlayerProduction(
+                card.any_production.clone(),
+            ));
+        }
+        for impact in &card.immediate_impacts {
+            if let Some(follow_up) = self.apply_own_impact(board, &card, impact) {
+                follow_ups.push(follow_up);
+            }
+        }
+        // The effects that were already in play fire for the card's tags.
+        for (source, impact) in &triggered {
+            if let Some(follow_up) = self.apply_own_impact(board, source, impact) {
+                follow_ups.push(follow_up);
+            }
+        }
+        Some(follow_ups)
+    }
+
+    // Resolves the impact if it only concerns the player, or else returns what is left to choose.
+    fn apply_own_impact(
+        &mut self,
+        board: &MarsBoard,
+        card: &Card,
+        impact: &ImmediateImpact,
+    ) -> Option<FollowUpChoice> {
+        let cities = || board.cities.len();
+        let cities_on_mars = || {
+            board
+                .cities
+                .keys()
+                .filter(|location| matches!(location, TileLocation::OnMars(_)))
+                .count()
+        };
+
+        match impact {
+            ImmediateImpact::RaiseTemperature => {
+                if board.temperature < MarsBoard::MAX_TEMPERATURE {
+                    self.terraform_rating += 1;
+                }
+            }
+            ImmediateImpact::RaiseOxygen => {
+                if board.oxygen < MarsBoard::MAX_OXYGEN {
+                    self.terraform_rating += 1;
+                }
+            }
+            ImmediateImpact::RaiseTerraformRating => self.terraform_rating += 1,
+            ImmediateImpact::GainTerraformRatingPerOwnTag(count, tag, per) => {
+                self.terraform_rating += count * (self.active_tag_count(*tag) / per);
+            }
+            ImmediateImpact::AddResourceToSameCard(resource, amount) => {
+                *self
+                    .card_resources
+                    .entry((card.clone(), *resource))
+                    .or_default() += amount;
+            }
+            ImmediateImpact::GainResource(resource, amount) => self.resources[resource] += amount,
+            ImmediateImpact::SpendResource(resource, amount) => {
+                self.resources[resource] = self.resources[resource].saturating_sub(*amount);
+            }
+            ImmediateImpact::GainResourcePerCity(resource, amount) => {
+                self.resources[resource] += amount * cities();
+            }
+            ImmediateImpact::GainResourcePerCityOnMars(resource, amount) => {
+                self.resources[resource] += amount * cities_on_mars();
+            }
+            ImmediateImpact::ChangeProduction(resource, change) => {
+                self.production[resource] += change;
+            }
+            ImmediateImpact::GainProductionPerCity(resource, amount) => {
+                self.production[resource] += (amount * cities()) as isize;
+            }
+            ImmediateImpact::GainProductionPerCityOnMars(resource, amount) => {
+                self.production[resource] += (amount * cities_on_mars()) as isize;
+            }
+            ImmediateImpact::GainProductionIfMinTags(resource, amount, tag, min_tags) => {
+                if self.active_tag_count(*tag) >= *min_tags {
+                    self.production[resource] += *amount as isize;
+                }
+            }
+            ImmediateImpact::GainProductionPerOwnTag(tag, per, resource, amount) => {
+                self.production[resource] +=
+                    (amount * (self.active_tag_count(*tag) / per)) as isize;
+            }
+            ImmediateImpact::PlaceOcean(_)
+            | ImmediateImpact::PlaceGreenery(_)
+            | ImmediateImpact::PlaceCity(..)
+            | ImmediateImpact::PlaceFloodingOcean(..)
+            | ImmediateImpact::PlaceSpecialTile(..)
+            | ImmediateImpact::GainMiningProductionMatchingPlacementBonus(_) => {
+                return Some(FollowUpChoice::Placement(impact.clone()));
+            }
+            ImmediateImpact::DrawCard(_)
+            | ImmediateImpact::DiscardCard(_)
+            | ImmediateImpact::LookAndBuyFromDeck(_)
+            | ImmediateImpact::LookAndTakeFromDeck(..) => {
+                return Some(FollowUpChoice::Deck(impact.clone()));
+            }
+            ImmediateImpact::GainProductionPerOpponentTag(..)
+            | ImmediateImpact::GainProductionPerAnyTag(..) => {
+                return Some(FollowUpChoice::Opponents(impact.clone()));
+            }
+            ImmediateImpact::AddResourceToAnotherCard(..)
+            | ImmediateImpact::AddResourceToAnyCard(..)
+            | ImmediateImpact::AddResourceToPlayedCard(_)
+            | ImmediateImpact::AddResourceToAnyCardWithExistingResource(..)
+            | ImmediateImpact::SpendResourceFromSameCard(..)
+            | ImmediateImpact::TransformResource(..)
+            | ImmediateImpact::TransformProduction(..)
+            | ImmediateImpact::DestroyAnyResource(..)
+            | ImmediateImpact::DestroyAnyCardResource(..)
+            | ImmediateImpact::StealResource(..)
+            | ImmediateImpact::CopyProductionOfCard(_)
+            | ImmediateImpact::OneOf(_)
+            | ImmediateImpact::Chained(..) => {
+                return Some(FollowUpChoice::Target(impact.clone()));
+            }
+        }
+        None
+    }
+
+    pub fn advance_generation(&mut self, production_steps: &[ProductionStep]) {
+        // Amounts stay signed until every step is done, so that the terraform rating income
+        // offsets negative megacredit production, whichever order the steps are in.
+        let mut new_resources = ResourceMap::new(Resource::ALL.map(|resource| {
+            self.resources[resource] as isize
+        }));
+
+        for step in production_steps {
+            match step {
+                ProductionStep::EnergyToHeat => {
+                    if !self.effects.contains(&CardEffect::EnergyKeptAtProduction) {
+                        new_resources[Resource::Heat] += new_resources[Resource::Energy];
+                        new_resources[Resource::Energy] = 0;
+                    }
+                }
+                ProductionStep::TerraformRatingIncome => {
+                    new_resources[Resource::Megacredits] += self.terraform_rating as isize;
+                }
+                ProductionStep::Production => {
+                    for (resource, production) in self.production.iter() {
+                        new_resources[resource] += production;
+                    }
+                }
+            }
+        }
+
+        // Income never leaves a player in debt: with a terraform rating too low
+        // to cover negative megacredit production, they are left with nothing.
+        self.resources = ResourceMap::new(
+            Resource::ALL.map(|resource| new_resources[resource].max(0) as usize),
+        );
+        self.tapped_active_cards.clear();
+        self.next_card_this_generation_effects.clear();
+    }
+}
+
+impl ActiveTags for PlayerState {
+    fn event_count(&self) -> usize {
+        self.played_cards.event_count()
+    }
+
+    fn active_tag_count(&self, tag_kind: CardTag) -> usize {
+        let corporation_tags = self
+            .corporation
+            .as_ref()
+            .map_or(0, |corporation| corporation.tags.count(tag_kind));
+        self.played_cards.active_tag_count(tag_kind) + corporation_tags
+    }
+
+    fn active_tag_count_for_action(&self, tag_kind: CardTag) -> usize {
+        let corporation_tags = self.corporation.as_ref().map_or(0, |corporation| {
+            corporation.tags.count(tag_kind) + corporation.tags.count(CardTag::Wild)
+        });
+        self.played_cards.active_tag_count_for_action(tag_kind) + corporation_tags
+    }
+
+    fn get_non_event_tags(&self) -> Box<dyn Iterator<Item = CardTag> + '_> {
+        let corporation_tags = self
+            .corporation
+            .iter()
+            .flat_map(|corporation| corporation.tags.iter().copied());
+        Box::new(self.played_cards.get_non_event_tags().chain(corporation_tags))
+    }
+}
+
+/// What is left to decide after a card is played from the player's own state alone,
+/// because it needs the rest of the game: the board, the other players, or the deck.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FollowUpChoice {
+    // a space for the tile, and whatever depends on where it goes
+    Placement(ImmediateImpact),
+    // the player whose production changes
+    AnyPlayerProduction(BTreeMap<Resource, isize>),
+    // a player, card, option, or amount to choose
+    Target(ImmediateImpact),
+    // cards to draw, look at, or discard
+    Deck(ImmediateImpact),
+    // counts the tags the opponents played
+    Opponents(ImmediateImpact),
+}
+
+/// The projects every player may fund on their turn, whatever cards they hold.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StandardProject {
+    SellPatents(#[serde(with = "card_ref::cards")] Vec<Card>), // 1 M$ per card discarded
+    PowerPlant,                                                 // +1 energy production
+    // raise the temperature; the ocean goes here if the temperature reaches 0 C
+    Asteroid(Option<Coordinates>),
+    Aquifer(Coordinates),
+    Greenery(Coordinates),
+    City(Coordinates), // +1 M$ production
+}
+
+impl StandardProject {
+    /// The megacredits the project costs, before any rebates.
+    pub fn cost(&self) -> usize {
+        match self {
+            StandardProject::SellPatents(_) => 0,
+            StandardProject::PowerPlant => POWER_PLANT_COST,
+            StandardProject::Asteroid(_) => ASTEROID_COST,
+            StandardProject::Aquifer(_) => AQUIFER_COST,
+            StandardProject::Greenery(_) => GREENERY_COST,
+            StandardProject::City(_) => CITY_COST,
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnAction {
+    PlayStandardProject(StandardProject),
+    // paid for with the default payment plan; its tiles go on the spaces, in the order it
+    // places them, including the ocean for a temperature raise that reaches 0 C
+    PlayCard(#[serde(with = "card_ref::card")] Card, Vec<Coordinates>),
+    PerformAction(CardAction),
+    ClaimMilestone,
+    FundAward(Award),
+    ConvertPlants(Coordinates), // 8 plants into a greenery on the space
+    // 8 heat into a temperature raise; the ocean goes here if the temperature reaches 0 C
+    ConvertHeat(Option<Coordinates>),
+    PlaceDelegate(Party), // with Turmoil
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayerTurn {
+    Play(TurnAction, Option<TurnAction>),
+    Pass,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOperation {
+    ChangeResources(PlayerId, BTreeMap<Resource, isize>),
+    ChangeProduction(PlayerId, BTreeMap<Resource, isize>),
+    ChangeCardResource(
+        PlayerId,
+        #[serde(with = "card_ref::card")] Card,
+        CardResource,
+        isize,
+    ),
+    DrawCards(PlayerId, usize),
+    RevealCard, // draw a card from the deck, show it to everyone, then discard it
+    DiscardCards(PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    // (from, to, cards): move cards between hands
+    PassCards(PlayerId, PlayerId, #[serde(with = "card_ref::cards")] Vec<Card>),
+    PutCardIntoPlay(PlayerId, #[serde(with = "card_ref::card")] Card),
+    PlaceCityTile(PlayerId, CityKind, TileLocation),
+    PlaceGreenery(PlayerId, Coordinates),
+    PlaceSpecialTile(PlayerId, SpecialTile, Coordinates),
+    PlaceOcean(Coordinates),
+    RemoveTile(TileLocation), // whichever tile is on the space, e.g. to replace it with another
+    RaiseTemperature,
+    RaiseOxygen,
+    RaiseTerraformRating(PlayerId, usize),
+    AddEffect(PlayerId, CardEffect),
+    MarkCardActionUsed(PlayerId, #[serde(with = "card_ref::card")] Card),
+    ResetCardActions,
+    ClaimMilestone, // TODO: add milestone info
+    FundAward(PlayerId, Award),
+    LowerTerraformRating(PlayerId, usize),
+    PlaceDelegate(Delegate, Party),
+    ScheduleEffect(ScheduledEffect),
+    PlayCorporation(PlayerId, Corporation),
+    DraftCard(PlayerId, #[serde(with = "card_ref::card")] Card),
+}
+
+/// Why `GameState::execute_operation()` would refuse to execute an operation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IllegalOperation {
+    UnknownPlayer(PlayerId),
+    InsufficientResource(Resource),
+    InsufficientProduction(Resource),
+    UnsupportedCardResource(CardResource),
+    InsufficientCardResource(CardResource),
+    CardNotInHand(String),
+    NoSuchHandIndex(usize), // out of range, or listed more than once
+    CardNotAllowed(String), // banned, or not part of the game's decks
+    RequirementsNotMet(String),
+    InvalidPayment(PaymentError),
+    NotEnoughCardsToDraw,
+    NoSuchSpace(TileLocation),
+    SpaceOccupied(TileLocation),
+    AllOceansPlaced,
+    IllegalPlacement(TileLocation), // the tile's placement restrictions rule the space out
+    NoTileToRemove(TileLocation),
+    PlacementRequired,              // e.g. the ocean for raising the temperature to 0 C
+    UnusedPlacements,               // more spaces given than there are tiles to place
+    AwardAlreadyFunded(Award),
+    AllAwardsFunded,
+    TemperatureMaxed,
+    OxygenMaxed,
+    InsufficientTerraformRating,
+    TurmoilNotInPlay,
+    NoDelegatesLeft,
+    EffectScheduledInThePast,
+    CorporationNotOffered(String),
+    CorporationAlreadyChosen,
+    NotEnoughCorporations,
+    NoDraftInProgress,
+    AlreadyDraftedFromPack,
+    CardNotInPack(String),
+    NotImplemented,
+}
+
+/// The phases of a generation, in the order they are played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamePhase {
+    // before the first generation, players choose their corporations and starting cards
+    Setup,
+    Research,
+    // Positions and saves from before phases were tracked are in the middle of a generation.
+    #[default]
+    Action,
+    Production,
+
+    // After the production phase of the generation in which Mars was terraformed,
+    // players may turn their remaining plants into greeneries one last time.
+    FinalGreeneries,
+    FinalScoring,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameState {
+    pub board: MarsBoard,
+    pub players: BTreeMap<PlayerId, PlayerState>,
+    #[serde(with = "card_ref::cards")]
+    pub draw_deck: Vec<Card>,
+    #[serde(with = "card_ref::cards")]
+    pub discard_pile: Vec<Card>,
+    pub generation: usize,
+    #[serde(default)]
+    pub phase: GamePhase,
+
+    // who holds the first player marker this generation; if unset, it's worked out from the
+    // generation, as if player 1 went first in the first generation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_player_marker: Option<PlayerId>,
+    // whose turn it is in the action phase; the generation's first player if not yet set
+    #[serde(default)]
+    pub turn_player: Option<PlayerId>,
+    // players who are done taking actions for the rest of the generation
+    #[serde(default)]
+    pub passed_players: BTreeSet<PlayerId>,
+
+    #[serde(default)]
+    pub options: GameOptions,
+
+    // in the order they were funded, which sets the cost of the next one
+    #[serde(default)]
+    pub funded_awards: Vec<(Award, PlayerId)>,
+
+    // all randomness in the game, e.g. reshuffling the discard pile, comes from here
+    pub rng: GameRng,
+
+    // derived from the rest of the state, and recomputed when a game is loaded
+    #[serde(skip)]
+    pub award_standings: AwardStandings,
+    #[serde(skip)]
+    pub milestone_notices: MilestoneNotices,
+
+    #[serde(default)]
+    pub parameter_timeline: ParameterTimeline,
+
+    // the parties, delegates, and global events, if the game is played with Turmoil
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turmoil: Option<Turmoil>,
+
+    // the corporations each player was dealt, until they choose one of them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub corporation_offers: BTreeMap<PlayerId, Vec<Corporation>>,
+
+    // effects waiting for a later phase, in the order they were scheduled
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scheduled_effects: Vec<ScheduledEffect>,
+
+    // the packs and picks of the research phase, when its cards are drafted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub research_draft: Option<ResearchDraft>,
+}
+
+/// The draft variant of the research phase: every player is dealt a pack of cards,
+/// picks one card of it, and passes the rest along, until every card is drafted.
+/// The players then buy from the cards they drafted, as from a regular research offer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResearchDraft {
+    pub seats: Vec<DraftSeat>, // in seating order
+    // packs go to the next seat in odd generations, and to the previous one in even ones
+    pub passes_to_next_seat: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DraftSeat {
+    pub player_id: PlayerId,
+    #[serde(with = "card_ref::cards")]
+    pub pack: Vec<Card>, // the pack in front of the player
+    #[serde(with = "card_ref::cards")]
+    pub drafted: Vec<Card>, // in the player's hand as soon as they are picked
+    pub has_picked: bool,   // from the pack in front of them
+}
+
+impl ResearchDraft {
+    pub fn is_complete(&self) -> bool {
+        self.seats.iter().all(|seat| seat.pack.is_empty())
+    }
+
+    pub fn seat(&self, player_id: PlayerId) -> Option<&DraftSeat> {
+        self.seats.iter().find(|seat| seat.player_id == player_id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlayAttempt {
+    Unplayable,
+
+    // operations so far + remaining impacts to resolve
+    PartiallyPlayable(Vec<GameOperation>, Vec<ImmediateImpact>),
+
+    Playable(Vec<GameOperation>),
+}
+
+impl GameState {
+    /// Check whether the operation can be executed, without changing anything.
+    /// Every operation that passes this check can be executed without panicking.
+    pub fn check_operation(&self, operation: &GameOperation) -> Result<(), IllegalOperation> {
+        let player = |player_id: &PlayerId| {
+            self.players
+                .get(player_id)
+                .ok_or(IllegalOperation::UnknownPlayer(*player_id))
+        };
+        let cards_in_hand = |player: &PlayerState, cards: &[Card]| {
+            // Listing a card twice is the same as listing a card that is not in hand.
+            let mut remaining_hand: Vec<&Card> = player.cards_in_hand.iter().collect();
+            for card in cards {
+                match remaining_hand.iter().position(|hand_card| *hand_card == card) {
+                    Some(index) => {
+                        remaining_hand.swap_remove(index);
+                    }
+                    None => return Err(IllegalOperation::CardNotInHand(card.name.clone())),
+                }
+            }
+            Ok(())
+        };
+
+        match operation {
+            GameOperation::ChangeResources(player_id, resources) => {
+                let player = player(player_id)?;
+                for (resource, change) in resources.iter() {
+                    if (player.resources[resource] as isize) + change < 0 {
+                        return Err(IllegalOperation::InsufficientResource(*resource));
+                    }
+                }
+            }
+            GameOperation::ChangeProduction(player_id, production) => {
+                let player = player(player_id)?;
+                for (resource, change) in production.iter() {
+                    let new_value = player.production[resource] + change;
+                    let minimum = match resource {
+                        Resource::Megacredits => -(player.terraform_rating as isize),
+                        _ => 0,
+                    };
+                    if new_value < minimum {
+                        return Err(IllegalOperation::InsufficientProduction(*resource));
+                    }
+                }
+            }
+            GameOperation::ChangeCardResource(player_id, card, card_resource, amount) => {
+                let player = player(player_id)?;
+                if card.supports_card_resource() != Some(*card_resource) {
+                    return Err(IllegalOperation::UnsupportedCardResource(*card_resource));
+                }
+
+                let current = player
+                    .card_resources
+                    .get(&(card.clone(), *card_resource))
+                    .copied()
+                    .unwrap_or_default();
+                if (current as isize) + amount < 0 {
+                    return Err(IllegalOperation::InsufficientCardResource(*card_resource));
+                }
+            }
+            GameOperation::DrawCards(player_id, count) => {
+                player(player_id)?;
+                if *count > self.draw_deck.len() + self.discard_pile.len() {
+                    return Err(IllegalOperation::NotEnoughCardsToDraw);
+                }
+            }
+            GameOperation::RevealCard => {
+                if self.draw_deck.is_empty() && self.discard_pile.is_empty() {
+                    return Err(IllegalOperation::NotEnoughCardsToDraw);
+                }
+            }
+            GameOperation::DiscardCards(player_id, cards)
+            | GameOperation::PassCards(player_id, _, cards) => {
+                cards_in_hand(player(player_id)?, cards)?;
+                if let GameOperation::PassCards(_, to_player_id, _) = operation {
+                    player(to_player_id)?;
+                }
+            }
+            GameOperation::PutCardIntoPlay(player_id, card) => {
+                cards_in_hand(player(player_id)?, std::slice::from_ref(card))?;
+                if !self.options.allows(card) {
+                    return Err(IllegalOperation::CardNotAllowed(card.name.clone()));
+                }
+            }
+            GameOperation::PlaceCityTile(player_id, _, location) => {
+                player(player_id)?;
+                self.check_empty_space(location)?;
+            }
+            GameOperation::PlaceGreenery(player_id, coordinates)
+            | GameOperation::PlaceSpecialTile(player_id, _, coordinates) => {
+                player(player_id)?;
+                self.check_empty_space(&TileLocation::OnMars(*coordinates))?;
+            }
+            GameOperation::PlaceOcean(coordinates) => {
+                if self.board.oceans.len() >= MarsBoard::MAX_OCEANS {
+                    return Err(IllegalOperation::AllOceansPlaced);
+                }
+                self.check_empty_space(&TileLocation::OnMars(*coordinates))?;
+            }
+            GameOperation::RemoveTile(location) => {
+                if !self.board.spaces.contains_key(location) {
+                    return Err(IllegalOperation::NoSuchSpace(location.clone()));
+                }
+                if let TileStatus::Empty(_) = self.board.get_tile_status(location) {
+                    return Err(IllegalOperation::NoTileToRemove(location.clone()));
+                }
+            }
+            GameOperation::RaiseTemperature => {
+                if self.board.temperature >= MarsBoard::MAX_TEMPERATURE {
+                    return Err(IllegalOperation::TemperatureMaxed);
+                }
+            }
+            GameOperation::RaiseOxygen => {
+                if self.board.oxygen >= MarsBoard::MAX_OXYGEN {
+                    return Err(IllegalOperation::OxygenMaxed);
+                }
+            }
+            GameOperation::RaiseTerraformRating(player_id, _)
+            | GameOperation::AddEffect(player_id, _)
+            | GameOperation::MarkCardActionUsed(player_id, _) => {
+                player(player_id)?;
+            }
+            GameOperation::ResetCardActions => {}
+            GameOperation::FundAward(player_id, award) => {
+                let player = player(player_id)?;
+                if self.funded_awards.iter().any(|(funded, _)| funded == award) {
+                    return Err(IllegalOperation::AwardAlreadyFunded(*award));
+                }
+                let cost = self
+                    .award_funding_cost()
+                    .ok_or(IllegalOperation::AllAwardsFunded)?;
+                if player.resources[Resource::Megacredits] < cost {
+                    return Err(IllegalOperation::InsufficientResource(Resource::Megacredits));
+                }
+            }
+            GameOperation::LowerTerraformRating(player_id, amount) => {
+                if player(player_id)?.terraform_rating < *amount {
+                    return Err(IllegalOperation::InsufficientTerraformRating);
+                }
+            }
+            GameOperation::PlaceDelegate(delegate, _) => {
+                self.check_delegate_placement(*delegate)?;
+            }
+            GameOperation::ScheduleEffect(effect) => {
+                self.check_effect_scheduling(effect)?;
+            }
+            GameOperation::PlayCorporation(player_id, corporation) => {
+                self.check_corporation_choice(*player_id, corporation)?;
+            }
+            GameOperation::DraftCard(player_id, card) => {
+                let seat = self
+                    .research_draft
+                    .as_ref()
+                    .ok_or(IllegalOperation::NoDraftInProgress)?
+                    .seat(*player_id)
+                    .ok_or(IllegalOperation::UnknownPlayer(*player_id))?;
+                if seat.has_picked {
+                    return Err(IllegalOperation::AlreadyDraftedFromPack);
+                }
+                if !seat.pack.contains(card) {
+                    return Err(IllegalOperation::CardNotInPack(card.name.clone()));
+                }
+            }
+            GameOperation::ClaimMilestone => {
+                return Err(IllegalOperation::NotImplemented);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_empty_space(&self, location: &TileLocation) -> Result<(), IllegalOperation> {
+        if !self.board.spaces.contains_key(location) {
+            return Err(IllegalOperation::NoSuchSpace(location.clone()));
+        }
+        match self.board.get_tile_status(location) {
+            TileStatus::Empty(_) => Ok(()),
+            _ => Err(IllegalOperation::SpaceOccupied(location.clone())),
+        }
+    }
+
+    pub fn execute_operation(&mut self, operation: GameOperation) -> Vec<GameEvent> {
+        let mut events = self.apply_operation(operation);
+        for event in &events {
+            self.award_standings.record(event);
+        }
+        let eligible_milestones = self.newly_eligible_milestones(&events);
+        events.extend(eligible_milestones);
+        let triggered = self.resolve_triggered_effects(&events);
+        events.extend(triggered);
+        events
+    }
+
+    /// The operations that fund the standard project for the player, in the order they are
+    /// to be executed: payment, tiles and global parameters, then placement bonuses and rebates.
+    pub fn standard_project_operations(
+        &self,
+        player_id: PlayerId,
+        project: &StandardProject,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+
+        let cost = project.cost();
+        if player.resources[Resource::Megacredits] < cost {
+            return Err(IllegalOperation::InsufficientResource(Resource::Megacredits));
+        }
+        let mut operations = vec![];
+        if cost > 0 {
+            operations.push(GameOperation::ChangeResources(
+                player_id,
+                btreemap! { Resource::Megacredits => -(cost as isize) },
+            ));
+        }
+
+        match project {
+            StandardProject::SellPatents(cards) => {
+                let discard = GameOperation::DiscardCards(player_id, cards.clone());
+                self.check_operation(&discard)?;
+                operations.push(discard);
+                operations.push(GameOperation::ChangeResources(
+                    player_id,
+                    btreemap! { Resource::Megacredits => cards.len() as isize },
+                ));
+
+                // Selling patents doesn't earn standard project rebates.
+                return Ok(operations);
+            }
+            StandardProject::PowerPlant => {
+                operations.push(GameOperation::ChangeProduction(
+                    player_id,
+                    btreemap! { Resource::Energy => 1 },
+                ));
+            }
+            StandardProject::Asteroid(ocean) => {
+                operations.extend(self.temperature_raise_operations(player, *ocean)?);
+            }
+            StandardProject::Aquifer(coordinates) => {
+                operations.extend(self.ocean_placement_operations(player, *coordinates)?);
+            }
+            StandardProject::Greenery(coordinates) => {
+                let restrictions = [
+                    LocationRestriction::LandTile,
+                    LocationRestriction::AdjacentToOwnedTileIfAble,
+                ];
+                operations.extend(self.greenery_placement_operations(
+                    player,
+                    *coordinates,
+                    &restrictions,
+                )?);
+            }
+            StandardProject::City(coordinates) => {
+                let restrictions = [
+                    LocationRestriction::LandTile,
+                    LocationRestriction::NotNextToACity,
+                ];
+                operations.extend(self.city_placement_operations(
+                    player,
+                    CityKind::RegularCity,
+                    *coordinates,
+                    &restrictions,
+                )?);
+                operations.push(GameOperation::ChangeProduction(
+                    player_id,
+                    btreemap! { Resource::Megacredits => 1 },
+                ));
+            }
+        }
+
+        let rebate: usize = player
+            .effects
+            .iter()
+            .map(|effect| match effect {
+                CardEffect::RebateForStandardProjects(amount) => *amount,
+                _ => 0,
+            })
+            .sum();
+        if rebate > 0 {
+            operations.push(GameOperation::ChangeResources(
+                player_id,
+                btreemap! { Resource::Megacredits => rebate as isize },
+            ));
+        }
+
+        Ok(operations)
+    }
+
+    /// Fund the standard project for the player, and return what happened.
+    /// Nothing changes if the project can't be funded.
+    pub fn play_standard_project(
+        &mut self,
+        player_id: PlayerId,
+        project: &StandardProject,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        self.play_turn_action(player_id, &TurnAction::PlayStandardProject(project.clone()))
+    }
+
+    /// The operations that carry out the action for the player, in the order they are
+    /// to be executed, including what the ruling party's policy adds.
+    /// Card actions and milestones aren't supported yet.
+    pub fn turn_action_operations(
+        &self,
+        player_id: PlayerId,
+        action: &TurnAction,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let operations = match action {
+            TurnAction::PlayStandardProject(project) => {
+                self.standard_project_operations(player_id, project)
+            }
+            TurnAction::ConvertPlants(coordinates) => {
+                self.greenery_conversion_operations(player_id, *coordinates)
+            }
+            TurnAction::ConvertHeat(ocean) => self.heat_conversion_operations(player_id, *ocean),
+            TurnAction::FundAward(award) => Ok(vec![GameOperation::FundAward(player_id, *award)]),
+            TurnAction::PlaceDelegate(party) => self.delegate_placement_operations(player_id, *party),
+            TurnAction::PlayCard(card, placements) => {
+                let player = self
+                    .players
+                    .get(&player_id)
+                    .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+                self.check_operation(&GameOperation::PutCardIntoPlay(player_id, card.clone()))?;
+                let plan = player
+                    .default_payment_plan(player.card_cost(card))
+                    .ok_or(IllegalOperation::InsufficientResource(Resource::Megacredits))?;
+                self.card_play_operations(player_id, card, &plan, placements)
+            }
+            TurnAction::PerformAction(_) | TurnAction::ClaimMilestone => {
+                Err(IllegalOperation::NotImplemented)
+            }
+        }?;
+        Ok(self.with_ruling_policy(player_id, operations))
+    }
+
+    pub fn play_turn_action(
+        &mut self,
+        player_id: PlayerId,
+        action: &TurnAction,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let operations = self.turn_action_operations(player_id, action)?;
+        self.execute_checked(operations)
+    }
+
+    // Every operation is checked against the game as the ones before it left it, so the
+    // operations run on a scratch copy first: if any fails, e.g. a project that can't be paid
+    // for after the Reds' charge, nothing changes.
+    pub(crate) fn execute_checked(
+        &mut self,
+        operations: Vec<GameOperation>,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let mut scratch_game = self.clone();
+        let mut events = vec![];
+        for operation in operations {
+            scratch_game.check_operation(&operation)?;
+            events.extend(scratch_game.execute_operation(operation));
+        }
+        *self = scratch_game;
+        Ok(events)
+    }
+
+    /// The operations that play the card from the player's hand, paid for exactly as planned,
+    /// in the order they are to be executed: payment, the card and its production, its
+    /// impacts, then its effects, which only take hold once it has been played.
+    /// Its tiles go on the given spaces, in the order it places them, and tiles with no legal
+    /// space left are skipped. Its attacks hit whoever the player-count rules aim them at.
+    /// Impacts that need any other choice, e.g. a card to add a resource to,
+    /// aren't supported yet.
+    pub fn card_play_operations(
+        &self,
+        player_id: PlayerId,
+        card: &Card,
+        plan: &PaymentPlan,
+        placements: &[Coordinates],
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+        let put_into_play = GameOperation::PutCardIntoPlay(player_id, card.clone());
+        self.check_operation(&put_into_play)?;
+        if !player.meets_card_requirements(&self.board, card) {
+            return Err(IllegalOperation::RequirementsNotMet(card.name.clone()));
+        }
+        plan.check(player, player.card_cost(card))
+            .map_err(IllegalOperation::InvalidPayment)?;
+
+        let payment: BTreeMap<Resource, isize> = plan
+            .amounts()
+            .iter()
+            .filter(|(_, spent)| *spent > 0)
+            .map(|(resource, spent)| (*resource, -(*spent as isize)))
+            .collect();
+        let mut paid_for = vec![];
+        if !payment.is_empty() {
+            paid_for.push(GameOperation::ChangeResources(player_id, payment));
+        }
+        paid_for.push(put_into_play);
+        if !card.own_production.is_empty() {
+            paid_for.push(GameOperation::ChangeProduction(
+                player_id,
+                card.own_production.clone(),
+            ));
+        }
+
+        // Each impact sees the game as the ones before it left it, e.g. the oceans already
+        // placed, so the operations are worked out on a scratch copy of the game.
+        let mut scratch_game = self.clone();
+        let mut operations = vec![];
+        scratch_game.execute_collecting(paid_for, &mut operations)?;
+        // The card can't be played if no one has the production it decreases.
+        let rules = PlayerCountRules::of(self);
+        for (resource, change) in &card.any_production {
+            let targets = rules.production_decrease_targets(
+                &scratch_game,
+                *resource,
+                change.unsigned_abs(),
+            );
+            let target = rules
+                .preferred_target(&scratch_game, player_id, &targets)
+                .ok_or(IllegalOperation::InsufficientProduction(*resource))?;
+            let decrease =
+                GameOperation::ChangeProduction(target, btreemap! { *resource => *change });
+            scratch_game.execute_collecting(vec![decrease], &mut operations)?;
+        }
+        let mut placements = placements.iter().copied();
+        for impact in &card.immediate_impacts {
+            let impact_operations =
+                scratch_game.card_impact_operations(player_id, card, impact, &mut placements)?;
+            scratch_game.execute_collecting(impact_operations, &mut operations)?;
+        }
+        if placements.next().is_some() {
+            return Err(IllegalOperation::UnusedPlacements);
+        }
+        operations.extend(
+            card.effects
+                .iter()
+                .map(|effect| GameOperation::AddEffect(player_id, effect.clone())),
+        );
+        Ok(operations)
+    }
+
+    fn execute_collecting(
+        &mut self,
+        operations: Vec<GameOperation>,
+        executed: &mut Vec<GameOperation>,
+    ) -> Result<(), IllegalOperation> {
+        for operation in operations {
+            self.check_operation(&operation)?;
+            self.execute_operation(operation.clone());
+            executed.push(operation);
+        }
+        Ok(())
+    }
+
+    // The operations that resolve one impact of the player's card, taking the spaces for
+    // its tiles from the placements.
+    fn card_impact_operations(
+        &self,
+        player_id: PlayerId,
+        card: &Card,
+        impact: &ImmediateImpact,
+        placements: &mut impl Iterator<Item = Coordinates>,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = &self.players[&player_id];
+        let mut next_placement = || placements.next().ok_or(IllegalOperation::PlacementRequired);
+        let gain = |resource: Resource, amount: usize| match amount {
+            0 => vec![],
+            _ => vec![GameOperation::ChangeResources(
+                player_id,
+                btreemap! { resource => amount as isize },
+            )],
+        };
+        let produce = |resource: Resource, change: isize| match change {
+            0 => vec![],
+            _ => vec![GameOperation::ChangeProduction(
+                player_id,
+                btreemap! { resource => change },
+            )],
+        };
+        let cities = self.board.cities.len();
+        let cities_on_mars = self
+            .board
+            .cities
+            .keys()
+            .filter(|location| matches!(location, TileLocation::OnMars(_)))
+            .count();
+
+        let operations = match impact {
+            ImmediateImpact::RaiseTemperature => match self.board.increase_temperature(player) {
+                PlayAttempt::Unplayable => vec![],
+                PlayAttempt::Playable(operations) => operations,
+                PlayAttempt::PartiallyPlayable(operations, _)
+                    if !self.board.has_legal_ocean_placement(player) =>
+                {
+                    operations
+                }
+                PlayAttempt::PartiallyPlayable(mut operations, _) => {
+                    operations.extend(self.ocean_placement_operations(player, next_placement()?)?);
+                    operations
+                }
+            },
+            ImmediateImpact::RaiseOxygen => match self.board.can_increase_oxygen(player) {
+                PlayAttempt::Unplayable => vec![],
+                PlayAttempt::Playable(operations)
+                | PlayAttempt::PartiallyPlayable(operations, _) => operations,
+            },
+            ImmediateImpact::RaiseTerraformRating => {
+                vec![GameOperation::RaiseTerraformRating(player_id, 1)]
+            }
+            ImmediateImpact::GainTerraformRatingPerOwnTag(count, tag, per) => {
+                match count * (player.active_tag_count(*tag) / per) {
+                    0 => vec![],
+                    steps => vec![GameOperation::RaiseTerraformRating(player_id, steps)],
+                }
+            }
+            ImmediateImpact::AddResourceToSameCard(card_resource, amount) => {
+                vec![GameOperation::ChangeCardResource(
+                    player_id,
+                    card.clone(),
+                    *card_resource,
+                    *amount as isize,
+                )]
+            }
+            ImmediateImpact::GainResource(resource, amount) => gain(*resource, *amount),
+            // Like plans that only follow the player's own state, spend what there is.
+            ImmediateImpact::SpendResource(resource, amount) => {
+                match (*amount).min(player.resources[resource]) {
+                    0 => vec![],
+                    spent => vec![GameOperation::ChangeResources(
+                        player_id,
+                        btreemap! { *resource => -(spent as isize) },
+                    )],
+                }
+            }
+            ImmediateImpact::GainResourcePerCity(resource, amount) => {
+                gain(*resource, amount * cities)
+            }
+            ImmediateImpact::GainResourcePerCityOnMars(resource, amount) => {
+                gain(*resource, amount * cities_on_mars)
+            }
+            ImmediateImpact::ChangeProduction(resource, change) => produce(*resource, *change),
+            ImmediateImpact::GainProductionPerCity(resource, amount) => {
+                produce(*resource, (amount * cities) as isize)
+            }
+            ImmediateImpact::GainProductionPerCityOnMars(resource, amount) => {
+                produce(*resource, (amount * cities_on_mars) as isize)
+            }
+            ImmediateImpact::GainProductionIfMinTags(resource, amount, tag, min_tags) => {
+                if player.active_tag_count(*tag) >= *min_tags {
+                    produce(*resource, *amount as isize)
+                } else {
+                    vec![]
+                }
+            }
+            ImmediateImpact::GainProductionPerOwnTag(tag, per, resource, amount) => {
+                produce(*resource, (amount * (player.active_tag_count(*tag) / per)) as isize)
+            }
+            ImmediateImpact::DrawCard(count) => vec![GameOperation::DrawCards(player_id, *count)],
+            // Tiles with nowhere to go are skipped, and the rest of the card still happens.
+            ImmediateImpact::PlaceOcean(_) => {
+                if self.board.has_legal_ocean_placement(player) {
+                    self.ocean_placement_operations(player, next_placement()?)?
+                } else {
+                    vec![]
+                }
+            }
+            ImmediateImpact::PlaceGreenery(restrictions)
+            | ImmediateImpact::PlaceCity(_, restrictions)
+            | ImmediateImpact::PlaceSpecialTile(_, restrictions)
+                if !self.board.has_legal_placement(player, restrictions) =>
+            {
+                vec![]
+            }
+            ImmediateImpact::PlaceGreenery(restrictions) => {
+                self.greenery_placement_operations(player, next_placement()?, restrictions)?
+            }
+            ImmediateImpact::PlaceCity(city_kind, restrictions) => self.city_placement_operations(
+                player,
+                *city_kind,
+                next_placement()?,
+                restrictions,
+            )?,
+            ImmediateImpact::PlaceSpecialTile(tile, restrictions) => {
+                let coordinates = next_placement()?;
+                let location = TileLocation::OnMars(coordinates);
+                self.check_empty_space(&location)?;
+                let empty_location = EmptyLocation::from(location.clone());
+                if !self
+                    .board
+                    .placement_satisfies_restrictions(player, &empty_location, restrictions)
+                {
+                    return Err(IllegalOperation::IllegalPlacement(location));
+                }
+
+                let mut operations =
+                    vec![GameOperation::PlaceSpecialTile(player_id, *tile, coordinates)];
+                operations.extend(self.board.placement_bonus_operations(player, &empty_location));
+                operations
+            }
+            // Taking resources is optional, so nothing happens if no opponent has any.
+            ImmediateImpact::DestroyAnyResource(resource, amount)
+            | ImmediateImpact::StealResource(resource, amount) => {
+                let rules = PlayerCountRules::of(self);
+                let targets = rules.resource_targets(self, player_id, *resource);
+                match rules.preferred_target(self, player_id, &targets) {
+                    Some(target) => {
+                        let held = self.players[&target].resources[resource];
+                        let taken = (*amount).min(held) as isize;
+                        let mut operations = vec![GameOperation::ChangeResources(
+                            target,
+                            btreemap! { *resource => -taken },
+                        )];
+                        if let ImmediateImpact::StealResource(..) = impact {
+                            operations.push(GameOperation::ChangeResources(
+                                player_id,
+                                btreemap! { *resource => taken },
+                            ));
+                        }
+                        operations
+                    }
+                    None => vec![],
+                }
+            }
+            ImmediateImpact::DestroyAnyCardResource(card_resource, amount) => {
+                let rules = PlayerCountRules::of(self);
+                let targets = rules.card_resource_targets(self, player_id, *card_resource);
+                let owners: Vec<PlayerId> = targets.iter().map(|(owner, _)| *owner).collect();
+                match rules.preferred_target(self, player_id, &owners) {
+                    Some(target) => {
+                        // The first of the target's cards, in card order.
+                        let (_, target_card) =
+                            targets.into_iter().find(|(owner, _)| *owner == target).unwrap();
+                        let present = self.players[&target].card_resources
+                            [&(target_card.clone(), *card_resource)];
+                        vec![GameOperation::ChangeCardResource(
+                            target,
+                            target_card,
+                            *card_resource,
+                            -((*amount).min(present) as isize),
+                        )]
+                    }
+                    None => vec![],
+                }
+            }
+            // Choices of cards or options that turns have no way to make yet.
+            _ => return Err(IllegalOperation::NotImplemented),
+        };
+        Ok(operations)
+    }
+
+    /// Turn plants into a greenery on the given space, raising the oxygen if it isn't maxed yet.
+    pub fn greenery_conversion_operations(
+        &self,
+        player_id: PlayerId,
+        coordinates: Coordinates,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+        if player.resources[Resource::Plants] < PLANTS_PER_GREENERY {
+            return Err(IllegalOperation::InsufficientResource(Resource::Plants));
+        }
+
+        let mut operations = vec![GameOperation::ChangeResources(
+            player_id,
+            btreemap! { Resource::Plants => -(PLANTS_PER_GREENERY as isize) },
+        )];
+        let restrictions = [
+            LocationRestriction::LandTile,
+            LocationRestriction::AdjacentToOwnedTileIfAble,
+        ];
+        operations.extend(self.greenery_placement_operations(player, coordinates, &restrictions)?);
+        Ok(operations)
+    }
+
+    pub fn convert_plants(
+        &mut self,
+        player_id: PlayerId,
+        coordinates: Coordinates,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        self.play_turn_action(player_id, &TurnAction::ConvertPlants(coordinates))
+    }
+
+    /// Turn heat into a temperature raise, placing an ocean on the given space
+    /// if the raise reaches 0 C.
+    pub fn heat_conversion_operations(
+        &self,
+        player_id: PlayerId,
+        ocean: Option<Coordinates>,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+        if player.resources[Resource::Heat] < HEAT_PER_TEMPERATURE_STEP {
+            return Err(IllegalOperation::InsufficientResource(Resource::Heat));
+        }
+
+        let mut operations = vec![GameOperation::ChangeResources(
+            player_id,
+            btreemap! { Resource::Heat => -(HEAT_PER_TEMPERATURE_STEP as isize) },
+        )];
+        operations.extend(self.temperature_raise_operations(player, ocean)?);
+        Ok(operations)
+    }
+
+    pub fn convert_heat(
+        &mut self,
+        player_id: PlayerId,
+        ocean: Option<Coordinates>,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        self.play_turn_action(player_id, &TurnAction::ConvertHeat(ocean))
+    }
+
+    /// Sell the cards at the given positions of the player's hand for 1 M$ each.
+    /// The sold cards go to the discard pile, from where they may be reshuffled into the deck.
+    pub fn sell_patents(
+        &mut self,
+        player_id: PlayerId,
+        hand_indices: &[usize],
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let hand = &self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?
+            .cards_in_hand;
+
+        let mut seen_indices = BTreeSet::new();
+        let mut cards = vec![];
+        for index in hand_indices {
+            if !seen_indices.insert(*index) || *index >= hand.len() {
+                return Err(IllegalOperation::NoSuchHandIndex(*index));
+            }
+            cards.push(hand[*index].clone());
+        }
+
+        self.play_standard_project(player_id, &StandardProject::SellPatents(cards))
+    }
+
+    // Raising the temperature to 0 C places an ocean on the given space. If no space is left
+    // for an ocean, the ocean is skipped, but the temperature still goes up.
+    fn temperature_raise_operations(
+        &self,
+        player: &PlayerState,
+        ocean: Option<Coordinates>,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        match self.board.increase_temperature(player) {
+            PlayAttempt::Unplayable => Err(IllegalOperation::TemperatureMaxed),
+            PlayAttempt::Playable(raise) => Ok(raise),
+            PlayAttempt::PartiallyPlayable(mut raise, _) => {
+                match ocean {
+                    Some(coordinates) => {
+                        raise.extend(self.ocean_placement_operations(player, coordinates)?)
+                    }
+                    None if self.board.has_legal_ocean_placement(player) => {
+                        return Err(IllegalOperation::PlacementRequired)
+                    }
+                    None => {}
+                }
+                Ok(raise)
+            }
+        }
+    }
+
+    // Placing an ocean tile raises the terraform rating of the player who placed it.
+    pub(crate) fn ocean_placement_operations(
+        &self,
+        player: &PlayerState,
+        coordinates: Coordinates,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let place_ocean = GameOperation::PlaceOcean(coordinates);
+        self.check_operation(&place_ocean)?;
+
+        let location = TileLocation::OnMars(coordinates);
+        let empty_location = EmptyLocation::from(location.clone());
+        let restrictions = [LocationRestriction::ReservedForOcean];
+        if !self
+            .board
+            .placement_satisfies_restrictions(player, &empty_location, &restrictions)
+        {
+            return Err(IllegalOperation::IllegalPlacement(location));
+        }
+
+        let mut operations = vec![
+            place_ocean,
+            GameOperation::RaiseTerraformRating(player.player_id, 1),
+        ];
+        operations.extend(self.board.placement_bonus_operations(player, &empty_location));
+        Ok(operations)
+    }
+
+    // Placing a greenery raises the oxygen level, and the terraform rating with it.
+    pub(crate) fn greenery_placement_operations(
+        &self,
+        player: &PlayerState,
+        coordinates: Coordinates,
+        restrictions: &[LocationRestriction],
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let location = TileLocation::OnMars(coordinates);
+        self.check_empty_space(&location)?;
+        match self.board.can_place_greenery(
+            player,
+            EmptyLocation::from(location.clone()),
+            restrictions,
+        ) {
+            PlayAttempt::Unplayable => Err(IllegalOperation::IllegalPlacement(location)),
+            PlayAttempt::Playable(operations) | PlayAttempt::PartiallyPlayable(operations, _) => {
+                Ok(operations)
+            }
+        }
+    }
+
+    pub(crate) fn city_placement_operations(
+        &self,
+        player: &PlayerState,
+        city_kind: CityKind,
+        coordinates: Coordinates,
+        restrictions: &[LocationRestriction],
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let location = TileLocation::OnMars(coordinates);
+        self.check_empty_space(&location)?;
+        let empty_location = EmptyLocation::from(location.clone());
+        if !self
+            .board
+            .placement_satisfies_restrictions(player, &empty_location, restrictions)
+        {
+            return Err(IllegalOperation::IllegalPlacement(location));
+        }
+
+        let mut operations = vec![GameOperation::PlaceCityTile(
+            player.player_id,
+            city_kind,
+            location,
+        )];
+        operations.extend(self.board.placement_bonus_operations(player, &empty_location));
+        Ok(operations)
+    }
+
+    /// Put the given tile in place of the one on its space, e.g. to flip an ocean into a city,
+    /// or hand a greenery to another player. Unlike a placement, the replacement earns no
+    /// bonuses and ignores placement restrictions. An empty space just removes the tile.
+    pub fn tile_replacement_operations(
+        &self,
+        replacement: &TileStatus,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let location = replacement.location().clone();
+        let remove_tile = GameOperation::RemoveTile(location.clone());
+        self.check_operation(&remove_tile)?;
+
+        let place_tile = match (replacement, &location) {
+            (TileStatus::Empty(_), _) => None,
+            (TileStatus::City(_, city_kind, player_id), _) => Some(GameOperation::PlaceCityTile(
+                *player_id,
+                *city_kind,
+                location.clone(),
+            )),
+            (TileStatus::Ocean(_), TileLocation::OnMars(coordinates)) => {
+                Some(GameOperation::PlaceOcean(*coordinates))
+            }
+            (TileStatus::Greenery(_, player_id), TileLocation::OnMars(coordinates)) => {
+                Some(GameOperation::PlaceGreenery(*player_id, *coordinates))
+            }
+            (TileStatus::SpecialTile(_, tile, player_id), TileLocation::OnMars(coordinates)) => {
+                Some(GameOperation::PlaceSpecialTile(*player_id, *tile, *coordinates))
+            }
+            // Only cities are ever placed off Mars.
+            (_, TileLocation::OffMars(_)) => {
+                return Err(IllegalOperation::IllegalPlacement(location))
+            }
+        };
+        Ok(std::iter::once(remove_tile).chain(place_tile).collect())
+    }
+
+    /// What funding the next award costs, or None if no more awards may be funded.
+    pub fn award_funding_cost(&self) -> Option<usize> {
+        AWARD_FUNDING_COSTS.get(self.funded_awards.len()).copied()
+    }
+
+    /// The number of production phases still to come, including the current generation's,
+    /// judged by how far the global parameters still have to go and the pace of the game.
+    pub fn estimated_generations_left(&self) -> usize {
+        estimate_game_generations_left(self, &ProjectionAssumptions::default())
+    }
+
+    // Production phase: every player's resources are paid out, and the next generation begins.
+    pub fn advance_generation(&mut self) -> Vec<GameEvent> {
+        self.produce();
+        let mut events = self.resolve_scheduled_effects(EffectTiming::EndOfGeneration);
+        events.extend(self.turmoil_phase());
+        self.first_player_marker = self
+            .first_player()
+            .and_then(|first_player| self.seats_after(first_player).next());
+        self.generation += 1;
+
+        self.phase = GamePhase::Research;
+        self.turn_player = None;
+        self.passed_players.clear();
+        events.extend(self.resolve_scheduled_effects(EffectTiming::StartOfGeneration));
+        events
+    }
+
+    /// The player who goes first this generation. Players are seated in player id order,
+    /// and the first player marker moves one seat along every generation.
+    pub fn first_player(&self) -> Option<PlayerId> {
+        if let Some(player_id) = self.first_player_marker {
+            if self.players.contains_key(&player_id) {
+                return Some(player_id);
+            }
+        }
+
+        let player_count = self.players.len();
+        if player_count == 0 {
+            return None;
+        }
+        let seat = (self.generation.max(1) - 1) % player_count;
+        self.players.keys().nth(seat).copied()
+    }
+
+    /// The player whose turn it is, or None outside of the action phase.
+    pub fn current_player(&self) -> Option<PlayerId> {
+        match self.phase {
+            GamePhase::Action => self.turn_player.or_else(|| self.first_player()),
+            GamePhase::Setup
+            | GamePhase::Research
+            | GamePhase::Production
+            | GamePhase::FinalGreeneries
+            | GamePhase::FinalScoring => None,
+        }
+    }
+
+    /// Move on to the next phase of the generation. Leaving the production phase
+    /// pays out production and starts the next generation with its research phase.
+    pub fn advance_phase(&mut self) -> Vec<GameEvent> {
+        match self.phase {
+            // The cards kept at setup stand in for the first generation's research.
+            GamePhase::Setup | GamePhase::Research => {
+                self.phase = GamePhase::Action;
+                self.turn_player = self.first_player();
+                self.passed_players.clear();
+                self.research_draft = None;
+            }
+            GamePhase::Action => {
+                self.phase = GamePhase::Production;
+                self.turn_player = None;
+            }
+            GamePhase::Production => return self.end_generation(),
+            GamePhase::FinalGreeneries => self.phase = GamePhase::FinalScoring,
+            GamePhase::FinalScoring => {}
+        }
+        vec![]
+    }
+
+    /// The production phase, after which the next generation begins, unless Mars is terraformed
+    /// or a solo game is out of generations. Then production still happens, but the game
+    /// moves on to the final greenery conversions.
+    pub fn end_generation(&mut self) -> Vec<GameEvent> {
+        if self.board.is_terraformed() || self.is_last_solo_generation() {
+            self.produce();
+            let events = self.resolve_scheduled_effects(EffectTiming::EndOfGeneration);
+            self.phase = GamePhase::FinalGreeneries;
+            self.turn_player = None;
+            events
+        } else {
+            self.advance_generation()
+        }
+    }
+
+    /// Whether the game is over, and only final scoring remains.
+    pub fn is_over(&self) -> bool {
+        self.phase == GamePhase::FinalScoring
+    }
+
+    // The production phase: every player produces, and the generation's parameters are recorded.
+    fn produce(&mut self) {
+        for player in self.players.values_mut() {
+            player.advance_generation(&self.options.production_steps);
+            self.award_standings.refresh_resources(player);
+        }
+        self.parameter_timeline.record(self.generation, &self.board);
+    }
+
+    /// Finish the current player's turn, and hand it to the next player in seating order
+    /// who hasn't passed. Once every player has passed, the action phase is over.
+    pub fn end_turn(&mut self, turn: &PlayerTurn) {
+        let current_player = match self.current_player() {
+            Some(player_id) => player_id,
+            None => return,
+        };
+        if let PlayerTurn::Pass = turn {
+            self.passed_players.insert(current_player);
+        }
+
+        let next_player = self
+            .seats_after(current_player)
+            .find(|player_id| !self.passed_players.contains(player_id));
+
+        match next_player {
+            Some(player_id) => self.turn_player = Some(player_id),
+            // Only production follows, and nothing happens until it's run.
+            None => {
+                self.advance_phase();
+            }
+        }
+    }
+
+    // Every player once, going around the table from the seat after the given player's,
+    // and ending with the player themselves.
+    fn seats_after(&self, player_id: PlayerId) -> impl Iterator<Item = PlayerId> {
+        let seats: Vec<PlayerId> = self.players.keys().copied().collect();
+        let seat = seats.iter().position(|id| *id == player_id).unwrap_or(0);
+        (1..=seats.len()).map(move |offset| seats[(seat + offset) % seats.len()])
+    }
+
+    fn apply_operation(&mut self, operation: GameOperation) -> Vec<GameEvent> {
+        match operation {
+            GameOperation::ChangeResources(player_id, resources) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                for (resource, change) in resources.iter() {
+                    if *change != 0 {
+                        let prior_value = &mut player.resources[resource];
+                        let new_value = (*prior_value as isize) + change;
+                        assert!(new_value >= 0);
+                        *prior_value = new_value as usize;
+                    }
+                }
+
+                vec![GameEvent::ResourcesChanged(player_id, resources)]
+            }
+            GameOperation::ChangeProduction(player_id, production) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                for (resource, change) in production.iter() {
+                    if *change != 0 {
+                        let prior_value = &mut player.production[resource];
+                        let new_value = *prior_value + change;
+                        if *resource == Resource::Megacredits {
+                            assert!(player.terraform_rating as isize + new_value >= 0);
+                        } else {
+                            assert!(new_value >= 0);
+                        }
+                        *prior_value = new_value;
+                    }
+                }
+
+                vec![GameEvent::ProductionChanged(player_id, production)]
+            }
+            GameOperation::ChangeCardResource(player_id, card, card_resource, amount) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+
+                assert!(card.supports_card_resource() == Some(card_resource));
+
+                player
+                    .card_resources
+                    .entry((card.clone(), card_resource))
+                    .and_modify(|quantity| {
+                        let new_quantity = (*quantity as isize) + amount;
+                        assert!(new_quantity >= 0);
+                        *quantity = new_quantity as usize;
+                    })
+                    .or_insert_with(|| {
+                        assert!(amount >= 0);
+                        amount as usize
+                    });
+
+                vec![GameEvent::CardResourceChanged(player_id, card, card_resource, amount)]
+            }
+            GameOperation::DrawCards(player_id, count) => {
+                let mut events = vec![];
+
+                let drawn_cards = self.take_cards_from_deck(count, &mut events);
+                let player = self.players.get_mut(&player_id).unwrap();
+                player.cards_in_hand.extend_from_slice(&drawn_cards);
+
+                events.push(GameEvent::CardsDrawn(player_id, drawn_cards));
+                events
+            }
+            GameOperation::RevealCard => {
+                let mut events = vec![];
+
+                let mut revealed_cards = self.take_cards_from_deck(1, &mut events);
+                let revealed_card = revealed_cards.pop().unwrap();
+                self.discard_pile.push(revealed_card.clone());
+
+                events.push(GameEvent::CardRevealed(revealed_card));
+                events
+            }
+            GameOperation::DiscardCards(player_id, discard) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+
+                let initial_hand_size = player.cards_in_hand.len();
+                player.cards_in_hand.retain(|card| !discard.contains(card));
+                assert_eq!(
+                    initial_hand_size,
+                    player.cards_in_hand.len() + discard.len()
+                );
+
+                self.discard_pile.extend_from_slice(&discard);
+
+                vec![GameEvent::CardsDiscarded(player_id, discard)]
+            }
+            GameOperation::PassCards(from_player_id, to_player_id, passed_cards) => {
+                let from_player = self.players.get_mut(&from_player_id).unwrap();
+
+                let initial_hand_size = from_player.cards_in_hand.len();
+                from_player.cards_in_hand.retain(|card| !passed_cards.contains(card));
+                assert_eq!(
+                    initial_hand_size,
+                    from_player.cards_in_hand.len() + passed_cards.len()
+                );
+
+                let to_player = self.players.get_mut(&to_player_id).unwrap();
+                to_player.cards_in_hand.extend_from_slice(&passed_cards);
+
+                vec![GameEvent::CardsPassed(from_player_id, to_player_id, passed_cards)]
+            }
+            GameOperation::PutCardIntoPlay(player_id, played_card) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+
+                let initial_hand_size = player.cards_in_hand.len();
+                player.cards_in_hand.retain(|card| *card != played_card);
+                assert_eq!(initial_hand_size, player.cards_in_hand.len() + 1);
+
+                player.played_cards.push(played_card.clone());
+                // The effects on the next card played are used up by this one.
+                player.next_card_this_generation_effects =
+                    played_card.next_card_this_generation_effects.clone();
+
+                vec![GameEvent::CardPlayed(player_id, played_card)]
+            }
+            GameOperation::PlaceCityTile(player_id, city_kind, location) => {
+                if let TileLocation::OnMars(coordinates) = location {
+                    assert!(!self.board.greeneries.contains_key(&coordinates));
+                    assert!(!self.board.special_tiles.contains_key(&coordinates));
+                    assert!(!self.board.oceans.contains(&coordinates));
+                }
+
+                let existing_city = self
+                    .board
+                    .cities
+                    .insert(location.clone(), (city_kind, player_id));
+                assert!(existing_city.is_none());
+
+                vec![GameEvent::CityPlaced(player_id, city_kind, location)]
+            }
+            GameOperation::PlaceGreenery(player_id, coordinates) => {
+                assert!(!self.board.cities.contains_key(&TileLocation::OnMars(coordinates)));
+                assert!(!self.board.special_tiles.contains_key(&coordinates));
+                assert!(!self.board.oceans.contains(&coordinates));
+
+                let existing_greenery = self.board.greeneries.insert(coordinates, player_id);
+                assert!(existing_greenery.is_none());
+
+                vec![GameEvent::GreeneryPlaced(player_id, coordinates)]
+            }
+            GameOperation::PlaceSpecialTile(player_id, tile, coordinates) => {
+                assert!(!self.board.cities.contains_key(&TileLocation::OnMars(coordinates)));
+                assert!(!self.board.greeneries.contains_key(&coordinates));
+                assert!(!self.board.oceans.contains(&coordinates));
+
+                let existing_tile = self
+                    .board
+                    .special_tiles
+                    .insert(coordinates, (tile, player_id));
+                assert!(existing_tile.is_none());
+
+                vec![GameEvent::SpecialTilePlaced(player_id, tile, coordinates)]
+            }
+            GameOperation::PlaceOcean(coordinates) => {
+                assert!(!self.board.cities.contains_key(&TileLocation::OnMars(coordinates)));
+                assert!(!self.board.greeneries.contains_key(&coordinates));
+                assert!(!self.board.special_tiles.contains_key(&coordinates));
+
+                assert!(self.board.oceans.len() < MarsBoard::MAX_OCEANS);
+
+                let is_new_ocean = self.board.oceans.insert(coordinates);
+                assert!(is_new_ocean);
+
+                vec![GameEvent::OceanPlaced(coordinates)]
+            }
+            GameOperation::RemoveTile(location) => {
+                let tile = self.board.get_tile_status(&location);
+                match &tile {
+                    TileStatus::Empty(_) => panic!("no tile to remove at {}", location),
+                    TileStatus::City(..) => {
+                        self.board.cities.remove(&location);
+                    }
+                    TileStatus::Ocean(_)
+                    | TileStatus::Greenery(..)
+                    | TileStatus::SpecialTile(..) => {
+                        if let TileLocation::OnMars(coordinates) = location {
+                            self.board.oceans.remove(&coordinates);
+                            self.board.greeneries.remove(&coordinates);
+                            self.board.special_tiles.remove(&coordinates);
+                        }
+                    }
+                }
+
+                vec![GameEvent::TileRemoved(tile)]
+            }
+            GameOperation::RaiseTemperature => {
+                assert!(self.board.temperature < MarsBoard::MAX_TEMPERATURE);
+
+                self.board.temperature += MarsBoard::TEMPERATURE_INCREMENT;
+
+                vec![GameEvent::TemperatureRaised(self.board.temperature)]
+            }
+            GameOperation::RaiseOxygen => {
+                assert!(self.board.oxygen < MarsBoard::MAX_OXYGEN);
+
+                self.board.oxygen += MarsBoard::OXYGEN_INCREMENT;
+
+                vec![GameEvent::OxygenRaised(self.board.oxygen)]
+            }
+            GameOperation::RaiseTerraformRating(player_id, amount) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                player.terraform_rating += amount;
+
+                vec![GameEvent::TerraformRatingRaised(player_id, amount)]
+            }
+            GameOperation::LowerTerraformRating(player_id, amount) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                assert!(player.terraform_rating >= amount);
+                player.terraform_rating -= amount;
+
+                vec![GameEvent::TerraformRatingLowered(player_id, amount)]
+            }
+            GameOperation::PlaceDelegate(delegate, party) => {
+                self.apply_delegate_placement(delegate, party)
+            }
+            GameOperation::ScheduleEffect(effect) => {
+                self.scheduled_effects.push(effect.clone());
+
+                vec![GameEvent::EffectScheduled(effect)]
+            }
+            GameOperation::PlayCorporation(player_id, corporation) => {
+                self.apply_corporation_choice(player_id, corporation)
+            }
+            GameOperation::DraftCard(player_id, card) => self.apply_draft_pick(player_id, card),
+            GameOperation::AddEffect(player_id, effect) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                if let CardEffect::IncreasedMetalsValue(increase) = effect {
+                    player.steel_value += increase;
+                    player.titanium_value += increase;
+                }
+                player.effects.push(effect.clone());
+
+                vec![GameEvent::EffectAdded(player_id, effect)]
+            }
+            GameOperation::MarkCardActionUsed(player_id, card) => {
+                let player = self.players.get_mut(&player_id).unwrap();
+                player.tapped_active_cards.insert(card.clone());
+
+                vec![GameEvent::CardActionUsed(player_id, card)]
+            }
+            GameOperation::ResetCardActions => {
+                for (_, player) in self.players.iter_mut() {
+                    player.tapped_active_cards.clear();
+                }
+
+                vec![GameEvent::CardActionsReset]
+            }
+            GameOperation::ClaimMilestone => todo!(),
+            GameOperation::FundAward(player_id, award) => {
+                let cost = self.award_funding_cost().unwrap();
+                let mut events = self.apply_operation(GameOperation::ChangeResources(
+                    player_id,
+                    btreemap! { Resource::Megacredits => -(cost as isize) },
+                ));
+                self.funded_awards.push((award, player_id));
+
+                events.push(GameEvent::AwardFunded(player_id, award));
+                events
+            }
+        }
+    }
+
+    // Cards are drawn from the end of the draw deck. If the deck runs out,
+    // the discard pile is shuffled to form the new deck.
+    fn take_cards_from_deck(&mut self, mut count: usize, events: &mut Vec<GameEvent>) -> Vec<Card> {
+        let mut taken_cards = vec![];
+
+        if count > self.draw_deck.len() {
+            count -= self.draw_deck.len();
+            taken_cards.append(&mut self.draw_deck);
+
+            self.discard_pile.shuffle(&mut self.rng);
+            self.draw_deck.append(&mut self.discard_pile);
+            events.push(GameEvent::DiscardPileReshuffled);
+        }
+
+        assert!(count <= self.draw_deck.len());
+        taken_cards.extend(self.draw_deck.drain((self.draw_deck.len() - count)..));
+        taken_cards
+    }
+
+    /// Whether this generation's research cards are drafted. The first generation
+    /// deals the starting hands instead, so it is never drafted.
+    pub fn drafts_research(&self) -> bool {
+        self.options.research_draft && self.generation > 1
+    }
+
+    /// Deal every player a pack for the research draft. Packs are as large as a regular
+    /// research offer, unless the deck and the discard pile cannot fill them all.
+    pub fn start_research_draft(&mut self) -> Result<Vec<GameEvent>, IllegalOperation> {
+        if self.players.is_empty() {
+            return Err(IllegalOperation::NotEnoughCardsToDraw);
+        }
+        let available_cards = self.draw_deck.len() + self.discard_pile.len();
+        let pack_size = RESEARCH_PHASE_OFFER_SIZE.min(available_cards / self.players.len());
+
+        let mut events = vec![];
+        let player_ids: Vec<PlayerId> = self.players.keys().copied().collect();
+        let mut seats = vec![];
+        for player_id in player_ids {
+            let pack = self.take_cards_from_deck(pack_size, &mut events);
+            events.push(GameEvent::DraftPackDealt(player_id, pack.clone()));
+            seats.push(DraftSeat {
+                player_id,
+                pack,
+                drafted: vec![],
+                has_picked: false,
+            });
+        }
+        self.research_draft = Some(ResearchDraft {
+            seats,
+            passes_to_next_seat: self.generation % 2 == 1,
+        });
+        Ok(events)
+    }
+
+    // Once every player has picked, the packs go around the table. The last card
+    // of each pack needs no choosing, so it goes to whoever the pack is passed to.
+    fn apply_draft_pick(&mut self, player_id: PlayerId, card: Card) -> Vec<GameEvent> {
+        let draft = self.research_draft.as_mut().unwrap();
+        let seat = draft
+            .seats
+            .iter_mut()
+            .find(|seat| seat.player_id == player_id)
+            .unwrap();
+        let index = seat.pack.iter().position(|in_pack| *in_pack == card).unwrap();
+        seat.pack.remove(index);
+        seat.drafted.push(card.clone());
+        seat.has_picked = true;
+        self.players
+            .get_mut(&player_id)
+            .unwrap()
+            .cards_in_hand
+            .push(card.clone());
+        let mut events = vec![GameEvent::CardDrafted(player_id, card)];
+
+        let draft = self.research_draft.as_mut().unwrap();
+        if draft.seats.iter().any(|seat| !seat.has_picked) || draft.is_complete() {
+            return events;
+        }
+        let mut packs: Vec<(PlayerId, Vec<Card>)> = draft
+            .seats
+            .iter_mut()
+            .map(|seat| (seat.player_id, std::mem::take(&mut seat.pack)))
+            .collect();
+        if draft.passes_to_next_seat {
+            packs.rotate_right(1);
+        } else {
+            packs.rotate_left(1);
+        }
+        for (seat, (from_player_id, pack)) in draft.seats.iter_mut().zip(packs) {
+            events.push(GameEvent::DraftPackPassed(
+                from_player_id,
+                seat.player_id,
+                pack.clone(),
+            ));
+            seat.pack = pack;
+            seat.has_picked = false;
+        }
+
+        for seat in draft.seats.iter_mut().filter(|seat| seat.pack.len() == 1) {
+            let last_card = seat.pack.pop().unwrap();
+            seat.drafted.push(last_card.clone());
+            seat.has_picked = true;
+            self.players
+                .get_mut(&seat.player_id)
+                .unwrap()
+                .cards_in_hand
+                .push(last_card.clone());
+            events.push(GameEvent::CardDrafted(seat.player_id, last_card));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::make_base_game_board;
+    use crate::board::Coordinates;
+    use crate::board::MarsBoard;
+    use crate::board::MarsBoardBuilder;
+    use crate::board::TileLocation;
+    use crate::board::TileStatus;
+    use crate::award::AwardStandings;
+    use crate::card::{Card, CardEffect};
+    use crate::card::CityKind;
+    use crate::card::SpecialLocation;
+    use crate::card::{BASE_GAME_CARDS_BY_NAME, CORPORATE_GAME_CARDS_BY_NAME};
+    use crate::game::PlayerStateBuilder;
+    use crate::game::FollowUpChoice;
+    use crate::game::DEFAULT_STARTING_TERRAFORM_RATING;
+    use crate::game::{GameOperation, GameState, PlayerId};
+    use crate::game::{DEFAULT_STEEL_VALUE, DEFAULT_TITANIUM_VALUE};
+    use crate::position::import_position;
+    use crate::resource::CardResource;
+    use crate::game::{GamePhase, PlayerState, PlayerTurn, StandardProject, TurnAction};
+    use crate::game::RESEARCH_PHASE_OFFER_SIZE;
+    use crate::game::IllegalOperation;
+    use crate::event::GameEvent;
+    use crate::options::ProductionStep;
+    use crate::resource::Resource;
+    use crate::test_utils::make_game;
+    use maplit::btreemap;
+
+    #[test]
+    fn turns_go_around_the_table_until_everyone_passes() {
+        let players = (1..=3).map(|id| PlayerStateBuilder::new(id).build()).collect();
+        let mut game = make_game(players);
+        game.phase = GamePhase::Research;
+        assert_eq!(None, game.current_player());
+
+        game.advance_phase();
+        assert_eq!(GamePhase::Action, game.phase);
+        assert_eq!(Some(PlayerId::from(1)), game.current_player());
+
+        let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+        let play = PlayerTurn::Play(power_plant, None);
+        game.end_turn(&play);
+        assert_eq!(Some(PlayerId::from(2)), game.current_player());
+        game.end_turn(&PlayerTurn::Pass);
+        assert_eq!(Some(PlayerId::from(3)), game.current_player());
+
+        // Passed players are skipped for the rest of the generation.
+        game.end_turn(&play);
+        assert_eq!(Some(PlayerId::from(1)), game.current_player());
+        game.end_turn(&PlayerTurn::Pass);
+        assert_eq!(Some(PlayerId::from(3)), game.current_player());
+        game.end_turn(&play);
+        assert_eq!(Some(PlayerId::from(3)), game.current_player());
+        game.end_turn(&PlayerTurn::Pass);
+
+        assert_eq!(GamePhase::Production, game.phase);
+        assert_eq!(None, game.current_player());
+    }
+
+    #[test]
+    fn first_player_moves_along_every_generation() {
+        let players = (1..=2).map(|id| PlayerStateBuilder::new(id).build()).collect();
+        let mut game = make_game(players);
+        assert_eq!(Some(PlayerId::from(1)), game.first_player());
+
+        game.phase = GamePhase::Production;
+        game.advance_phase();
+        assert_eq!(2, game.generation);
+        assert_eq!(GamePhase::Research, game.phase);
+
+        game.advance_phase();
+        assert_eq!(Some(PlayerId::from(2)), game.current_player());
+    }
+
+    #[test]
+    fn the_first_player_marker_goes_around_the_table() {
+        let players = (1..=3).map(|id| PlayerStateBuilder::new(id).build()).collect();
+        let mut game = make_game(players);
+        game.generation = 4;
+        game.first_player_marker = Some(PlayerId::from(3));
+        assert_eq!(Some(PlayerId::from(3)), game.first_player());
+
+        game.phase = GamePhase::Production;
+        game.advance_phase();
+        assert_eq!(Some(PlayerId::from(1)), game.first_player_marker);
+        game.advance_phase();
+        assert_eq!(Some(PlayerId::from(1)), game.current_player());
+
+        // Turns still go around the table from the first player, skipping players who passed.
+        game.end_turn(&PlayerTurn::Pass);
+        assert_eq!(Some(PlayerId::from(2)), game.current_player());
+        game.end_turn(&PlayerTurn::Pass);
+        assert_eq!(Some(PlayerId::from(3)), game.current_player());
+        let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+        game.end_turn(&PlayerTurn::Play(power_plant, None));
+        assert_eq!(Some(PlayerId::from(3)), game.current_player());
+        game.end_turn(&PlayerTurn::Pass);
+        assert_eq!(GamePhase::Production, game.phase);
+
+        game.advance_phase();
+        game.advance_phase();
+        assert_eq!(Some(PlayerId::from(2)), game.current_player());
+    }
+
+    #[test]
+    fn the_game_ends_after_the_generation_that_terraforms_mars() {
+        let players = vec![PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 3, 0, 0)
+            .with_production(0, 0, 0, 3, 0, 0)
+            .build()];
+        let mut game = make_game(players);
+        game.board.oxygen = MarsBoard::MAX_OXYGEN;
+        game.board.temperature = MarsBoard::MAX_TEMPERATURE;
+        game.board.oceans = (0..MarsBoard::MAX_OCEANS - 1)
+            .map(|q| Coordinates::new(q as isize, 0))
+            .collect();
+        assert!(!game.board.is_terraformed());
+
+        // One ocean short, the game goes on.
+        game.phase = GamePhase::Production;
+        game.advance_phase();
+        assert_eq!((2, GamePhase::Research), (game.generation, game.phase));
+
+        game.board.oceans.insert(Coordinates::new(-1, 0));
+        assert!(game.board.is_terraformed());
+        game.advance_phase();
+        game.end_turn(&PlayerTurn::Pass);
+        assert!(!game.is_over());
+
+        // Production still happens in the last generation, but no new generation begins.
+        game.advance_phase();
+        assert_eq!((2, GamePhase::FinalGreeneries), (game.generation, game.phase));
+        let p1 = PlayerId::from(1);
+        assert_eq!(40, game.players[&p1].resources[Resource::Megacredits]);
+        assert_eq!(9, game.players[&p1].resources[Resource::Plants]);
+        assert_eq!(None, game.current_player());
+
+        // The last plants become a greenery, without raising the maxed oxygen.
+        game.convert_plants(p1, Coordinates::new(3, -1)).unwrap();
+        assert_eq!(1, game.players[&p1].resources[Resource::Plants]);
+        assert_eq!(Some(&p1), game.board.greeneries.get(&Coordinates::new(3, -1)));
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(Resource::Plants)),
+            game.convert_plants(p1, Coordinates::new(3, 0))
+        );
+        assert!(!game.is_over());
+
+        game.advance_phase();
+        assert!(game.is_over());
+        game.advance_phase();
+        assert!(game.is_over());
+        assert_eq!(2, game.generation);
+    }
+
+    #[test]
+    fn standard_projects_are_paid_for_and_earn_rebates() {
+        let p1 = PlayerStateBuilder::new(1)
+            .with_resources(100, 0, 0, 0, 0, 0)
+            .with_effects_override(vec![CardEffect::RebateForStandardProjects(3)])
+            .build();
+        let mut game = make_game(vec![p1]);
+        let p1 = PlayerId::from(1);
+
+        game.play_standard_project(p1, &StandardProject::PowerPlant).unwrap();
+        assert_eq!(92, game.players[&p1].resources[Resource::Megacredits]);
+        assert_eq!(1, game.players[&p1].production[Resource::Energy]);
+
+        let city = Coordinates::new(3, -1);
+        game.play_standard_project(p1, &StandardProject::City(city)).unwrap();
+        assert_eq!(70, game.players[&p1].resources[Resource::Megacredits]);
+        assert_eq!(1, game.players[&p1].production[Resource::Megacredits]);
+        assert!(game.board.cities.contains_key(&TileLocation::OnMars(city)));
+
+        // Cities can't be next to each other, and greeneries go next to their owner's tiles.
+        let next_to_city = Coordinates::new(3, 0);
+        assert_eq!(
+            Err(IllegalOperation::IllegalPlacement(TileLocation::OnMars(next_to_city))),
+            game.standard_project_operations(p1, &StandardProject::City(next_to_city)),
+        );
+        let far_away = Coordinates::new(0, -2);
+        assert_eq!(
+            Err(IllegalOperation::IllegalPlacement(TileLocation::OnMars(far_away))),
+            game.standard_project_operations(p1, &StandardProject::Greenery(far_away)),
+        );
+
+        game.play_standard_project(p1, &StandardProject::Greenery(next_to_city)).unwrap();
+        assert_eq!(50, game.players[&p1].resources[Resource::Megacredits]);
+        assert_eq!(1, game.board.oxygen);
+        assert_eq!(DEFAULT_STARTING_TERRAFORM_RATING + 1, game.players[&p1].terraform_rating);
+
+        // Projects the player can't afford change nothing.
+        game.players.get_mut(&p1).unwrap().resources[Resource::Megacredits] = 10;
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(Resource::Megacredits)),
+            game.play_standard_project(p1, &StandardProject::PowerPlant),
+        );
+        assert_eq!(10, game.players[&p1].resources[Resource::Megacredits]);
+    }
+
+    #[test]
+    fn oceans_from_standard_projects_raise_terraform_rating_and_pay_bonuses() {
+        let p1 = PlayerStateBuilder::new(1)
+            .with_resources(40, 0, 0, 0, 0, 0)
+            .build();
+        let mut game = make_game(vec![p1]);
+        let p1 = PlayerId::from(1);
+
+        let land = Coordinates::new(3, 0);
+        assert_eq!(
+            Err(IllegalOperation::IllegalPlacement(TileLocation::OnMars(land))),
+            game.standard_project_operations(p1, &StandardProject::Aquifer(land)),
+        );
+
+        // The space reserved for an ocean comes with 2 steel.
+        let ocean = Coordinates::new(5, -1);
+        game.play_standard_project(p1, &StandardProject::Aquifer(ocean)).unwrap();
+        assert!(game.board.oceans.contains(&ocean));
+        assert_eq!(22, game.players[&p1].resources[Resource::Megacredits]);
+        assert_eq!(2, game.players[&p1].resources[Resource::Steel]);
+        assert_eq!(DEFAULT_STARTING_TERRAFORM_RATING + 1, game.players[&p1].terraform_rating);
+    }
+
+    #[test]
+    fn asteroids_that_reach_zero_degrees_need_an_ocean_placement() {
+        let p1 = PlayerStateBuilder::new(1)
+            .with_resources(20, 0, 0, 0, 0, 0)
+            .build();
+        let mut game = make_game(vec![p1]);
+        game.board.temperature = -2;
+        let p1 = PlayerId::from(1);
+
+        assert_eq!(
+            Err(IllegalOperation::PlacementRequired),
+            game.standard_project_operations(p1, &StandardProject::Asteroid(None)),
+        );
+
+        let ocean = Coordinates::new(5, -1);
+        game.play_standard_project(p1, &StandardProject::Asteroid(Some(ocean))).unwrap();
+        assert_eq!(0, game.board.temperature);
+        assert!(game.board.oceans.contains(&ocean));
+        assert_eq!(DEFAULT_STARTING_TERRAFORM_RATING + 2, game.players[&p1].terraform_rating);
+        assert_eq!(6, game.players[&p1].resources[Resource::Megacredits]);
+    }
+
+    #[test]
+    fn sold_patents_go_to_the_discard_pile() {
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+        let predators = BASE_GAME_CARDS_BY_NAME["Predators"].clone();
+        let p1 = PlayerStateBuilder::new(1)
+            .with_cards_in_hand(vec![solar_power.clone(), predators.clone()])
+            .with_effects_override(vec![CardEffect::RebateForStandardProjects(3)])
+            .build();
+        let mut game = make_game(vec![p1]);
+        let p1 = PlayerId::from(1);
+
+        // Selling the same card twice takes two copies of it.
+        let not_in_hand = StandardProject::SellPatents(vec![solar_power.clone(); 2]);
+        assert_eq!(
+            Err(IllegalOperation::CardNotInHand("Solar Power".to_string())),
+            game.play_standard_project(p1, &not_in_hand),
+        );
+
+        let sell = StandardProject::SellPatents(vec![solar_power.clone(), predators.clone()]);
+        game.play_standard_project(p1, &sell).unwrap();
+        assert!(game.players[&p1].cards_in_hand.is_empty());
+        assert_eq!(vec![solar_power, predators], game.discard_pile);
+        // No rebate for selling patents.
+        assert_eq!(2, game.players[&p1].resources[Resource::Megacredits]);
+    }
+
+    #[test]
+    fn patents_are_sold_by_their_position_in_hand() {
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+        let predators = BASE_GAME_CARDS_BY_NAME["Predators"].clone();
+        let birds = BASE_GAME_CARDS_BY_NAME["Birds"].clone();
+        let p1 = PlayerStateBuilder::new(1)
+            .with_cards_in_hand(vec![solar_power.clone(), predators.clone(), birds.clone()])
+            .build();
+        let mut game = make_game(vec![p1]);
+        let p1 = PlayerId::from(1);
+
+        assert_eq!(Err(IllegalOperation::NoSuchHandIndex(3)), game.sell_patents(p1, &[0, 3]));
+        assert_eq!(Err(IllegalOperation::NoSuchHandIndex(2)), game.sell_patents(p1, &[2, 2]));
+        assert_eq!(3, game.players[&p1].cards_in_hand.len());
+
+        game.sell_patents(p1, &[2, 0]).unwrap();
+        assert_eq!(vec![predators], game.players[&p1].cards_in_hand);
+        assert_eq!(vec![birds, solar_power], game.discard_pile);
+        assert_eq!(2, game.players[&p1].resources[Resource::Megacredits]);
+    }
+
+    #[test]
+    fn turn_actions_refer_to_cards_by_name() {
+        let action = TurnAction::PlayCard(
+            BASE_GAME_CARDS_BY_NAME["Predators"].clone(),
+            vec![Coordinates::new(1, -1)],
+        );
+
+        let json = serde_json::to_string(&action).unwrap();
+        assert_eq!(r#"{"PlayCard":["Predators",[{"x":1,"y":-1}]]}"#, json);
+        assert_eq!(action, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn player_state_round_trips_with_card_references() {
+        let predators = BASE_GAME_CARDS_BY_NAME["Predators"].clone();
+        let player = PlayerStateBuilder::new(1)
+            .with_played_cards(vec![predators.clone()])
+            .with_cards_in_hand(vec![BASE_GAME_CARDS_BY_NAME["Solar Power"].clone()])
+            .with_card_resources(btreemap! { (predators.clone(), CardResource::Animal) => 3 })
+            .with_tapped_cards(vec![predators])
+            .build();
+
+        let json = serde_json::to_string(&player).unwrap();
+        assert!(!json.contains("immediate_impacts"));
+
+        let parsed: PlayerState = serde_json::from_str(&json).unwrap();
+        assert_eq!(player, parsed);
+    }
+
+    #[test]
+    fn playing_a_card_pays_for_it_and_resolves_its_own_impacts() {
+        let game = import_position(
+            "player p1\nresources 30 2 0 0 0 0\nproduction 0 0 0 0 1 0\n\
+             hand Domed Crater; Space Station",
+        )
+        .unwrap();
+        let mut player_state = game.players[&PlayerId::from(1)].clone();
+        let domed_crater = BASE_GAME_CARDS_BY_NAME["Domed Crater"].clone();
+        let space_station = BASE_GAME_CARDS_BY_NAME["Space Station"].clone();
+
+        // The steel pays for 4 of the 24 M$.
+        let follow_ups = player_state.play_card(&game.board, &domed_crater).unwrap();
+        assert_eq!(
+            vec![FollowUpChoice::Placement(
+                domed_crater.immediate_impacts[1].clone()
+            )],
+            follow_ups
+        );
+        assert_eq!(10, player_state.resources[Resource::Megacredits]);
+        assert_eq!(0, player_state.resources[Resource::Steel]);
+        assert_eq!(3, player_state.resources[Resource::Plants]);
+        assert_eq!(3, player_state.production[Resource::Megacredits]);
+        assert_eq!(0, player_state.production[Resource::Energy]);
+        assert_eq!(vec![domed_crater.clone()], player_state.played_cards);
+        assert_eq!(vec![space_station.clone()], player_state.cards_in_hand);
+
+        // It is no longer in hand.
+        assert_eq!(None, player_state.play_card(&game.board, &domed_crater));
+
+        assert_eq!(
+            Some(vec![]),
+            player_state.play_card(&game.board, &space_station)
+        );
+        assert_eq!(0, player_state.resources[Resource::Megacredits]);
+        assert_eq!(space_station.effects, player_state.effects);
+        assert!(player_state.cards_in_hand.is_empty());
+    }
+
+    #[test]
+    fn cards_played_on_a_turn_place_their_tiles_and_raise_the_parameters() {
+        let mut game = import_position(
+            "oxygen 5\nplayer p1\nresources 60 2 0 0 0 0\nproduction 0 0 0 0 1 0\n\
+             hand Domed Crater; Towing A Comet",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let domed_crater = BASE_GAME_CARDS_BY_NAME["Domed Crater"].clone();
+        let towing_a_comet = BASE_GAME_CARDS_BY_NAME["Towing A Comet"].clone();
+        let play = |card: &Card, placements: Vec<Coordinates>| {
+            PlayerTurn::Play(TurnAction::PlayCard(card.clone(), placements), None)
+        };
+
+        // The city needs a space, and nothing changes without one.
+        let before = game.clone();
+        assert_eq!(
+            Err(IllegalOperation::PlacementRequired),
+            game.take_turn(p1, &play(&domed_crater, vec![]))
+        );
+        assert_eq!(before, game);
+
+        let city = Coordinates::new(3, -1);
+        let events = game
+            .take_turn(p1, &play(&domed_crater, vec![city]))
+            .unwrap();
+        assert!(events.contains(&GameEvent::CardPlayed(p1, domed_crater.clone())));
+        assert_eq!(
+            Some(&(CityKind::RegularCity, p1)),
+            game.board.cities.get(&TileLocation::OnMars(city))
+        );
+        let player = &game.players[&p1];
+        // The steel pays for 4 of the 24 M$.
+        assert_eq!(40, player.resources[Resource::Megacredits]);
+        assert_eq!(0, player.resources[Resource::Steel]);
+        assert_eq!(3, player.production[Resource::Megacredits]);
+        assert_eq!(0, player.production[Resource::Energy]);
+        assert_eq!(vec![domed_crater], player.played_cards);
+
+        // The comet's oxygen and ocean reach the board, and raise the terraform rating.
+        let ocean = Coordinates::new(5, -1);
+        game.take_turn(p1, &play(&towing_a_comet, vec![ocean, city]))
+            .unwrap_err();
+        game.take_turn(p1, &play(&towing_a_comet, vec![ocean]))
+            .unwrap();
+        assert_eq!(6, game.board.oxygen);
+        assert!(game.board.oceans.contains(&ocean));
+        let player = &game.players[&p1];
+        assert_eq!(22, player.terraform_rating);
+        assert_eq!(17, player.resources[Resource::Megacredits]);
+        assert!(player.cards_in_hand.is_empty());
+
+        // Cards the player no longer holds can't be played again.
+        assert_eq!(
+            Err(IllegalOperation::CardNotInHand(towing_a_comet.name.clone())),
+            game.take_turn(p1, &play(&towing_a_comet, vec![]))
+        );
+    }
+
+    #[test]
+    fn requirements_tolerances_stack_for_global_parameters() {
+        let game = import_position(
+            "oxygen 5\ntemperature -16\nplayer p1\nresources 50 0 0 0 0 0\n\
+             hand Windmills; Herbivores; Bushes; Arctic Algae; Special Design",
+        )
+        .unwrap();
+        let card = |name: &str| CORPORATE_GAME_CARDS_BY_NAME[name].clone();
+        let mut player_state = game.players[&PlayerId::from(1)].clone();
+        let playable = |player_state: &PlayerState, name: &str| {
+            player_state.can_play_card(&game.board, &card(name)).is_some()
+        };
+        assert!(!playable(&player_state, "Windmills"));
+        assert!(playable(&player_state, "Arctic Algae"));
+
+        // Like Inventrix, two steps either way: 7% oxygen is close enough, but -10 C is not.
+        player_state.effects = vec![CardEffect::GlobalRequirementsTolerance(2)];
+        assert!(playable(&player_state, "Windmills"));
+        assert!(!playable(&player_state, "Herbivores"));
+        assert!(!playable(&player_state, "Bushes"));
+
+        // Special Design's two more steps for the next card stack with them.
+        player_state
+            .play_card(&game.board, &card("Special Design"))
+            .unwrap();
+        assert_eq!(4, player_state.global_requirements_tolerance());
+        assert!(playable(&player_state, "Herbivores"));
+        assert!(playable(&player_state, "Bushes"));
+    }
+
+    #[test]
+    fn effects_override_replaces_the_effects_of_played_cards() {
+        let player_state = PlayerStateBuilder::new(1)
+            .with_played_cards(vec![BASE_GAME_CARDS_BY_NAME["Space Station"].clone()])
+            .with_effects_override(vec![CardEffect::IncreasedMetalsValue(1)])
+            .with_terraform_rating(25)
+            .build();
+
+        assert_eq!(vec![CardEffect::IncreasedMetalsValue(1)], player_state.effects);
+        assert_eq!(DEFAULT_STEEL_VALUE + 1, player_state.steel_value);
+        assert_eq!(DEFAULT_TITANIUM_VALUE + 1, player_state.titanium_value);
+        assert_eq!(25, player_state.terraform_rating);
+    }
+
+    #[test]
+    fn research_draft_packs_go_around_the_table() {
+        let mut game = import_position("seed 3\nplayer p1\nplayer p2\nplayer p3").unwrap();
+        game.generation = 2;
+        game.phase = GamePhase::Research;
+        let (p1, p2, p3) = (PlayerId::from(1), PlayerId::from(2), PlayerId::from(3));
+        game.start_research_draft().unwrap();
+        let pack = |game: &GameState, player_id| {
+            game.research_draft.as_ref().unwrap().seat(player_id).unwrap().pack.clone()
+        };
+        let p2_pack = pack(&game, p2);
+        assert_eq!(RESEARCH_PHASE_OFFER_SIZE, p2_pack.len());
+
+        for player_id in [p1, p2, p3] {
+            let card = pack(&game, player_id)[0].clone();
+            game.check_operation(&GameOperation::DraftCard(player_id, card.clone()))
+                .unwrap();
+            game.execute_operation(GameOperation::DraftCard(player_id, card.clone()));
+            if player_id == p1 {
+                assert_eq!(
+                    Err(IllegalOperation::AlreadyDraftedFromPack),
+                    game.check_operation(&GameOperation::DraftCard(p1, pack(&game, p1)[0].clone()))
+                );
+            }
+        }
+
+        // In even generations, packs go to the previous seat.
+        assert_eq!(p2_pack[1..].to_vec(), pack(&game, p1));
+        let not_in_pack = game.players[&p1].cards_in_hand[0].clone();
+        assert_eq!(
+            Err(IllegalOperation::CardNotInPack(not_in_pack.name.clone())),
+            game.check_operation(&GameOperation::DraftCard(p1, not_in_pack))
+        );
+
+        // The last card of each pack is handed over without a pick.
+        for _ in 0..2 {
+            for player_id in [p1, p2, p3] {
+                let card = pack(&game, player_id)[0].clone();
+                game.execute_operation(GameOperation::DraftCard(player_id, card));
+            }
+        }
+        let draft = game.research_draft.clone().unwrap();
+        assert!(draft.is_complete());
+        for seat in &draft.seats {
+            assert_eq!(RESEARCH_PHASE_OFFER_SIZE, seat.drafted.len());
+            assert_eq!(seat.drafted, game.players[&seat.player_id].cards_in_hand);
+        }
+
+        game.advance_phase();
+        assert_eq!(None, game.research_draft);
+    }
+
+    #[test]
+    fn production_follows_the_configured_steps() {
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 0, 3, 1)
+            .with_production(0, 0, 0, 0, 2, 0)
+            .with_terraform_rating(20)
+            .build();
+
+        let mut standard = player.clone();
+        standard.advance_generation(&ProductionStep::STANDARD);
+        assert_eq!(20, standard.resources[Resource::Megacredits]);
+        assert_eq!(2, standard.resources[Resource::Energy]);
+        assert_eq!(4, standard.resources[Resource::Heat]);
+
+        // A variant without the conversion keeps the energy, as does an effect.
+        let mut variant = player.clone();
+        variant.advance_generation(&[ProductionStep::Production]);
+        let mut with_effect = player;
+        with_effect.effects.push(CardEffect::EnergyKeptAtProduction);
+        with_effect.advance_generation(&ProductionStep::STANDARD);
+        for kept in [variant, with_effect] {
+            assert_eq!(5, kept.resources[Resource::Energy]);
+            assert_eq!(1, kept.resources[Resource::Heat]);
+        }
+    }
+
+    #[test]
+    fn production_never_leaves_megacredits_negative() {
+        let mut player = PlayerStateBuilder::new(1)
+            .with_resources(1, 0, 0, 0, 0, 0)
+            .with_production(-5, 0, 0, 0, 0, 0)
+            .with_terraform_rating(2)
+            .build();
+
+        // The income counts as a whole, even when production comes before the rating.
+        let mut reordered = player.clone();
+        reordered.advance_generation(&[
+            ProductionStep::Production,
+            ProductionStep::TerraformRatingIncome,
+        ]);
+        assert_eq!(0, reordered.resources[Resource::Megacredits]);
+
+        player.terraform_rating = 6;
+        player.advance_generation(&[
+            ProductionStep::Production,
+            ProductionStep::TerraformRatingIncome,
+        ]);
+        assert_eq!(2, player.resources[Resource::Megacredits]);
+    }
+
+    #[test]
+    fn test_victory_points_from_tags_count_own_card_tags() {
+        let played_cards: Vec<_> = [
+            BASE_GAME_CARDS_BY_NAME["Ganymede Colony"], // Jovian + 1VP/Jovian
+            BASE_GAME_CARDS_BY_NAME["Water Import From Europa"], // Jovian + 1VP/Jovian
+            BASE_GAME_CARDS_BY_NAME["Methane From Titan"], // Jovian + 2VP immediate
+            BASE_GAME_CARDS_BY_NAME["Tundra Farming"],  // 2VP immediate, not Jovian
+        ]
+        .iter()
+        .copied()
+        .cloned()
+        .collect();
+
+        let player_state = PlayerStateBuilder::new(1)
+            .with_played_cards(played_cards)
+            .build();
+
+        let board = make_base_game_board();
+
+        // cards are worth 10 points:
+        // 3 Jovian tags valued at 2VP per Jovian card + 4VP immediate
+        let points_from_cards: isize = 10;
+        let expected_points = (DEFAULT_STARTING_TERRAFORM_RATING as isize) + points_from_cards;
+        assert_eq!(
+            expected_points,
+            player_state.get_total_victory_points(&board)
+        );
+    }
+
+    #[test]
+    fn test_per_city_victory_points_count_all_player_cities_both_on_and_off_mars() {
+        let p1_played_cards: Vec<_> = [BASE_GAME_CARDS_BY_NAME["Immigration Shuttles"]]
+            .iter()
+            .copied()
+            .cloned()
+            .collect();
+
+        let p2_played_cards: Vec<_> = [BASE_GAME_CARDS_BY_NAME["Ganymede Colony"]]
+            .iter()
+            .copied()
+            .cloned()
+            .collect();
+
+        let p1_player_state = PlayerStateBuilder::new(1)
+            .with_played_cards(p1_played_cards)
+            .build();
+        let p2_player_state = PlayerStateBuilder::new(2)
+            .with_played_cards(p2_played_cards)
+            .build();
+
+        let board = MarsBoardBuilder::new()
+            .with_city(
+                Coordinates::new(0, 0),
+                CityKind::RegularCity,
+                p1_player_state.player_id,
+            )
+            .with_city(
+                TileLocation::OffMars(SpecialLocation::GanymedeColony),
+                CityKind::GanymedeColony,
+                p2_player_state.player_id,
+            )
+            .with_city(
+                Coordinates::new(5, -3),
+                CityKind::RegularCity,
+                p2_player_state.player_id,
+            )
+            .build();
+
+        // 1VP from immigration shuttles because of 3 cities in existence
+        assert_eq!(
+            1 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p1_player_state.get_total_victory_points(&board)
+        );
+
+        // 1VP from Ganymede Colony's Jovian tag
+        assert_eq!(
+            1 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p2_player_state.get_total_victory_points(&board)
+        );
+    }
+
+    #[test]
+    fn test_city_and_greneery_scoring() {
+        let p1_player_state = PlayerStateBuilder::new(1).build();
+        let p2_player_state = PlayerStateBuilder::new(2).build();
+
+        let board = MarsBoardBuilder::new()
+            .with_city(
+                Coordinates::new(0, 0),
+                CityKind::RegularCity,
+                p1_player_state.player_id,
+            )
+            .with_greenery(Coordinates::new(1, 0), p1_player_state.player_id)
+            .with_greenery(Coordinates::new(1, -1), p2_player_state.player_id)
+            .build();
+
+        // 1VP from the greenery, 2VP from the city adjacent to 2 greeneries
+        assert_eq!(
+            3 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p1_player_state.get_total_victory_points(&board)
+        );
+
+        // 1VP from the greenery
+        assert_eq!(
+            1 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p2_player_state.get_total_victory_points(&board)
+        );
+    }
+
+    #[test]
+    fn test_capital_city_scoring() {
+        let p1_played_cards: Vec<_> = [BASE_GAME_CARDS_BY_NAME["Capital"]]
+            .iter()
+            .copied()
+            .cloned()
+            .collect();
+
+        let p1_player_state = PlayerStateBuilder::new(1)
+            .with_played_cards(p1_played_cards)
+            .build();
+        let p2_player_state = PlayerStateBuilder::new(2).build();
+
+        let board = MarsBoardBuilder::new()
+            .with_city(
+                Coordinates::new(4, -5),
+                CityKind::Capital,
+                p1_player_state.player_id,
+            )
+            .with_greenery(Coordinates::new(3, -5), p2_player_state.player_id)
+            .with_greenery(Coordinates::new(4, -6), p2_player_state.player_id)
+            .with_ocean(Coordinates::new(5, -5))
+            .with_ocean(Coordinates::new(5, -6))
+            .with_ocean(Coordinates::new(4, -4))
+            .build();
+
+        // 3VP from the oceans adjacent to the capital, 2VP from the adjacent greeneries
+        assert_eq!(
+            5 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p1_player_state.get_total_victory_points(&board)
+        );
+
+        // 2VP from the greeneries
+        assert_eq!(
+            2 + DEFAULT_STARTING_TERRAFORM_RATING as isize,
+            p2_player_state.get_total_victory_points(&board)
+        );
+    }
+
+    #[test]
+    fn games_with_the_same_seed_and_decisions_are_identical() {
+        let play = |seed: u64| -> GameState {
+            let mut game = import_position(&format!("seed {}\nplayer p1\nplayer p2", seed)).unwrap();
+            let p1 = PlayerId::from(1);
+            let p2 = PlayerId::from(2);
+
+            game.execute_operation(GameOperation::DrawCards(p1, 10));
+            let hand = game.players[&p1].cards_in_hand.clone();
+            game.execute_operation(GameOperation::DiscardCards(p1, hand));
+
+            // Drawing the rest of the deck and then some reshuffles the discard pile.
+            let remaining = game.draw_deck.len();
+            game.execute_operation(GameOperation::DrawCards(p2, remaining + 5));
+            game
+        };
+
+        let first = serde_json::to_string(&play(99)).unwrap();
+        let second = serde_json::to_string(&play(99)).unwrap();
+        assert_eq!(first, second);
+        assert_ne!(first, serde_json::to_string(&play(100)).unwrap());
+    }
+
+    #[test]
+    fn replaced_tiles_change_hands_and_scores() {
+        let mut game = import_position(
+            "greenery 4,-3 p1\n\
+             city 3,-2 p2\n\
+             player p1\n\
+             player p2",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let greenery = TileLocation::OnMars(Coordinates::new(4, -3));
+        let city = TileLocation::OnMars(Coordinates::new(3, -2));
+        assert_eq!(1, game.players[&p1].score_breakdown(&game.board).greeneries);
+        assert_eq!(1, game.players[&p2].score_breakdown(&game.board).city_adjacency);
+
+        // The greenery goes over to p2, whose city it is next to.
+        let operations = game
+            .tile_replacement_operations(&TileStatus::Greenery(greenery.clone(), p2))
+            .unwrap();
+        let events = game.execute_checked(operations).unwrap();
+        assert_eq!(
+            vec![
+                GameEvent::TileRemoved(TileStatus::Greenery(greenery.clone(), p1)),
+                GameEvent::GreeneryPlaced(p2, Coordinates::new(4, -3)),
+            ],
+            events
+        );
+        assert_eq!(Some(&p2), game.board.greeneries.get(&Coordinates::new(4, -3)));
+        assert_eq!(0, game.players[&p1].score_breakdown(&game.board).greeneries);
+        assert_eq!(1, game.players[&p2].score_breakdown(&game.board).greeneries);
+        assert_eq!(AwardStandings::compute(&game), game.award_standings);
+
+        // Removing the city leaves the space empty, and the greenery worth nothing more.
+        let operations = game
+            .tile_replacement_operations(&TileStatus::Empty(city.clone().into()))
+            .unwrap();
+        game.execute_checked(operations).unwrap();
+        assert!(game.board.cities.is_empty());
+        assert_eq!(0, game.players[&p2].score_breakdown(&game.board).city_adjacency);
+        assert_eq!(AwardStandings::compute(&game), game.award_standings);
+
+        assert_eq!(
+            Err(IllegalOperation::NoTileToRemove(city.clone())),
+            game.tile_replacement_operations(&TileStatus::City(city, CityKind::RegularCity, p1))
+        );
+    }
+}
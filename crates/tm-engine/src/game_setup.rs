@@ -0,0 +1,291 @@
+//! Game setup: each player is dealt corporations and project cards, and chooses one of
+//! the corporations and which of the cards to keep, before the first generation begins.
+//!
+//! The chosen corporation's starting resources, production, and effects are granted
+//! as it is played. Keeping a card costs the same as buying it in the research phase,
+//! except for players of the beginner corporation, who keep all of their cards for free.
+
+use rand::seq::SliceRandom;
+
+use crate::{
+    card::Card,
+    corporation::Corporation,
+    event::GameEvent,
+    game::{GameOperation, GamePhase, GameState, IllegalOperation, PlayerId},
+    resource::Resource,
+};
+
+pub const CORPORATIONS_DEALT: usize = 2;
+pub const STARTING_HAND_SIZE: usize = 10;
+
+impl GameState {
+    /// Start the setup phase: every player is dealt corporations to choose from,
+    /// drawn from the given ones without repeats, along with their starting hand.
+    /// If the game's options allow it, the beginner corporation is offered as well.
+    pub fn deal_corporations(
+        &mut self,
+        corporations: &[Corporation],
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        if corporations.len() < CORPORATIONS_DEALT * self.players.len() {
+            return Err(IllegalOperation::NotEnoughCorporations);
+        }
+
+        let mut scratch_game = self.clone();
+        let mut shuffled = corporations.to_vec();
+        shuffled.shuffle(&mut scratch_game.rng);
+
+        let mut events = vec![];
+        let player_ids: Vec<PlayerId> = scratch_game.players.keys().copied().collect();
+        for (player_id, dealt) in player_ids
+            .into_iter()
+            .zip(shuffled.chunks(CORPORATIONS_DEALT))
+        {
+            let mut offer = dealt.to_vec();
+            if scratch_game.options.beginner_corporations {
+                offer.push(Corporation::beginner());
+            }
+            scratch_game
+                .corporation_offers
+                .insert(player_id, offer.clone());
+            events.push(GameEvent::CorporationsDealt(player_id, offer));
+            events.extend(scratch_game.execute_checked(vec![GameOperation::DrawCards(
+                player_id,
+                STARTING_HAND_SIZE,
+            )])?);
+        }
+        scratch_game.phase = GamePhase::Setup;
+
+        *self = scratch_game;
+        Ok(events)
+    }
+
+    /// The player plays one of the corporations they were dealt, and pays for the cards
+    /// of their starting hand that they keep. The rest of the hand is discarded.
+    /// With the beginner corporation, the whole hand is kept for free instead.
+    pub fn corporation_choice_operations(
+        &self,
+        player_id: PlayerId,
+        corporation: &Corporation,
+        kept_cards: &[Card],
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+
+        let purchase = if corporation.is_beginner() {
+            vec![]
+        } else {
+            self.card_purchase_operations(player_id, &player.cards_in_hand, kept_cards)?
+        };
+
+        let mut starting_resources: Vec<(Resource, isize)> = vec![(
+            Resource::Megacredits,
+            corporation.starting_megacredits as isize,
+        )];
+        starting_resources.extend(
+            corporation
+                .starting_resources
+                .iter()
+                .map(|(resource, amount)| (*resource, *amount as isize)),
+        );
+
+        let mut operations = vec![
+            GameOperation::PlayCorporation(player_id, corporation.clone()),
+            GameOperation::ChangeResources(player_id, starting_resources.into_iter().collect()),
+        ];
+        if !corporation.starting_production.is_empty() {
+            operations.push(GameOperation::ChangeProduction(
+                player_id,
+                corporation.starting_production.clone(),
+            ));
+        }
+        operations.extend(
+            corporation
+                .effects
+                .iter()
+                .map(|effect| GameOperation::AddEffect(player_id, effect.clone())),
+        );
+        operations.extend(purchase);
+        Ok(operations)
+    }
+
+    /// Resolve the player's choice of corporation and starting hand. Once every player
+    /// has chosen, the first generation begins with its action phase.
+    pub fn choose_corporation(
+        &mut self,
+        player_id: PlayerId,
+        corporation: &Corporation,
+        kept_cards: &[Card],
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let operations = self.corporation_choice_operations(player_id, corporation, kept_cards)?;
+        let mut scratch_game = self.clone();
+        let events = scratch_game.execute_checked(operations)?;
+        if scratch_game.corporation_offers.is_empty() {
+            scratch_game.advance_phase();
+        }
+
+        *self = scratch_game;
+        Ok(events)
+    }
+
+    pub(crate) fn check_corporation_choice(
+        &self,
+        player_id: PlayerId,
+        corporation: &Corporation,
+    ) -> Result<(), IllegalOperation> {
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+        if player.corporation.is_some() {
+            return Err(IllegalOperation::CorporationAlreadyChosen);
+        }
+
+        let offered = self
+            .corporation_offers
+            .get(&player_id)
+            .is_some_and(|offer| offer.contains(corporation));
+        if !offered {
+            return Err(IllegalOperation::CorporationNotOffered(
+                corporation.name.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply_corporation_choice(
+        &mut self,
+        player_id: PlayerId,
+        corporation: Corporation,
+    ) -> Vec<GameEvent> {
+        self.corporation_offers.remove(&player_id);
+        let player = self.players.get_mut(&player_id).unwrap();
+        player.corporation = Some(corporation.clone());
+
+        vec![GameEvent::CorporationPlayed(player_id, corporation)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{CardEffect, CardTag},
+        corporation::{CORPORATIONS, CORPORATIONS_BY_NAME},
+        game::ActiveTags,
+        position::import_position,
+    };
+
+    fn dealt_game() -> GameState {
+        let mut game = import_position("seed 11\nplayer p1\nplayer p2").unwrap();
+        for player in game.players.values_mut() {
+            player.resources[Resource::Megacredits] = 0;
+        }
+        game.deal_corporations(&CORPORATIONS).unwrap();
+        game
+    }
+
+    #[test]
+    fn every_player_is_dealt_distinct_corporations_and_a_starting_hand() {
+        let game = dealt_game();
+        assert_eq!(GamePhase::Setup, game.phase);
+        assert_eq!(None, game.current_player());
+
+        let mut dealt: Vec<&str> = vec![];
+        for (player_id, player) in &game.players {
+            let offer = &game.corporation_offers[player_id];
+            assert_eq!(CORPORATIONS_DEALT, offer.len());
+            dealt.extend(offer.iter().map(|corporation| corporation.name.as_str()));
+            assert_eq!(STARTING_HAND_SIZE, player.cards_in_hand.len());
+        }
+        dealt.sort_unstable();
+        dealt.dedup();
+        assert_eq!(CORPORATIONS_DEALT * game.players.len(), dealt.len());
+    }
+
+    #[test]
+    fn the_chosen_corporation_sets_up_the_player_before_the_first_generation() {
+        let mut game = dealt_game();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+
+        let mut game_with_inventrix = game.clone();
+        let inventrix = CORPORATIONS_BY_NAME["Inventrix"].clone();
+        game_with_inventrix
+            .corporation_offers
+            .insert(p1, vec![inventrix.clone()]);
+        let kept = game.players[&p1].cards_in_hand[..2].to_vec();
+        game_with_inventrix
+            .choose_corporation(p1, &inventrix, &kept)
+            .unwrap();
+        let player = &game_with_inventrix.players[&p1];
+        assert_eq!(45 - 6, player.resources[Resource::Megacredits]);
+        assert_eq!(kept, player.cards_in_hand);
+        assert_eq!(1, player.active_tag_count(CardTag::Science));
+        assert!(player
+            .effects
+            .contains(&CardEffect::GlobalRequirementsTolerance(2)));
+        assert_eq!(GamePhase::Setup, game_with_inventrix.phase);
+
+        for player_id in [p1, p2] {
+            let corporation = game.corporation_offers[&player_id][0].clone();
+            game.choose_corporation(player_id, &corporation, &[])
+                .unwrap();
+            assert_eq!(Some(corporation), game.players[&player_id].corporation);
+        }
+        assert!(game.corporation_offers.is_empty());
+        assert_eq!((1, GamePhase::Action), (game.generation, game.phase));
+        assert_eq!(Some(p1), game.current_player());
+    }
+
+    #[test]
+    fn only_an_offered_corporation_may_be_chosen_and_only_once() {
+        let mut game = dealt_game();
+        let p1 = PlayerId::from(1);
+        let not_offered = CORPORATIONS
+            .iter()
+            .find(|corporation| !game.corporation_offers[&p1].contains(corporation))
+            .unwrap()
+            .clone();
+        assert_eq!(
+            Err(IllegalOperation::CorporationNotOffered(
+                not_offered.name.clone()
+            )),
+            game.choose_corporation(p1, &not_offered, &[]).map(|_| ())
+        );
+
+        let offered = game.corporation_offers[&p1][0].clone();
+        game.choose_corporation(p1, &offered, &[]).unwrap();
+        assert_eq!(
+            Err(IllegalOperation::CorporationAlreadyChosen),
+            game.check_operation(&GameOperation::PlayCorporation(p1, offered))
+        );
+    }
+
+    #[test]
+    fn the_beginner_corporation_keeps_the_whole_hand_for_free() {
+        let p1 = PlayerId::from(1);
+        let beginner = Corporation::beginner();
+        let mut game = dealt_game();
+        assert_eq!(
+            Err(IllegalOperation::CorporationNotOffered(
+                beginner.name.clone()
+            )),
+            game.choose_corporation(p1, &beginner, &[]).map(|_| ())
+        );
+
+        let mut game = import_position("seed 11\nplayer p1\nplayer p2").unwrap();
+        game.options.beginner_corporations = true;
+        game.players.get_mut(&p1).unwrap().resources[Resource::Megacredits] = 0;
+        game.deal_corporations(&CORPORATIONS).unwrap();
+        assert!(game.corporation_offers[&p1].contains(&beginner));
+
+        let hand = game.players[&p1].cards_in_hand.clone();
+        game.choose_corporation(p1, &beginner, &[]).unwrap();
+        let player = &game.players[&p1];
+        assert_eq!(42, player.resources[Resource::Megacredits]);
+        assert_eq!(hand, player.cards_in_hand);
+        assert!(player.effects.is_empty());
+    }
+}
@@ -0,0 +1,202 @@
+//! Per-player statistics of a game, accumulated from its events while it is played:
+//! megacredits spent on cards, tiles placed, and terraform rating gained by its source.
+//!
+//! Events do not say why they happened, so spending and terraform rating are attributed
+//! from the events just before them. A megacredit payment counts as spent on a card
+//! if the player's very next event plays the card, and a raise of the terraform rating
+//! is credited to the global parameter raised last before it, or to other sources,
+//! e.g. the card's own impacts, if none was. Oceans are not placed by anyone in particular,
+//! so they count for the player whose terraform rating they raise.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::{GameEvent, GameObserver},
+    game::{GameState, PlayerId},
+    resource::Resource,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TerraformSource {
+    Temperature,
+    Oxygen,
+    Ocean,
+    Other,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerStatistics {
+    pub cards_played: usize,
+    pub megacredits_spent_on_cards: usize,
+
+    pub cities_placed: usize, // including cities off Mars
+    pub greeneries_placed: usize,
+    pub special_tiles_placed: usize,
+    pub oceans_placed: usize,
+
+    pub terraform_rating_gained: BTreeMap<TerraformSource, usize>,
+}
+
+impl PlayerStatistics {
+    pub fn tiles_placed(&self) -> usize {
+        self.cities_placed + self.greeneries_placed + self.special_tiles_placed + self.oceans_placed
+    }
+
+    pub fn total_terraform_rating_gained(&self) -> usize {
+        self.terraform_rating_gained.values().sum()
+    }
+}
+
+/// Keeps every player's `PlayerStatistics` up to date by observing the game's events.
+///
+/// Only public information is used, so the statistics can be shown to anyone.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameStatistics {
+    pub players: BTreeMap<PlayerId, PlayerStatistics>,
+
+    // the megacredits paid by a player in the previous event
+    #[serde(skip)]
+    last_payment: Option<(PlayerId, usize)>,
+    // the global parameter raised since the last raise of a terraform rating, if any
+    #[serde(skip)]
+    last_raised: Option<TerraformSource>,
+}
+
+impl GameStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The statistics of a game from its whole event log, e.g. one loaded from a `GameStore`.
+    pub fn from_events(events: &[GameEvent]) -> Self {
+        let mut statistics = Self::new();
+        for event in events {
+            statistics.observe(event);
+        }
+        statistics
+    }
+
+    pub fn observe(&mut self, event: &GameEvent) {
+        let last_payment = self.last_payment.take();
+        match event {
+            GameEvent::ResourcesChanged(player_id, changes) => {
+                let megacredits = changes.get(&Resource::Megacredits).copied().unwrap_or(0);
+                if megacredits < 0 {
+                    self.last_payment = Some((*player_id, (-megacredits) as usize));
+                }
+            }
+            GameEvent::CardPlayed(player_id, _) => {
+                let player = self.player(*player_id);
+                player.cards_played += 1;
+                if let Some((payer, megacredits)) = last_payment {
+                    if payer == *player_id {
+                        player.megacredits_spent_on_cards += megacredits;
+                    }
+                }
+                self.last_raised = None;
+            }
+            GameEvent::CityPlaced(player_id, _, _) => self.player(*player_id).cities_placed += 1,
+            GameEvent::GreeneryPlaced(player_id, _) => {
+                self.player(*player_id).greeneries_placed += 1
+            }
+            GameEvent::SpecialTilePlaced(player_id, _, _) => {
+                self.player(*player_id).special_tiles_placed += 1
+            }
+            GameEvent::OceanPlaced(_) => self.last_raised = Some(TerraformSource::Ocean),
+            GameEvent::TemperatureRaised(_) => {
+                self.last_raised = Some(TerraformSource::Temperature)
+            }
+            GameEvent::OxygenRaised(_) => self.last_raised = Some(TerraformSource::Oxygen),
+            GameEvent::TerraformRatingRaised(player_id, amount) => {
+                let source = self.last_raised.take().unwrap_or(TerraformSource::Other);
+                let player = self.player(*player_id);
+                *player.terraform_rating_gained.entry(source).or_default() += amount;
+                if source == TerraformSource::Ocean {
+                    player.oceans_placed += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn player(&mut self, player_id: PlayerId) -> &mut PlayerStatistics {
+        self.players.entry(player_id).or_default()
+    }
+}
+
+impl GameObserver for GameStatistics {
+    fn on_event(&mut self, _game: &GameState, event: &GameEvent) {
+        self.observe(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::Coordinates,
+        card::{CityKind, BASE_GAME_CARDS_BY_NAME},
+        event::EventBus,
+        game::{GameOperation, PlayerId, PlayerStateBuilder},
+        game_stats::{GameStatistics, TerraformSource},
+        resource::Resource,
+        test_utils::make_game,
+    };
+
+    #[test]
+    fn spending_tiles_and_terraform_rating_are_attributed_to_their_player() {
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let mut game = make_game(vec![
+            PlayerStateBuilder::new(1)
+                .with_resources(40, 0, 0, 0, 0, 0)
+                .with_cards_in_hand(vec![BASE_GAME_CARDS_BY_NAME["Solar Power"].clone()])
+                .build(),
+            PlayerStateBuilder::new(2)
+                .with_resources(40, 0, 0, 0, 0, 0)
+                .build(),
+        ]);
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(GameStatistics::new());
+
+        let operations = vec![
+            // p1 plays a card.
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -11 }),
+            GameOperation::PutCardIntoPlay(p1, BASE_GAME_CARDS_BY_NAME["Solar Power"].clone()),
+            // p2 builds a city with the standard project, which is not spending on cards.
+            GameOperation::ChangeResources(p2, btreemap! { Resource::Megacredits => -25 }),
+            GameOperation::PlaceCityTile(p2, CityKind::RegularCity, Coordinates::new(0, 0).into()),
+            // p1 places an ocean, and p2 raises the temperature.
+            GameOperation::PlaceOcean(Coordinates::new(5, -5)),
+            GameOperation::RaiseTerraformRating(p1, 1),
+            GameOperation::RaiseTemperature,
+            GameOperation::RaiseTerraformRating(p2, 1),
+            GameOperation::RaiseTerraformRating(p2, 2),
+        ];
+        for operation in operations {
+            bus.execute(&mut game, operation);
+        }
+
+        let statistics = bus.observer::<GameStatistics>(handle).unwrap();
+        let p1_statistics = &statistics.players[&p1];
+        assert_eq!(1, p1_statistics.cards_played);
+        assert_eq!(11, p1_statistics.megacredits_spent_on_cards);
+        assert_eq!(1, p1_statistics.oceans_placed);
+        assert_eq!(
+            btreemap! { TerraformSource::Ocean => 1 },
+            p1_statistics.terraform_rating_gained
+        );
+
+        let p2_statistics = &statistics.players[&p2];
+        assert_eq!(0, p2_statistics.megacredits_spent_on_cards);
+        assert_eq!(1, p2_statistics.tiles_placed());
+        assert_eq!(
+            btreemap! { TerraformSource::Temperature => 1, TerraformSource::Other => 2 },
+            p2_statistics.terraform_rating_gained
+        );
+        assert_eq!(3, p2_statistics.total_terraform_rating_gained());
+    }
+}
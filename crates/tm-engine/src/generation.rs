@@ -0,0 +1,328 @@
+//! Playing whole generations: the research phase, turns around the table until every
+//! player has passed, and the production phase, with the decisions left to the players.
+//!
+//! The first generation's research deals every player their starting hand instead,
+//! unless the game began with a setup phase, where the starting hands were dealt already.
+
+use maplit::btreemap;
+
+use crate::{
+    card::Card,
+    corporation::Corporation,
+    event::GameEvent,
+    game::{
+        GameOperation, GamePhase, GameState, IllegalOperation, PlayerId, PlayerTurn,
+        CARD_PURCHASE_COST, RESEARCH_PHASE_OFFER_SIZE,
+    },
+    game_setup::STARTING_HAND_SIZE,
+    resource::Resource,
+};
+
+/// Makes the decisions of every player in the game, e.g. a bot, or a script of moves.
+pub trait GenerationPlayer {
+    /// Which of the dealt corporations the player plays at setup,
+    /// and which cards of their starting hand they keep.
+    fn choose_corporation(
+        &mut self,
+        game: &GameState,
+        player_id: PlayerId,
+        offered: &[Corporation],
+    ) -> (Corporation, Vec<Card>);
+
+    /// Which card the player drafts from the pack in front of them,
+    /// when the research phase is drafted.
+    fn draft_card(&mut self, game: &GameState, player_id: PlayerId, pack: &[Card]) -> Card;
+
+    /// Which of the cards offered in the research phase the player buys.
+    fn buy_cards(&mut self, game: &GameState, player_id: PlayerId, offered: &[Card]) -> Vec<Card>;
+
+    /// The player's next turn in the action phase.
+    fn take_turn(&mut self, game: &GameState, player_id: PlayerId) -> PlayerTurn;
+}
+
+impl GameState {
+    /// Play out the rest of the current generation, from whichever phase it is in:
+    /// every player chooses their corporation at setup, or else researches
+    /// (drafting the cards first, if the game's options say so),
+    /// then they take turns until all of them have passed,
+    /// and production starts the next generation, with the next first player.
+    /// Nothing happens once Mars is terraformed and only the final greeneries remain.
+    ///
+    /// An illegal decision ends the generation early, with the game as it was
+    /// before the research or turn it was made in.
+    pub fn play_generation(
+        &mut self,
+        players: &mut impl GenerationPlayer,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let generation = self.generation;
+        let mut events = vec![];
+        while self.generation == generation {
+            match self.phase {
+                GamePhase::Setup => {
+                    let player_ids: Vec<PlayerId> =
+                        self.corporation_offers.keys().copied().collect();
+                    for player_id in player_ids {
+                        let offered = &self.corporation_offers[&player_id];
+                        let (corporation, kept_cards) =
+                            players.choose_corporation(self, player_id, offered);
+                        events.extend(self.choose_corporation(
+                            player_id,
+                            &corporation,
+                            &kept_cards,
+                        )?);
+                    }
+                    if self.phase == GamePhase::Setup {
+                        self.advance_phase();
+                    }
+                }
+                GamePhase::Research => {
+                    if self.drafts_research() {
+                        events.extend(self.draft(players)?);
+                    }
+                    let player_ids: Vec<PlayerId> = self.players.keys().copied().collect();
+                    for player_id in player_ids {
+                        events.extend(self.research(players, player_id)?);
+                    }
+                    self.advance_phase();
+                }
+                GamePhase::Action => {
+                    let player_id = self.current_player().unwrap();
+                    let turn = players.take_turn(self, player_id);
+                    events.extend(self.take_turn(player_id, &turn)?);
+                }
+                GamePhase::Production => events.extend(self.advance_phase()),
+                GamePhase::FinalGreeneries | GamePhase::FinalScoring => break,
+            }
+        }
+        Ok(events)
+    }
+
+    /// How many cards each player is offered in this generation's research phase.
+    pub fn research_offer_size(&self) -> usize {
+        if self.generation <= 1 {
+            STARTING_HAND_SIZE
+        } else {
+            RESEARCH_PHASE_OFFER_SIZE
+        }
+    }
+
+    /// The player pays for the cards they buy out of the ones they were offered,
+    /// which are already in their hand, and discards the rest.
+    pub fn card_purchase_operations(
+        &self,
+        player_id: PlayerId,
+        offered: &[Card],
+        bought: &[Card],
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let mut not_bought = offered.to_vec();
+        for card in bought {
+            match not_bought
+                .iter()
+                .position(|offered_card| offered_card == card)
+            {
+                Some(index) => {
+                    not_bought.remove(index);
+                }
+                None => return Err(IllegalOperation::CardNotInHand(card.name.clone())),
+            }
+        }
+
+        let mut operations = vec![];
+        if !bought.is_empty() {
+            let cost = (bought.len() * CARD_PURCHASE_COST) as isize;
+            operations.push(GameOperation::ChangeResources(
+                player_id,
+                btreemap! { Resource::Megacredits => -cost },
+            ));
+        }
+        if !not_bought.is_empty() {
+            operations.push(GameOperation::DiscardCards(player_id, not_bought));
+        }
+        Ok(operations)
+    }
+
+    // Every player picks from the pack in front of them, until all the packs are used up.
+    fn draft(
+        &mut self,
+        players: &mut impl GenerationPlayer,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let mut scratch_game = self.clone();
+        let mut events = scratch_game.start_research_draft()?;
+        while let Some(seat) = scratch_game.research_draft.as_ref().and_then(|draft| {
+            draft
+                .seats
+                .iter()
+                .find(|seat| !seat.has_picked && !seat.pack.is_empty())
+                .cloned()
+        }) {
+            let card = players.draft_card(&scratch_game, seat.player_id, &seat.pack);
+            events.extend(
+                scratch_game.execute_checked(vec![GameOperation::DraftCard(seat.player_id, card)])?,
+            );
+        }
+
+        *self = scratch_game;
+        Ok(events)
+    }
+
+    // The player is offered the top cards of the deck, or the cards they drafted,
+    // and pays for the ones they keep.
+    fn research(
+        &mut self,
+        players: &mut impl GenerationPlayer,
+        player_id: PlayerId,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let mut scratch_game = self.clone();
+        let mut events = vec![];
+        let offered = match self
+            .research_draft
+            .as_ref()
+            .and_then(|draft| draft.seat(player_id))
+        {
+            Some(seat) => seat.drafted.clone(),
+            None => {
+                let available_cards = self.draw_deck.len() + self.discard_pile.len();
+                let offer_size = self.research_offer_size().min(available_cards);
+                events.extend(
+                    scratch_game
+                        .execute_checked(vec![GameOperation::DrawCards(player_id, offer_size)])?,
+                );
+                let hand = &scratch_game.players[&player_id].cards_in_hand;
+                hand[(hand.len() - offer_size)..].to_vec()
+            }
+        };
+        let bought = players.buy_cards(&scratch_game, player_id, &offered);
+
+        let operations = scratch_game.card_purchase_operations(player_id, &offered, &bought)?;
+        events.extend(scratch_game.execute_checked(operations)?);
+
+        *self = scratch_game;
+        Ok(events)
+    }
+
+    /// One or two actions, or a pass, after which the turn goes to the next player.
+    /// Nothing changes if either action is illegal.
+    pub fn take_turn(
+        &mut self,
+        player_id: PlayerId,
+        turn: &PlayerTurn,
+    ) -> Result<Vec<GameEvent>, IllegalOperation> {
+        let mut scratch_game = self.clone();
+        let mut events = vec![];
+        if let PlayerTurn::Play(action, second_action) = turn {
+            for action in std::iter::once(action).chain(second_action) {
+                events.extend(scratch_game.play_turn_action(player_id, action)?);
+            }
+        }
+        scratch_game.end_turn(turn);
+
+        *self = scratch_game;
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{PlayerStateBuilder, StandardProject, TurnAction};
+    use crate::position::import_position;
+    use crate::test_utils::make_game;
+
+    // Buys the first card offered, and builds one power plant per generation.
+    struct PowerPlanter;
+
+    impl GenerationPlayer for PowerPlanter {
+        fn choose_corporation(
+            &mut self,
+            _: &GameState,
+            _: PlayerId,
+            offered: &[Corporation],
+        ) -> (Corporation, Vec<Card>) {
+            (offered[0].clone(), vec![])
+        }
+
+        fn draft_card(&mut self, _: &GameState, _: PlayerId, pack: &[Card]) -> Card {
+            pack[0].clone()
+        }
+
+        fn buy_cards(&mut self, _: &GameState, _: PlayerId, offered: &[Card]) -> Vec<Card> {
+            offered.iter().take(1).cloned().collect()
+        }
+
+        fn take_turn(&mut self, game: &GameState, player_id: PlayerId) -> PlayerTurn {
+            if game.players[&player_id].production[Resource::Energy] < game.generation as isize {
+                let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+                PlayerTurn::Play(power_plant, None)
+            } else {
+                PlayerTurn::Pass
+            }
+        }
+    }
+
+    #[test]
+    fn generations_go_from_research_through_production() {
+        let mut game = import_position("seed 7\nplayer p1\nplayer p2").unwrap();
+        for player in game.players.values_mut() {
+            player.resources[Resource::Megacredits] = 30;
+        }
+        game.phase = GamePhase::Research;
+        let deck_size = game.draw_deck.len();
+
+        game.play_generation(&mut PowerPlanter).unwrap();
+        assert_eq!((2, GamePhase::Research), (game.generation, game.phase));
+        assert_eq!(Some(PlayerId::from(2)), game.first_player());
+        // The first research deals the starting hands.
+        assert_eq!(deck_size - 2 * STARTING_HAND_SIZE, game.draw_deck.len());
+        assert_eq!(2 * (STARTING_HAND_SIZE - 1), game.discard_pile.len());
+        for player in game.players.values() {
+            assert_eq!(1, player.cards_in_hand.len());
+            assert_eq!(1, player.production[Resource::Energy]);
+            // 30 M$, less 3 for the card and 11 for the power plant, plus production.
+            assert_eq!(16 + 20, player.resources[Resource::Megacredits]);
+            assert_eq!(1, player.resources[Resource::Energy]);
+        }
+    }
+
+    #[test]
+    fn drafted_cards_are_the_research_offer() {
+        let mut game = import_position("seed 7\nplayer p1\nplayer p2").unwrap();
+        for player in game.players.values_mut() {
+            player.resources[Resource::Megacredits] = 30;
+        }
+        game.options.research_draft = true;
+        game.generation = 2;
+        game.phase = GamePhase::Research;
+        let deck_size = game.draw_deck.len();
+
+        let events = game.play_generation(&mut PowerPlanter).unwrap();
+        assert_eq!(
+            2 * RESEARCH_PHASE_OFFER_SIZE,
+            events
+                .iter()
+                .filter(|event| matches!(event, GameEvent::CardDrafted(..)))
+                .count()
+        );
+        assert_eq!(deck_size - 8, game.draw_deck.len());
+        assert_eq!(6, game.discard_pile.len());
+        for player in game.players.values() {
+            assert_eq!(1, player.cards_in_hand.len());
+        }
+        assert_eq!(None, game.research_draft);
+    }
+
+    #[test]
+    fn illegal_turns_leave_the_game_as_it_was() {
+        let players = vec![PlayerStateBuilder::new(1).build()];
+        let mut game = make_game(players);
+        let players_before = game.players.clone();
+
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(
+                Resource::Megacredits
+            )),
+            game.play_generation(&mut PowerPlanter).map(|_| ())
+        );
+        assert_eq!(players_before, game.players);
+        assert_eq!(Some(PlayerId::from(1)), game.current_player());
+    }
+}
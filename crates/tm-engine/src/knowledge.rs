@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rand::prelude::SliceRandom;
+
+use crate::{
+    card::Card,
+    event::{GameEvent, GameObserver},
+    game::{GameState, PlayerId},
+    rng::GameRng,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KnownLocation {
+    OwnHand,
+    OpponentHand(PlayerId), // e.g. a card this player drafted past that opponent
+    DiscardPile,
+    PlayedBy(PlayerId),
+}
+
+/// What a single player knows about the whereabouts of individual cards.
+///
+/// Cards without a known location might be in the draw deck or in an opponent's hand.
+/// Those are the only cards this player should consider when estimating draw odds
+/// or when sampling the hidden parts of the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KnowledgeState {
+    pub player_id: PlayerId,
+    pub known_locations: HashMap<String, KnownLocation>,
+}
+
+impl KnowledgeState {
+    pub fn new(player_id: PlayerId) -> Self {
+        Self {
+            player_id,
+            known_locations: HashMap::new(),
+        }
+    }
+
+    pub fn has_seen(&self, card: &Card) -> bool {
+        self.known_locations.contains_key(&card.name)
+    }
+
+    pub fn observe(&mut self, event: &GameEvent) {
+        match event {
+            // Other players' draws are hidden from this player.
+            GameEvent::CardsDrawn(player_id, cards) if *player_id == self.player_id => {
+                self.record(cards, KnownLocation::OwnHand);
+            }
+            // The pack in front of the player is as good as in their hand, until it's passed on.
+            GameEvent::DraftPackDealt(player_id, cards) if *player_id == self.player_id => {
+                self.record(cards, KnownLocation::OwnHand);
+            }
+            GameEvent::CardDrafted(player_id, card) if *player_id == self.player_id => {
+                self.record(std::slice::from_ref(card), KnownLocation::OwnHand);
+            }
+            GameEvent::CardsDiscarded(_, cards) => {
+                self.record(cards, KnownLocation::DiscardPile);
+            }
+            GameEvent::CardsPassed(from_player_id, to_player_id, cards)
+            | GameEvent::DraftPackPassed(from_player_id, to_player_id, cards) => {
+                if *from_player_id == self.player_id {
+                    self.record(cards, KnownLocation::OpponentHand(*to_player_id));
+                } else if *to_player_id == self.player_id {
+                    self.record(cards, KnownLocation::OwnHand);
+                }
+            }
+            GameEvent::CardRevealed(card) => {
+                self.record(std::slice::from_ref(card), KnownLocation::DiscardPile);
+            }
+            GameEvent::CardPlayed(player_id, card) => {
+                self.record(std::slice::from_ref(card), KnownLocation::PlayedBy(*player_id));
+            }
+            GameEvent::DiscardPileReshuffled => {
+                // Discarded cards are back in the deck, in an unknown order.
+                self.known_locations
+                    .retain(|_, location| *location != KnownLocation::DiscardPile);
+            }
+            _ => {}
+        }
+    }
+
+    /// The cards of the given deck whose location this player does not know.
+    pub fn unaccounted_cards<'a>(&self, deck: &'a [Card]) -> Vec<&'a Card> {
+        deck.iter().filter(|card| !self.has_seen(card)).collect()
+    }
+
+    /// The probability, from this player's point of view, that a card drawn from the deck
+    /// satisfies the given predicate. Assumes every unaccounted card is equally likely.
+    pub fn draw_probability<F: Fn(&Card) -> bool>(&self, deck: &[Card], predicate: F) -> f64 {
+        let unaccounted_cards = self.unaccounted_cards(deck);
+        if unaccounted_cards.is_empty() {
+            0.0
+        } else {
+            let matching = unaccounted_cards.iter().filter(|card| predicate(card)).count();
+            matching as f64 / unaccounted_cards.len() as f64
+        }
+    }
+
+    /// Determinize the hidden information: pick the given number of cards that could be drawn,
+    /// e.g. to stand in for the next research phase offer.
+    pub fn sample_unaccounted_cards(
+        &self,
+        deck: &[Card],
+        count: usize,
+        rng: &mut GameRng,
+    ) -> Vec<Card> {
+        self.unaccounted_cards(deck)
+            .choose_multiple(rng, count)
+            .map(|card| (*card).clone())
+            .collect()
+    }
+
+    fn record(&mut self, cards: &[Card], location: KnownLocation) {
+        for card in cards {
+            self.known_locations.insert(card.name.clone(), location);
+        }
+    }
+}
+
+/// Keeps every player's `KnowledgeState` up to date by observing the game's events.
+#[derive(Clone, Debug, Default)]
+pub struct KnowledgeTracker {
+    pub players: BTreeMap<PlayerId, KnowledgeState>,
+}
+
+impl KnowledgeTracker {
+    pub fn new(game: &GameState) -> Self {
+        let players = game
+            .players
+            .values()
+            .map(|player| {
+                // Players know the cards in their own hand and everyone's played cards.
+                let mut knowledge = KnowledgeState::new(player.player_id);
+                knowledge.record(&player.cards_in_hand, KnownLocation::OwnHand);
+                for other_player in game.players.values() {
+                    knowledge.record(
+                        &other_player.played_cards,
+                        KnownLocation::PlayedBy(other_player.player_id),
+                    );
+                }
+                knowledge.record(&game.discard_pile, KnownLocation::DiscardPile);
+
+                (player.player_id, knowledge)
+            })
+            .collect();
+
+        Self { players }
+    }
+}
+
+impl GameObserver for KnowledgeTracker {
+    fn on_event(&mut self, _game: &GameState, event: &GameEvent) {
+        for knowledge in self.players.values_mut() {
+            knowledge.observe(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{CardTag, BASE_GAME_DECK},
+        event::EventBus,
+        game::{GameOperation, GamePhase, GameState, PlayerId, PlayerStateBuilder},
+        knowledge::{KnowledgeTracker, KnownLocation},
+        position::import_position,
+        rng::GameRng,
+        test_utils::make_game,
+    };
+
+    fn make_two_player_game() -> GameState {
+        let p1 = PlayerStateBuilder::new(1).build();
+        let p2 = PlayerStateBuilder::new(2).build();
+        let mut game = make_game(vec![p1, p2]);
+        game.draw_deck = BASE_GAME_DECK.clone();
+        game
+    }
+
+    #[test]
+    fn players_only_learn_about_their_own_draws() {
+        let mut game = make_two_player_game();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(KnowledgeTracker::new(&game));
+        bus.execute(&mut game, GameOperation::DrawCards(p1, 3));
+
+        let tracker = bus.observer::<KnowledgeTracker>(handle).unwrap();
+        let p1_hand = &game.players[&p1].cards_in_hand;
+        assert!(p1_hand.iter().all(|card| tracker.players[&p1].has_seen(card)));
+        assert!(p1_hand.iter().all(|card| !tracker.players[&p2].has_seen(card)));
+
+        let deck_size = BASE_GAME_DECK.len();
+        assert_eq!(
+            deck_size - 3,
+            tracker.players[&p1].unaccounted_cards(&BASE_GAME_DECK).len()
+        );
+        assert_eq!(
+            deck_size,
+            tracker.players[&p2].unaccounted_cards(&BASE_GAME_DECK).len()
+        );
+    }
+
+    #[test]
+    fn passed_and_discarded_cards_are_tracked_until_reshuffled() {
+        let mut game = make_two_player_game();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(KnowledgeTracker::new(&game));
+        bus.execute(&mut game, GameOperation::DrawCards(p1, 2));
+
+        let passed_card = game.players[&p1].cards_in_hand[0].clone();
+        let discarded_card = game.players[&p1].cards_in_hand[1].clone();
+        bus.execute(&mut game, GameOperation::PassCards(p1, p2, vec![passed_card.clone()]));
+        bus.execute(&mut game, GameOperation::DiscardCards(p1, vec![discarded_card.clone()]));
+
+        {
+            let tracker = bus.observer::<KnowledgeTracker>(handle).unwrap();
+            assert_eq!(
+                Some(&KnownLocation::OpponentHand(p2)),
+                tracker.players[&p1].known_locations.get(&passed_card.name)
+            );
+            assert_eq!(
+                Some(&KnownLocation::OwnHand),
+                tracker.players[&p2].known_locations.get(&passed_card.name)
+            );
+            assert!(tracker.players[&p2].has_seen(&discarded_card));
+        }
+
+        // Drawing more cards than the deck holds reshuffles the discard pile into the deck.
+        let remaining = game.draw_deck.len();
+        bus.execute(&mut game, GameOperation::DrawCards(p2, remaining + 1));
+
+        let tracker = bus.observer::<KnowledgeTracker>(handle).unwrap();
+        assert!(tracker.players[&p2].has_seen(&discarded_card)); // drew it back
+        assert!(!tracker.players[&p1].has_seen(&discarded_card));
+    }
+
+    #[test]
+    fn draft_packs_are_known_to_go_to_the_next_seat() {
+        let mut game = import_position("seed 3\nplayer p1\nplayer p2\nplayer p3").unwrap();
+        game.generation = 2;
+        game.phase = GamePhase::Research;
+        let (p1, p2, p3) = (PlayerId::from(1), PlayerId::from(2), PlayerId::from(3));
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(KnowledgeTracker::new(&game));
+        for event in game.start_research_draft().unwrap() {
+            bus.publish(&game, &event);
+        }
+        let pack = |game: &GameState, player_id| {
+            game.research_draft.as_ref().unwrap().seat(player_id).unwrap().pack.clone()
+        };
+        let p1_pack = pack(&game, p1);
+
+        for player_id in [p1, p2, p3] {
+            let card = pack(&game, player_id)[0].clone();
+            bus.execute(&mut game, GameOperation::DraftCard(player_id, card));
+        }
+
+        // In even generations, packs go to the previous seat.
+        let tracker = bus.observer::<KnowledgeTracker>(handle).unwrap();
+        for card in &p1_pack[1..] {
+            assert_eq!(
+                Some(&KnownLocation::OpponentHand(p3)),
+                tracker.players[&p1].known_locations.get(&card.name)
+            );
+            assert_eq!(
+                Some(&KnownLocation::OwnHand),
+                tracker.players[&p3].known_locations.get(&card.name)
+            );
+            assert!(!tracker.players[&p2].has_seen(card));
+        }
+        assert_eq!(
+            Some(&KnownLocation::OwnHand),
+            tracker.players[&p1].known_locations.get(&p1_pack[0].name)
+        );
+    }
+
+    #[test]
+    fn draw_probability_only_considers_unaccounted_cards() {
+        let game = make_two_player_game();
+        let tracker = KnowledgeTracker::new(&game);
+        let knowledge = &tracker.players[&PlayerId::from(1)];
+
+        let jovian_cards = BASE_GAME_DECK
+            .iter()
+            .filter(|card| card.tags.contains(&CardTag::Jovian))
+            .count();
+        let expected = jovian_cards as f64 / BASE_GAME_DECK.len() as f64;
+        let actual =
+            knowledge.draw_probability(&BASE_GAME_DECK, |card| card.tags.contains(&CardTag::Jovian));
+        assert!((expected - actual).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sampled_cards_are_never_known_to_the_player() {
+        let mut game = make_two_player_game();
+        let p1 = PlayerId::from(1);
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(KnowledgeTracker::new(&game));
+        bus.execute(&mut game, GameOperation::DrawCards(p1, 100));
+
+        let tracker = bus.observer::<KnowledgeTracker>(handle).unwrap();
+        let mut rng = GameRng::from_seed(0);
+        let sample = tracker.players[&p1].sample_unaccounted_cards(&BASE_GAME_DECK, 10, &mut rng);
+        assert_eq!(10, sample.len());
+        assert!(sample.iter().all(|card| !tracker.players[&p1].has_seen(card)));
+    }
+}
@@ -0,0 +1,40 @@
+//! The rules engine: the board, the game state, and the resolution of game operations,
+//! along with what is derived from them, e.g. scores, standings, and views of the game.
+
+#![allow(dead_code)]
+
+// The card modules are re-exported, so they are found at the same paths as the engine's own.
+pub use tm_cards::{card, card_ref, corporation, deck_stats, registry, resource};
+
+pub mod award;
+pub mod board;
+pub mod compact;
+pub mod diff;
+pub mod event;
+pub mod game;
+pub mod game_setup;
+pub mod generation;
+pub mod game_stats;
+pub mod knowledge;
+pub mod migrate;
+pub mod milestone;
+pub mod notation;
+pub mod options;
+pub mod payment;
+pub mod position;
+pub mod projection;
+pub mod rng;
+pub mod rules;
+pub mod save;
+pub mod schedule;
+pub mod scoring;
+pub mod script;
+pub mod solo;
+pub mod spectator;
+pub mod stamp;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod timeline;
+pub mod triggers;
+pub mod turmoil;
+pub mod view;
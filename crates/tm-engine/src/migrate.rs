@@ -0,0 +1,151 @@
+//! Lenient loading: upgrades of saves and recorded games written by older builds, wherever
+//! they can still be read, along with a report of exactly what was changed to read them.
+//!
+//! Older documents may lack a build stamp, hold whole cards where cards are now written by
+//! name, lack fields that were added since, or carry fields that were removed. Differences
+//! from this build's stamp are reported instead of refused.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use crate::stamp::{BuildStamp, StampMismatch};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Migration {
+    // a game state saved on its own, before saves were stamped
+    WrappedGameState,
+    // missing from the stamp, and taken from this build's
+    FilledStampField { field: String, value: String },
+    // a whole card, written before cards were written by name
+    ReplacedCardBody { path: String, name: String },
+    FilledDefault { path: String },
+    DroppedField { path: String },
+    // loaded anyway
+    StampMismatch(StampMismatch),
+}
+
+impl fmt::Display for Migration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Migration::WrappedGameState => write!(f, "wrapped the game state in a save"),
+            Migration::FilledStampField { field, value } => {
+                write!(f, "filled in the missing {} with {}", field, value)
+            }
+            Migration::ReplacedCardBody { path, name } => {
+                write!(f, "{}: replaced card \"{}\" by its name", path, name)
+            }
+            Migration::FilledDefault { path } => write!(f, "{}: filled in the default", path),
+            Migration::DroppedField { path } => write!(f, "{}: dropped the unknown field", path),
+            Migration::StampMismatch(mismatch) => write!(f, "{}, but loaded anyway", mismatch),
+        }
+    }
+}
+
+/// Fill in the fields of a build stamp that the object lacks with this build's, then report
+/// how the stamp differs from this build's.
+pub fn upgrade_stamp(object: &mut Map<String, Value>, path: &str, migrations: &mut Vec<Migration>) {
+    let current = serde_json::to_value(BuildStamp::current()).unwrap();
+    for (field, value) in current.as_object().unwrap() {
+        if !object.contains_key(field) {
+            object.insert(field.clone(), value.clone());
+            migrations.push(Migration::FilledStampField {
+                field: join(path, field),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    if let Ok(stamp) = serde_json::from_value::<BuildStamp>(Value::Object(object.clone())) {
+        migrations.extend(stamp.mismatches().into_iter().map(Migration::StampMismatch));
+    }
+}
+
+/// Replace the whole cards anywhere in the value by their names.
+pub fn replace_card_bodies(value: &mut Value, path: &str, migrations: &mut Vec<Migration>) {
+    if let Some(name) = card_body_name(value) {
+        migrations.push(Migration::ReplacedCardBody {
+            path: path.to_string(),
+            name: name.clone(),
+        });
+        *value = Value::String(name);
+        return;
+    }
+    match value {
+        Value::Object(object) => {
+            for (key, field) in object.iter_mut() {
+                replace_card_bodies(field, &join(path, key), migrations);
+            }
+        }
+        Value::Array(elements) => {
+            for (index, element) in elements.iter_mut().enumerate() {
+                replace_card_bodies(element, &join(path, &index.to_string()), migrations);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Corporations also have names and tags, but neither a kind nor a cost.
+fn card_body_name(value: &Value) -> Option<String> {
+    let object = value.as_object()?;
+    if object.contains_key("kind") && object.contains_key("cost") {
+        object.get("name")?.as_str().map(str::to_string)
+    } else {
+        None
+    }
+}
+
+/// Report the fields that reading filled in with defaults, or dropped as unknown, by comparing
+/// the document as read with the document as written out again.
+pub fn report_field_changes(
+    read: &Value,
+    written: &Value,
+    path: &str,
+    migrations: &mut Vec<Migration>,
+) {
+    match (read, written) {
+        (Value::Object(read), Value::Object(written)) => {
+            for (key, written_field) in written {
+                let field_path = join(path, key);
+                match read.get(key) {
+                    Some(read_field) => {
+                        report_field_changes(read_field, written_field, &field_path, migrations)
+                    }
+                    None => migrations.push(Migration::FilledDefault { path: field_path }),
+                }
+            }
+            // Empty fields are left out when written, whether they are known or not.
+            for (key, read_field) in read {
+                if !written.contains_key(key) && !is_empty(read_field) {
+                    migrations.push(Migration::DroppedField {
+                        path: join(path, key),
+                    });
+                }
+            }
+        }
+        (Value::Array(read), Value::Array(written)) if read.len() == written.len() => {
+            for (index, (read, written)) in read.iter().zip(written).enumerate() {
+                report_field_changes(read, written, &join(path, &index.to_string()), migrations);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(elements) => elements.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
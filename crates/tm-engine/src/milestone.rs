@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::CardTag,
+    event::GameEvent,
+    game::{ActiveTags, GameState, PlayerId},
+};
+
+/// The milestones of the Tharsis map.
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Milestone {
+    Terraformer, // terraform rating of at least 35
+    Mayor,       // at least 3 cities
+    Gardener,    // at least 3 greeneries
+    Builder,     // at least 8 building tags in play
+    Planner,     // at least 16 cards in hand
+}
+
+impl Milestone {
+    pub const COUNT: usize = 5;
+
+    pub const ALL: [Milestone; Milestone::COUNT] = [
+        Milestone::Terraformer,
+        Milestone::Mayor,
+        Milestone::Gardener,
+        Milestone::Builder,
+        Milestone::Planner,
+    ];
+
+    #[inline]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn requirement(self) -> usize {
+        match self {
+            Milestone::Terraformer => 35,
+            Milestone::Mayor | Milestone::Gardener => 3,
+            Milestone::Builder => 8,
+            Milestone::Planner => 16,
+        }
+    }
+
+    pub fn progress(self, game: &GameState, player_id: PlayerId) -> usize {
+        let player = &game.players[&player_id];
+        match self {
+            Milestone::Terraformer => player.terraform_rating,
+            Milestone::Mayor => game
+                .board
+                .cities
+                .values()
+                .filter(|(_, owner)| *owner == player_id)
+                .count(),
+            Milestone::Gardener => game
+                .board
+                .greeneries
+                .values()
+                .filter(|owner| **owner == player_id)
+                .count(),
+            Milestone::Builder => player.active_tag_count(CardTag::Building),
+            Milestone::Planner => player.cards_in_hand.len(),
+        }
+    }
+
+    pub fn is_met(self, game: &GameState, player_id: PlayerId) -> bool {
+        self.progress(game, player_id) >= self.requirement()
+    }
+
+    // The player whose progress towards this milestone the event could have increased, if any.
+    fn may_progress(self, event: &GameEvent) -> Option<PlayerId> {
+        match (self, event) {
+            (Milestone::Terraformer, GameEvent::TerraformRatingRaised(player_id, _))
+            | (Milestone::Mayor, GameEvent::CityPlaced(player_id, _, _))
+            | (Milestone::Gardener, GameEvent::GreeneryPlaced(player_id, _))
+            | (Milestone::Builder, GameEvent::CardPlayed(player_id, _))
+            | (Milestone::Planner, GameEvent::CardsDrawn(player_id, _))
+            | (Milestone::Planner, GameEvent::CardsPassed(_, player_id, _))
+            | (Milestone::Planner, GameEvent::CardDrafted(player_id, _)) => Some(*player_id),
+            _ => None,
+        }
+    }
+}
+
+/// What claiming a milestone costs.
+pub const MILESTONE_CLAIM_COST: usize = 8;
+
+/// Victory points for each claimed milestone.
+pub const MILESTONE_POINTS: isize = 5;
+
+/// Which milestones each player has already been told they qualify for.
+///
+/// Players are only notified the first time they qualify, even if they later stop
+/// qualifying (e.g. by playing cards from a 16-card hand) and qualify again.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MilestoneNotices {
+    notified: BTreeMap<PlayerId, [bool; Milestone::COUNT]>,
+}
+
+impl MilestoneNotices {
+    /// Treat every milestone that players currently qualify for as already notified,
+    /// e.g. for a game that was loaded or set up mid-way through.
+    pub fn compute(game: &GameState) -> Self {
+        let mut notices = Self::default();
+        for player_id in game.players.keys() {
+            for milestone in Milestone::ALL {
+                if milestone.is_met(game, *player_id) {
+                    notices.notified_mut(*player_id)[milestone.index()] = true;
+                }
+            }
+        }
+        notices
+    }
+
+    pub fn was_notified(&self, player_id: PlayerId, milestone: Milestone) -> bool {
+        self.notified
+            .get(&player_id)
+            .is_some_and(|notified| notified[milestone.index()])
+    }
+
+    fn notified_mut(&mut self, player_id: PlayerId) -> &mut [bool; Milestone::COUNT] {
+        self.notified.entry(player_id).or_default()
+    }
+}
+
+impl GameState {
+    // The `MilestoneEligible` events caused by the given events, which have already been applied.
+    // Milestones cannot be claimed yet, so every milestone is still unclaimed.
+    pub(crate) fn newly_eligible_milestones(&mut self, events: &[GameEvent]) -> Vec<GameEvent> {
+        let mut eligible = vec![];
+        for event in events {
+            for milestone in Milestone::ALL {
+                let player_id = match milestone.may_progress(event) {
+                    Some(player_id) => player_id,
+                    None => continue,
+                };
+                if !self.milestone_notices.was_notified(player_id, milestone)
+                    && milestone.is_met(self, player_id)
+                {
+                    self.milestone_notices.notified_mut(player_id)[milestone.index()] = true;
+                    eligible.push(GameEvent::MilestoneEligible(player_id, milestone));
+                }
+            }
+        }
+        eligible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Coordinates, TileLocation},
+        card::CityKind,
+        event::GameEvent,
+        game::{GameOperation, PlayerId},
+        milestone::Milestone,
+        position::import_position,
+    };
+
+    #[test]
+    fn players_are_notified_the_first_time_they_qualify() {
+        let mut game = import_position(
+            "city 0,0 p1\n\
+             city 5,-4 p1\n\
+             player p1\n\
+             tr 34\n\
+             player p2",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let city = |x, y| {
+            GameOperation::PlaceCityTile(
+                p1,
+                CityKind::RegularCity,
+                TileLocation::OnMars(Coordinates::new(x, y)),
+            )
+        };
+
+        let events = game.execute_operation(city(2, -3));
+        assert_eq!(
+            Some(&GameEvent::MilestoneEligible(p1, Milestone::Mayor)),
+            events.last()
+        );
+        assert!(game.milestone_notices.was_notified(p1, Milestone::Mayor));
+
+        let events = game.execute_operation(GameOperation::RaiseTerraformRating(p1, 1));
+        assert_eq!(
+            Some(&GameEvent::MilestoneEligible(p1, Milestone::Terraformer)),
+            events.last()
+        );
+
+        // Already notified, so qualifying further does not notify again.
+        let events = game.execute_operation(city(-2, 3));
+        assert!(!events
+            .iter()
+            .any(|event| matches!(event, GameEvent::MilestoneEligible(..))));
+        assert!(!game
+            .milestone_notices
+            .was_notified(PlayerId::from(2), Milestone::Mayor));
+    }
+}
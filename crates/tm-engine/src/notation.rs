@@ -0,0 +1,488 @@
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    award::Award,
+    board::Coordinates,
+    card::Card,
+    game::{PlayerId, StandardProject},
+    position::{parse_number, parse_player_id, parse_variant},
+    registry::CardRegistry,
+    resource::Resource,
+};
+
+/// Compact, line-oriented notation for the actions players take, for game records that
+/// people can read, edit, and share, and that diff well next to the JSON event log.
+///
+/// Each non-empty line holds one action, prefixed with the generation and the player;
+/// everything after a `#` outside of a card name is a comment. Cards are referred to by
+/// their quoted names, since card ids are only stable for a given version of the deck data.
+///
+/// ```text
+/// g3: p1 play "Asteroid Mining" pay 14M+3S     # M, S, T, P, E, H: resources spent
+/// g3: p2 play "Capital" pay 26M place 4,-5     # one "place" per tile the card places
+/// g3: p1 action "Predators"
+/// g3: p2 project sell "Solar Power" "Birds"
+/// g3: p1 project power-plant
+/// g3: p2 project asteroid place 5,-1           # the ocean, if the temperature reaches 0 C
+/// g3: p1 project aquifer place 5,-1
+/// g3: p2 project greenery place 3,0
+/// g3: p1 project city place 3,-1
+/// g3: p2 convert plants place 3,0              # 8 plants into a greenery
+/// g3: p1 convert heat                          # 8 heat into a temperature raise
+/// g3: p2 milestone
+/// g3: p1 award Banker
+/// g3: p2 pass
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotatedAction {
+    pub generation: usize,
+    pub player_id: PlayerId,
+    pub action: Action,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    PlayCard {
+        card: Card,
+        payment: BTreeMap<Resource, usize>,
+        placements: Vec<Coordinates>,
+    },
+    UseCardAction(Card),
+    StandardProject(StandardProject),
+    ClaimMilestone,
+    FundAward(Award),
+    ConvertPlants(Coordinates),
+    ConvertHeat(Option<Coordinates>), // the ocean, if the temperature reaches 0 C
+    Pass,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NotationError {
+    pub line: usize, // 1-based
+    pub message: String,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse every action in the text, skipping blank lines and comments.
+pub fn parse_actions(text: &str) -> Result<Vec<NotatedAction>, NotationError> {
+    let mut actions = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let tokens = tokenize(line).map_err(|message| NotationError {
+            line: index + 1,
+            message,
+        })?;
+        if tokens.is_empty() {
+            continue;
+        }
+
+        let action = parse_tokens(&tokens).map_err(|message| NotationError {
+            line: index + 1,
+            message,
+        })?;
+        actions.push(action);
+    }
+    Ok(actions)
+}
+
+/// Write the actions out one per line, in the notation `parse_actions()` reads.
+pub fn write_actions(actions: &[NotatedAction]) -> String {
+    actions
+        .iter()
+        .map(|action| format!("{}\n", action))
+        .collect()
+}
+
+impl std::str::FromStr for NotatedAction {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(line)?;
+        if tokens.is_empty() {
+            return Err("expected an action, found an empty line".into());
+        }
+        parse_tokens(&tokens)
+    }
+}
+
+impl fmt::Display for NotatedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "g{}: p{} {}",
+            self.generation,
+            usize::from(self.player_id),
+            self.action
+        )
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::PlayCard {
+                card,
+                payment,
+                placements,
+            } => {
+                write!(f, "play \"{}\"", card.name)?;
+                let paid: Vec<String> = payment
+                    .iter()
+                    .filter(|(_, amount)| **amount > 0)
+                    .map(|(resource, amount)| format!("{}{}", amount, resource_letter(*resource)))
+                    .collect();
+                if !paid.is_empty() {
+                    write!(f, " pay {}", paid.join("+"))?;
+                }
+                for coordinates in placements {
+                    write!(f, " place {}", coordinates)?;
+                }
+                Ok(())
+            }
+            Action::UseCardAction(card) => write!(f, "action \"{}\"", card.name),
+            Action::StandardProject(project) => {
+                write!(f, "project ")?;
+                match project {
+                    StandardProject::SellPatents(cards) => {
+                        write!(f, "sell")?;
+                        for card in cards {
+                            write!(f, " \"{}\"", card.name)?;
+                        }
+                        Ok(())
+                    }
+                    StandardProject::PowerPlant => write!(f, "power-plant"),
+                    StandardProject::Asteroid(None) => write!(f, "asteroid"),
+                    StandardProject::Asteroid(Some(ocean)) => write!(f, "asteroid place {}", ocean),
+                    StandardProject::Aquifer(ocean) => write!(f, "aquifer place {}", ocean),
+                    StandardProject::Greenery(greenery) => {
+                        write!(f, "greenery place {}", greenery)
+                    }
+                    StandardProject::City(city) => write!(f, "city place {}", city),
+                }
+            }
+            Action::ClaimMilestone => write!(f, "milestone"),
+            Action::FundAward(award) => write!(f, "award {:?}", award),
+            Action::ConvertPlants(greenery) => write!(f, "convert plants place {}", greenery),
+            Action::ConvertHeat(None) => write!(f, "convert heat"),
+            Action::ConvertHeat(Some(ocean)) => write!(f, "convert heat place {}", ocean),
+            Action::Pass => write!(f, "pass"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String), // a card name
+}
+
+// Split the line into whitespace-separated words and quoted card names, dropping comments.
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&next) = chars.peek() {
+        if next.is_whitespace() {
+            chars.next();
+        } else if next == '#' {
+            break;
+        } else if next == '"' {
+            chars.next();
+            let mut name = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => name.push(c),
+                    None => return Err(format!("unterminated card name \"{}", name)),
+                }
+            }
+            tokens.push(Token::Quoted(name));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' || c == '#' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_tokens(tokens: &[Token]) -> Result<NotatedAction, String> {
+    let mut tokens = tokens.iter();
+
+    let generation = match tokens.next() {
+        Some(Token::Word(word)) => word
+            .strip_prefix('g')
+            .and_then(|rest| rest.strip_suffix(':'))
+            .ok_or_else(|| format!("\"{}\" is not a generation, expected e.g. \"g3:\"", word))
+            .and_then(parse_number)?,
+        _ => return Err("expected the generation, e.g. \"g3:\"".into()),
+    };
+    let player_id = parse_player_id(&expect_word(tokens.next(), "a player")?)?;
+
+    let verb = expect_word(tokens.next(), "an action")?;
+    let rest = tokens.as_slice();
+    let action = match verb.as_str() {
+        "play" => {
+            let (card, options) = rest
+                .split_first()
+                .ok_or_else(|| String::from("expected the name of the card played"))?;
+            let card = parse_card(card)?;
+
+            let mut payment = BTreeMap::new();
+            let mut placements = vec![];
+            let mut options = options.iter();
+            while let Some(option) = options.next() {
+                match expect_word(Some(option), "\"pay\" or \"place\"")?.as_str() {
+                    "pay" => {
+                        let amounts = expect_word(options.next(), "the payment, e.g. \"14M+3S\"")?;
+                        for amount in amounts.split('+') {
+                            let (resource, amount) = parse_amount(amount)?;
+                            *payment.entry(resource).or_insert(0) += amount;
+                        }
+                    }
+                    "place" => placements.push(parse_coordinates(options.next())?),
+                    other => {
+                        return Err(format!(
+                            "unexpected \"{}\", expected \"pay\" or \"place\"",
+                            other
+                        ))
+                    }
+                }
+            }
+
+            Action::PlayCard {
+                card,
+                payment,
+                placements,
+            }
+        }
+        "action" => match rest {
+            [card] => Action::UseCardAction(parse_card(card)?),
+            _ => return Err("expected the name of the card whose action is used".into()),
+        },
+        "project" => Action::StandardProject(parse_standard_project(rest)?),
+        "convert" => parse_conversion(rest)?,
+        "award" => match rest {
+            [award] => Action::FundAward(parse_variant(&expect_word(Some(award), "an award")?)?),
+            _ => return Err("expected the award funded, e.g. \"Banker\"".into()),
+        },
+        "milestone" | "pass" => {
+            if !rest.is_empty() {
+                return Err(format!("unexpected arguments after \"{}\"", verb));
+            }
+            match verb.as_str() {
+                "milestone" => Action::ClaimMilestone,
+                _ => Action::Pass,
+            }
+        }
+        other => return Err(format!("unknown action \"{}\"", other)),
+    };
+
+    Ok(NotatedAction {
+        generation,
+        player_id,
+        action,
+    })
+}
+
+fn parse_standard_project(tokens: &[Token]) -> Result<StandardProject, String> {
+    let (project, rest) = tokens
+        .split_first()
+        .ok_or_else(|| String::from("expected a standard project"))?;
+    let project = expect_word(Some(project), "a standard project")?;
+
+    // Every project but selling patents takes at most one placement.
+    let placement = || -> Result<Option<Coordinates>, String> {
+        match rest {
+            [] => Ok(None),
+            [place, coordinates] if *place == Token::Word("place".into()) => {
+                parse_coordinates(Some(coordinates)).map(Some)
+            }
+            _ => Err(format!("expected \"place x,y\" after \"{}\"", project)),
+        }
+    };
+    let required_placement =
+        || placement()?.ok_or_else(|| format!("expected \"place x,y\" after \"{}\"", project));
+
+    Ok(match project.as_str() {
+        "sell" => {
+            StandardProject::SellPatents(rest.iter().map(parse_card).collect::<Result<_, _>>()?)
+        }
+        "power-plant" => {
+            if !rest.is_empty() {
+                return Err("unexpected arguments after \"power-plant\"".into());
+            }
+            StandardProject::PowerPlant
+        }
+        "asteroid" => StandardProject::Asteroid(placement()?),
+        "aquifer" => StandardProject::Aquifer(required_placement()?),
+        "greenery" => StandardProject::Greenery(required_placement()?),
+        "city" => StandardProject::City(required_placement()?),
+        other => return Err(format!("unknown standard project \"{}\"", other)),
+    })
+}
+
+fn parse_conversion(tokens: &[Token]) -> Result<Action, String> {
+    let (resource, rest) = tokens
+        .split_first()
+        .ok_or_else(|| String::from("expected \"plants\" or \"heat\""))?;
+    let resource = expect_word(Some(resource), "\"plants\" or \"heat\"")?;
+    let placement = match rest {
+        [] => None,
+        [place, coordinates] if *place == Token::Word("place".into()) => {
+            Some(parse_coordinates(Some(coordinates))?)
+        }
+        _ => return Err(format!("expected \"place x,y\" after \"{}\"", resource)),
+    };
+
+    match resource.as_str() {
+        "plants" => placement
+            .map(Action::ConvertPlants)
+            .ok_or_else(|| "expected \"place x,y\" after \"plants\"".into()),
+        "heat" => Ok(Action::ConvertHeat(placement)),
+        other => Err(format!(
+            "unknown conversion \"{}\", expected \"plants\" or \"heat\"",
+            other
+        )),
+    }
+}
+
+fn expect_word(token: Option<&Token>, expected: &str) -> Result<String, String> {
+    match token {
+        Some(Token::Word(word)) => Ok(word.clone()),
+        Some(Token::Quoted(name)) => Err(format!("expected {}, found \"{}\"", expected, name)),
+        None => Err(format!("expected {}", expected)),
+    }
+}
+
+fn parse_card(token: &Token) -> Result<Card, String> {
+    match token {
+        Token::Quoted(name) => CardRegistry::global()
+            .get_by_name(name)
+            .cloned()
+            .ok_or_else(|| format!("unknown card \"{}\"", name)),
+        Token::Word(word) => Err(format!("expected a quoted card name, found {}", word)),
+    }
+}
+
+fn parse_coordinates(token: Option<&Token>) -> Result<Coordinates, String> {
+    let text = expect_word(token, "coordinates, e.g. \"3,-1\"")?;
+    let (x, y) = text.split_once(',').ok_or_else(|| {
+        format!(
+            "\"{}\" is not a location on Mars, expected e.g. \"3,-1\"",
+            text
+        )
+    })?;
+    Ok(Coordinates::new(parse_number(x)?, parse_number(y)?))
+}
+
+fn parse_amount(text: &str) -> Result<(Resource, usize), String> {
+    let split = text.len() - text.chars().last().map_or(0, char::len_utf8);
+    let (amount, letter) = text.split_at(split);
+    let resource = Resource::ALL
+        .iter()
+        .copied()
+        .find(|resource| resource_letter(*resource) == letter)
+        .ok_or_else(|| format!("\"{}\" is not a payment, expected e.g. \"14M\"", text))?;
+    Ok((resource, parse_number(amount)?))
+}
+
+fn resource_letter(resource: Resource) -> &'static str {
+    match resource {
+        Resource::Megacredits => "M",
+        Resource::Steel => "S",
+        Resource::Titanium => "T",
+        Resource::Plants => "P",
+        Resource::Energy => "E",
+        Resource::Heat => "H",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::Coordinates,
+        card::BASE_GAME_CARDS_BY_NAME,
+        game::{PlayerId, StandardProject},
+        notation::{parse_actions, write_actions, Action, NotatedAction},
+        resource::Resource,
+    };
+
+    #[test]
+    fn actions_round_trip_through_the_notation() {
+        let text = "
+            # the start of generation 3
+            g3: p1 play \"Asteroid Mining\" pay 14M+3S   # titanium would have been better
+            g3: p2 play \"Capital\" pay 26M place 4,-5
+            g3: p1 action \"Predators\"
+            g3: p2 project sell \"Solar Power\" \"Birds\"
+            g3: p1 project asteroid place 5,-1
+            g3: p2 project city place 3,-1
+            g3: p1 award Thermalist
+            g3: p2 convert plants place 3,0
+            g3: p2 convert heat place 5,-1
+            g3: p1 pass
+        ";
+        let actions = parse_actions(text).unwrap();
+        assert_eq!(10, actions.len());
+
+        assert_eq!(
+            NotatedAction {
+                generation: 3,
+                player_id: PlayerId::from(1),
+                action: Action::PlayCard {
+                    card: BASE_GAME_CARDS_BY_NAME["Asteroid Mining"].clone(),
+                    payment: btreemap! { Resource::Megacredits => 14, Resource::Steel => 3 },
+                    placements: vec![],
+                },
+            },
+            actions[0]
+        );
+        assert_eq!(
+            Action::StandardProject(StandardProject::City(Coordinates::new(3, -1))),
+            actions[5].action
+        );
+        assert_eq!(
+            Action::ConvertHeat(Some(Coordinates::new(5, -1))),
+            actions[8].action
+        );
+
+        let written = write_actions(&actions);
+        assert_eq!(
+            "g3: p2 play \"Capital\" pay 26M place 4,-5",
+            written.lines().nth(1).unwrap()
+        );
+        assert_eq!(actions, parse_actions(&written).unwrap());
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_line() {
+        let error = parse_actions("g1: p1 pass\ng1: p2 play \"Not A Real Card\"").unwrap_err();
+        assert_eq!(2, error.line);
+        assert_eq!("unknown card \"Not A Real Card\"", error.message);
+
+        let error = parse_actions("g1: p1 project city").unwrap_err();
+        assert_eq!("expected \"place x,y\" after \"city\"", error.message);
+
+        let error = parse_actions("g1: p1 convert plants").unwrap_err();
+        assert_eq!("expected \"place x,y\" after \"plants\"", error.message);
+
+        let error = parse_actions("g1: p1 play \"Predators\" pay 14X").unwrap_err();
+        assert_eq!(
+            "\"14X\" is not a payment, expected e.g. \"14M\"",
+            error.message
+        );
+    }
+}
@@ -0,0 +1,417 @@
+//! Settings chosen when a game is created, e.g. which expansions' cards are used,
+//! and which cards a league or an experiment excludes from play.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, BASE_GAME_CARDS_BY_NAME, BASE_GAME_DECK, CORPORATE_ERA_DECK},
+    event::GameEvent,
+    game::{GameOperation, GameState, PlayerId},
+    resource::Resource,
+};
+
+/// A set of cards that is added to the deck as a whole.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Expansion {
+    Base,
+    Corporate,
+}
+
+impl Expansion {
+    pub const ALL: [Expansion; 2] = [Expansion::Base, Expansion::Corporate];
+
+    pub fn cards(self) -> &'static [Card] {
+        match self {
+            Expansion::Base => &BASE_GAME_DECK,
+            Expansion::Corporate => &CORPORATE_ERA_DECK[BASE_GAME_DECK.len()..],
+        }
+    }
+
+    /// The expansion the named card belongs to, if any.
+    pub fn of(card_name: &str) -> Option<Expansion> {
+        if BASE_GAME_CARDS_BY_NAME.contains_key(card_name) {
+            Some(Expansion::Base)
+        } else if Expansion::Corporate
+            .cards()
+            .iter()
+            .any(|card| card.name == card_name)
+        {
+            Some(Expansion::Corporate)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Expansion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expansion::Base => write!(f, "base"),
+            Expansion::Corporate => write!(f, "corporate"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameOptions {
+    pub expansions: BTreeSet<Expansion>,
+
+    // never shuffled into the deck, and never allowed into play
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub banned_cards: BTreeSet<String>,
+
+    // For each expansion listed here, only the listed cards of that expansion are used.
+    // Expansions not listed here contribute all of their cards.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub included_cards: BTreeMap<Expansion, BTreeSet<String>>,
+
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub handicaps: BTreeMap<PlayerId, Handicap>,
+
+    pub turn_order: TurnOrder,
+
+    // at setup, every player may choose the beginner corporation instead of a dealt one
+    pub beginner_corporations: bool,
+
+    // the research phase's cards are drafted, in every generation but the first
+    pub research_draft: bool,
+
+    // one player against the clock, with the rules of the `solo` module
+    pub solo: bool,
+
+    // variants may leave out or reorder steps, e.g. to keep energy between generations
+    pub production_steps: Vec<ProductionStep>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TurnOrder {
+    // players take one action at a time, in turn, until all of them have passed
+    #[default]
+    Sequential,
+    // "Fast mode": every player privately plans all their actions for the generation at once,
+    // and the plans are then resolved together in a fixed order.
+    Simultaneous,
+}
+
+/// A step of the production phase, which every player goes through
+/// in the order the game options list the steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProductionStep {
+    // leftover energy becomes heat, unless one of the player's effects keeps it
+    EnergyToHeat,
+    // megacredits equal to the terraform rating
+    TerraformRatingIncome,
+    // resources according to production
+    Production,
+}
+
+impl ProductionStep {
+    pub const STANDARD: [ProductionStep; 3] = [
+        ProductionStep::EnergyToHeat,
+        ProductionStep::TerraformRatingIncome,
+        ProductionStep::Production,
+    ];
+}
+
+/// A head start given to one player when the game is set up,
+/// e.g. to balance a game between players of different skill.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Handicap {
+    pub megacredits: usize,
+    pub terraform_rating: usize,
+    pub cards: usize, // dealt on top of the usual cards
+}
+
+impl Handicap {
+    pub fn is_empty(&self) -> bool {
+        *self == Handicap::default()
+    }
+}
+
+impl fmt::Display for Handicap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} M$, +{} TR, +{} card(s)",
+            self.megacredits, self.terraform_rating, self.cards
+        )
+    }
+}
+
+impl Default for GameOptions {
+    // The corporate era game, with every card.
+    fn default() -> Self {
+        Self {
+            expansions: Expansion::ALL.iter().copied().collect(),
+            banned_cards: BTreeSet::new(),
+            included_cards: BTreeMap::new(),
+            handicaps: BTreeMap::new(),
+            turn_order: TurnOrder::default(),
+            beginner_corporations: false,
+            research_draft: false,
+            solo: false,
+            production_steps: ProductionStep::STANDARD.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameOptionsError {
+    UnknownCard(String),
+    UnknownPlayer(PlayerId),
+    ExpansionNotEnabled(Expansion),
+    CardNotInExpansion(Expansion, String),
+    SoloNeedsOnePlayer(usize), // the number of players in the game
+}
+
+impl fmt::Display for GameOptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameOptionsError::UnknownCard(name) => write!(f, "no card named \"{}\"", name),
+            GameOptionsError::UnknownPlayer(player_id) => {
+                write!(f, "{} has a handicap but is not in the game", player_id)
+            }
+            GameOptionsError::ExpansionNotEnabled(expansion) => {
+                write!(f, "the {} expansion is not used in this game", expansion)
+            }
+            GameOptionsError::CardNotInExpansion(expansion, name) => {
+                write!(f, "\"{}\" is not part of the {} expansion", name, expansion)
+            }
+            GameOptionsError::SoloNeedsOnePlayer(players) => {
+                write!(f, "the solo game is for one player, not {}", players)
+            }
+        }
+    }
+}
+
+impl GameOptions {
+    pub fn base_game() -> Self {
+        Self {
+            expansions: vec![Expansion::Base].into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn corporate_era() -> Self {
+        Self::default()
+    }
+
+    /// Whether the card may be in the deck, in a hand, or in play.
+    pub fn allows(&self, card: &Card) -> bool {
+        if self.banned_cards.contains(&card.name) {
+            return false;
+        }
+
+        match Expansion::of(&card.name) {
+            Some(expansion) if self.expansions.contains(&expansion) => {
+                match self.included_cards.get(&expansion) {
+                    Some(included_cards) => included_cards.contains(&card.name),
+                    None => true,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Every allowed card, in the order of the deck files.
+    pub fn deck(&self) -> Vec<Card> {
+        self.expansions
+            .iter()
+            .flat_map(|expansion| expansion.cards().iter())
+            .filter(|card| self.allows(card))
+            .cloned()
+            .collect()
+    }
+
+    /// Check that every listed card exists in the expansion it is listed for,
+    /// so that a misspelled name does not silently leave a card in the game.
+    pub fn validate(&self) -> Result<(), GameOptionsError> {
+        for name in &self.banned_cards {
+            if Expansion::of(name).is_none() {
+                return Err(GameOptionsError::UnknownCard(name.clone()));
+            }
+        }
+
+        for (expansion, included_cards) in &self.included_cards {
+            if !self.expansions.contains(expansion) {
+                return Err(GameOptionsError::ExpansionNotEnabled(*expansion));
+            }
+            for name in included_cards {
+                match Expansion::of(name) {
+                    Some(card_expansion) if card_expansion == *expansion => {}
+                    Some(_) => {
+                        return Err(GameOptionsError::CardNotInExpansion(
+                            *expansion,
+                            name.clone(),
+                        ))
+                    }
+                    None => return Err(GameOptionsError::UnknownCard(name.clone())),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl GameState {
+    /// Give every player their handicap. Part of setting up the game, so it must be done
+    /// exactly once, before the first research phase.
+    ///
+    /// Each handicap is announced by a `HandicapApplied` event, followed by the events
+    /// of the resources, terraform rating, and cards it grants.
+    pub fn apply_handicaps(&mut self) -> Result<Vec<GameEvent>, GameOptionsError> {
+        if let Some(player_id) = self
+            .options
+            .handicaps
+            .keys()
+            .find(|player_id| !self.players.contains_key(player_id))
+        {
+            return Err(GameOptionsError::UnknownPlayer(*player_id));
+        }
+
+        let mut events = vec![];
+        let handicaps = self.options.handicaps.clone();
+        for (player_id, handicap) in handicaps {
+            if handicap.is_empty() {
+                continue;
+            }
+            events.push(GameEvent::HandicapApplied(player_id, handicap));
+
+            if handicap.megacredits > 0 {
+                events.extend(self.execute_operation(GameOperation::ChangeResources(
+                    player_id,
+                    btreemap! { Resource::Megacredits => handicap.megacredits as isize },
+                )));
+            }
+            if handicap.terraform_rating > 0 {
+                events.extend(self.execute_operation(GameOperation::RaiseTerraformRating(
+                    player_id,
+                    handicap.terraform_rating,
+                )));
+            }
+
+            let available_cards = self.draw_deck.len() + self.discard_pile.len();
+            let cards = handicap.cards.min(available_cards);
+            if cards > 0 {
+                events.extend(self.execute_operation(GameOperation::DrawCards(player_id, cards)));
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use maplit::{btreemap, btreeset};
+
+    use crate::{
+        card::{BASE_GAME_DECK, CORPORATE_ERA_DECK, CORPORATE_GAME_CARDS_BY_NAME},
+        game::{GameOperation, IllegalOperation, PlayerId},
+        options::{Expansion, GameOptions, GameOptionsError, Handicap},
+        position::import_position,
+    };
+
+    #[test]
+    fn banned_cards_are_left_out_of_the_deck() {
+        let options = GameOptions {
+            banned_cards: btreeset! { "Predators".to_string() },
+            ..Default::default()
+        };
+        assert!(options.validate().is_ok());
+
+        let deck = options.deck();
+        assert_eq!(CORPORATE_ERA_DECK.len() - 1, deck.len());
+        assert!(deck.iter().all(|card| card.name != "Predators"));
+        assert!(!options.allows(CORPORATE_GAME_CARDS_BY_NAME["Predators"]));
+
+        assert_eq!(BASE_GAME_DECK.len(), GameOptions::base_game().deck().len());
+    }
+
+    #[test]
+    fn cards_the_game_does_not_allow_cannot_be_played() {
+        let mut game = import_position("player p1\nhand Solar Power").unwrap();
+        game.options.banned_cards.insert("Solar Power".to_string());
+        let p1 = PlayerId::from(1);
+        let solar_power = CORPORATE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+
+        assert_eq!(
+            Err(IllegalOperation::CardNotAllowed("Solar Power".to_string())),
+            game.check_operation(&GameOperation::PutCardIntoPlay(p1, solar_power))
+        );
+    }
+
+    #[test]
+    fn inclusion_lists_only_restrict_their_own_expansion() {
+        let options = GameOptions {
+            included_cards: btreemap! {
+                Expansion::Corporate => btreeset! { "Research".to_string() },
+            },
+            ..Default::default()
+        };
+        assert!(options.validate().is_ok());
+
+        let deck = options.deck();
+        assert_eq!(BASE_GAME_DECK.len() + 1, deck.len());
+        assert_eq!("Research", deck.last().unwrap().name);
+        assert!(!options.allows(CORPORATE_GAME_CARDS_BY_NAME["Lagrange Observatory"]));
+        assert!(options.allows(CORPORATE_GAME_CARDS_BY_NAME["Predators"]));
+    }
+
+    #[test]
+    fn misspelled_or_misplaced_cards_are_rejected() {
+        let misspelled = GameOptions {
+            banned_cards: btreeset! { "Predator".to_string() },
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(GameOptionsError::UnknownCard("Predator".to_string())),
+            misspelled.validate()
+        );
+
+        let misplaced = GameOptions {
+            included_cards: btreemap! {
+                Expansion::Base => btreeset! { "Research".to_string() },
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            Err(GameOptionsError::CardNotInExpansion(
+                Expansion::Base,
+                "Research".to_string()
+            )),
+            misplaced.validate()
+        );
+
+        let disabled = GameOptions {
+            included_cards: btreemap! { Expansion::Corporate => BTreeSet::new() },
+            ..GameOptions::base_game()
+        };
+        assert_eq!(
+            Err(GameOptionsError::ExpansionNotEnabled(Expansion::Corporate)),
+            disabled.validate()
+        );
+    }
+
+    #[test]
+    fn handicaps_must_belong_to_players_in_the_game() {
+        let mut game = import_position("player p1\n").unwrap();
+        game.options
+            .handicaps
+            .insert(PlayerId::from(2), Handicap::default());
+        assert_eq!(
+            Err(GameOptionsError::UnknownPlayer(PlayerId::from(2))),
+            game.apply_handicaps()
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! Payment plans: exactly which resources a player spends on a card. Megacredits can always
+//! be spent, steel only on building cards, titanium only on space cards, and heat only with
+//! an effect that lets it count as megacredits, as Helion's does.
+//!
+//! A plan must cover the cost, without paying more than it has to: megacredits or heat are
+//! never spent beyond the cost, and no steel or titanium is spent that the cost is covered
+//! without. A metal's last unit may still be worth more than what is left to pay.
+//!
+//! Cards cost what is printed on them less the player's discounts, which stack, down to
+//! nothing at most.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CardEffect},
+    game::PlayerState,
+    resource::{PaymentCost, Resource},
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PaymentPlan {
+    pub megacredits: usize,
+    pub steel: usize,
+    pub titanium: usize,
+    pub heat: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaymentError {
+    NotPaidWithMegacredits(PaymentCost), // e.g. the cost of a card action paid in plants
+    ResourceNotAllowed(Resource),
+    InsufficientResource(Resource),
+    Underpaid { paid: usize, cost: usize },
+    Overpaid { paid: usize, cost: usize },
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::NotPaidWithMegacredits(cost) => {
+                write!(f, "{:?} is not paid for with megacredits", cost)
+            }
+            PaymentError::ResourceNotAllowed(resource) => {
+                write!(f, "{:?} cannot be used to pay for this", resource)
+            }
+            PaymentError::InsufficientResource(resource) => {
+                write!(f, "not enough {:?} to pay with", resource)
+            }
+            PaymentError::Underpaid { paid, cost } => {
+                write!(f, "paid {} M$ for a cost of {} M$", paid, cost)
+            }
+            PaymentError::Overpaid { paid, cost } => {
+                write!(
+                    f,
+                    "paid {} M$ for a cost of {} M$, more than needed",
+                    paid, cost
+                )
+            }
+        }
+    }
+}
+
+impl PaymentPlan {
+    pub fn megacredits(megacredits: usize) -> Self {
+        Self {
+            megacredits,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn amounts(&self) -> [(Resource, usize); 4] {
+        [
+            (Resource::Megacredits, self.megacredits),
+            (Resource::Steel, self.steel),
+            (Resource::Titanium, self.titanium),
+            (Resource::Heat, self.heat),
+        ]
+    }
+
+    /// What the plan is worth to the player, in megacredits.
+    pub fn value(&self, player: &PlayerState) -> usize {
+        self.megacredits
+            + self.steel * player.steel_value
+            + self.titanium * player.titanium_value
+            + self.heat
+    }
+
+    /// Whether the player can pay the cost exactly as planned.
+    pub fn check(&self, player: &PlayerState, cost: PaymentCost) -> Result<(), PaymentError> {
+        let (amount, steel_allowed, titanium_allowed) = match cost {
+            PaymentCost::Megacredits(amount) => (amount, false, false),
+            PaymentCost::Building(amount) => (amount, true, false),
+            PaymentCost::Space(amount) => (amount, false, true),
+            PaymentCost::SpaceOrBuilding(amount) => (amount, true, true),
+            _ => return Err(PaymentError::NotPaidWithMegacredits(cost)),
+        };
+        let allowed = |resource: Resource| match resource {
+            Resource::Steel => steel_allowed,
+            Resource::Titanium => titanium_allowed,
+            Resource::Heat => player.can_pay_with_heat(),
+            _ => true,
+        };
+
+        for (resource, spent) in self.amounts().iter() {
+            if *spent > 0 && !allowed(*resource) {
+                return Err(PaymentError::ResourceNotAllowed(*resource));
+            }
+            if *spent > player.resources[resource] {
+                return Err(PaymentError::InsufficientResource(*resource));
+            }
+        }
+
+        let paid = self.value(player);
+        if paid < amount {
+            return Err(PaymentError::Underpaid { paid, cost: amount });
+        }
+        let excess = paid - amount;
+        let least_metal_value = [
+            (self.steel, player.steel_value),
+            (self.titanium, player.titanium_value),
+        ]
+        .iter()
+        .filter(|(spent, _)| *spent > 0)
+        .map(|(_, value)| *value)
+        .min();
+        let spends_money = self.megacredits + self.heat > 0;
+        if excess > 0 && (spends_money || least_metal_value.is_some_and(|value| excess >= value)) {
+            return Err(PaymentError::Overpaid { paid, cost: amount });
+        }
+        Ok(())
+    }
+}
+
+impl PlayerState {
+    /// How much less the player pays for the card: every discount of their effects, and of
+    /// the card they played just before it this generation, that applies to its tags.
+    pub fn card_discount(&self, card: &Card) -> usize {
+        self.effects
+            .iter()
+            .chain(self.next_card_this_generation_effects.iter())
+            .map(|effect| match effect {
+                CardEffect::AnyCardDiscount(amount) => *amount,
+                CardEffect::CardDiscountForTag(tag, amount) if card.tags.contains(tag) => *amount,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// What the card costs the player, after their discounts.
+    pub fn card_cost(&self, card: &Card) -> PaymentCost {
+        card.cost.discounted(self.card_discount(card))
+    }
+
+    /// Whether heat counts as megacredits when the player pays for things.
+    pub fn can_pay_with_heat(&self) -> bool {
+        self.effects.contains(&CardEffect::HeatAsMegacredits)
+    }
+
+    /// The plan that spends as much steel or titanium as the cost allows, titanium first,
+    /// then megacredits, then heat, or None if the player cannot afford the cost.
+    /// Metals are worth more to save for later than megacredits only when they cannot be
+    /// spent at all, so using them up first is never worse.
+    pub fn default_payment_plan(&self, cost: PaymentCost) -> Option<PaymentPlan> {
+        let metals = match cost {
+            PaymentCost::Megacredits(_) => vec![],
+            PaymentCost::Building(_) => vec![Resource::Steel],
+            PaymentCost::Space(_) => vec![Resource::Titanium],
+            PaymentCost::SpaceOrBuilding(_) => vec![Resource::Titanium, Resource::Steel],
+            _ => return None,
+        };
+        let metal_value = |resource: Resource| match resource {
+            Resource::Steel => self.steel_value,
+            _ => self.titanium_value,
+        };
+
+        let mut plan = PaymentPlan::default();
+        let mut remaining = cost.amount();
+        for metal in &metals {
+            let used = (remaining / metal_value(*metal)).min(self.resources[metal]);
+            match metal {
+                Resource::Steel => plan.steel = used,
+                _ => plan.titanium = used,
+            }
+            remaining -= used * metal_value(*metal);
+        }
+
+        let heat = if self.can_pay_with_heat() {
+            self.resources[Resource::Heat]
+        } else {
+            0
+        };
+        if remaining > self.resources[Resource::Megacredits] + heat {
+            // Less than one unit's worth is left, and the money does not cover it.
+            let metal = metals.iter().find(|metal| {
+                let spent = match metal {
+                    Resource::Steel => plan.steel,
+                    _ => plan.titanium,
+                };
+                self.resources[*metal] > spent && metal_value(**metal) >= remaining
+            })?;
+            match metal {
+                Resource::Steel => plan.steel += 1,
+                _ => plan.titanium += 1,
+            }
+            remaining = 0;
+        }
+        plan.megacredits = remaining.min(self.resources[Resource::Megacredits]);
+        plan.heat = remaining - plan.megacredits;
+        Some(plan)
+    }
+
+    /// Spend the resources of a plan, which must have been checked.
+    pub fn apply_payment(&mut self, plan: &PaymentPlan) {
+        for (resource, spent) in plan.amounts().iter() {
+            self.resources[resource] -= spent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{CardEffect, CORPORATE_GAME_CARDS_BY_NAME},
+        game::{PlayerId, PlayerStateBuilder},
+        payment::{PaymentError, PaymentPlan},
+        position::import_position,
+        resource::{PaymentCost, Resource},
+    };
+
+    #[test]
+    fn plans_must_cover_the_cost_with_allowed_resources_and_no_more() {
+        // 10 M$, 3 steel, 2 titanium, and 8 heat.
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(10, 3, 2, 0, 0, 8)
+            .build();
+        let building = PaymentCost::Building(12);
+        let plan = |megacredits, steel, titanium, heat| PaymentPlan {
+            megacredits,
+            steel,
+            titanium,
+            heat,
+        };
+
+        assert_eq!(Ok(()), plan(6, 3, 0, 0).check(&player, building));
+        assert_eq!(
+            Err(PaymentError::ResourceNotAllowed(Resource::Titanium)),
+            plan(6, 0, 2, 0).check(&player, building)
+        );
+        assert_eq!(
+            Err(PaymentError::InsufficientResource(Resource::Steel)),
+            plan(4, 4, 0, 0).check(&player, building)
+        );
+        assert_eq!(
+            Err(PaymentError::Underpaid { paid: 11, cost: 12 }),
+            plan(5, 3, 0, 0).check(&player, building)
+        );
+        assert_eq!(
+            Err(PaymentError::Overpaid { paid: 13, cost: 12 }),
+            plan(7, 3, 0, 0).check(&player, building)
+        );
+        // Titanium worth more than what is left to pay is fine, but not a spare unit of it.
+        let space = PaymentCost::Space(5);
+        assert_eq!(Ok(()), plan(0, 0, 2, 0).check(&player, space));
+        assert_eq!(
+            Err(PaymentError::Overpaid { paid: 6, cost: 2 }),
+            plan(0, 0, 2, 0).check(&player, PaymentCost::Space(2))
+        );
+        assert_eq!(
+            Err(PaymentError::NotPaidWithMegacredits(PaymentCost::Plants(1))),
+            plan(1, 0, 0, 0).check(&player, PaymentCost::Plants(1))
+        );
+
+        // Heat only counts with an effect that allows it.
+        let megacredits = PaymentCost::Megacredits(14);
+        assert_eq!(
+            Err(PaymentError::ResourceNotAllowed(Resource::Heat)),
+            plan(6, 0, 0, 8).check(&player, megacredits)
+        );
+        assert_eq!(None, player.default_payment_plan(megacredits));
+        let helion = PlayerStateBuilder::new(1)
+            .with_resources(10, 3, 2, 0, 0, 8)
+            .with_effects_override(vec![CardEffect::HeatAsMegacredits])
+            .build();
+        assert_eq!(Ok(()), plan(6, 0, 0, 8).check(&helion, megacredits));
+        assert_eq!(
+            Some(plan(10, 0, 0, 4)),
+            helion.default_payment_plan(megacredits)
+        );
+    }
+
+    #[test]
+    fn the_default_plan_spends_metals_first() {
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(10, 3, 2, 0, 0, 0)
+            .build();
+        let mut paid = player.clone();
+        let plan = player
+            .default_payment_plan(PaymentCost::SpaceOrBuilding(20))
+            .unwrap();
+        assert_eq!(
+            PaymentPlan {
+                megacredits: 8,
+                steel: 3,
+                titanium: 2,
+                heat: 0,
+            },
+            plan
+        );
+        assert_eq!(
+            Ok(()),
+            plan.check(&player, PaymentCost::SpaceOrBuilding(20))
+        );
+        paid.apply_payment(&plan);
+        assert_eq!(2, paid.resources[Resource::Megacredits]);
+
+        // A last unit of titanium covers what the megacredits cannot.
+        let poor = PlayerStateBuilder::new(1)
+            .with_resources(1, 0, 2, 0, 0, 0)
+            .build();
+        let plan = poor.default_payment_plan(PaymentCost::Space(5)).unwrap();
+        assert_eq!((0, 2), (plan.megacredits, plan.titanium));
+        assert_eq!(None, poor.default_payment_plan(PaymentCost::Space(8)));
+    }
+
+    #[test]
+    fn discounts_stack_down_to_nothing() {
+        let game = import_position(
+            "player p1\nresources 4 0 0 0 0 0\nplayed Earth Office; Earth Catapult\n\
+             hand Indentured Workers; Acquired Company; Asteroid Mining",
+        )
+        .unwrap();
+        let mut player = game.players[&PlayerId::from(1)].clone();
+        let card = |name: &str| CORPORATE_GAME_CARDS_BY_NAME[name].clone();
+
+        // Earth Office only lowers the cost of cards with an Earth tag.
+        assert_eq!(
+            PaymentCost::Megacredits(5),
+            player.card_cost(&card("Acquired Company"))
+        );
+        assert_eq!(
+            PaymentCost::Space(28),
+            player.card_cost(&card("Asteroid Mining"))
+        );
+        assert_eq!(
+            None,
+            player.can_play_card(&game.board, &card("Acquired Company"))
+        );
+
+        // Indentured Workers' discount for the next card takes the cost below nothing.
+        player
+            .play_card(&game.board, &card("Indentured Workers"))
+            .unwrap();
+        assert_eq!(
+            Some(PaymentCost::Megacredits(0)),
+            player.can_play_card(&game.board, &card("Acquired Company"))
+        );
+        player
+            .play_card(&game.board, &card("Acquired Company"))
+            .unwrap();
+        assert_eq!(4, player.resources[Resource::Megacredits]);
+        assert_eq!(
+            PaymentCost::Space(28),
+            player.card_cost(&card("Asteroid Mining"))
+        );
+    }
+}
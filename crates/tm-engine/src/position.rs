@@ -0,0 +1,730 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fmt,
+};
+
+use maplit::btreeset;
+use rand::prelude::SliceRandom;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    award::{Award, AwardStandings, AWARD_FUNDING_COSTS},
+    board::{make_base_game_board, Coordinates, MarsBoard, TileLocation},
+    card::{Card, CityKind, SpecialLocation, SpecialTile},
+    game::{GamePhase, GameState, PlayerId, PlayerState, PlayerStateBuilder},
+    milestone::MilestoneNotices,
+    options::{Expansion, GameOptions, GameOptionsError, Handicap, TurnOrder},
+    rng::GameRng,
+    timeline::ParameterTimeline,
+};
+
+/// Compact, line-oriented notation for describing a position from a physical game,
+/// so that it can be loaded into the engine and analyzed.
+///
+/// Each non-empty line holds one directive; everything after a `#` is a comment.
+/// Directives that describe a player apply to the most recent `player` line.
+/// Tags are not entered directly: they are derived from the player's played cards.
+///
+/// ```text
+/// deck corporate                    # "base" or "corporate" (default)
+/// ban Predators; Birds              # never in the deck, and never allowed into play
+/// include Research; Lagrange Observatory
+///                                   # use only the listed cards of their expansions
+/// seed 12345                       # shuffles the unknown cards; defaults to 0
+/// turn-order simultaneous           # "sequential" (default) or "simultaneous"
+/// solo                              # the solo rules; the position must have a single player
+/// generation 5
+/// first-player p2                   # holds the first player marker; defaults to rotating from p1
+/// oxygen 4                          # percent
+/// temperature -20                   # degrees Celsius
+/// ocean 5,-1
+/// greenery 1,0 p1
+/// city 0,0 p1                       # city kind defaults to RegularCity
+/// city GanymedeColony p2 GanymedeColony
+/// city 3,-6 neutral                 # the solo game's neutral cities and greeneries
+/// special 3,-3 p2 MiningArea
+/// award Banker p1                   # funded by p1; awards are listed in the order funded
+///
+/// player p1
+/// tr 25
+/// resources 10 2 0 5 0 3            # M$, steel, titanium, plants, energy, heat
+/// production 3 1 0 2 0 1
+/// played Ganymede Colony; Predators
+/// hand Solar Power; GHG Factories
+/// card-resource Predators 2
+/// tapped Predators
+/// handicap 5 1 2                    # extra M$, TR, and cards, granted when the game is set up
+/// ```
+pub fn import_position(text: &str) -> Result<GameState, PositionError> {
+    let mut parser = PositionParser::new();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        parser
+            .parse_line(line)
+            .map_err(|message| PositionError { line: index + 1, message })?;
+    }
+
+    parser.build()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PositionError {
+    pub line: usize, // 1-based; 0 if the error concerns the position as a whole
+    pub message: String,
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "invalid position: {}", self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+struct PlayerEntry {
+    builder: PlayerStateBuilder,
+    resources: Option<Vec<usize>>,
+    production: Option<Vec<isize>>,
+    card_resources: Vec<(String, usize)>,
+    tapped_cards: Vec<String>,
+}
+
+struct PositionParser {
+    options: GameOptions,
+    board: MarsBoard,
+    generation: usize,
+    first_player: Option<PlayerId>,
+    seed: u64,
+    funded_awards: Vec<(Award, PlayerId)>,
+    players: BTreeMap<PlayerId, PlayerEntry>,
+    current_player: Option<PlayerId>,
+}
+
+impl PositionParser {
+    fn new() -> Self {
+        Self {
+            options: GameOptions::default(),
+            board: make_base_game_board(),
+            generation: 1,
+            first_player: None,
+            seed: 0,
+            funded_awards: vec![],
+            players: BTreeMap::new(),
+            current_player: None,
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let (directive, rest) = match line.find(char::is_whitespace) {
+            Some(split) => (&line[..split], line[split..].trim()),
+            None => (line, ""),
+        };
+        let args: Vec<&str> = rest.split_whitespace().collect();
+
+        match directive {
+            "deck" => {
+                if !self.players.is_empty() {
+                    return Err("the deck must be chosen before any players are described".into());
+                }
+                self.options.expansions = match rest {
+                    "base" => btreeset! { Expansion::Base },
+                    "corporate" => Expansion::ALL.iter().copied().collect(),
+                    _ => return Err(format!("unknown deck \"{}\"", rest)),
+                };
+            }
+            "ban" | "include" => {
+                if !self.players.is_empty() {
+                    return Err(format!(
+                        "the {} list must be given before any players are described",
+                        directive
+                    ));
+                }
+                for name in split_card_names(rest) {
+                    let expansion =
+                        Expansion::of(name).ok_or_else(|| format!("no card named \"{}\"", name))?;
+                    if directive == "ban" {
+                        self.options.banned_cards.insert(name.to_string());
+                    } else {
+                        self.options
+                            .included_cards
+                            .entry(expansion)
+                            .or_default()
+                            .insert(name.to_string());
+                    }
+                }
+            }
+            "turn-order" => {
+                self.options.turn_order = match rest {
+                    "sequential" => TurnOrder::Sequential,
+                    "simultaneous" => TurnOrder::Simultaneous,
+                    _ => return Err(format!("unknown turn order \"{}\"", rest)),
+                };
+            }
+            "solo" => {
+                expect_arg_count(&args, 0, 0)?;
+                self.options.solo = true;
+            }
+            "generation" => {
+                self.generation = parse_number(rest)?;
+                if self.generation == 0 {
+                    return Err("generations are numbered starting from 1".into());
+                }
+            }
+            "first-player" => {
+                expect_arg_count(&args, 1, 1)?;
+                self.first_player = Some(parse_player_id(args[0])?);
+            }
+            "seed" => {
+                self.seed = parse_number(rest)?;
+            }
+            "oxygen" => {
+                let oxygen: usize = parse_number(rest)?;
+                if oxygen > MarsBoard::MAX_OXYGEN {
+                    return Err(format!("oxygen cannot exceed {}%", MarsBoard::MAX_OXYGEN));
+                }
+                self.board.oxygen = oxygen;
+            }
+            "temperature" => {
+                let temperature: isize = parse_number(rest)?;
+                if !(MarsBoard::STARTING_TEMPERATURE..=MarsBoard::MAX_TEMPERATURE).contains(&temperature)
+                    || temperature % MarsBoard::TEMPERATURE_INCREMENT != 0
+                {
+                    return Err(format!("{} is not a valid temperature", temperature));
+                }
+                self.board.temperature = temperature;
+            }
+            "ocean" => {
+                expect_arg_count(&args, 1, 1)?;
+                let coordinates = self.parse_mars_coordinates(args[0])?;
+                if !self.board.spaces[&TileLocation::OnMars(coordinates)].is_reserved_for_ocean() {
+                    return Err(format!("{} is not reserved for oceans", args[0]));
+                }
+                if self.board.oceans.len() == MarsBoard::MAX_OCEANS {
+                    return Err(format!("at most {} oceans may be placed", MarsBoard::MAX_OCEANS));
+                }
+                self.ensure_unoccupied(&TileLocation::OnMars(coordinates))?;
+                self.board.oceans.insert(coordinates);
+            }
+            "greenery" => {
+                expect_arg_count(&args, 2, 2)?;
+                let coordinates = self.parse_mars_coordinates(args[0])?;
+                let player_id = parse_tile_owner(args[1])?;
+                self.ensure_unoccupied(&TileLocation::OnMars(coordinates))?;
+                self.board.greeneries.insert(coordinates, player_id);
+            }
+            "city" => {
+                expect_arg_count(&args, 2, 3)?;
+                let location = self.parse_location(args[0])?;
+                let player_id = parse_tile_owner(args[1])?;
+                let city_kind = match args.get(2) {
+                    Some(name) => parse_variant::<CityKind>(name)?,
+                    None => CityKind::RegularCity,
+                };
+                self.ensure_unoccupied(&location)?;
+                self.board.cities.insert(location, (city_kind, player_id));
+            }
+            "special" => {
+                expect_arg_count(&args, 3, 3)?;
+                let coordinates = self.parse_mars_coordinates(args[0])?;
+                let player_id = parse_player_id(args[1])?;
+                let tile = parse_variant::<SpecialTile>(args[2])?;
+                self.ensure_unoccupied(&TileLocation::OnMars(coordinates))?;
+                self.board.special_tiles.insert(coordinates, (tile, player_id));
+            }
+            "award" => {
+                expect_arg_count(&args, 2, 2)?;
+                let award = parse_variant::<Award>(args[0])?;
+                let player_id = parse_player_id(args[1])?;
+                if self.funded_awards.iter().any(|(funded, _)| *funded == award) {
+                    return Err(format!("the {} award was already funded", args[0]));
+                }
+                if self.funded_awards.len() == AWARD_FUNDING_COSTS.len() {
+                    return Err(format!(
+                        "at most {} awards may be funded",
+                        AWARD_FUNDING_COSTS.len()
+                    ));
+                }
+                self.funded_awards.push((award, player_id));
+            }
+            "player" => {
+                expect_arg_count(&args, 1, 1)?;
+                let player_number = parse_player_number(args[0])?;
+                let player_id = PlayerId::from(player_number);
+                if player_id.is_neutral() {
+                    return Err("players are numbered starting from 1".into());
+                }
+                if self.players.contains_key(&player_id) {
+                    return Err(format!("player {} was already described", args[0]));
+                }
+
+                self.players.insert(
+                    player_id,
+                    PlayerEntry {
+                        builder: PlayerStateBuilder::new(player_number),
+                        resources: None,
+                        production: None,
+                        card_resources: vec![],
+                        tapped_cards: vec![],
+                    },
+                );
+                self.current_player = Some(player_id);
+            }
+            "tr" => {
+                let terraform_rating = parse_number(rest)?;
+                self.current_player_entry()?.builder.terraform_rating = terraform_rating;
+            }
+            "resources" => {
+                expect_arg_count(&args, 6, 6)?;
+                let amounts = args
+                    .iter()
+                    .map(|arg| parse_number::<usize>(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let entry = self.current_player_entry()?;
+                if entry.resources.replace(amounts).is_some() {
+                    return Err("resources were already given for this player".into());
+                }
+            }
+            "handicap" => {
+                expect_arg_count(&args, 3, 3)?;
+                let amounts = args
+                    .iter()
+                    .map(|arg| parse_number::<usize>(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let player_id = self.current_player_entry()?.builder.player_id;
+                let handicap = Handicap {
+                    megacredits: amounts[0],
+                    terraform_rating: amounts[1],
+                    cards: amounts[2],
+                };
+                if self.options.handicaps.insert(player_id, handicap).is_some() {
+                    return Err("a handicap was already given for this player".into());
+                }
+            }
+            "production" => {
+                expect_arg_count(&args, 6, 6)?;
+                let amounts = args
+                    .iter()
+                    .map(|arg| parse_number::<isize>(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if amounts[1..].iter().any(|amount| *amount < 0) {
+                    return Err("only M$ production may be negative".into());
+                }
+                if amounts[0] < -5 {
+                    return Err("M$ production cannot be lower than -5".into());
+                }
+
+                let entry = self.current_player_entry()?;
+                if entry.production.replace(amounts).is_some() {
+                    return Err("production was already given for this player".into());
+                }
+            }
+            "played" => {
+                let cards = self.parse_card_list(rest)?;
+                let builder = &mut self.current_player_entry()?.builder;
+                builder.played_cards.get_or_insert_with(Vec::new).extend(cards);
+            }
+            "hand" => {
+                let cards = self.parse_card_list(rest)?;
+                let builder = &mut self.current_player_entry()?.builder;
+                builder.cards_in_hand.get_or_insert_with(Vec::new).extend(cards);
+            }
+            "card-resource" => {
+                let (name, amount) = match rest.rsplit_once(char::is_whitespace) {
+                    Some((name, amount)) => (name.trim(), parse_number(amount)?),
+                    None => return Err("expected a card name followed by an amount".into()),
+                };
+                self.find_card(name)?;
+                self.current_player_entry()?
+                    .card_resources
+                    .push((name.to_string(), amount));
+            }
+            "tapped" => {
+                let names: Vec<String> = split_card_names(rest).map(String::from).collect();
+                for name in &names {
+                    self.find_card(name)?;
+                }
+                self.current_player_entry()?.tapped_cards.extend(names);
+            }
+            _ => return Err(format!("unknown directive \"{}\"", directive)),
+        }
+
+        Ok(())
+    }
+
+    fn build(self) -> Result<GameState, PositionError> {
+        let whole_position_error = |message: String| PositionError { line: 0, message };
+
+        if self.players.is_empty() {
+            return Err(whole_position_error("no players were described".into()));
+        }
+
+        let mut players: BTreeMap<PlayerId, PlayerState> = BTreeMap::new();
+        for (player_id, entry) in self.players {
+            let mut builder = entry.builder;
+            if let Some(r) = entry.resources {
+                builder = builder.with_resources(r[0], r[1], r[2], r[3], r[4], r[5]);
+            }
+            if let Some(p) = entry.production {
+                builder = builder.with_production(p[0], p[1], p[2], p[3], p[4], p[5]);
+            }
+            let mut player = builder.build();
+
+            for (name, amount) in entry.card_resources {
+                let card = player
+                    .played_cards
+                    .iter()
+                    .find(|card| card.name == name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        whole_position_error(format!(
+                            "card resources placed on \"{}\", which {:?} has not played",
+                            name, player_id
+                        ))
+                    })?;
+                let card_resource = card.supports_card_resource().ok_or_else(|| {
+                    whole_position_error(format!("\"{}\" does not hold card resources", name))
+                })?;
+                player.card_resources.insert((card, card_resource), amount);
+            }
+
+            for name in entry.tapped_cards {
+                let card = player
+                    .played_cards
+                    .iter()
+                    .find(|card| card.name == name && !card.actions.is_empty())
+                    .cloned()
+                    .ok_or_else(|| {
+                        whole_position_error(format!(
+                            "\"{}\" is not an active card played by {:?}",
+                            name, player_id
+                        ))
+                    })?;
+                player.tapped_active_cards.insert(card);
+            }
+
+            players.insert(player_id, player);
+        }
+
+        let mut owned_tiles: Vec<PlayerId> = vec![];
+        owned_tiles.extend(self.board.cities.values().map(|(_, player_id)| *player_id));
+        owned_tiles.extend(self.board.greeneries.values().copied());
+        owned_tiles.extend(self.board.special_tiles.values().map(|(_, player_id)| *player_id));
+        owned_tiles.extend(self.funded_awards.iter().map(|(_, player_id)| *player_id));
+        if let Some(player_id) = owned_tiles
+            .iter()
+            .find(|id| !id.is_neutral() && !players.contains_key(id))
+        {
+            return Err(whole_position_error(format!(
+                "{:?} owns tiles or funded an award but was never described",
+                player_id
+            )));
+        }
+
+        if self.options.solo && players.len() != 1 {
+            return Err(whole_position_error(
+                GameOptionsError::SoloNeedsOnePlayer(players.len()).to_string(),
+            ));
+        }
+
+        if let Some(player_id) = self.first_player.filter(|id| !players.contains_key(id)) {
+            return Err(whole_position_error(format!(
+                "{:?} holds the first player marker but was never described",
+                player_id
+            )));
+        }
+
+        // Each physical card exists exactly once, so a card cannot be both in a hand and played.
+        let mut known_cards: HashSet<&str> = HashSet::new();
+        for player in players.values() {
+            for card in player.played_cards.iter().chain(player.cards_in_hand.iter()) {
+                if !known_cards.insert(card.name.as_ref()) {
+                    return Err(whole_position_error(format!(
+                        "\"{}\" appears more than once in the position",
+                        card.name
+                    )));
+                }
+            }
+        }
+
+        self.options
+            .validate()
+            .map_err(|error| whole_position_error(error.to_string()))?;
+        let mut draw_deck: Vec<Card> = self
+            .options
+            .deck()
+            .into_iter()
+            .filter(|card| !known_cards.contains(card.name.as_str()))
+            .collect();
+        let mut rng = GameRng::from_seed(self.seed);
+        draw_deck.shuffle(&mut rng);
+
+        let mut game = GameState {
+            board: self.board,
+            players,
+            draw_deck,
+            discard_pile: vec![],
+            generation: self.generation,
+            phase: GamePhase::default(),
+            first_player_marker: self.first_player,
+            turn_player: None,
+            passed_players: BTreeSet::new(),
+            options: self.options,
+            funded_awards: self.funded_awards,
+            rng,
+            award_standings: AwardStandings::default(),
+            milestone_notices: MilestoneNotices::default(),
+            parameter_timeline: ParameterTimeline::default(),
+            turmoil: None,
+            corporation_offers: BTreeMap::new(),
+            scheduled_effects: vec![],
+            research_draft: None,
+        };
+        game.award_standings = AwardStandings::compute(&game);
+        game.milestone_notices = MilestoneNotices::compute(&game);
+        Ok(game)
+    }
+
+    fn current_player_entry(&mut self) -> Result<&mut PlayerEntry, String> {
+        let player_id = self
+            .current_player
+            .ok_or_else(|| String::from("player details must follow a \"player\" line"))?;
+        Ok(self.players.get_mut(&player_id).unwrap())
+    }
+
+    fn find_card(&self, name: &str) -> Result<&Card, String> {
+        let card = self
+            .options
+            .expansions
+            .iter()
+            .flat_map(|expansion| expansion.cards().iter())
+            .find(|card| card.name == name)
+            .ok_or_else(|| format!("no card named \"{}\" in the chosen deck", name))?;
+        if !self.options.allows(card) {
+            return Err(format!("\"{}\" is not allowed in this game", name));
+        }
+        Ok(card)
+    }
+
+    fn parse_card_list(&self, text: &str) -> Result<Vec<Card>, String> {
+        split_card_names(text)
+            .map(|name| self.find_card(name).cloned())
+            .collect()
+    }
+
+    fn parse_mars_coordinates(&self, text: &str) -> Result<Coordinates, String> {
+        match self.parse_location(text)? {
+            TileLocation::OnMars(coordinates) => Ok(coordinates),
+            TileLocation::OffMars(_) => Err(format!("{} is not a location on Mars", text)),
+        }
+    }
+
+    fn parse_location(&self, text: &str) -> Result<TileLocation, String> {
+        let location = match text.split_once(',') {
+            Some((x, y)) => TileLocation::OnMars(Coordinates::new(parse_number(x)?, parse_number(y)?)),
+            None => TileLocation::OffMars(parse_variant::<SpecialLocation>(text)?),
+        };
+
+        if self.board.spaces.contains_key(&location) {
+            Ok(location)
+        } else {
+            Err(format!("{} is not a space on the {} board", text, self.board.board_name))
+        }
+    }
+
+    fn ensure_unoccupied(&self, location: &TileLocation) -> Result<(), String> {
+        let occupied = self.board.cities.contains_key(location)
+            || match location {
+                TileLocation::OnMars(coordinates) => {
+                    self.board.oceans.contains(coordinates)
+                        || self.board.greeneries.contains_key(coordinates)
+                        || self.board.special_tiles.contains_key(coordinates)
+                }
+                TileLocation::OffMars(_) => false,
+            };
+
+        if occupied {
+            Err(format!("{:?} already has a tile on it", location))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub fn split_card_names(text: &str) -> impl Iterator<Item = &str> {
+    text.split(';').map(str::trim).filter(|name| !name.is_empty())
+}
+
+fn expect_arg_count(args: &[&str], min: usize, max: usize) -> Result<(), String> {
+    if args.len() < min || args.len() > max {
+        Err(if min == max {
+            format!("expected {} arguments, found {}", min, args.len())
+        } else {
+            format!("expected {} to {} arguments, found {}", min, max, args.len())
+        })
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a valid number", text.trim()))
+}
+
+fn parse_player_number(text: &str) -> Result<usize, String> {
+    let digits = text
+        .strip_prefix('p')
+        .or_else(|| text.strip_prefix('P'))
+        .ok_or_else(|| format!("\"{}\" is not a player, expected e.g. \"p1\"", text))?;
+    parse_number(digits)
+}
+
+pub fn parse_player_id(text: &str) -> Result<PlayerId, String> {
+    parse_player_number(text).map(PlayerId::from)
+}
+
+// Cities and greeneries may also be the solo game's neutral tiles.
+fn parse_tile_owner(text: &str) -> Result<PlayerId, String> {
+    if text == "neutral" {
+        Ok(PlayerId::NEUTRAL)
+    } else {
+        parse_player_id(text)
+    }
+}
+
+// Enum values are spelled the same way as in the JSON deck files.
+pub(crate) fn parse_variant<T: DeserializeOwned>(text: &str) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(text.to_string()))
+        .map_err(|_| format!("\"{}\" is not a recognized value", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{Coordinates, TileLocation},
+        card::{CityKind, SpecialLocation, BASE_GAME_CARDS_BY_NAME},
+        game::PlayerId,
+        position::import_position,
+        resource::{CardResource, Resource},
+    };
+
+    #[test]
+    fn imports_board_and_player_details() {
+        let text = "
+            # a mid-game position
+            generation 5
+            first-player p2
+            oxygen 4
+            temperature -20
+            ocean 5,-1
+            greenery 1,0 p1
+            city 0,0 p1
+            city GanymedeColony p2 GanymedeColony
+
+            player p1
+            tr 25
+            resources 10 2 0 5 0 3
+            production 3 1 0 2 0 1
+            played Tundra Farming; Predators
+            hand Solar Power
+            card-resource Predators 2
+            tapped Predators
+
+            player p2
+            played Ganymede Colony
+        ";
+
+        let game = import_position(text).unwrap();
+        assert_eq!(5, game.generation);
+        assert_eq!(Some(PlayerId::from(2)), game.first_player());
+        assert_eq!(4, game.board.oxygen);
+        assert_eq!(-20, game.board.temperature);
+        assert!(game.board.oceans.contains(&Coordinates::new(5, -1)));
+        assert_eq!(
+            Some(&(CityKind::GanymedeColony, PlayerId::from(2))),
+            game.board
+                .cities
+                .get(&TileLocation::OffMars(SpecialLocation::GanymedeColony))
+        );
+
+        let p1 = &game.players[&PlayerId::from(1)];
+        assert_eq!(25, p1.terraform_rating);
+        assert_eq!(10, p1.resources[&Resource::Megacredits]);
+        assert_eq!(2, p1.production[&Resource::Plants]);
+        assert_eq!(2, p1.played_cards.len());
+        assert_eq!(1, p1.cards_in_hand.len());
+
+        let predators = BASE_GAME_CARDS_BY_NAME["Predators"].clone();
+        assert_eq!(
+            Some(&2),
+            p1.card_resources.get(&(predators.clone(), CardResource::Animal))
+        );
+        assert!(p1.tapped_active_cards.contains(&predators));
+
+        // Cards known to be in hands or in play are not in the draw deck.
+        assert!(game.draw_deck.iter().all(|card| card.name != "Solar Power"));
+        assert!(game.draw_deck.iter().all(|card| card.name != "Ganymede Colony"));
+
+        // The imported position scores like any other game: TR, 2VP from Tundra Farming,
+        // 1VP per animal on Predators, the greenery, and the city next to it.
+        assert_eq!(
+            25 + 2 + 2 + 1 + 1,
+            p1.get_total_victory_points(&game.board)
+        );
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        let text = "player p1\nplayed Tundra Farming; Not A Real Card\n";
+        let error = import_position(text).unwrap_err();
+        assert_eq!(2, error.line);
+    }
+
+    #[test]
+    fn rejects_tiles_on_occupied_spaces() {
+        let text = "player p1\ngreenery 1,0 p1\ncity 1,0 p1\n";
+        let error = import_position(text).unwrap_err();
+        assert_eq!(3, error.line);
+    }
+
+    #[test]
+    fn banned_and_excluded_cards_stay_out_of_the_game() {
+        let text = "ban Predators\ninclude Research\nplayer p1\nhand Solar Power\n";
+        let game = import_position(text).unwrap();
+        assert!(game.draw_deck.iter().all(|card| card.name != "Predators"));
+        assert!(game.draw_deck.iter().any(|card| card.name == "Research"));
+        assert!(game
+            .draw_deck
+            .iter()
+            .all(|card| card.name != "Lagrange Observatory"));
+
+        let error = import_position("ban Predators\nplayer p1\nplayed Predators\n").unwrap_err();
+        assert_eq!(3, error.line);
+        assert_eq!("\"Predators\" is not allowed in this game", error.message);
+
+        let error = import_position("deck base\ninclude Research\nplayer p1\n").unwrap_err();
+        assert_eq!(0, error.line);
+
+        let error = import_position("player p1\nban Predators\n").unwrap_err();
+        assert_eq!(2, error.line);
+    }
+
+    #[test]
+    fn rejects_tiles_owned_by_unknown_players() {
+        let text = "player p1\ngreenery 1,0 p3\n";
+        let error = import_position(text).unwrap_err();
+        assert_eq!(0, error.line);
+
+        let error = import_position("first-player p2\nplayer p1\n").unwrap_err();
+        assert_eq!(0, error.line);
+    }
+}
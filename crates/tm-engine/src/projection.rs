@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::MarsBoard,
+    game::{GameState, PlayerId},
+    resource::Resource,
+    timeline::ParameterTimeline,
+};
+
+pub const PLANTS_PER_GREENERY: usize = 8;
+pub const HEAT_PER_TEMPERATURE_STEP: usize = 8;
+
+// Total number of global parameter steps on the standard board: 14 oxygen, 19 temperature, 9 oceans.
+const TOTAL_PARAMETER_STEPS: usize = 42;
+
+// How many generations' worth of evidence the typical pace counts as, against the pace
+// observed in the game so far.
+const TYPICAL_PACE_WEIGHT: f64 = 2.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectionAssumptions {
+    // Number of production phases still to come, including the current generation's.
+    // If not given, it is estimated from how far the global parameters still have to go.
+    pub remaining_generations: Option<usize>,
+
+    // How many global parameter steps all players together complete in a typical generation;
+    // once generations of the game have been played, it is blended with their pace.
+    pub parameter_steps_per_generation: f64,
+}
+
+impl Default for ProjectionAssumptions {
+    fn default() -> Self {
+        Self {
+            remaining_generations: None,
+            parameter_steps_per_generation: 4.0,
+        }
+    }
+}
+
+/// Expected end-of-game victory points of a player, if the game continues at the assumed pace
+/// and the player turns all of their plants into greeneries and all heat into temperature.
+///
+/// Awards are not part of the projection, since awards are not yet part of the game model.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreProjection {
+    pub player_id: PlayerId,
+    pub remaining_generations: usize,
+
+    // what the player would score if the game ended right now
+    pub current_points: isize,
+
+    // greeneries from stockpiled plants and plant income, worth 1VP each,
+    // plus 1TR each while the oxygen level has not yet reached its maximum
+    pub projected_greeneries: usize,
+    pub greenery_points: isize,
+
+    // temperature steps from stockpiled heat, heat income, and energy income
+    pub projected_temperature_steps: usize,
+    pub temperature_points: isize,
+
+    pub total_points: isize,
+}
+
+pub fn remaining_parameter_steps(board: &MarsBoard) -> usize {
+    let oxygen_steps = (MarsBoard::MAX_OXYGEN - board.oxygen) / MarsBoard::OXYGEN_INCREMENT;
+    let temperature_steps = ((MarsBoard::MAX_TEMPERATURE - board.temperature)
+        / MarsBoard::TEMPERATURE_INCREMENT) as usize;
+    let ocean_steps = MarsBoard::MAX_OCEANS - board.oceans.len();
+
+    oxygen_steps + temperature_steps + ocean_steps
+}
+
+pub fn estimate_remaining_generations(board: &MarsBoard, steps_per_generation: f64) -> usize {
+    assert!(steps_per_generation > 0.0);
+    debug_assert!(remaining_parameter_steps(board) <= TOTAL_PARAMETER_STEPS);
+
+    // The game always lasts at least until the end of the current generation.
+    let remaining_steps = remaining_parameter_steps(board) as f64;
+    ((remaining_steps / steps_per_generation).ceil() as usize).max(1)
+}
+
+/// The pace of the rest of the game, in global parameter steps per generation: the typical
+/// pace, pulled toward the pace of the game so far the more generations have been played.
+pub fn expected_steps_per_generation(
+    timeline: &ParameterTimeline,
+    typical_steps_per_generation: f64,
+) -> f64 {
+    match timeline.observed_steps() {
+        Some((steps, generations)) => {
+            (typical_steps_per_generation * TYPICAL_PACE_WEIGHT + steps as f64)
+                / (TYPICAL_PACE_WEIGHT + generations as f64)
+        }
+        None => typical_steps_per_generation,
+    }
+}
+
+/// The number of production phases still to come in the game, including the current
+/// generation's, unless the assumptions fix it.
+pub fn estimate_game_generations_left(
+    game: &GameState,
+    assumptions: &ProjectionAssumptions,
+) -> usize {
+    assumptions.remaining_generations.unwrap_or_else(|| {
+        let steps_per_generation = expected_steps_per_generation(
+            &game.parameter_timeline,
+            assumptions.parameter_steps_per_generation,
+        );
+        estimate_remaining_generations(&game.board, steps_per_generation)
+    })
+}
+
+pub fn project_final_score(
+    game: &GameState,
+    player_id: PlayerId,
+    assumptions: &ProjectionAssumptions,
+) -> ScoreProjection {
+    let board = &game.board;
+    let player = &game.players[&player_id];
+
+    let remaining_generations = estimate_game_generations_left(game, assumptions);
+    let income =
+        |resource: Resource| (player.production[&resource].max(0) as usize) * remaining_generations;
+
+    let final_plants = player.resources[&Resource::Plants] + income(Resource::Plants);
+    let projected_greeneries = final_plants / PLANTS_PER_GREENERY;
+    let oxygen_steps_left = (MarsBoard::MAX_OXYGEN - board.oxygen) / MarsBoard::OXYGEN_INCREMENT;
+    let greenery_points =
+        (projected_greeneries + projected_greeneries.min(oxygen_steps_left)) as isize;
+
+    // Energy left over at the end of each generation turns into heat.
+    let final_heat = player.resources[&Resource::Heat]
+        + player.resources[&Resource::Energy]
+        + income(Resource::Heat)
+        + income(Resource::Energy);
+    let temperature_steps_left = ((MarsBoard::MAX_TEMPERATURE - board.temperature)
+        / MarsBoard::TEMPERATURE_INCREMENT) as usize;
+    let projected_temperature_steps =
+        (final_heat / HEAT_PER_TEMPERATURE_STEP).min(temperature_steps_left);
+    let temperature_points = projected_temperature_steps as isize;
+
+    let current_points = player.get_total_victory_points(board);
+
+    ScoreProjection {
+        player_id,
+        remaining_generations,
+        current_points,
+        projected_greeneries,
+        greenery_points,
+        projected_temperature_steps,
+        temperature_points,
+        total_points: current_points + greenery_points + temperature_points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{make_base_game_board, MarsBoard},
+        game::PlayerStateBuilder,
+        projection::{estimate_remaining_generations, project_final_score, ProjectionAssumptions},
+        test_utils::{board_with_parameters, make_game},
+    };
+
+    #[test]
+    fn plants_and_plant_income_become_greeneries() {
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 10, 0, 0)
+            .with_production(0, 0, 0, 3, 0, 0)
+            .build();
+        let player_id = player.player_id;
+        let game = make_game(vec![player]);
+
+        let assumptions = ProjectionAssumptions {
+            remaining_generations: Some(2),
+            ..Default::default()
+        };
+        let projection = project_final_score(&game, player_id, &assumptions);
+
+        // 10 plants + 2 * 3 plant income = 16 plants = 2 greeneries, each worth 1VP and 1TR.
+        assert_eq!(2, projection.projected_greeneries);
+        assert_eq!(4, projection.greenery_points);
+        assert_eq!(projection.current_points + 4, projection.total_points);
+    }
+
+    #[test]
+    fn terraforming_points_are_capped_by_parameters_left() {
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 24, 4, 20)
+            .build();
+        let player_id = player.player_id;
+
+        let mut board = make_base_game_board();
+        board.oxygen = MarsBoard::MAX_OXYGEN - 1;
+        board.temperature = MarsBoard::MAX_TEMPERATURE - MarsBoard::TEMPERATURE_INCREMENT;
+        let mut game = make_game(vec![player]);
+        game.board = board;
+
+        let assumptions = ProjectionAssumptions {
+            remaining_generations: Some(1),
+            ..Default::default()
+        };
+        let projection = project_final_score(&game, player_id, &assumptions);
+
+        // 3 greeneries, only one of which raises oxygen.
+        assert_eq!(3, projection.projected_greeneries);
+        assert_eq!(4, projection.greenery_points);
+
+        // 24 heat (including energy) would be enough for 3 steps, but only 1 remains.
+        assert_eq!(1, projection.projected_temperature_steps);
+        assert_eq!(1, projection.temperature_points);
+    }
+
+    #[test]
+    fn remaining_generations_follow_parameter_progress() {
+        let mut board = make_base_game_board();
+        assert_eq!(11, estimate_remaining_generations(&board, 4.0));
+
+        board.oxygen = MarsBoard::MAX_OXYGEN;
+        board.temperature = MarsBoard::MAX_TEMPERATURE;
+        assert_eq!(3, estimate_remaining_generations(&board, 4.0));
+
+        // Even with all parameters maxed, the current generation still has to finish.
+        let board = board_with_parameters(
+            MarsBoard::MAX_OXYGEN,
+            MarsBoard::MAX_TEMPERATURE,
+            MarsBoard::MAX_OCEANS,
+        );
+        assert_eq!(1, estimate_remaining_generations(&board, 4.0));
+    }
+
+    #[test]
+    fn the_observed_pace_is_blended_with_the_typical_one() {
+        let player = PlayerStateBuilder::new(1).build();
+        let player_id = player.player_id;
+        let mut game = make_game(vec![player]);
+        let assumptions = ProjectionAssumptions::default();
+        assert_eq!(
+            11,
+            project_final_score(&game, player_id, &assumptions).remaining_generations
+        );
+
+        // 7 steps in the first generation, against 4 steps typically counted twice:
+        // 5 steps per generation, so 35 more steps take 7 generations.
+        game.board.oxygen = 7 * MarsBoard::OXYGEN_INCREMENT;
+        game.advance_generation();
+        assert_eq!(7, game.estimated_generations_left());
+        assert_eq!(
+            7,
+            project_final_score(&game, player_id, &assumptions).remaining_generations
+        );
+    }
+}
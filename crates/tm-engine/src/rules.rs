@@ -0,0 +1,259 @@
+//! Rules that depend on the number of players: how awards are scored, and who the effects
+//! aimed at "any player" can hit when a card is played.
+//!
+//! In a two-player game, every attack hits the one player there is to catch up with,
+//! and with more players the one in the lead.
+
+use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CardEffect},
+    game::{GameOperation, GameState, PlayerId},
+    resource::{CardResource, Resource},
+};
+
+/// Second place of an award is only scored with at least this many players.
+pub const MIN_PLAYERS_FOR_SECOND_PLACE: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerCountRules {
+    pub player_count: usize,
+}
+
+impl PlayerCountRules {
+    pub fn new(player_count: usize) -> Self {
+        Self { player_count }
+    }
+
+    pub fn of(game: &GameState) -> Self {
+        Self::new(game.players.len())
+    }
+
+    pub fn is_two_player(self) -> bool {
+        self.player_count == 2
+    }
+
+    pub fn scores_second_place(self) -> bool {
+        self.player_count >= MIN_PLAYERS_FOR_SECOND_PLACE
+    }
+
+    /// The players whose production of the resource can be decreased by the amount,
+    /// e.g. by a card's `any_production`, in player id order.
+    ///
+    /// The decrease is mandatory and may hit the player themself, so a card decreasing
+    /// production can only be played if there is any target at all.
+    pub fn production_decrease_targets(
+        self,
+        game: &GameState,
+        resource: Resource,
+        amount: usize,
+    ) -> Vec<PlayerId> {
+        game.players
+            .keys()
+            .copied()
+            .filter(|player_id| {
+                let decrease = btreemap! { resource => -(amount as isize) };
+                game.check_operation(&GameOperation::ChangeProduction(*player_id, decrease))
+                    .is_ok()
+            })
+            .collect()
+    }
+
+    /// The opponents holding any of the resource, for impacts that remove or steal
+    /// up to an amount from any player, e.g. `StealResource`. Those impacts are optional,
+    /// so the player never has to take from themself.
+    pub fn resource_targets(
+        self,
+        game: &GameState,
+        player_id: PlayerId,
+        resource: Resource,
+    ) -> Vec<PlayerId> {
+        game.players
+            .values()
+            .filter(|opponent| opponent.player_id != player_id && opponent.resources[resource] > 0)
+            .map(|opponent| opponent.player_id)
+            .collect()
+    }
+
+    /// The opponents' cards the card resource can be removed from, e.g. by
+    /// `DestroyAnyCardResource`, skipping cards whose resources are protected.
+    pub fn card_resource_targets(
+        self,
+        game: &GameState,
+        player_id: PlayerId,
+        card_resource: CardResource,
+    ) -> Vec<(PlayerId, Card)> {
+        game.players
+            .values()
+            .filter(|opponent| opponent.player_id != player_id)
+            .filter(|opponent| {
+                !opponent.effects.iter().any(|effect| {
+                    matches!(effect, CardEffect::CannotRemoveAnyCardResources(protected)
+                        if protected.contains(&card_resource))
+                })
+            })
+            .flat_map(|opponent| {
+                opponent
+                    .card_resources
+                    .iter()
+                    .filter(|((card, resource), amount)| {
+                        *resource == card_resource
+                            && **amount > 0
+                            && !card
+                                .effects
+                                .contains(&CardEffect::CannotRemoveThisCardResource(card_resource))
+                    })
+                    .map(move |((card, _), _)| (opponent.player_id, card.clone()))
+            })
+            .collect()
+    }
+
+    /// Which of the targets to aim an attack at: in a two-player game the opponent, and
+    /// otherwise the opponent with the most victory points, the lowest player id among
+    /// equals. The player themself is only chosen if there is no one else.
+    pub fn preferred_target(
+        self,
+        game: &GameState,
+        player_id: PlayerId,
+        targets: &[PlayerId],
+    ) -> Option<PlayerId> {
+        let mut opponents = targets
+            .iter()
+            .copied()
+            .filter(|target| *target != player_id);
+        let preferred = if self.is_two_player() {
+            opponents.next()
+        } else {
+            opponents
+                .fold(None, |best: Option<(PlayerId, isize)>, target| {
+                    let points = game.players[&target].get_total_victory_points(&game.board);
+                    match best {
+                        Some((_, best_points)) if best_points >= points => best,
+                        _ => Some((target, points)),
+                    }
+                })
+                .map(|(target, _)| target)
+        };
+        preferred.or_else(|| targets.iter().copied().find(|target| *target == player_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{BASE_GAME_CARDS_BY_NAME, CORPORATE_GAME_CARDS_BY_NAME},
+        game::{IllegalOperation, PlayerId, PlayerTurn, TurnAction},
+        position::import_position,
+        resource::{CardResource, Resource},
+        rules::PlayerCountRules,
+    };
+
+    #[test]
+    fn two_player_attacks_hit_the_opponent_or_nobody() {
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let mut game = import_position(
+            "player p1\n\
+             resources 0 0 0 5 0 0\n\
+             production 0 0 0 1 0 0\n\
+             player p2",
+        )
+        .unwrap();
+        let rules = PlayerCountRules::of(&game);
+        assert!(rules.is_two_player());
+
+        // Only p1 has plant production, so p1 has to decrease their own.
+        let targets = rules.production_decrease_targets(&game, Resource::Plants, 1);
+        assert_eq!(vec![p1], targets);
+        assert_eq!(Some(p1), rules.preferred_target(&game, p1, &targets));
+        // Megacredit production can go negative, down to minus the terraform rating.
+        assert_eq!(
+            vec![p1, p2],
+            rules.production_decrease_targets(&game, Resource::Megacredits, 20)
+        );
+        assert!(rules
+            .production_decrease_targets(&game, Resource::Heat, 1)
+            .is_empty());
+
+        // Taking up to some plants is optional, and there is no one else to take them from.
+        assert!(rules
+            .resource_targets(&game, p1, Resource::Plants)
+            .is_empty());
+        assert_eq!(
+            vec![p1],
+            rules.resource_targets(&game, p2, Resource::Plants)
+        );
+
+        // Pets protects its animals, Fish does not.
+        for name in ["Pets", "Fish"] {
+            let card = BASE_GAME_CARDS_BY_NAME[name].clone();
+            let player = game.players.get_mut(&p2).unwrap();
+            player.played_cards.push(card.clone());
+            player
+                .card_resources
+                .insert((card, CardResource::Animal), 2);
+        }
+        let fish = BASE_GAME_CARDS_BY_NAME["Fish"].clone();
+        assert_eq!(
+            vec![(p2, fish)],
+            rules.card_resource_targets(&game, p1, CardResource::Animal)
+        );
+    }
+
+    #[test]
+    fn multiplayer_attacks_aim_at_the_leader() {
+        let game = import_position(
+            "player p1\n\
+             player p2\n\
+             tr 22\n\
+             player p3\n\
+             tr 25",
+        )
+        .unwrap();
+        let rules = PlayerCountRules::of(&game);
+        let everyone: Vec<PlayerId> = game.players.keys().copied().collect();
+        assert_eq!(
+            Some(PlayerId::from(3)),
+            rules.preferred_target(&game, PlayerId::from(1), &everyone)
+        );
+        assert_eq!(
+            Some(PlayerId::from(2)),
+            rules.preferred_target(&game, PlayerId::from(3), &everyone)
+        );
+    }
+
+    #[test]
+    fn attacks_of_cards_played_on_a_turn_hit_the_preferred_target() {
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let mut game = import_position(
+            "player p1\n\
+             resources 30 0 0 0 0 0\n\
+             hand Energy Tapping; Asteroid; Herbivores\n\
+             player p2\n\
+             resources 0 0 0 5 0 0\n\
+             production 0 0 0 0 1 0",
+        )
+        .unwrap();
+        let play = |name: &str| {
+            let card = CORPORATE_GAME_CARDS_BY_NAME[name].clone();
+            PlayerTurn::Play(TurnAction::PlayCard(card, vec![]), None)
+        };
+
+        // The energy production comes from the opponent.
+        game.take_turn(p1, &play("Energy Tapping")).unwrap();
+        assert_eq!(1, game.players[&p1].production[Resource::Energy]);
+        assert_eq!(0, game.players[&p2].production[Resource::Energy]);
+
+        // The asteroid removes up to 3 of the opponent's plants.
+        game.take_turn(p1, &play("Asteroid")).unwrap();
+        assert_eq!(2, game.players[&p1].resources[Resource::Titanium]);
+        assert_eq!(2, game.players[&p2].resources[Resource::Plants]);
+
+        // Nobody has plant production to lose, so Herbivores can't be played.
+        game.board.oxygen = 8;
+        assert_eq!(
+            Err(IllegalOperation::InsufficientProduction(Resource::Plants)),
+            game.take_turn(p1, &play("Herbivores"))
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    award::AwardStandings,
+    game::GameState,
+    migrate::{replace_card_bodies, report_field_changes, upgrade_stamp, Migration},
+    milestone::MilestoneNotices,
+    stamp::{BuildStamp, StampMismatch},
+};
+
+/// Bumped whenever the layout of `SavedGame` changes incompatibly.
+pub const SAVE_FORMAT_VERSION: u32 = 2;
+
+/// A saved game, stamped with the build it was saved by.
+///
+/// Cards are saved by name, so a save can only be loaded faithfully by a build
+/// whose deck data matches the one that wrote it, and which plays by the same rules.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedGame {
+    // Only the stamp is read to check compatibility, so that it can be checked
+    // even if the rest of the save does not parse against the current deck.
+    #[serde(flatten)]
+    pub stamp: BuildStamp,
+    pub game: GameState,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadError {
+    Malformed(String),
+    DeckMismatch {
+        saved_hash: String,
+        current_hash: String,
+    },
+    RulesMismatch {
+        saved_revision: u32,
+        current_revision: u32,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Malformed(message) => write!(f, "malformed save: {}", message),
+            LoadError::DeckMismatch {
+                saved_hash,
+                current_hash,
+            } => write!(
+                f,
+                "save was made with different card data (deck hash {}, current deck hash {})",
+                saved_hash, current_hash
+            ),
+            LoadError::RulesMismatch {
+                saved_revision,
+                current_revision,
+            } => write!(
+                f,
+                "save was made under different rules (revision {}, current revision {})",
+                saved_revision, current_revision
+            ),
+        }
+    }
+}
+
+pub fn save_game(game: &GameState) -> String {
+    let saved_game = SavedGame {
+        stamp: BuildStamp::current(),
+        game: game.clone(),
+    };
+    serde_json::to_string_pretty(&saved_game).unwrap()
+}
+
+pub fn load_game(text: &str) -> Result<GameState, LoadError> {
+    load_game_with_warnings(text).map(|(game, _)| game)
+}
+
+/// Load a game, along with how the build that saved it differs from this one
+/// in ways that do not keep the game from loading.
+pub fn load_game_with_warnings(text: &str) -> Result<(GameState, Vec<StampMismatch>), LoadError> {
+    let stamp: BuildStamp =
+        serde_json::from_str(text).map_err(|e| LoadError::Malformed(e.to_string()))?;
+    let warnings = stamp.check().map_err(|mismatch| match mismatch {
+        StampMismatch::DeckHash { stamped, current } => LoadError::DeckMismatch {
+            saved_hash: stamped,
+            current_hash: current,
+        },
+        StampMismatch::RulesRevision { stamped, current } => LoadError::RulesMismatch {
+            saved_revision: stamped,
+            current_revision: current,
+        },
+        StampMismatch::EngineVersion { .. } => {
+            unreachable!("engine versions are only warned about")
+        }
+    })?;
+
+    let mut game = serde_json::from_str::<SavedGame>(text)
+        .map(|saved_game| saved_game.game)
+        .map_err(|e| LoadError::Malformed(e.to_string()))?;
+    game.award_standings = AwardStandings::compute(&game);
+    game.milestone_notices = MilestoneNotices::compute(&game);
+    Ok((game, warnings))
+}
+
+/// Load a game saved by an older build wherever it can still be read, as described in
+/// `migrate`, along with every change that was made to read it.
+pub fn load_game_leniently(text: &str) -> Result<(GameState, Vec<Migration>), LoadError> {
+    let malformed = |e: serde_json::Error| LoadError::Malformed(e.to_string());
+    let mut value: Value = serde_json::from_str(text).map_err(malformed)?;
+
+    let mut migrations = vec![];
+    if value.get("game").is_none() && value.get("players").is_some() {
+        value = json!({ "game": value });
+        migrations.push(Migration::WrappedGameState);
+    }
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| LoadError::Malformed("a save must be an object".to_string()))?;
+    upgrade_stamp(object, "", &mut migrations);
+    if let Some(game) = object.get_mut("game") {
+        replace_card_bodies(game, "game", &mut migrations);
+    }
+
+    let saved_game: SavedGame = serde_json::from_value(value.clone()).map_err(malformed)?;
+    let written = serde_json::to_value(&saved_game).unwrap();
+    report_field_changes(&value, &written, "", &mut migrations);
+
+    let mut game = saved_game.game;
+    game.award_standings = AwardStandings::compute(&game);
+    game.milestone_notices = MilestoneNotices::compute(&game);
+    Ok((game, migrations))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        position::import_position,
+        registry::CardRegistry,
+        migrate::Migration,
+        save::{load_game, load_game_leniently, save_game, LoadError},
+        stamp::{StampMismatch, RULES_REVISION},
+    };
+
+    const POSITION: &str = "
+        generation 3
+        ocean 5,-1
+        greenery 1,0 p1
+        city 0,0 p1
+        player p1
+        played Predators
+        hand Solar Power
+        card-resource Predators 2
+        player p2
+    ";
+
+    #[test]
+    fn saved_games_round_trip() {
+        let game = import_position(POSITION).unwrap();
+
+        let text = save_game(&game);
+        assert!(text.contains(&CardRegistry::global().content_hash_hex()));
+        assert_eq!(game, load_game(&text).unwrap());
+    }
+
+    #[test]
+    fn saves_from_other_deck_versions_are_reported() {
+        let game = import_position(POSITION).unwrap();
+        let current_hash = CardRegistry::global().content_hash_hex();
+        let text = save_game(&game).replace(&current_hash, "0123456789abcdef");
+
+        assert_eq!(
+            Err(LoadError::DeckMismatch {
+                saved_hash: "0123456789abcdef".to_string(),
+                current_hash,
+            }),
+            load_game(&text)
+        );
+
+        let text = save_game(&game).replace(
+            &format!("\"rules_revision\": {}", RULES_REVISION),
+            "\"rules_revision\": 0",
+        );
+        assert_eq!(
+            Err(LoadError::RulesMismatch {
+                saved_revision: 0,
+                current_revision: RULES_REVISION,
+            }),
+            load_game(&text)
+        );
+    }
+
+    #[test]
+    fn saves_from_older_builds_are_upgraded_leniently() {
+        let game = import_position(POSITION).unwrap();
+        let mut saved: serde_json::Value = serde_json::from_str(&save_game(&game)).unwrap();
+
+        // As saved before saves were stamped, before cards were written by name, and before
+        // the timeline of the global parameters was kept, with a field that was since removed.
+        let mut state = saved["game"].take();
+        let predators = serde_json::to_value(&game.players[&1.into()].played_cards[0]).unwrap();
+        state["players"]["1"]["played_cards"][0] = predators;
+        state.as_object_mut().unwrap().remove("parameter_timeline");
+        state["ocean_count"] = 1.into();
+        let text = state.to_string();
+        assert!(load_game(&text).is_err());
+
+        let (loaded, migrations) = load_game_leniently(&text).unwrap();
+        assert_eq!(game, loaded);
+        assert_eq!(Migration::WrappedGameState, migrations[0]);
+        let filled: Vec<&str> = migrations
+            .iter()
+            .filter_map(|migration| match migration {
+                Migration::FilledStampField { field, .. } => Some(field.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["deck_hash", "engine_version", "rules_revision"], filled);
+        assert!(migrations.contains(&Migration::ReplacedCardBody {
+            path: "game.players.1.played_cards.0".to_string(),
+            name: "Predators".to_string(),
+        }));
+        assert!(migrations.contains(&Migration::FilledDefault {
+            path: "game.parameter_timeline".to_string(),
+        }));
+        assert!(migrations.contains(&Migration::DroppedField {
+            path: "game.ocean_count".to_string(),
+        }));
+        assert_eq!(7, migrations.len());
+
+        // Saves from other rules are warned about rather than refused.
+        let text = save_game(&game).replace(
+            &format!("\"rules_revision\": {}", RULES_REVISION),
+            "\"rules_revision\": 0",
+        );
+        let (_, migrations) = load_game_leniently(&text).unwrap();
+        assert_eq!(
+            vec![Migration::StampMismatch(StampMismatch::RulesRevision {
+                stamped: 0,
+                current: RULES_REVISION,
+            })],
+            migrations
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Delayed effects: impacts that are set up now, but only resolve in a later phase,
+//! e.g. at the end of the generation, or at the start of the next one.
+//!
+//! A scheduled effect is a list of operations, which resolve all together or not at all:
+//! if any of them is no longer legal when the effect comes due, the effect is skipped.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    event::GameEvent,
+    game::{GameOperation, GameState, IllegalOperation},
+};
+
+/// When in its generation a scheduled effect resolves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EffectTiming {
+    EndOfGeneration,   // after production, before Turmoil's government changes
+    StartOfGeneration, // once the generation has begun, before its research phase
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledEffect {
+    pub generation: usize,
+    pub timing: EffectTiming,
+    pub operations: Vec<GameOperation>,
+}
+
+impl ScheduledEffect {
+    /// The effect resolves at the end of the current generation.
+    pub fn end_of_generation(game: &GameState, operations: Vec<GameOperation>) -> Self {
+        Self {
+            generation: game.generation,
+            timing: EffectTiming::EndOfGeneration,
+            operations,
+        }
+    }
+
+    /// The effect resolves once the next generation begins.
+    pub fn start_of_next_generation(game: &GameState, operations: Vec<GameOperation>) -> Self {
+        Self {
+            generation: game.generation + 1,
+            timing: EffectTiming::StartOfGeneration,
+            operations,
+        }
+    }
+
+    // Whether the effect's time has already come and gone.
+    fn is_due_before(&self, game: &GameState) -> bool {
+        match self.timing {
+            EffectTiming::EndOfGeneration => self.generation < game.generation,
+            EffectTiming::StartOfGeneration => self.generation <= game.generation,
+        }
+    }
+}
+
+impl GameState {
+    pub(crate) fn check_effect_scheduling(
+        &self,
+        effect: &ScheduledEffect,
+    ) -> Result<(), IllegalOperation> {
+        if effect.is_due_before(self) {
+            return Err(IllegalOperation::EffectScheduledInThePast);
+        }
+        Ok(())
+    }
+
+    /// Resolve the effects scheduled for the given point of the current generation,
+    /// in the order they were scheduled.
+    pub(crate) fn resolve_scheduled_effects(&mut self, timing: EffectTiming) -> Vec<GameEvent> {
+        let generation = self.generation;
+        let (due, pending): (Vec<ScheduledEffect>, Vec<ScheduledEffect>) =
+            std::mem::take(&mut self.scheduled_effects)
+                .into_iter()
+                .partition(|effect| effect.generation == generation && effect.timing == timing);
+        self.scheduled_effects = pending;
+
+        let mut events = vec![];
+        for effect in due {
+            let mut scratch_game = self.clone();
+            let legality = effect.operations.iter().try_for_each(|operation| {
+                scratch_game.check_operation(operation)?;
+                scratch_game.execute_operation(operation.clone());
+                Ok(())
+            });
+            match legality {
+                Ok(()) => {
+                    for operation in effect.operations {
+                        events.extend(self.execute_operation(operation));
+                    }
+                }
+                Err(error) => events.push(GameEvent::ScheduledEffectSkipped(error)),
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use super::*;
+    use crate::{
+        game::{PlayerId, PlayerStateBuilder},
+        resource::Resource,
+        test_utils::make_game,
+    };
+
+    #[test]
+    fn scheduled_effects_wait_for_their_phase() {
+        let players = vec![PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 0, 0, 0)
+            .build()];
+        let mut game = make_game(players);
+        let p1 = PlayerId::from(1);
+
+        let gain_plants = GameOperation::ChangeResources(p1, btreemap! { Resource::Plants => 2 });
+        let end_of_generation = ScheduledEffect::end_of_generation(&game, vec![gain_plants]);
+        let gain_heat_production =
+            GameOperation::ChangeProduction(p1, btreemap! { Resource::Heat => 1 });
+        let next_generation =
+            ScheduledEffect::start_of_next_generation(&game, vec![gain_heat_production]);
+        for effect in [end_of_generation, next_generation] {
+            game.check_operation(&GameOperation::ScheduleEffect(effect.clone()))
+                .unwrap();
+            game.execute_operation(GameOperation::ScheduleEffect(effect));
+        }
+        assert_eq!(0, game.players[&p1].resources[Resource::Plants]);
+
+        // The plants come after production, so the heat production doesn't pay out yet.
+        game.advance_generation();
+        assert_eq!(2, game.players[&p1].resources[Resource::Plants]);
+        assert_eq!(1, game.players[&p1].production[Resource::Heat]);
+        assert_eq!(0, game.players[&p1].resources[Resource::Heat]);
+        assert!(game.scheduled_effects.is_empty());
+    }
+
+    #[test]
+    fn effects_may_not_be_scheduled_in_the_past() {
+        let mut game = make_game(vec![PlayerStateBuilder::new(1).build()]);
+        game.generation = 3;
+        let mut effect = ScheduledEffect::start_of_next_generation(&game, vec![]);
+        effect.generation = 3;
+        assert_eq!(
+            Err(IllegalOperation::EffectScheduledInThePast),
+            game.check_operation(&GameOperation::ScheduleEffect(effect))
+        );
+    }
+
+    #[test]
+    fn effects_that_became_illegal_are_skipped_as_a_whole() {
+        let players = vec![PlayerStateBuilder::new(1)
+            .with_resources(5, 0, 0, 0, 0, 0)
+            .build()];
+        let mut game = make_game(players);
+        let p1 = PlayerId::from(1);
+
+        let pay = GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -8 });
+        let gain = GameOperation::ChangeResources(p1, btreemap! { Resource::Plants => 3 });
+        game.scheduled_effects
+            .push(ScheduledEffect::end_of_generation(&game, vec![gain, pay]));
+
+        let events = game.resolve_scheduled_effects(EffectTiming::EndOfGeneration);
+        assert_eq!(
+            vec![GameEvent::ScheduledEffectSkipped(
+                IllegalOperation::InsufficientResource(Resource::Megacredits)
+            )],
+            events
+        );
+        assert_eq!(0, game.players[&p1].resources[Resource::Plants]);
+        assert!(game.scheduled_effects.is_empty());
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::BTreeMap, thread};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::Award,
+    board::{MarsBoard, TileStatus},
+    card::CityKind,
+    game::{GameState, PlayerId, PlayerState},
+};
+
+/// A player's victory points, by where they come from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreBreakdown {
+    pub terraform_rating: isize,
+    pub cards: isize,
+    pub greeneries: isize,
+
+    // 1VP per greenery adjacent to each owned city
+    pub city_adjacency: isize,
+
+    // 1VP per ocean adjacent to each owned capital
+    pub capital_adjacency: isize,
+
+    // 5VP for first place and 2VP for second in each funded award; depends on the other
+    // players, so only included when scoring all players at once
+    #[serde(default)]
+    pub awards: isize,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> isize {
+        self.terraform_rating
+            + self.cards
+            + self.greeneries
+            + self.city_adjacency
+            + self.capital_adjacency
+            + self.awards
+    }
+}
+
+/// The points every player gets from tiles, computed in one pass over the board.
+#[derive(Debug, Default)]
+pub(crate) struct TilePoints {
+    greeneries: BTreeMap<PlayerId, isize>,
+    city_adjacency: BTreeMap<PlayerId, isize>,
+    capital_adjacency: BTreeMap<PlayerId, isize>,
+}
+
+impl TilePoints {
+    pub(crate) fn compute(board: &MarsBoard) -> Self {
+        let mut tile_points = Self::default();
+
+        for player_id in board.greeneries.values() {
+            *tile_points.greeneries.entry(*player_id).or_default() += 1;
+        }
+
+        for (location, (city_kind, player_id)) in &board.cities {
+            let mut adjacent_greeneries = 0;
+            let mut adjacent_oceans = 0;
+            for status in board.get_neighbor_tile_status(location) {
+                match status {
+                    TileStatus::Greenery(..) => adjacent_greeneries += 1,
+                    TileStatus::Ocean(_) => adjacent_oceans += 1,
+                    _ => {}
+                }
+            }
+
+            *tile_points.city_adjacency.entry(*player_id).or_default() += adjacent_greeneries;
+            if *city_kind == CityKind::Capital {
+                *tile_points.capital_adjacency.entry(*player_id).or_default() += adjacent_oceans;
+            }
+        }
+
+        tile_points
+    }
+
+    pub(crate) fn breakdown(&self, player: &PlayerState, card_points: isize) -> ScoreBreakdown {
+        let points = |map: &BTreeMap<PlayerId, isize>| {
+            map.get(&player.player_id).copied().unwrap_or_default()
+        };
+
+        ScoreBreakdown {
+            terraform_rating: player.terraform_rating as isize,
+            cards: card_points,
+            greeneries: points(&self.greeneries),
+            city_adjacency: points(&self.city_adjacency),
+            capital_adjacency: points(&self.capital_adjacency),
+            awards: 0,
+        }
+    }
+}
+
+/// Score every player at once, in player id order.
+///
+/// The board is only scanned once for all players, rather than once per player
+/// as separate `get_total_victory_points()` calls would.
+pub fn score_all_players(game: &GameState) -> Vec<(PlayerId, ScoreBreakdown)> {
+    score_all_players_with_threads(game, 1)
+}
+
+/// Like `score_all_players()`, with the per-player card scoring spread over the given
+/// number of threads. The results do not depend on the number of threads.
+pub fn score_all_players_with_threads(
+    game: &GameState,
+    threads: usize,
+) -> Vec<(PlayerId, ScoreBreakdown)> {
+    assert!(threads >= 1);
+
+    let tile_points = TilePoints::compute(&game.board);
+    let players: Vec<&PlayerState> = game.players.values().collect();
+
+    let card_points: Vec<isize> = if threads == 1 || players.len() <= 1 {
+        players
+            .iter()
+            .map(|player| player.card_victory_points(&game.board))
+            .collect()
+    } else {
+        let chunk_size = players.len().div_ceil(threads);
+        thread::scope(|scope| {
+            let handles: Vec<_> = players
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|player| player.card_victory_points(&game.board))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    };
+
+    let funded_awards: Vec<Award> = game.funded_awards.iter().map(|(award, _)| *award).collect();
+    let award_points = game.award_standings.points(&funded_awards);
+
+    players
+        .into_iter()
+        .zip(card_points)
+        .map(|(player, card_points)| {
+            let mut breakdown = tile_points.breakdown(player, card_points);
+            breakdown.awards = award_points
+                .get(&player.player_id)
+                .copied()
+                .unwrap_or_default();
+            (player.player_id, breakdown)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::PlayerId,
+        position::import_position,
+        scoring::{score_all_players, score_all_players_with_threads, ScoreBreakdown},
+    };
+
+    #[test]
+    fn all_players_are_scored_like_individual_players() {
+        let game = import_position(
+            "ocean 5,-5\n\
+             greenery 4,-3 p1\n\
+             greenery 5,-3 p2\n\
+             city 5,-4 p1 Capital\n\
+             player p1\n\
+             tr 25\n\
+             played Search For Life\n\
+             card-resource Search For Life 1\n\
+             player p2\n\
+             player p3",
+        )
+        .unwrap();
+
+        let scores = score_all_players(&game);
+        assert_eq!(
+            (
+                PlayerId::from(1),
+                ScoreBreakdown {
+                    terraform_rating: 25,
+                    cards: 3,
+                    greeneries: 1,
+                    city_adjacency: 2,
+                    capital_adjacency: 1,
+                    awards: 0,
+                }
+            ),
+            scores[0]
+        );
+        for (player_id, breakdown) in &scores {
+            let player = &game.players[player_id];
+            assert_eq!(*breakdown, player.score_breakdown(&game.board));
+            assert_eq!(
+                breakdown.total(),
+                player.get_total_victory_points(&game.board)
+            );
+        }
+
+        assert_eq!(scores, score_all_players_with_threads(&game, 2));
+    }
+}
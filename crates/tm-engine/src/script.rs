@@ -0,0 +1,597 @@
+use std::{collections::BTreeMap, fmt};
+
+use maplit::btreemap;
+
+use crate::{
+    board::{Coordinates, TileLocation},
+    card::{Card, ImmediateImpact},
+    game::{
+        GameOperation, GamePhase, GameState, IllegalOperation, PlayAttempt, PlayerId, PlayerState,
+    },
+    notation::{parse_actions, Action, NotatedAction},
+    payment::{PaymentError, PaymentPlan},
+    position::{import_position, parse_number, parse_player_id, split_card_names},
+    resource::Resource,
+};
+
+/// Rules tests written as scripts: a starting position, the actions taken from it,
+/// and what the game should look like afterwards. Contributors can cover a card or a rule
+/// by adding a script to the `scripts` directory, without writing any Rust.
+///
+/// The `position` section uses the position notation of `import_position()`, and the
+/// `actions` section the action notation of `parse_actions()`. An action prefixed with `!`
+/// must be rejected, and leave the game unchanged. The `expect` section takes a subset of
+/// the position directives, and checks them against the game after the last action.
+///
+/// ```text
+/// # Lake Marineris places two oceans, each raising the terraform rating.
+/// position
+///   temperature 0
+///   player p1
+///   resources 20 0 0 0 0 0
+///   hand Lake Marineris
+/// actions
+///   g1: p1 play "Lake Marineris" pay 18M place 5,-1 place 8,-4
+///   ! g1: p1 project aquifer place 5,-1  # the space is taken
+/// expect
+///   ocean 5,-1
+///   player p1
+///   tr 22
+///   played Lake Marineris
+/// ```
+pub fn run_script(text: &str) -> Result<GameState, ScriptError> {
+    let sections = split_sections(text)?;
+
+    let mut game = import_position(&sections.position).map_err(|error| ScriptError {
+        line: error.line,
+        message: error.message,
+    })?;
+
+    let actions = parse_actions(&sections.actions).map_err(|error| ScriptError {
+        line: error.line,
+        message: error.message,
+    })?;
+    for (action, (line, rejected)) in actions.iter().zip(sections.action_lines.iter().copied()) {
+        let outcome = apply_action(&mut game, action);
+        match (outcome, rejected) {
+            (Ok(()), false) | (Err(_), true) => {}
+            (Ok(()), true) => {
+                return Err(ScriptError {
+                    line,
+                    message: format!("expected \"{}\" to be rejected", action),
+                })
+            }
+            (Err(message), false) => return Err(ScriptError { line, message }),
+        }
+    }
+
+    for (line, expectation) in &sections.expectations {
+        let line = *line;
+        check_expectation(&game, expectation, sections.expected_player(line))
+            .map_err(|message| ScriptError { line, message })?;
+    }
+
+    Ok(game)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize, // 1-based
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Carry out the action for its player, moving on to its generation first if need be.
+/// If the action is illegal, the game is left unchanged.
+pub fn apply_action(game: &mut GameState, action: &NotatedAction) -> Result<(), String> {
+    if action.generation < game.generation {
+        return Err(format!(
+            "\"{}\" is for generation {}, but the game is already in generation {}",
+            action, action.generation, game.generation
+        ));
+    }
+
+    // Work on a copy, so that an action rejected halfway through changes nothing.
+    let mut next = game.clone();
+    while next.generation < action.generation || next.phase != GamePhase::Action {
+        if next.is_over() {
+            return Err(format!("\"{}\" is after the end of the game", action));
+        }
+        next.advance_phase();
+    }
+
+    let player_id = action.player_id;
+    match &action.action {
+        Action::PlayCard {
+            card,
+            payment,
+            placements,
+        } => play_card(&mut next, player_id, card, payment, placements)?,
+        Action::StandardProject(project) => {
+            next.play_standard_project(player_id, project)
+                .map_err(describe)?;
+        }
+        Action::Pass => {
+            if !next.players.contains_key(&player_id) {
+                return Err(describe(IllegalOperation::UnknownPlayer(player_id)));
+            }
+            next.passed_players.insert(player_id);
+        }
+        Action::FundAward(award) => {
+            execute(&mut next, vec![GameOperation::FundAward(player_id, *award)])?;
+        }
+        Action::ConvertPlants(coordinates) => {
+            next.convert_plants(player_id, *coordinates)
+                .map_err(describe)?;
+        }
+        Action::ConvertHeat(ocean) => {
+            next.convert_heat(player_id, *ocean).map_err(describe)?;
+        }
+        Action::UseCardAction(_) | Action::ClaimMilestone => {
+            return Err(describe(IllegalOperation::NotImplemented));
+        }
+    }
+
+    *game = next;
+    Ok(())
+}
+
+fn describe(error: IllegalOperation) -> String {
+    format!("illegal operation: {:?}", error)
+}
+
+fn execute(game: &mut GameState, operations: Vec<GameOperation>) -> Result<(), String> {
+    for operation in operations {
+        game.check_operation(&operation).map_err(describe)?;
+        game.execute_operation(operation);
+    }
+    Ok(())
+}
+
+// Card requirements are not checked yet: scripts start from positions where they are met.
+fn play_card(
+    game: &mut GameState,
+    player_id: PlayerId,
+    card: &Card,
+    payment: &BTreeMap<Resource, usize>,
+    placements: &[Coordinates],
+) -> Result<(), String> {
+    let player = game
+        .players
+        .get(&player_id)
+        .ok_or_else(|| describe(IllegalOperation::UnknownPlayer(player_id)))?;
+    check_payment(player, card, payment)?;
+
+    let mut operations = vec![];
+    if !payment.is_empty() {
+        operations.push(GameOperation::ChangeResources(
+            player_id,
+            payment
+                .iter()
+                .map(|(resource, amount)| (*resource, -(*amount as isize)))
+                .collect(),
+        ));
+    }
+    operations.push(GameOperation::PutCardIntoPlay(player_id, card.clone()));
+    if !card.own_production.is_empty() {
+        operations.push(GameOperation::ChangeProduction(
+            player_id,
+            card.own_production.clone(),
+        ));
+    }
+    execute(game, operations)?;
+
+    // Each impact sees the game as the previous ones left it, e.g. the oceans already placed.
+    let mut placements = placements.iter();
+    for impact in &card.immediate_impacts {
+        let player = &game.players[&player_id];
+        let operations = match impact {
+            ImmediateImpact::RaiseTemperature => match game.board.increase_temperature(player) {
+                PlayAttempt::Unplayable => vec![],
+                PlayAttempt::Playable(operations) => operations,
+                PlayAttempt::PartiallyPlayable(operations, _)
+                    if !game.board.has_legal_ocean_placement(player) =>
+                {
+                    operations
+                }
+                PlayAttempt::PartiallyPlayable(mut operations, _) => {
+                    let ocean = next_placement(&mut placements, "the ocean at 0 C")?;
+                    operations.extend(
+                        game.ocean_placement_operations(player, ocean)
+                            .map_err(describe)?,
+                    );
+                    operations
+                }
+            },
+            ImmediateImpact::RaiseOxygen => match game.board.can_increase_oxygen(player) {
+                PlayAttempt::Unplayable => vec![],
+                PlayAttempt::Playable(operations)
+                | PlayAttempt::PartiallyPlayable(operations, _) => operations,
+            },
+            ImmediateImpact::RaiseTerraformRating => {
+                vec![GameOperation::RaiseTerraformRating(player_id, 1)]
+            }
+            // Tiles with nowhere to go are skipped, and the rest of the card still happens.
+            ImmediateImpact::PlaceOcean(_) if !game.board.has_legal_ocean_placement(player) => {
+                vec![]
+            }
+            ImmediateImpact::PlaceGreenery(restrictions)
+            | ImmediateImpact::PlaceCity(_, restrictions)
+                if !game.board.has_legal_placement(player, restrictions) =>
+            {
+                vec![]
+            }
+            ImmediateImpact::PlaceOcean(_) => {
+                let ocean = next_placement(&mut placements, "the ocean")?;
+                game.ocean_placement_operations(player, ocean)
+                    .map_err(describe)?
+            }
+            ImmediateImpact::PlaceGreenery(restrictions) => {
+                let greenery = next_placement(&mut placements, "the greenery")?;
+                game.greenery_placement_operations(player, greenery, restrictions)
+                    .map_err(describe)?
+            }
+            ImmediateImpact::PlaceCity(city_kind, restrictions) => {
+                let city = next_placement(&mut placements, "the city")?;
+                game.city_placement_operations(player, *city_kind, city, restrictions)
+                    .map_err(describe)?
+            }
+            ImmediateImpact::DrawCard(count) => vec![GameOperation::DrawCards(player_id, *count)],
+            ImmediateImpact::GainResource(resource, amount) => {
+                vec![GameOperation::ChangeResources(
+                    player_id,
+                    btreemap! { *resource => *amount as isize },
+                )]
+            }
+            ImmediateImpact::ChangeProduction(resource, change) => {
+                vec![GameOperation::ChangeProduction(
+                    player_id,
+                    btreemap! { *resource => *change },
+                )]
+            }
+            ImmediateImpact::AddResourceToSameCard(card_resource, amount) => {
+                vec![GameOperation::ChangeCardResource(
+                    player_id,
+                    card.clone(),
+                    *card_resource,
+                    *amount as isize,
+                )]
+            }
+            // Choices that the notation has no way to make yet, e.g. whose plants to destroy.
+            _ => {
+                return Err(format!(
+                    "scripts cannot resolve {:?} from \"{}\" yet",
+                    impact, card.name
+                ))
+            }
+        };
+        execute(game, operations)?;
+    }
+
+    if placements.next().is_some() {
+        return Err(format!("\"{}\" does not place that many tiles", card.name));
+    }
+
+    // The card's effects only take hold once it has been played, so they miss its own impacts.
+    let effects = card
+        .effects
+        .iter()
+        .map(|effect| GameOperation::AddEffect(player_id, effect.clone()))
+        .collect();
+    execute(game, effects)
+}
+
+fn next_placement<'a>(
+    placements: &mut impl Iterator<Item = &'a Coordinates>,
+    tile: &str,
+) -> Result<Coordinates, String> {
+    placements
+        .next()
+        .copied()
+        .ok_or_else(|| format!("expected a \"place\" for {}", tile))
+}
+
+// Steel only pays for building cards, titanium only for space cards, and heat only with
+// Helion's effect; nothing is paid beyond the cost after discounts.
+fn check_payment(
+    player: &PlayerState,
+    card: &Card,
+    payment: &BTreeMap<Resource, usize>,
+) -> Result<(), String> {
+    let mut plan = PaymentPlan::default();
+    for (resource, amount) in payment {
+        let spent = match resource {
+            Resource::Megacredits => &mut plan.megacredits,
+            Resource::Steel => &mut plan.steel,
+            Resource::Titanium => &mut plan.titanium,
+            Resource::Heat => &mut plan.heat,
+            _ => {
+                return Err(format!(
+                    "{:?} cannot be used to pay for \"{}\"",
+                    resource, card.name
+                ))
+            }
+        };
+        *spent = *amount;
+    }
+
+    plan.check(player, player.card_cost(card)).map_err(|error| match error {
+        PaymentError::NotPaidWithMegacredits(_) => {
+            format!("\"{}\" is not paid for with megacredits", card.name)
+        }
+        PaymentError::ResourceNotAllowed(resource) => format!(
+            "{:?} cannot be used to pay for \"{}\"",
+            resource, card.name
+        ),
+        PaymentError::Underpaid { paid, cost } | PaymentError::Overpaid { paid, cost } => format!(
+            "paid {} M$ for \"{}\", which costs {} M$",
+            paid, card.name, cost
+        ),
+        PaymentError::InsufficientResource(_) => format!("{} for \"{}\"", error, card.name),
+    })
+}
+
+struct Sections {
+    // Each section keeps the script's line numbering, with the other sections' lines blanked.
+    position: String,
+    actions: String,
+    action_lines: Vec<(usize, bool)>, // (line, whether the action must be rejected)
+    expectations: Vec<(usize, String)>,
+}
+
+impl Sections {
+    // The player that "player" directives before the line pointed expectations at.
+    fn expected_player(&self, line: usize) -> Option<PlayerId> {
+        self.expectations
+            .iter()
+            .take_while(|(expectation_line, _)| *expectation_line <= line)
+            .filter_map(|(_, expectation)| expectation.strip_prefix("player "))
+            .filter_map(|player| parse_player_id(player.trim()).ok())
+            .last()
+    }
+}
+
+fn split_sections(text: &str) -> Result<Sections, ScriptError> {
+    let mut sections = Sections {
+        position: String::new(),
+        actions: String::new(),
+        action_lines: vec![],
+        expectations: vec![],
+    };
+
+    let mut current_section = None;
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = raw_line.trim();
+        let mut position_line = "";
+        let mut action_line = "";
+
+        match line {
+            "position" | "actions" | "expect" => current_section = Some(line),
+            _ => {
+                let content = line.split('#').next().unwrap_or_default().trim();
+                match current_section {
+                    _ if content.is_empty() => {}
+                    Some("position") => position_line = line,
+                    Some("actions") => {
+                        let (rejected, action) = match line.strip_prefix('!') {
+                            Some(action) => (true, action.trim()),
+                            None => (false, line),
+                        };
+                        action_line = action;
+                        sections.action_lines.push((index + 1, rejected));
+                    }
+                    Some(_) => sections.expectations.push((index + 1, content.to_string())),
+                    None => {
+                        return Err(ScriptError {
+                            line: index + 1,
+                            message: "expected a \"position\", \"actions\", or \"expect\" section"
+                                .into(),
+                        })
+                    }
+                }
+            }
+        }
+
+        sections.position.push_str(position_line);
+        sections.position.push('\n');
+        sections.actions.push_str(action_line);
+        sections.actions.push('\n');
+    }
+
+    Ok(sections)
+}
+
+fn check_expectation(
+    game: &GameState,
+    expectation: &str,
+    player_id: Option<PlayerId>,
+) -> Result<(), String> {
+    let (directive, rest) = match expectation.split_once(char::is_whitespace) {
+        Some((directive, rest)) => (directive, rest.trim()),
+        None => (expectation, ""),
+    };
+    let player = || -> Result<&PlayerState, String> {
+        let player_id = player_id
+            .ok_or_else(|| String::from("player details must follow a \"player\" line"))?;
+        game.players
+            .get(&player_id)
+            .ok_or_else(|| format!("there is no player p{}", usize::from(player_id)))
+    };
+    let compare = |what: &str, expected: String, actual: String| {
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(format!("expected {} {}, found {}", what, expected, actual))
+        }
+    };
+    let card_names = |cards: &[Card]| {
+        let mut names: Vec<&str> = cards.iter().map(|card| card.name.as_str()).collect();
+        names.sort_unstable();
+        names.join("; ")
+    };
+
+    match directive {
+        "player" => {
+            player()?;
+            Ok(())
+        }
+        "generation" => compare(
+            directive,
+            parse_number::<usize>(rest)?.to_string(),
+            game.generation.to_string(),
+        ),
+        "oxygen" => compare(
+            directive,
+            parse_number::<usize>(rest)?.to_string(),
+            game.board.oxygen.to_string(),
+        ),
+        "temperature" => compare(
+            directive,
+            parse_number::<isize>(rest)?.to_string(),
+            game.board.temperature.to_string(),
+        ),
+        "ocean" => {
+            let coordinates = parse_coordinates(rest)?;
+            if game.board.oceans.contains(&coordinates) {
+                Ok(())
+            } else {
+                Err(format!("expected an ocean at {}", coordinates))
+            }
+        }
+        "greenery" | "city" => {
+            let (coordinates, owner) = rest
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("expected the {}'s location and owner", directive))?;
+            let coordinates = parse_coordinates(coordinates)?;
+            let owner = parse_player_id(owner.trim())?;
+            let actual = if directive == "greenery" {
+                game.board.greeneries.get(&coordinates).copied()
+            } else {
+                game.board
+                    .cities
+                    .get(&TileLocation::OnMars(coordinates))
+                    .map(|(_, owner)| *owner)
+            };
+            if actual == Some(owner) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "expected a {} of p{} at {}",
+                    directive,
+                    usize::from(owner),
+                    coordinates
+                ))
+            }
+        }
+        "tr" => compare(
+            directive,
+            parse_number::<usize>(rest)?.to_string(),
+            player()?.terraform_rating.to_string(),
+        ),
+        "resources" | "production" => {
+            let expected = rest
+                .split_whitespace()
+                .map(|amount| parse_number::<isize>(amount).map(|amount| amount.to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let player = player()?;
+            let actual: Vec<String> = Resource::ALL
+                .iter()
+                .map(|resource| match directive {
+                    "resources" => player.resources[resource].to_string(),
+                    _ => player.production[resource].to_string(),
+                })
+                .collect();
+            compare(directive, expected.join(" "), actual.join(" "))
+        }
+        "hand" | "played" => {
+            let mut expected: Vec<&str> = split_card_names(rest).collect();
+            expected.sort_unstable();
+            let player = player()?;
+            let actual = match directive {
+                "hand" => card_names(&player.cards_in_hand),
+                _ => card_names(&player.played_cards),
+            };
+            compare(directive, expected.join("; "), actual)
+        }
+        "card-resource" => {
+            let (name, amount) = rest
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| String::from("expected a card name followed by an amount"))?;
+            let amount: usize = parse_number(amount)?;
+            let actual: usize = player()?
+                .card_resources
+                .iter()
+                .filter(|((card, _), _)| card.name == name.trim())
+                .map(|(_, amount)| *amount)
+                .sum();
+            compare(
+                &format!("{} on {}", directive, name.trim()),
+                amount.to_string(),
+                actual.to_string(),
+            )
+        }
+        _ => Err(format!("unknown expectation \"{}\"", directive)),
+    }
+}
+
+fn parse_coordinates(text: &str) -> Result<Coordinates, String> {
+    let (x, y) = text.split_once(',').ok_or_else(|| {
+        format!(
+            "\"{}\" is not a location on Mars, expected e.g. \"3,-1\"",
+            text
+        )
+    })?;
+    Ok(Coordinates::new(parse_number(x)?, parse_number(y)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use crate::script::run_script;
+
+    #[test]
+    fn every_script_passes() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("scripts");
+        let mut paths: Vec<_> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension() == Some("tm".as_ref()))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty());
+
+        for path in paths {
+            let text = fs::read_to_string(&path).unwrap();
+            if let Err(error) = run_script(&text) {
+                panic!("{}: {}", path.display(), error);
+            }
+        }
+    }
+
+    #[test]
+    fn failed_expectations_point_at_their_line() {
+        let script = "
+            position
+              player p1
+              resources 20 0 0 0 0 0
+            actions
+              g1: p1 project power-plant
+            expect
+              player p1
+              resources 9 0 0 0 0 0
+              production 0 0 0 0 2 0
+        ";
+        let error = run_script(script).unwrap_err();
+        assert_eq!(10, error.line);
+        assert_eq!(
+            "expected production 0 0 0 0 2 0, found 0 0 0 0 1 0",
+            error.message
+        );
+    }
+}
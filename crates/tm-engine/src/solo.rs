@@ -0,0 +1,269 @@
+//! The solo game: a single player, starting with a lower terraform rating, who must have
+//! terraformed Mars by the end of the last generation.
+//!
+//! The game ends after that generation's production phase even if Mars is not terraformed,
+//! though the final greenery conversions still happen, and may complete the oxygen.
+//! The player wins if every global parameter is at its maximum once the game is over.
+//!
+//! At setup, neutral cities are placed, each with a greenery next to it. They belong to
+//! `PlayerId::NEUTRAL`, so they score nothing themselves, but count for the player's
+//! cities next to them like any other tile. Where each tile goes is decided by the cost
+//! of a revealed card: the first city is that many spaces along the land spaces where
+//! a city may go, in reading order from the top left of Mars, the second city from the
+//! bottom right instead, and each greenery that many spaces clockwise around its city.
+//! Counting starts from zero and wraps around. The neutral greeneries raise no oxygen.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::{Coordinates, TileLocation, TileStatus},
+    card::CityKind,
+    event::GameEvent,
+    game::{GameOperation, GameState, PlayerId, DEFAULT_SOLO_STARTING_TERRAFORM_RATING},
+    options::GameOptionsError,
+};
+
+pub const SOLO_GENERATIONS: usize = 14;
+pub const NEUTRAL_CITIES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SoloResult {
+    Won,
+    Lost,
+}
+
+impl GameState {
+    pub fn is_solo(&self) -> bool {
+        self.options.solo
+    }
+
+    /// Give the solo player their starting terraform rating, and place the neutral tiles.
+    /// Part of setting up the game, so it must be done exactly once, before the first
+    /// research phase.
+    pub fn apply_solo_setup(&mut self) -> Result<Vec<GameEvent>, GameOptionsError> {
+        if !self.is_solo() {
+            return Ok(vec![]);
+        }
+        if self.players.len() != 1 {
+            return Err(GameOptionsError::SoloNeedsOnePlayer(self.players.len()));
+        }
+
+        for player in self.players.values_mut() {
+            player.terraform_rating = DEFAULT_SOLO_STARTING_TERRAFORM_RATING;
+        }
+        Ok(self.place_neutral_tiles())
+    }
+
+    fn place_neutral_tiles(&mut self) -> Vec<GameEvent> {
+        let mut events = vec![];
+        for city_index in 0..NEUTRAL_CITIES {
+            let mut city_spaces = self.empty_land(|coordinates| {
+                !self
+                    .board
+                    .get_neighbor_tile_status(&TileLocation::OnMars(coordinates))
+                    .any(|status| matches!(status, TileStatus::City(..)))
+            });
+            // Rows go down as z grows, and coordinates within a row compare from left to right.
+            city_spaces.sort_by_key(|coordinates| (coordinates.get_z(), *coordinates));
+            if city_index % 2 == 1 {
+                city_spaces.reverse();
+            }
+
+            let count = self.reveal_card_cost(&mut events);
+            let city = match city_spaces.get(count % city_spaces.len().max(1)) {
+                Some(city) => *city,
+                None => break,
+            };
+            self.board.cities.insert(
+                TileLocation::OnMars(city),
+                (CityKind::RegularCity, PlayerId::NEUTRAL),
+            );
+            events.push(GameEvent::NeutralCityPlaced(city));
+
+            // Neighbors are listed clockwise.
+            let mut around_city: Vec<Coordinates> =
+                self.board.neighbors(&TileLocation::OnMars(city)).collect();
+            let greenery_spaces = self.empty_land(|coordinates| around_city.contains(&coordinates));
+            around_city.retain(|coordinates| greenery_spaces.contains(coordinates));
+
+            let count = self.reveal_card_cost(&mut events);
+            if let Some(greenery) = around_city.get(count % around_city.len().max(1)) {
+                self.board.greeneries.insert(*greenery, PlayerId::NEUTRAL);
+                events.push(GameEvent::NeutralGreeneryPlaced(*greenery));
+            }
+        }
+        events
+    }
+
+    // The empty land spaces on Mars that the filter accepts, in no particular order.
+    fn empty_land(&self, filter: impl Fn(Coordinates) -> bool) -> Vec<Coordinates> {
+        self.board
+            .spaces
+            .values()
+            .filter(|space| space.is_land() && !space.is_reserved_for_ocean())
+            .filter_map(|space| match space.location {
+                TileLocation::OnMars(coordinates) => Some(coordinates),
+                TileLocation::OffMars(_) => None,
+            })
+            .filter(|coordinates| {
+                matches!(
+                    self.board
+                        .get_tile_status(&TileLocation::OnMars(*coordinates)),
+                    TileStatus::Empty(_)
+                )
+            })
+            .filter(|coordinates| filter(*coordinates))
+            .collect()
+    }
+
+    // Without any cards left to reveal, the count is zero.
+    fn reveal_card_cost(&mut self, events: &mut Vec<GameEvent>) -> usize {
+        if self.draw_deck.is_empty() && self.discard_pile.is_empty() {
+            return 0;
+        }
+        events.extend(self.execute_operation(GameOperation::RevealCard));
+        self.discard_pile
+            .last()
+            .map_or(0, |card| card.cost.amount())
+    }
+
+    /// Whether this generation is the last one, because the solo game's time is up.
+    pub fn is_last_solo_generation(&self) -> bool {
+        self.is_solo() && self.generation >= SOLO_GENERATIONS
+    }
+
+    /// The outcome of a solo game, once it is over.
+    pub fn solo_result(&self) -> Option<SoloResult> {
+        if !self.is_solo() || !self.is_over() {
+            return None;
+        }
+
+        if self.board.is_terraformed() {
+            Some(SoloResult::Won)
+        } else {
+            Some(SoloResult::Lost)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        board::MarsBoard,
+        game::{GamePhase, PlayerId},
+        position::import_position,
+        test_utils::board_with_parameters,
+    };
+
+    fn play_out_generation(game: &mut GameState) {
+        game.phase = GamePhase::Production;
+        game.advance_phase();
+    }
+
+    #[test]
+    fn the_solo_player_starts_with_a_lower_terraform_rating() {
+        let mut game = import_position("solo\nplayer p1").unwrap();
+        let events = game.apply_solo_setup().unwrap();
+        assert!(!events.is_empty());
+        assert_eq!(
+            DEFAULT_SOLO_STARTING_TERRAFORM_RATING,
+            game.players[&PlayerId::from(1)].terraform_rating
+        );
+
+        let mut game = import_position("player p1\nplayer p2").unwrap();
+        game.options.solo = true;
+        assert_eq!(
+            Err(GameOptionsError::SoloNeedsOnePlayer(2)),
+            game.apply_solo_setup()
+        );
+        assert!(import_position("solo\nplayer p1\nplayer p2").is_err());
+    }
+
+    #[test]
+    fn the_solo_game_is_lost_if_mars_is_not_terraformed_in_time() {
+        let mut game = import_position("solo\ngeneration 13\nplayer p1").unwrap();
+        play_out_generation(&mut game);
+        assert_eq!((14, GamePhase::Research), (game.generation, game.phase));
+        assert_eq!(None, game.solo_result());
+
+        play_out_generation(&mut game);
+        assert_eq!(GamePhase::FinalGreeneries, game.phase);
+        game.advance_phase();
+        assert!(game.is_over());
+        assert_eq!(Some(SoloResult::Lost), game.solo_result());
+
+        // Without the solo rules, the game goes on.
+        let mut game = import_position("generation 14\nplayer p1").unwrap();
+        play_out_generation(&mut game);
+        assert_eq!((15, GamePhase::Research), (game.generation, game.phase));
+    }
+
+    #[test]
+    fn the_solo_game_is_won_by_terraforming_mars() {
+        let mut game = import_position("solo\ngeneration 12\nplayer p1").unwrap();
+        game.board = board_with_parameters(
+            MarsBoard::MAX_OXYGEN,
+            MarsBoard::MAX_TEMPERATURE,
+            MarsBoard::MAX_OCEANS,
+        );
+        play_out_generation(&mut game);
+        game.advance_phase();
+        assert_eq!((12, true), (game.generation, game.is_over()));
+        assert_eq!(Some(SoloResult::Won), game.solo_result());
+    }
+
+    #[test]
+    fn neutral_tiles_are_placed_at_setup_and_only_score_for_the_player_next_to_them() {
+        let mut game = import_position("solo\nseed 4\nplayer p1").unwrap();
+        let events = game.apply_solo_setup().unwrap();
+
+        let neutral_cities: Vec<Coordinates> = events
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::NeutralCityPlaced(coordinates) => Some(*coordinates),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(NEUTRAL_CITIES, neutral_cities.len());
+        assert_eq!(NEUTRAL_CITIES, game.board.greeneries.len());
+        assert_eq!(0, game.board.oxygen);
+        for (greenery, owner) in &game.board.greeneries {
+            assert!(owner.is_neutral());
+            let next_to_a_city = neutral_cities.iter().any(|city| {
+                game.board
+                    .neighbors(&TileLocation::OnMars(*city))
+                    .any(|neighbor| neighbor == *greenery)
+            });
+            assert!(next_to_a_city);
+        }
+
+        // A city of the player's next to a neutral greenery scores for it.
+        let p1 = PlayerId::from(1);
+        let (greenery, _) = game.board.greeneries.iter().next().unwrap();
+        let space = game
+            .board
+            .neighbors(&TileLocation::OnMars(*greenery))
+            .find(|neighbor| {
+                matches!(
+                    game.board.get_tile_status(&TileLocation::OnMars(*neighbor)),
+                    TileStatus::Empty(_)
+                )
+            })
+            .unwrap();
+        game.board
+            .cities
+            .insert(TileLocation::OnMars(space), (CityKind::RegularCity, p1));
+        let breakdown = game.players[&p1].score_breakdown(&game.board);
+        assert_eq!((0, 1), (breakdown.greeneries, breakdown.city_adjacency));
+        assert_eq!(
+            vec![(p1, breakdown)],
+            crate::scoring::score_all_players(&game)
+        );
+
+        assert!(
+            import_position("solo\ncity 4,-6 neutral\ngreenery 4,-5 neutral\nplayer p1").is_ok()
+        );
+        assert!(import_position("player p0").is_err());
+    }
+}
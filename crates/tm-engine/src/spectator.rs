@@ -0,0 +1,420 @@
+use std::{collections::BTreeMap, io::Write};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::TileStatus,
+    card::CityKind,
+    event::{GameEvent, GameObserver},
+    game::{GameState, PlayerId},
+    resource::Resource,
+    turmoil::Delegate,
+};
+
+// Assigned to players in order of their ids; they repeat if there are more players than symbols.
+const PLAYER_SYMBOLS: [char; 5] = ['●', '■', '▲', '◆', '★'];
+const PLAYER_ANSI_COLORS: [u8; 5] = [31, 32, 34, 33, 35]; // red, green, blue, yellow, magenta
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Verbosity {
+    Summary, // tiles, global parameters, terraform rating, and played cards
+    Normal,  // also resources, production, card resources, card actions, and hand sizes
+    Verbose, // also effects and deck bookkeeping
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextStreamOptions {
+    pub verbosity: Verbosity,
+    pub use_colors: bool, // ANSI escape codes, for terminals
+}
+
+impl Default for TextStreamOptions {
+    fn default() -> Self {
+        Self {
+            verbosity: Verbosity::Normal,
+            use_colors: false,
+        }
+    }
+}
+
+/// Renders the game's events as one line of text each, for spectators.
+///
+/// Only public information is written out: for cards that go into or out of a hand,
+/// only the number of cards is shown. Each generation starts with a separator line.
+/// Lines are flushed as they are written, so the output can be followed live.
+pub struct TextStreamObserver<W: Write> {
+    writer: W,
+    options: TextStreamOptions,
+    current_generation: Option<usize>,
+}
+
+impl<W: Write> TextStreamObserver<W> {
+    pub fn new(writer: W, options: TextStreamOptions) -> Self {
+        Self {
+            writer,
+            options,
+            current_generation: None,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn player_label(&self, game: &GameState, player_id: PlayerId) -> String {
+        let index = game
+            .players
+            .keys()
+            .position(|id| *id == player_id)
+            .unwrap_or_default();
+        let symbol = PLAYER_SYMBOLS[index % PLAYER_SYMBOLS.len()];
+        let label = format!("{} {}", symbol, player_id);
+
+        if self.options.use_colors {
+            let color = PLAYER_ANSI_COLORS[index % PLAYER_ANSI_COLORS.len()];
+            format!("\x1b[{}m{}{}", color, label, ANSI_RESET)
+        } else {
+            label
+        }
+    }
+
+    fn describe(&self, game: &GameState, event: &GameEvent) -> String {
+        let player = |player_id: &PlayerId| self.player_label(game, *player_id);
+
+        match event {
+            GameEvent::ResourcesChanged(player_id, changes) => {
+                format!(
+                    "{} resources: {}",
+                    player(player_id),
+                    format_changes(changes)
+                )
+            }
+            GameEvent::ProductionChanged(player_id, changes) => {
+                format!(
+                    "{} production: {}",
+                    player(player_id),
+                    format_changes(changes)
+                )
+            }
+            GameEvent::CardResourceChanged(player_id, card, card_resource, amount) => format!(
+                "{} {:+} {:?} on {}",
+                player(player_id),
+                amount,
+                card_resource,
+                card.name
+            ),
+            GameEvent::CardsDrawn(player_id, cards) => {
+                format!("{} draws {}", player(player_id), count_cards(cards.len()))
+            }
+            GameEvent::DraftPackDealt(player_id, cards) => format!(
+                "{} is dealt a draft pack of {}",
+                player(player_id),
+                count_cards(cards.len())
+            ),
+            GameEvent::CardDrafted(player_id, _) => format!("{} drafts a card", player(player_id)),
+            GameEvent::DraftPackPassed(from_player_id, to_player_id, cards) => format!(
+                "{} passes a draft pack of {} to {}",
+                player(from_player_id),
+                count_cards(cards.len()),
+                player(to_player_id)
+            ),
+            GameEvent::CardsDiscarded(player_id, cards) => {
+                format!(
+                    "{} discards {}",
+                    player(player_id),
+                    count_cards(cards.len())
+                )
+            }
+            GameEvent::CardsPassed(from_player_id, to_player_id, cards) => format!(
+                "{} passes {} to {}",
+                player(from_player_id),
+                count_cards(cards.len()),
+                player(to_player_id)
+            ),
+            GameEvent::CardRevealed(card) => format!("Revealed from the deck: {}", card.name),
+            GameEvent::DiscardPileReshuffled => {
+                "The discard pile is shuffled to form a new draw deck".to_string()
+            }
+            GameEvent::CardPlayed(player_id, card) => {
+                format!("{} plays {}", player(player_id), card.name)
+            }
+            GameEvent::CityPlaced(player_id, city_kind, location) => match city_kind {
+                CityKind::RegularCity => {
+                    format!("{} places a city at {}", player(player_id), location)
+                }
+                _ => format!(
+                    "{} places {:?} at {}",
+                    player(player_id),
+                    city_kind,
+                    location
+                ),
+            },
+            GameEvent::GreeneryPlaced(player_id, coordinates) => {
+                format!("{} places a greenery at {}", player(player_id), coordinates)
+            }
+            GameEvent::NeutralCityPlaced(coordinates) => {
+                format!("A neutral city is placed at {}", coordinates)
+            }
+            GameEvent::NeutralGreeneryPlaced(coordinates) => {
+                format!("A neutral greenery is placed at {}", coordinates)
+            }
+            GameEvent::SpecialTilePlaced(player_id, special_tile, coordinates) => format!(
+                "{} places {:?} at {}",
+                player(player_id),
+                special_tile,
+                coordinates
+            ),
+            GameEvent::OceanPlaced(coordinates) => format!("An ocean is placed at {}", coordinates),
+            GameEvent::TileRemoved(tile) => match tile {
+                TileStatus::Empty(_) => unreachable!(),
+                TileStatus::Ocean(location) => format!("The ocean at {} is removed", location),
+                TileStatus::City(location, CityKind::RegularCity, player_id) => {
+                    format!("{}'s city at {} is removed", player(player_id), location)
+                }
+                TileStatus::City(location, city_kind, player_id) => format!(
+                    "{}'s {:?} at {} is removed",
+                    player(player_id),
+                    city_kind,
+                    location
+                ),
+                TileStatus::Greenery(location, player_id) => {
+                    format!("{}'s greenery at {} is removed", player(player_id), location)
+                }
+                TileStatus::SpecialTile(location, special_tile, player_id) => format!(
+                    "{}'s {:?} at {} is removed",
+                    player(player_id),
+                    special_tile,
+                    location
+                ),
+            },
+            GameEvent::TemperatureRaised(temperature) => {
+                format!("Temperature rises to {}°C", temperature)
+            }
+            GameEvent::OxygenRaised(oxygen) => format!("Oxygen rises to {}%", oxygen),
+            GameEvent::TerraformRatingRaised(player_id, amount) => format!(
+                "{} raises their terraform rating by {} to {}",
+                player(player_id),
+                amount,
+                game.players[player_id].terraform_rating
+            ),
+            GameEvent::TerraformRatingLowered(player_id, amount) => format!(
+                "{} loses {} terraform rating, down to {}",
+                player(player_id),
+                amount,
+                game.players[player_id].terraform_rating
+            ),
+            GameEvent::EffectAdded(player_id, _) => {
+                format!("{} gains an ongoing effect", player(player_id))
+            }
+            GameEvent::EffectTriggered(player_id, _) => {
+                format!("An effect of {} triggers", player(player_id))
+            }
+            GameEvent::CardActionUsed(player_id, card) => {
+                format!("{} uses the action of {}", player(player_id), card.name)
+            }
+            GameEvent::CardActionsReset => "Card actions are available again".to_string(),
+            GameEvent::MilestoneEligible(player_id, milestone) => {
+                format!("{} qualifies for the {:?} milestone", player(player_id), milestone)
+            }
+            GameEvent::AwardFunded(player_id, award) => {
+                format!("{} funds the {:?} award", player(player_id), award)
+            }
+            GameEvent::CorporationsDealt(player_id, corporations) => {
+                let names: Vec<&str> = corporations
+                    .iter()
+                    .map(|corporation| corporation.name.as_ref())
+                    .collect();
+                format!("{} is dealt {}", player(player_id), names.join(", "))
+            }
+            GameEvent::CorporationPlayed(player_id, corporation) => {
+                format!("{} plays {}", player(player_id), corporation.name)
+            }
+            GameEvent::HandicapApplied(player_id, handicap) => {
+                format!("{} starts with a handicap: {}", player(player_id), handicap)
+            }
+            GameEvent::DelegatePlaced(Delegate::Player(player_id), party) => {
+                format!("{} sends a delegate to {}", player(player_id), party)
+            }
+            GameEvent::DelegatePlaced(Delegate::Neutral, party) => {
+                format!("A neutral delegate joins {}", party)
+            }
+            GameEvent::GlobalEventResolved(global_event) => {
+                format!("Global event: {:?}", global_event)
+            }
+            GameEvent::NewGovernment(party, Delegate::Player(player_id)) => {
+                format!("{} rule, chaired by {}", party, player(player_id))
+            }
+            GameEvent::NewGovernment(party, Delegate::Neutral) => {
+                format!("{} rule, with a neutral chairman", party)
+            }
+            GameEvent::PlannedActionSkipped(player_id, reason) => {
+                format!("{} skips a planned action: {:?}", player(player_id), reason)
+            }
+            GameEvent::EffectScheduled(effect) => format!(
+                "An effect is scheduled for the {:?} of generation {}",
+                effect.timing, effect.generation
+            ),
+            GameEvent::ScheduledEffectSkipped(reason) => {
+                format!("A scheduled effect is skipped: {:?}", reason)
+            }
+        }
+    }
+}
+
+impl<W: Write + 'static> GameObserver for TextStreamObserver<W> {
+    fn on_event(&mut self, game: &GameState, event: &GameEvent) {
+        if event_verbosity(event) > self.options.verbosity {
+            return;
+        }
+
+        let mut output = String::new();
+        if self.current_generation != Some(game.generation) {
+            self.current_generation = Some(game.generation);
+            output.push_str(&format!("===== Generation {} =====\n", game.generation));
+        }
+        output.push_str(&self.describe(game, event));
+        output.push('\n');
+
+        // A spectator that went away must not interrupt the game, so write errors are ignored.
+        let _ = self
+            .writer
+            .write_all(output.as_bytes())
+            .and_then(|_| self.writer.flush());
+    }
+}
+
+// The least verbose setting at which the event is shown.
+fn event_verbosity(event: &GameEvent) -> Verbosity {
+    match event {
+        GameEvent::CardPlayed(..)
+        | GameEvent::CardRevealed(..)
+        | GameEvent::CityPlaced(..)
+        | GameEvent::GreeneryPlaced(..)
+        | GameEvent::SpecialTilePlaced(..)
+        | GameEvent::OceanPlaced(..)
+        | GameEvent::NeutralCityPlaced(..)
+        | GameEvent::NeutralGreeneryPlaced(..)
+        | GameEvent::TileRemoved(..)
+        | GameEvent::TemperatureRaised(..)
+        | GameEvent::OxygenRaised(..)
+        | GameEvent::TerraformRatingRaised(..)
+        | GameEvent::MilestoneEligible(..)
+        | GameEvent::AwardFunded(..)
+        | GameEvent::HandicapApplied(..)
+        | GameEvent::CorporationPlayed(..)
+        | GameEvent::TerraformRatingLowered(..)
+        | GameEvent::GlobalEventResolved(..)
+        | GameEvent::NewGovernment(..) => Verbosity::Summary,
+
+        GameEvent::ResourcesChanged(..)
+        | GameEvent::ProductionChanged(..)
+        | GameEvent::CardResourceChanged(..)
+        | GameEvent::CardsDrawn(..)
+        | GameEvent::CardsDiscarded(..)
+        | GameEvent::CardsPassed(..)
+        | GameEvent::CorporationsDealt(..)
+        | GameEvent::DraftPackDealt(..)
+        | GameEvent::CardDrafted(..)
+        | GameEvent::CardActionUsed(..)
+        | GameEvent::EffectTriggered(..)
+        | GameEvent::DelegatePlaced(..)
+        | GameEvent::PlannedActionSkipped(..)
+        | GameEvent::ScheduledEffectSkipped(..) => Verbosity::Normal,
+
+        GameEvent::DiscardPileReshuffled
+        | GameEvent::DraftPackPassed(..)
+        | GameEvent::EffectAdded(..)
+        | GameEvent::EffectScheduled(..)
+        | GameEvent::CardActionsReset => Verbosity::Verbose,
+    }
+}
+
+fn format_changes(changes: &BTreeMap<Resource, isize>) -> String {
+    let parts: Vec<String> = changes
+        .iter()
+        .filter(|(_, change)| **change != 0)
+        .map(|(resource, change)| format!("{:+} {:?}", change, resource))
+        .collect();
+
+    if parts.is_empty() {
+        "no change".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+fn count_cards(count: usize) -> String {
+    match count {
+        1 => "1 card".to_string(),
+        _ => format!("{} cards", count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::Coordinates,
+        event::EventBus,
+        game::{GameOperation, PlayerId},
+        position::import_position,
+        spectator::{TextStreamObserver, TextStreamOptions, Verbosity},
+    };
+
+    fn stream_text(options: TextStreamOptions) -> String {
+        let mut game = import_position("generation 2\nplayer p1\nplayer p2").unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+
+        let mut bus = EventBus::new();
+        let handle = bus.subscribe(TextStreamObserver::new(Vec::<u8>::new(), options));
+        bus.execute(&mut game, GameOperation::DrawCards(p1, 4));
+        bus.execute(
+            &mut game,
+            GameOperation::PlaceOcean(Coordinates::new(5, -1)),
+        );
+        bus.execute(&mut game, GameOperation::RaiseTerraformRating(p1, 1));
+
+        game.generation += 1;
+        bus.execute(&mut game, GameOperation::RaiseOxygen);
+        bus.execute(&mut game, GameOperation::DrawCards(p2, 1));
+
+        let observer = bus.observer::<TextStreamObserver<Vec<u8>>>(handle).unwrap();
+        String::from_utf8(observer.get_ref().clone()).unwrap()
+    }
+
+    #[test]
+    fn events_are_streamed_with_generation_separators() {
+        let text = stream_text(TextStreamOptions::default());
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(
+            vec![
+                "===== Generation 2 =====",
+                "● Player 1 draws 4 cards",
+                "An ocean is placed at 5,-1",
+                "● Player 1 raises their terraform rating by 1 to 21",
+                "===== Generation 3 =====",
+                "Oxygen rises to 1%",
+                "■ Player 2 draws 1 card",
+            ],
+            lines
+        );
+    }
+
+    #[test]
+    fn verbosity_and_colors_are_configurable() {
+        let text = stream_text(TextStreamOptions {
+            verbosity: Verbosity::Summary,
+            use_colors: true,
+        });
+
+        assert!(!text.contains("draws"));
+        assert!(text.contains("\x1b[31m● Player 1\x1b[0m raises their terraform rating"));
+        assert_eq!(5, text.lines().count());
+    }
+}
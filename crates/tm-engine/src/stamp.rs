@@ -0,0 +1,140 @@
+//! Build stamps: the engine version, rules revision, and card data behind a save, a recorded
+//! game, or the engine at the other end of a connection.
+//!
+//! A game only plays out the same under the same rules and cards, so differences in either
+//! are refused. Engines of different versions that agree on both should play the same games,
+//! so a different engine version is only warned about.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::registry::CardRegistry;
+
+/// Bumped whenever the same operations resolve differently, e.g. when effects that used to be
+/// ignored start to fire, so that games recorded under other rules are not replayed wrongly.
+pub const RULES_REVISION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BuildStamp {
+    pub engine_version: String,
+    pub rules_revision: u32,
+    pub deck_hash: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StampMismatch {
+    EngineVersion { stamped: String, current: String },
+    RulesRevision { stamped: u32, current: u32 },
+    DeckHash { stamped: String, current: String },
+}
+
+impl StampMismatch {
+    /// Whether games cannot carry over between the builds, rather than only being warned about.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, StampMismatch::EngineVersion { .. })
+    }
+}
+
+impl fmt::Display for StampMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StampMismatch::EngineVersion { stamped, current } => write!(
+                f,
+                "made by engine version {}, but this is version {}",
+                stamped, current
+            ),
+            StampMismatch::RulesRevision { stamped, current } => write!(
+                f,
+                "made under rules revision {}, but these are revision {}",
+                stamped, current
+            ),
+            StampMismatch::DeckHash { stamped, current } => write!(
+                f,
+                "made with different card data (deck hash {}, current deck hash {})",
+                stamped, current
+            ),
+        }
+    }
+}
+
+impl BuildStamp {
+    /// The stamp of this build.
+    pub fn current() -> Self {
+        Self {
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            rules_revision: RULES_REVISION,
+            deck_hash: CardRegistry::global().content_hash_hex(),
+        }
+    }
+
+    /// Every way in which the stamp differs from this build's.
+    pub fn mismatches(&self) -> Vec<StampMismatch> {
+        let current = BuildStamp::current();
+        let mut mismatches = vec![];
+        if self.engine_version != current.engine_version {
+            mismatches.push(StampMismatch::EngineVersion {
+                stamped: self.engine_version.clone(),
+                current: current.engine_version,
+            });
+        }
+        if self.rules_revision != current.rules_revision {
+            mismatches.push(StampMismatch::RulesRevision {
+                stamped: self.rules_revision,
+                current: current.rules_revision,
+            });
+        }
+        if self.deck_hash != current.deck_hash {
+            mismatches.push(StampMismatch::DeckHash {
+                stamped: self.deck_hash.clone(),
+                current: current.deck_hash,
+            });
+        }
+        mismatches
+    }
+
+    /// The differences from this build to warn about,
+    /// or the first one that games cannot carry over.
+    pub fn check(&self) -> Result<Vec<StampMismatch>, StampMismatch> {
+        let (fatal, warnings): (Vec<_>, Vec<_>) = self
+            .mismatches()
+            .into_iter()
+            .partition(StampMismatch::is_fatal);
+        match fatal.into_iter().next() {
+            Some(mismatch) => Err(mismatch),
+            None => Ok(warnings),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stamp::{BuildStamp, StampMismatch, RULES_REVISION};
+
+    #[test]
+    fn only_other_rules_or_cards_are_refused() {
+        assert_eq!(Ok(vec![]), BuildStamp::current().check());
+
+        let older_engine = BuildStamp {
+            engine_version: "0.0.1".to_string(),
+            ..BuildStamp::current()
+        };
+        let warnings = older_engine.check().unwrap();
+        assert_eq!(1, warnings.len());
+        assert!(!warnings[0].is_fatal());
+
+        let newer_rules = BuildStamp {
+            engine_version: "0.0.1".to_string(),
+            rules_revision: RULES_REVISION + 1,
+            ..BuildStamp::current()
+        };
+        assert_eq!(
+            Err(StampMismatch::RulesRevision {
+                stamped: RULES_REVISION + 1,
+                current: RULES_REVISION,
+            }),
+            newer_rules.check()
+        );
+        assert_eq!(2, newer_rules.mismatches().len());
+    }
+}
@@ -0,0 +1,208 @@
+//! Scaffolding for tests of code built on the engine, e.g. bots: games, boards, and
+//! positions to start from, and assertions that explain what differs when they fail.
+//!
+//! Compiled for the crate's own tests, and with the `test-utils` feature for everyone else.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    award::AwardStandings,
+    board::{
+        make_base_game_board, Coordinates, MarsBoard, MarsBoardBuilder, TileLocation, TileStatus,
+    },
+    game::{GamePhase, GameState, PlayerId, PlayerState},
+    milestone::MilestoneNotices,
+    options::GameOptions,
+    position::import_position,
+    resource::Resource,
+    rng::GameRng,
+    timeline::ParameterTimeline,
+};
+
+/// A base game on a fresh board in its first generation, with an empty draw deck.
+pub fn make_game(players: Vec<PlayerState>) -> GameState {
+    let mut game = GameState {
+        board: make_base_game_board(),
+        players: players
+            .into_iter()
+            .map(|player| (player.player_id, player))
+            .collect(),
+        draw_deck: vec![],
+        discard_pile: vec![],
+        generation: 1,
+        phase: GamePhase::default(),
+        first_player_marker: None,
+        turn_player: None,
+        passed_players: BTreeSet::new(),
+        options: GameOptions::base_game(),
+        funded_awards: vec![],
+        rng: GameRng::from_seed(0),
+        award_standings: AwardStandings::default(),
+        milestone_notices: MilestoneNotices::default(),
+        parameter_timeline: ParameterTimeline::default(),
+        turmoil: None,
+        corporation_offers: BTreeMap::new(),
+        scheduled_effects: vec![],
+        research_draft: None,
+    };
+    game.award_standings = AwardStandings::compute(&game);
+    game.milestone_notices = MilestoneNotices::compute(&game);
+    game
+}
+
+/// A base game board with the global parameters at the given levels. The oceans are placed
+/// on the first spaces reserved for them.
+pub fn board_with_parameters(oxygen: usize, temperature: isize, oceans: usize) -> MarsBoard {
+    assert!(oxygen <= MarsBoard::MAX_OXYGEN);
+    assert!(temperature <= MarsBoard::MAX_TEMPERATURE);
+    assert!(oceans <= MarsBoard::MAX_OCEANS);
+
+    let ocean_spaces: Vec<_> = make_base_game_board()
+        .spaces
+        .values()
+        .filter(|space| space.is_reserved_for_ocean())
+        .filter_map(|space| match space.location {
+            TileLocation::OnMars(coordinates) => Some(coordinates),
+            TileLocation::OffMars(_) => None,
+        })
+        .take(oceans)
+        .collect();
+    ocean_spaces
+        .into_iter()
+        .fold(
+            MarsBoardBuilder::new()
+                .with_oxygen(oxygen)
+                .with_temperature(temperature),
+            |board, coordinates| board.with_ocean(coordinates),
+        )
+        .build()
+}
+
+/// Two players halfway through a game: p1 builds an engine, p2 goes for terraform rating.
+pub fn mid_game_scenario() -> GameState {
+    import_position(
+        "\
+generation 5
+oxygen 5
+temperature -16
+ocean 5,-1
+greenery 1,0 p1
+city 0,0 p1
+player p1
+tr 26
+resources 18 4 2 6 1 3
+production 4 2 1 2 1 1
+played Space Station; Solar Power
+hand GHG Factories
+player p2
+tr 31
+resources 9 0 0 3 2 12
+production 1 0 0 1 2 4
+hand Predators
+",
+    )
+    .unwrap()
+}
+
+/// The same two players in the last generation: every global parameter but one ocean is maxed.
+/// Their tiles stay where they were, and the missing oceans go on the first free spaces
+/// reserved for them.
+pub fn final_generation_scenario() -> GameState {
+    let mut game = mid_game_scenario();
+    game.board.oxygen = MarsBoard::MAX_OXYGEN;
+    game.board.temperature = MarsBoard::MAX_TEMPERATURE;
+
+    let mut free_ocean_spaces: Vec<Coordinates> = game
+        .board
+        .spaces
+        .values()
+        .filter(|space| space.is_reserved_for_ocean())
+        .filter_map(|space| match space.location {
+            TileLocation::OnMars(coordinates) => Some(coordinates),
+            TileLocation::OffMars(_) => None,
+        })
+        .filter(|coordinates| {
+            matches!(
+                game.board.get_tile_status(&TileLocation::OnMars(*coordinates)),
+                TileStatus::Empty(_)
+            )
+        })
+        .collect();
+    free_ocean_spaces.sort();
+    let missing_oceans = MarsBoard::MAX_OCEANS - 1 - game.board.oceans.len();
+    game.board
+        .oceans
+        .extend(free_ocean_spaces.into_iter().take(missing_oceans));
+
+    game.generation = 11;
+    game
+}
+
+#[track_caller]
+pub fn assert_vp_eq(expected: isize, game: &GameState, player_id: PlayerId) {
+    let player = &game.players[&player_id];
+    let breakdown = player.score_breakdown(&game.board);
+    assert_eq!(
+        expected,
+        breakdown.total(),
+        "{:?} scores {:?}",
+        player_id,
+        breakdown
+    );
+}
+
+/// Resources in the order of `PlayerStateBuilder::with_resources()`:
+/// M$, steel, titanium, plants, energy, heat.
+#[track_caller]
+pub fn assert_resources_eq(expected: [usize; 6], player: &PlayerState) {
+    let resources = [
+        Resource::Megacredits,
+        Resource::Steel,
+        Resource::Titanium,
+        Resource::Plants,
+        Resource::Energy,
+        Resource::Heat,
+    ]
+    .map(|resource| player.resources[resource]);
+    assert_eq!(
+        expected, resources,
+        "resources of {:?} (M$, steel, titanium, plants, energy, heat)",
+        player.player_id
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::MarsBoard,
+        game::{PlayerId, PlayerStateBuilder},
+        projection::remaining_parameter_steps,
+        test_utils::{
+            assert_resources_eq, assert_vp_eq, final_generation_scenario, make_game,
+            mid_game_scenario,
+        },
+    };
+
+    #[test]
+    fn canned_games_are_consistent() {
+        let game = make_game(vec![PlayerStateBuilder::new(1)
+            .with_resources(5, 0, 0, 8, 0, 0)
+            .build()]);
+        assert_vp_eq(20, &game, PlayerId::from(1));
+        assert_resources_eq([5, 0, 0, 8, 0, 0], &game.players[&PlayerId::from(1)]);
+
+        let mid_game = mid_game_scenario();
+        assert_eq!(2, mid_game.players.len());
+        let final_generation = final_generation_scenario();
+        assert_eq!(1, remaining_parameter_steps(&final_generation.board));
+        assert_eq!(MarsBoard::MAX_OXYGEN, final_generation.board.oxygen);
+
+        // The tiles of the middle of the game are still there.
+        assert_eq!(mid_game.board.cities, final_generation.board.cities);
+        assert_eq!(mid_game.board.greeneries, final_generation.board.greeneries);
+        assert!(mid_game
+            .board
+            .oceans
+            .is_subset(&final_generation.board.oceans));
+    }
+}
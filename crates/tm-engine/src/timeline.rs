@@ -0,0 +1,101 @@
+//! The history of the global parameters over the game, one snapshot per generation,
+//! e.g. for charting the pace of terraforming, or for projecting how long the game goes on.
+//!
+//! The board has no Venus track, so only oxygen, temperature, and oceans are recorded.
+
+use serde::{Deserialize, Serialize};
+
+use crate::board::MarsBoard;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterSnapshot {
+    pub generation: usize, // the generation that ended with the parameters at these levels
+    pub oxygen: usize,
+    pub temperature: isize,
+    pub oceans: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParameterTimeline {
+    pub snapshots: Vec<ParameterSnapshot>, // oldest first
+}
+
+impl ParameterTimeline {
+    pub fn record(&mut self, generation: usize, board: &MarsBoard) {
+        self.snapshots.push(ParameterSnapshot {
+            generation,
+            oxygen: board.oxygen,
+            temperature: board.temperature,
+            oceans: board.oceans.len(),
+        });
+    }
+
+    /// The number of global parameter steps completed and the number of generations they
+    /// took, or none if the timeline is too short to tell.
+    ///
+    /// A timeline starting in the first generation is measured from the starting levels;
+    /// one starting later, e.g. in a game imported from a position, from its first snapshot.
+    pub fn observed_steps(&self) -> Option<(usize, usize)> {
+        let (first, last) = (self.snapshots.first()?, self.snapshots.last()?);
+        let (steps, generations) = if first.generation == 1 {
+            (last.completed_steps(), self.snapshots.len())
+        } else {
+            (
+                last.completed_steps() - first.completed_steps(),
+                self.snapshots.len() - 1,
+            )
+        };
+
+        if generations == 0 {
+            None
+        } else {
+            Some((steps, generations))
+        }
+    }
+
+    /// The average number of global parameter steps completed per generation.
+    pub fn steps_per_generation(&self) -> Option<f64> {
+        self.observed_steps()
+            .map(|(steps, generations)| steps as f64 / generations as f64)
+    }
+}
+
+impl ParameterSnapshot {
+    pub fn completed_steps(&self) -> usize {
+        let oxygen_steps = (self.oxygen - MarsBoard::STARTING_OXYGEN) / MarsBoard::OXYGEN_INCREMENT;
+        let temperature_steps = ((self.temperature - MarsBoard::STARTING_TEMPERATURE)
+            / MarsBoard::TEMPERATURE_INCREMENT) as usize;
+
+        oxygen_steps + temperature_steps + self.oceans
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{make_base_game_board, MarsBoard},
+        timeline::ParameterTimeline,
+    };
+
+    #[test]
+    fn pace_is_measured_from_the_start_or_the_first_snapshot() {
+        let mut board = make_base_game_board();
+        let mut timeline = ParameterTimeline::default();
+        assert_eq!(None, timeline.steps_per_generation());
+
+        board.oxygen += 2 * MarsBoard::OXYGEN_INCREMENT;
+        timeline.record(1, &board);
+        assert_eq!(Some(2.0), timeline.steps_per_generation());
+
+        board.temperature += 4 * MarsBoard::TEMPERATURE_INCREMENT;
+        timeline.record(2, &board);
+        assert_eq!(Some(3.0), timeline.steps_per_generation());
+
+        // Whatever happened before the first snapshot is unknown.
+        timeline.snapshots.remove(0);
+        assert_eq!(None, timeline.steps_per_generation());
+        board.oxygen += MarsBoard::OXYGEN_INCREMENT;
+        timeline.record(3, &board);
+        assert_eq!(Some(1.0), timeline.steps_per_generation());
+    }
+}
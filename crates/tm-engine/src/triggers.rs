@@ -0,0 +1,266 @@
+//! Triggered effects: the effects in play that fire whenever something happens in the game,
+//! like Arctic Algae's plants for every ocean placed, or Media Group's megacredits for every
+//! event played.
+//!
+//! Every event that the execution of an operation produces is checked against the effects
+//! of every player. The impacts of the effects that fire are resolved for the effect's owner
+//! right away, as operations of their own, after an `EffectTriggered` event.
+//!
+//! A card's effects do not fire for the card itself, since they only take hold once it has
+//! been played. Impacts that need a choice the engine cannot make for the player yet, such as
+//! where to place a tile or which card to discard, are not resolved. Of several impacts
+//! to choose from, the first that can be resolved is.
+
+use maplit::btreemap;
+
+use crate::{
+    card::{Card, CardEffect, ImmediateImpact},
+    event::GameEvent,
+    game::{GameOperation, GameState, PlayerId, PlayerState},
+};
+
+impl PlayerState {
+    /// Every effect of the player, with the card it comes from, if any.
+    /// Effects granted by the corporation or by the game itself come from no card.
+    pub fn effect_sources(&self) -> Vec<(Option<&Card>, &CardEffect)> {
+        self.effects
+            .iter()
+            .enumerate()
+            .map(|(index, effect)| {
+                // Identical effects of different cards are matched to the cards in order.
+                let earlier = self.effects[..index]
+                    .iter()
+                    .filter(|other| *other == effect)
+                    .count();
+                let source = self
+                    .played_cards
+                    .iter()
+                    .filter(|card| card.effects.contains(effect))
+                    .nth(earlier);
+                (source, effect)
+            })
+            .collect()
+    }
+
+    /// The impacts that the player's effects trigger when a card is played, by the player
+    /// themselves if `own` is set, or else by another player, with the effect that fired
+    /// and the card it comes from. Effects fire once for each matching tag on the card.
+    pub fn impacts_triggered_by_card(
+        &self,
+        played: &Card,
+        own: bool,
+    ) -> Vec<(Option<&Card>, &CardEffect, ImmediateImpact)> {
+        let mut impacts = vec![];
+        for (source, effect) in self.effect_sources() {
+            if source == Some(played) {
+                continue;
+            }
+            let fired: Vec<ImmediateImpact> = match effect {
+                CardEffect::OnAnyTagPlayed(tag, impact) => {
+                    vec![impact.clone(); played.tags.count(*tag)]
+                }
+                CardEffect::OnOwnTagPlayed(tag, impact) if own => {
+                    vec![impact.clone(); played.tags.count(*tag)]
+                }
+                CardEffect::OnOwnTagCombinationPlayed(tags, combination_impacts)
+                    if own && tags.iter().all(|tag| played.tags.count(*tag) > 0) =>
+                {
+                    combination_impacts.clone()
+                }
+                _ => vec![],
+            };
+            impacts.extend(fired.into_iter().map(|impact| (source, effect, impact)));
+        }
+        impacts
+    }
+}
+
+impl GameState {
+    /// The effects that fire in response to the event, each with the operations that resolve
+    /// it for its owner, in seating order.
+    pub fn triggered_operations(
+        &self,
+        event: &GameEvent,
+    ) -> Vec<(PlayerId, CardEffect, Vec<GameOperation>)> {
+        let mut triggered = vec![];
+        for (owner_id, owner) in &self.players {
+            let mut impacts: Vec<(Option<&Card>, &CardEffect, ImmediateImpact)> = vec![];
+            let mut played = None;
+            match event {
+                GameEvent::CardPlayed(player_id, card) => {
+                    let own = player_id == owner_id;
+                    impacts = owner.impacts_triggered_by_card(card, own);
+                    played = Some(card).filter(|_| own);
+                }
+                _ => {
+                    for (source, effect) in owner.effect_sources() {
+                        let impact = match (effect, event) {
+                            (CardEffect::OnAnyPlacedOcean(impact), GameEvent::OceanPlaced(_))
+                            | (CardEffect::OnAnyPlacedCity(impact), GameEvent::CityPlaced(..)) => {
+                                impact
+                            }
+                            (
+                                CardEffect::OnOwnPlacedGreenery(impact),
+                                GameEvent::GreeneryPlaced(player_id, _),
+                            ) if player_id == owner_id => impact,
+                            _ => continue,
+                        };
+                        impacts.push((source, effect, impact.clone()));
+                    }
+                }
+            }
+
+            for (source, effect, impact) in impacts {
+                if let Some(operations) = trigger_operations(owner, source, played, &impact) {
+                    triggered.push((*owner_id, effect.clone(), operations));
+                }
+            }
+        }
+        triggered
+    }
+
+    // Fire the effects that the events trigger, and those that their own events trigger.
+    pub(crate) fn resolve_triggered_effects(&mut self, events: &[GameEvent]) -> Vec<GameEvent> {
+        let mut triggered_events = vec![];
+        for event in events {
+            for (owner_id, effect, operations) in self.triggered_operations(event) {
+                triggered_events.push(GameEvent::EffectTriggered(owner_id, effect));
+                for operation in operations {
+                    triggered_events.extend(self.execute_operation(operation));
+                }
+            }
+        }
+        triggered_events
+    }
+}
+
+// The operations that resolve the impact for the owner of the effect, or None if it needs
+// a choice that cannot be made for them. `played` is the owner's card that fired the effect.
+fn trigger_operations(
+    owner: &PlayerState,
+    source: Option<&Card>,
+    played: Option<&Card>,
+    impact: &ImmediateImpact,
+) -> Option<Vec<GameOperation>> {
+    let owner_id = owner.player_id;
+    let add_card_resource = |card: &Card, amount: usize| {
+        let card_resource = card.supports_card_resource()?;
+        Some(vec![GameOperation::ChangeCardResource(
+            owner_id,
+            card.clone(),
+            card_resource,
+            amount as isize,
+        )])
+    };
+
+    match impact {
+        ImmediateImpact::RaiseTerraformRating => {
+            Some(vec![GameOperation::RaiseTerraformRating(owner_id, 1)])
+        }
+        ImmediateImpact::GainResource(resource, amount) => {
+            Some(vec![GameOperation::ChangeResources(
+                owner_id,
+                btreemap! { *resource => *amount as isize },
+            )])
+        }
+        ImmediateImpact::ChangeProduction(resource, change) => {
+            Some(vec![GameOperation::ChangeProduction(
+                owner_id,
+                btreemap! { *resource => *change },
+            )])
+        }
+        ImmediateImpact::DrawCard(count) => Some(vec![GameOperation::DrawCards(owner_id, *count)]),
+        ImmediateImpact::AddResourceToSameCard(card_resource, amount) => {
+            let source =
+                source.filter(|card| card.supports_card_resource() == Some(*card_resource));
+            add_card_resource(source?, *amount)
+        }
+        ImmediateImpact::AddResourceToPlayedCard(amount) => add_card_resource(played?, *amount),
+        ImmediateImpact::OneOf(options) => options
+            .iter()
+            .find_map(|option| trigger_operations(owner, source, played, option)),
+        ImmediateImpact::Chained(first, second) => {
+            let mut operations = trigger_operations(owner, source, played, first)?;
+            operations.extend(trigger_operations(owner, source, played, second)?);
+            Some(operations)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::TileLocation,
+        card::{CardEffect, ImmediateImpact, CORPORATE_GAME_CARDS_BY_NAME},
+        event::GameEvent,
+        game::{GameOperation, PlayerId, StandardProject},
+        position::import_position,
+        resource::{CardResource, Resource},
+    };
+
+    #[test]
+    fn effects_in_play_fire_for_their_owner_whenever_their_event_happens() {
+        let mut game = import_position(
+            "player p1\nplayed Arctic Algae; Pets; Media Group; Viral Enhancers\n\
+             hand Virus; Lichen\nplayer p2\nresources 50 0 0 0 0 0",
+        )
+        .unwrap();
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let card = |name: &str| CORPORATE_GAME_CARDS_BY_NAME[name].clone();
+        // The first space in reading order that is, or is not, reserved for oceans.
+        let mut spaces: Vec<_> = game
+            .board
+            .spaces
+            .values()
+            .filter_map(|space| match space.location {
+                TileLocation::OnMars(coordinates) => Some((
+                    coordinates.get_z(),
+                    coordinates,
+                    space.is_reserved_for_ocean(),
+                )),
+                TileLocation::OffMars(_) => None,
+            })
+            .collect();
+        spaces.sort();
+        let first_space = |ocean: bool| {
+            spaces
+                .iter()
+                .find(|(_, _, reserved)| *reserved == ocean)
+                .map(|(_, coordinates, _)| *coordinates)
+                .unwrap()
+        };
+
+        // Anyone's ocean gives Arctic Algae's owner plants.
+        let events = game
+            .play_standard_project(p2, &StandardProject::Aquifer(first_space(true)))
+            .unwrap();
+        let algae =
+            CardEffect::OnAnyPlacedOcean(ImmediateImpact::GainResource(Resource::Plants, 2));
+        assert!(events.contains(&GameEvent::EffectTriggered(p1, algae)));
+        assert_eq!(2, game.players[&p1].resources[Resource::Plants]);
+
+        // Anyone's city adds an animal to Pets.
+        game.play_standard_project(p2, &StandardProject::City(first_space(false)))
+            .unwrap();
+        let pets = (card("Pets"), CardResource::Animal);
+        assert_eq!(Some(&1), game.players[&p1].card_resources.get(&pets));
+
+        // The player's own event pays out from Media Group, and its microbe tag fires
+        // Viral Enhancers, which gives a plant rather than needing a card to add to.
+        game.execute_operation(GameOperation::PutCardIntoPlay(p1, card("Virus")));
+        game.execute_operation(GameOperation::PutCardIntoPlay(p1, card("Lichen")));
+        let player = &game.players[&p1];
+        assert_eq!(3, player.resources[Resource::Megacredits]);
+        assert_eq!(4, player.resources[Resource::Plants]);
+
+        // Plans that only follow the player's own state see the same payout.
+        let mut state =
+            import_position("player p1\nresources 1 0 0 0 0 0\nplayed Media Group\nhand Virus")
+                .unwrap()
+                .players[&p1]
+                .clone();
+        state.play_card(&game.board, &card("Virus")).unwrap();
+        assert_eq!(3, state.resources[Resource::Megacredits]);
+    }
+}
@@ -0,0 +1,830 @@
+//! Turmoil: the political parties of Mars, the delegates players send to them,
+//! and the global events that hit every player at the end of each generation.
+//!
+//! The party with the most delegates is dominant, and takes power at the end of the
+//! generation: its leader becomes the chairman, every player gets its ruling bonus,
+//! and its policy applies to the actions taken until the next change of government.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use maplit::btreemap;
+use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::TileLocation,
+    card::CardTag,
+    event::GameEvent,
+    game::{ActiveTags, GameOperation, GameState, IllegalOperation, PlayerId, PlayerState},
+    resource::Resource,
+    rng::GameRng,
+};
+
+/// What sending a delegate from the reserve costs. The delegate in the lobby is free.
+pub const DELEGATE_COST: usize = 5;
+
+/// Each player's delegates at the start of the game, including the one in the lobby.
+pub const DELEGATES_PER_PLAYER: usize = 7;
+
+/// The cap on the tags or tiles counted by most global events, before influence.
+const GLOBAL_EVENT_MAX_COUNT: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Party {
+    MarsFirst,
+    Scientists,
+    Unity,
+    Greens,
+    Reds,
+    Kelvinists,
+}
+
+impl Party {
+    // In the order the dominance passes around the table when parties are tied.
+    pub const ALL: [Party; 6] = [
+        Party::MarsFirst,
+        Party::Scientists,
+        Party::Unity,
+        Party::Greens,
+        Party::Reds,
+        Party::Kelvinists,
+    ];
+
+    /// What every player gets when the party takes power.
+    pub fn ruling_bonus(self, game: &GameState, player_id: PlayerId) -> Vec<GameOperation> {
+        let player = &game.players[&player_id];
+        let megacredits = match self {
+            Party::MarsFirst => player.active_tag_count(CardTag::Building),
+            Party::Scientists => player.active_tag_count(CardTag::Science),
+            Party::Unity => {
+                player.active_tag_count(CardTag::Earth) + player.active_tag_count(CardTag::Jovian)
+            }
+            Party::Greens => [CardTag::Plant, CardTag::Microbe, CardTag::Animal]
+                .iter()
+                .map(|tag| player.active_tag_count(*tag))
+                .sum(),
+            Party::Kelvinists => player.production[Resource::Heat].max(0) as usize,
+            Party::Reds => {
+                // The players with the lowest terraform rating gain 1 TR.
+                let lowest = game.players.values().map(|p| p.terraform_rating).min();
+                return if lowest == Some(player.terraform_rating) {
+                    vec![GameOperation::RaiseTerraformRating(player_id, 1)]
+                } else {
+                    vec![]
+                };
+            }
+        };
+        gain(player_id, Resource::Megacredits, megacredits)
+    }
+
+    /// What the party's policy adds to the operations of an action the player takes while
+    /// the party rules: costs go first, so the action is only taken if they can be paid.
+    pub fn apply_policy(
+        self,
+        player_id: PlayerId,
+        operations: Vec<GameOperation>,
+    ) -> Vec<GameOperation> {
+        let (mut costs, mut gains) = (vec![], vec![]);
+        for operation in &operations {
+            match (self, operation) {
+                // Terraforming costs 3 M$ per step of TR raised.
+                (Party::Reds, GameOperation::RaiseTerraformRating(raised, steps))
+                    if *raised == player_id =>
+                {
+                    costs.push(GameOperation::ChangeResources(
+                        player_id,
+                        btreemap! { Resource::Megacredits => -3 * (*steps as isize) },
+                    ));
+                }
+                // Placing a tile on Mars gains 1 steel.
+                (Party::MarsFirst, GameOperation::PlaceGreenery(placer, _))
+                | (Party::MarsFirst, GameOperation::PlaceSpecialTile(placer, _, _))
+                    if *placer == player_id =>
+                {
+                    gains.extend(gain(player_id, Resource::Steel, 1));
+                }
+                (
+                    Party::MarsFirst,
+                    GameOperation::PlaceCityTile(placer, _, TileLocation::OnMars(_)),
+                ) if *placer == player_id => {
+                    gains.extend(gain(player_id, Resource::Steel, 1));
+                }
+                // Placing a greenery gains 4 M$.
+                (Party::Greens, GameOperation::PlaceGreenery(placer, _))
+                    if *placer == player_id =>
+                {
+                    gains.extend(gain(player_id, Resource::Megacredits, 4));
+                }
+                _ => {}
+            }
+        }
+
+        costs.extend(operations);
+        costs.extend(gains);
+        costs
+    }
+}
+
+impl fmt::Display for Party {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Party::MarsFirst => write!(f, "Mars First"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// A delegate in a party, or the chairman. Neutral delegates are placed by global events.
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Delegate {
+    Neutral,
+    Player(PlayerId),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyState {
+    pub delegates: Vec<Delegate>, // in the order they were placed, including the leader
+    pub leader: Option<Delegate>,
+}
+
+impl PartyState {
+    pub fn delegate_count(&self, delegate: Delegate) -> usize {
+        self.delegates.iter().filter(|d| **d == delegate).count()
+    }
+
+    // The first delegate leads the party, until someone else has more delegates in it.
+    fn add(&mut self, delegate: Delegate) {
+        self.delegates.push(delegate);
+        match self.leader {
+            None => self.leader = Some(delegate),
+            Some(leader) => {
+                if self.delegate_count(delegate) > self.delegate_count(leader) {
+                    self.leader = Some(delegate);
+                }
+            }
+        }
+    }
+}
+
+/// The events that hit every player at the end of a generation. Each is revealed two
+/// generations ahead, and the parties it names get a neutral delegate when it is revealed
+/// and when it comes into effect. Influence softens the losses and adds to the gains.
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GlobalEvent {
+    GlobalDustStorm,     // lose all heat, and 2 M$ per building tag
+    Pandemic,            // lose 3 M$ per building tag
+    Riots,               // lose 4 M$ per city tile
+    Sabotage,            // -1 energy and steel production, 1 steel per influence
+    EcoSabotage,         // lose all plants except 3 plus influence
+    InterplanetaryTrade, // 2 M$ per space tag
+    StrongSociety,       // 2 M$ per city tile
+    SuccessfulOrganisms, // 1 plant per plant production
+    ScientificCommunity, // 1 M$ per card in hand, uncapped
+}
+
+impl GlobalEvent {
+    pub const ALL: [GlobalEvent; 9] = [
+        GlobalEvent::GlobalDustStorm,
+        GlobalEvent::Pandemic,
+        GlobalEvent::Riots,
+        GlobalEvent::Sabotage,
+        GlobalEvent::EcoSabotage,
+        GlobalEvent::InterplanetaryTrade,
+        GlobalEvent::StrongSociety,
+        GlobalEvent::SuccessfulOrganisms,
+        GlobalEvent::ScientificCommunity,
+    ];
+
+    /// The party that gets a neutral delegate when the event is revealed.
+    pub fn revealed_party(self) -> Party {
+        match self {
+            GlobalEvent::GlobalDustStorm
+            | GlobalEvent::EcoSabotage
+            | GlobalEvent::SuccessfulOrganisms => Party::Greens,
+            GlobalEvent::Pandemic | GlobalEvent::Riots => Party::MarsFirst,
+            GlobalEvent::Sabotage => Party::Kelvinists,
+            GlobalEvent::InterplanetaryTrade => Party::Unity,
+            GlobalEvent::StrongSociety | GlobalEvent::ScientificCommunity => Party::Reds,
+        }
+    }
+
+    /// The party that gets a neutral delegate when the event comes into effect.
+    pub fn current_party(self) -> Party {
+        match self {
+            GlobalEvent::GlobalDustStorm => Party::Kelvinists,
+            GlobalEvent::Pandemic => Party::Greens,
+            GlobalEvent::Riots | GlobalEvent::Sabotage | GlobalEvent::EcoSabotage => Party::Reds,
+            GlobalEvent::InterplanetaryTrade | GlobalEvent::StrongSociety => Party::MarsFirst,
+            GlobalEvent::SuccessfulOrganisms | GlobalEvent::ScientificCommunity => {
+                Party::Scientists
+            }
+        }
+    }
+
+    /// What the event does to the player, who has the given influence.
+    pub fn effect(
+        self,
+        game: &GameState,
+        player_id: PlayerId,
+        influence: usize,
+    ) -> Vec<GameOperation> {
+        let player = &game.players[&player_id];
+        let capped = |count: usize| count.min(GLOBAL_EVENT_MAX_COUNT);
+        let cities = game
+            .board
+            .cities
+            .values()
+            .filter(|(_, owner)| *owner == player_id)
+            .count();
+
+        match self {
+            GlobalEvent::GlobalDustStorm => {
+                let mut operations = lose(player, Resource::Heat, player.resources[Resource::Heat]);
+                let buildings = capped(player.active_tag_count(CardTag::Building));
+                operations.extend(lose(
+                    player,
+                    Resource::Megacredits,
+                    2 * buildings.saturating_sub(influence),
+                ));
+                operations
+            }
+            GlobalEvent::Pandemic => {
+                let buildings = capped(player.active_tag_count(CardTag::Building));
+                lose(
+                    player,
+                    Resource::Megacredits,
+                    3 * buildings.saturating_sub(influence),
+                )
+            }
+            GlobalEvent::Riots => lose(
+                player,
+                Resource::Megacredits,
+                4 * capped(cities).saturating_sub(influence),
+            ),
+            GlobalEvent::Sabotage => {
+                let production: BTreeMap<Resource, isize> = [Resource::Energy, Resource::Steel]
+                    .iter()
+                    .filter(|resource| player.production[**resource] > 0)
+                    .map(|resource| (*resource, -1))
+                    .collect();
+                let mut operations = vec![];
+                if !production.is_empty() {
+                    operations.push(GameOperation::ChangeProduction(player_id, production));
+                }
+                operations.extend(gain(player_id, Resource::Steel, influence));
+                operations
+            }
+            GlobalEvent::EcoSabotage => {
+                let kept = 3 + influence;
+                let plants = player.resources[Resource::Plants];
+                lose(player, Resource::Plants, plants.saturating_sub(kept))
+            }
+            GlobalEvent::InterplanetaryTrade => gain(
+                player_id,
+                Resource::Megacredits,
+                2 * (capped(player.active_tag_count(CardTag::Space)) + influence),
+            ),
+            GlobalEvent::StrongSociety => gain(
+                player_id,
+                Resource::Megacredits,
+                2 * (capped(cities) + influence),
+            ),
+            GlobalEvent::SuccessfulOrganisms => {
+                let plant_production = player.production[Resource::Plants].max(0) as usize;
+                gain(
+                    player_id,
+                    Resource::Plants,
+                    capped(plant_production) + influence,
+                )
+            }
+            GlobalEvent::ScientificCommunity => gain(
+                player_id,
+                Resource::Megacredits,
+                player.cards_in_hand.len() + influence,
+            ),
+        }
+    }
+}
+
+fn gain(player_id: PlayerId, resource: Resource, amount: usize) -> Vec<GameOperation> {
+    if amount == 0 {
+        return vec![];
+    }
+    vec![GameOperation::ChangeResources(
+        player_id,
+        btreemap! { resource => amount as isize },
+    )]
+}
+
+// Players never lose more than they have.
+fn lose(player: &PlayerState, resource: Resource, amount: usize) -> Vec<GameOperation> {
+    let amount = amount.min(player.resources[resource]);
+    if amount == 0 {
+        return vec![];
+    }
+    vec![GameOperation::ChangeResources(
+        player.player_id,
+        btreemap! { resource => -(amount as isize) },
+    )]
+}
+
+/// The political state of Mars.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turmoil {
+    pub parties: BTreeMap<Party, PartyState>,
+    pub ruling_party: Party,
+    pub dominant_party: Party,
+    pub chairman: Delegate,
+
+    // players whose free delegate for this generation is still in the lobby
+    pub lobby: BTreeSet<PlayerId>,
+    // delegates each player has left to send, not counting the one in the lobby
+    pub reserves: BTreeMap<PlayerId, usize>,
+
+    // the events two generations, one generation, and no generations away
+    pub distant_event: Option<GlobalEvent>,
+    pub coming_event: Option<GlobalEvent>,
+    pub current_event: Option<GlobalEvent>,
+    pub event_deck: Vec<GlobalEvent>, // drawn from the back
+}
+
+impl Turmoil {
+    /// Turmoil as the game starts: the Greens rule with a neutral chairman, and every
+    /// player has a delegate in the lobby. No events have been revealed yet.
+    pub fn new(player_ids: impl IntoIterator<Item = PlayerId>, rng: &mut GameRng) -> Self {
+        let player_ids: Vec<PlayerId> = player_ids.into_iter().collect();
+        let mut event_deck = GlobalEvent::ALL.to_vec();
+        event_deck.shuffle(rng);
+
+        Self {
+            parties: Party::ALL
+                .iter()
+                .map(|party| (*party, PartyState::default()))
+                .collect(),
+            ruling_party: Party::Greens,
+            dominant_party: Party::Greens,
+            chairman: Delegate::Neutral,
+            lobby: player_ids.iter().copied().collect(),
+            reserves: player_ids
+                .iter()
+                .map(|player_id| (*player_id, DELEGATES_PER_PLAYER - 1))
+                .collect(),
+            distant_event: None,
+            coming_event: None,
+            current_event: None,
+            event_deck,
+        }
+    }
+
+    pub fn delegate_count(&self, party: Party) -> usize {
+        self.parties[&party].delegates.len()
+    }
+
+    pub fn has_delegate_available(&self, player_id: PlayerId) -> bool {
+        self.lobby.contains(&player_id) || self.reserves.get(&player_id).copied().unwrap_or(0) > 0
+    }
+
+    /// The player's influence, which softens the global events: 1 for being the chairman,
+    /// 1 for leading the dominant party, and 1 for any other delegate in it.
+    pub fn influence(&self, player_id: PlayerId) -> usize {
+        let delegate = Delegate::Player(player_id);
+        let dominant = &self.parties[&self.dominant_party];
+        let leads = dominant.leader == Some(delegate);
+        let others_in_dominant = dominant.delegate_count(delegate) - usize::from(leads);
+
+        usize::from(self.chairman == delegate)
+            + usize::from(leads)
+            + usize::from(others_in_dominant > 0)
+    }
+
+    // A player's delegate comes from the lobby if it is still there, or from the reserve.
+    fn place(&mut self, delegate: Delegate, party: Party) {
+        if let Delegate::Player(player_id) = delegate {
+            if !self.lobby.remove(&player_id) {
+                let reserve = self.reserves.get_mut(&player_id).unwrap();
+                *reserve -= 1;
+            }
+        }
+
+        self.parties.get_mut(&party).unwrap().add(delegate);
+        if self.delegate_count(party) > self.delegate_count(self.dominant_party) {
+            self.dominant_party = party;
+        }
+    }
+
+    fn return_to_reserve(&mut self, delegate: Delegate) {
+        if let Delegate::Player(player_id) = delegate {
+            *self.reserves.entry(player_id).or_insert(0) += 1;
+        }
+    }
+
+    // The party with the most delegates; ties go to the first one after the previously
+    // dominant party, in the order of `Party::ALL`.
+    fn find_dominant_party(&self) -> Party {
+        let start = Party::ALL
+            .iter()
+            .position(|party| *party == self.dominant_party)
+            .unwrap();
+        (1..=Party::ALL.len())
+            .map(|offset| Party::ALL[(start + offset) % Party::ALL.len()])
+            .fold(self.dominant_party, |best, party| {
+                if self.delegate_count(party) > self.delegate_count(best) {
+                    party
+                } else {
+                    best
+                }
+            })
+    }
+}
+
+impl GameState {
+    /// Bring Turmoil into the game, revealing the first global events. Part of setting up
+    /// the game, so it must be done exactly once, before the first research phase.
+    pub fn setup_turmoil(&mut self) -> Vec<GameEvent> {
+        self.turmoil = Some(Turmoil::new(self.players.keys().copied(), &mut self.rng));
+
+        let mut events = self.reveal_next_event();
+        events.extend(self.reveal_next_event());
+        events
+    }
+
+    /// The operations that send one of the player's delegates to the party:
+    /// the one in the lobby for free, or one from the reserve for `DELEGATE_COST`.
+    pub fn delegate_placement_operations(
+        &self,
+        player_id: PlayerId,
+        party: Party,
+    ) -> Result<Vec<GameOperation>, IllegalOperation> {
+        let turmoil = self
+            .turmoil
+            .as_ref()
+            .ok_or(IllegalOperation::TurmoilNotInPlay)?;
+        let player = self
+            .players
+            .get(&player_id)
+            .ok_or(IllegalOperation::UnknownPlayer(player_id))?;
+
+        let mut operations = vec![];
+        if !turmoil.lobby.contains(&player_id) {
+            if !turmoil.has_delegate_available(player_id) {
+                return Err(IllegalOperation::NoDelegatesLeft);
+            }
+            if player.resources[Resource::Megacredits] < DELEGATE_COST {
+                return Err(IllegalOperation::InsufficientResource(
+                    Resource::Megacredits,
+                ));
+            }
+            operations.push(GameOperation::ChangeResources(
+                player_id,
+                btreemap! { Resource::Megacredits => -(DELEGATE_COST as isize) },
+            ));
+        }
+        operations.push(GameOperation::PlaceDelegate(
+            Delegate::Player(player_id),
+            party,
+        ));
+        Ok(operations)
+    }
+
+    /// The operations of the player's action, with the ruling party's policy applied.
+    pub(crate) fn with_ruling_policy(
+        &self,
+        player_id: PlayerId,
+        operations: Vec<GameOperation>,
+    ) -> Vec<GameOperation> {
+        match &self.turmoil {
+            Some(turmoil) => turmoil.ruling_party.apply_policy(player_id, operations),
+            None => operations,
+        }
+    }
+
+    pub(crate) fn check_delegate_placement(
+        &self,
+        delegate: Delegate,
+    ) -> Result<(), IllegalOperation> {
+        let turmoil = self
+            .turmoil
+            .as_ref()
+            .ok_or(IllegalOperation::TurmoilNotInPlay)?;
+        if let Delegate::Player(player_id) = delegate {
+            if !self.players.contains_key(&player_id) {
+                return Err(IllegalOperation::UnknownPlayer(player_id));
+            }
+            if !turmoil.has_delegate_available(player_id) {
+                return Err(IllegalOperation::NoDelegatesLeft);
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply_delegate_placement(
+        &mut self,
+        delegate: Delegate,
+        party: Party,
+    ) -> Vec<GameEvent> {
+        let turmoil = self.turmoil.as_mut().expect("Turmoil is not in play.");
+        turmoil.place(delegate, party);
+        vec![GameEvent::DelegatePlaced(delegate, party)]
+    }
+
+    /// The end of a generation with Turmoil, after production: every player loses 1 TR,
+    /// the current global event hits, the dominant party takes power, and the next global
+    /// event is revealed.
+    #[must_use]
+    pub(crate) fn turmoil_phase(&mut self) -> Vec<GameEvent> {
+        if self.turmoil.is_none() {
+            return vec![];
+        }
+
+        let mut operations = vec![];
+        for (player_id, player) in &self.players {
+            if player.terraform_rating > 0 {
+                operations.push(GameOperation::LowerTerraformRating(*player_id, 1));
+            }
+        }
+        let mut events = self.execute_all(operations);
+
+        events.extend(self.resolve_global_event());
+        events.extend(self.change_government());
+        events.extend(self.reveal_next_event());
+
+        let turmoil = self.turmoil.as_mut().unwrap();
+        for (player_id, reserve) in turmoil.reserves.iter_mut() {
+            if *reserve > 0 && turmoil.lobby.insert(*player_id) {
+                *reserve -= 1;
+            }
+        }
+        events
+    }
+
+    fn resolve_global_event(&mut self) -> Vec<GameEvent> {
+        let turmoil = self.turmoil.as_mut().unwrap();
+        turmoil.current_event = turmoil.coming_event.take();
+        let global_event = match turmoil.current_event {
+            Some(global_event) => global_event,
+            None => return vec![],
+        };
+
+        let mut events = self.execute_all(vec![GameOperation::PlaceDelegate(
+            Delegate::Neutral,
+            global_event.current_party(),
+        )]);
+        events.push(GameEvent::GlobalEventResolved(global_event));
+
+        let turmoil = self.turmoil.as_ref().unwrap();
+        let operations: Vec<GameOperation> = self
+            .players
+            .keys()
+            .flat_map(|player_id| {
+                global_event.effect(self, *player_id, turmoil.influence(*player_id))
+            })
+            .collect();
+        events.extend(self.execute_all(operations));
+        events
+    }
+
+    // The dominant party's leader becomes the chairman, and its other delegates go back
+    // to their reserves, as does the previous chairman.
+    fn change_government(&mut self) -> Vec<GameEvent> {
+        let turmoil = self.turmoil.as_mut().unwrap();
+        let ruling_party = turmoil.dominant_party;
+        let party = std::mem::take(turmoil.parties.get_mut(&ruling_party).unwrap());
+
+        let previous_chairman = turmoil.chairman;
+        turmoil.return_to_reserve(previous_chairman);
+        let chairman = party.leader.unwrap_or(Delegate::Neutral);
+        let mut returning = party.delegates;
+        if let Some(index) = returning.iter().position(|d| *d == chairman) {
+            returning.remove(index);
+        }
+        for delegate in returning {
+            turmoil.return_to_reserve(delegate);
+        }
+
+        turmoil.ruling_party = ruling_party;
+        turmoil.chairman = chairman;
+        turmoil.dominant_party = turmoil.find_dominant_party();
+
+        let mut events = vec![GameEvent::NewGovernment(ruling_party, chairman)];
+        if let Delegate::Player(player_id) = chairman {
+            events
+                .extend(self.execute_all(vec![GameOperation::RaiseTerraformRating(player_id, 1)]));
+        }
+
+        // The bonus sees the terraform rating the new chairman already gained.
+        let operations: Vec<GameOperation> = self
+            .players
+            .keys()
+            .flat_map(|player_id| ruling_party.ruling_bonus(self, *player_id))
+            .collect();
+        events.extend(self.execute_all(operations));
+        events
+    }
+
+    // The coming event moves up, and the next one is drawn to be the distant event.
+    fn reveal_next_event(&mut self) -> Vec<GameEvent> {
+        let turmoil = self.turmoil.as_mut().unwrap();
+        turmoil.coming_event = turmoil.distant_event.take();
+        turmoil.distant_event = turmoil.event_deck.pop();
+
+        match turmoil.distant_event {
+            Some(global_event) => self.execute_all(vec![GameOperation::PlaceDelegate(
+                Delegate::Neutral,
+                global_event.revealed_party(),
+            )]),
+            None => vec![],
+        }
+    }
+
+    // For operations the rules carry out on their own, which are legal by construction.
+    fn execute_all(&mut self, operations: Vec<GameOperation>) -> Vec<GameEvent> {
+        operations
+            .into_iter()
+            .flat_map(|operation| {
+                debug_assert_eq!(Ok(()), self.check_operation(&operation));
+                self.execute_operation(operation)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::Coordinates,
+        event::GameEvent,
+        game::{
+            GameOperation, GameState, IllegalOperation, PlayerId, PlayerStateBuilder,
+            StandardProject, TurnAction,
+        },
+        resource::Resource,
+        test_utils::make_game,
+        turmoil::{Delegate, GlobalEvent, Party, DELEGATES_PER_PLAYER, DELEGATE_COST},
+    };
+
+    fn turmoil_game() -> GameState {
+        let mut game = make_game(vec![
+            PlayerStateBuilder::new(1)
+                .with_resources(20, 0, 0, 0, 0, 0)
+                .build(),
+            PlayerStateBuilder::new(2).build(),
+        ]);
+        game.setup_turmoil();
+        game
+    }
+
+    #[test]
+    fn setup_reveals_two_events_and_seats_their_delegates() {
+        let game = turmoil_game();
+        let turmoil = game.turmoil.as_ref().unwrap();
+
+        let coming = turmoil.coming_event.unwrap();
+        let distant = turmoil.distant_event.unwrap();
+        assert_ne!(coming, distant);
+        assert_eq!(GlobalEvent::ALL.len() - 2, turmoil.event_deck.len());
+
+        let neutral_delegates: usize = turmoil
+            .parties
+            .values()
+            .map(|party| party.delegate_count(Delegate::Neutral))
+            .sum();
+        assert_eq!(2, neutral_delegates);
+        assert_eq!(Delegate::Neutral, turmoil.chairman);
+        assert_eq!(Party::Greens, turmoil.ruling_party);
+        assert_eq!(2, turmoil.lobby.len());
+    }
+
+    #[test]
+    fn delegates_come_from_the_lobby_then_cost_megacredits() {
+        let mut game = turmoil_game();
+        let p1 = PlayerId::from(1);
+        let send = TurnAction::PlaceDelegate(Party::Scientists);
+
+        // Without the neutral delegates of the revealed events, the first delegate placed
+        // makes its party dominant.
+        for party in game.turmoil.as_mut().unwrap().parties.values_mut() {
+            *party = Default::default();
+        }
+
+        game.play_turn_action(p1, &send).unwrap();
+        assert_eq!(20, game.players[&p1].resources[Resource::Megacredits]);
+        let events = game.play_turn_action(p1, &send).unwrap();
+        assert_eq!(
+            20 - DELEGATE_COST,
+            game.players[&p1].resources[Resource::Megacredits]
+        );
+        assert!(events.contains(&GameEvent::DelegatePlaced(
+            Delegate::Player(p1),
+            Party::Scientists
+        )));
+
+        let turmoil = game.turmoil.as_ref().unwrap();
+        let scientists = &turmoil.parties[&Party::Scientists];
+        assert_eq!(2, scientists.delegate_count(Delegate::Player(p1)));
+        assert_eq!(Some(Delegate::Player(p1)), scientists.leader);
+        assert_eq!(DELEGATES_PER_PLAYER - 2, turmoil.reserves[&p1]);
+        assert_eq!(Party::Scientists, turmoil.dominant_party);
+        assert_eq!(2, turmoil.influence(p1));
+        assert_eq!(0, turmoil.influence(PlayerId::from(2)));
+    }
+
+    #[test]
+    fn delegates_cannot_be_sent_without_turmoil() {
+        let game = make_game(vec![PlayerStateBuilder::new(1).build()]);
+        assert_eq!(
+            Err(IllegalOperation::TurmoilNotInPlay),
+            game.turn_action_operations(PlayerId::from(1), &TurnAction::PlaceDelegate(Party::Reds))
+        );
+    }
+
+    #[test]
+    fn the_dominant_party_takes_power_at_the_end_of_the_generation() {
+        let mut game = turmoil_game();
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        for _ in 0..3 {
+            game.play_turn_action(p1, &TurnAction::PlaceDelegate(Party::Reds))
+                .unwrap();
+        }
+        let coming = game.turmoil.as_ref().unwrap().coming_event.unwrap();
+        let tr_before = (
+            game.players[&p1].terraform_rating,
+            game.players[&p2].terraform_rating,
+        );
+
+        let events = game.advance_generation();
+        let turmoil = game.turmoil.as_ref().unwrap();
+        assert!(events.contains(&GameEvent::GlobalEventResolved(coming)));
+        assert_eq!(Some(coming), turmoil.current_event);
+        assert!(events.contains(&GameEvent::NewGovernment(Party::Reds, Delegate::Player(p1))));
+        assert_eq!(Party::Reds, turmoil.ruling_party);
+        assert_eq!(Delegate::Player(p1), turmoil.chairman);
+
+        // Everyone loses 1 TR; the chairman gains 1, and the Reds' bonus gives the
+        // player with the lowest TR another.
+        assert_eq!(tr_before.0, game.players[&p1].terraform_rating);
+        assert_eq!(tr_before.1, game.players[&p2].terraform_rating);
+
+        // The chairman's party mates went back to the reserve, and the lobby was refilled.
+        assert_eq!(DELEGATES_PER_PLAYER - 2, turmoil.reserves[&p1]);
+        assert!(turmoil.lobby.contains(&p1));
+        assert_eq!(0, turmoil.parties[&Party::Reds].delegates.len());
+    }
+
+    #[test]
+    fn the_reds_charge_for_raising_the_terraform_rating() {
+        let mut game = turmoil_game();
+        let p1 = PlayerId::from(1);
+        game.turmoil.as_mut().unwrap().ruling_party = Party::Reds;
+        game.players.get_mut(&p1).unwrap().resources[Resource::Heat] = 8;
+
+        let operations = game
+            .turn_action_operations(p1, &TurnAction::ConvertHeat(None))
+            .unwrap();
+        assert_eq!(
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -3 }),
+            operations[0]
+        );
+        game.play_turn_action(p1, &TurnAction::ConvertHeat(None))
+            .unwrap();
+        assert_eq!(17, game.players[&p1].resources[Resource::Megacredits]);
+
+        // With the charge paid, the greenery can't be, and neither is anything else.
+        game.players.get_mut(&p1).unwrap().resources[Resource::Megacredits] = 23;
+        let before = game.clone();
+        let greenery = StandardProject::Greenery(Coordinates::new(3, 0));
+        assert_eq!(
+            Err(IllegalOperation::InsufficientResource(Resource::Megacredits)),
+            game.play_standard_project(p1, &greenery)
+        );
+        assert_eq!(before, game);
+    }
+
+    #[test]
+    fn influence_softens_global_events() {
+        let mut game = turmoil_game();
+        let p1 = PlayerId::from(1);
+        game.players.get_mut(&p1).unwrap().resources[Resource::Plants] = 10;
+
+        let without_influence = GlobalEvent::EcoSabotage.effect(&game, p1, 0);
+        let with_influence = GlobalEvent::EcoSabotage.effect(&game, p1, 2);
+        assert_eq!(
+            vec![GameOperation::ChangeResources(
+                p1,
+                btreemap! { Resource::Plants => -7 }
+            )],
+            without_influence
+        );
+        assert_eq!(
+            vec![GameOperation::ChangeResources(
+                p1,
+                btreemap! { Resource::Plants => -5 }
+            )],
+            with_influence
+        );
+    }
+}
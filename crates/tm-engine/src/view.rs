@@ -0,0 +1,157 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::MarsBoard,
+    card::{Card, CardTag},
+    card_ref,
+    game::{ActiveTags, GameState, PlayerId, PlayerState},
+    resource::{CardResource, ResourceMap},
+    timeline::ParameterTimeline,
+};
+
+/// What one participant of a game is allowed to see of it:
+/// everything except the draw deck, the discard pile, and other players' hands.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameView {
+    pub viewer: Option<PlayerId>, // `None` for spectators
+    pub board: MarsBoard,
+    pub generation: usize,
+    pub parameter_timeline: ParameterTimeline,
+    pub draw_deck_size: usize,
+    pub discard_pile_size: usize,
+    pub players: Vec<PlayerView>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerView {
+    #[serde(flatten)]
+    pub summary: PublicSummary,
+
+    // Only shown to the player holding them; everyone else sees the summary's hand size.
+    #[serde(with = "card_ref::cards")]
+    pub cards_in_hand: Vec<Card>,
+}
+
+/// Everything about a player that their opponents can see, e.g. for an opponent panel,
+/// or for modeling what the opponent might do next. Of the hand, only its size is known.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicSummary {
+    pub player_id: PlayerId,
+    pub resources: ResourceMap<usize>,
+    pub production: ResourceMap<isize>,
+    pub terraform_rating: usize,
+    #[serde(with = "card_ref::cards")]
+    pub played_cards: Vec<Card>,
+    pub tags: BTreeMap<CardTag, usize>, // of the played cards that aren't events
+    pub event_count: usize,
+    #[serde(with = "card_ref::card_resources")]
+    pub card_resources: BTreeMap<(Card, CardResource), usize>,
+    #[serde(with = "card_ref::card_set")]
+    pub tapped_active_cards: HashSet<Card>,
+    pub hand_size: usize,
+}
+
+impl GameView {
+    pub fn new(game: &GameState, viewer: Option<PlayerId>) -> Self {
+        Self {
+            viewer,
+            board: game.board.clone(),
+            generation: game.generation,
+            parameter_timeline: game.parameter_timeline.clone(),
+            draw_deck_size: game.draw_deck.len(),
+            discard_pile_size: game.discard_pile.len(),
+            players: game
+                .players
+                .values()
+                .map(|player| PlayerView::new(player, viewer == Some(player.player_id)))
+                .collect(),
+        }
+    }
+}
+
+impl PlayerView {
+    fn new(player: &PlayerState, show_hand: bool) -> Self {
+        Self {
+            summary: player.public_summary(),
+            cards_in_hand: if show_hand {
+                player.cards_in_hand.clone()
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+impl PlayerState {
+    pub fn public_summary(&self) -> PublicSummary {
+        let mut tags = BTreeMap::new();
+        for tag in self.get_non_event_tags() {
+            *tags.entry(tag).or_insert(0) += 1;
+        }
+
+        PublicSummary {
+            player_id: self.player_id,
+            resources: self.resources,
+            production: self.production,
+            terraform_rating: self.terraform_rating,
+            played_cards: self.played_cards.clone(),
+            tags,
+            event_count: self.event_count(),
+            card_resources: self.card_resources.clone(),
+            tapped_active_cards: self.tapped_active_cards.clone(),
+            hand_size: self.cards_in_hand.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{card::CardTag, game::PlayerId, position::import_position, view::GameView};
+
+    #[test]
+    fn summaries_count_the_tags_of_played_cards_but_not_events() {
+        let game = import_position(
+            "player p1\nplayed Solar Power; Research; Asteroid\nhand Predators",
+        )
+        .unwrap();
+        let summary = game.players[&PlayerId::from(1)].public_summary();
+
+        let expected_tags = btreemap! {
+            CardTag::Building => 1,
+            CardTag::Power => 1,
+            CardTag::Science => 2,
+        };
+        assert_eq!(expected_tags, summary.tags);
+        assert_eq!(1, summary.event_count);
+        assert_eq!(1, summary.hand_size);
+        assert_eq!(3, summary.played_cards.len());
+    }
+
+    #[test]
+    fn hands_are_only_visible_to_their_holder() {
+        let game = import_position(
+            "player p1\nhand Solar Power; Predators\nplayer p2\nhand GHG Factories",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+
+        let seat_view = GameView::new(&game, Some(p1));
+        assert_eq!(2, seat_view.players[0].cards_in_hand.len());
+        assert!(seat_view.players[1].cards_in_hand.is_empty());
+        assert_eq!(1, seat_view.players[1].summary.hand_size);
+
+        let spectator_view = GameView::new(&game, None);
+        assert!(spectator_view
+            .players
+            .iter()
+            .all(|player| player.cards_in_hand.is_empty()));
+        assert_eq!(game.draw_deck.len(), spectator_view.draw_deck_size);
+
+        let json = serde_json::to_string(&spectator_view).unwrap();
+        assert!(!json.contains("Solar Power"));
+    }
+}
@@ -0,0 +1,103 @@
+//! Looking up the tiles around every space of a mid-game board, with the board's neighbor
+//! iterator against the boxed iterator chains over cloned statuses it replaced, and the
+//! scoring and placement-validation workloads built on top of them.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use tm_engine::{
+    board::{Coordinates, MarsBoard, TileLocation, TileStatus},
+    card::LocationRestriction,
+    game::PlayerId,
+    scoring::score_all_players,
+    test_utils::mid_game_scenario,
+};
+
+// clockwise neighbors, starting from the right neighbor
+const NEIGHBORS_DX_DY: [(isize, isize); 6] = [(1, -1), (0, -1), (-1, 0), (-1, 1), (0, 1), (1, 0)];
+
+fn chained_neighbor_tile_status<'a>(
+    board: &'a MarsBoard,
+    location: Option<(isize, isize)>,
+) -> Box<dyn Iterator<Item = TileStatus> + 'a> {
+    let neighbors = location.map(|(x, y)| {
+        NEIGHBORS_DX_DY
+            .iter()
+            .map(move |(dx, dy)| Coordinates::new(x + dx, y + dy))
+            .filter(move |neighbor| board.is_in_bounds(*neighbor))
+            .map(TileLocation::OnMars)
+    });
+    Box::new(
+        neighbors
+            .into_iter()
+            .flatten()
+            .map(move |neighbor| board.get_tile_status(&neighbor)),
+    )
+}
+
+fn owned_neighbors(statuses: impl Iterator<Item = TileStatus>) -> usize {
+    statuses.filter(|status| status.owner().is_some()).count()
+}
+
+fn neighbor_tile_status(c: &mut Criterion) {
+    let board = mid_game_scenario().board;
+    let bounds = board.bounds;
+    let spaces: Vec<(isize, isize)> = (bounds.min_x..=bounds.max_x)
+        .flat_map(|x| (bounds.min_y..=bounds.max_y).map(move |y| (x, y)))
+        .filter(|(x, y)| board.is_in_bounds(Coordinates::new(*x, *y)))
+        .collect();
+    let locations: Vec<TileLocation> = spaces
+        .iter()
+        .map(|(x, y)| TileLocation::OnMars(Coordinates::new(*x, *y)))
+        .collect();
+
+    let mut group = c.benchmark_group("neighbor_tile_status");
+    group.bench_function("neighbors_iterator", |b| {
+        b.iter(|| {
+            locations
+                .iter()
+                .map(|location| {
+                    owned_neighbors(board.get_neighbor_tile_status(black_box(location)))
+                })
+                .sum::<usize>()
+        })
+    });
+    group.bench_function("iterator_chains", |b| {
+        b.iter(|| {
+            spaces
+                .iter()
+                .map(|space| {
+                    owned_neighbors(chained_neighbor_tile_status(
+                        &board,
+                        black_box(Some(*space)),
+                    ))
+                })
+                .sum::<usize>()
+        })
+    });
+    group.finish();
+}
+
+fn workloads(c: &mut Criterion) {
+    let game = mid_game_scenario();
+    let player = &game.players[&PlayerId::from(1)];
+
+    let mut group = c.benchmark_group("neighbor_workloads");
+    group.bench_function("vp_scoring", |b| {
+        b.iter(|| score_all_players(black_box(&game)))
+    });
+    group.bench_function("placement_validation", |b| {
+        b.iter(|| {
+            game.board.has_legal_placement(
+                black_box(player),
+                &[
+                    LocationRestriction::LandTile,
+                    LocationRestriction::NextToAtLeastTwoCities,
+                ],
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, neighbor_tile_status, workloads);
+criterion_main!(benches);
@@ -0,0 +1,127 @@
+//! Enumerating every play of a generation, with the buffers reused across the card purchases
+//! considered, against the same enumeration allocating fresh vectors at every step of the
+//! recursion, as it did before the buffers were introduced.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use tm_sim::{
+    board::{make_base_game_board, MarsBoard},
+    card::{Card, BASE_GAME_CARDS_BY_NAME},
+    game::{PlayerState, PlayerStateBuilder, TurnAction},
+    sim::get_possible_generation_plays,
+};
+
+type Play = (Vec<Card>, Vec<TurnAction>, PlayerState);
+
+fn possible_plays_with_fresh_allocations(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    offered_cards: &[Card],
+) -> Vec<Play> {
+    let mut result = vec![];
+    for variation in 0..(1usize << offered_cards.len()) {
+        let mut current_state = initial_state.clone();
+        let mut purchased_cards: Vec<&Card> = Vec::new();
+        for (i, card) in offered_cards.iter().enumerate() {
+            if variation & (1usize << i) != 0 {
+                purchased_cards.push(card);
+            }
+        }
+
+        if current_state.purchase_cards(&purchased_cards).is_some() {
+            for (actions, final_state) in plays_from(0, board, &current_state) {
+                let bought = purchased_cards.iter().copied().cloned().collect();
+                result.push((bought, actions, final_state));
+            }
+        }
+    }
+    result
+}
+
+fn plays_from(
+    next_card_index_to_consider: usize,
+    board: &MarsBoard,
+    state: &PlayerState,
+) -> Vec<(Vec<TurnAction>, PlayerState)> {
+    match state.cards_in_hand.get(next_card_index_to_consider) {
+        None => {
+            let mut final_state = state.clone();
+            let mut actions = vec![];
+            for _ in 0..final_state.heat_conversions_available(board) {
+                final_state.convert_heat();
+                actions.push(TurnAction::ConvertHeat(None));
+            }
+            vec![(actions, final_state)]
+        }
+        Some(card) => {
+            let mut next_state = state.clone();
+            if next_state.play_card(board, card).is_some() {
+                plays_from(next_card_index_to_consider, board, &next_state)
+                    .into_iter()
+                    .map(|(actions, final_state)| {
+                        let mut all_actions = vec![TurnAction::PlayCard(card.clone(), vec![])];
+                        all_actions.extend(actions);
+                        (all_actions, final_state)
+                    })
+                    .collect()
+            } else {
+                plays_from(next_card_index_to_consider + 1, board, state)
+            }
+        }
+    }
+}
+
+fn possible_plays(c: &mut Criterion) {
+    let board = make_base_game_board();
+    let player_state = PlayerStateBuilder::new(1)
+        .with_resources(60, 0, 0, 0, 0, 16)
+        .build();
+    let opponent_state = PlayerStateBuilder::new(2).build();
+    let offered_cards: Vec<Card> = [
+        "Solar Power",
+        "Fueled Generators",
+        "GHG Factories",
+        "Nuclear Power",
+        "Geothermal Power",
+        "Windmills",
+        "Power Plant",
+        "Heat Trappers",
+    ]
+    .iter()
+    .map(|name| BASE_GAME_CARDS_BY_NAME[name].clone())
+    .collect();
+
+    let reused = get_possible_generation_plays(
+        &board,
+        &player_state,
+        &vec![&opponent_state],
+        offered_cards.clone(),
+    );
+    let fresh = possible_plays_with_fresh_allocations(&board, &player_state, &offered_cards);
+    assert_eq!(reused.len(), fresh.len());
+
+    let mut group = c.benchmark_group("possible_generation_plays");
+    group.bench_function("reused_buffers", |b| {
+        b.iter(|| {
+            get_possible_generation_plays(
+                black_box(&board),
+                black_box(&player_state),
+                &vec![&opponent_state],
+                offered_cards.clone(),
+            )
+        })
+    });
+    group.bench_function("fresh_allocations", |b| {
+        b.iter(|| {
+            possible_plays_with_fresh_allocations(
+                black_box(&board),
+                black_box(&player_state),
+                &offered_cards,
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, possible_plays);
+criterion_main!(benches);
@@ -0,0 +1,330 @@
+//! The action space of a turn, as a fixed list of numbered slots, so that learned policies and
+//! remote agents can pick an action by its index.
+//!
+//! Every card of the registry can be played with one of three payment buckets, and every
+//! placement goes on one of the spaces of Mars, numbered in reading order. The layout only
+//! depends on the card names, the spaces of the base board, and the awards and parties,
+//! and is the same for every game: most slots are illegal at any given time.
+//!
+//! The layout is versioned. Any change to it, e.g. a new card or a new kind of action,
+//! changes its fingerprint, and must come with a new `ACTION_SPACE_VERSION`.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::Award,
+    board::{make_base_game_board, Coordinates, TileLocation},
+    game::{PlayerTurn, StandardProject, TurnAction},
+    registry::CardRegistry,
+    turmoil::Party,
+};
+
+/// Bumped whenever the slots, or their order, change.
+pub const ACTION_SPACE_VERSION: u32 = 1;
+
+/// The index of passing, in every version.
+pub const PASS_INDEX: usize = 0;
+
+/// How a card is paid for: with megacredits alone, or with as much steel or titanium
+/// as is worth spending on it, and megacredits for the rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PaymentBucket {
+    Megacredits,
+    Steel,
+    Titanium,
+}
+
+impl PaymentBucket {
+    pub const ALL: [PaymentBucket; 3] = [
+        PaymentBucket::Megacredits,
+        PaymentBucket::Steel,
+        PaymentBucket::Titanium,
+    ];
+}
+
+/// One slot of the action space. Cards are named, and spaces given by their number.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionSlot {
+    Pass,
+    PlayCard(String, PaymentBucket),
+    PerformAction(String, usize), // the card, and which of its actions
+    SellPatent(String),           // a single card
+    PowerPlant,
+    Asteroid(Option<usize>), // where the ocean goes if the temperature reaches 0 C
+    Aquifer(usize),
+    Greenery(usize),
+    City(usize),
+    ConvertPlants(usize),
+    ConvertHeat(Option<usize>),
+    ClaimMilestone,
+    FundAward(Award),
+    PlaceDelegate(Party),
+}
+
+pub struct ActionSpace {
+    slots: Vec<ActionSlot>,
+    indices: HashMap<ActionSlot, usize>,
+    spaces: Vec<Coordinates>, // of Mars, in reading order
+}
+
+impl Default for ActionSpace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActionSpace {
+    pub fn new() -> Self {
+        let board = make_base_game_board();
+        let mut spaces: Vec<(Coordinates, bool)> = board
+            .spaces
+            .values()
+            .filter_map(|space| match space.location {
+                TileLocation::OnMars(coordinates) => {
+                    Some((coordinates, space.is_reserved_for_ocean()))
+                }
+                TileLocation::OffMars(_) => None,
+            })
+            .collect();
+        spaces.sort_by_key(|(coordinates, _)| (coordinates.get_z(), *coordinates));
+        let ocean_spaces: Vec<usize> = (0..spaces.len()).filter(|i| spaces[*i].1).collect();
+        let land_spaces: Vec<usize> = (0..spaces.len()).filter(|i| !spaces[*i].1).collect();
+
+        let cards = CardRegistry::global().cards();
+        let mut slots = vec![ActionSlot::Pass];
+        for card in cards {
+            for payment in PaymentBucket::ALL.iter() {
+                slots.push(ActionSlot::PlayCard(card.name.clone(), *payment));
+            }
+        }
+        for card in cards {
+            for index in 0..card.actions.len() {
+                slots.push(ActionSlot::PerformAction(card.name.clone(), index));
+            }
+        }
+        slots.extend(
+            cards
+                .iter()
+                .map(|card| ActionSlot::SellPatent(card.name.clone())),
+        );
+        slots.push(ActionSlot::PowerPlant);
+        slots.push(ActionSlot::Asteroid(None));
+        slots.extend(ocean_spaces.iter().map(|i| ActionSlot::Asteroid(Some(*i))));
+        slots.extend(ocean_spaces.iter().map(|i| ActionSlot::Aquifer(*i)));
+        slots.extend(land_spaces.iter().map(|i| ActionSlot::Greenery(*i)));
+        slots.extend(land_spaces.iter().map(|i| ActionSlot::City(*i)));
+        slots.extend(land_spaces.iter().map(|i| ActionSlot::ConvertPlants(*i)));
+        slots.push(ActionSlot::ConvertHeat(None));
+        slots.extend(
+            ocean_spaces
+                .iter()
+                .map(|i| ActionSlot::ConvertHeat(Some(*i))),
+        );
+        slots.push(ActionSlot::ClaimMilestone);
+        slots.extend(Award::ALL.iter().map(|award| ActionSlot::FundAward(*award)));
+        slots.extend(
+            Party::ALL
+                .iter()
+                .map(|party| ActionSlot::PlaceDelegate(*party)),
+        );
+
+        let indices = slots
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| (slot.clone(), index))
+            .collect();
+        Self {
+            slots,
+            indices,
+            spaces: spaces
+                .into_iter()
+                .map(|(coordinates, _)| coordinates)
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn slots(&self) -> &[ActionSlot] {
+        &self.slots
+    }
+
+    /// 64-bit FNV-1a over the slots' JSON, as hex: the same for the same layout,
+    /// across platforms and compiler versions.
+    pub fn fingerprint(&self) -> String {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let layout = serde_json::to_string(&self.slots).unwrap();
+        let hash = layout.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        format!("{:016x}", hash)
+    }
+
+    /// The index of a turn of a single action, or of passing. The payment bucket only
+    /// matters for playing cards. Turns of two actions, and actions the space does not
+    /// cover, e.g. selling several cards at once, have no index.
+    pub fn encode(&self, turn: &PlayerTurn, payment: PaymentBucket) -> Option<usize> {
+        let slot = match turn {
+            PlayerTurn::Pass => ActionSlot::Pass,
+            PlayerTurn::Play(_, Some(_)) => return None,
+            PlayerTurn::Play(action, None) => self.slot_of(action, payment)?,
+        };
+        self.indices.get(&slot).copied()
+    }
+
+    /// The turn at the index, and how to pay for it if it plays a card.
+    pub fn decode(&self, index: usize) -> Option<(PlayerTurn, PaymentBucket)> {
+        let registry = CardRegistry::global();
+        let card = |name: &str| registry.get_by_name(name).cloned();
+        let space = |index: usize| self.spaces.get(index).copied();
+        let project = TurnAction::PlayStandardProject;
+
+        let mut payment = PaymentBucket::Megacredits;
+        let action = match self.slots.get(index)? {
+            ActionSlot::Pass => return Some((PlayerTurn::Pass, payment)),
+            ActionSlot::PlayCard(name, bucket) => {
+                payment = *bucket;
+                // Slots don't say where a card's tiles go.
+                TurnAction::PlayCard(card(name)?, vec![])
+            }
+            ActionSlot::PerformAction(name, action) => {
+                TurnAction::PerformAction(card(name)?.actions.get(*action)?.clone())
+            }
+            ActionSlot::SellPatent(name) => {
+                project(StandardProject::SellPatents(vec![card(name)?]))
+            }
+            ActionSlot::PowerPlant => project(StandardProject::PowerPlant),
+            ActionSlot::Asteroid(None) => project(StandardProject::Asteroid(None)),
+            ActionSlot::Asteroid(Some(ocean)) => {
+                project(StandardProject::Asteroid(Some(space(*ocean)?)))
+            }
+            ActionSlot::Aquifer(ocean) => project(StandardProject::Aquifer(space(*ocean)?)),
+            ActionSlot::Greenery(land) => project(StandardProject::Greenery(space(*land)?)),
+            ActionSlot::City(land) => project(StandardProject::City(space(*land)?)),
+            ActionSlot::ConvertPlants(land) => TurnAction::ConvertPlants(space(*land)?),
+            ActionSlot::ConvertHeat(None) => TurnAction::ConvertHeat(None),
+            ActionSlot::ConvertHeat(Some(ocean)) => TurnAction::ConvertHeat(Some(space(*ocean)?)),
+            ActionSlot::ClaimMilestone => TurnAction::ClaimMilestone,
+            ActionSlot::FundAward(award) => TurnAction::FundAward(*award),
+            ActionSlot::PlaceDelegate(party) => TurnAction::PlaceDelegate(*party),
+        };
+        Some((PlayerTurn::Play(action, None), payment))
+    }
+
+    fn slot_of(&self, action: &TurnAction, payment: PaymentBucket) -> Option<ActionSlot> {
+        let space =
+            |coordinates: &Coordinates| self.spaces.iter().position(|space| space == coordinates);
+
+        Some(match action {
+            TurnAction::PlayCard(card, _) => ActionSlot::PlayCard(card.name.clone(), payment),
+            // Cards with the same action share the slot of the first of them.
+            TurnAction::PerformAction(card_action) => {
+                CardRegistry::global().cards().iter().find_map(|card| {
+                    let index = card.actions.iter().position(|other| other == card_action)?;
+                    Some(ActionSlot::PerformAction(card.name.clone(), index))
+                })?
+            }
+            TurnAction::PlayStandardProject(project) => match project {
+                StandardProject::SellPatents(cards) => match cards.as_slice() {
+                    [card] => ActionSlot::SellPatent(card.name.clone()),
+                    _ => return None,
+                },
+                StandardProject::PowerPlant => ActionSlot::PowerPlant,
+                StandardProject::Asteroid(None) => ActionSlot::Asteroid(None),
+                StandardProject::Asteroid(Some(ocean)) => ActionSlot::Asteroid(Some(space(ocean)?)),
+                StandardProject::Aquifer(ocean) => ActionSlot::Aquifer(space(ocean)?),
+                StandardProject::Greenery(land) => ActionSlot::Greenery(space(land)?),
+                StandardProject::City(land) => ActionSlot::City(space(land)?),
+            },
+            TurnAction::ConvertPlants(land) => ActionSlot::ConvertPlants(space(land)?),
+            TurnAction::ConvertHeat(None) => ActionSlot::ConvertHeat(None),
+            TurnAction::ConvertHeat(Some(ocean)) => ActionSlot::ConvertHeat(Some(space(ocean)?)),
+            TurnAction::ClaimMilestone => ActionSlot::ClaimMilestone,
+            TurnAction::FundAward(award) => ActionSlot::FundAward(*award),
+            TurnAction::PlaceDelegate(party) => ActionSlot::PlaceDelegate(*party),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        action_space::{ActionSlot, ActionSpace, PaymentBucket, ACTION_SPACE_VERSION, PASS_INDEX},
+        board::Coordinates,
+        card::CORPORATE_GAME_CARDS_BY_NAME,
+        game::{PlayerTurn, StandardProject, TurnAction},
+    };
+
+    #[test]
+    fn the_layout_only_changes_with_the_version() {
+        // If this fails, the layout changed: bump ACTION_SPACE_VERSION, then update the test.
+        let space = ActionSpace::new();
+        assert_eq!(
+            (1, 1071, "e1197970633d19b3"),
+            (
+                ACTION_SPACE_VERSION,
+                space.len(),
+                space.fingerprint().as_str()
+            )
+        );
+        assert_eq!(ActionSlot::Pass, space.slots()[PASS_INDEX]);
+        assert_eq!(ActionSlot::PowerPlant, space.slots()[873]);
+        assert_eq!(ActionSlot::ClaimMilestone, space.slots()[1059]);
+        assert_eq!(
+            ActionSlot::PlayCard("Asteroid Mining".to_string(), PaymentBucket::Titanium),
+            space.slots()[291]
+        );
+    }
+
+    #[test]
+    fn turns_are_encoded_and_decoded() {
+        let space = ActionSpace::new();
+        let mut shared = 0;
+        for index in 0..space.len() {
+            let (turn, payment) = space.decode(index).unwrap();
+            match space.encode(&turn, payment) {
+                Some(encoded) if encoded == index => {}
+                // Identical actions of different cards share the slot of the first card.
+                Some(encoded) => {
+                    assert!(matches!(
+                        space.slots()[index],
+                        ActionSlot::PerformAction(..)
+                    ));
+                    assert_eq!(space.decode(encoded).unwrap().0, turn);
+                    shared += 1;
+                }
+                None => panic!("{:?} has no index", turn),
+            }
+        }
+        assert!(shared < 10);
+        assert_eq!(None, space.decode(space.len()));
+
+        let asteroid_mining = CORPORATE_GAME_CARDS_BY_NAME["Asteroid Mining"].clone();
+        let play = PlayerTurn::Play(TurnAction::PlayCard(asteroid_mining, vec![]), None);
+        assert_eq!(Some(291), space.encode(&play, PaymentBucket::Titanium));
+        assert_eq!(
+            Some(PASS_INDEX),
+            space.encode(&PlayerTurn::Pass, PaymentBucket::Steel)
+        );
+
+        let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+        let two_actions = PlayerTurn::Play(power_plant.clone(), Some(power_plant));
+        assert_eq!(None, space.encode(&two_actions, PaymentBucket::Megacredits));
+        let off_the_board =
+            PlayerTurn::Play(TurnAction::ConvertPlants(Coordinates::new(99, 0)), None);
+        assert_eq!(
+            None,
+            space.encode(&off_the_board, PaymentBucket::Megacredits)
+        );
+    }
+}
@@ -0,0 +1,316 @@
+//! An index of a directory of recorded games, e.g. a large self-play dataset, so that games
+//! can be found by their players, corporations, cards played, and final scores
+//! without replaying every game for every question.
+//!
+//! Every `.json` file in the directory is read as a `GameRecord`. Files that do not parse
+//! or replay, or that were recorded under other rules or with other cards, are listed as
+//! skipped, rather than failing the whole index.
+
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    game::{GameState, PlayerId},
+    migrate::{replace_card_bodies, report_field_changes, upgrade_stamp, Migration},
+    scoring::score_all_players,
+    whatif::GameRecord,
+};
+
+const ARCHIVE_EXTENSION: &str = "json";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedPlayer {
+    pub player_id: PlayerId,
+    pub corporation: Option<String>,
+    pub cards_played: BTreeSet<String>,
+    pub final_score: isize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedGame {
+    pub path: PathBuf,
+    pub generation: usize,
+    pub players: Vec<IndexedPlayer>,
+}
+
+impl IndexedGame {
+    fn from_final_state(path: PathBuf, game: &GameState) -> Self {
+        let players = score_all_players(game)
+            .into_iter()
+            .map(|(player_id, score)| {
+                let player = &game.players[&player_id];
+                IndexedPlayer {
+                    player_id,
+                    corporation: player
+                        .corporation
+                        .as_ref()
+                        .map(|corporation| corporation.name.clone()),
+                    cards_played: player
+                        .played_cards
+                        .iter()
+                        .map(|card| card.name.clone())
+                        .collect(),
+                    final_score: score.total(),
+                }
+            })
+            .collect();
+        Self {
+            path,
+            generation: game.generation,
+            players,
+        }
+    }
+
+    /// The highest final score in the game.
+    pub fn winning_score(&self) -> Option<isize> {
+        self.players.iter().map(|player| player.final_score).max()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    pub games: Vec<IndexedGame>,
+    // the archives that could not be indexed, and why
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+/// Which games to find. A game matches if it has the given number of players,
+/// and a single one of its players meets all of the per-player criteria.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveQuery {
+    pub player_count: Option<usize>,
+
+    // per-player criteria
+    pub corporation: Option<String>,
+    pub card: Option<String>,
+    pub min_score: Option<isize>,
+    pub winner: bool, // the player had the game's highest score, possibly tied
+}
+
+impl ArchiveQuery {
+    pub fn matches(&self, game: &IndexedGame) -> bool {
+        if self
+            .player_count
+            .is_some_and(|count| count != game.players.len())
+        {
+            return false;
+        }
+
+        let winning_score = game.winning_score();
+        game.players.iter().any(|player| {
+            self.corporation
+                .as_ref()
+                .is_none_or(|name| player.corporation.as_ref() == Some(name))
+                && self
+                    .card
+                    .as_ref()
+                    .is_none_or(|name| player.cards_played.contains(name))
+                && self
+                    .min_score
+                    .is_none_or(|min_score| player.final_score >= min_score)
+                && (!self.winner || Some(player.final_score) == winning_score)
+        })
+    }
+}
+
+impl ArchiveIndex {
+    /// Index every archive in the directory, spreading the files over the given number
+    /// of threads. Games are listed in the order of their paths, whatever the threads.
+    pub fn build(directory: &Path, threads: usize) -> io::Result<Self> {
+        assert!(threads >= 1);
+
+        let paths = archive_paths(directory)?;
+
+        let mut results: Vec<Result<IndexedGame, String>> = vec![Err(String::new()); paths.len()];
+        let chunk_size = paths.len().div_ceil(threads).max(1);
+        std::thread::scope(|scope| {
+            for (chunk_paths, chunk_results) in
+                paths.chunks(chunk_size).zip(results.chunks_mut(chunk_size))
+            {
+                scope.spawn(move || {
+                    for (path, result) in chunk_paths.iter().zip(chunk_results) {
+                        *result = index_archive(path);
+                    }
+                });
+            }
+        });
+
+        let mut index = ArchiveIndex::default();
+        for (path, result) in paths.into_iter().zip(results) {
+            match result {
+                Ok(game) => index.games.push(game),
+                Err(reason) => index.skipped.push((path, reason)),
+            }
+        }
+        Ok(index)
+    }
+
+    pub fn query<'a>(&'a self, query: &'a ArchiveQuery) -> impl Iterator<Item = &'a IndexedGame> {
+        self.games.iter().filter(move |game| query.matches(game))
+    }
+}
+
+/// The archives in the directory, in the order of their paths.
+pub fn archive_paths(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == ARCHIVE_EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Load a recorded game, unless it was recorded under other rules or with other cards.
+pub fn load_archive(path: &Path) -> Result<GameRecord, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let record: GameRecord = serde_json::from_str(&text).map_err(|error| error.to_string())?;
+    record
+        .stamp
+        .check()
+        .map_err(|mismatch| format!("recorded game {}", mismatch))?;
+    Ok(record)
+}
+
+/// Read a game recorded by an older build wherever it can still be read, as described in
+/// `migrate`, along with every change that was made to read it.
+pub fn upgrade_record(text: &str) -> Result<(GameRecord, Vec<Migration>), String> {
+    let mut value: Value = serde_json::from_str(text).map_err(|error| error.to_string())?;
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| "a recorded game must be an object".to_string())?;
+
+    let mut migrations = vec![];
+    let stamp = object.entry("stamp").or_insert_with(|| json!({}));
+    if let Some(stamp) = stamp.as_object_mut() {
+        upgrade_stamp(stamp, "stamp", &mut migrations);
+    }
+    replace_card_bodies(&mut value, "", &mut migrations);
+
+    let record: GameRecord =
+        serde_json::from_value(value.clone()).map_err(|error| error.to_string())?;
+    let written = serde_json::to_value(&record).unwrap();
+    report_field_changes(&value, &written, "", &mut migrations);
+    Ok((record, migrations))
+}
+
+fn index_archive(path: &Path) -> Result<IndexedGame, String> {
+    let record = load_archive(path)?;
+    let game = record.replay().map_err(|error| format!("{:?}", error))?;
+    Ok(IndexedGame::from_final_state(path.to_path_buf(), &game))
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use super::*;
+    use crate::{
+        card::BASE_GAME_CARDS_BY_NAME, corporation::CORPORATIONS_BY_NAME, game::GameOperation,
+        position::import_position, resource::Resource,
+    };
+
+    fn record_game(card_name: &str, corporation_name: &str) -> GameRecord {
+        let mut game = import_position("player p1\nresources 50 0 0 0 0 0\nplayer p2").unwrap();
+        let p1 = PlayerId::from(1);
+        let card = BASE_GAME_CARDS_BY_NAME[card_name].clone();
+        let player = game.players.get_mut(&p1).unwrap();
+        player.corporation = Some(CORPORATIONS_BY_NAME[corporation_name].clone());
+        player.cards_in_hand.push(card.clone());
+
+        let mut record = GameRecord::new(game.clone());
+        record.execute_operation(&mut game, GameOperation::PutCardIntoPlay(p1, card));
+        record.execute_operation(
+            &mut game,
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -10 }),
+        );
+        record
+    }
+
+    #[test]
+    fn archives_are_indexed_in_parallel_and_queried() {
+        let directory =
+            std::env::temp_dir().join(format!("terraforming_mars_archive_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let records = [
+            ("a.json", record_game("Moss", "Inventrix")),
+            ("b.json", record_game("Colonizer Training Camp", "Teractor")),
+            ("c.json", record_game("Moss", "Teractor")),
+        ];
+        for (name, record) in &records {
+            fs::write(directory.join(name), serde_json::to_string(record).unwrap()).unwrap();
+        }
+        fs::write(directory.join("broken.json"), "{").unwrap();
+        fs::write(directory.join("notes.txt"), "not an archive").unwrap();
+
+        let index = ArchiveIndex::build(&directory, 2).unwrap();
+        assert_eq!(index, ArchiveIndex::build(&directory, 1).unwrap());
+        assert_eq!(3, index.games.len());
+        let skipped: Vec<&PathBuf> = index.skipped.iter().map(|(path, _)| path).collect();
+        assert_eq!(vec![&directory.join("broken.json")], skipped);
+
+        let query = ArchiveQuery {
+            corporation: Some("Teractor".to_string()),
+            card: Some("Moss".to_string()),
+            ..Default::default()
+        };
+        let found: Vec<&Path> = index
+            .query(&query)
+            .map(|game| game.path.as_path())
+            .collect();
+        assert_eq!(vec![directory.join("c.json").as_path()], found);
+
+        // The corporation and the card must be the same player's.
+        let query = ArchiveQuery {
+            player_count: Some(2),
+            corporation: Some("Teractor".to_string()),
+            card: Some("Moss".to_string()),
+            min_score: Some(1000),
+            ..Default::default()
+        };
+        assert_eq!(0, index.query(&query).count());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn records_from_older_builds_are_upgraded() {
+        let record = record_game("Moss", "Teractor");
+        let mut value = serde_json::to_value(&record).unwrap();
+        value.as_object_mut().unwrap().remove("stamp");
+        let moss = serde_json::to_value(BASE_GAME_CARDS_BY_NAME.get("Moss")).unwrap();
+        value["steps"][0]["Operation"]["PutCardIntoPlay"][1] = moss;
+
+        let (upgraded, migrations) = upgrade_record(&value.to_string()).unwrap();
+        // Standings and notices are derived again once the record is replayed.
+        assert_eq!(
+            serde_json::to_value(&record).unwrap(),
+            serde_json::to_value(&upgraded).unwrap()
+        );
+        assert_eq!(
+            Migration::FilledStampField {
+                field: "stamp.deck_hash".to_string(),
+                value: format!("\"{}\"", record.stamp.deck_hash),
+            },
+            migrations[0]
+        );
+        assert_eq!(
+            Migration::ReplacedCardBody {
+                path: "steps.0.Operation.PutCardIntoPlay.1".to_string(),
+                name: "Moss".to_string(),
+            },
+            migrations[3]
+        );
+        assert_eq!(4, migrations.len());
+    }
+}
@@ -0,0 +1,375 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::Card,
+    card_ref,
+    decision::{Decision, DecisionKind},
+    eval::{evaluate_with_trace, EvalTrace},
+    game::{GameState, PlayerId, PlayerState},
+    knowledge::KnowledgeTracker,
+    projection::ProjectionAssumptions,
+    registry::CardRegistry,
+    rng::GameRng,
+    sim::{plan_generations, PlanEvaluation, PlanningOptions},
+    whatif::{GameRecord, RecordedDecision, WhatIfError},
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReviewOptions {
+    // a decision whose value is more than this many victory points below
+    // the best alternative's is a blunder
+    pub threshold: f64,
+
+    pub planning: PlanningOptions,
+    pub seed: u64,
+
+    // used to explain blunders by evaluating the states the plans lead to
+    #[serde(default)]
+    pub assumptions: ProjectionAssumptions,
+}
+
+impl Default for ReviewOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 2.0,
+            planning: PlanningOptions {
+                generations: 1,
+                rollouts: 1,
+                threads: 1,
+            },
+            seed: 0,
+            assumptions: ProjectionAssumptions::default(),
+        }
+    }
+}
+
+/// A recorded decision, valued by the planner next to the best alternative it found.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecisionReview {
+    pub step: usize,
+    pub player_id: PlayerId,
+    pub generation: usize,
+
+    #[serde(with = "card_ref::cards")]
+    pub chosen: Vec<Card>,
+    pub chosen_value: f64,
+
+    #[serde(with = "card_ref::cards")]
+    pub best: Vec<Card>,
+    pub best_value: f64,
+
+    pub is_blunder: bool,
+
+    // Only for blunders: evaluations of the player's state at the end of the generation
+    // after the chosen and after the best purchase, to show where the two differ.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chosen_trace: Option<EvalTrace>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub best_trace: Option<EvalTrace>,
+}
+
+impl DecisionReview {
+    /// How many victory points the decision is expected to have cost.
+    pub fn loss(&self) -> f64 {
+        self.best_value - self.chosen_value
+    }
+}
+
+/// Every reviewed decision of a game, in the order they were made.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameReview {
+    pub threshold: f64,
+    pub decisions: Vec<DecisionReview>,
+}
+
+impl GameReview {
+    pub fn blunders(&self) -> impl Iterator<Item = &DecisionReview> {
+        self.decisions.iter().filter(|review| review.is_blunder)
+    }
+}
+
+fn card_names(cards: &[Card]) -> String {
+    let names: Vec<&str> = cards.iter().map(|card| card.name.as_str()).collect();
+    format!("[{}]", names.join(", "))
+}
+
+impl fmt::Display for GameReview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for review in &self.decisions {
+            write!(
+                f,
+                "step {}, generation {}, {:?}: bought {} ({:.1} VP)",
+                review.step,
+                review.generation,
+                review.player_id,
+                card_names(&review.chosen),
+                review.chosen_value
+            )?;
+            if review.is_blunder {
+                write!(f, " ??")?;
+            }
+            if review.loss() > 0.0 {
+                write!(
+                    f,
+                    "; best was {} ({:.1} VP), {:.1} VP better",
+                    card_names(&review.best),
+                    review.best_value,
+                    review.loss()
+                )?;
+            }
+            writeln!(f)?;
+            if let (Some(chosen_trace), Some(best_trace)) =
+                (&review.chosen_trace, &review.best_trace)
+            {
+                writeln!(f, "    chosen: {}", chosen_trace)?;
+                writeln!(f, "    best: {}", best_trace)?;
+            }
+        }
+
+        let blunder_count = self.blunders().count();
+        write!(
+            f,
+            "{} decision(s) reviewed, {} blunder(s) more than {:.1} VP below the best alternative",
+            self.decisions.len(),
+            blunder_count,
+            self.threshold
+        )
+    }
+}
+
+/// Re-plan every recorded research decision of a game, and flag the ones that fell more than
+/// the threshold below the best alternative.
+///
+/// Turn decisions are not reviewed, since the planner does not enumerate alternative turns.
+pub fn review_game(
+    record: &GameRecord,
+    options: &ReviewOptions,
+) -> Result<GameReview, WhatIfError> {
+    let mut rng = GameRng::from_seed(options.seed);
+
+    let mut decisions = vec![];
+    for recorded in &record.decisions {
+        if let (DecisionKind::Research(offered), Decision::BuyCards(bought)) =
+            (&recorded.kind, &recorded.decision)
+        {
+            let game = record.state_at(recorded.step)?;
+            let plans = plan_research(&game, recorded, offered, options, &mut rng.fork());
+            if let Some(review) = review_purchase(&game, recorded, bought, &plans, options) {
+                decisions.push(review);
+            }
+        }
+    }
+
+    Ok(GameReview {
+        threshold: options.threshold,
+        decisions,
+    })
+}
+
+fn plan_research(
+    game: &GameState,
+    recorded: &RecordedDecision,
+    offered: &[Card],
+    options: &ReviewOptions,
+    rng: &mut GameRng,
+) -> Vec<PlanEvaluation> {
+    // The offered cards were already drawn into the player's hand when the decision was made.
+    let mut player: PlayerState = game.players[&recorded.player_id].clone();
+    player.cards_in_hand.retain(|card| !offered.contains(card));
+    let opponents: Vec<&PlayerState> = game
+        .players
+        .values()
+        .filter(|opponent| opponent.player_id != recorded.player_id)
+        .collect();
+
+    let knowledge = &KnowledgeTracker::new(game).players[&recorded.player_id];
+    let unseen_cards: Vec<Card> = knowledge
+        .unaccounted_cards(CardRegistry::global().cards())
+        .into_iter()
+        .cloned()
+        .collect();
+
+    plan_generations(
+        &game.board,
+        &player,
+        &opponents,
+        offered.to_vec(),
+        &unseen_cards,
+        &options.planning,
+        rng,
+    )
+}
+
+fn review_purchase(
+    game: &GameState,
+    recorded: &RecordedDecision,
+    bought: &[Card],
+    plans: &[PlanEvaluation],
+    options: &ReviewOptions,
+) -> Option<DecisionReview> {
+    let best = plans.first()?;
+
+    // The purchase is worth as much as the best way of following it up.
+    let mut chosen_names: Vec<&str> = bought.iter().map(|card| card.name.as_str()).collect();
+    chosen_names.sort_unstable();
+    let chosen = plans.iter().find(|plan| {
+        let mut names: Vec<&str> = plan
+            .bought_cards
+            .iter()
+            .map(|card| card.name.as_str())
+            .collect();
+        names.sort_unstable();
+        names == chosen_names
+    })?;
+
+    let is_blunder = best.mean_value - chosen.mean_value > options.threshold;
+    let trace = |plan: &PlanEvaluation| {
+        let mut state = game.clone();
+        state
+            .players
+            .insert(recorded.player_id, plan.final_state.clone());
+        evaluate_with_trace(&state, recorded.player_id, &options.assumptions)
+    };
+
+    Some(DecisionReview {
+        step: recorded.step,
+        player_id: recorded.player_id,
+        generation: game.generation,
+        chosen: bought.to_vec(),
+        chosen_value: chosen.mean_value,
+        best: best.bought_cards.clone(),
+        best_value: best.mean_value,
+        is_blunder,
+        chosen_trace: if is_blunder { Some(trace(chosen)) } else { None },
+        best_trace: if is_blunder { Some(trace(best)) } else { None },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        blunder::{review_game, review_purchase, GameReview, ReviewOptions},
+        card::{Card, CORPORATE_GAME_CARDS_BY_NAME},
+        decision::{Decision, DecisionKind},
+        game::{GameOperation, PlayerId, PlayerStateBuilder, CARD_PURCHASE_COST},
+        position::import_position,
+        resource::Resource,
+        sim::PlanEvaluation,
+        whatif::{GameRecord, RecordedDecision},
+    };
+
+    fn plan(bought_cards: Vec<Card>, mean_value: f64) -> PlanEvaluation {
+        PlanEvaluation {
+            bought_cards,
+            actions: vec![],
+            final_state: PlayerStateBuilder::new(1).build(),
+            sampled_values: vec![mean_value as isize],
+            mean_value,
+            standard_error: 0.0,
+        }
+    }
+
+    #[test]
+    fn every_recorded_research_decision_is_reviewed() {
+        let mut game = import_position(
+            "player p1\n\
+             resources 20 0 0 0 0 0\n\
+             hand Research\n\
+             player p2\n\
+             resources 20 0 0 0 0 0\n\
+             hand Lagrange Observatory\n",
+        )
+        .unwrap();
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let research = CORPORATE_GAME_CARDS_BY_NAME["Research"].clone();
+        let observatory = CORPORATE_GAME_CARDS_BY_NAME["Lagrange Observatory"].clone();
+        let mut record = GameRecord::new(game.clone());
+
+        record.record_decision(
+            p1,
+            DecisionKind::Research(vec![research.clone()]),
+            Decision::BuyCards(vec![]),
+        );
+        record.execute_operation(&mut game, GameOperation::DiscardCards(p1, vec![research]));
+
+        record.record_decision(
+            p2,
+            DecisionKind::Research(vec![observatory.clone()]),
+            Decision::BuyCards(vec![observatory.clone()]),
+        );
+        record.execute_operation(
+            &mut game,
+            GameOperation::ChangeResources(
+                p2,
+                btreemap! { Resource::Megacredits => -(CARD_PURCHASE_COST as isize) },
+            ),
+        );
+
+        // Turns are not reviewed.
+        record.record_decision(p1, DecisionKind::TakeTurn, Decision::Pass);
+
+        let review = review_game(&record, &ReviewOptions::default()).unwrap();
+        assert_eq!(2, review.decisions.len());
+        assert_eq!(
+            (0, p1),
+            (review.decisions[0].step, review.decisions[0].player_id)
+        );
+        assert_eq!(
+            (1, p2),
+            (review.decisions[1].step, review.decisions[1].player_id)
+        );
+        assert_eq!(vec![observatory], review.decisions[1].chosen);
+        for decision in &review.decisions {
+            assert!(decision.loss() >= 0.0);
+        }
+    }
+
+    #[test]
+    fn purchases_far_below_the_best_plan_are_blunders() {
+        let game = import_position("player p1\n").unwrap();
+        let research = CORPORATE_GAME_CARDS_BY_NAME["Research"].clone();
+        let recorded = RecordedDecision {
+            step: 3,
+            player_id: PlayerId::from(1),
+            kind: DecisionKind::Research(vec![research.clone()]),
+            decision: Decision::BuyCards(vec![]),
+        };
+        let plans = vec![plan(vec![research], 21.0), plan(vec![], 20.0)];
+
+        let options = ReviewOptions {
+            threshold: 0.5,
+            ..Default::default()
+        };
+        let review = review_purchase(&game, &recorded, &[], &plans, &options).unwrap();
+        assert!(review.is_blunder);
+        assert_eq!(1.0, review.loss());
+
+        let lenient = ReviewOptions {
+            threshold: 1.0,
+            ..Default::default()
+        };
+        assert!(
+            !review_purchase(&game, &recorded, &[], &plans, &lenient)
+                .unwrap()
+                .is_blunder
+        );
+
+        let report = GameReview {
+            threshold: 0.5,
+            decisions: vec![review],
+        }
+        .to_string();
+        assert_eq!(
+            "step 3, generation 1, PlayerId(1): bought [] (20.0 VP) ??; \
+             best was [Research] (21.0 VP), 1.0 VP better\n\
+             \x20   chosen: terraform rating +20.0, total +20.0\n\
+             \x20   best: terraform rating +20.0, total +20.0\n\
+             1 decision(s) reviewed, 1 blunder(s) more than 0.5 VP below the best alternative",
+            report
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Bounds on the victory points a player can still end the game with, for cutting off
+//! lines of play that cannot beat one already found.
+//!
+//! The upper bound is admissible: no line of play from the state ends with more points,
+//! as long as the player only gets the cards already in hand. It is deliberately generous
+//! wherever being exact would mean searching, e.g. every tile is assumed to be placed
+//! where it scores the most. The lower bound is what the player can secure on their own,
+//! without opponents taking anything away.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::{MarsBoard, TileLocation},
+    card::{CardTag, CityKind, ImmediateImpact, VictoryPointValue},
+    game::PlayerState,
+    projection::{remaining_parameter_steps, PLANTS_PER_GREENERY},
+    resource::Resource,
+};
+
+// Standard projects, the cheapest way of placing a tile without a card.
+const GREENERY_PROJECT_COST: usize = 23;
+const CITY_PROJECT_COST: usize = 25;
+
+// A tile has at most this many neighbors.
+const MAX_NEIGHBORS: usize = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VpBounds {
+    pub lower: isize,
+    pub upper: isize,
+}
+
+/// Bounds on the player's final victory points, if the game ends after the given number of
+/// production phases, counting the current generation's.
+pub fn final_vp_bounds(
+    board: &MarsBoard,
+    player: &PlayerState,
+    generations_left: usize,
+) -> VpBounds {
+    let current_points = player.score_breakdown(board);
+    let hand = HandAllowance::of(player);
+
+    // Every global parameter step still to go could be the player's.
+    let terraform_rating_gain = remaining_parameter_steps(board) + hand.terraform_rating;
+    let income = |resource: Resource, bonus: usize| {
+        let production = player.production[&resource] + bonus as isize;
+        player.resources[&resource] + production.max(0) as usize * generations_left
+    };
+    let megacredits = income(
+        Resource::Megacredits,
+        player.terraform_rating + terraform_rating_gain,
+    );
+    let plants = income(Resource::Plants, 0);
+
+    let greeneries =
+        plants / PLANTS_PER_GREENERY + megacredits / GREENERY_PROJECT_COST + hand.greeneries;
+    let cities = megacredits / CITY_PROJECT_COST + hand.cities;
+    let own_cities = board
+        .cities
+        .values()
+        .filter(|(_, owner)| *owner == player.player_id)
+        .count()
+        + cities;
+    let owns_capital = board
+        .cities
+        .values()
+        .any(|(kind, owner)| *kind == CityKind::Capital && *owner == player.player_id);
+
+    // Each greenery scores itself and a point for each of the player's neighboring cities,
+    // and each city a point for each neighboring greenery.
+    let tile_points = greeneries * (1 + own_cities.min(MAX_NEIGHBORS)) + cities * MAX_NEIGHBORS;
+    let capital_points = if owns_capital || hand.capital {
+        MarsBoard::MAX_OCEANS - board.oceans.len()
+    } else {
+        0
+    };
+
+    let upper = current_points.total() - current_points.cards
+        + card_points_upper_bound(board, player)
+        + (terraform_rating_gain + tile_points + capital_points) as isize;
+
+    // Plants already stockpiled can be turned into greeneries right away.
+    let free_land = board
+        .spaces
+        .keys()
+        .filter(|location| matches!(location, TileLocation::OnMars(_)))
+        .count()
+        .saturating_sub(board.greeneries.len() + board.cities.len() + board.oceans.len());
+    let secured_greeneries =
+        (player.resources[&Resource::Plants] / PLANTS_PER_GREENERY).min(free_land);
+    let lower = current_points.total() + secured_greeneries as isize;
+
+    VpBounds { lower, upper }
+}
+
+/// The most points the player's cards could be worth, if every card in hand were played,
+/// except that cards costing points are counted as if they were worth nothing.
+pub fn card_points_upper_bound(board: &MarsBoard, player: &PlayerState) -> isize {
+    let mut optimistic = player.clone();
+    let hand = std::mem::take(&mut optimistic.cards_in_hand);
+    let lost_points: isize = hand
+        .iter()
+        .map(|card| match card.points {
+            Some(VictoryPointValue::Immediate(points)) if points < 0 => -points,
+            _ => 0,
+        })
+        .sum();
+    optimistic.played_cards.extend(hand);
+
+    optimistic.card_victory_points(board) + lost_points
+}
+
+// What playing every card in hand could add on top of standard projects and conversions.
+#[derive(Debug, Default)]
+struct HandAllowance {
+    terraform_rating: usize, // not counting global parameter steps
+    greeneries: usize,
+    cities: usize,
+    capital: bool,
+}
+
+impl HandAllowance {
+    fn of(player: &PlayerState) -> Self {
+        let tag_count = |tag: CardTag| {
+            player
+                .played_cards
+                .iter()
+                .chain(&player.cards_in_hand)
+                .map(|card| card.tags.count(tag) + card.tags.count(CardTag::Wild))
+                .sum::<usize>()
+        };
+
+        let mut allowance = Self::default();
+        for card in &player.cards_in_hand {
+            for impact in &card.immediate_impacts {
+                allowance.add(&Self::of_impact(impact, &tag_count));
+            }
+        }
+        allowance
+    }
+
+    fn of_impact(impact: &ImmediateImpact, tag_count: &dyn Fn(CardTag) -> usize) -> Self {
+        match impact {
+            ImmediateImpact::RaiseTerraformRating => Self {
+                terraform_rating: 1,
+                ..Default::default()
+            },
+            ImmediateImpact::GainTerraformRatingPerOwnTag(count, tag, per) => Self {
+                terraform_rating: count * (tag_count(*tag) / per),
+                ..Default::default()
+            },
+            ImmediateImpact::PlaceGreenery(_) => Self {
+                greeneries: 1,
+                ..Default::default()
+            },
+            ImmediateImpact::PlaceCity(kind, _) => Self {
+                cities: 1,
+                capital: *kind == CityKind::Capital,
+                ..Default::default()
+            },
+            ImmediateImpact::SpendResourceFromSameCard(_, _, impact) => {
+                Self::of_impact(impact, tag_count)
+            }
+            ImmediateImpact::Chained(first, second) => {
+                let mut allowance = Self::of_impact(first, tag_count);
+                allowance.add(&Self::of_impact(second, tag_count));
+                allowance
+            }
+            ImmediateImpact::OneOf(impacts) => {
+                // Generous: the best of every option at once.
+                let mut allowance = Self::default();
+                for impact in impacts {
+                    let option = Self::of_impact(impact, tag_count);
+                    allowance.terraform_rating =
+                        allowance.terraform_rating.max(option.terraform_rating);
+                    allowance.greeneries = allowance.greeneries.max(option.greeneries);
+                    allowance.cities = allowance.cities.max(option.cities);
+                    allowance.capital |= option.capital;
+                }
+                allowance
+            }
+            _ => Self::default(),
+        }
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.terraform_rating += other.terraform_rating;
+        self.greeneries += other.greeneries;
+        self.cities += other.cities;
+        self.capital |= other.capital;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{make_base_game_board, MarsBoard},
+        bounds::final_vp_bounds,
+        card::BASE_GAME_CARDS_BY_NAME,
+        game::PlayerStateBuilder,
+    };
+
+    #[test]
+    fn bounds_enclose_the_current_points_and_tighten_near_the_end() {
+        let mut board = make_base_game_board();
+        let player = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 17, 0, 0)
+            .build();
+
+        let early = final_vp_bounds(&board, &player, 10);
+        // 20 TR, and two greeneries from the plants at hand.
+        assert_eq!(22, early.lower);
+        assert!(early.upper > 20 + 42);
+
+        board.oxygen = MarsBoard::MAX_OXYGEN;
+        board.temperature = MarsBoard::MAX_TEMPERATURE;
+        let late = final_vp_bounds(&board, &player, 1);
+        assert_eq!(22, late.lower);
+        assert!(late.upper < early.upper);
+        assert!(late.upper >= late.lower);
+    }
+
+    #[test]
+    fn cards_in_hand_raise_the_upper_bound_by_their_points() {
+        let board = make_base_game_board();
+        let player = PlayerStateBuilder::new(1).build();
+        let without_cards = final_vp_bounds(&board, &player, 1);
+
+        // 1VP, and nothing that raises the terraform rating or places tiles.
+        let player = PlayerStateBuilder::new(1)
+            .with_cards_in_hand(vec![BASE_GAME_CARDS_BY_NAME["Solar Power"].clone()])
+            .build();
+        let with_card = final_vp_bounds(&board, &player, 1);
+        assert_eq!(without_cards.upper + 1, with_card.upper);
+        assert_eq!(without_cards.lower, with_card.lower);
+    }
+}
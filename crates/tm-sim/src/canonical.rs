@@ -0,0 +1,203 @@
+//! Canonical forms of action sequences, so that plays which only differ in the order of
+//! independent actions compare, hash, and cache as the same play.
+//!
+//! Two card plays are independent when playing them in either order leads to the same state:
+//! neither card changes how later cards are played, neither helps meet the other's
+//! requirements, and neither fires the effects of a card played before them, whose gains
+//! could pay for the other. Anything else, e.g. a card action or a standard project, keeps
+//! its place, and no card play moves past it. Actions carry no payment details, so there is
+//! nothing to merge besides the order.
+
+use crate::{
+    card::{Card, CardEffect, CardRequirement, CardTag, ImmediateImpact},
+    game::TurnAction,
+};
+
+/// The sequence with every run of independent card plays sorted by card name.
+///
+/// Sequences that can be turned into each other by swapping neighboring independent plays
+/// have the same canonical form.
+pub fn canonicalize_actions(actions: &[TurnAction]) -> Vec<TurnAction> {
+    let mut canonical = actions.to_vec();
+
+    // Bubble sort, only swapping neighbors that commute. Sequences are a handful
+    // of actions long, so the quadratic worst case does not matter.
+    let mut swapped = true;
+    while swapped {
+        swapped = false;
+        for i in 1..canonical.len() {
+            if let (TurnAction::PlayCard(first, _), TurnAction::PlayCard(second, _)) =
+                (&canonical[i - 1], &canonical[i])
+            {
+                let fires_effects = |card: &Card| {
+                    canonical[..i - 1].iter().any(|earlier| match earlier {
+                        TurnAction::PlayCard(earlier, _) => fires_effect_of(card, earlier),
+                        _ => false,
+                    })
+                };
+                if second.name < first.name
+                    && are_independent(first, second)
+                    && !fires_effects(first)
+                    && !fires_effects(second)
+                {
+                    canonical.swap(i - 1, i);
+                    swapped = true;
+                }
+            }
+        }
+    }
+
+    canonical
+}
+
+pub fn are_equivalent(first: &[TurnAction], second: &[TurnAction]) -> bool {
+    canonicalize_actions(first) == canonicalize_actions(second)
+}
+
+fn are_independent(first: &Card, second: &Card) -> bool {
+    !is_order_sensitive(first)
+        && !is_order_sensitive(second)
+        && !helps_meet_requirements(first, second)
+        && !helps_meet_requirements(second, first)
+}
+
+// Cards whose outcome depends on what was played before them, or that change the outcome
+// of what is played after them.
+fn is_order_sensitive(card: &Card) -> bool {
+    !card.effects.is_empty()
+        || !card.next_card_this_generation_effects.is_empty()
+        || card.immediate_impacts.iter().any(is_order_sensitive_impact)
+}
+
+fn is_order_sensitive_impact(impact: &ImmediateImpact) -> bool {
+    match impact {
+        ImmediateImpact::GainTerraformRatingPerOwnTag(..)
+        | ImmediateImpact::AddResourceToAnotherCard(..)
+        | ImmediateImpact::AddResourceToAnyCard(..)
+        | ImmediateImpact::AddResourceToPlayedCard(..)
+        | ImmediateImpact::AddResourceToAnyCardWithExistingResource(..)
+        | ImmediateImpact::GainResourcePerCity(..)
+        | ImmediateImpact::GainResourcePerCityOnMars(..)
+        | ImmediateImpact::GainProductionPerCity(..)
+        | ImmediateImpact::GainProductionPerCityOnMars(..)
+        | ImmediateImpact::GainProductionIfMinTags(..)
+        | ImmediateImpact::GainProductionPerOwnTag(..)
+        | ImmediateImpact::GainProductionPerOpponentTag(..)
+        | ImmediateImpact::GainProductionPerAnyTag(..)
+        | ImmediateImpact::CopyProductionOfCard(..)
+        | ImmediateImpact::DrawCard(..)
+        | ImmediateImpact::DiscardCard(..)
+        | ImmediateImpact::LookAndBuyFromDeck(..)
+        | ImmediateImpact::LookAndTakeFromDeck(..) => true,
+        ImmediateImpact::SpendResourceFromSameCard(_, _, impact) => {
+            is_order_sensitive_impact(impact)
+        }
+        ImmediateImpact::Chained(first, second) => {
+            is_order_sensitive_impact(first) || is_order_sensitive_impact(second)
+        }
+        ImmediateImpact::OneOf(impacts) => impacts.iter().any(is_order_sensitive_impact),
+        _ => false,
+    }
+}
+
+// Whether the card's tags fire an effect of the earlier card.
+fn fires_effect_of(card: &Card, earlier: &Card) -> bool {
+    earlier.effects.iter().any(|effect| match effect {
+        CardEffect::OnAnyTagPlayed(tag, _) | CardEffect::OnOwnTagPlayed(tag, _) => {
+            card.tags.contains(tag)
+        }
+        CardEffect::OnOwnTagCombinationPlayed(tags, _) => {
+            tags.iter().all(|tag| card.tags.contains(tag))
+        }
+        _ => false,
+    })
+}
+
+// Whether playing the first card can make a difference to meeting the second's requirements.
+fn helps_meet_requirements(first: &Card, second: &Card) -> bool {
+    second
+        .requirements
+        .iter()
+        .any(|requirement| match requirement {
+            CardRequirement::MinTags(tag, _) => {
+                first.tags.contains(tag) || first.tags.contains(&CardTag::Wild)
+            }
+            CardRequirement::MinProduction(resource, _) => {
+                first.own_production.contains_key(resource)
+            }
+            CardRequirement::MinOwnedGreeneries(_)
+            | CardRequirement::MaxOxygen(_)
+            | CardRequirement::MinOxygen(_)
+            | CardRequirement::MaxTemperature(_)
+            | CardRequirement::MinTemperature(_)
+            | CardRequirement::MaxOceans(_)
+            | CardRequirement::MinOceans(_) => first.immediate_impacts.iter().any(changes_board),
+        })
+}
+
+// Whether the impact can raise a global parameter or place a tile.
+fn changes_board(impact: &ImmediateImpact) -> bool {
+    match impact {
+        ImmediateImpact::RaiseTemperature
+        | ImmediateImpact::RaiseOxygen
+        | ImmediateImpact::PlaceOcean(_)
+        | ImmediateImpact::PlaceGreenery(_)
+        | ImmediateImpact::PlaceCity(..)
+        | ImmediateImpact::PlaceFloodingOcean(..)
+        | ImmediateImpact::PlaceSpecialTile(..) => true,
+        ImmediateImpact::SpendResourceFromSameCard(_, _, impact) => changes_board(impact),
+        ImmediateImpact::Chained(first, second) => changes_board(first) || changes_board(second),
+        ImmediateImpact::OneOf(impacts) => impacts.iter().any(changes_board),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        canonical::{are_equivalent, canonicalize_actions},
+        card::BASE_GAME_CARDS_BY_NAME,
+        game::{StandardProject, TurnAction},
+    };
+
+    fn play(name: &str) -> TurnAction {
+        TurnAction::PlayCard(BASE_GAME_CARDS_BY_NAME[name].clone(), vec![])
+    }
+
+    #[test]
+    fn independent_plays_are_sorted_by_name() {
+        // Neither card has effects or requirements.
+        let actions = vec![play("Solar Power"), play("Asteroid Mining")];
+        assert_eq!(
+            vec![play("Asteroid Mining"), play("Solar Power")],
+            canonicalize_actions(&actions)
+        );
+        assert!(are_equivalent(
+            &actions,
+            &[play("Asteroid Mining"), play("Solar Power")]
+        ));
+    }
+
+    #[test]
+    fn plays_that_depend_on_each_other_keep_their_order() {
+        // Space Station discounts the Space-tagged Asteroid Mining played after it.
+        let actions = vec![play("Space Station"), play("Asteroid Mining")];
+        assert_eq!(actions, canonicalize_actions(&actions));
+
+        // The megacredits Optimal Aerobraking gives for the space event may pay for the next card.
+        let actions = vec![
+            play("Optimal Aerobraking"),
+            play("Towing A Comet"),
+            play("Asteroid Mining"),
+        ];
+        assert_eq!(actions, canonicalize_actions(&actions));
+
+        // Nothing moves past a standard project.
+        let actions = vec![
+            play("Solar Power"),
+            TurnAction::PlayStandardProject(StandardProject::PowerPlant),
+            play("Asteroid Mining"),
+        ];
+        assert_eq!(actions, canonicalize_actions(&actions));
+    }
+}
@@ -0,0 +1,266 @@
+//! Training data for learned policies and evaluators: every recorded decision of a game,
+//! turned into a (state features, chosen action, final outcome) example.
+//!
+//! Datasets are written as JSON lines. The first line is a `DatasetHeader`, with the format
+//! version and the name of every feature, in order. Every following line is one
+//! `TrainingExample`:
+//! - `features`: the numbers in `FEATURE_NAMES` order, describing the game as it was
+//!   when the decision was made, from the deciding player's point of view;
+//! - `kind` and `decision`: what the player was asked, and what they chose,
+//!   in the same form as in game records;
+//! - `outcome`: how the game ended for the deciding player.
+
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    archive::{archive_paths, load_archive},
+    decision::{Decision, DecisionKind},
+    game::{GameState, PlayerId},
+    resource::Resource,
+    scoring::score_all_players,
+    whatif::{GameRecord, WhatIfError},
+};
+
+/// Bumped whenever the features or the layout of the examples change.
+pub const DATASET_FORMAT_VERSION: u32 = 1;
+
+pub const FEATURE_NAMES: [&str; 23] = [
+    "generation",
+    "temperature",
+    "oxygen",
+    "oceans",
+    "terraform_rating",
+    "megacredits",
+    "steel",
+    "titanium",
+    "plants",
+    "energy",
+    "heat",
+    "megacredit_production",
+    "steel_production",
+    "titanium_production",
+    "plant_production",
+    "energy_production",
+    "heat_production",
+    "cards_in_hand",
+    "cards_played",
+    "score",
+    "best_opponent_score",
+    "best_opponent_terraform_rating",
+    "opponents",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetHeader {
+    pub version: u32,
+    pub features: Vec<String>,
+}
+
+impl Default for DatasetHeader {
+    fn default() -> Self {
+        Self {
+            version: DATASET_FORMAT_VERSION,
+            features: FEATURE_NAMES.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub final_score: isize,
+    pub won: bool,     // had the highest final score, possibly tied
+    pub margin: isize, // over the best opponent, negative when behind
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TrainingExample {
+    pub player_id: PlayerId,
+    pub features: Vec<f64>,
+    pub kind: DecisionKind,
+    pub decision: Decision,
+    pub outcome: Outcome,
+}
+
+/// The features of the game from the player's point of view, in `FEATURE_NAMES` order.
+/// Only what the player can see is used, e.g. opponents' hands are not.
+pub fn state_features(game: &GameState, player_id: PlayerId) -> Vec<f64> {
+    let player = &game.players[&player_id];
+    let scores = score_all_players(game);
+    let score = |id: PlayerId| {
+        scores
+            .iter()
+            .find(|(scored_id, _)| *scored_id == id)
+            .map_or(0, |(_, breakdown)| breakdown.total())
+    };
+    let opponents: Vec<PlayerId> = game
+        .players
+        .keys()
+        .copied()
+        .filter(|id| *id != player_id)
+        .collect();
+
+    let mut features = vec![
+        game.generation as f64,
+        game.board.temperature as f64,
+        game.board.oxygen as f64,
+        game.board.oceans.len() as f64,
+        player.terraform_rating as f64,
+    ];
+    features.extend(
+        Resource::ALL
+            .iter()
+            .map(|resource| player.resources[resource] as f64),
+    );
+    features.extend(
+        Resource::ALL
+            .iter()
+            .map(|resource| player.production[resource] as f64),
+    );
+    features.extend([
+        player.cards_in_hand.len() as f64,
+        player.played_cards.len() as f64,
+        score(player_id) as f64,
+        opponents.iter().map(|id| score(*id)).max().unwrap_or(0) as f64,
+        opponents
+            .iter()
+            .map(|id| game.players[id].terraform_rating)
+            .max()
+            .unwrap_or(0) as f64,
+        opponents.len() as f64,
+    ]);
+    debug_assert_eq!(FEATURE_NAMES.len(), features.len());
+    features
+}
+
+/// One example per recorded decision of the game, in the order they were made.
+pub fn training_examples(record: &GameRecord) -> Result<Vec<TrainingExample>, WhatIfError> {
+    let final_state = record.replay()?;
+    let final_scores: Vec<(PlayerId, isize)> = score_all_players(&final_state)
+        .into_iter()
+        .map(|(player_id, breakdown)| (player_id, breakdown.total()))
+        .collect();
+    let outcome = |player_id: PlayerId| {
+        let final_score = final_scores
+            .iter()
+            .find(|(id, _)| *id == player_id)
+            .map_or(0, |(_, score)| *score);
+        let best_opponent_score = final_scores
+            .iter()
+            .filter(|(id, _)| *id != player_id)
+            .map(|(_, score)| *score)
+            .max();
+        Outcome {
+            final_score,
+            won: best_opponent_score.is_none_or(|best| final_score >= best),
+            margin: best_opponent_score.map_or(0, |best| final_score - best),
+        }
+    };
+
+    let mut examples = vec![];
+    for recorded in &record.decisions {
+        let game = record.state_at(recorded.step)?;
+        examples.push(TrainingExample {
+            player_id: recorded.player_id,
+            features: state_features(&game, recorded.player_id),
+            kind: recorded.kind.clone(),
+            decision: recorded.decision.clone(),
+            outcome: outcome(recorded.player_id),
+        });
+    }
+    Ok(examples)
+}
+
+/// Write the header line, then one line per example.
+pub fn write_dataset<'a>(
+    examples: impl IntoIterator<Item = &'a TrainingExample>,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, &DatasetHeader::default())?;
+    writeln!(writer)?;
+    for example in examples {
+        serde_json::to_writer(&mut *writer, example)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Export every game archived in the directory as one dataset, in the order of their paths.
+/// Returns the number of examples written, and the archives that were skipped, and why.
+pub fn export_archive_dataset(
+    directory: &Path,
+    writer: &mut impl Write,
+) -> io::Result<(usize, Vec<(PathBuf, String)>)> {
+    let mut examples = vec![];
+    let mut skipped = vec![];
+    for path in archive_paths(directory)? {
+        let game_examples = load_archive(&path)
+            .and_then(|record| training_examples(&record).map_err(|error| format!("{:?}", error)));
+        match game_examples {
+            Ok(game_examples) => examples.extend(game_examples),
+            Err(reason) => skipped.push((path, reason)),
+        }
+    }
+    write_dataset(&examples, writer)?;
+    Ok((examples.len(), skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{GameOperation, StandardProject, TurnAction},
+        position::import_position,
+    };
+
+    #[test]
+    fn every_decision_becomes_an_example_with_the_final_outcome() {
+        let mut game = import_position("player p1\nplayer p2\nresources 10 0 0 0 0 0").unwrap();
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let mut record = GameRecord::new(game.clone());
+        let raise = GameOperation::RaiseTerraformRating(p2, 2);
+        record.record_decision(
+            p2,
+            DecisionKind::TakeTurn,
+            Decision::Act(TurnAction::PlayStandardProject(StandardProject::Asteroid(None))),
+        );
+        record.execute_operation(&mut game, raise);
+        record.record_decision(p1, DecisionKind::TakeTurn, Decision::Pass);
+
+        let examples = training_examples(&record).unwrap();
+        assert_eq!(2, examples.len());
+        let megacredits = FEATURE_NAMES
+            .iter()
+            .position(|name| *name == "megacredits")
+            .unwrap();
+        assert_eq!(10.0, examples[0].features[megacredits]);
+        assert_eq!(
+            Outcome {
+                final_score: 22,
+                won: true,
+                margin: 2
+            },
+            examples[0].outcome
+        );
+        // The second decision sees the raised terraform rating.
+        let best_opponent_rating = FEATURE_NAMES
+            .iter()
+            .position(|name| *name == "best_opponent_terraform_rating")
+            .unwrap();
+        assert_eq!(22.0, examples[1].features[best_opponent_rating]);
+        assert_eq!(-2, examples[1].outcome.margin);
+
+        let mut output = vec![];
+        write_dataset(&examples, &mut output).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+        assert_eq!(3, lines.len());
+        let header: DatasetHeader = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(FEATURE_NAMES.len(), header.features.len());
+        let example: TrainingExample = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(examples[1], example);
+    }
+}
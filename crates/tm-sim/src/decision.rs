@@ -0,0 +1,791 @@
+//! Turn-based games where each seat is either a human, who may take hours to answer,
+//! or a bot, which answers as soon as it is asked.
+//!
+//! The game asks for one decision at a time per player, as a `DecisionRequest`.
+//! An `AsyncGame` holds the requests that are still waiting for an answer, and is saved
+//! alongside the game, so that a restarted server picks up exactly where it left off.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use maplit::btreemap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::Coordinates,
+    card::Card,
+    card_ref,
+    event::GameEvent,
+    game::{
+        GameOperation, GamePhase, GameState, IllegalOperation, PlayerId, PlayerState,
+        TurnAction, CARD_PURCHASE_COST, PLANTS_PER_GREENERY,
+    },
+    heatmap::{make_heat_map, PlacementKind},
+    options::TurnOrder,
+    resource::Resource,
+    sim::get_possible_generation_plays,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Seat {
+    Human,
+    Bot,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecisionKind {
+    // which of the offered cards to buy
+    Research(#[serde(with = "card_ref::cards")] Vec<Card>),
+    // one action, or passing for the rest of the generation
+    TakeTurn,
+    // every action for the rest of the generation, in simultaneous turn order
+    PlanGeneration,
+    // where to turn the remaining plants into greeneries, once Mars is terraformed
+    ConvertPlants,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecisionRequest {
+    pub id: u64,
+    pub player_id: PlayerId,
+    pub generation: usize,
+    pub kind: DecisionKind,
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Decision {
+    BuyCards(#[serde(with = "card_ref::cards")] Vec<Card>),
+    Act(TurnAction),
+    Pass,
+    // the actions in the order they are to be taken
+    Plan(Vec<TurnAction>),
+    // one greenery per space, in order
+    ConvertPlants(Vec<Coordinates>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecisionError {
+    NoSuchRequest(u64),
+    NotYourDecision(u64),
+    WrongKindOfDecision,
+    CardNotOffered(String),
+    Illegal(IllegalOperation),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum Phase {
+    Research,
+    Actions { passed: BTreeSet<PlayerId> },
+    // plans are kept private until every player has made theirs
+    Planning {
+        plans: BTreeMap<PlayerId, Vec<TurnAction>>,
+    },
+    // the players who may still convert plants, in the order they get to
+    FinalGreeneries {
+        waiting: Vec<PlayerId>,
+    },
+    Over,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AsyncGame {
+    pub seats: BTreeMap<PlayerId, Seat>,
+    pub pending: Vec<DecisionRequest>,
+    phase: Phase,
+    next_request_id: u64,
+}
+
+impl AsyncGame {
+    /// Begin the research phase of the current generation. Bots make their decisions right away.
+    pub fn start(game: &mut GameState, seats: BTreeMap<PlayerId, Seat>) -> (Self, Vec<GameEvent>) {
+        assert!(seats.keys().eq(game.players.keys()));
+
+        let mut async_game = Self {
+            seats,
+            pending: vec![],
+            phase: Phase::Research,
+            next_request_id: 1,
+        };
+        let mut events = vec![];
+        async_game.begin_research_phase(game, &mut events);
+        async_game.run_bots(game, &mut events);
+        (async_game, events)
+    }
+
+    pub fn pending_for(&self, player_id: PlayerId) -> impl Iterator<Item = &DecisionRequest> {
+        self.pending
+            .iter()
+            .filter(move |request| request.player_id == player_id)
+    }
+
+    /// Answer any requests addressed to bots, e.g. ones left over when a server stopped
+    /// after asking a bot but before it answered.
+    pub fn resume(&mut self, game: &mut GameState) -> Vec<GameEvent> {
+        let mut events = vec![];
+        self.run_bots(game, &mut events);
+        events
+    }
+
+    /// Apply a player's answer to one of their pending requests, then move the game along
+    /// until a human's decision is needed. Nothing changes if the answer is rejected.
+    pub fn answer(
+        &mut self,
+        game: &mut GameState,
+        player_id: PlayerId,
+        request_id: u64,
+        decision: Decision,
+    ) -> Result<Vec<GameEvent>, DecisionError> {
+        let mut events = self.apply_answer(game, player_id, request_id, decision)?;
+        self.run_bots(game, &mut events);
+        Ok(events)
+    }
+
+    fn apply_answer(
+        &mut self,
+        game: &mut GameState,
+        player_id: PlayerId,
+        request_id: u64,
+        decision: Decision,
+    ) -> Result<Vec<GameEvent>, DecisionError> {
+        let index = self
+            .pending
+            .iter()
+            .position(|request| request.id == request_id)
+            .ok_or(DecisionError::NoSuchRequest(request_id))?;
+        if self.pending[index].player_id != player_id {
+            return Err(DecisionError::NotYourDecision(request_id));
+        }
+
+        let operations = match (&self.pending[index].kind, &decision) {
+            (DecisionKind::Research(offered), Decision::BuyCards(bought)) => {
+                research_operations(player_id, offered, bought)?
+            }
+            (DecisionKind::TakeTurn, Decision::Act(action)) => game
+                .turn_action_operations(player_id, action)
+                .map_err(DecisionError::Illegal)?,
+            (DecisionKind::TakeTurn, Decision::Pass) => vec![],
+            // The plan is checked as if nobody else acted in between; actions that other
+            // players' actions make illegal are skipped when the plans are resolved.
+            (DecisionKind::PlanGeneration, Decision::Plan(actions)) => {
+                let mut scratch_game = game.clone();
+                for action in actions {
+                    scratch_game
+                        .play_turn_action(player_id, action)
+                        .map_err(DecisionError::Illegal)?;
+                }
+                vec![]
+            }
+            (DecisionKind::ConvertPlants, Decision::ConvertPlants(spaces)) => {
+                conversion_operations(game, player_id, spaces)?
+            }
+            _ => return Err(DecisionError::WrongKindOfDecision),
+        };
+
+        // Each operation may depend on the ones before it, so check them against a scratch copy.
+        let mut scratch_game = game.clone();
+        for operation in &operations {
+            scratch_game
+                .check_operation(operation)
+                .map_err(DecisionError::Illegal)?;
+            scratch_game.execute_operation(operation.clone());
+        }
+
+        let mut events = vec![];
+        match (&mut self.phase, &decision) {
+            (Phase::Planning { plans }, Decision::Plan(actions)) => {
+                plans.insert(player_id, actions.clone());
+            }
+            _ => {
+                for operation in operations {
+                    events.extend(game.execute_operation(operation));
+                }
+            }
+        }
+        self.pending.remove(index);
+
+        match &mut self.phase {
+            Phase::Research => {
+                if self.pending.is_empty() {
+                    self.begin_action_phase(game, &mut events);
+                }
+            }
+            Phase::Planning { .. } => {
+                if self.pending.is_empty() {
+                    self.resolve_plans(game, &mut events);
+                }
+            }
+            Phase::Actions { passed } => {
+                if decision == Decision::Pass {
+                    passed.insert(player_id);
+                }
+                self.request_next_turn(game, Some(player_id), &mut events);
+            }
+            Phase::FinalGreeneries { waiting } => {
+                waiting.retain(|waiting_id| *waiting_id != player_id);
+                self.request_next_conversion(game);
+            }
+            Phase::Over => unreachable!(),
+        }
+
+        Ok(events)
+    }
+
+    fn request(&mut self, game: &GameState, player_id: PlayerId, kind: DecisionKind) {
+        self.pending.push(DecisionRequest {
+            id: self.next_request_id,
+            player_id,
+            generation: game.generation,
+            kind,
+        });
+        self.next_request_id += 1;
+    }
+
+    fn begin_research_phase(&mut self, game: &mut GameState, events: &mut Vec<GameEvent>) {
+        self.phase = Phase::Research;
+
+        let player_ids: Vec<PlayerId> = game.players.keys().copied().collect();
+        for player_id in player_ids {
+            let available_cards = game.draw_deck.len() + game.discard_pile.len();
+            let offer_size = game.research_offer_size().min(available_cards);
+            events.extend(game.execute_operation(GameOperation::DrawCards(player_id, offer_size)));
+
+            let hand = &game.players[&player_id].cards_in_hand;
+            let offered = hand[(hand.len() - offer_size)..].to_vec();
+            self.request(game, player_id, DecisionKind::Research(offered));
+        }
+    }
+
+    fn begin_action_phase(&mut self, game: &mut GameState, events: &mut Vec<GameEvent>) {
+        match game.options.turn_order {
+            TurnOrder::Sequential => {
+                self.phase = Phase::Actions {
+                    passed: BTreeSet::new(),
+                };
+                self.request_next_turn(game, None, events);
+            }
+            TurnOrder::Simultaneous => {
+                self.phase = Phase::Planning {
+                    plans: BTreeMap::new(),
+                };
+                let player_ids: Vec<PlayerId> = game.players.keys().copied().collect();
+                for player_id in player_ids {
+                    self.request(game, player_id, DecisionKind::PlanGeneration);
+                }
+            }
+        }
+    }
+
+    // Plans are resolved in rounds: in each round, every player with actions left takes
+    // their next one, in order of player ids. An action that is no longer legal when its
+    // turn comes is skipped as a whole. Then the generation ends.
+    fn resolve_plans(&mut self, game: &mut GameState, events: &mut Vec<GameEvent>) {
+        let plans = match &mut self.phase {
+            Phase::Planning { plans } => std::mem::take(plans),
+            _ => unreachable!(),
+        };
+
+        let rounds = plans.values().map(Vec::len).max().unwrap_or_default();
+        for round in 0..rounds {
+            for (player_id, actions) in &plans {
+                if let Some(action) = actions.get(round) {
+                    match game.play_turn_action(*player_id, action) {
+                        Ok(action_events) => events.extend(action_events),
+                        Err(error) => {
+                            events.push(GameEvent::PlannedActionSkipped(*player_id, error))
+                        }
+                    }
+                }
+            }
+        }
+
+        self.end_generation(game, events);
+    }
+
+    // Players take turns in order of their ids, starting from the generation's first player,
+    // until all of them have passed, after which the generation ends.
+    fn request_next_turn(
+        &mut self,
+        game: &mut GameState,
+        previous_player_id: Option<PlayerId>,
+        events: &mut Vec<GameEvent>,
+    ) {
+        let passed = match &self.phase {
+            Phase::Actions { passed } => passed,
+            Phase::Research
+            | Phase::Planning { .. }
+            | Phase::FinalGreeneries { .. }
+            | Phase::Over => unreachable!(),
+        };
+
+        let player_ids: Vec<PlayerId> = game.players.keys().copied().collect();
+        let seat = |player_id: PlayerId| player_ids.iter().position(|id| *id == player_id);
+        let start = match previous_player_id {
+            Some(previous) => seat(previous).map_or(0, |index| index + 1),
+            None => game.first_player().and_then(seat).unwrap_or(0),
+        };
+        let next_player_id = (0..player_ids.len())
+            .map(|offset| player_ids[(start + offset) % player_ids.len()])
+            .find(|player_id| !passed.contains(player_id));
+
+        match next_player_id {
+            Some(player_id) => self.request(game, player_id, DecisionKind::TakeTurn),
+            None => self.end_generation(game, events),
+        }
+    }
+
+    // Production, then the next generation's research phase, unless Mars is terraformed:
+    // then players with enough plants for a greenery may convert them, and the game is over.
+    fn end_generation(&mut self, game: &mut GameState, events: &mut Vec<GameEvent>) {
+        events.extend(game.end_generation());
+        if game.phase != GamePhase::FinalGreeneries {
+            self.begin_research_phase(game, events);
+            return;
+        }
+
+        let waiting = game
+            .players
+            .values()
+            .filter(|player| player.resources[Resource::Plants] >= PLANTS_PER_GREENERY)
+            .map(|player| player.player_id)
+            .collect();
+        self.phase = Phase::FinalGreeneries { waiting };
+        self.request_next_conversion(game);
+    }
+
+    // Players convert their plants one after another, in order of their ids,
+    // so that each of them sees where the others placed their greeneries.
+    fn request_next_conversion(&mut self, game: &mut GameState) {
+        let next_player_id = match &self.phase {
+            Phase::FinalGreeneries { waiting } => waiting.first().copied(),
+            _ => unreachable!(),
+        };
+        match next_player_id {
+            Some(player_id) => self.request(game, player_id, DecisionKind::ConvertPlants),
+            None => {
+                game.advance_phase();
+                self.phase = Phase::Over;
+            }
+        }
+    }
+
+    fn run_bots(&mut self, game: &mut GameState, events: &mut Vec<GameEvent>) {
+        while let Some(request) = self
+            .pending
+            .iter()
+            .find(|request| self.seats[&request.player_id] == Seat::Bot)
+            .cloned()
+        {
+            let decision = bot_decision(game, &request);
+            let bot_events = self
+                .apply_answer(game, request.player_id, request.id, decision)
+                .expect("bots only make legal decisions");
+            events.extend(bot_events);
+        }
+    }
+}
+
+fn research_operations(
+    player_id: PlayerId,
+    offered: &[Card],
+    bought: &[Card],
+) -> Result<Vec<GameOperation>, DecisionError> {
+    let mut not_bought: Vec<Card> = offered.to_vec();
+    for card in bought {
+        match not_bought
+            .iter()
+            .position(|offered_card| offered_card == card)
+        {
+            Some(index) => {
+                not_bought.remove(index);
+            }
+            None => return Err(DecisionError::CardNotOffered(card.name.clone())),
+        }
+    }
+
+    let mut operations = vec![];
+    if !bought.is_empty() {
+        let cost = (bought.len() * CARD_PURCHASE_COST) as isize;
+        operations.push(GameOperation::ChangeResources(
+            player_id,
+            btreemap! { Resource::Megacredits => -cost },
+        ));
+    }
+    if !not_bought.is_empty() {
+        operations.push(GameOperation::DiscardCards(player_id, not_bought));
+    }
+    Ok(operations)
+}
+
+// Each greenery is checked against the board with the greeneries before it.
+fn conversion_operations(
+    game: &GameState,
+    player_id: PlayerId,
+    spaces: &[Coordinates],
+) -> Result<Vec<GameOperation>, DecisionError> {
+    let mut scratch_game = game.clone();
+    let mut operations = vec![];
+    for coordinates in spaces {
+        let greenery = scratch_game
+            .greenery_conversion_operations(player_id, *coordinates)
+            .map_err(DecisionError::Illegal)?;
+        for operation in &greenery {
+            scratch_game.execute_operation(operation.clone());
+        }
+        operations.extend(greenery);
+    }
+    Ok(operations)
+}
+
+// Bots buy the cards that look best by the end of the generation, and then pass.
+// Once the game is over, they turn all the plants they can into greeneries on the best spaces.
+fn bot_decision(game: &GameState, request: &DecisionRequest) -> Decision {
+    match &request.kind {
+        DecisionKind::Research(offered) => {
+            let mut player: PlayerState = game.players[&request.player_id].clone();
+            player.cards_in_hand.retain(|card| !offered.contains(card));
+            let opponents: Vec<&PlayerState> = game
+                .players
+                .values()
+                .filter(|opponent| opponent.player_id != request.player_id)
+                .collect();
+
+            let best_purchase =
+                get_possible_generation_plays(&game.board, &player, &opponents, offered.clone())
+                    .into_iter()
+                    .max_by_key(|(bought, _, final_state)| {
+                        (
+                            final_state.get_total_victory_points(&game.board),
+                            final_state.resources[&Resource::Megacredits],
+                            std::cmp::Reverse(bought.len()),
+                        )
+                    })
+                    .map(|(bought, _, _)| bought)
+                    .unwrap_or_default();
+            Decision::BuyCards(best_purchase)
+        }
+        DecisionKind::TakeTurn => Decision::Pass,
+        DecisionKind::PlanGeneration => Decision::Plan(vec![]),
+        DecisionKind::ConvertPlants => {
+            let mut scratch_game = game.clone();
+            let mut spaces = vec![];
+            loop {
+                let player = &scratch_game.players[&request.player_id];
+                let best_space = make_heat_map(&scratch_game.board, player)
+                    .best_space(PlacementKind::Greenery)
+                    .map(|space| space.coordinates);
+                match best_space {
+                    Some(coordinates)
+                        if scratch_game
+                            .convert_plants(request.player_id, coordinates)
+                            .is_ok() =>
+                    {
+                        spaces.push(coordinates)
+                    }
+                    _ => break,
+                }
+            }
+            Decision::ConvertPlants(spaces)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::{Coordinates, TileLocation},
+        decision::{AsyncGame, Decision, DecisionError, DecisionKind, Seat},
+        event::GameEvent,
+        game::{IllegalOperation, PlayerId, StandardProject, TurnAction},
+        game_setup::STARTING_HAND_SIZE,
+        position::import_position,
+        resource::Resource,
+        scoring::score_all_players,
+    };
+
+    #[test]
+    fn bots_answer_immediately_and_humans_are_waited_for() {
+        let mut game =
+            import_position("player p1\nresources 20 0 0 0 0 0\nplayer p2\nresources 10 0 0 0 0 0")
+                .unwrap();
+        let human = PlayerId::from(1);
+        let bot = PlayerId::from(2);
+
+        let (mut async_game, _) = AsyncGame::start(
+            &mut game,
+            btreemap! { human => Seat::Human, bot => Seat::Bot },
+        );
+
+        // The bot has already made its research decision.
+        assert_eq!(1, async_game.pending.len());
+        let request = async_game.pending[0].clone();
+        assert_eq!(human, request.player_id);
+        let offered = match &request.kind {
+            DecisionKind::Research(offered) => offered.clone(),
+            kind => panic!("unexpected decision: {:?}", kind),
+        };
+        // The first generation's research deals the starting hands.
+        assert_eq!(STARTING_HAND_SIZE, offered.len());
+        assert!(game.players[&bot].cards_in_hand.len() <= STARTING_HAND_SIZE);
+
+        // Rejected answers change nothing.
+        let before = (game.clone(), async_game.clone());
+        assert_eq!(
+            Err(DecisionError::NotYourDecision(request.id)),
+            async_game.answer(&mut game, bot, request.id, Decision::BuyCards(vec![]))
+        );
+        assert_eq!(
+            Err(DecisionError::WrongKindOfDecision),
+            async_game.answer(&mut game, human, request.id, Decision::Pass)
+        );
+        assert_eq!(before, (game.clone(), async_game.clone()));
+
+        async_game
+            .answer(
+                &mut game,
+                human,
+                request.id,
+                Decision::BuyCards(offered[..2].to_vec()),
+            )
+            .unwrap();
+        assert_eq!(offered[..2], game.players[&human].cards_in_hand[..]);
+        assert_eq!(14, game.players[&human].resources[&Resource::Megacredits]);
+
+        // Action phase: the human goes first, then the bot passes, and so on.
+        let turn = async_game.pending[0].clone();
+        assert_eq!((human, DecisionKind::TakeTurn), (turn.player_id, turn.kind));
+        async_game
+            .answer(
+                &mut game,
+                human,
+                turn.id,
+                Decision::Act(TurnAction::PlayStandardProject(StandardProject::PowerPlant)),
+            )
+            .unwrap();
+        assert_eq!(3, game.players[&human].resources[&Resource::Megacredits]);
+        assert_eq!(1, game.players[&human].production[Resource::Energy]);
+
+        let turn = async_game.pending[0].clone();
+        assert_eq!(human, turn.player_id);
+        async_game
+            .answer(&mut game, human, turn.id, Decision::Pass)
+            .unwrap();
+
+        // Everyone passed: production, then the next generation's research phase.
+        assert_eq!(2, game.generation);
+        assert_eq!(
+            3 + 20,
+            game.players[&human].resources[&Resource::Megacredits]
+        );
+        assert!(matches!(
+            async_game.pending[0].kind,
+            DecisionKind::Research(_)
+        ));
+    }
+
+    #[test]
+    fn turns_are_checked_like_any_other_turn() {
+        let mut game = import_position("player p1\nplayer p2").unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let (mut async_game, _) = AsyncGame::start(
+            &mut game,
+            btreemap! { p1 => Seat::Human, p2 => Seat::Human },
+        );
+
+        for request in async_game.pending.clone() {
+            async_game
+                .answer(
+                    &mut game,
+                    request.player_id,
+                    request.id,
+                    Decision::BuyCards(vec![]),
+                )
+                .unwrap();
+        }
+
+        let turn = async_game.pending[0].clone();
+        let before = (game.clone(), async_game.clone());
+        assert_eq!(
+            Err(DecisionError::Illegal(IllegalOperation::InsufficientResource(
+                Resource::Megacredits
+            ))),
+            async_game.answer(
+                &mut game,
+                p1,
+                turn.id,
+                Decision::Act(TurnAction::PlayStandardProject(StandardProject::PowerPlant))
+            )
+        );
+        assert_eq!(before, (game, async_game));
+    }
+
+    #[test]
+    fn simultaneous_plans_are_resolved_together_in_player_order() {
+        let mut game = import_position(
+            "turn-order simultaneous
+             player p1
+             resources 30 0 0 0 0 0
+             player p2
+             resources 40 0 0 0 0 0",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+        let (mut async_game, _) = AsyncGame::start(
+            &mut game,
+            btreemap! { p1 => Seat::Human, p2 => Seat::Human },
+        );
+        for request in async_game.pending.clone() {
+            async_game
+                .answer(
+                    &mut game,
+                    request.player_id,
+                    request.id,
+                    Decision::BuyCards(vec![]),
+                )
+                .unwrap();
+        }
+
+        // Both players plan at once, and nothing happens until both plans are in.
+        let requests = async_game.pending.clone();
+        assert_eq!(2, requests.len());
+        assert!(requests
+            .iter()
+            .all(|request| request.kind == DecisionKind::PlanGeneration));
+
+        let contested = Coordinates::new(1, 0);
+        let greenery =
+            |coordinates| TurnAction::PlayStandardProject(StandardProject::Greenery(coordinates));
+        let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+
+        // Each plan must be affordable on its own.
+        assert_eq!(
+            Err(DecisionError::Illegal(IllegalOperation::InsufficientResource(
+                Resource::Megacredits
+            ))),
+            async_game.answer(
+                &mut game,
+                p1,
+                requests[0].id,
+                Decision::Plan(vec![greenery(contested), power_plant.clone()])
+            )
+        );
+
+        let events = async_game
+            .answer(
+                &mut game,
+                p2,
+                requests[1].id,
+                Decision::Plan(vec![greenery(contested), power_plant]),
+            )
+            .unwrap();
+        assert!(events.is_empty());
+        assert!(game.board.greeneries.is_empty());
+
+        // Player 1 resolves first, so player 2's greenery no longer fits.
+        let events = async_game
+            .answer(
+                &mut game,
+                p1,
+                requests[0].id,
+                Decision::Plan(vec![greenery(contested)]),
+            )
+            .unwrap();
+        assert_eq!(Some(&p1), game.board.greeneries.get(&contested));
+        assert!(events.contains(&GameEvent::PlannedActionSkipped(
+            p2,
+            IllegalOperation::SpaceOccupied(TileLocation::OnMars(contested))
+        )));
+        assert_eq!(1, game.board.oxygen);
+        assert_eq!(1, game.players[&p2].production[Resource::Energy]);
+
+        // The generation is over.
+        assert_eq!(2, game.generation);
+        assert!(async_game
+            .pending
+            .iter()
+            .all(|request| matches!(request.kind, DecisionKind::Research(_))));
+    }
+
+    #[test]
+    fn plants_are_converted_one_last_time_after_mars_is_terraformed() {
+        let mut game = import_position(
+            "oxygen 14
+             temperature 8
+             ocean 5,-1
+             ocean 3,-3
+             ocean 7,-3
+             ocean 4,-4
+             ocean 8,-4
+             ocean 5,-5
+             ocean 8,-5
+             ocean 5,-6
+             ocean 6,-7
+             player p1
+             resources 0 0 0 8 0 0
+             player p2
+             resources 0 0 0 17 0 0
+             player p3
+             resources 0 0 0 7 0 0",
+        )
+        .unwrap();
+        let (p1, p2, p3) = (PlayerId::from(1), PlayerId::from(2), PlayerId::from(3));
+        let (mut async_game, _) = AsyncGame::start(
+            &mut game,
+            btreemap! { p1 => Seat::Human, p2 => Seat::Bot, p3 => Seat::Bot },
+        );
+        let research = async_game.pending[0].id;
+        async_game
+            .answer(&mut game, p1, research, Decision::BuyCards(vec![]))
+            .unwrap();
+        let turn = async_game.pending[0].id;
+        async_game
+            .answer(&mut game, p1, turn, Decision::Pass)
+            .unwrap();
+
+        // Mars is terraformed, so there is no next generation; p3 is short of a greenery.
+        assert_eq!(1, game.generation);
+        let conversion = async_game.pending[0].clone();
+        assert_eq!(
+            (p1, DecisionKind::ConvertPlants),
+            (conversion.player_id, conversion.kind)
+        );
+        assert_eq!(
+            Err(DecisionError::Illegal(IllegalOperation::InsufficientResource(
+                Resource::Plants
+            ))),
+            async_game.answer(
+                &mut game,
+                p1,
+                conversion.id,
+                Decision::ConvertPlants(vec![Coordinates::new(3, -1), Coordinates::new(3, 0)])
+            )
+        );
+        async_game
+            .answer(
+                &mut game,
+                p1,
+                conversion.id,
+                Decision::ConvertPlants(vec![Coordinates::new(3, -1)]),
+            )
+            .unwrap();
+
+        // The bot turns all the plants it can into greeneries, and then the game is over.
+        assert!(async_game.pending.is_empty());
+        assert!(game.is_over());
+        assert!(game.players[&p2].resources[Resource::Plants] < 8);
+        let greeneries = |player_id| {
+            game.board
+                .greeneries
+                .values()
+                .filter(|owner| **owner == player_id)
+                .count()
+        };
+        assert_eq!((1, 2, 0), (greeneries(p1), greeneries(p2), greeneries(p3)));
+        let scores = score_all_players(&game);
+        assert_eq!(1, scores[0].1.greeneries);
+        assert_eq!(2, scores[1].1.greeneries);
+    }
+}
@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CardEffect, CardTag, ImmediateImpact, VictoryPointValue},
+    card_ref,
+    game::{GameState, PlayerState, RESEARCH_PHASE_OFFER_SIZE},
+    knowledge::KnowledgeState,
+    options::GameOptions,
+    projection::{estimate_game_generations_left, ProjectionAssumptions},
+    resource::Resource,
+    synergy::{synergies_for_card, SynergyKind},
+};
+
+// Rough exchange rates used to put cards on a common scale; all card values are in megacredits.
+const MEGACREDITS_PER_VICTORY_POINT: f64 = 5.0;
+const MEGACREDITS_PER_TERRAFORM_RATING: f64 = 10.0; // 1VP plus income for the rest of the game
+const MEGACREDITS_PER_CARD: f64 = 3.0;
+
+// Value of one effect trigger or one card resource added to a host card.
+const MEGACREDITS_PER_TRIGGER: f64 = 2.0;
+const MEGACREDITS_PER_FED_HOST: f64 = 3.0;
+
+// Share of the cards seen in future research phases that a player typically buys.
+const BOUGHT_CARD_FRACTION: f64 = 0.5;
+
+/// Chooses one card at a time from the packs passed around during the draft variant.
+pub trait DraftAgent {
+    fn pick(&mut self, game: &GameState, knowledge: &KnowledgeState, pack: &[Card]) -> DraftPick;
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DraftPick {
+    #[serde(with = "card_ref::card")]
+    pub card: Card,
+    pub value: f64, // in megacredits
+    pub rationale: String,
+}
+
+/// Drafts the card with the highest estimated value for the player's hand.
+///
+/// A card's value combines what it is worth on its own, what the cards the player
+/// is still likely to draw make of its discounts and effects, and its synergies with
+/// the cards already in the player's hand or in play.
+#[derive(Clone, Debug)]
+pub struct SynergyDraftBot {
+    pub assumptions: ProjectionAssumptions,
+
+    // all cards that may be in the game, used to estimate draw odds
+    pub deck: Vec<Card>,
+}
+
+impl Default for SynergyDraftBot {
+    fn default() -> Self {
+        Self::for_options(&GameOptions::default())
+    }
+}
+
+impl SynergyDraftBot {
+    pub fn for_options(options: &GameOptions) -> Self {
+        Self {
+            assumptions: ProjectionAssumptions::default(),
+            deck: options.deck(),
+        }
+    }
+
+    /// Value every card of the pack, best pick first. Ties keep the pack order.
+    /// Cards the game does not allow, e.g. banned ones, are left out.
+    pub fn rank_pack(
+        &self,
+        game: &GameState,
+        knowledge: &KnowledgeState,
+        pack: &[Card],
+    ) -> Vec<DraftPick> {
+        let player = &game.players[&knowledge.player_id];
+        let remaining_generations = estimate_game_generations_left(game, &self.assumptions);
+
+        let mut picks: Vec<DraftPick> = pack
+            .iter()
+            .filter(|card| game.options.allows(card))
+            .map(|card| {
+                let mut reasons = vec![];
+                let value = standalone_value(card, remaining_generations, &mut reasons)
+                    + self.engine_value(card, knowledge, remaining_generations, &mut reasons)
+                    + synergy_value(card, player, &mut reasons);
+
+                DraftPick {
+                    card: card.clone(),
+                    value,
+                    rationale: format!("{}: {:.1} M$ ({})", card.name, value, reasons.join("; ")),
+                }
+            })
+            .collect();
+        picks.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap());
+        picks
+    }
+
+    // Discounts and tag-triggered effects pay off once per matching card played later on,
+    // so their value depends on the odds of drawing such cards.
+    fn engine_value(
+        &self,
+        card: &Card,
+        knowledge: &KnowledgeState,
+        remaining_generations: usize,
+        reasons: &mut Vec<String>,
+    ) -> f64 {
+        let future_purchases =
+            (remaining_generations * RESEARCH_PHASE_OFFER_SIZE) as f64 * BOUGHT_CARD_FRACTION;
+        let mut tag_odds = |tag: CardTag| {
+            let odds = knowledge.draw_probability(&self.deck, |other| other.tags.contains(&tag));
+            reasons.push(format!(
+                "{:.0}% of unseen cards have a {:?} tag",
+                odds * 100.0,
+                tag
+            ));
+            odds
+        };
+
+        let mut value = 0.0;
+        for effect in &card.effects {
+            value += match effect {
+                CardEffect::AnyCardDiscount(amount) => *amount as f64 * future_purchases,
+                CardEffect::CardDiscountForTag(tag, amount) => {
+                    *amount as f64 * tag_odds(*tag) * future_purchases
+                }
+                CardEffect::OnOwnTagPlayed(tag, _) | CardEffect::OnAnyTagPlayed(tag, _) => {
+                    MEGACREDITS_PER_TRIGGER * tag_odds(*tag) * future_purchases
+                }
+                _ => 0.0,
+            };
+        }
+        value
+    }
+}
+
+impl DraftAgent for SynergyDraftBot {
+    fn pick(&mut self, game: &GameState, knowledge: &KnowledgeState, pack: &[Card]) -> DraftPick {
+        let mut picks = self.rank_pack(game, knowledge, pack).into_iter();
+        let mut best = picks
+            .next()
+            .expect("cannot pick from a pack without allowed cards");
+        if let Some(runner_up) = picks.next() {
+            best.rationale = format!(
+                "{}; ahead of {} ({:.1} M$)",
+                best.rationale, runner_up.card.name, runner_up.value
+            );
+        }
+        best
+    }
+}
+
+fn resource_value(resource: Resource) -> f64 {
+    match resource {
+        Resource::Megacredits => 1.0,
+        Resource::Steel => 2.0,
+        Resource::Titanium => 3.0,
+        Resource::Plants => 2.0,
+        Resource::Energy => 1.5,
+        Resource::Heat => 1.0,
+    }
+}
+
+// What the card is worth when played, regardless of the player's other cards.
+fn standalone_value(card: &Card, remaining_generations: usize, reasons: &mut Vec<String>) -> f64 {
+    let cost = card.cost.amount();
+    let mut value = -(cost as f64);
+    reasons.push(format!("costs {} M$", cost));
+
+    if let Some(VictoryPointValue::Immediate(points)) = card.points {
+        value += points as f64 * MEGACREDITS_PER_VICTORY_POINT;
+        reasons.push(format!("{} VP", points));
+    }
+
+    for (resource, change) in &card.own_production {
+        value += *change as f64 * resource_value(*resource) * remaining_generations as f64;
+        reasons.push(format!(
+            "{:+} {:?} production for {} generation(s)",
+            change, resource, remaining_generations
+        ));
+    }
+
+    let mut terraforming_steps = 0;
+    for impact in &card.immediate_impacts {
+        value += match impact {
+            ImmediateImpact::RaiseTemperature
+            | ImmediateImpact::RaiseOxygen
+            | ImmediateImpact::RaiseTerraformRating
+            | ImmediateImpact::PlaceOcean(_) => {
+                terraforming_steps += 1;
+                MEGACREDITS_PER_TERRAFORM_RATING
+            }
+            ImmediateImpact::PlaceGreenery(_) => {
+                terraforming_steps += 1;
+                MEGACREDITS_PER_TERRAFORM_RATING + MEGACREDITS_PER_VICTORY_POINT
+            }
+            ImmediateImpact::DrawCard(count) => *count as f64 * MEGACREDITS_PER_CARD,
+            ImmediateImpact::GainResource(resource, amount) => {
+                *amount as f64 * resource_value(*resource)
+            }
+            _ => 0.0,
+        };
+    }
+    if terraforming_steps > 0 {
+        reasons.push(format!("{} terraforming step(s)", terraforming_steps));
+    }
+
+    value
+}
+
+fn synergy_value(card: &Card, player: &PlayerState, reasons: &mut Vec<String>) -> f64 {
+    let synergies = synergies_for_card(card, &player.cards_in_hand, &player.played_cards);
+
+    let mut value = 0.0;
+    for synergy in &synergies {
+        value += match synergy.kind {
+            SynergyKind::Discount(_, amount) => amount as f64,
+            SynergyKind::TagTrigger(_) => MEGACREDITS_PER_TRIGGER,
+            SynergyKind::ResourceFeeder(_) => MEGACREDITS_PER_FED_HOST,
+        };
+        let partner = if synergy.enabler == *card {
+            &synergy.beneficiary
+        } else {
+            &synergy.enabler
+        };
+        reasons.push(format!("synergy with {}", partner.name));
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{Card, CORPORATE_GAME_CARDS_BY_NAME},
+        draft::{DraftAgent, SynergyDraftBot},
+        game::PlayerId,
+        knowledge::KnowledgeTracker,
+        position::import_position,
+        projection::ProjectionAssumptions,
+    };
+
+    fn cards(names: &[&str]) -> Vec<Card> {
+        names
+            .iter()
+            .map(|name| CORPORATE_GAME_CARDS_BY_NAME[name].clone())
+            .collect()
+    }
+
+    #[test]
+    fn synergies_tip_the_pick_and_are_explained() {
+        let pack = cards(&["Heather", "Trans-Neptune Probe"]);
+        let mut bot = SynergyDraftBot {
+            assumptions: ProjectionAssumptions {
+                remaining_generations: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let game = import_position("player p1\n").unwrap();
+        let knowledge = &KnowledgeTracker::new(&game).players[&PlayerId::from(1)];
+        let without_synergy = bot.pick(&game, knowledge, &pack);
+        assert_eq!("Heather", without_synergy.card.name);
+
+        let game = import_position("player p1\nplayed Space Station\n").unwrap();
+        let knowledge = &KnowledgeTracker::new(&game).players[&PlayerId::from(1)];
+        let with_synergy = bot.pick(&game, knowledge, &pack);
+        assert_eq!("Trans-Neptune Probe", with_synergy.card.name);
+        assert!(with_synergy.rationale.starts_with("Trans-Neptune Probe: "));
+        assert!(with_synergy
+            .rationale
+            .contains("synergy with Space Station"));
+        assert!(with_synergy
+            .rationale
+            .ends_with("ahead of Heather (0.0 M$)"));
+    }
+
+    #[test]
+    fn engine_cards_are_valued_by_draw_odds() {
+        let bot = SynergyDraftBot::default();
+        let game = import_position("generation 1\nplayer p1\n").unwrap();
+        let knowledge = &KnowledgeTracker::new(&game).players[&PlayerId::from(1)];
+
+        let picks = bot.rank_pack(&game, knowledge, &cards(&["Earth Office"]));
+        assert!(picks[0]
+            .rationale
+            .contains("% of unseen cards have a Earth tag"));
+        assert!(picks[0].value > 0.0);
+    }
+
+    #[test]
+    fn cards_the_game_does_not_allow_are_never_picked() {
+        let pack = cards(&["Heather", "Trans-Neptune Probe"]);
+        let game = import_position("ban Heather\nplayer p1\n").unwrap();
+        let knowledge = &KnowledgeTracker::new(&game).players[&PlayerId::from(1)];
+
+        let mut bot = SynergyDraftBot::for_options(&game.options);
+        assert!(bot.deck.iter().all(|card| card.name != "Heather"));
+        assert_eq!(1, bot.rank_pack(&game, knowledge, &pack).len());
+        assert_eq!(
+            "Trans-Neptune Probe",
+            bot.pick(&game, knowledge, &pack).card.name
+        );
+    }
+}
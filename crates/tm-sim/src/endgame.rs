@@ -0,0 +1,213 @@
+//! Search of the card plays in the last generations of a game, for a single player.
+//!
+//! Near the end of the game, few cards are left to play and no future research phase matters,
+//! so every order of playing the cards in hand can be tried. The search is exhaustive over
+//! those orders, except for lines cut off because not even the upper bound of
+//! `final_vp_bounds()` beats the best line found so far, so no order of card plays scores
+//! more than the one it returns.
+//!
+//! Only cards are played: standard projects, plant and heat conversions, and award funding
+//! are not searched, so a line using them as well may score more.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::MarsBoard,
+    bounds::final_vp_bounds,
+    canonical::canonicalize_actions,
+    game::{PlayerState, TurnAction},
+    options::ProductionStep,
+    resource::ResourceMap,
+};
+
+/// Card plays are searched exhaustively only when the game ends within this many generations...
+pub const ENDGAME_MAX_GENERATIONS: usize = 2;
+
+/// ...and the player has at most this many cards to choose from, counting offered cards.
+pub const ENDGAME_MAX_CARDS: usize = 10;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EndgameSolution {
+    // the cards to play in each remaining generation, in order, in canonical form
+    pub plays: Vec<Vec<TurnAction>>,
+    pub final_state: PlayerState,
+    pub final_points: isize,
+
+    // number of positions visited, to see how much the bounds and deduplication saved
+    pub nodes_searched: usize,
+}
+
+/// The card plays that end the game with the most victory points, if the game ends after
+/// the given number of generations, counting the current one.
+///
+/// No cards are bought in later generations, no actions other than playing cards are taken,
+/// and the player's opponents are not considered.
+pub fn solve_endgame(
+    board: &MarsBoard,
+    state: &PlayerState,
+    generations: usize,
+) -> EndgameSolution {
+    assert!(generations >= 1);
+
+    let mut solver = Solver {
+        board,
+        generations,
+        line: vec![vec![]],
+        best: None,
+        visited: HashSet::new(),
+        nodes_searched: 0,
+    };
+    solver.search(state, 0);
+
+    let (plays, final_state, final_points) = solver.best.unwrap();
+    EndgameSolution {
+        plays: plays
+            .iter()
+            .map(|actions| canonicalize_actions(actions))
+            .collect(),
+        final_state,
+        final_points,
+        nodes_searched: solver.nodes_searched,
+    }
+}
+
+// (generation, names of the cards in hand, resources, production, terraform rating)
+type SearchedPosition = (usize, Vec<String>, ResourceMap<usize>, ResourceMap<isize>, usize);
+
+struct Solver<'a> {
+    board: &'a MarsBoard,
+    generations: usize,
+
+    line: Vec<Vec<TurnAction>>, // the line leading to the position being searched
+    best: Option<(Vec<Vec<TurnAction>>, PlayerState, isize)>,
+
+    // every position searched; positions reached by playing the same cards in another order
+    // are only searched once
+    visited: HashSet<SearchedPosition>,
+    nodes_searched: usize,
+}
+
+impl<'a> Solver<'a> {
+    fn search(&mut self, state: &PlayerState, generation: usize) {
+        let mut hand: Vec<String> = state
+            .cards_in_hand
+            .iter()
+            .map(|card| card.name.clone())
+            .collect();
+        hand.sort_unstable();
+        let position = (
+            generation,
+            hand,
+            state.resources,
+            state.production,
+            state.terraform_rating,
+        );
+        if !self.visited.insert(position) {
+            return;
+        }
+        self.nodes_searched += 1;
+
+        if let Some((_, _, best_points)) = &self.best {
+            let generations_left = self.generations - generation;
+            if final_vp_bounds(self.board, state, generations_left).upper <= *best_points {
+                return;
+            }
+        }
+
+        // Playing cards first finds good lines early, so that more of the rest is cut off.
+        let mut tried: HashSet<&str> = HashSet::new();
+        for card in &state.cards_in_hand {
+            if !tried.insert(&card.name) {
+                continue;
+            }
+            let mut next_state = state.clone();
+            if next_state.play_card(self.board, card).is_some() {
+                self.line
+                    .last_mut()
+                    .unwrap()
+                    .push(TurnAction::PlayCard(card.clone(), vec![]));
+                self.search(&next_state, generation);
+                self.line.last_mut().unwrap().pop();
+            }
+        }
+
+        if generation + 1 == self.generations {
+            let points = state.get_total_victory_points(self.board);
+            let is_better = match &self.best {
+                Some((_, _, best_points)) => points > *best_points,
+                None => true,
+            };
+            if is_better {
+                self.best = Some((self.line.clone(), state.clone(), points));
+            }
+        } else {
+            let mut next_state = state.clone();
+            next_state.advance_generation(&ProductionStep::STANDARD);
+
+            self.line.push(vec![]);
+            self.search(&next_state, generation + 1);
+            self.line.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        board::make_base_game_board,
+        card::{Card, CardKind, VictoryPointValue},
+        endgame::solve_endgame,
+        game::{PlayerStateBuilder, TurnAction},
+        resource::PaymentCost,
+    };
+
+    fn card(name: &str, cost: usize, points: isize) -> Card {
+        Card::new(
+            name.to_string(),
+            CardKind::Automatic,
+            vec![],
+            PaymentCost::Megacredits(cost),
+            vec![],
+            Some(VictoryPointValue::Immediate(points)),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn the_solver_saves_up_for_the_cards_worth_the_most() {
+        let board = make_base_game_board();
+        // 10 M$ now, and 20 more from the terraform rating in the last generation.
+        let medium = card("Medium", 10, 2);
+        let costly = card("Costly", 28, 5);
+        let state = PlayerStateBuilder::new(1)
+            .with_resources(10, 0, 0, 0, 0, 0)
+            .with_cards_in_hand(vec![
+                medium.clone(),
+                costly.clone(),
+                card("Cheap", 2, 1),
+                card("Penalty", 0, -1),
+            ])
+            .build();
+
+        // Playing the medium card right away would leave too little for the costly one.
+        let solution = solve_endgame(&board, &state, 2);
+        assert_eq!(20 + 5 + 1, solution.final_points);
+        let played: Vec<&TurnAction> = solution.plays.iter().flatten().collect();
+        assert!(played.contains(&&TurnAction::PlayCard(costly, vec![])));
+        assert!(!played.contains(&&TurnAction::PlayCard(medium, vec![])));
+        assert_eq!(2, solution.final_state.cards_in_hand.len());
+
+        // Within a single generation, only what is affordable now can be played.
+        let solution = solve_endgame(&board, &state, 1);
+        assert_eq!(20 + 2, solution.final_points);
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{GameState, PlayerId},
+    projection::{estimate_game_generations_left, project_final_score, ProjectionAssumptions},
+    resource::Resource,
+    threat::plant_risk,
+};
+
+/// Score a game state from the point of view of the given player; higher is better.
+///
+/// The score is the player's projected final victory points minus those of the best opponent,
+/// so that it is comparable across the different stages of the game, less the points the player
+/// expects to lose to attacks on a stockpile of plants.
+pub fn evaluate(game: &GameState, player_id: PlayerId, assumptions: &ProjectionAssumptions) -> f64 {
+    let own_points = project_final_score(game, player_id, assumptions).total_points;
+    let best_opponent_points = game
+        .players
+        .keys()
+        .filter(|other_id| **other_id != player_id)
+        .map(|other_id| project_final_score(game, *other_id, assumptions).total_points)
+        .max()
+        .unwrap_or_default();
+
+    (own_points - best_opponent_points) as f64 - plant_risk(game, player_id)
+}
+
+/// How many standard errors of their difference one value must be ahead of another
+/// to be clearly better, rather than roughly equal.
+pub const CLEARLY_BETTER_MARGIN: f64 = 2.0;
+
+/// Whether a value is clearly better than another, given the standard error of each.
+/// Exact values, without any error, are clearly better whenever they are higher.
+pub fn is_clearly_better(value: f64, error: f64, other_value: f64, other_error: f64) -> bool {
+    let difference_error = (error * error + other_error * other_error).sqrt();
+    value > other_value + CLEARLY_BETTER_MARGIN * difference_error
+}
+
+/// An evaluation, and how far it can be trusted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EvalEstimate {
+    pub value: f64,
+    pub spread: f64, // standard deviation over the evaluators that were averaged
+}
+
+impl EvalEstimate {
+    pub fn is_clearly_better_than(&self, other: &EvalEstimate) -> bool {
+        is_clearly_better(self.value, self.spread, other.value, other.spread)
+    }
+}
+
+impl fmt::Display for EvalEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:+.1} \u{b1} {:.1}", self.value, self.spread)
+    }
+}
+
+/// `evaluate()`, averaged over an ensemble of projections that disagree on how long the game
+/// lasts: one generation shorter than estimated, as estimated, and one generation longer.
+/// If the assumptions give the number of generations left, it is taken as certain.
+pub fn evaluate_ensemble(
+    game: &GameState,
+    player_id: PlayerId,
+    assumptions: &ProjectionAssumptions,
+) -> EvalEstimate {
+    let values: Vec<f64> = if assumptions.remaining_generations.is_some() {
+        vec![evaluate(game, player_id, assumptions)]
+    } else {
+        let estimate = estimate_game_generations_left(game, assumptions);
+        (estimate.saturating_sub(1).max(1)..=estimate + 1)
+            .map(|generations| {
+                let member = ProjectionAssumptions {
+                    remaining_generations: Some(generations),
+                    ..assumptions.clone()
+                };
+                evaluate(game, player_id, &member)
+            })
+            .collect()
+    };
+
+    let count = values.len() as f64;
+    let value = values.iter().sum::<f64>() / count;
+    let variance = values
+        .iter()
+        .map(|member| (member - value) * (member - value))
+        .sum::<f64>()
+        / count;
+    EvalEstimate {
+        value,
+        spread: variance.sqrt(),
+    }
+}
+
+/// How much each feature of a state contributed to its evaluation, to explain
+/// why the engine prefers one state over another.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EvalTrace {
+    pub player_id: PlayerId,
+
+    // feature -> contribution, in a fixed order; the contributions add up to the value
+    pub contributions: Vec<(String, f64)>,
+    pub value: f64,
+}
+
+/// `evaluate()`, broken down into the contribution of each feature.
+pub fn evaluate_with_trace(
+    game: &GameState,
+    player_id: PlayerId,
+    assumptions: &ProjectionAssumptions,
+) -> EvalTrace {
+    let player = &game.players[&player_id];
+    let breakdown = player.score_breakdown(&game.board);
+    let projection = project_final_score(game, player_id, assumptions);
+
+    let mut contributions = vec![
+        ("terraform rating".to_string(), breakdown.terraform_rating),
+        ("card VP".to_string(), breakdown.cards),
+        ("greenery tiles".to_string(), breakdown.greeneries),
+        ("city adjacency".to_string(), breakdown.city_adjacency),
+        ("capital adjacency".to_string(), breakdown.capital_adjacency),
+        (
+            "projected greeneries".to_string(),
+            projection.greenery_points,
+        ),
+        (
+            "projected temperature".to_string(),
+            projection.temperature_points,
+        ),
+    ];
+
+    let best_opponent = game
+        .players
+        .keys()
+        .filter(|other_id| **other_id != player_id)
+        .map(|other_id| project_final_score(game, *other_id, assumptions))
+        .max_by_key(|projection| projection.total_points);
+    if let Some(opponent) = best_opponent {
+        contributions.push((
+            format!("best opponent ({:?})", opponent.player_id),
+            -opponent.total_points,
+        ));
+    }
+
+    let mut contributions: Vec<(String, f64)> = contributions
+        .into_iter()
+        .map(|(feature, points)| (feature, points as f64))
+        .collect();
+    contributions.push(("plants at risk".to_string(), -plant_risk(game, player_id)));
+    let value = contributions.iter().map(|(_, contribution)| contribution).sum();
+    EvalTrace {
+        player_id,
+        contributions,
+        value,
+    }
+}
+
+/// How much each group of features of `evaluate_with_trace()` counts, for evaluators tuned
+/// away from plain victory points; the default weights evaluate exactly like `evaluate()`.
+/// The megacredit features are not part of `evaluate()`, so they count for nothing by default.
+/// Weights missing from serialized sets take their default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvalWeights {
+    pub terraform_rating: f64,
+    pub card_points: f64,
+    pub tile_points: f64,      // greeneries and the adjacency of cities and capitals
+    pub projected_points: f64, // greeneries and temperature raises still to come
+    pub best_opponent: f64,
+    pub plant_risk: f64,
+    pub megacredits: f64,
+    pub megacredit_production: f64,
+}
+
+impl Default for EvalWeights {
+    fn default() -> Self {
+        Self {
+            terraform_rating: 1.0,
+            card_points: 1.0,
+            tile_points: 1.0,
+            projected_points: 1.0,
+            best_opponent: 1.0,
+            plant_risk: 1.0,
+            megacredits: 0.0,
+            megacredit_production: 0.0,
+        }
+    }
+}
+
+impl EvalWeights {
+    pub const COUNT: usize = 8;
+
+    /// The weights in the order of their fields, for optimizers that work on plain vectors.
+    pub fn to_vec(&self) -> Vec<f64> {
+        vec![
+            self.terraform_rating,
+            self.card_points,
+            self.tile_points,
+            self.projected_points,
+            self.best_opponent,
+            self.plant_risk,
+            self.megacredits,
+            self.megacredit_production,
+        ]
+    }
+
+    pub fn from_slice(values: &[f64]) -> Self {
+        assert_eq!(Self::COUNT, values.len());
+        Self {
+            terraform_rating: values[0],
+            card_points: values[1],
+            tile_points: values[2],
+            projected_points: values[3],
+            best_opponent: values[4],
+            plant_risk: values[5],
+            megacredits: values[6],
+            megacredit_production: values[7],
+        }
+    }
+
+    fn weight_of(&self, feature: &str) -> f64 {
+        match feature {
+            "terraform rating" => self.terraform_rating,
+            "card VP" => self.card_points,
+            "greenery tiles" | "city adjacency" | "capital adjacency" => self.tile_points,
+            "projected greeneries" | "projected temperature" => self.projected_points,
+            "plants at risk" => self.plant_risk,
+            feature if feature.starts_with("best opponent") => self.best_opponent,
+            _ => 1.0,
+        }
+    }
+}
+
+/// `evaluate()`, with every feature scaled by its weight.
+pub fn weighted_evaluate(
+    game: &GameState,
+    player_id: PlayerId,
+    assumptions: &ProjectionAssumptions,
+    weights: &EvalWeights,
+) -> f64 {
+    let player = &game.players[&player_id];
+    let trace = evaluate_with_trace(game, player_id, assumptions);
+    let features: f64 = trace
+        .contributions
+        .iter()
+        .map(|(feature, contribution)| weights.weight_of(feature) * contribution)
+        .sum();
+
+    features
+        + weights.megacredits * player.resources[Resource::Megacredits] as f64
+        + weights.megacredit_production * player.production[Resource::Megacredits] as f64
+}
+
+impl fmt::Display for EvalTrace {
+    // Features that contribute nothing are left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (feature, contribution) in &self.contributions {
+            if *contribution != 0.0 {
+                write!(f, "{} {:+.1}, ", feature, contribution)?;
+            }
+        }
+        write!(f, "total {:+.1}", self.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval::{
+            evaluate, evaluate_ensemble, evaluate_with_trace, weighted_evaluate, EvalEstimate,
+            EvalWeights,
+        },
+        game::PlayerStateBuilder,
+        projection::ProjectionAssumptions,
+        test_utils::make_game,
+    };
+
+    #[test]
+    fn evaluation_is_the_projected_margin_over_the_best_opponent() {
+        let p1 = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 16, 0, 0)
+            .build();
+        let p2 = PlayerStateBuilder::new(2).build();
+        let (p1_id, p2_id) = (p1.player_id, p2.player_id);
+        let game = make_game(vec![p1, p2]);
+
+        let assumptions = ProjectionAssumptions::default();
+
+        // Two greeneries from plants, each worth 1VP and 1TR.
+        assert_eq!(4.0, evaluate(&game, p1_id, &assumptions));
+        assert_eq!(-4.0, evaluate(&game, p2_id, &assumptions));
+
+        let trace = evaluate_with_trace(&game, p1_id, &assumptions);
+        assert_eq!(4.0, trace.value);
+        let weights = EvalWeights::default();
+        assert_eq!(4.0, weighted_evaluate(&game, p1_id, &assumptions, &weights));
+        assert_eq!(weights, EvalWeights::from_slice(&weights.to_vec()));
+        let greedy = EvalWeights {
+            best_opponent: 0.0,
+            ..weights
+        };
+        assert_eq!(24.0, weighted_evaluate(&game, p1_id, &assumptions, &greedy));
+        assert_eq!(
+            "terraform rating +20.0, projected greeneries +4.0, \
+             best opponent (PlayerId(2)) -20.0, total +4.0",
+            trace.to_string()
+        );
+    }
+
+    #[test]
+    fn ensemble_evaluations_are_uncertain_about_the_length_of_the_game() {
+        let p1 = PlayerStateBuilder::new(1)
+            .with_production(0, 0, 0, 8, 0, 0)
+            .build();
+        let p2 = PlayerStateBuilder::new(2).build();
+        let p1_id = p1.player_id;
+        let game = make_game(vec![p1, p2]);
+
+        // The plant production is worth more greeneries the longer the game goes on.
+        let estimate = evaluate_ensemble(&game, p1_id, &ProjectionAssumptions::default());
+        assert!(estimate.spread > 0.0);
+        let exact = evaluate_ensemble(
+            &game,
+            p1_id,
+            &ProjectionAssumptions {
+                remaining_generations: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(0.0, exact.spread);
+
+        let roughly = EvalEstimate {
+            value: exact.value + 1.0,
+            spread: 1.0,
+        };
+        assert!(!roughly.is_clearly_better_than(&exact));
+        assert!(roughly.is_clearly_better_than(&EvalEstimate {
+            value: exact.value - 2.0,
+            spread: 0.0,
+        }));
+        assert!(exact.is_clearly_better_than(&EvalEstimate {
+            value: exact.value - 0.5,
+            spread: 0.0,
+        }));
+    }
+}
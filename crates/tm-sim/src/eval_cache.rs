@@ -0,0 +1,358 @@
+//! Caches of position evaluations keyed by `compact_key()`, so that positions reached again,
+//! in the same run or, with a file-backed cache, in a later one, are not evaluated twice.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    compact::{CompactKey, COMPACT_KEY_VERSION},
+    eval::evaluate,
+    game::{GameState, PlayerId},
+    projection::ProjectionAssumptions,
+    registry::CardRegistry,
+};
+
+#[derive(Debug)]
+pub enum EvalCacheError {
+    Malformed(String),
+    // the file holds evaluations made with other assumptions, card data, or key encoding
+    Mismatched,
+    Io(io::Error),
+}
+
+impl fmt::Display for EvalCacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalCacheError::Malformed(message) => {
+                write!(f, "malformed evaluation cache: {}", message)
+            }
+            EvalCacheError::Mismatched => write!(
+                f,
+                "evaluation cache was written with other assumptions, card data, or key encoding"
+            ),
+            EvalCacheError::Io(error) => write!(f, "storage error: {}", error),
+        }
+    }
+}
+
+impl From<io::Error> for EvalCacheError {
+    fn from(error: io::Error) -> Self {
+        EvalCacheError::Io(error)
+    }
+}
+
+/// Evaluations of positions under one set of projection assumptions.
+pub trait EvalCache: Send + Sync {
+    fn assumptions(&self) -> &ProjectionAssumptions;
+
+    fn get(&self, key: &CompactKey, player_id: PlayerId) -> Option<f64>;
+
+    fn insert(&self, key: CompactKey, player_id: PlayerId, value: f64);
+}
+
+/// Like `evaluate()` with the cache's assumptions, but only evaluates positions
+/// the cache has not seen yet. Positions without a compact key are evaluated every time.
+pub fn cached_evaluate(cache: &dyn EvalCache, game: &GameState, player_id: PlayerId) -> f64 {
+    let key = match game.compact_key() {
+        Ok(key) => key,
+        Err(_) => return evaluate(game, player_id, cache.assumptions()),
+    };
+    match cache.get(&key, player_id) {
+        Some(value) => value,
+        None => {
+            let value = evaluate(game, player_id, cache.assumptions());
+            cache.insert(key, player_id, value);
+            value
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InMemoryEvalCache {
+    assumptions: ProjectionAssumptions,
+    values: Mutex<HashMap<(CompactKey, PlayerId), f64>>,
+}
+
+impl InMemoryEvalCache {
+    pub fn new(assumptions: ProjectionAssumptions) -> Self {
+        Self {
+            assumptions,
+            values: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl EvalCache for InMemoryEvalCache {
+    fn assumptions(&self) -> &ProjectionAssumptions {
+        &self.assumptions
+    }
+
+    fn get(&self, key: &CompactKey, player_id: PlayerId) -> Option<f64> {
+        self.values
+            .lock()
+            .unwrap()
+            .get(&(key.clone(), player_id))
+            .copied()
+    }
+
+    fn insert(&self, key: CompactKey, player_id: PlayerId, value: f64) {
+        self.values.lock().unwrap().insert((key, player_id), value);
+    }
+}
+
+// First line of a cache file. Entries written under a different header are stale:
+// the keys would not match, or the values would have been computed differently.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct CacheHeader {
+    compact_key_version: u32,
+    deck_hash: String,
+    assumptions: ProjectionAssumptions,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    key: Vec<u64>,
+    player_id: PlayerId,
+    value: f64,
+}
+
+/// A cache kept in a file of JSON lines, shared between runs and between processes.
+///
+/// The file is mapped and read once when opened; new evaluations are kept in memory until
+/// `flush()` appends them. Each flush is a single append, so several processes may flush
+/// into the same file, and each sees the others' entries the next time it opens the file.
+/// Nothing is ever removed from the file: one written with other assumptions, card data,
+/// or key encoding is refused rather than started over, since other runs may still use it.
+///
+/// A line left half-written by a process that stopped while flushing is skipped; the next
+/// flush starts on a line of its own.
+#[derive(Debug)]
+pub struct FileEvalCache {
+    path: PathBuf,
+    cache: InMemoryEvalCache,
+    unflushed: Mutex<Vec<CacheEntry>>,
+}
+
+impl FileEvalCache {
+    pub fn open(
+        path: impl Into<PathBuf>,
+        assumptions: ProjectionAssumptions,
+    ) -> Result<Self, EvalCacheError> {
+        let path = path.into();
+        let header = CacheHeader {
+            compact_key_version: COMPACT_KEY_VERSION,
+            deck_hash: CardRegistry::global().content_hash_hex(),
+            assumptions,
+        };
+        let cache = InMemoryEvalCache::new(header.assumptions.clone());
+
+        let header_line = format!("{}\n", serde_json::to_string(&header).unwrap());
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => file.write_all(header_line.as_bytes())?,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let file = File::open(&path)?;
+        // Safety: the file is only ever appended to, so the mapped bytes don't change
+        // while they are read.
+        let map = unsafe { Mmap::map(&file)? };
+        let mut lines = map.split(|byte| *byte == b'\n');
+        let saved_header = serde_json::from_slice::<CacheHeader>(lines.next().unwrap_or_default())
+            .map_err(|e| EvalCacheError::Malformed(format!("line 1: {}", e)))?;
+        if saved_header != header {
+            return Err(EvalCacheError::Mismatched);
+        }
+        for line in lines {
+            if let Ok(entry) = serde_json::from_slice::<CacheEntry>(line) {
+                cache.insert(
+                    CompactKey::from_words(entry.key),
+                    entry.player_id,
+                    entry.value,
+                );
+            }
+        }
+
+        Ok(Self {
+            path,
+            cache,
+            unflushed: Mutex::new(vec![]),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    /// Append the evaluations made since the last flush to the file.
+    pub fn flush(&self) -> Result<(), EvalCacheError> {
+        let mut unflushed = self.unflushed.lock().unwrap();
+        if unflushed.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut text = String::new();
+        let mut last_byte = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last_byte)?;
+        if last_byte[0] != b'\n' {
+            text.push('\n');
+        }
+        for entry in unflushed.iter() {
+            text.push_str(&serde_json::to_string(entry).unwrap());
+            text.push('\n');
+        }
+        file.write_all(text.as_bytes())?;
+        unflushed.clear();
+        Ok(())
+    }
+}
+
+impl EvalCache for FileEvalCache {
+    fn assumptions(&self) -> &ProjectionAssumptions {
+        self.cache.assumptions()
+    }
+
+    fn get(&self, key: &CompactKey, player_id: PlayerId) -> Option<f64> {
+        self.cache.get(key, player_id)
+    }
+
+    fn insert(&self, key: CompactKey, player_id: PlayerId, value: f64) {
+        self.unflushed.lock().unwrap().push(CacheEntry {
+            key: key.as_words().to_vec(),
+            player_id,
+            value,
+        });
+        self.cache.insert(key, player_id, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::PathBuf,
+    };
+
+    use crate::{
+        eval::evaluate,
+        eval_cache::{
+            cached_evaluate, EvalCache, EvalCacheError, FileEvalCache, InMemoryEvalCache,
+        },
+        position::import_position,
+        projection::ProjectionAssumptions,
+    };
+
+    #[test]
+    fn repeated_positions_are_looked_up() {
+        let game = import_position("player p1\nresources 0 0 0 16 0 0\nplayer p2\n").unwrap();
+        let p1_id = *game.players.keys().next().unwrap();
+        let cache = InMemoryEvalCache::new(ProjectionAssumptions::default());
+
+        let value = cached_evaluate(&cache, &game, p1_id);
+        assert_eq!(
+            evaluate(&game, p1_id, &ProjectionAssumptions::default()),
+            value
+        );
+        assert_eq!(1, cache.len());
+
+        // A planted value proves that the second call did not evaluate again.
+        cache.insert(game.compact_key().unwrap(), p1_id, 100.0);
+        assert_eq!(100.0, cached_evaluate(&cache, &game, p1_id));
+        assert_eq!(1, cache.len());
+    }
+
+    fn cache_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "terraforming_mars_eval_cache_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn file_caches_survive_reopening_but_refuse_other_assumptions() {
+        let path = cache_path("reopened");
+        let game = import_position("player p1\nresources 0 0 0 16 0 0\nplayer p2\n").unwrap();
+        let p1_id = *game.players.keys().next().unwrap();
+
+        let cache = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        cached_evaluate(&cache, &game, p1_id);
+        cache.flush().unwrap();
+
+        let reopened = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        assert_eq!(1, reopened.len());
+        assert!(reopened.get(&game.compact_key().unwrap(), p1_id).is_some());
+
+        // Another run's entries are left alone.
+        let other_assumptions = ProjectionAssumptions {
+            remaining_generations: Some(1),
+            ..Default::default()
+        };
+        assert!(matches!(
+            FileEvalCache::open(&path, other_assumptions),
+            Err(EvalCacheError::Mismatched)
+        ));
+        let reopened = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        assert_eq!(1, reopened.len());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn half_written_lines_are_skipped() {
+        let path = cache_path("torn");
+        let game = import_position("player p1\nresources 0 0 0 16 0 0\nplayer p2\n").unwrap();
+        let p1_id = *game.players.keys().next().unwrap();
+
+        let cache = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        cached_evaluate(&cache, &game, p1_id);
+        cache.flush().unwrap();
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"key\":[1,2")
+            .unwrap();
+
+        // A later flush lands after the torn line, which is then in the middle of the file.
+        let cache = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        let richer = import_position("player p1\nresources 9 0 0 16 0 0\nplayer p2\n").unwrap();
+        cached_evaluate(&cache, &richer, p1_id);
+        cache.flush().unwrap();
+
+        let reopened = FileEvalCache::open(&path, ProjectionAssumptions::default()).unwrap();
+        assert_eq!(2, reopened.len());
+        assert!(reopened.get(&richer.compact_key().unwrap(), p1_id).is_some());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::{Coordinates, EmptyLocation, MarsBoard, TileLocation, TileStatus},
+    card::{ImmediateImpact, LocationRestriction},
+    game::{GameState, PlayerId, PlayerState},
+    resource::Resource,
+};
+
+// Rough exchange rates used to put tiles, resources, and points on a common scale.
+// All placement values are expressed in megacredits.
+const MEGACREDITS_PER_VICTORY_POINT: f64 = 5.0;
+const MEGACREDITS_PER_TERRAFORM_RATING: f64 = 10.0; // 1VP plus income for the rest of the game
+const MEGACREDITS_PER_CARD: f64 = 3.0;
+const MEGACREDITS_PER_PLANT: f64 = 2.0;
+
+// Empty land next to a city may later hold a greenery, but there is no guarantee of that.
+const POTENTIAL_GREENERY_FRACTION: f64 = 0.5;
+
+// Likewise, empty land next to an ocean may later hold a tile, earning the ocean's bonus.
+const POTENTIAL_TILE_FRACTION: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PlacementKind {
+    City,
+    Greenery,
+    Ocean,
+}
+
+/// The value of placing each kind of tile on a single space, `None` if the placement is illegal.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpaceScore {
+    pub coordinates: Coordinates,
+    pub name: Option<String>,
+    pub city: Option<f64>,
+    pub greenery: Option<f64>,
+    pub ocean: Option<f64>,
+}
+
+impl SpaceScore {
+    pub fn get(&self, kind: PlacementKind) -> Option<f64> {
+        match kind {
+            PlacementKind::City => self.city,
+            PlacementKind::Greenery => self.greenery,
+            PlacementKind::Ocean => self.ocean,
+        }
+    }
+}
+
+/// Per-space placement values for one player, for all spaces on Mars.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeatMap {
+    pub board_name: String,
+    pub player_id: PlayerId,
+    pub spaces: Vec<SpaceScore>,
+}
+
+impl HeatMap {
+    pub fn get(&self, coordinates: Coordinates) -> Option<&SpaceScore> {
+        self.spaces.iter().find(|space| space.coordinates == coordinates)
+    }
+
+    pub fn best_space(&self, kind: PlacementKind) -> Option<&SpaceScore> {
+        self.spaces
+            .iter()
+            .filter(|space| space.get(kind).is_some())
+            .max_by(|a, b| a.get(kind).partial_cmp(&b.get(kind)).unwrap())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Draw the board as rows of hexes, top row first, showing the value of the given placement.
+    /// Spaces where the placement is not legal are shown as dots.
+    pub fn render_ascii(&self, kind: PlacementKind) -> String {
+        const CELL_WIDTH: usize = 6;
+
+        let mut rows: BTreeMap<isize, Vec<&SpaceScore>> = BTreeMap::new();
+        for space in &self.spaces {
+            rows.entry(space.coordinates.get_z()).or_default().push(space);
+        }
+
+        let mut output = String::new();
+        for (z, row) in rows.iter_mut() {
+            row.sort_by_key(|space| space.coordinates);
+
+            output.push_str(&" ".repeat(z.unsigned_abs() * CELL_WIDTH / 2));
+            for space in row.iter() {
+                let cell = match space.get(kind) {
+                    Some(value) => format!("{:.1}", value),
+                    None => ".".to_string(),
+                };
+                output.push_str(&format!("{:^width$}", cell, width = CELL_WIDTH));
+            }
+            output.truncate(output.trim_end().len());
+            output.push('\n');
+        }
+
+        output
+    }
+}
+
+pub fn make_heat_map(board: &MarsBoard, player: &PlayerState) -> HeatMap {
+    let empty_land_next_to_own_tiles: Vec<Coordinates> = empty_mars_spaces(board)
+        .filter(|(coordinates, _)| {
+            board.spaces[&TileLocation::OnMars(*coordinates)].is_land()
+                && board.placement_satisfies_restrictions(
+                    player,
+                    &TileLocation::OnMars(*coordinates).into(),
+                    &[LocationRestriction::AdjacentToOwnedTile],
+                )
+        })
+        .map(|(coordinates, _)| coordinates)
+        .collect();
+
+    let mut spaces: Vec<SpaceScore> = board
+        .spaces
+        .values()
+        .filter_map(|space| match &space.location {
+            TileLocation::OnMars(coordinates) => Some((*coordinates, space)),
+            TileLocation::OffMars(_) => None,
+        })
+        .map(|(coordinates, space)| {
+            let location = TileLocation::OnMars(coordinates);
+            let is_empty = matches!(board.get_tile_status(&location), TileStatus::Empty(_));
+
+            let (city, greenery, ocean) = if is_empty {
+                let empty_location: EmptyLocation = location.clone().into();
+
+                // Greeneries must be placed next to an owned tile, if that is possible at all.
+                let greenery_allowed = space.is_land()
+                    && (empty_land_next_to_own_tiles.is_empty()
+                        || empty_land_next_to_own_tiles.contains(&coordinates));
+                let city_allowed = board.placement_satisfies_restrictions(
+                    player,
+                    &empty_location,
+                    &[LocationRestriction::LandTile, LocationRestriction::NotNextToACity],
+                );
+                let ocean_allowed =
+                    space.is_reserved_for_ocean() && board.oceans.len() < MarsBoard::MAX_OCEANS;
+
+                (
+                    Some(city_value(board, player, &empty_location)).filter(|_| city_allowed),
+                    Some(greenery_value(board, player, &empty_location))
+                        .filter(|_| greenery_allowed),
+                    Some(ocean_value(board, player, &empty_location)).filter(|_| ocean_allowed),
+                )
+            } else {
+                (None, None, None)
+            };
+
+            SpaceScore {
+                coordinates,
+                name: space.name.clone(),
+                city,
+                greenery,
+                ocean,
+            }
+        })
+        .collect();
+    spaces.sort_by_key(|space| space.coordinates);
+
+    HeatMap {
+        board_name: board.board_name.clone(),
+        player_id: player.player_id,
+        spaces,
+    }
+}
+
+/// Where the player should place an ocean, or `None` if no ocean may be placed.
+///
+/// Besides what the placement itself earns, oceans earn megacredits to whoever later places
+/// tiles next to them, so spaces whose neighbors the player is likelier to build on are preferred
+/// over those next to opponents' tiles.
+pub fn choose_ocean_placement(game: &GameState, player_id: PlayerId) -> Option<Coordinates> {
+    let heat_map = make_heat_map(&game.board, &game.players[&player_id]);
+    heat_map
+        .spaces
+        .iter()
+        .filter_map(|space| {
+            let forecast = ocean_adjacency_forecast(
+                &game.board,
+                player_id,
+                game.players.len(),
+                space.coordinates,
+            );
+            space.ocean.map(|value| (space.coordinates, value + forecast))
+        })
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(coordinates, _)| coordinates)
+}
+
+/// The megacredits the player can expect from tiles later placed next to an ocean on the given
+/// space, less what the average opponent can expect from them.
+///
+/// Any empty land next to the ocean may later hold a tile. Each player is likelier to place it
+/// the more of their tiles are already next to that land.
+pub fn ocean_adjacency_forecast(
+    board: &MarsBoard,
+    player_id: PlayerId,
+    player_count: usize,
+    coordinates: Coordinates,
+) -> f64 {
+    let location = TileLocation::OnMars(coordinates);
+    let net_share: f64 = board
+        .get_neighbor_tile_status(&location)
+        .filter_map(|status| match status {
+            TileStatus::Empty(empty_location) => {
+                let location: TileLocation = empty_location.into();
+                Some(location).filter(|location| board.spaces[location].is_land())
+            }
+            _ => None,
+        })
+        .map(|land| {
+            let (mut own_tiles, mut other_tiles) = (0, 0);
+            for status in board.get_neighbor_tile_status(&land) {
+                match status {
+                    TileStatus::City(_, _, owner)
+                    | TileStatus::Greenery(_, owner)
+                    | TileStatus::SpecialTile(_, _, owner) => {
+                        if owner == player_id {
+                            own_tiles += 1;
+                        } else {
+                            other_tiles += 1;
+                        }
+                    }
+                    TileStatus::Empty(_) | TileStatus::Ocean(_) => {}
+                }
+            }
+
+            let own_share =
+                (1 + own_tiles) as f64 / (player_count + own_tiles + other_tiles) as f64;
+            let opponent_share = if player_count > 1 {
+                (1.0 - own_share) / (player_count - 1) as f64
+            } else {
+                0.0
+            };
+            own_share - opponent_share
+        })
+        .sum();
+
+    net_share * POTENTIAL_TILE_FRACTION * board.ocean_adjacency_megacredits as f64
+}
+
+fn empty_mars_spaces(board: &MarsBoard) -> impl Iterator<Item = (Coordinates, TileStatus)> + '_ {
+    board.spaces.keys().filter_map(move |location| match location {
+        TileLocation::OnMars(coordinates) => match board.get_tile_status(location) {
+            status @ TileStatus::Empty(_) => Some((*coordinates, status)),
+            _ => None,
+        },
+        TileLocation::OffMars(_) => None,
+    })
+}
+
+pub(crate) fn placement_bonus_value(
+    board: &MarsBoard,
+    player: &PlayerState,
+    empty_location: &EmptyLocation,
+) -> f64 {
+    board
+        .get_placement_bonuses(player, empty_location)
+        .iter()
+        .map(|bonus| match bonus {
+            ImmediateImpact::GainResource(resource, amount) => {
+                let unit_value = match resource {
+                    Resource::Megacredits | Resource::Energy | Resource::Heat => 1.0,
+                    Resource::Steel => player.steel_value as f64,
+                    Resource::Titanium => player.titanium_value as f64,
+                    Resource::Plants => MEGACREDITS_PER_PLANT,
+                };
+                unit_value * (*amount as f64)
+            }
+            ImmediateImpact::DrawCard(count) => MEGACREDITS_PER_CARD * (*count as f64),
+            _ => 0.0,
+        })
+        .sum()
+}
+
+fn count_neighbors<F: Fn(&TileStatus) -> bool>(
+    board: &MarsBoard,
+    empty_location: &EmptyLocation,
+    predicate: F,
+) -> usize {
+    let location: TileLocation = empty_location.clone().into();
+    board
+        .get_neighbor_tile_status(&location)
+        .filter(|status| predicate(status))
+        .count()
+}
+
+fn city_value(board: &MarsBoard, player: &PlayerState, empty_location: &EmptyLocation) -> f64 {
+    let adjacent_greeneries = count_neighbors(board, empty_location, |status| {
+        matches!(status, TileStatus::Greenery(_, _))
+    });
+    let adjacent_empty_land = count_neighbors(board, empty_location, |status| match status {
+        TileStatus::Empty(location) => {
+            let location: TileLocation = location.clone().into();
+            board.spaces[&location].is_land()
+        }
+        _ => false,
+    });
+
+    placement_bonus_value(board, player, empty_location)
+        + MEGACREDITS_PER_VICTORY_POINT
+            * (adjacent_greeneries as f64
+                + POTENTIAL_GREENERY_FRACTION * adjacent_empty_land as f64)
+}
+
+fn greenery_value(board: &MarsBoard, player: &PlayerState, empty_location: &EmptyLocation) -> f64 {
+    let adjacent_own_cities = count_neighbors(board, empty_location, |status| {
+        matches!(status, TileStatus::City(_, _, owner) if *owner == player.player_id)
+    });
+    let oxygen_bonus = if board.oxygen < MarsBoard::MAX_OXYGEN {
+        MEGACREDITS_PER_TERRAFORM_RATING
+    } else {
+        0.0
+    };
+
+    placement_bonus_value(board, player, empty_location)
+        + oxygen_bonus
+        + MEGACREDITS_PER_VICTORY_POINT * (1 + adjacent_own_cities) as f64
+}
+
+fn ocean_value(board: &MarsBoard, player: &PlayerState, empty_location: &EmptyLocation) -> f64 {
+    // The megacredits for adjacent oceans are part of the placement bonus.
+    placement_bonus_value(board, player, empty_location) + MEGACREDITS_PER_TERRAFORM_RATING
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{make_base_game_board, Coordinates, MarsBoardBuilder, TileLocation},
+        card::CityKind,
+        game::PlayerStateBuilder,
+        game::{GameState, PlayerId},
+        heatmap::{
+            choose_ocean_placement, make_heat_map, ocean_adjacency_forecast, HeatMap,
+            PlacementKind,
+        },
+        test_utils::make_game,
+    };
+
+    #[test]
+    fn legal_placements_on_an_empty_board() {
+        let board = make_base_game_board();
+        let player = PlayerStateBuilder::new(1).build();
+        let heat_map = make_heat_map(&board, &player);
+
+        let count = |kind| {
+            heat_map
+                .spaces
+                .iter()
+                .filter(|space| space.get(kind).is_some())
+                .count()
+        };
+        assert_eq!(61, heat_map.spaces.len());
+        assert_eq!(12, count(PlacementKind::Ocean));
+        assert_eq!(49, count(PlacementKind::City));
+        assert_eq!(49, count(PlacementKind::Greenery));
+    }
+
+    #[test]
+    fn greeneries_are_worth_more_next_to_own_cities() {
+        let player = PlayerStateBuilder::new(1).build();
+        let board = MarsBoardBuilder::new()
+            .with_city(
+                Coordinates::new(3, -6),
+                CityKind::RegularCity,
+                player.player_id,
+            )
+            .build();
+        let heat_map = make_heat_map(&board, &player);
+
+        // Greeneries must now go next to the city, and cities cannot go next to it.
+        let greenery_spaces: Vec<_> = heat_map
+            .spaces
+            .iter()
+            .filter(|space| space.greenery.is_some())
+            .map(|space| space.coordinates)
+            .collect();
+        let neighbors: Vec<_> = board
+            .neighbors(&TileLocation::OnMars(Coordinates::new(3, -6)))
+            .collect();
+        assert_eq!(6, greenery_spaces.len());
+        assert!(greenery_spaces.iter().all(|c| neighbors.contains(c)));
+        assert!(neighbors
+            .iter()
+            .all(|c| heat_map.get(*c).unwrap().city.is_none()));
+
+        // Both spaces have no placement bonus and no adjacent oceans.
+        let next_to_city = heat_map.get(Coordinates::new(2, -6)).unwrap();
+        let empty_board_heat_map = make_heat_map(&make_base_game_board(), &player);
+        let far_from_city = empty_board_heat_map.get(Coordinates::new(2, -6)).unwrap();
+        assert!(next_to_city.greenery.unwrap() > far_from_city.greenery.unwrap());
+    }
+
+    #[test]
+    fn oceans_are_placed_where_the_player_will_collect_their_bonus() {
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let game = |board| GameState {
+            board,
+            ..make_game(vec![
+                PlayerStateBuilder::new(1).build(),
+                PlayerStateBuilder::new(2).build(),
+            ])
+        };
+
+        // Nobody has a head start next to either ocean space.
+        let empty = game(make_base_game_board());
+        for coordinates in [Coordinates::new(4, -8), Coordinates::new(8, -5)] {
+            assert_eq!(0.0, ocean_adjacency_forecast(&empty.board, p1, 2, coordinates));
+        }
+
+        // Both spaces earn 6 M$ right away, but p1 is next to the empty land around one of them,
+        // and p2 next to the land around the other.
+        let board = MarsBoardBuilder::new()
+            .with_greenery(Coordinates::new(3, -6), p1)
+            .with_greenery(Coordinates::new(7, -6), p2)
+            .build();
+        let forecast = |player_id, x, y| {
+            ocean_adjacency_forecast(&board, player_id, 2, Coordinates::new(x, y))
+        };
+        assert!(forecast(p1, 4, -8) > 0.0);
+        assert!(forecast(p1, 8, -5) < 0.0);
+        assert!((forecast(p1, 4, -8) + forecast(p2, 4, -8)).abs() < 1e-9);
+
+        let contested = game(board);
+        assert_eq!(Some(Coordinates::new(4, -8)), choose_ocean_placement(&contested, p1));
+        assert_eq!(Some(Coordinates::new(8, -5)), choose_ocean_placement(&contested, p2));
+    }
+
+    #[test]
+    fn heat_map_round_trips_through_json_and_renders() {
+        let board = make_base_game_board();
+        let player = PlayerStateBuilder::new(1).build();
+        let heat_map = make_heat_map(&board, &player);
+
+        let json = heat_map.to_json();
+        let parsed: HeatMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(heat_map, parsed);
+
+        let rendered = heat_map.render_ascii(PlacementKind::Ocean);
+        let rows: Vec<_> = rendered.lines().collect();
+        assert_eq!(9, rows.len());
+        assert_eq!(12, rendered.split_whitespace().filter(|cell| *cell != ".").count());
+    }
+}
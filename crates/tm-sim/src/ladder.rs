@@ -0,0 +1,561 @@
+//! Elo ratings of local agents, so that tournaments between versions of the engine
+//! show whether a change actually made it stronger.
+//!
+//! Each agent is identified by a name and a version label, e.g. `draft-bot` at `v2`;
+//! every version is rated separately, and head-to-head records are kept between all of them.
+
+use std::{cmp::Ordering, collections::BTreeMap, fmt, fs, io, path::PathBuf, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+pub const INITIAL_RATING: f64 = 1500.0;
+
+// Maximum rating change from a single two-player game.
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AgentId {
+    pub name: String,
+    pub version: String,
+}
+
+impl AgentId {
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for AgentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AgentRating {
+    pub rating: f64,
+    pub games: usize,
+}
+
+impl Default for AgentRating {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+/// Results of all games between two agents, from the first agent's point of view.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadToHead {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl HeadToHead {
+    fn flipped(self) -> Self {
+        Self {
+            wins: self.losses,
+            losses: self.wins,
+            draws: self.draws,
+        }
+    }
+}
+
+/// Serialized as lists of entries, since JSON maps only allow string keys.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "LadderEntries", into = "LadderEntries")]
+pub struct Ladder {
+    ratings: BTreeMap<AgentId, AgentRating>,
+
+    // keyed by the pair of agents in sorted order
+    head_to_head: BTreeMap<(AgentId, AgentId), HeadToHead>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LadderEntries {
+    ratings: Vec<(AgentId, AgentRating)>,
+    head_to_head: Vec<(AgentId, AgentId, HeadToHead)>,
+}
+
+impl From<LadderEntries> for Ladder {
+    fn from(entries: LadderEntries) -> Self {
+        Self {
+            ratings: entries.ratings.into_iter().collect(),
+            head_to_head: entries
+                .head_to_head
+                .into_iter()
+                .map(|(first, second, record)| ((first, second), record))
+                .collect(),
+        }
+    }
+}
+
+impl From<Ladder> for LadderEntries {
+    fn from(ladder: Ladder) -> Self {
+        Self {
+            ratings: ladder.ratings.into_iter().collect(),
+            head_to_head: ladder
+                .head_to_head
+                .into_iter()
+                .map(|((first, second), record)| (first, second, record))
+                .collect(),
+        }
+    }
+}
+
+impl Ladder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Agents that have not played yet have the initial rating.
+    pub fn rating(&self, agent: &AgentId) -> AgentRating {
+        self.ratings.get(agent).copied().unwrap_or_default()
+    }
+
+    /// All rated agents, highest rating first.
+    pub fn standings(&self) -> Vec<(&AgentId, AgentRating)> {
+        let mut standings: Vec<_> = self
+            .ratings
+            .iter()
+            .map(|(agent, rating)| (agent, *rating))
+            .collect();
+        standings.sort_by(|a, b| b.1.rating.partial_cmp(&a.1.rating).unwrap());
+        standings
+    }
+
+    pub fn head_to_head(&self, agent: &AgentId, opponent: &AgentId) -> HeadToHead {
+        if agent <= opponent {
+            let key = (agent.clone(), opponent.clone());
+            self.head_to_head.get(&key).copied().unwrap_or_default()
+        } else {
+            self.head_to_head(opponent, agent).flipped()
+        }
+    }
+
+    /// Rate a finished game from every agent's final score.
+    ///
+    /// Games with more than two agents count as a two-player game between every pair of them,
+    /// with each pairing's rating change scaled down so that a game is worth the same in total.
+    pub fn record_game(&mut self, final_scores: &[(AgentId, isize)]) {
+        assert!(
+            final_scores.len() >= 2,
+            "a rated game needs at least two agents"
+        );
+        for (index, (agent, _)) in final_scores.iter().enumerate() {
+            assert!(
+                final_scores[index + 1..]
+                    .iter()
+                    .all(|(other, _)| other != agent),
+                "{} cannot play against itself",
+                agent
+            );
+        }
+
+        let k_factor = K_FACTOR / (final_scores.len() - 1) as f64;
+        let mut rating_changes = vec![0.0; final_scores.len()];
+        for (first, (first_agent, first_score)) in final_scores.iter().enumerate() {
+            for (second, (second_agent, second_score)) in
+                final_scores.iter().enumerate().skip(first + 1)
+            {
+                let first_rating = self.rating(first_agent).rating;
+                let second_rating = self.rating(second_agent).rating;
+                let expected = 1.0 / (1.0 + 10f64.powf((second_rating - first_rating) / 400.0));
+
+                let (outcome, result) = match first_score.cmp(second_score) {
+                    Ordering::Greater => (1.0, HeadToHead { wins: 1, losses: 0, draws: 0 }),
+                    Ordering::Less => (0.0, HeadToHead { wins: 0, losses: 1, draws: 0 }),
+                    Ordering::Equal => (0.5, HeadToHead { wins: 0, losses: 0, draws: 1 }),
+                };
+                self.add_result(first_agent, second_agent, result);
+
+                let change = k_factor * (outcome - expected);
+                rating_changes[first] += change;
+                rating_changes[second] -= change;
+            }
+        }
+
+        // All changes are computed from the ratings before the game, then applied at once.
+        for ((agent, _), change) in final_scores.iter().zip(rating_changes) {
+            let rating = self.ratings.entry(agent.clone()).or_default();
+            rating.rating += change;
+            rating.games += 1;
+        }
+    }
+
+    fn add_result(&mut self, agent: &AgentId, opponent: &AgentId, result: HeadToHead) {
+        let (key, result) = if agent <= opponent {
+            ((agent.clone(), opponent.clone()), result)
+        } else {
+            ((opponent.clone(), agent.clone()), result.flipped())
+        };
+        let record = self.head_to_head.entry(key).or_default();
+        record.wins += result.wins;
+        record.losses += result.losses;
+        record.draws += result.draws;
+    }
+}
+
+#[derive(Debug)]
+pub enum LadderError {
+    Malformed(String),
+    Io(io::Error),
+    #[cfg(feature = "sqlite-ladder")]
+    Database(rusqlite::Error),
+}
+
+impl fmt::Display for LadderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LadderError::Malformed(message) => write!(f, "malformed ladder: {}", message),
+            LadderError::Io(error) => write!(f, "storage error: {}", error),
+            #[cfg(feature = "sqlite-ladder")]
+            LadderError::Database(error) => write!(f, "database error: {}", error),
+        }
+    }
+}
+
+impl From<io::Error> for LadderError {
+    fn from(error: io::Error) -> Self {
+        LadderError::Io(error)
+    }
+}
+
+#[cfg(feature = "sqlite-ladder")]
+impl From<rusqlite::Error> for LadderError {
+    fn from(error: rusqlite::Error) -> Self {
+        LadderError::Database(error)
+    }
+}
+
+/// Persistent storage for a ladder. A ladder that was never saved loads as empty.
+pub trait LadderStore: Send + Sync {
+    fn load(&self) -> Result<Ladder, LadderError>;
+
+    // Replaces any previously saved ladder.
+    fn save(&self, ladder: &Ladder) -> Result<(), LadderError>;
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryLadderStore {
+    ladder: Mutex<Ladder>,
+}
+
+impl InMemoryLadderStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl LadderStore for InMemoryLadderStore {
+    fn load(&self) -> Result<Ladder, LadderError> {
+        Ok(self.ladder.lock().unwrap().clone())
+    }
+
+    fn save(&self, ladder: &Ladder) -> Result<(), LadderError> {
+        *self.ladder.lock().unwrap() = ladder.clone();
+        Ok(())
+    }
+}
+
+/// Stores the ladder as a single JSON file, replaced atomically on every save.
+#[derive(Debug)]
+pub struct FileLadderStore {
+    path: PathBuf,
+    lock: Mutex<()>, // serializes all writes to the file
+}
+
+impl FileLadderStore {
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl LadderStore for FileLadderStore {
+    fn load(&self) -> Result<Ladder, LadderError> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Ladder::new()),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_str(&text).map_err(|e| LadderError::Malformed(e.to_string()))
+    }
+
+    fn save(&self, ladder: &Ladder) -> Result<(), LadderError> {
+        let _guard = self.lock.lock().unwrap();
+
+        let mut temporary_path = self.path.clone().into_os_string();
+        temporary_path.push(".tmp");
+        fs::write(
+            &temporary_path,
+            serde_json::to_string_pretty(ladder).unwrap(),
+        )?;
+        fs::rename(&temporary_path, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Stores the ladder in an SQLite database, with one table of ratings
+/// and one of head-to-head records, so that it can also be queried directly.
+#[cfg(feature = "sqlite-ladder")]
+pub struct SqliteLadderStore {
+    connection: Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite-ladder")]
+impl SqliteLadderStore {
+    pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, LadderError> {
+        Self::with_connection(rusqlite::Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, LadderError> {
+        Self::with_connection(rusqlite::Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: rusqlite::Connection) -> Result<Self, LadderError> {
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS ratings (
+                name TEXT NOT NULL,
+                version TEXT NOT NULL,
+                rating REAL NOT NULL,
+                games INTEGER NOT NULL,
+                PRIMARY KEY (name, version)
+            );
+            CREATE TABLE IF NOT EXISTS head_to_head (
+                name TEXT NOT NULL,
+                version TEXT NOT NULL,
+                opponent_name TEXT NOT NULL,
+                opponent_version TEXT NOT NULL,
+                wins INTEGER NOT NULL,
+                losses INTEGER NOT NULL,
+                draws INTEGER NOT NULL,
+                PRIMARY KEY (name, version, opponent_name, opponent_version)
+            );",
+        )?;
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+#[cfg(feature = "sqlite-ladder")]
+impl LadderStore for SqliteLadderStore {
+    fn load(&self) -> Result<Ladder, LadderError> {
+        let connection = self.connection.lock().unwrap();
+        let mut ladder = Ladder::new();
+
+        let mut statement =
+            connection.prepare("SELECT name, version, rating, games FROM ratings")?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                AgentId {
+                    name: row.get(0)?,
+                    version: row.get(1)?,
+                },
+                AgentRating {
+                    rating: row.get(2)?,
+                    games: row.get::<_, i64>(3)? as usize,
+                },
+            ))
+        })?;
+        for row in rows {
+            let (agent, rating) = row?;
+            ladder.ratings.insert(agent, rating);
+        }
+
+        let mut statement = connection.prepare(
+            "SELECT name, version, opponent_name, opponent_version, wins, losses, draws
+             FROM head_to_head",
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((
+                AgentId {
+                    name: row.get(0)?,
+                    version: row.get(1)?,
+                },
+                AgentId {
+                    name: row.get(2)?,
+                    version: row.get(3)?,
+                },
+                HeadToHead {
+                    wins: row.get::<_, i64>(4)? as usize,
+                    losses: row.get::<_, i64>(5)? as usize,
+                    draws: row.get::<_, i64>(6)? as usize,
+                },
+            ))
+        })?;
+        for row in rows {
+            let (agent, opponent, record) = row?;
+            ladder.head_to_head.insert((agent, opponent), record);
+        }
+
+        Ok(ladder)
+    }
+
+    fn save(&self, ladder: &Ladder) -> Result<(), LadderError> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction()?;
+        transaction.execute("DELETE FROM ratings", [])?;
+        transaction.execute("DELETE FROM head_to_head", [])?;
+
+        for (agent, rating) in &ladder.ratings {
+            transaction.execute(
+                "INSERT INTO ratings (name, version, rating, games) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    agent.name,
+                    agent.version,
+                    rating.rating,
+                    rating.games as i64
+                ],
+            )?;
+        }
+        for ((agent, opponent), record) in &ladder.head_to_head {
+            transaction.execute(
+                "INSERT INTO head_to_head
+                 (name, version, opponent_name, opponent_version, wins, losses, draws)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    agent.name,
+                    agent.version,
+                    opponent.name,
+                    opponent.version,
+                    record.wins as i64,
+                    record.losses as i64,
+                    record.draws as i64,
+                ],
+            )?;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::ladder::{
+        AgentId, FileLadderStore, HeadToHead, InMemoryLadderStore, Ladder, LadderStore,
+        INITIAL_RATING,
+    };
+
+    fn make_ladder() -> Ladder {
+        let (old, new) = (AgentId::new("planner", "v1"), AgentId::new("planner", "v2"));
+        let mut ladder = Ladder::new();
+        ladder.record_game(&[(old.clone(), 60), (new.clone(), 72)]);
+        ladder.record_game(&[(new.clone(), 65), (old.clone(), 65)]);
+        ladder
+    }
+
+    fn exercise_store(store: &dyn LadderStore) {
+        assert_eq!(Ladder::new(), store.load().unwrap());
+
+        let mut ladder = make_ladder();
+        store.save(&ladder).unwrap();
+        assert_eq!(ladder, store.load().unwrap());
+
+        ladder.record_game(&[
+            (AgentId::new("greedy", "v1"), 50),
+            (AgentId::new("planner", "v2"), 70),
+        ]);
+        store.save(&ladder).unwrap();
+        assert_eq!(ladder, store.load().unwrap());
+    }
+
+    #[test]
+    fn winners_gain_what_losers_lose() {
+        let (old, new) = (AgentId::new("planner", "v1"), AgentId::new("planner", "v2"));
+        let ladder = make_ladder();
+
+        let old_rating = ladder.rating(&old);
+        let new_rating = ladder.rating(&new);
+        assert_eq!(2, old_rating.games);
+        assert!(new_rating.rating > INITIAL_RATING);
+        assert!((old_rating.rating + new_rating.rating - 2.0 * INITIAL_RATING).abs() < 1e-9);
+
+        assert_eq!(
+            HeadToHead {
+                wins: 1,
+                losses: 0,
+                draws: 1
+            },
+            ladder.head_to_head(&new, &old)
+        );
+        assert_eq!(
+            HeadToHead {
+                wins: 0,
+                losses: 1,
+                draws: 1
+            },
+            ladder.head_to_head(&old, &new)
+        );
+
+        let standings = ladder.standings();
+        assert_eq!(
+            vec![&new, &old],
+            standings
+                .iter()
+                .map(|(agent, _)| *agent)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn multiplayer_games_count_as_every_pairing() {
+        let agents: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|name| AgentId::new(name, "v1"))
+            .collect();
+        let mut ladder = Ladder::new();
+        ladder.record_game(&[
+            (agents[0].clone(), 80),
+            (agents[1].clone(), 70),
+            (agents[2].clone(), 60),
+        ]);
+
+        // The winner of a three-player game gains as much as the winner of a two-player game.
+        let mut two_player = Ladder::new();
+        two_player.record_game(&[(agents[0].clone(), 80), (agents[1].clone(), 70)]);
+        assert!(
+            (ladder.rating(&agents[0]).rating - two_player.rating(&agents[0]).rating).abs() < 1e-9
+        );
+        assert_eq!(INITIAL_RATING, ladder.rating(&agents[1]).rating);
+        assert_eq!(1, ladder.head_to_head(&agents[1], &agents[2]).wins);
+    }
+
+    #[test]
+    fn in_memory_store_keeps_the_ladder() {
+        exercise_store(&InMemoryLadderStore::new());
+    }
+
+    #[test]
+    fn file_store_keeps_the_ladder() {
+        let path = std::env::temp_dir().join(format!(
+            "terraforming_mars_ladder_{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        exercise_store(&FileLadderStore::open(&path));
+
+        // Reopening finds the same ladder.
+        let ladder = FileLadderStore::open(&path).load().unwrap();
+        assert_eq!(3, ladder.standings().len());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "sqlite-ladder")]
+    #[test]
+    fn sqlite_store_keeps_the_ladder() {
+        exercise_store(&crate::ladder::SqliteLadderStore::open_in_memory().unwrap());
+    }
+}
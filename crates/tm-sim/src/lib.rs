@@ -0,0 +1,38 @@
+//! Search, planning, and agents: everything that plays or analyzes games
+//! on top of the engine.
+
+#![allow(dead_code)]
+
+// The engine's modules are re-exported, so they are found at the same paths as our own.
+pub use tm_engine::{
+    award, board, card, card_ref, compact, corporation, deck_stats, diff, event, game, game_setup,
+    game_stats, generation, knowledge, migrate, milestone, options, position, projection, registry,
+    resource, rng, rules, save, schedule, scoring, solo, spectator, stamp, timeline, turmoil,
+    view,
+};
+#[cfg(any(test, feature = "test-utils"))]
+pub use tm_engine::test_utils;
+
+pub mod action_space;
+pub mod archive;
+pub mod blunder;
+pub mod bounds;
+pub mod canonical;
+pub mod dataset;
+pub mod decision;
+pub mod draft;
+pub mod endgame;
+pub mod eval;
+pub mod eval_cache;
+pub mod heatmap;
+pub mod ladder;
+pub mod priors;
+pub mod search_tree;
+pub mod scenario;
+pub mod setup;
+pub mod sim;
+pub mod stats;
+pub mod synergy;
+pub mod threat;
+pub mod tuning;
+pub mod whatif;
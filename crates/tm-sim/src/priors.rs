@@ -0,0 +1,78 @@
+//! Corporation-conditioned priors for the evaluator: the weights that suit the strategy each
+//! corporation is built for, such as building cities with Interplanetary Cinematics' steel,
+//! or banking megacredits for Teractor's Earth discount.
+//!
+//! The built-in priors are kept in a JSON data file, keyed by corporation name. Each entry
+//! only lists the weights it changes, and the rest keep their default. Players without a
+//! corporation, or with one that has no entry, are evaluated with the default weights.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    corporation::Corporation,
+    eval::EvalWeights,
+    game::{GameState, PlayerId},
+};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CorporationPriors {
+    pub weights: BTreeMap<String, EvalWeights>,
+}
+
+impl CorporationPriors {
+    pub fn builtin() -> Self {
+        let priors_text = include_str!("./priors/corporations.json");
+        Self::from_json(priors_text).unwrap()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(text)
+    }
+
+    pub fn weights_for(&self, corporation: Option<&Corporation>) -> EvalWeights {
+        corporation
+            .and_then(|corporation| self.weights.get(&corporation.name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The weights for the corporation the player was assigned, once they have one.
+    pub fn weights_for_player(&self, game: &GameState, player_id: PlayerId) -> EvalWeights {
+        self.weights_for(game.players[&player_id].corporation.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        corporation::CORPORATIONS_BY_NAME, eval::EvalWeights, game::PlayerId,
+        position::import_position, priors::CorporationPriors,
+    };
+
+    #[test]
+    fn priors_are_selected_by_the_assigned_corporation() {
+        let priors = CorporationPriors::builtin();
+        for name in priors.weights.keys() {
+            assert!(CORPORATIONS_BY_NAME.contains_key(name.as_str()), "{}", name);
+        }
+
+        let p1 = PlayerId::from(1);
+        let mut game = import_position("player p1").unwrap();
+        assert_eq!(EvalWeights::default(), priors.weights_for_player(&game, p1));
+
+        let teractor = CORPORATIONS_BY_NAME["Teractor"];
+        game.players.get_mut(&p1).unwrap().corporation = Some(teractor.clone());
+        let weights = priors.weights_for_player(&game, p1);
+        assert_eq!(priors.weights["Teractor"], weights);
+        assert!(weights.megacredits > 0.0);
+        // Weights the entry leaves out keep their default.
+        assert_eq!(EvalWeights::default().plant_risk, weights.plant_risk);
+
+        let priors = CorporationPriors::from_json(r#"{"Inventrix": {"plant_risk": 2.0}}"#).unwrap();
+        assert_eq!(EvalWeights::default(), priors.weights_for(Some(teractor)));
+        assert!(CorporationPriors::from_json(r#"{"Teractor": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Practice scenarios: positions from the early, middle, and late game, each with the best
+//! line of play for one player, as found by the endgame solver.
+//!
+//! A scenario poses the question "which cards do you play, and when, if the game ends after
+//! this many generations?" For late-game positions that is the real end of the game; earlier
+//! positions are cut short at the same horizon, which keeps them within reach of the solver.
+//!
+//! Scenarios are stored as JSON files, one per scenario, with the position written in the
+//! notation of `import_position()` so that it can be read and edited by hand.
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CORPORATE_ERA_DECK},
+    endgame::solve_endgame,
+    game::{GameState, PlayerId, TurnAction},
+    options::ProductionStep,
+    position::{import_position, parse_player_id, PositionError},
+    rng::GameRng,
+};
+
+const SCENARIO_EXTENSION: &str = "json";
+
+/// Positions where the best line is to play every card, or none, teach nothing;
+/// this many positions are tried for each scenario before giving up on it.
+const MAX_ATTEMPTS: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GameStage {
+    Early,
+    Mid,
+    Late,
+}
+
+impl GameStage {
+    pub const ALL: [GameStage; 3] = [GameStage::Early, GameStage::Mid, GameStage::Late];
+
+    fn setup(self) -> StageSetup {
+        match self {
+            GameStage::Early => StageSetup {
+                generation: 3,
+                oxygen: 1,
+                temperature: -26,
+                terraform_rating: 22,
+                megacredits: (10, 30),
+                max_production: 3,
+                hand_size: 5,
+                generations: 2,
+            },
+            GameStage::Mid => StageSetup {
+                generation: 6,
+                oxygen: 6,
+                temperature: -14,
+                terraform_rating: 30,
+                megacredits: (15, 40),
+                max_production: 6,
+                hand_size: 7,
+                generations: 2,
+            },
+            GameStage::Late => StageSetup {
+                generation: 9,
+                oxygen: 11,
+                temperature: 0,
+                terraform_rating: 38,
+                megacredits: (20, 60),
+                max_production: 8,
+                hand_size: 9,
+                generations: 1,
+            },
+        }
+    }
+}
+
+impl fmt::Display for GameStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameStage::Early => write!(f, "early"),
+            GameStage::Mid => write!(f, "mid"),
+            GameStage::Late => write!(f, "late"),
+        }
+    }
+}
+
+// The shape of the positions generated for a stage; amounts are drawn up to the maximums.
+struct StageSetup {
+    generation: usize,
+    oxygen: usize,
+    temperature: isize,
+    terraform_rating: usize,
+    megacredits: (usize, usize),
+    max_production: isize,
+    hand_size: usize,
+    generations: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub stage: GameStage,
+    pub position: String, // in the notation of `import_position()`
+    pub player: String,
+    pub generations: usize, // until the game ends, counting the current one
+
+    // the solver's answer: the cards to play in each generation, and the points they score
+    pub best_plays: Vec<Vec<TurnAction>>,
+    pub best_points: isize,
+}
+
+impl Scenario {
+    pub fn game(&self) -> Result<(GameState, PlayerId), PositionError> {
+        let game = import_position(&self.position)?;
+        let player_id =
+            parse_player_id(&self.player).map_err(|message| PositionError { line: 0, message })?;
+        Ok((game, player_id))
+    }
+
+    /// The victory points the player ends the game with by playing the given cards,
+    /// in each generation in order, or why the line cannot be played.
+    pub fn points_of_line(&self, plays: &[Vec<TurnAction>]) -> Result<isize, String> {
+        if plays.len() > self.generations {
+            return Err(format!(
+                "the game ends after {} generations, but {} were played",
+                self.generations,
+                plays.len()
+            ));
+        }
+
+        let (game, player_id) = self.game().map_err(|error| error.to_string())?;
+        let mut state = game.players[&player_id].clone();
+        for generation in 0..self.generations {
+            if generation > 0 {
+                state.advance_generation(&ProductionStep::STANDARD);
+            }
+            for action in plays.get(generation).into_iter().flatten() {
+                let card = match action {
+                    TurnAction::PlayCard(card, _) => card,
+                    other => return Err(format!("only cards are played, not {:?}", other)),
+                };
+                if !state.cards_in_hand.contains(card) {
+                    return Err(format!("\"{}\" is not in hand", card.name));
+                }
+                if state.play_card(&game.board, card).is_none() {
+                    return Err(format!(
+                        "\"{}\" cannot be played in generation {}",
+                        card.name,
+                        generation + 1
+                    ));
+                }
+            }
+        }
+        Ok(state.get_total_victory_points(&game.board))
+    }
+}
+
+/// Generate the given number of scenarios for every stage of the game, the same ones
+/// for the same seed. Fewer are generated for a stage if no instructive positions are found.
+pub fn generate_scenarios(seed: u64, per_stage: usize) -> Vec<Scenario> {
+    let mut rng = GameRng::from_seed(seed);
+    let mut scenarios = vec![];
+    for stage in GameStage::ALL.iter().copied() {
+        for index in 1..=per_stage {
+            let name = format!("{}-{:02}", stage, index);
+            if let Some(scenario) =
+                (0..MAX_ATTEMPTS).find_map(|_| try_scenario(&mut rng, stage, &name))
+            {
+                scenarios.push(scenario);
+            }
+        }
+    }
+    scenarios
+}
+
+fn try_scenario(rng: &mut GameRng, stage: GameStage, name: &str) -> Option<Scenario> {
+    let setup = stage.setup();
+    let hand: Vec<&Card> = CORPORATE_ERA_DECK
+        .choose_multiple(rng, setup.hand_size)
+        .collect();
+    let production: Vec<String> = (0..6)
+        .map(|_| rng.gen_range(0, setup.max_production + 1).to_string())
+        .collect();
+    let position = format!(
+        "generation {}\noxygen {}\ntemperature {}\n\nplayer p1\ntr {}\n\
+         resources {} {} {} 0 0 0\nproduction {}\nhand {}\n",
+        setup.generation,
+        setup.oxygen,
+        setup.temperature,
+        setup.terraform_rating,
+        rng.gen_range(setup.megacredits.0, setup.megacredits.1 + 1),
+        rng.gen_range(0, 6),
+        rng.gen_range(0, 4),
+        production.join(" "),
+        hand.iter()
+            .map(|card| card.name.as_str())
+            .collect::<Vec<_>>()
+            .join("; "),
+    );
+
+    let game = import_position(&position).ok()?;
+    let player_id = PlayerId::from(1);
+    let state = &game.players[&player_id];
+    let solution = solve_endgame(&game.board, state, setup.generations);
+
+    // There must be something to play, and a playable card best left unplayed.
+    let played = solution.plays.iter().flatten().count();
+    let passed_up = state
+        .cards_in_hand
+        .iter()
+        .filter(|card| state.can_play_card(&game.board, card).is_some())
+        .any(|card| solution.final_state.cards_in_hand.contains(card));
+    if played == 0 || !passed_up {
+        return None;
+    }
+
+    Some(Scenario {
+        name: name.to_string(),
+        stage,
+        position,
+        player: "p1".to_string(),
+        generations: setup.generations,
+        best_plays: solution.plays,
+        best_points: solution.final_points,
+    })
+}
+
+/// Write every scenario into the directory, as `<name>.json`.
+pub fn write_scenarios(directory: &Path, scenarios: &[Scenario]) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(directory)?;
+    let mut paths = vec![];
+    for scenario in scenarios {
+        let path = directory.join(format!("{}.{}", scenario.name, SCENARIO_EXTENSION));
+        fs::write(&path, serde_json::to_string_pretty(scenario).unwrap())?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub fn load_scenario(path: &Path) -> Result<Scenario, String> {
+    let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    serde_json::from_str(&text).map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_scenarios_are_graded_and_their_best_lines_score_as_solved() {
+        let scenarios = generate_scenarios(7, 2);
+        assert_eq!(scenarios, generate_scenarios(7, 2));
+        for stage in GameStage::ALL.iter() {
+            assert!(scenarios.iter().any(|scenario| scenario.stage == *stage));
+        }
+
+        for scenario in &scenarios {
+            assert_eq!(
+                Ok(scenario.best_points),
+                scenario.points_of_line(&scenario.best_plays)
+            );
+            // Passing every generation scores no more than the best line.
+            let passing = scenario.points_of_line(&[]).unwrap();
+            assert!(passing <= scenario.best_points);
+        }
+
+        let scenario = &scenarios[0];
+        let (game, player_id) = scenario.game().unwrap();
+        let not_in_hand = CORPORATE_ERA_DECK
+            .iter()
+            .find(|card| !game.players[&player_id].cards_in_hand.contains(card))
+            .unwrap();
+        assert!(scenario
+            .points_of_line(&[vec![TurnAction::PlayCard(not_in_hand.clone(), vec![])]])
+            .is_err());
+    }
+
+    #[test]
+    fn scenarios_are_written_and_loaded_as_files() {
+        let directory = std::env::temp_dir().join(format!(
+            "terraforming_mars_scenarios_{}",
+            std::process::id()
+        ));
+        let scenarios = generate_scenarios(3, 1);
+        let paths = write_scenarios(&directory, &scenarios).unwrap();
+        assert_eq!(scenarios.len(), paths.len());
+        for (scenario, path) in scenarios.iter().zip(&paths) {
+            assert_eq!(Ok(scenario.clone()), load_scenario(path));
+        }
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,221 @@
+//! The tree of plays explored by the planner, for visualizing how it valued the alternatives.
+//!
+//! Each plan is a path from the root: first the cards bought, then the cards played in order.
+//! Plans sharing a prefix share the nodes of that prefix. Every node is annotated with
+//! the number of rollouts played through it and the values of the plans below it.
+
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{game::TurnAction, resource::Resource, sim::PlanEvaluation};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchNode {
+    pub id: usize,
+    pub parent: Option<usize>,
+
+    // the action on the edge from the parent; none for the root
+    pub action: Option<String>,
+    pub children: Vec<usize>,
+
+    pub visits: usize,
+    pub best_value: f64,
+    pub mean_value: f64, // over all rollouts through the node
+
+    // a summary of the player's state, only for nodes where a plan ends
+    pub state: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchTree {
+    pub nodes: Vec<SearchNode>, // the root is the first node
+}
+
+impl SearchTree {
+    pub fn from_plans(plans: &[PlanEvaluation]) -> Self {
+        let mut tree = SearchTree {
+            nodes: vec![new_node(0, None, None)],
+        };
+        // sum of all values through each node, to average at the end
+        let mut value_sums = vec![0.0];
+
+        for plan in plans {
+            let names: Vec<&str> = plan
+                .bought_cards
+                .iter()
+                .map(|card| card.name.as_str())
+                .collect();
+            let mut path = vec![format!("buy [{}]", names.join(", "))];
+            path.extend(plan.actions.iter().map(describe_action));
+
+            let visits = plan.sampled_values.len();
+            let value_sum: f64 = plan.sampled_values.iter().map(|value| *value as f64).sum();
+
+            let mut node_id = 0;
+            tree.visit(node_id, visits, plan.mean_value);
+            value_sums[node_id] += value_sum;
+            for action in path {
+                node_id = tree.child(node_id, action);
+                value_sums.resize(tree.nodes.len(), 0.0);
+                tree.visit(node_id, visits, plan.mean_value);
+                value_sums[node_id] += value_sum;
+            }
+
+            let state = &plan.final_state;
+            tree.nodes[node_id].state = Some(format!(
+                "{} TR, {} M$, {} cards in hand",
+                state.terraform_rating,
+                state.resources[&Resource::Megacredits],
+                state.cards_in_hand.len()
+            ));
+        }
+
+        for (node, value_sum) in tree.nodes.iter_mut().zip(value_sums) {
+            if node.visits > 0 {
+                node.mean_value = value_sum / node.visits as f64;
+            }
+        }
+        tree
+    }
+
+    fn child(&mut self, parent_id: usize, action: String) -> usize {
+        let existing = self.nodes[parent_id]
+            .children
+            .iter()
+            .copied()
+            .find(|child_id| self.nodes[*child_id].action.as_ref() == Some(&action));
+        match existing {
+            Some(child_id) => child_id,
+            None => {
+                let child_id = self.nodes.len();
+                self.nodes
+                    .push(new_node(child_id, Some(parent_id), Some(action)));
+                self.nodes[parent_id].children.push(child_id);
+                child_id
+            }
+        }
+    }
+
+    fn visit(&mut self, node_id: usize, visits: usize, value: f64) {
+        let node = &mut self.nodes[node_id];
+        node.best_value = if node.visits == 0 {
+            value
+        } else {
+            node.best_value.max(value)
+        };
+        node.visits += visits;
+    }
+
+    /// The tree in the GraphViz DOT language, e.g. for `dot -Tsvg`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let mut label = format!(
+                "visits {}\\nbest {:.1}, mean {:.1}",
+                node.visits, node.best_value, node.mean_value
+            );
+            if let Some(state) = &node.state {
+                write!(label, "\\n{}", escape(state)).unwrap();
+            }
+            writeln!(dot, "    n{} [label=\"{}\"];", node.id, label).unwrap();
+
+            if let (Some(parent), Some(action)) = (node.parent, &node.action) {
+                writeln!(
+                    dot,
+                    "    n{} -> n{} [label=\"{}\"];",
+                    parent,
+                    node.id,
+                    escape(action)
+                )
+                .unwrap();
+            }
+        }
+        dot.push('}');
+        dot
+    }
+}
+
+fn new_node(id: usize, parent: Option<usize>, action: Option<String>) -> SearchNode {
+    SearchNode {
+        id,
+        parent,
+        action,
+        children: vec![],
+        visits: 0,
+        best_value: 0.0,
+        mean_value: 0.0,
+        state: None,
+    }
+}
+
+fn describe_action(action: &TurnAction) -> String {
+    match action {
+        TurnAction::PlayCard(card, _) => format!("play {}", card.name),
+        other => format!("{:?}", other),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{Card, CORPORATE_GAME_CARDS_BY_NAME},
+        game::{PlayerStateBuilder, TurnAction},
+        search_tree::SearchTree,
+        sim::PlanEvaluation,
+    };
+
+    fn plan(
+        bought_cards: Vec<Card>,
+        played: Vec<Card>,
+        sampled_values: Vec<isize>,
+    ) -> PlanEvaluation {
+        let mean_value = sampled_values.iter().sum::<isize>() as f64 / sampled_values.len() as f64;
+        PlanEvaluation {
+            bought_cards,
+            actions: played
+                .into_iter()
+                .map(|card| TurnAction::PlayCard(card, vec![]))
+                .collect(),
+            final_state: PlayerStateBuilder::new(1).build(),
+            sampled_values,
+            mean_value,
+            standard_error: 0.0,
+        }
+    }
+
+    #[test]
+    fn plans_sharing_a_purchase_share_its_node() {
+        let research = CORPORATE_GAME_CARDS_BY_NAME["Research"].clone();
+        let plans = vec![
+            plan(vec![research.clone()], vec![research.clone()], vec![24, 26]),
+            plan(vec![research], vec![], vec![20, 20]),
+            plan(vec![], vec![], vec![21, 21]),
+        ];
+
+        let tree = SearchTree::from_plans(&plans);
+        // root, buy [Research], play Research, buy []
+        assert_eq!(4, tree.nodes.len());
+
+        let root = &tree.nodes[0];
+        assert_eq!(6, root.visits);
+        assert_eq!(25.0, root.best_value);
+        assert_eq!(22.0, root.mean_value);
+        assert_eq!(2, root.children.len());
+
+        let bought = &tree.nodes[root.children[0]];
+        assert_eq!(Some("buy [Research]".to_string()), bought.action);
+        assert_eq!(4, bought.visits);
+        assert_eq!(22.5, bought.mean_value);
+        assert!(bought.state.is_some()); // the plan that plays nothing ends here
+
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph search {\n"));
+        assert!(dot.contains("    n1 -> n2 [label=\"play Research\"];\n"));
+        assert!(dot.ends_with('}'));
+    }
+}
@@ -0,0 +1,366 @@
+//! Advice for setting up the game: which corporation to take, which preludes to keep,
+//! and which cards of the initial offer to buy.
+//!
+//! Corporations and preludes are not part of the crate's deck data, so they are described
+//! by the caller. A corporation is put into play as a card carrying its tags and effects,
+//! on top of its starting resources and production. Of a prelude, only the production and
+//! resources it grants are applied, like the planner does for every card.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CardEffect, CardKind, CardTag, ImmediateImpact},
+    card_ref,
+    draft::{DraftPick, SynergyDraftBot},
+    game::{GameState, PlayerId, PlayerState, TurnAction},
+    knowledge::KnowledgeTracker,
+    resource::{PaymentCost, Resource},
+    rng::GameRng,
+    sim::{evaluate_offers_batch, PlanEvaluation, PlanningOptions},
+};
+
+/// Number of preludes each player keeps of the ones dealt.
+pub const PRELUDES_KEPT: usize = 2;
+
+/// Only the most valuable cards of the initial offer, by the draft bot's estimate,
+/// are considered for purchase, so that the purchases to plan stay few.
+pub const SETUP_SHORTLIST_SIZE: usize = 6;
+
+/// Shallow planning: the first generations decide what a starting hand is worth.
+pub const SETUP_PLANNING: PlanningOptions = PlanningOptions {
+    generations: 3,
+    rollouts: 4,
+    threads: 1,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Corporation {
+    pub name: String,
+    pub starting_megacredits: usize,
+
+    #[serde(default)]
+    pub starting_resources: BTreeMap<Resource, usize>,
+    #[serde(default)]
+    pub production: BTreeMap<Resource, isize>,
+    #[serde(default)]
+    pub tags: Vec<CardTag>,
+    #[serde(default)]
+    pub effects: Vec<CardEffect>,
+}
+
+impl Corporation {
+    /// The card standing in for the corporation among the player's played cards.
+    pub fn as_card(&self) -> Card {
+        Card::new(
+            self.name.clone(),
+            CardKind::Automatic,
+            self.tags.clone(),
+            PaymentCost::Megacredits(0),
+            vec![],
+            None,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![],
+            vec![],
+            self.effects.clone(),
+            vec![],
+        )
+    }
+}
+
+/// The cards dealt to a player at the start of the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SetupOffer {
+    pub corporations: Vec<Corporation>,
+
+    #[serde(default)]
+    pub preludes: Vec<Card>,
+
+    #[serde(with = "card_ref::cards")]
+    pub cards: Vec<Card>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SetupAdvice {
+    pub corporation: String,
+    pub preludes: Vec<String>,
+
+    #[serde(with = "card_ref::cards")]
+    pub bought_cards: Vec<Card>,
+    pub first_plays: Vec<TurnAction>, // in the first generation, in canonical form
+
+    // mean victory points at the end of the planning horizon
+    pub value: f64,
+
+    // the draft bot's estimate of the bought cards, in megacredits; breaks ties in value,
+    // as the planner only scores cards it sees played
+    pub card_value: f64,
+}
+
+/// Every choice of corporation and preludes, each with the best purchase from the initial
+/// offer and the cards to play first, best choice first. Choices and purchases worth
+/// the same victory points are ranked by the value of the cards bought.
+///
+/// The player's state in the game is taken as the state before the corporation is chosen.
+/// Cards the offer does not hold are assumed to be unseen, except those the game's options
+/// leave out.
+pub fn advise_setup(
+    game: &GameState,
+    player_id: PlayerId,
+    offer: &SetupOffer,
+    options: &PlanningOptions,
+    rng: &mut GameRng,
+) -> Vec<SetupAdvice> {
+    let player = &game.players[&player_id];
+    let opponents: Vec<&PlayerState> = game
+        .players
+        .values()
+        .filter(|opponent| opponent.player_id != player_id)
+        .collect();
+    let bot = SynergyDraftBot::for_options(&game.options);
+    let knowledge = &KnowledgeTracker::new(game).players[&player_id];
+
+    let mut choices: Vec<(&Corporation, Vec<&Card>, Vec<DraftPick>)> = vec![];
+    let mut states_and_offers: Vec<(PlayerState, Vec<Card>)> = vec![];
+    for corporation in &offer.corporations {
+        for preludes in prelude_choices(&offer.preludes) {
+            let mut state = player.clone();
+            start_with_corporation(&mut state, corporation);
+            for prelude in &preludes {
+                play_prelude(&mut state, prelude);
+            }
+
+            let mut position = game.clone();
+            position.players.insert(player_id, state.clone());
+            let mut shortlist = bot.rank_pack(&position, knowledge, &offer.cards);
+            shortlist.truncate(SETUP_SHORTLIST_SIZE);
+
+            states_and_offers.push((
+                state,
+                shortlist.iter().map(|pick| pick.card.clone()).collect(),
+            ));
+            choices.push((corporation, preludes, shortlist));
+        }
+    }
+
+    let unseen_cards: Vec<Card> = bot
+        .deck
+        .iter()
+        .filter(|card| !offer.cards.contains(card))
+        .cloned()
+        .collect();
+    let plans = evaluate_offers_batch(
+        &game.board,
+        &states_and_offers,
+        &opponents,
+        &unseen_cards,
+        options,
+        rng,
+    );
+
+    let mut advice: Vec<SetupAdvice> = choices
+        .into_iter()
+        .zip(plans)
+        .filter_map(|((corporation, preludes, shortlist), plans)| {
+            let card_value = |plan: &PlanEvaluation| -> f64 {
+                shortlist
+                    .iter()
+                    .filter(|pick| plan.bought_cards.contains(&pick.card))
+                    .map(|pick| pick.value)
+                    .sum()
+            };
+            let best = plans
+                .into_iter()
+                .map(|plan| (card_value(&plan), plan))
+                .max_by(|(a_value, a), (b_value, b)| {
+                    (a.mean_value, a_value)
+                        .partial_cmp(&(b.mean_value, b_value))
+                        .unwrap()
+                });
+            let (card_value, best) = best?;
+            Some(SetupAdvice {
+                corporation: corporation.name.clone(),
+                preludes: preludes
+                    .iter()
+                    .map(|prelude| prelude.name.clone())
+                    .collect(),
+                bought_cards: best.bought_cards,
+                first_plays: best.actions,
+                value: best.mean_value,
+                card_value,
+            })
+        })
+        .collect();
+    advice.sort_by(|a, b| {
+        (b.value, b.card_value)
+            .partial_cmp(&(a.value, a.card_value))
+            .unwrap()
+    });
+    advice
+}
+
+// Every way of keeping `PRELUDES_KEPT` of the preludes dealt, or all of them if fewer.
+fn prelude_choices(preludes: &[Card]) -> Vec<Vec<&Card>> {
+    let mut choices = vec![];
+    let mut chosen = vec![];
+    choose_preludes(
+        preludes,
+        PRELUDES_KEPT.min(preludes.len()),
+        &mut chosen,
+        &mut choices,
+    );
+    choices
+}
+
+fn choose_preludes<'a>(
+    preludes: &'a [Card],
+    count: usize,
+    chosen: &mut Vec<&'a Card>,
+    choices: &mut Vec<Vec<&'a Card>>,
+) {
+    if count == 0 {
+        choices.push(chosen.clone());
+        return;
+    }
+    for (index, prelude) in preludes.iter().enumerate() {
+        chosen.push(prelude);
+        choose_preludes(&preludes[(index + 1)..], count - 1, chosen, choices);
+        chosen.pop();
+    }
+}
+
+fn start_with_corporation(state: &mut PlayerState, corporation: &Corporation) {
+    state.resources[Resource::Megacredits] += corporation.starting_megacredits;
+    for (resource, amount) in &corporation.starting_resources {
+        state.resources[resource] += amount;
+    }
+    for (resource, change) in &corporation.production {
+        state.production[resource] += change;
+    }
+    put_into_play(state, corporation.as_card());
+}
+
+fn play_prelude(state: &mut PlayerState, prelude: &Card) {
+    for (resource, change) in &prelude.own_production {
+        state.production[resource] += change;
+    }
+    for impact in &prelude.immediate_impacts {
+        match impact {
+            ImmediateImpact::GainResource(resource, amount) => state.resources[resource] += amount,
+            ImmediateImpact::ChangeProduction(resource, change) => {
+                state.production[resource] += change
+            }
+            _ => {}
+        }
+    }
+    put_into_play(state, prelude.clone());
+}
+
+fn put_into_play(state: &mut PlayerState, card: Card) {
+    for effect in &card.effects {
+        if let CardEffect::IncreasedMetalsValue(increase) = effect {
+            state.steel_value += increase;
+            state.titanium_value += increase;
+        }
+    }
+    state.effects.extend(card.effects.iter().cloned());
+    state.played_cards.push(card);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use maplit::btreemap;
+
+    use crate::{
+        card::{Card, CardKind, CardTag, ImmediateImpact, BASE_GAME_CARDS_BY_NAME},
+        position::import_position,
+        resource::{PaymentCost, Resource},
+        rng::GameRng,
+        setup::{advise_setup, Corporation, SetupOffer},
+        sim::PlanningOptions,
+    };
+
+    fn prelude(name: &str, impact: ImmediateImpact) -> Card {
+        Card::new(
+            name.to_string(),
+            CardKind::Automatic,
+            vec![CardTag::Building],
+            PaymentCost::Megacredits(0),
+            vec![],
+            None,
+            BTreeMap::new(),
+            BTreeMap::new(),
+            vec![impact],
+            vec![],
+            vec![],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn every_corporation_and_pair_of_preludes_is_considered() {
+        let game = import_position("player p1\nplayer p2\n").unwrap();
+        let player_id = *game.players.keys().next().unwrap();
+        let corporation = |name: &str, starting_megacredits| Corporation {
+            name: name.to_string(),
+            starting_megacredits,
+            starting_resources: BTreeMap::new(),
+            production: btreemap! { Resource::Plants => 1 },
+            tags: vec![CardTag::Plant],
+            effects: vec![],
+        };
+        let offer = SetupOffer {
+            corporations: vec![corporation("Rich", 60), corporation("Poor", 0)],
+            preludes: vec![
+                prelude(
+                    "Funding",
+                    ImmediateImpact::GainResource(Resource::Megacredits, 15),
+                ),
+                prelude(
+                    "Mohole",
+                    ImmediateImpact::ChangeProduction(Resource::Heat, 3),
+                ),
+                // Global parameters are not raised by the planner.
+                prelude("Warming", ImmediateImpact::RaiseTemperature),
+            ],
+            cards: vec![
+                BASE_GAME_CARDS_BY_NAME["Solar Power"].clone(),
+                BASE_GAME_CARDS_BY_NAME["Tundra Farming"].clone(),
+            ],
+        };
+
+        let advice = advise_setup(
+            &game,
+            player_id,
+            &offer,
+            &PlanningOptions {
+                generations: 2,
+                rollouts: 2,
+                threads: 2,
+            },
+            &mut GameRng::from_seed(0),
+        );
+
+        // Two corporations, and three ways of keeping two of three preludes.
+        assert_eq!(6, advice.len());
+        for pair in advice.windows(2) {
+            assert!(pair[0].value >= pair[1].value);
+        }
+        let best = &advice[0];
+        assert_eq!("Rich", best.corporation);
+        assert_eq!(vec!["Funding", "Mohole"], best.preludes);
+        assert!(!best.bought_cards.is_empty());
+        assert!(best.card_value > 0.0);
+
+        // Without money, nothing can be bought.
+        let poor = advice
+            .iter()
+            .find(|advice| advice.corporation == "Poor" && advice.preludes == ["Mohole", "Warming"])
+            .unwrap();
+        assert!(poor.bought_cards.is_empty());
+    }
+}
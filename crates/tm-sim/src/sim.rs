@@ -0,0 +1,976 @@
+use std::time::{Duration, Instant};
+
+use rand::prelude::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::MarsBoard,
+    card::Card,
+    canonical::canonicalize_actions,
+    card_ref,
+    bounds::final_vp_bounds,
+    endgame::{solve_endgame, ENDGAME_MAX_CARDS, ENDGAME_MAX_GENERATIONS},
+    eval::is_clearly_better,
+    game::{PlayerState, TurnAction, RESEARCH_PHASE_OFFER_SIZE},
+    options::ProductionStep,
+    projection::{estimate_remaining_generations, ProjectionAssumptions},
+    resource::Resource,
+    rng::GameRng,
+};
+
+pub fn get_possible_generation_plays(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    offered_cards: Vec<Card>,
+) -> Vec<(Vec<Card>, Vec<TurnAction>, PlayerState)> {
+    // return vec of (bought cards, actions taken, final state)
+    let mut result: Vec<(Vec<Card>, Vec<TurnAction>, PlayerState)> = Vec::with_capacity(1024);
+
+    assert!(offered_cards.len() <= 10);
+    let all_variations = 1usize << offered_cards.len();
+
+    let mut scratch = PlayScratch::default();
+    for variation in 0..all_variations {
+        let mut current_state = initial_state.clone();
+        scratch.purchased_cards.clear();
+        for (i, card) in offered_cards.iter().enumerate() {
+            if variation & (1usize << i) != 0 {
+                scratch.purchased_cards.push(card);
+            }
+        }
+
+        match current_state.purchase_cards(&scratch.purchased_cards) {
+            None => continue,
+            Some(_) => {
+                make_all_possible_plays(board, &current_state, opponent_states, &mut scratch);
+
+                let purchased_cards = &scratch.purchased_cards;
+                result.extend(scratch.plays.drain(..).map(|(turns, final_state)| {
+                    (
+                        purchased_cards.iter().copied().cloned().collect(),
+                        turns,
+                        final_state,
+                    )
+                }));
+            }
+        }
+    }
+
+    result
+}
+
+/// Buffers that live for one generation's worth of play generation and are reused
+/// for every card purchase considered, so that exploring the possible plays only
+/// allocates for the plays that are returned.
+#[derive(Debug, Default)]
+struct PlayScratch<'a> {
+    purchased_cards: Vec<&'a Card>,
+
+    // the actions leading to the play currently being explored
+    actions: Vec<TurnAction>,
+
+    // (actions taken, final state) for each complete play found so far
+    plays: Vec<(Vec<TurnAction>, PlayerState)>,
+}
+
+fn make_all_possible_plays(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    scratch: &mut PlayScratch,
+) {
+    scratch.actions.clear();
+    make_all_possible_plays_recursively(0, board, initial_state, opponent_states, scratch);
+}
+
+#[allow(clippy::only_used_in_recursion)]
+fn make_all_possible_plays_recursively(
+    next_card_index_to_consider: usize,
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    scratch: &mut PlayScratch,
+) {
+    match initial_state.cards_in_hand.get(next_card_index_to_consider) {
+        None => {
+            // Heat is only good for raising the temperature, so the play ends by converting
+            // all that the player has.
+            let mut final_state = initial_state.clone();
+            let conversions = final_state.heat_conversions_available(board);
+            for _ in 0..conversions {
+                final_state.convert_heat();
+                scratch.actions.push(TurnAction::ConvertHeat(None));
+            }
+            scratch.plays.push((scratch.actions.clone(), final_state));
+            scratch.actions.truncate(scratch.actions.len() - conversions);
+        }
+        Some(card) => {
+            let mut next_state = initial_state.clone();
+            if next_state.play_card(board, card).is_some() {
+                scratch.actions.push(TurnAction::PlayCard(card.clone(), vec![]));
+                // The card left the hand, so the next one to consider took its place.
+                make_all_possible_plays_recursively(
+                    next_card_index_to_consider,
+                    board,
+                    &next_state,
+                    opponent_states,
+                    scratch,
+                );
+                scratch.actions.pop();
+            } else {
+                make_all_possible_plays_recursively(
+                    next_card_index_to_consider + 1,
+                    board,
+                    initial_state,
+                    opponent_states,
+                    scratch,
+                );
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanningOptions {
+    // number of generations to plan for, including the current one
+    pub generations: usize,
+
+    // number of sampled futures used to value each plan for the current generation
+    pub rollouts: usize,
+
+    // number of threads to spread the rollouts over; does not affect the results
+    pub threads: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlanEvaluation {
+    #[serde(with = "card_ref::cards")]
+    pub bought_cards: Vec<Card>,
+    pub actions: Vec<TurnAction>, // in canonical form
+    pub final_state: PlayerState,
+
+    // victory points at the end of the planning horizon, one per rollout
+    pub sampled_values: Vec<isize>,
+    pub mean_value: f64,
+    // of the mean value, from the spread of the rollouts; zero for exact values
+    #[serde(default)]
+    pub standard_error: f64,
+}
+
+impl PlanEvaluation {
+    /// Whether this plan is better than the other by more than the rollouts' noise,
+    /// rather than roughly as good.
+    pub fn is_clearly_better_than(&self, other: &PlanEvaluation) -> bool {
+        is_clearly_better(
+            self.mean_value,
+            self.standard_error,
+            other.mean_value,
+            other.standard_error,
+        )
+    }
+}
+
+/// Value every possible play for the current generation by looking ahead several generations.
+///
+/// Future research phases are not assumed to offer nothing: each rollout deals the player
+/// offers sampled without replacement from `unseen_cards` (the cards that could still be drawn),
+/// plays each future generation greedily, and records the resulting victory points.
+/// The values of all rollouts are averaged, and the plans are returned best-first.
+///
+/// Each rollout draws from its own generator forked off `rng`, so the results are the same
+/// for a given seed no matter how many threads are used.
+///
+/// If the game is expected to end within the planning horizon and few cards are involved,
+/// every purchase is instead valued by the best card plays `solve_endgame()` finds, without
+/// sampling. Purchases dominated by another, i.e. that can't even tie its value according to
+/// `final_vp_bounds()`, are left out.
+pub fn plan_generations(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    offered_cards: Vec<Card>,
+    unseen_cards: &[Card],
+    options: &PlanningOptions,
+    rng: &mut GameRng,
+) -> Vec<PlanEvaluation> {
+    assert!(options.generations >= 1);
+    assert!(options.rollouts >= 1);
+    assert!(options.threads >= 1);
+
+    let remaining_generations = estimate_remaining_generations(
+        board,
+        ProjectionAssumptions::default().parameter_steps_per_generation,
+    );
+    plan_generations_ending_in(
+        board,
+        initial_state,
+        opponent_states,
+        offered_cards,
+        unseen_cards,
+        options,
+        remaining_generations,
+        rng,
+    )
+}
+
+/// `plan_generations()` for many candidate hands at once, e.g. every starting hand
+/// a corporation could be dealt, all on the same board and against the same opponents.
+///
+/// Returns the plans for each state and its offer, in the order given. The estimate of
+/// how long the game goes on is made once for all of them, and `unseen_cards` is shared,
+/// with each item's own hand and offer left out of the offers sampled for it. The items are
+/// spread over the threads of the options; each item's own rollouts run on a single thread.
+/// The results are the same for a given seed no matter how many threads are used.
+pub fn evaluate_offers_batch(
+    board: &MarsBoard,
+    states_and_offers: &[(PlayerState, Vec<Card>)],
+    opponent_states: &Vec<&PlayerState>,
+    unseen_cards: &[Card],
+    options: &PlanningOptions,
+    rng: &mut GameRng,
+) -> Vec<Vec<PlanEvaluation>> {
+    assert!(options.generations >= 1);
+    assert!(options.rollouts >= 1);
+    assert!(options.threads >= 1);
+
+    let remaining_generations = estimate_remaining_generations(
+        board,
+        ProjectionAssumptions::default().parameter_steps_per_generation,
+    );
+    let item_options = PlanningOptions {
+        threads: 1,
+        ..options.clone()
+    };
+
+    // Fork all generators up front, in item order, before any work is handed out.
+    let mut rngs: Vec<GameRng> = states_and_offers.iter().map(|_| rng.fork()).collect();
+    let mut results: Vec<Vec<PlanEvaluation>> = vec![vec![]; states_and_offers.len()];
+
+    let chunk_size = states_and_offers.len().div_ceil(options.threads).max(1);
+    std::thread::scope(|scope| {
+        for ((items, chunk_rngs), chunk_results) in states_and_offers
+            .chunks(chunk_size)
+            .zip(rngs.chunks_mut(chunk_size))
+            .zip(results.chunks_mut(chunk_size))
+        {
+            let item_options = &item_options;
+            scope.spawn(move || {
+                for (((state, offered), rng), result) in
+                    items.iter().zip(chunk_rngs).zip(chunk_results)
+                {
+                    let unseen: Vec<Card> = unseen_cards
+                        .iter()
+                        .filter(|card| {
+                            !offered.contains(card) && !state.cards_in_hand.contains(card)
+                        })
+                        .cloned()
+                        .collect();
+                    *result = plan_generations_ending_in(
+                        board,
+                        state,
+                        opponent_states,
+                        offered.clone(),
+                        &unseen,
+                        item_options,
+                        remaining_generations,
+                        rng,
+                    );
+                }
+            });
+        }
+    });
+
+    results
+}
+
+#[allow(clippy::too_many_arguments)]
+fn plan_generations_ending_in(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    offered_cards: Vec<Card>,
+    unseen_cards: &[Card],
+    options: &PlanningOptions,
+    remaining_generations: usize,
+    rng: &mut GameRng,
+) -> Vec<PlanEvaluation> {
+    let is_endgame = remaining_generations <= options.generations.min(ENDGAME_MAX_GENERATIONS)
+        && initial_state.cards_in_hand.len() + offered_cards.len() <= ENDGAME_MAX_CARDS;
+
+    let plays = get_possible_generation_plays(board, initial_state, opponent_states, offered_cards);
+    if is_endgame {
+        return solve_plays(board, initial_state, plays, remaining_generations);
+    }
+
+    let rollout_values: Vec<isize> = if options.generations == 1 {
+        vec![]
+    } else {
+        let rollout_states: Vec<&PlayerState> = plays
+            .iter()
+            .flat_map(|(_, _, final_state)| std::iter::repeat_n(final_state, options.rollouts))
+            .collect();
+        run_rollouts(
+            board,
+            &rollout_states,
+            opponent_states,
+            unseen_cards,
+            options.generations - 1,
+            options.threads,
+            rng,
+        )
+    };
+
+    let mut result: Vec<PlanEvaluation> = plays
+        .into_iter()
+        .enumerate()
+        .map(|(plan_index, (bought_cards, actions, final_state))| {
+            let sampled_values: Vec<isize> = if options.generations == 1 {
+                // Nothing left to sample, the outcome is already known.
+                vec![final_state.get_total_victory_points(board)]
+            } else {
+                let start = plan_index * options.rollouts;
+                rollout_values[start..(start + options.rollouts)].to_vec()
+            };
+
+            evaluate_plan(bought_cards, actions, final_state, sampled_values)
+        })
+        .collect();
+
+    result.sort_by(|a, b| b.mean_value.partial_cmp(&a.mean_value).unwrap());
+    result
+}
+
+// The exact values of the plays when the game ends within the given number of generations,
+// with one plan per purchase: the best line of play that follows it. Purchases are solved
+// in order of their upper bounds, so that the dominated ones need not be solved at all.
+fn solve_plays(
+    board: &MarsBoard,
+    initial_state: &PlayerState,
+    plays: Vec<(Vec<Card>, Vec<TurnAction>, PlayerState)>,
+    generations: usize,
+) -> Vec<PlanEvaluation> {
+    let mut purchases: Vec<(Vec<Card>, PlayerState, isize)> = vec![];
+    for (bought_cards, _, _) in plays {
+        if purchases.iter().any(|(cards, _, _)| *cards == bought_cards) {
+            continue;
+        }
+
+        let mut state_after_purchase = initial_state.clone();
+        state_after_purchase
+            .purchase_cards(&bought_cards.iter().collect())
+            .unwrap();
+        let upper = final_vp_bounds(board, &state_after_purchase, generations).upper;
+        purchases.push((bought_cards, state_after_purchase, upper));
+    }
+    purchases.sort_by_key(|(_, _, upper)| std::cmp::Reverse(*upper));
+
+    let mut best_points: Option<isize> = None;
+    let mut result = vec![];
+    for (bought_cards, state_after_purchase, upper) in purchases {
+        if best_points.is_some_and(|best_points| upper < best_points) {
+            break;
+        }
+
+        let solution = solve_endgame(board, &state_after_purchase, generations);
+        best_points = best_points.max(Some(solution.final_points));
+        let actions = solution.plays.into_iter().next().unwrap_or_default();
+        result.push(evaluate_plan(
+            bought_cards,
+            actions,
+            solution.final_state,
+            vec![solution.final_points],
+        ));
+    }
+
+    result.sort_by(|a, b| b.mean_value.partial_cmp(&a.mean_value).unwrap());
+    result
+}
+
+fn evaluate_plan(
+    bought_cards: Vec<Card>,
+    actions: Vec<TurnAction>,
+    final_state: PlayerState,
+    sampled_values: Vec<isize>,
+) -> PlanEvaluation {
+    let count = sampled_values.len() as f64;
+    let mean_value = sampled_values.iter().sum::<isize>() as f64 / count;
+    let standard_error = if sampled_values.len() < 2 {
+        0.0
+    } else {
+        let variance = sampled_values
+            .iter()
+            .map(|value| (*value as f64 - mean_value).powi(2))
+            .sum::<f64>()
+            / (count - 1.0);
+        (variance / count).sqrt()
+    };
+    PlanEvaluation {
+        bought_cards,
+        actions: canonicalize_actions(&actions),
+        final_state,
+        sampled_values,
+        mean_value,
+        standard_error,
+    }
+}
+
+/// The search of `plan_generations()`, run a slice at a time, e.g. between the frames of a UI
+/// or the ticks of a server, and queried for the best plan found so far in between.
+///
+/// Rollouts are run round-robin over the plans, so that all plans have about as many samples
+/// whenever the search is paused. Once done, the plans are the same as `plan_generations()`
+/// returns for the same generator, unless that solved the endgame exactly instead.
+/// The number of threads in the options is not used.
+#[derive(Debug)]
+pub struct SearchSession {
+    board: MarsBoard,
+    opponent_states: Vec<PlayerState>,
+    unseen_cards: Vec<Card>,
+    future_generations: usize,
+    rollouts: usize,
+
+    plays: Vec<(Vec<Card>, Vec<TurnAction>, PlayerState)>,
+    sampled_values: Vec<Vec<isize>>, // per play, in rollout order
+
+    // one per rollout, forked in the same order as by `plan_generations()`: play by play
+    rngs: Vec<GameRng>,
+    next_rollout: usize, // counted round-robin: rollout 0 of every play, then rollout 1, ...
+}
+
+impl SearchSession {
+    pub fn new(
+        board: &MarsBoard,
+        initial_state: &PlayerState,
+        opponent_states: &[&PlayerState],
+        offered_cards: Vec<Card>,
+        unseen_cards: &[Card],
+        options: &PlanningOptions,
+        rng: &mut GameRng,
+    ) -> Self {
+        assert!(options.generations >= 1);
+        assert!(options.rollouts >= 1);
+
+        let opponent_states: Vec<PlayerState> =
+            opponent_states.iter().map(|state| (*state).clone()).collect();
+        let plays = get_possible_generation_plays(
+            board,
+            initial_state,
+            &opponent_states.iter().collect(),
+            offered_cards,
+        );
+
+        let (sampled_values, rngs) = if options.generations == 1 {
+            // Nothing left to sample, the outcome is already known.
+            let values = plays
+                .iter()
+                .map(|(_, _, final_state)| vec![final_state.get_total_victory_points(board)])
+                .collect();
+            (values, vec![])
+        } else {
+            let rngs = (0..plays.len() * options.rollouts)
+                .map(|_| rng.fork())
+                .collect();
+            (vec![vec![]; plays.len()], rngs)
+        };
+
+        Self {
+            board: board.clone(),
+            opponent_states,
+            unseen_cards: unseen_cards.to_vec(),
+            future_generations: options.generations - 1,
+            rollouts: options.rollouts,
+            plays,
+            sampled_values,
+            rngs,
+            next_rollout: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_rollout == self.rngs.len()
+    }
+
+    /// Run at most `max_rollouts` more rollouts. Returns whether the search is done.
+    pub fn step(&mut self, max_rollouts: usize) -> bool {
+        let opponent_states: Vec<&PlayerState> = self.opponent_states.iter().collect();
+        for _ in 0..max_rollouts {
+            if self.is_done() {
+                break;
+            }
+
+            let play_index = self.next_rollout % self.plays.len();
+            let rollout_index = self.next_rollout / self.plays.len();
+            let value = rollout_future_generations(
+                &self.board,
+                &self.plays[play_index].2,
+                &opponent_states,
+                &self.unseen_cards,
+                self.future_generations,
+                &mut self.rngs[play_index * self.rollouts + rollout_index],
+            );
+            self.sampled_values[play_index].push(value);
+            self.next_rollout += 1;
+        }
+
+        self.is_done()
+    }
+
+    /// Run rollouts until the time is up or the search is done. Returns whether it is done.
+    ///
+    /// At least one rollout is run, if any are left, and the last one may end past the deadline.
+    pub fn step_for(&mut self, time: Duration) -> bool {
+        let deadline = Instant::now() + time;
+        while !self.step(1) && Instant::now() < deadline {}
+        self.is_done()
+    }
+
+    /// The plans valued so far, best-first. Plans without any samples yet are left out.
+    pub fn plans(&self) -> Vec<PlanEvaluation> {
+        let mut result: Vec<PlanEvaluation> = self
+            .plays
+            .iter()
+            .zip(&self.sampled_values)
+            .filter(|(_, sampled_values)| !sampled_values.is_empty())
+            .map(|((bought_cards, actions, final_state), sampled_values)| {
+                evaluate_plan(
+                    bought_cards.clone(),
+                    actions.clone(),
+                    final_state.clone(),
+                    sampled_values.clone(),
+                )
+            })
+            .collect();
+
+        result.sort_by(|a, b| b.mean_value.partial_cmp(&a.mean_value).unwrap());
+        result
+    }
+
+    pub fn best_plan(&self) -> Option<PlanEvaluation> {
+        self.plans().into_iter().next()
+    }
+}
+
+// Runs one rollout per state, returning the values in the same order as the states.
+fn run_rollouts(
+    board: &MarsBoard,
+    rollout_states: &[&PlayerState],
+    opponent_states: &Vec<&PlayerState>,
+    unseen_cards: &[Card],
+    future_generations: usize,
+    threads: usize,
+    rng: &mut GameRng,
+) -> Vec<isize> {
+    // Fork all generators up front, in rollout order, before any work is handed out.
+    let mut rngs: Vec<GameRng> = rollout_states.iter().map(|_| rng.fork()).collect();
+    let mut values: Vec<isize> = vec![0; rollout_states.len()];
+
+    let chunk_size = rollout_states.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        for ((states, chunk_rngs), chunk_values) in rollout_states
+            .chunks(chunk_size)
+            .zip(rngs.chunks_mut(chunk_size))
+            .zip(values.chunks_mut(chunk_size))
+        {
+            scope.spawn(move || {
+                for ((state, rng), value) in states.iter().zip(chunk_rngs).zip(chunk_values) {
+                    *value = rollout_future_generations(
+                        board,
+                        state,
+                        opponent_states,
+                        unseen_cards,
+                        future_generations,
+                        rng,
+                    );
+                }
+            });
+        }
+    });
+
+    values
+}
+
+fn rollout_future_generations(
+    board: &MarsBoard,
+    state_after_current_generation: &PlayerState,
+    opponent_states: &Vec<&PlayerState>,
+    unseen_cards: &[Card],
+    future_generations: usize,
+    rng: &mut GameRng,
+) -> isize {
+    let mut deck: Vec<&Card> = unseen_cards.iter().collect();
+    deck.shuffle(rng);
+
+    let mut state = state_after_current_generation.clone();
+    for _ in 0..future_generations {
+        state.advance_generation(&ProductionStep::STANDARD);
+
+        let offer_size = RESEARCH_PHASE_OFFER_SIZE.min(deck.len());
+        let offer: Vec<Card> = deck.drain(..offer_size).cloned().collect();
+
+        // Assume the player makes the choice that looks best at the end of that generation.
+        state = get_possible_generation_plays(board, &state, opponent_states, offer)
+            .drain(..)
+            .map(|(_, _, final_state)| final_state)
+            .max_by_key(|final_state| {
+                (
+                    final_state.get_total_victory_points(board),
+                    final_state.resources[&Resource::Megacredits],
+                )
+            })
+            .unwrap_or(state);
+    }
+
+    state.get_total_victory_points(board)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::{make_base_game_board, Coordinates, MarsBoard, MarsBoardBuilder}, card::{BASE_GAME_CARDS_BY_NAME, BASE_GAME_DECK, Card}, game::{PlayerState, TurnAction}, sim::{evaluate_offers_batch, get_possible_generation_plays, plan_generations, PlanningOptions, SearchSession}};
+    use crate::game::PlayerStateBuilder;
+    use crate::resource::Resource;
+    use crate::rng::GameRng;
+
+    #[test]
+    fn get_possible_plays_when_no_card_buys_or_plays_exist() {
+        let board = make_base_game_board();
+
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(2, 0, 0, 0, 0, 0)
+            .build();
+
+        let offered_cards = [
+            BASE_GAME_CARDS_BY_NAME["Fueled Generators"],
+            BASE_GAME_CARDS_BY_NAME["Nuclear Power"],
+            BASE_GAME_CARDS_BY_NAME["Solar Power"],
+            BASE_GAME_CARDS_BY_NAME["GHG Factories"],
+        ];
+
+        let opponent_state = PlayerStateBuilder::new(2).build();
+        let opponent_states = vec![&opponent_state];
+
+        let expected_plays: Vec<(Vec<Card>, Vec<TurnAction>, PlayerState)> = vec![
+            (vec![], vec![], player_state.clone()),
+        ];
+
+        let actual_plays = get_possible_generation_plays(
+            &board,
+            &player_state,
+            &opponent_states,
+            offered_cards.iter().copied().cloned().collect(),
+        );
+
+        assert_eq!(expected_plays, actual_plays);
+    }
+
+    #[test]
+    fn plays_end_by_converting_heat_until_the_temperature_maxes_out() {
+        let board = MarsBoardBuilder::new()
+            .with_temperature(MarsBoard::MAX_TEMPERATURE - 2 * MarsBoard::TEMPERATURE_INCREMENT)
+            .build();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(0, 0, 0, 0, 0, 30)
+            .build();
+        let opponent_state = PlayerStateBuilder::new(2).build();
+
+        let plays =
+            get_possible_generation_plays(&board, &player_state, &vec![&opponent_state], vec![]);
+        assert_eq!(1, plays.len());
+
+        let (_, actions, final_state) = &plays[0];
+        assert_eq!(&vec![TurnAction::ConvertHeat(None); 2], actions);
+        assert_eq!(14, final_state.resources[Resource::Heat]);
+        assert_eq!(player_state.terraform_rating + 2, final_state.terraform_rating);
+    }
+
+    #[test]
+    fn each_play_only_includes_its_own_actions() {
+        let board = make_base_game_board();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(40, 0, 0, 0, 0, 0)
+            .build();
+        let offered_cards = vec![
+            BASE_GAME_CARDS_BY_NAME["Solar Power"].clone(),
+            BASE_GAME_CARDS_BY_NAME["Fueled Generators"].clone(),
+            BASE_GAME_CARDS_BY_NAME["GHG Factories"].clone(),
+        ];
+        let opponent_state = PlayerStateBuilder::new(2).build();
+
+        let plays = get_possible_generation_plays(
+            &board,
+            &player_state,
+            &vec![&opponent_state],
+            offered_cards,
+        );
+        assert_eq!(8, plays.len());
+        for (bought_cards, actions, final_state) in plays {
+            let played: Vec<Card> = bought_cards
+                .iter()
+                .filter(|card| !final_state.cards_in_hand.contains(card))
+                .cloned()
+                .collect();
+            assert_eq!(played, final_state.played_cards);
+            assert_eq!(
+                played
+                    .into_iter()
+                    .map(|card| TurnAction::PlayCard(card, vec![]))
+                    .collect::<Vec<_>>(),
+                actions
+            );
+        }
+    }
+
+    #[test]
+    fn single_generation_plans_are_valued_without_sampling() {
+        let board = make_base_game_board();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(6, 0, 0, 0, 0, 0)
+            .build();
+        let offered_cards: Vec<Card> = vec![
+            BASE_GAME_CARDS_BY_NAME["Fueled Generators"].clone(),
+            BASE_GAME_CARDS_BY_NAME["Nuclear Power"].clone(),
+        ];
+
+        let mut rng = GameRng::from_seed(0);
+        let plans = plan_generations(
+            &board,
+            &player_state,
+            &vec![],
+            offered_cards,
+            &BASE_GAME_DECK,
+            &PlanningOptions { generations: 1, rollouts: 8, threads: 1 },
+            &mut rng,
+        );
+
+        // No purchase, either single card, or both cards.
+        assert_eq!(4, plans.len());
+        for plan in plans {
+            assert_eq!(vec![20], plan.sampled_values);
+            assert_eq!(0.0, plan.standard_error);
+        }
+    }
+
+    #[test]
+    fn multi_generation_plans_are_averaged_over_sampled_offers() {
+        let board = make_base_game_board();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(3, 0, 0, 0, 0, 0)
+            .build();
+        let offered_cards: Vec<Card> = vec![BASE_GAME_CARDS_BY_NAME["Tundra Farming"].clone()];
+
+        let mut rng = GameRng::from_seed(0);
+        let plans = plan_generations(
+            &board,
+            &player_state,
+            &vec![],
+            offered_cards,
+            &BASE_GAME_DECK,
+            &PlanningOptions { generations: 3, rollouts: 5, threads: 1 },
+            &mut rng,
+        );
+
+        assert_eq!(2, plans.len());
+        for pair in plans.windows(2) {
+            assert!(pair[0].mean_value >= pair[1].mean_value);
+        }
+        for plan in plans {
+            assert_eq!(5, plan.sampled_values.len());
+            let total: isize = plan.sampled_values.iter().sum();
+            assert_eq!(total as f64 / 5.0, plan.mean_value);
+            // Rollouts that disagree leave the mean uncertain.
+            let all_agree = plan
+                .sampled_values
+                .iter()
+                .all(|value| *value == plan.sampled_values[0]);
+            assert_eq!(all_agree, plan.standard_error == 0.0);
+        }
+    }
+
+    #[test]
+    fn batches_match_planning_each_hand_on_its_own() {
+        let board = make_base_game_board();
+        let tundra_farming = BASE_GAME_CARDS_BY_NAME["Tundra Farming"].clone();
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+        let states_and_offers = vec![
+            (
+                PlayerStateBuilder::new(1).with_resources(6, 0, 0, 0, 0, 0).build(),
+                vec![tundra_farming.clone()],
+            ),
+            (
+                PlayerStateBuilder::new(1).with_resources(12, 0, 0, 0, 0, 0).build(),
+                vec![solar_power.clone(), tundra_farming.clone()],
+            ),
+        ];
+
+        let batch_with_threads = |threads| {
+            evaluate_offers_batch(
+                &board,
+                &states_and_offers,
+                &vec![],
+                &BASE_GAME_DECK,
+                &PlanningOptions { generations: 3, rollouts: 4, threads },
+                &mut GameRng::from_seed(7),
+            )
+        };
+        let batch = batch_with_threads(1);
+        assert_eq!(batch, batch_with_threads(2));
+
+        // Each item draws from the next generator forked off the batch's.
+        let mut rng = GameRng::from_seed(7);
+        for ((state, offered), plans) in states_and_offers.iter().zip(&batch) {
+            let unseen: Vec<Card> = BASE_GAME_DECK
+                .iter()
+                .filter(|card| !offered.contains(card))
+                .cloned()
+                .collect();
+            let expected = plan_generations(
+                &board,
+                state,
+                &vec![],
+                offered.clone(),
+                &unseen,
+                &PlanningOptions { generations: 3, rollouts: 4, threads: 1 },
+                &mut rng.fork(),
+            );
+            assert_eq!(&expected, plans);
+        }
+    }
+
+    #[test]
+    fn plans_do_not_depend_on_the_number_of_threads() {
+        let board = make_base_game_board();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(6, 0, 0, 0, 0, 0)
+            .build();
+        let offered_cards: Vec<Card> = vec![
+            BASE_GAME_CARDS_BY_NAME["Tundra Farming"].clone(),
+            BASE_GAME_CARDS_BY_NAME["Solar Power"].clone(),
+        ];
+
+        let plan_with_threads = |threads| {
+            plan_generations(
+                &board,
+                &player_state,
+                &vec![],
+                offered_cards.clone(),
+                &BASE_GAME_DECK,
+                &PlanningOptions { generations: 3, rollouts: 5, threads },
+                &mut GameRng::from_seed(1234),
+            )
+        };
+
+        let single_threaded = plan_with_threads(1);
+        assert_eq!(single_threaded, plan_with_threads(3));
+        assert_eq!(single_threaded, plan_with_threads(8));
+    }
+
+    #[test]
+    fn search_sessions_end_with_the_same_plans_as_a_blocking_search() {
+        let board = make_base_game_board();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(6, 0, 0, 0, 0, 0)
+            .build();
+        let offered_cards: Vec<Card> = vec![
+            BASE_GAME_CARDS_BY_NAME["Tundra Farming"].clone(),
+            BASE_GAME_CARDS_BY_NAME["Solar Power"].clone(),
+        ];
+        let options = PlanningOptions { generations: 3, rollouts: 5, threads: 1 };
+
+        let blocking = plan_generations(
+            &board,
+            &player_state,
+            &vec![],
+            offered_cards.clone(),
+            &BASE_GAME_DECK,
+            &options,
+            &mut GameRng::from_seed(1234),
+        );
+
+        let mut session = SearchSession::new(
+            &board,
+            &player_state,
+            &[],
+            offered_cards,
+            &BASE_GAME_DECK,
+            &options,
+            &mut GameRng::from_seed(1234),
+        );
+        assert_eq!(None, session.best_plan());
+
+        // One slice samples every plan once, before any plan is sampled twice.
+        assert!(!session.step(blocking.len()));
+        let plans = session.plans();
+        assert_eq!(blocking.len(), plans.len());
+        assert!(plans.iter().all(|plan| plan.sampled_values.len() == 1));
+
+        while !session.step(3) {}
+        assert_eq!(blocking, session.plans());
+        assert_eq!(blocking.first(), session.best_plan().as_ref());
+    }
+
+    #[test]
+    fn plans_for_the_last_generation_are_solved_exactly() {
+        let mut board = MarsBoardBuilder::new()
+            .with_oxygen(MarsBoard::MAX_OXYGEN)
+            .with_temperature(MarsBoard::MAX_TEMPERATURE);
+        for i in 0..(MarsBoard::MAX_OCEANS as isize - 1) {
+            board = board.with_ocean(Coordinates::new(i, -i));
+        }
+        let board = board.build();
+        let player_state = PlayerStateBuilder::new(1)
+            .with_resources(30, 0, 0, 0, 0, 0)
+            .build();
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+
+        let plans = plan_generations(
+            &board,
+            &player_state,
+            &vec![],
+            vec![solar_power.clone()],
+            &BASE_GAME_DECK,
+            &PlanningOptions { generations: 3, rollouts: 5, threads: 1 },
+            &mut GameRng::from_seed(0),
+        );
+
+        // One exact value per purchase, with the bought card played right away.
+        assert_eq!(2, plans.len());
+        assert_eq!(vec![solar_power.clone()], plans[0].bought_cards);
+        assert_eq!(
+            vec![TurnAction::PlayCard(solar_power, vec![])],
+            plans[0].actions
+        );
+        assert_eq!(vec![21], plans[0].sampled_values);
+        assert_eq!(vec![20], plans[1].sampled_values);
+    }
+
+    #[test]
+    fn endgame_purchases_that_cannot_tie_the_best_are_not_solved() {
+        let mut board = MarsBoardBuilder::new()
+            .with_oxygen(MarsBoard::MAX_OXYGEN)
+            .with_temperature(MarsBoard::MAX_TEMPERATURE);
+        for i in 0..(MarsBoard::MAX_OCEANS as isize) {
+            board = board.with_ocean(Coordinates::new(i, -i));
+        }
+        let board = board.build();
+        // Too little income for any tile, so nothing but Solar Power can score.
+        let player_state = PlayerStateBuilder::new(1)
+            .with_terraform_rating(0)
+            .with_resources(14, 0, 0, 0, 0, 0)
+            .build();
+        let solar_power = BASE_GAME_CARDS_BY_NAME["Solar Power"].clone();
+
+        let plans = plan_generations(
+            &board,
+            &player_state,
+            &vec![],
+            vec![solar_power.clone()],
+            &BASE_GAME_DECK,
+            &PlanningOptions { generations: 1, rollouts: 1, threads: 1 },
+            &mut GameRng::from_seed(0),
+        );
+
+        assert_eq!(1, plans.len());
+        assert_eq!(vec![solar_power], plans[0].bought_cards);
+        assert_eq!(vec![1], plans[0].sampled_values);
+    }
+}
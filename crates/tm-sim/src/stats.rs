@@ -0,0 +1,235 @@
+//! Statistics for balance experiments, e.g. "is agent B stronger than agent A" or
+//! "does having card X in the opening hand win more games": win rates with confidence intervals,
+//! and a sequential probability ratio test that stops an experiment as soon as it is decided.
+
+use serde::{Deserialize, Serialize};
+
+/// z-score of a two-sided 95% confidence interval.
+pub const Z_95: f64 = 1.959_963_984_540_054;
+
+/// The result of one game, from the point of view of the agent or setup being tested.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WinRate {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl WinRate {
+    pub fn record(&mut self, outcome: GameOutcome) {
+        match outcome {
+            GameOutcome::Win => self.wins += 1,
+            GameOutcome::Draw => self.draws += 1,
+            GameOutcome::Loss => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Draws count as half a win. Zero if no games were played.
+    pub fn rate(&self) -> f64 {
+        if self.games() == 0 {
+            0.0
+        } else {
+            (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+        }
+    }
+
+    /// Wilson score interval around the win rate, for the given z-score (e.g. `Z_95`).
+    ///
+    /// Unlike the normal approximation, the interval stays within [0, 1] and
+    /// is still meaningful when every game so far was won or lost.
+    pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+        if self.games() == 0 {
+            return (0.0, 1.0);
+        }
+
+        let n = self.games() as f64;
+        let p = self.rate();
+        let z2 = z * z;
+        let center = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+        let half_width = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / (1.0 + z2 / n);
+        (
+            (center - half_width).max(0.0),
+            (center + half_width).min(1.0),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SprtDecision {
+    Continue,
+    // the win rate is consistent with the null hypothesis, e.g. "no stronger"
+    AcceptNull,
+    // the win rate is consistent with the alternative hypothesis, e.g. "stronger"
+    AcceptAlternative,
+}
+
+/// Sequential probability ratio test between two win rates.
+///
+/// Games are fed in one at a time, and the test decides as soon as the evidence for either
+/// hypothesis is strong enough for the chosen error rates, usually long before a fixed-size
+/// experiment of the same power would have finished. Draws count as half a win and half a loss.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Sprt {
+    // win rates under the null and the alternative hypothesis
+    pub null_rate: f64,
+    pub alternative_rate: f64,
+
+    // chance of accepting the alternative when the null hypothesis is true, and vice versa
+    pub false_positive_rate: f64,
+    pub false_negative_rate: f64,
+
+    pub results: WinRate,
+    log_likelihood_ratio: f64,
+}
+
+impl Sprt {
+    pub fn new(
+        null_rate: f64,
+        alternative_rate: f64,
+        false_positive_rate: f64,
+        false_negative_rate: f64,
+    ) -> Self {
+        assert!(0.0 < null_rate && null_rate < 1.0);
+        assert!(0.0 < alternative_rate && alternative_rate < 1.0);
+        assert!(null_rate != alternative_rate);
+        assert!(0.0 < false_positive_rate && false_positive_rate < 0.5);
+        assert!(0.0 < false_negative_rate && false_negative_rate < 0.5);
+
+        Self {
+            null_rate,
+            alternative_rate,
+            false_positive_rate,
+            false_negative_rate,
+            results: WinRate::default(),
+            log_likelihood_ratio: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, outcome: GameOutcome) -> SprtDecision {
+        let win = (self.alternative_rate / self.null_rate).ln();
+        let loss = ((1.0 - self.alternative_rate) / (1.0 - self.null_rate)).ln();
+        self.log_likelihood_ratio += match outcome {
+            GameOutcome::Win => win,
+            GameOutcome::Draw => 0.5 * (win + loss),
+            GameOutcome::Loss => loss,
+        };
+        self.results.record(outcome);
+
+        self.decision()
+    }
+
+    pub fn decision(&self) -> SprtDecision {
+        let lower_bound = (self.false_negative_rate / (1.0 - self.false_positive_rate)).ln();
+        let upper_bound = ((1.0 - self.false_negative_rate) / self.false_positive_rate).ln();
+
+        if self.log_likelihood_ratio >= upper_bound {
+            SprtDecision::AcceptAlternative
+        } else if self.log_likelihood_ratio <= lower_bound {
+            SprtDecision::AcceptNull
+        } else {
+            SprtDecision::Continue
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExperimentResult {
+    pub decision: SprtDecision, // `Continue` if the game limit was reached undecided
+    pub results: WinRate,
+}
+
+/// Play games one at a time until the test is decided or `max_games` have been played.
+/// The closure is given the index of the game to play, e.g. to derive its seed.
+pub fn run_sequential_experiment<F: FnMut(usize) -> GameOutcome>(
+    mut sprt: Sprt,
+    max_games: usize,
+    mut play_game: F,
+) -> ExperimentResult {
+    let mut decision = sprt.decision();
+    for game_index in 0..max_games {
+        if decision != SprtDecision::Continue {
+            break;
+        }
+        decision = sprt.record(play_game(game_index));
+    }
+
+    ExperimentResult {
+        decision,
+        results: sprt.results,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{run_sequential_experiment, GameOutcome, Sprt, SprtDecision, WinRate, Z_95};
+
+    #[test]
+    fn wilson_intervals_match_known_values() {
+        let win_rate = WinRate {
+            wins: 60,
+            draws: 0,
+            losses: 40,
+        };
+        let (low, high) = win_rate.confidence_interval(Z_95);
+        assert!((low - 0.5020).abs() < 1e-4, "{}", low);
+        assert!((high - 0.6906).abs() < 1e-4, "{}", high);
+
+        // Never below zero, even without a single win.
+        let shutout = WinRate {
+            wins: 0,
+            draws: 0,
+            losses: 10,
+        };
+        let (low, high) = shutout.confidence_interval(Z_95);
+        assert_eq!(0.0, low);
+        assert!(high > 0.0 && high < 0.35);
+
+        assert_eq!((0.0, 1.0), WinRate::default().confidence_interval(Z_95));
+    }
+
+    #[test]
+    fn draws_count_as_half_a_win() {
+        let mut win_rate = WinRate::default();
+        win_rate.record(GameOutcome::Win);
+        win_rate.record(GameOutcome::Draw);
+        win_rate.record(GameOutcome::Loss);
+        win_rate.record(GameOutcome::Loss);
+        assert_eq!(4, win_rate.games());
+        assert_eq!(0.375, win_rate.rate());
+    }
+
+    #[test]
+    fn lopsided_experiments_stop_early() {
+        let sprt = Sprt::new(0.5, 0.6, 0.05, 0.05);
+
+        let stronger = run_sequential_experiment(sprt.clone(), 1000, |_| GameOutcome::Win);
+        assert_eq!(SprtDecision::AcceptAlternative, stronger.decision);
+        assert!(stronger.results.games() < 20);
+
+        let weaker = run_sequential_experiment(sprt.clone(), 1000, |_| GameOutcome::Loss);
+        assert_eq!(SprtDecision::AcceptNull, weaker.decision);
+        assert!(weaker.results.games() < 20);
+
+        // An even split is not enough evidence to decide within a handful of games.
+        let undecided = run_sequential_experiment(sprt, 10, |index| {
+            if index % 2 == 0 {
+                GameOutcome::Win
+            } else {
+                GameOutcome::Loss
+            }
+        });
+        assert_eq!(SprtDecision::Continue, undecided.decision);
+        assert_eq!(10, undecided.results.games());
+    }
+}
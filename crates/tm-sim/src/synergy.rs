@@ -0,0 +1,300 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    card::{Card, CardAction, CardEffect, CardTag, ImmediateImpact},
+    card_ref,
+    game::PlayerState,
+    resource::CardResource,
+};
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CardLocation {
+    Hand,
+    Played,
+}
+
+#[derive(Clone, Copy, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SynergyKind {
+    // the enabler makes the beneficiary cheaper to play; no tag means any card is discounted
+    Discount(Option<CardTag>, usize),
+
+    // playing the beneficiary triggers the enabler's effect, since it has the given tag
+    TagTrigger(CardTag),
+
+    // the enabler adds card resources of the given kind, which the beneficiary can host
+    ResourceFeeder(CardResource),
+}
+
+/// A pair of cards that are worth more together than apart.
+///
+/// The enabler is the card providing the discount, effect, or card resources,
+/// and the beneficiary is the card that makes use of them.
+/// At least one of the two is still in hand: synergies among played cards are already in effect.
+#[derive(Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Synergy {
+    pub kind: SynergyKind,
+    #[serde(with = "card_ref::card")]
+    pub enabler: Card,
+    pub enabler_location: CardLocation,
+    #[serde(with = "card_ref::card")]
+    pub beneficiary: Card,
+    pub beneficiary_location: CardLocation,
+}
+
+impl fmt::Display for Synergy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self.kind {
+            SynergyKind::Discount(Some(tag), amount) => {
+                format!("{} M$ discount for its {:?} tag", amount, tag)
+            }
+            SynergyKind::Discount(None, amount) => format!("{} M$ discount", amount),
+            SynergyKind::TagTrigger(tag) => format!("its {:?} tag triggers an effect", tag),
+            SynergyKind::ResourceFeeder(resource) => format!("hosts {:?} resources", resource),
+        };
+        write!(
+            f,
+            "{} ({:?}) -> {} ({:?}): {}",
+            self.enabler.name,
+            self.enabler_location,
+            self.beneficiary.name,
+            self.beneficiary_location,
+            description
+        )
+    }
+}
+
+/// All synergies within the player's hand, and between the hand and the player's played cards.
+pub fn player_synergies(player: &PlayerState) -> Vec<Synergy> {
+    find_synergies(&player.cards_in_hand, &player.played_cards)
+}
+
+/// The synergies that a card would add to a player's hand, for example when deciding
+/// whether to draft or buy it. The card itself is one side of every returned synergy.
+pub fn synergies_for_card(card: &Card, hand: &[Card], played: &[Card]) -> Vec<Synergy> {
+    let mut extended_hand = hand.to_vec();
+    extended_hand.push(card.clone());
+
+    let mut synergies = find_synergies(&extended_hand, played);
+    synergies.retain(|synergy| {
+        (synergy.enabler == *card && synergy.enabler_location == CardLocation::Hand)
+            || (synergy.beneficiary == *card && synergy.beneficiary_location == CardLocation::Hand)
+    });
+    synergies
+}
+
+/// Synergies within the hand and between hand and played cards, sorted and without duplicates.
+pub fn find_synergies(hand: &[Card], played: &[Card]) -> Vec<Synergy> {
+    let located_cards: Vec<(&Card, CardLocation)> = hand
+        .iter()
+        .map(|card| (card, CardLocation::Hand))
+        .chain(played.iter().map(|card| (card, CardLocation::Played)))
+        .collect();
+
+    let mut synergies = vec![];
+    for &(enabler, enabler_location) in &located_cards {
+        for &(beneficiary, beneficiary_location) in &located_cards {
+            if enabler == beneficiary {
+                continue;
+            }
+            if enabler_location == CardLocation::Played
+                && beneficiary_location == CardLocation::Played
+            {
+                continue;
+            }
+
+            let mut push = |kind| {
+                synergies.push(Synergy {
+                    kind,
+                    enabler: enabler.clone(),
+                    enabler_location,
+                    beneficiary: beneficiary.clone(),
+                    beneficiary_location,
+                })
+            };
+
+            // Discounts and tag triggers only apply to cards that are yet to be played.
+            if beneficiary_location == CardLocation::Hand {
+                for effect in enabler
+                    .effects
+                    .iter()
+                    .chain(enabler.next_card_this_generation_effects.iter())
+                {
+                    match effect {
+                        CardEffect::AnyCardDiscount(amount) => {
+                            push(SynergyKind::Discount(None, *amount))
+                        }
+                        CardEffect::CardDiscountForTag(tag, amount)
+                            if beneficiary.tags.contains(tag) =>
+                        {
+                            push(SynergyKind::Discount(Some(*tag), *amount))
+                        }
+                        _ => {}
+                    }
+                }
+
+                for effect in &enabler.effects {
+                    match effect {
+                        CardEffect::OnOwnTagPlayed(tag, _) | CardEffect::OnAnyTagPlayed(tag, _)
+                            if beneficiary.tags.contains(tag) =>
+                        {
+                            push(SynergyKind::TagTrigger(*tag))
+                        }
+                        CardEffect::OnOwnTagCombinationPlayed(tags, _)
+                            if tags.iter().all(|tag| beneficiary.tags.contains(tag)) =>
+                        {
+                            // Every tag of the combination is needed; report the leading one.
+                            push(SynergyKind::TagTrigger(tags[0]))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if let Some(hosted_resource) = beneficiary.supports_card_resource() {
+                // A played card's immediate impacts have already happened.
+                let include_immediate = enabler_location == CardLocation::Hand;
+                if fed_card_resources(enabler, include_immediate).contains(&hosted_resource) {
+                    push(SynergyKind::ResourceFeeder(hosted_resource));
+                }
+            }
+        }
+    }
+
+    synergies.sort();
+    synergies.dedup();
+    synergies
+}
+
+// Card resources that the card can add to some other card.
+fn fed_card_resources(card: &Card, include_immediate: bool) -> Vec<CardResource> {
+    let mut impacts: Vec<&ImmediateImpact> = vec![];
+    if include_immediate {
+        impacts.extend(card.immediate_impacts.iter());
+    }
+    for action in &card.actions {
+        match action {
+            CardAction::CauseFreeImpact(impact)
+            | CardAction::SpendSameCardResource(_, _, impact)
+            | CardAction::TakeAnyCardResource(_, _, impact)
+            | CardAction::RandomizeBasedOnRevealedCardTag(_, _, _, impact) => impacts.push(impact),
+            CardAction::SpendResource(_, action_impacts)
+            | CardAction::SpendProduction(_, _, action_impacts) => {
+                impacts.extend(action_impacts.iter())
+            }
+        }
+    }
+    for effect in &card.effects {
+        match effect {
+            CardEffect::OnAnyPlacedOcean(impact)
+            | CardEffect::OnAnyPlacedCity(impact)
+            | CardEffect::OnAnyTagPlayed(_, impact)
+            | CardEffect::OnOwnPlacedGreenery(impact)
+            | CardEffect::OnOwnTagPlayed(_, impact) => impacts.push(impact),
+            CardEffect::OnOwnTagCombinationPlayed(_, effect_impacts) => {
+                impacts.extend(effect_impacts.iter())
+            }
+            _ => {}
+        }
+    }
+
+    let mut result = vec![];
+    while let Some(impact) = impacts.pop() {
+        match impact {
+            ImmediateImpact::AddResourceToAnotherCard(resource, _)
+            | ImmediateImpact::AddResourceToAnyCard(resource, _) => result.push(*resource),
+            ImmediateImpact::SpendResourceFromSameCard(_, _, inner) => impacts.push(inner),
+            ImmediateImpact::OneOf(options) => impacts.extend(options.iter()),
+            ImmediateImpact::Chained(first, second) => {
+                impacts.push(first);
+                impacts.push(second);
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        card::{Card, CardTag, BASE_GAME_CARDS_BY_NAME, CORPORATE_GAME_CARDS_BY_NAME},
+        resource::CardResource,
+        synergy::{find_synergies, synergies_for_card, CardLocation, SynergyKind},
+    };
+
+    fn cards(names: &[&str]) -> Vec<Card> {
+        names
+            .iter()
+            .map(|name| CORPORATE_GAME_CARDS_BY_NAME[name].clone())
+            .collect()
+    }
+
+    #[test]
+    fn tag_discounts_apply_to_cards_in_hand() {
+        let hand = cards(&["Asteroid Mining"]);
+        let played = cards(&["Space Station"]);
+
+        let synergies = find_synergies(&hand, &played);
+        assert_eq!(1, synergies.len());
+        assert_eq!(
+            SynergyKind::Discount(Some(CardTag::Space), 2),
+            synergies[0].kind
+        );
+        assert_eq!("Space Station", synergies[0].enabler.name);
+        assert_eq!(CardLocation::Played, synergies[0].enabler_location);
+        assert_eq!("Asteroid Mining", synergies[0].beneficiary.name);
+
+        // Once both are played, there is nothing left to plan for.
+        assert!(find_synergies(&[], &cards(&["Space Station", "Asteroid Mining"])).is_empty());
+    }
+
+    #[test]
+    fn tag_triggered_engines_are_detected() {
+        let hand = cards(&["Mars University", "Research"]);
+
+        let synergies = find_synergies(&hand, &[]);
+        assert!(synergies.iter().any(|synergy| {
+            synergy.kind == SynergyKind::TagTrigger(CardTag::Science)
+                && synergy.enabler.name == "Mars University"
+                && synergy.beneficiary.name == "Research"
+        }));
+    }
+
+    #[test]
+    fn resource_feeders_are_matched_with_hosts() {
+        let hand = cards(&["Imported Nitrogen"]);
+        let played = cards(&["Tardigrades", "Predators"]);
+
+        let kinds: Vec<_> = find_synergies(&hand, &played)
+            .into_iter()
+            .map(|synergy| (synergy.kind, synergy.beneficiary.name))
+            .collect();
+        assert!(kinds.contains(&(
+            SynergyKind::ResourceFeeder(CardResource::Microbe),
+            "Tardigrades".to_owned()
+        )));
+        assert!(kinds.contains(&(
+            SynergyKind::ResourceFeeder(CardResource::Animal),
+            "Predators".to_owned()
+        )));
+
+        // An event's impacts are spent once it is played, so it no longer feeds anything.
+        let played = cards(&["Imported Nitrogen", "Tardigrades"]);
+        assert!(find_synergies(&[], &played).is_empty());
+    }
+
+    #[test]
+    fn synergies_for_card_only_involve_that_card() {
+        let card = BASE_GAME_CARDS_BY_NAME["Optimal Aerobraking"];
+        let hand = cards(&["Asteroid Mining", "Research"]);
+        let played = cards(&["Space Station"]);
+
+        let synergies = synergies_for_card(card, &hand, &played);
+        assert!(!synergies.is_empty());
+        for synergy in &synergies {
+            assert!(synergy.enabler == *card || synergy.beneficiary == *card);
+        }
+    }
+}
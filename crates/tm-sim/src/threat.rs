@@ -0,0 +1,378 @@
+//! What opponents could plausibly do on their very next turn that would hurt a player,
+//! so that agents can play defensively, e.g. spend their plants before an asteroid hits.
+//!
+//! Only what the player may know is used: the opponents' public summaries, the board,
+//! the cards the player knows to be in an opponent's hand, and the odds of the rest.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::Award,
+    card::{Card, ImmediateImpact},
+    game::{GameState, PlayerId, GREENERY_COST, PLANTS_PER_GREENERY},
+    knowledge::{KnowledgeState, KnowledgeTracker, KnownLocation},
+    milestone::{Milestone, MILESTONE_CLAIM_COST, MILESTONE_POINTS},
+    registry::CardRegistry,
+    resource::{PaymentCost, Resource},
+    view::PublicSummary,
+};
+
+// Damage is in megacredits, at the same rough exchange rates as when drafting.
+const MEGACREDITS_PER_VICTORY_POINT: f64 = 5.0;
+
+/// Players holding at least this many plants, enough for a greenery, are warned about
+/// attacks on their plants.
+pub const PLANT_WARNING_THRESHOLD: usize = PLANTS_PER_GREENERY;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Threat {
+    pub opponent: PlayerId,
+    pub kind: ThreatKind,
+    pub likelihood: f64, // that the opponent is able to do it on their next turn
+    pub damage: f64,     // in megacredits, if they do
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThreatKind {
+    DestroyPlants(usize), // up to this many of the player's plants
+    ClaimMilestone(Milestone),
+    FundAward(Award), // one the opponent would score more of than the player
+}
+
+impl Threat {
+    pub fn expected_damage(&self) -> f64 {
+        self.likelihood * self.damage
+    }
+}
+
+impl fmt::Display for Threat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ThreatKind::DestroyPlants(plants) => {
+                write!(f, "{:?} may destroy {} plant(s)", self.opponent, plants)?
+            }
+            ThreatKind::ClaimMilestone(milestone) => {
+                write!(f, "{:?} may claim {:?}", self.opponent, milestone)?
+            }
+            ThreatKind::FundAward(award) => write!(f, "{:?} may fund {:?}", self.opponent, award)?,
+        }
+        write!(f, ", {:.0}% likely", self.likelihood * 100.0)
+    }
+}
+
+/// The threats every opponent of the knowledge's player poses on their next turn,
+/// most dangerous first, by expected damage.
+///
+/// The deck is every card that may be in the game, used to estimate the odds that
+/// an opponent holds a card they have not been seen to hold.
+pub fn assess_threats(game: &GameState, knowledge: &KnowledgeState, deck: &[Card]) -> Vec<Threat> {
+    let player_id = knowledge.player_id;
+    let mut threats = vec![];
+    for opponent in game.players.values() {
+        if opponent.player_id == player_id {
+            continue;
+        }
+        let summary = opponent.public_summary();
+        threats.extend(plant_destruction_threat(game, knowledge, deck, &summary));
+        threats.extend(milestone_threats(game, &summary));
+        threats.extend(award_threats(game, player_id, &summary));
+    }
+    threats.sort_by(|a, b| {
+        b.expected_damage()
+            .partial_cmp(&a.expected_damage())
+            .unwrap()
+    });
+    threats
+}
+
+/// The most dangerous attack any opponent could plausibly make on the player's plants
+/// on their next turn, if the player holds at least `min_plants` plants.
+pub fn plant_destruction_warning(
+    game: &GameState,
+    knowledge: &KnowledgeState,
+    deck: &[Card],
+    min_plants: usize,
+) -> Option<Threat> {
+    if game.players[&knowledge.player_id].resources[Resource::Plants] < min_plants {
+        return None;
+    }
+    game.players
+        .values()
+        .filter(|opponent| opponent.player_id != knowledge.player_id)
+        .filter_map(|opponent| {
+            plant_destruction_threat(game, knowledge, deck, &opponent.public_summary())
+        })
+        .max_by(|a, b| {
+            a.expected_damage()
+                .partial_cmp(&b.expected_damage())
+                .unwrap()
+        })
+}
+
+/// The victory points the player expects to lose to attacks on their plants before they can
+/// spend them, as far as they know; zero unless they are warned about such attacks.
+pub fn plant_risk(game: &GameState, player_id: PlayerId) -> f64 {
+    // Most of the time, there is no need to work out what the player knows.
+    if game.players[&player_id].resources[Resource::Plants] < PLANT_WARNING_THRESHOLD {
+        return 0.0;
+    }
+    let knowledge = &KnowledgeTracker::new(game).players[&player_id];
+    let deck = CardRegistry::global().cards();
+    plant_destruction_warning(game, knowledge, deck, PLANT_WARNING_THRESHOLD)
+        .map_or(0.0, |threat| {
+            threat.expected_damage() / MEGACREDITS_PER_VICTORY_POINT
+        })
+}
+
+// The most plants any card the opponent can afford would destroy, weighted by the odds
+// that they hold such a card. Plants are only at risk until the player spends them.
+fn plant_destruction_threat(
+    game: &GameState,
+    knowledge: &KnowledgeState,
+    deck: &[Card],
+    opponent: &PublicSummary,
+) -> Option<Threat> {
+    let plants = game.players[&knowledge.player_id].resources[Resource::Plants];
+    let destroys = |card: &Card| {
+        if can_afford(opponent, card.cost) {
+            plants.min(plants_destroyed(card))
+        } else {
+            0
+        }
+    };
+    let worst = deck.iter().map(destroys).max().unwrap_or_default();
+    if worst == 0 {
+        return None;
+    }
+
+    let known_hand: Vec<&Card> = deck
+        .iter()
+        .filter(|card| {
+            knowledge.known_locations.get(&card.name)
+                == Some(&KnownLocation::OpponentHand(opponent.player_id))
+        })
+        .collect();
+    let known_worst = known_hand.iter().map(|card| destroys(card)).max();
+    if known_worst == Some(worst) {
+        return Some(plant_threat(opponent.player_id, worst, 1.0));
+    }
+
+    let unknown_hand_size = opponent.hand_size.saturating_sub(known_hand.len());
+    let odds = knowledge.draw_probability(deck, |card| destroys(card) == worst);
+    let likelihood = 1.0 - (1.0 - odds).powi(unknown_hand_size as i32);
+    Some(plant_threat(opponent.player_id, worst, likelihood))
+}
+
+fn plant_threat(opponent: PlayerId, plants: usize, likelihood: f64) -> Threat {
+    Threat {
+        opponent,
+        kind: ThreatKind::DestroyPlants(plants),
+        likelihood,
+        damage: plants as f64 * GREENERY_COST as f64 / PLANTS_PER_GREENERY as f64,
+    }
+}
+
+fn plants_destroyed(card: &Card) -> usize {
+    card.immediate_impacts
+        .iter()
+        .map(|impact| match impact {
+            ImmediateImpact::DestroyAnyResource(Resource::Plants, amount)
+            | ImmediateImpact::PlaceFloodingOcean(Resource::Plants, amount, _) => *amount,
+            ImmediateImpact::OneOf(impacts) => impacts
+                .iter()
+                .map(|impact| match impact {
+                    ImmediateImpact::DestroyAnyResource(Resource::Plants, amount) => *amount,
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or_default(),
+            _ => 0,
+        })
+        .sum()
+}
+
+// Whether the opponent's megacredits and any metals that may pay for the card cover its cost.
+// Metals are valued at their usual rate, since effects that raise it are not public knowledge
+// of the summary.
+fn can_afford(opponent: &PublicSummary, cost: PaymentCost) -> bool {
+    let resources = &opponent.resources;
+    let steel = resources[Resource::Steel] * 2;
+    let titanium = resources[Resource::Titanium] * 3;
+    let metals = match cost {
+        PaymentCost::Building(_) => steel,
+        PaymentCost::Space(_) => titanium,
+        PaymentCost::SpaceOrBuilding(_) => steel + titanium,
+        PaymentCost::Megacredits(_) => 0,
+        _ => return false,
+    };
+    resources[Resource::Megacredits] + metals >= cost.amount()
+}
+
+fn milestone_threats<'a>(
+    game: &'a GameState,
+    opponent: &'a PublicSummary,
+) -> impl Iterator<Item = Threat> + 'a {
+    let affordable = opponent.resources[Resource::Megacredits] >= MILESTONE_CLAIM_COST;
+    Milestone::ALL
+        .iter()
+        .filter(move |milestone| affordable && milestone.is_met(game, opponent.player_id))
+        .map(move |milestone| Threat {
+            opponent: opponent.player_id,
+            kind: ThreatKind::ClaimMilestone(*milestone),
+            likelihood: 1.0,
+            damage: MILESTONE_POINTS as f64 * MEGACREDITS_PER_VICTORY_POINT,
+        })
+}
+
+fn award_threats<'a>(
+    game: &'a GameState,
+    player_id: PlayerId,
+    opponent: &'a PublicSummary,
+) -> impl Iterator<Item = Threat> + 'a {
+    let affordable = game
+        .award_funding_cost()
+        .is_some_and(|cost| opponent.resources[Resource::Megacredits] >= cost);
+    Award::ALL
+        .iter()
+        .filter(move |award| {
+            affordable
+                && !game
+                    .funded_awards
+                    .iter()
+                    .any(|(funded, _)| funded == *award)
+        })
+        .filter_map(move |award| {
+            let points = game.award_standings.award_points(*award);
+            let swing = points.get(&opponent.player_id).copied().unwrap_or_default()
+                - points.get(&player_id).copied().unwrap_or_default();
+            (swing > 0).then_some(Threat {
+                opponent: opponent.player_id,
+                kind: ThreatKind::FundAward(*award),
+                likelihood: 1.0,
+                damage: swing as f64 * MEGACREDITS_PER_VICTORY_POINT,
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        award::Award,
+        card::{Card, CORPORATE_GAME_CARDS_BY_NAME},
+        event::GameEvent,
+        game::PlayerId,
+        knowledge::KnowledgeTracker,
+        milestone::Milestone,
+        position::import_position,
+        threat::{assess_threats, plant_destruction_warning, plant_risk, ThreatKind},
+    };
+
+    fn cards(names: &[&str]) -> Vec<Card> {
+        names
+            .iter()
+            .map(|name| CORPORATE_GAME_CARDS_BY_NAME[name].clone())
+            .collect()
+    }
+
+    #[test]
+    fn plants_are_at_risk_from_affordable_asteroids() {
+        let deck = cards(&["Asteroid", "Deimos Down", "Research", "Solar Power"]);
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let game = import_position(
+            "player p1\n\
+             resources 0 0 0 0 0 0\n\
+             player p2\n\
+             resources 0 0 0 5 0 0\n\
+             hand Solar Power; Research",
+        )
+        .unwrap();
+        // With no plants, there is nothing to destroy.
+        let knowledge = &KnowledgeTracker::new(&game).players[&p1];
+        assert!(assess_threats(&game, knowledge, &deck).is_empty());
+
+        let game = import_position(
+            "player p1\n\
+             resources 0 0 0 5 0 0\n\
+             player p2\n\
+             resources 0 0 5 0 0 0\n\
+             hand Solar Power; Research",
+        )
+        .unwrap();
+        let mut knowledge = KnowledgeTracker::new(&game).players[&p1].clone();
+        // p2 can pay for Asteroid with titanium, but not for Deimos Down.
+        let threats = assess_threats(&game, &knowledge, &deck);
+        assert_eq!(1, threats.len());
+        assert_eq!(p2, threats[0].opponent);
+        assert_eq!(ThreatKind::DestroyPlants(3), threats[0].kind);
+        let unknown_odds = threats[0].likelihood;
+        assert!(unknown_odds > 0.0 && unknown_odds < 1.0);
+
+        // Once p1 passed the asteroid to p2, it is certain.
+        knowledge.observe(&GameEvent::CardsPassed(p1, p2, cards(&["Asteroid"])));
+        let threats = assess_threats(&game, &knowledge, &deck);
+        assert_eq!(1.0, threats[0].likelihood);
+    }
+
+    #[test]
+    fn players_are_only_warned_about_plants_worth_protecting() {
+        let deck = cards(&["Asteroid", "Research"]);
+        let p1 = PlayerId::from(1);
+        let position = |plants| {
+            import_position(&format!(
+                "player p1\n\
+                 resources 0 0 0 {} 0 0\n\
+                 player p2\n\
+                 resources 20 0 0 0 0 0\n\
+                 hand Research",
+                plants
+            ))
+            .unwrap()
+        };
+
+        let game = position(7);
+        let knowledge = &KnowledgeTracker::new(&game).players[&p1];
+        assert_eq!(None, plant_destruction_warning(&game, knowledge, &deck, 8));
+        assert_eq!(0.0, plant_risk(&game, p1));
+
+        let game = position(8);
+        let knowledge = &KnowledgeTracker::new(&game).players[&p1];
+        let warning = plant_destruction_warning(&game, knowledge, &deck, 8).unwrap();
+        assert_eq!(ThreatKind::DestroyPlants(3), warning.kind);
+        assert!(plant_risk(&game, p1) > 0.0);
+    }
+
+    #[test]
+    fn milestones_and_awards_the_opponent_can_afford_are_threats() {
+        let (p1, p2) = (PlayerId::from(1), PlayerId::from(2));
+        let game = import_position(
+            "award Banker p1\n\
+             greenery 4,-3 p2\n\
+             greenery 5,-3 p2\n\
+             greenery 0,-2 p2\n\
+             player p1\n\
+             resources 0 0 0 0 0 6\n\
+             player p2\n\
+             resources 14 0 0 0 0 2",
+        )
+        .unwrap();
+        let knowledge = &KnowledgeTracker::new(&game).players[&p1];
+        let kinds: Vec<_> = assess_threats(&game, knowledge, &[])
+            .into_iter()
+            .map(|threat| (threat.opponent, threat.kind))
+            .collect();
+        // p2 leads the Landlord award by three tiles, but trails p1 in the Thermalist award.
+        assert_eq!(
+            vec![
+                (p2, ThreatKind::ClaimMilestone(Milestone::Gardener)),
+                (p2, ThreatKind::FundAward(Award::Landlord)),
+            ],
+            kinds
+        );
+
+        // Nothing is affordable to a player without megacredits.
+        let knowledge = &KnowledgeTracker::new(&game).players[&p2];
+        assert!(assess_threats(&game, knowledge, &[]).is_empty());
+    }
+}
@@ -0,0 +1,522 @@
+//! Tuning the evaluator's weights by self-play, with the cross-entropy method: every iteration
+//! samples a population of weight sets around the current mean, plays each of them against
+//! the baseline weights, and moves the mean to the weight sets that won by the most.
+//!
+//! The games are played by `WeightedBots`, which take whichever standard project or conversion
+//! their weights value most, until nothing is worth more than passing. They buy no cards, since
+//! cards cannot be played in games yet. Every game is seeded from the tuning seed and its place
+//! in the batch, so the same options always tune the same weights, however many threads are used.
+//!
+//! Tuned weights are persisted in a JSON file of weight sets, each with the provenance of its
+//! tuning run. The games confirming that the tuned weights beat the baseline are rated on the
+//! tournament ladder, with both weight sets as versions of the `weighted-bot` agent.
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    board::{Coordinates, TileLocation, TileStatus},
+    card::Card,
+    corporation::Corporation,
+    eval::{weighted_evaluate, EvalWeights},
+    game::{GamePhase, GameState, PlayerId, PlayerTurn, StandardProject, TurnAction},
+    generation::GenerationPlayer,
+    ladder::{AgentId, Ladder},
+    position::import_position,
+    priors::CorporationPriors,
+    projection::ProjectionAssumptions,
+    rng::GameRng,
+    scoring::score_all_players,
+};
+
+pub const WEIGHTED_BOT: &str = "weighted-bot";
+pub const BASELINE_VERSION: &str = "default";
+
+// The spread of the first population around the baseline, and the least spread kept later on,
+// so that the search never stops exploring altogether.
+const INITIAL_SPREAD: f64 = 0.5;
+const MIN_SPREAD: f64 = 0.05;
+
+/// Plays every seat by the seat's own weights. Seats without weights of their own play by
+/// the priors for their corporation, from the moment they are assigned one.
+pub struct WeightedBots {
+    pub weights: BTreeMap<PlayerId, EvalWeights>,
+    pub priors: CorporationPriors,
+    pub assumptions: ProjectionAssumptions,
+}
+
+impl WeightedBots {
+    fn value(&self, game: &GameState, player_id: PlayerId) -> f64 {
+        let weights = match self.weights.get(&player_id) {
+            Some(weights) => weights.clone(),
+            None => self.priors.weights_for_player(game, player_id),
+        };
+        weighted_evaluate(game, player_id, &self.assumptions, &weights)
+    }
+}
+
+impl GenerationPlayer for WeightedBots {
+    fn choose_corporation(
+        &mut self,
+        _: &GameState,
+        _: PlayerId,
+        offered: &[Corporation],
+    ) -> (Corporation, Vec<Card>) {
+        (offered[0].clone(), vec![])
+    }
+
+    fn draft_card(&mut self, _: &GameState, _: PlayerId, pack: &[Card]) -> Card {
+        pack[0].clone()
+    }
+
+    fn buy_cards(&mut self, _: &GameState, _: PlayerId, _: &[Card]) -> Vec<Card> {
+        vec![]
+    }
+
+    fn take_turn(&mut self, game: &GameState, player_id: PlayerId) -> PlayerTurn {
+        let mut best_value = self.value(game, player_id);
+        let mut best_turn = PlayerTurn::Pass;
+        for (action, after) in legal_actions(game, player_id) {
+            let value = self.value(&after, player_id);
+            if value > best_value {
+                best_value = value;
+                best_turn = PlayerTurn::Play(action, None);
+            }
+        }
+        best_turn
+    }
+}
+
+// The standard projects and conversions the player can afford, each with the game after it.
+// Tiles go on the first space they may go on, in reading order.
+fn legal_actions(game: &GameState, player_id: PlayerId) -> Vec<(TurnAction, GameState)> {
+    let try_action = |action: TurnAction| {
+        // Most actions are ruled out without copying the game.
+        game.turn_action_operations(player_id, &action).ok()?;
+        let mut after = game.clone();
+        after
+            .play_turn_action(player_id, &action)
+            .ok()
+            .map(|_| (action, after))
+    };
+    let first_space = |spaces: &[Coordinates], action: fn(Coordinates) -> TurnAction| {
+        spaces
+            .iter()
+            .find_map(|coordinates| try_action(action(*coordinates)))
+    };
+
+    let mut ocean_spaces = vec![];
+    let mut land_spaces = vec![];
+    for space in game.board.spaces.values() {
+        let coordinates = match space.location {
+            TileLocation::OnMars(coordinates) => coordinates,
+            TileLocation::OffMars(_) => continue,
+        };
+        if !matches!(
+            game.board.get_tile_status(&space.location),
+            TileStatus::Empty(_)
+        ) {
+            continue;
+        }
+        if space.is_reserved_for_ocean() {
+            ocean_spaces.push(coordinates);
+        } else if space.is_land() {
+            land_spaces.push(coordinates);
+        }
+    }
+    ocean_spaces.sort_by_key(|coordinates| (coordinates.get_z(), *coordinates));
+    land_spaces.sort_by_key(|coordinates| (coordinates.get_z(), *coordinates));
+
+    let project = TurnAction::PlayStandardProject;
+    vec![
+        try_action(project(StandardProject::PowerPlant)),
+        try_action(project(StandardProject::Asteroid(None))),
+        try_action(TurnAction::ConvertHeat(None)),
+        first_space(&ocean_spaces, |space| {
+            TurnAction::PlayStandardProject(StandardProject::Aquifer(space))
+        }),
+        first_space(&land_spaces, |space| {
+            TurnAction::PlayStandardProject(StandardProject::Greenery(space))
+        }),
+        first_space(&land_spaces, |space| {
+            TurnAction::PlayStandardProject(StandardProject::City(space))
+        }),
+        first_space(&land_spaces, TurnAction::ConvertPlants),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Play a game between bots with the given weights, one seat each, from the first research
+/// phase until the game is over or the generation limit is reached.
+/// Returns the final score of every seat, in order.
+pub fn play_match(weights: &[EvalWeights], seed: u64, max_generations: usize) -> Vec<isize> {
+    let position: String = std::iter::once(format!("seed {}", seed))
+        .chain((1..=weights.len()).map(|seat| format!("player p{}", seat)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut game = import_position(&position).unwrap();
+    game.phase = GamePhase::Research;
+
+    let mut bots = WeightedBots {
+        weights: game
+            .players
+            .keys()
+            .copied()
+            .zip(weights.iter().cloned())
+            .collect(),
+        priors: CorporationPriors::default(),
+        assumptions: ProjectionAssumptions::default(),
+    };
+    while !game.is_over() && game.generation <= max_generations {
+        if game.phase == GamePhase::FinalGreeneries {
+            game.advance_phase();
+        } else if game.play_generation(&mut bots).is_err() {
+            break;
+        }
+    }
+
+    let scores: BTreeMap<PlayerId, isize> = score_all_players(&game)
+        .into_iter()
+        .map(|(player_id, breakdown)| (player_id, breakdown.total()))
+        .collect();
+    scores.into_values().collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TuningOptions {
+    pub iterations: usize,
+    pub population: usize, // weight sets sampled in every iteration
+    pub elite: usize,      // of which the best ones set the next iteration's mean
+    pub games_per_candidate: usize,
+    pub max_generations: usize, // games still going after this many generations are scored as is
+    pub threads: usize,         // does not affect the results
+    pub seed: u64,
+    pub version: String, // under which the tuned weights are saved and rated
+}
+
+impl Default for TuningOptions {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            population: 16,
+            elite: 4,
+            games_per_candidate: 4,
+            max_generations: 20,
+            threads: 4,
+            seed: 0,
+            version: "tuned".to_string(),
+        }
+    }
+}
+
+/// Where a weight set came from, so that it can be reproduced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TuningProvenance {
+    pub method: String,
+    pub options: TuningOptions,
+    pub baseline: EvalWeights,
+    pub games_played: usize,
+    pub engine_version: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TunedWeights {
+    pub version: String,
+    pub weights: EvalWeights,
+    // mean final score margin over the baseline, in games played after the tuning
+    pub margin: f64,
+    pub provenance: TuningProvenance,
+}
+
+/// Tune weights, starting from the baseline, then confirm them against it in as many games
+/// as each candidate played, and rate those games on the ladder.
+pub fn tune_weights(
+    baseline: &EvalWeights,
+    options: &TuningOptions,
+    ladder: &mut Ladder,
+) -> TunedWeights {
+    assert!(options.population >= options.elite && options.elite >= 1);
+    assert!(options.games_per_candidate >= 1);
+    assert!(options.threads >= 1);
+
+    let mut rng = GameRng::from_seed(options.seed);
+    let mut mean = baseline.to_vec();
+    let mut spread = vec![INITIAL_SPREAD; EvalWeights::COUNT];
+    let mut best: Option<(EvalWeights, f64)> = None;
+    let mut games_played = 0;
+
+    for _ in 0..options.iterations {
+        let candidates: Vec<EvalWeights> = (0..options.population)
+            .map(|_| {
+                let values: Vec<f64> = mean
+                    .iter()
+                    .zip(&spread)
+                    .map(|(mean, spread)| mean + spread * standard_normal(&mut rng))
+                    .collect();
+                EvalWeights::from_slice(&values)
+            })
+            .collect();
+        let first_seed = rng.gen::<u64>();
+        let margins = match_margins(&candidates, baseline, first_seed, options);
+        games_played += candidates.len() * options.games_per_candidate;
+
+        let mut ranked: Vec<(EvalWeights, f64)> = candidates.into_iter().zip(margins).collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        if best
+            .as_ref()
+            .is_none_or(|(_, margin)| ranked[0].1 > *margin)
+        {
+            best = Some(ranked[0].clone());
+        }
+
+        let elite: Vec<Vec<f64>> = ranked[..options.elite]
+            .iter()
+            .map(|(weights, _)| weights.to_vec())
+            .collect();
+        for index in 0..EvalWeights::COUNT {
+            let values: Vec<f64> = elite.iter().map(|weights| weights[index]).collect();
+            let elite_mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance = values
+                .iter()
+                .map(|value| (value - elite_mean).powi(2))
+                .sum::<f64>()
+                / values.len() as f64;
+            mean[index] = elite_mean;
+            spread[index] = variance.sqrt().max(MIN_SPREAD);
+        }
+    }
+
+    // The best margin seen while tuning is biased upwards by the selection; fresh games are not.
+    let weights = best.map_or_else(|| baseline.clone(), |(weights, _)| weights);
+    let confirmation_seed = rng.gen::<u64>();
+    let tuned_agent = AgentId::new(WEIGHTED_BOT, &options.version);
+    let baseline_agent = AgentId::new(WEIGHTED_BOT, BASELINE_VERSION);
+    let mut margin_sum = 0;
+    for (game, (candidate_score, baseline_score)) in
+        candidate_games(&weights, baseline, confirmation_seed, options)
+            .into_iter()
+            .enumerate()
+    {
+        margin_sum += candidate_score - baseline_score;
+        if tuned_agent != baseline_agent {
+            ladder.record_game(&seated(
+                game,
+                (tuned_agent.clone(), candidate_score),
+                (baseline_agent.clone(), baseline_score),
+            ));
+        }
+    }
+    games_played += options.games_per_candidate;
+
+    TunedWeights {
+        version: options.version.clone(),
+        weights,
+        margin: margin_sum as f64 / options.games_per_candidate as f64,
+        provenance: TuningProvenance {
+            method: "cross-entropy".to_string(),
+            options: options.clone(),
+            baseline: baseline.clone(),
+            games_played,
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    }
+}
+
+// The candidate takes the first seat in even games, and the second in odd ones.
+fn seated<T>(game: usize, candidate: T, baseline: T) -> Vec<T> {
+    if game.is_multiple_of(2) {
+        vec![candidate, baseline]
+    } else {
+        vec![baseline, candidate]
+    }
+}
+
+// (candidate score, baseline score) of every game between the two.
+fn candidate_games(
+    candidate: &EvalWeights,
+    baseline: &EvalWeights,
+    first_seed: u64,
+    options: &TuningOptions,
+) -> Vec<(isize, isize)> {
+    (0..options.games_per_candidate)
+        .map(|game| {
+            let weights = seated(game, candidate.clone(), baseline.clone());
+            let scores = play_match(
+                &weights,
+                first_seed.wrapping_add(game as u64),
+                options.max_generations,
+            );
+            if game.is_multiple_of(2) {
+                (scores[0], scores[1])
+            } else {
+                (scores[1], scores[0])
+            }
+        })
+        .collect()
+}
+
+// The mean final score margin of every candidate over the baseline, with the games
+// of all candidates spread over the threads.
+fn match_margins(
+    candidates: &[EvalWeights],
+    baseline: &EvalWeights,
+    first_seed: u64,
+    options: &TuningOptions,
+) -> Vec<f64> {
+    let mut margins = vec![0.0; candidates.len()];
+    let chunk_size = candidates.len().div_ceil(options.threads).max(1);
+    std::thread::scope(|scope| {
+        for (chunk_index, (chunk, chunk_margins)) in candidates
+            .chunks(chunk_size)
+            .zip(margins.chunks_mut(chunk_size))
+            .enumerate()
+        {
+            scope.spawn(move || {
+                for (offset, (candidate, margin)) in chunk.iter().zip(chunk_margins).enumerate() {
+                    let index = chunk_index * chunk_size + offset;
+                    let seed =
+                        first_seed.wrapping_add((index * options.games_per_candidate) as u64);
+                    let games = candidate_games(candidate, baseline, seed, options);
+                    let total: isize = games
+                        .iter()
+                        .map(|(candidate_score, baseline_score)| candidate_score - baseline_score)
+                        .sum();
+                    *margin = total as f64 / games.len() as f64;
+                }
+            });
+        }
+    });
+    margins
+}
+
+// Box-Muller, from two uniform samples.
+fn standard_normal(rng: &mut GameRng) -> f64 {
+    let uniform: f64 = rng.gen_range(f64::EPSILON, 1.0);
+    let angle: f64 = rng.gen_range(0.0, std::f64::consts::TAU);
+    (-2.0 * uniform.ln()).sqrt() * angle.cos()
+}
+
+/// The weight sets saved in the file, best margin first; none if there is no file yet.
+pub fn load_weight_sets(path: &Path) -> io::Result<Vec<TunedWeights>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(error),
+    };
+    serde_json::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Add the weight set to the file, replacing any saved under the same version.
+pub fn save_weight_set(path: &Path, tuned: &TunedWeights) -> io::Result<()> {
+    let mut sets = load_weight_sets(path)?;
+    sets.retain(|set| set.version != tuned.version);
+    sets.push(tuned.clone());
+    sets.sort_by(|a, b| b.margin.partial_cmp(&a.margin).unwrap());
+    fs::write(path, serde_json::to_string_pretty(&sets).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use super::*;
+    use crate::corporation::CORPORATIONS_BY_NAME;
+
+    fn small_options() -> TuningOptions {
+        TuningOptions {
+            iterations: 2,
+            population: 3,
+            elite: 2,
+            games_per_candidate: 2,
+            max_generations: 2,
+            threads: 2,
+            seed: 5,
+            version: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn bots_take_the_turn_their_weights_value_most() {
+        let game = import_position("player p1\nresources 20 0 0 0 0 0\nplayer p2").unwrap();
+        let p1 = PlayerId::from(1);
+        let mut bots = WeightedBots {
+            weights: btreemap! {
+                p1 => EvalWeights::default(),
+                PlayerId::from(2) => EvalWeights::default(),
+            },
+            priors: CorporationPriors::default(),
+            assumptions: ProjectionAssumptions::default(),
+        };
+        // Energy production turns into heat, and heat into temperature raises, for years to come.
+        let power_plant = TurnAction::PlayStandardProject(StandardProject::PowerPlant);
+        assert_eq!(
+            PlayerTurn::Play(power_plant.clone(), None),
+            bots.take_turn(&game, p1)
+        );
+
+        // Weights that value megacredits more than anything they buy keep them.
+        bots.weights.insert(
+            p1,
+            EvalWeights {
+                megacredits: 1.0,
+                ..EvalWeights::default()
+            },
+        );
+        assert_eq!(PlayerTurn::Pass, bots.take_turn(&game, p1));
+
+        // Without weights of its own, the seat plays by its corporation's priors.
+        bots.weights.remove(&p1);
+        assert_eq!(
+            PlayerTurn::Play(power_plant, None),
+            bots.take_turn(&game, p1)
+        );
+        bots.priors = CorporationPriors::from_json(r#"{"Teractor": {"megacredits": 1.0}}"#)
+            .unwrap();
+        let mut with_teractor = game.clone();
+        with_teractor.players.get_mut(&p1).unwrap().corporation =
+            Some(CORPORATIONS_BY_NAME["Teractor"].clone());
+        assert_eq!(PlayerTurn::Pass, bots.take_turn(&with_teractor, p1));
+
+        let weights = vec![EvalWeights::default(), EvalWeights::default()];
+        let scores = play_match(&weights, 1, 2);
+        assert_eq!(2, scores.len());
+        assert_eq!(scores, play_match(&weights, 1, 2));
+    }
+
+    #[test]
+    fn tuning_is_reproducible_and_rates_the_confirmation_games() {
+        let baseline = EvalWeights::default();
+        let mut ladder = Ladder::new();
+        let tuned = tune_weights(&baseline, &small_options(), &mut ladder);
+
+        let single_threaded = TuningOptions {
+            threads: 1,
+            ..small_options()
+        };
+        let again = tune_weights(&baseline, &single_threaded, &mut Ladder::new());
+        assert_eq!(
+            (&tuned.weights, tuned.margin),
+            (&again.weights, again.margin)
+        );
+        assert_eq!(2 * 3 * 2 + 2, tuned.provenance.games_played);
+        assert_eq!(2, ladder.rating(&AgentId::new(WEIGHTED_BOT, "test")).games);
+
+        let path = std::env::temp_dir().join(format!(
+            "terraforming_mars_weights_{}.json",
+            std::process::id()
+        ));
+        save_weight_set(&path, &tuned).unwrap();
+        save_weight_set(&path, &tuned).unwrap();
+        let saved = load_weight_sets(&path).unwrap();
+        assert_eq!(1, saved.len());
+        assert_eq!(
+            (&tuned.version, tuned.margin, &tuned.provenance),
+            (&saved[0].version, saved[0].margin, &saved[0].provenance)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
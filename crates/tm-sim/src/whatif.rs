@@ -0,0 +1,316 @@
+//! Counterfactual analysis of finished games: replay a recorded game with one step changed,
+//! and see how the final scores would have differed.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    award::AwardStandings,
+    decision::{Decision, DecisionKind},
+    event::EventBus,
+    game::{GameOperation, GameState, IllegalOperation, PlayerId},
+    milestone::MilestoneNotices,
+    scoring::score_all_players,
+    stamp::BuildStamp,
+};
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameStep {
+    Operation(GameOperation),
+    // the production phase at the end of a generation
+    ProductionPhase,
+}
+
+/// Everything needed to replay a game: its starting state, and every step taken from it.
+///
+/// All randomness comes from the starting state's `GameRng`,
+/// so replaying the same steps always reproduces the same game.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameRecord {
+    // the build that recorded the game; only one with the same rules and cards replays it
+    pub stamp: BuildStamp,
+    pub initial_state: GameState,
+    pub steps: Vec<GameStep>,
+
+    // the decisions players made, for analyzing them after the game
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub decisions: Vec<RecordedDecision>,
+}
+
+/// A decision a player made, and where in the game it was made.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedDecision {
+    // index of the first step taken as a result of the decision
+    pub step: usize,
+    pub player_id: PlayerId,
+    pub kind: DecisionKind,
+    pub decision: Decision,
+}
+
+/// One changed decision: the step at the given index is replaced by the given steps.
+/// An empty replacement removes the step; keeping the original step in the replacement
+/// inserts new steps around it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Alteration {
+    pub step: usize,
+    pub replacement: Vec<GameStep>,
+}
+
+impl Alteration {
+    pub fn remove(step: usize) -> Self {
+        Self {
+            step,
+            replacement: vec![],
+        }
+    }
+
+    pub fn replace(step: usize, operation: GameOperation) -> Self {
+        Self {
+            step,
+            replacement: vec![GameStep::Operation(operation)],
+        }
+    }
+
+    pub fn insert_before(record: &GameRecord, step: usize, operation: GameOperation) -> Self {
+        Self {
+            step,
+            replacement: vec![GameStep::Operation(operation), record.steps[step].clone()],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WhatIfError {
+    NoSuchStep(usize),
+    // the unaltered record does not replay cleanly, so it cannot be compared against
+    InvalidRecord(usize, IllegalOperation),
+}
+
+/// How the final scores of a game would have differed with one decision altered.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhatIfReport {
+    pub original_scores: BTreeMap<PlayerId, isize>,
+    pub altered_scores: BTreeMap<PlayerId, isize>,
+
+    // altered minus original, per player
+    pub score_deltas: BTreeMap<PlayerId, isize>,
+
+    // Steps of the altered game that became illegal, e.g. playing a card that was
+    // no longer bought. They are skipped, and the replay continues without them.
+    // Indexes refer to the altered list of steps.
+    pub skipped_steps: Vec<(usize, IllegalOperation)>,
+}
+
+impl GameRecord {
+    pub fn new(initial_state: GameState) -> Self {
+        Self {
+            stamp: BuildStamp::current(),
+            initial_state,
+            steps: vec![],
+            decisions: vec![],
+        }
+    }
+
+    /// Note the decision a player is about to make; the steps recorded next carry it out.
+    pub fn record_decision(&mut self, player_id: PlayerId, kind: DecisionKind, decision: Decision) {
+        self.decisions.push(RecordedDecision {
+            step: self.steps.len(),
+            player_id,
+            kind,
+            decision,
+        });
+    }
+
+    /// Execute the operation on the game, and record it as the next step.
+    pub fn execute_operation(&mut self, game: &mut GameState, operation: GameOperation) {
+        self.steps.push(GameStep::Operation(operation.clone()));
+        game.execute_operation(operation);
+    }
+
+    pub fn advance_generation(&mut self, game: &mut GameState) {
+        self.steps.push(GameStep::ProductionPhase);
+        game.advance_generation();
+    }
+
+    /// The state at the end of the recorded game. Fails on the first illegal step.
+    pub fn replay(&self) -> Result<GameState, WhatIfError> {
+        self.state_at(self.steps.len())
+    }
+
+    /// Like `replay()`, publishing the events of every step to the bus's observers.
+    pub fn replay_observed(&self, bus: &mut EventBus) -> Result<GameState, WhatIfError> {
+        let (game, skipped_steps) = replay_steps(&self.initial_state, &self.steps, bus);
+        match skipped_steps.into_iter().next() {
+            Some((index, error)) => Err(WhatIfError::InvalidRecord(index, error)),
+            None => Ok(game),
+        }
+    }
+
+    /// The state of the game just before the step at the given index was taken.
+    pub fn state_at(&self, step: usize) -> Result<GameState, WhatIfError> {
+        if step > self.steps.len() {
+            return Err(WhatIfError::NoSuchStep(step));
+        }
+        let (game, skipped_steps) =
+            replay_steps(&self.initial_state, &self.steps[..step], &mut EventBus::new());
+        match skipped_steps.into_iter().next() {
+            Some((index, error)) => Err(WhatIfError::InvalidRecord(index, error)),
+            None => Ok(game),
+        }
+    }
+
+    /// Replay the game with the alteration made, and compare the final scores to the original.
+    pub fn what_if(&self, alteration: &Alteration) -> Result<WhatIfReport, WhatIfError> {
+        if alteration.step >= self.steps.len() {
+            return Err(WhatIfError::NoSuchStep(alteration.step));
+        }
+        let original = self.replay()?;
+
+        let mut altered_steps = self.steps[..alteration.step].to_vec();
+        altered_steps.extend(alteration.replacement.iter().cloned());
+        altered_steps.extend(self.steps[alteration.step + 1..].iter().cloned());
+        let (altered, skipped_steps) =
+            replay_steps(&self.initial_state, &altered_steps, &mut EventBus::new());
+
+        let original_scores = final_scores(&original);
+        let altered_scores = final_scores(&altered);
+        let score_deltas = altered_scores
+            .iter()
+            .map(|(player_id, score)| (*player_id, score - original_scores[player_id]))
+            .collect();
+
+        Ok(WhatIfReport {
+            original_scores,
+            altered_scores,
+            score_deltas,
+            skipped_steps,
+        })
+    }
+}
+
+fn replay_steps(
+    initial_state: &GameState,
+    steps: &[GameStep],
+    bus: &mut EventBus,
+) -> (GameState, Vec<(usize, IllegalOperation)>) {
+    let mut game = initial_state.clone();
+
+    // Derived state is not serialized, so a record loaded from disk starts without it.
+    game.award_standings = AwardStandings::compute(&game);
+    game.milestone_notices = MilestoneNotices::compute(&game);
+
+    let mut skipped_steps = vec![];
+    for (index, step) in steps.iter().enumerate() {
+        match step {
+            GameStep::Operation(operation) => match game.check_operation(operation) {
+                Ok(()) => {
+                    bus.execute(&mut game, operation.clone());
+                }
+                Err(error) => skipped_steps.push((index, error)),
+            },
+            GameStep::ProductionPhase => {
+                for event in game.advance_generation() {
+                    bus.publish(&game, &event);
+                }
+            }
+        }
+    }
+    (game, skipped_steps)
+}
+
+fn final_scores(game: &GameState) -> BTreeMap<PlayerId, isize> {
+    score_all_players(game)
+        .into_iter()
+        .map(|(player_id, breakdown)| (player_id, breakdown.total()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use maplit::btreemap;
+
+    use crate::{
+        board::Coordinates,
+        card::CORPORATE_GAME_CARDS_BY_NAME,
+        game::{GameOperation, IllegalOperation, PlayerId},
+        position::import_position,
+        resource::Resource,
+        whatif::{Alteration, GameRecord, WhatIfError},
+    };
+
+    fn make_record() -> GameRecord {
+        let mut game = import_position(
+            "player p1\n\
+             resources 20 0 0 0 0 0\n\
+             hand Research\n\
+             player p2",
+        )
+        .unwrap();
+        let p1 = PlayerId::from(1);
+        let research = CORPORATE_GAME_CARDS_BY_NAME["Research"].clone();
+
+        let mut record = GameRecord::new(game.clone());
+        record.execute_operation(
+            &mut game,
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -11 }),
+        );
+        record.execute_operation(&mut game, GameOperation::PutCardIntoPlay(p1, research));
+        record.execute_operation(
+            &mut game,
+            GameOperation::PlaceGreenery(p1, Coordinates::new(1, 0)),
+        );
+        record.advance_generation(&mut game);
+
+        assert_eq!(Ok(game), record.replay());
+        record
+    }
+
+    #[test]
+    fn removing_a_step_reports_the_score_delta() {
+        let record = make_record();
+        let p1 = PlayerId::from(1);
+        let p2 = PlayerId::from(2);
+
+        // Without the greenery, player 1 loses its 1VP.
+        let report = record.what_if(&Alteration::remove(2)).unwrap();
+        assert_eq!(-1, report.score_deltas[&p1]);
+        assert_eq!(0, report.score_deltas[&p2]);
+        assert_eq!(report.original_scores[&p1] - 1, report.altered_scores[&p1]);
+        assert!(report.skipped_steps.is_empty());
+    }
+
+    #[test]
+    fn steps_made_illegal_by_the_alteration_are_skipped() {
+        let record = make_record();
+        let p1 = PlayerId::from(1);
+
+        // Spending the money on something else leaves too little to pay for Research,
+        // but playing it does not check for payment, so only the payment is skipped.
+        let alteration = Alteration::insert_before(
+            &record,
+            0,
+            GameOperation::ChangeResources(p1, btreemap! { Resource::Megacredits => -15 }),
+        );
+        let report = record.what_if(&alteration).unwrap();
+        assert_eq!(
+            vec![(
+                1,
+                IllegalOperation::InsufficientResource(Resource::Megacredits)
+            )],
+            report.skipped_steps
+        );
+        assert_eq!(0, report.score_deltas[&p1]);
+    }
+
+    #[test]
+    fn alterations_must_refer_to_recorded_steps() {
+        let record = make_record();
+        assert_eq!(
+            Err(WhatIfError::NoSuchStep(4)),
+            record.what_if(&Alteration::remove(4))
+        );
+    }
+}
@@ -1,12 +0,0 @@
-#![allow(dead_code)]
-
-#[macro_use]
-extern crate lazy_static;
-
-mod card;
-mod game;
-mod resource;
-mod sim;
-mod board;
-
-pub fn main() {}